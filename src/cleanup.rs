@@ -0,0 +1,216 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::logger::DEBUG_LOG_NAME;
+
+/// Differences between the download directory and the database, as
+/// found by `scan()`. `orphans` are the files in the download directory
+/// that no episode or podcast refers to, along with their size in bytes;
+/// `missing` are the episodes whose downloaded file no longer exists,
+/// along with the path recorded for them.
+#[derive(Debug, Default)]
+pub struct CleanupReport
+{
+	pub orphans: Vec<(PathBuf, u64)>,
+	pub missing: Vec<(i64, PathBuf)>,
+}
+
+impl CleanupReport
+{
+	/// Returns the total size of the orphaned files, in bytes.
+	pub fn orphan_bytes(&self) -> u64
+	{
+		return self.orphans.iter().map(|(_, size)| size).sum();
+	}
+}
+
+/// Compares the files in the download directory with the files recorded
+/// in the database. `files` are the downloaded episode files, with the
+/// ID of their episode; `covers` are the cover art files of podcasts,
+/// which are kept in the same directory. Paths are compared once
+/// symbolic links are resolved, so that a file reached by another path
+/// is not taken for an orphan. Nothing is changed on disk.
+///
+/// Only the folders of the podcasts, i.e., the directories within the
+/// download directory, are searched. The download directory may be the
+/// one the database is kept in, as it is by default on Linux, so files
+/// directly in it are left alone, as are the database at `db_path`, the
+/// files that go with it and the debug log, wherever they are.
+pub fn scan(
+	download_path: &Path,
+	db_path: &Path,
+	files: &[(i64, PathBuf)],
+	covers: &[PathBuf],
+) -> Result<CleanupReport>
+{
+	let mut report = CleanupReport::default();
+	let mut referenced = HashSet::new();
+	for (ep_id, path) in files.iter()
+	{
+		match fs::canonicalize(path)
+		{
+			Ok(path) if path.is_file() => {
+				referenced.insert(path);
+			}
+			_ => report.missing.push((*ep_id, path.clone())),
+		}
+	}
+	referenced.extend(covers.iter().filter_map(|path| fs::canonicalize(path).ok()));
+
+	// there is nothing to clean up if nothing was ever downloaded
+	if download_path.is_dir()
+	{
+		let mut found = Vec::new();
+		let entries = fs::read_dir(download_path).with_context(|| {
+			format!("Could not read directory {}", download_path.to_string_lossy())
+		})?;
+		for entry in entries
+		{
+			let entry = entry?;
+			if entry.file_type()?.is_dir()
+			{
+				list_files(&entry.path(), &mut found)?;
+			}
+		}
+		for path in found.into_iter().filter(|path| !is_db_file(path, db_path))
+		{
+			let resolved = fs::canonicalize(&path)
+				.with_context(|| format!("Could not read {}", path.to_string_lossy()))?;
+			if !referenced.contains(&resolved)
+			{
+				let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+				report.orphans.push((path, size));
+			}
+		}
+	}
+	report.orphans.sort();
+	return Ok(report);
+}
+
+/// Returns whether a file belongs with the database at `db_path`: the
+/// database itself, the files sqlite keeps next to it (e.g.,
+/// "data.db-wal"), the lock file, and the debug log along with the older
+/// logs kept when it is rotated.
+fn is_db_file(path: &Path, db_path: &Path) -> bool
+{
+	let (dir, db_dir) = match (path.parent(), db_path.parent())
+	{
+		(Some(dir), Some(db_dir)) => (dir, db_dir),
+		_ => return false,
+	};
+	let same_dir = match (fs::canonicalize(dir), fs::canonicalize(db_dir))
+	{
+		(Ok(dir), Ok(db_dir)) => dir == db_dir,
+		_ => dir == db_dir,
+	};
+	if !same_dir
+	{
+		return false;
+	}
+	let name = path.file_name().unwrap_or_default().to_string_lossy();
+	let db_name = db_path.file_name().unwrap_or_default().to_string_lossy();
+	return (!db_name.is_empty() && name.starts_with(db_name.as_ref()))
+		|| name.starts_with(DEBUG_LOG_NAME);
+}
+
+/// Adds the paths of all regular files in a directory and the
+/// directories within it to `found`. Symbolic links are not followed,
+/// so that nothing outside of the directory is ever listed.
+fn list_files(dir: &Path, found: &mut Vec<PathBuf>) -> Result<()>
+{
+	let entries = fs::read_dir(dir)
+		.with_context(|| format!("Could not read directory {}", dir.to_string_lossy()))?;
+	for entry in entries
+	{
+		let entry = entry?;
+		let file_type = entry.file_type()?;
+		if file_type.is_dir()
+		{
+			list_files(&entry.path(), found)?;
+		}
+		else if file_type.is_file()
+		{
+			found.push(entry.path());
+		}
+	}
+	return Ok(());
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn orphans_and_missing_files_found()
+	{
+		let dir = std::env::temp_dir().join(
+			format!("shellcaster-test-{}-cleanup", std::process::id())
+		);
+		let _ = fs::remove_dir_all(&dir);
+		let podcast = dir.join("Podcast");
+		fs::create_dir_all(&podcast).unwrap();
+		fs::write(podcast.join("kept.mp3"), b"kept").unwrap();
+		fs::write(podcast.join("cover.jpg"), b"cover").unwrap();
+		fs::write(podcast.join("orphan.mp3"), b"orphan").unwrap();
+		fs::create_dir_all(dir.join("Removed")).unwrap();
+		fs::write(dir.join("Removed").join("old.mp3"), b"old episode").unwrap();
+
+		let files = vec![
+			// the same file, reached through a path with ".." in it
+			(1, dir.join("Removed").join("..").join("Podcast").join("kept.mp3")),
+			(2, podcast.join("deleted.mp3")),
+		];
+		let covers = vec![podcast.join("cover.jpg")];
+		let db_path = dir.join("data.db");
+		let report = scan(&dir, &db_path, &files, &covers).unwrap();
+		assert_eq!(report.orphans, vec![
+			(podcast.join("orphan.mp3"), 6),
+			(dir.join("Removed").join("old.mp3"), 11),
+		]);
+		assert_eq!(report.orphan_bytes(), 17);
+		assert_eq!(report.missing, vec![(2, podcast.join("deleted.mp3"))]);
+
+		// a download directory that does not exist yet has no orphans
+		let report = scan(&dir.join("nothing"), &db_path, &files[..1], &covers).unwrap();
+		assert!(report.orphans.is_empty() && report.missing.is_empty());
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn database_in_download_dir_kept()
+	{
+		let dir = std::env::temp_dir().join(
+			format!("shellcaster-test-{}-cleanup-db", std::process::id())
+		);
+		let _ = fs::remove_dir_all(&dir);
+		let podcast = dir.join("Podcast");
+		fs::create_dir_all(&podcast).unwrap();
+		fs::write(podcast.join("orphan.mp3"), b"orphan").unwrap();
+		// the database and everything next to it, in the download
+		// directory itself as on Linux by default
+		for name in ["data.db", "data.db-wal", "data.db-shm", "data.db.lock", "debug.log",
+			"debug.log.1", "backup.db"]
+		{
+			fs::write(dir.join(name), b"keep").unwrap();
+		}
+		let report = scan(&dir, &dir.join("data.db"), &[], &[]).unwrap();
+		assert_eq!(report.orphans, vec![(podcast.join("orphan.mp3"), 6)]);
+
+		// or in a directory of its own within it
+		let db_dir = dir.join("db");
+		fs::create_dir_all(&db_dir).unwrap();
+		for name in ["data.db", "data.db-journal", "debug.log"]
+		{
+			fs::write(db_dir.join(name), b"keep").unwrap();
+		}
+		let report = scan(&dir, &db_dir.join("data.db"), &[], &[]).unwrap();
+		assert_eq!(report.orphans, vec![(podcast.join("orphan.mp3"), 6)]);
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
@@ -0,0 +1,93 @@
+use anyhow::{anyhow, Result};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Copies `data` to the system clipboard. If a command is given (e.g.,
+/// "wl-copy" or "xclip -selection clipboard"), the data is piped to it;
+/// otherwise it is sent to the terminal as an OSC 52 escape sequence,
+/// which also works over SSH if the terminal supports it.
+pub fn copy(data: &[u8], command: Option<&str>) -> Result<()>
+{
+	match command
+	{
+		Some(command) => return copy_with_command(data, command),
+		None => {
+			let tmux = std::env::var_os("TMUX").is_some();
+			let mut stdout = io::stdout();
+			stdout.write_all(osc52_sequence(data, tmux).as_bytes())?;
+			stdout.flush()?;
+			return Ok(());
+		}
+	}
+}
+
+/// Returns the raw bytes of a path, so that paths that are not valid
+/// Unicode are copied exactly as they are.
+pub fn path_bytes(path: &Path) -> Vec<u8>
+{
+	#[cfg(unix)]
+	{
+		use std::os::unix::ffi::OsStrExt;
+		return path.as_os_str().as_bytes().to_vec();
+	}
+	#[cfg(not(unix))]
+	{
+		return path.to_string_lossy().as_bytes().to_vec();
+	}
+}
+
+/// Builds the OSC 52 escape sequence that asks the terminal to put
+/// `data` on the clipboard. The data is base64 encoded, so control
+/// characters in it cannot end the sequence early. Inside tmux, the
+/// sequence is wrapped so that tmux passes it on to the terminal.
+fn osc52_sequence(data: &[u8], tmux: bool) -> String
+{
+	let sequence = format!("\x1b]52;c;{}\x07", base64::encode(data));
+	if tmux
+	{
+		return format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+	}
+	return sequence;
+}
+
+/// Pipes `data` to an external command. As with the browser command, the
+/// command is not run through a shell.
+fn copy_with_command(data: &[u8], command: &str) -> Result<()>
+{
+	let args: Vec<&str> = command.split_whitespace().collect();
+	if args.is_empty()
+	{
+		return Err(anyhow!("Clipboard command is empty"));
+	}
+	let mut child = Command::new(args[0])
+		.args(&args[1..])
+		.stdin(Stdio::piped())
+		.stdout(Stdio::null())
+		.stderr(Stdio::null())
+		.spawn()?;
+	if let Some(mut stdin) = child.stdin.take()
+	{
+		stdin.write_all(data)?;
+	}
+	std::thread::spawn(move || child.wait());
+	return Ok(());
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn osc52_escapes_data()
+	{
+		let sequence = osc52_sequence(b"/tmp/a\x07b\x1b.mp3", false);
+		assert_eq!(sequence, "\x1b]52;c;L3RtcC9hB2IbLm1wMw==\x07");
+
+		let wrapped = osc52_sequence(b"url", true);
+		assert_eq!(wrapped, "\x1bPtmux;\x1b\x1b]52;c;dXJs\x07\x1b\\");
+	}
+}
@@ -1,11 +1,16 @@
 use anyhow::{anyhow, Context, Result};
 use chrono::format::{Item, strftime::StrftimeItems};
 use serde::Deserialize;
+use std::fmt;
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use log::LevelFilter;
 
 use crate::keymap::Keybindings;
+use crate::types::{DateFormat, EpisodeColumn, Layout, PanelWidth, PodcastSort};
 use crate::ui::colors::AppColors;
 
 // Specifies how long, in milliseconds, to display messages at the
@@ -16,22 +21,69 @@ pub const MESSAGE_TIME: u64 = 5000;
 // (unplayed/total) after the podcast title
 pub const PODCAST_UNPLAYED_TOTALS_LENGTH: usize = 25;
 
-// How many columns we need, minimum, before we display the duration of
-// the episode
-pub const EPISODE_DURATION_LENGTH: usize = 45;
-
-// How many columns we need, minimum, before we display the pubdate
-// of the episode
-pub const EPISODE_PUBDATE_LENGTH: usize = 60;
+// How many columns are kept, minimum, for the title of an episode before
+// the other columns of the episode menu are dropped
+pub const EPISODE_TITLE_LENGTH: usize = 30;
 
 // How many columns we need (total terminal window width) before we
-// display the details panel
+// display the details panel in the automatic layout
 pub const DETAILS_PANEL_LENGTH: u16 = 135;
 
+// Minimum number of columns of each of the main panels; layouts with
+// more panels than fit at this width fall back on fewer panels
+pub const MIN_PANEL_WIDTH: u16 = 12;
+
+// How many days after it was published an unplayed episode is shown as
+// new in the episode menu
+pub const NEW_EPISODE_DAYS: i64 = 7;
+
+// Default number of days for which publication dates are shown relative
+// to today, when relative dates are enabled
+pub const RELATIVE_DATES_MAX_DAYS: i64 = 30;
+
 // How many lines will be scrolled by the big scroll,
 // in relation to the rows eg: 4 = 1/4 of the screen
 pub const BIG_SCROLL_AMOUNT: u16 = 4;
 
+// Smallest terminal size, in columns and rows, in which the panels are
+// drawn; below that, only a message asking for a bigger terminal is shown
+pub const MIN_TERMINAL_COLS: u16 = 40;
+pub const MIN_TERMINAL_ROWS: u16 = 10;
+
+// Maximum number of messages kept in the message log
+pub const MAX_LOG_ENTRIES: usize = 500;
+
+// Maximum number of pages that will be retrieved when following the
+// links to older pages of a paged feed
+pub const MAX_FEED_PAGES: usize = 50;
+
+// Maximum number of seconds to wait before retrying a request, when a
+// server asks us to slow down
+pub const MAX_RETRY_AFTER: u64 = 60;
+
+// Maximum number of times a request is retried when a server asks us to
+// slow down; these do not count towards the `max_retries` setting
+pub const MAX_RATE_LIMITED_RETRIES: usize = 5;
+
+// Minimum number of seconds a player must run for an episode to count as
+// listened to, if half of what is left of the episode is less than that
+pub const MIN_LISTEN_SECONDS: u64 = 30;
+
+// Size, in bytes, at which the debug log is moved aside and a new one is
+// started, and how many of the older logs are kept
+pub const MAX_DEBUG_LOG_SIZE: u64 = 1_000_000;
+pub const DEBUG_LOGS_KEPT: usize = 3;
+
+// Server and device name used to sync subscriptions with gpodder.net,
+// unless others are set in the config file
+pub const DEFAULT_GPODDER_SERVER: &str = "https://gpodder.net";
+pub const DEFAULT_GPODDER_DEVICE: &str = "shellcaster";
+
+// Maximum number of seconds to wait on exit for the downloads in progress
+// to finish, or to stop once they are cancelled
+pub const SHUTDOWN_WAIT_SECS: u64 = 120;
+pub const SHUTDOWN_CANCEL_SECS: u64 = 5;
+
 
 /// Identifies the user's selection for what to do with new episodes
 /// when syncing.
@@ -49,14 +101,72 @@ pub enum DownloadNewEpisodes
 pub struct Config
 {
 	pub download_path: PathBuf,
-	pub play_command: String,
+	pub db_path: Option<PathBuf>,
+	pub play_command_audio: String,
+	pub play_command_video: String,
+	pub player_uses_terminal: bool,
+	pub stream_episodes: bool,
+	pub mark_played_on_exit: bool,
+	pub browser_command: Option<String>,
+	pub clipboard_command: Option<String>,
+	pub editor_command: Option<String>,
+	pub mark_played_on_play: bool,
 	pub download_new_episodes: DownloadNewEpisodes,
 	pub simultaneous_downloads: usize,
+	pub sync_threads: usize,
 	pub max_retries: usize,
+	pub fetch_full_archive: bool,
+	pub max_sync_failures: usize,
+	pub same_host_delay: u64,
+	pub max_episodes_per_feed: usize,
+	pub auto_refresh_minutes: u64,
+	pub undo_removal_minutes: u64,
+	pub download_covers: bool,
+	pub podcast_sort: PodcastSort,
+	pub hide_played: bool,
+	pub enable_mouse: bool,
+	pub jump_by_letter: bool,
+	pub key_hints: bool,
+	pub layout: Layout,
+	pub podcast_width: Option<PanelWidth>,
+	pub episode_columns: Vec<EpisodeColumn>,
+	pub wrap_list_titles: bool,
+	pub date_format: DateFormat,
+	pub detail_date_format: DateFormat,
+	pub log_file: Option<PathBuf>,
+	pub log_level: LevelFilter,
 	pub keybindings: Keybindings,
 	pub colors: AppColors,
 	pub filename_prefix: String,
 	pub filename_suffix: String,
+	pub gpodder: Option<GpodderConfig>,
+}
+
+/// Account on gpodder.net, or another server with the same API, with
+/// which the list of subscriptions is synced. The server is given
+/// either a password or a token; neither is ever printed, not even
+/// when debugging.
+#[derive(Clone)]
+pub struct GpodderConfig
+{
+	pub server: String,
+	pub username: String,
+	pub device: String,
+	pub password: Option<String>,
+	pub token: Option<String>,
+}
+
+impl fmt::Debug for GpodderConfig
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		return f
+			.debug_struct("GpodderConfig")
+			.field("server", &self.server)
+			.field("username", &self.username)
+			.field("device", &self.device)
+			.finish_non_exhaustive();
+	}
 }
 
 /// A temporary struct used to deserialize data from the TOML configuration
@@ -65,14 +175,111 @@ pub struct Config
 struct ConfigFromToml
 {
 	download_path: Option<String>,
+	db_path: Option<String>,
 	play_command: Option<String>,
+	play_command_audio: Option<String>,
+	play_command_video: Option<String>,
+	player_uses_terminal: Option<bool>,
+	stream_episodes: Option<bool>,
+	mark_played_on_exit: Option<bool>,
+	browser_command: Option<String>,
+	clipboard_command: Option<String>,
+	editor_command: Option<String>,
+	mark_played_on_play: Option<bool>,
 	download_new_episodes: Option<String>,
 	simultaneous_downloads: Option<usize>,
+	sync_threads: Option<usize>,
 	max_retries: Option<usize>,
+	fetch_full_archive: Option<bool>,
+	max_sync_failures: Option<usize>,
+	same_host_delay: Option<u64>,
+	max_episodes_per_feed: Option<usize>,
+	auto_refresh_minutes: Option<u64>,
+	undo_removal_minutes: Option<u64>,
+	download_covers: Option<bool>,
+	podcast_sort: Option<String>,
+	hide_played: Option<bool>,
+	enable_mouse: Option<bool>,
+	jump_by_letter: Option<bool>,
+	key_hints: Option<bool>,
+	layout: Option<String>,
+	podcast_width: Option<String>,
+	episode_columns: Option<Vec<String>>,
+	wrap_list_titles: Option<bool>,
+	relative_dates: Option<bool>,
+	relative_dates_max_days: Option<i64>,
+	date_format: Option<String>,
+	detail_date_format: Option<String>,
+	log_file: Option<String>,
+	log_level: Option<String>,
+	color: Option<bool>,
+	truecolor: Option<TrueColor>,
+	theme: Option<String>,
 	keybindings: Option<KeybindingsFromToml>,
 	colors: Option<AppColorsFromToml>,
 	filename_prefix: Option<String>,
 	filename_suffix: Option<String>,
+	gpodder: Option<GpodderFromToml>,
+}
+
+/// A temporary struct used to deserialize the gpodder.net account from
+/// the TOML configuration file. As with GpodderConfig, the password and
+/// token are left out when debugging.
+#[derive(Deserialize)]
+struct GpodderFromToml
+{
+	server: Option<String>,
+	username: Option<String>,
+	device: Option<String>,
+	password: Option<String>,
+	token: Option<String>,
+}
+
+impl fmt::Debug for GpodderFromToml
+{
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+	{
+		return f
+			.debug_struct("GpodderFromToml")
+			.field("server", &self.server)
+			.field("username", &self.username)
+			.field("device", &self.device)
+			.finish_non_exhaustive();
+	}
+}
+
+/// Keys bound to an action in the config file: either a list of keys,
+/// which is empty to unbind the action, or a single key, or "none".
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList
+{
+	Keys(Vec<String>),
+	Key(String),
+}
+
+impl KeyList
+{
+	/// Returns the keys in the list, which are none for "none".
+	pub fn keys(&self) -> &[String]
+	{
+		return match self
+		{
+			KeyList::Keys(keys) => keys,
+			KeyList::Key(key) if key.eq_ignore_ascii_case("none") => &[],
+			KeyList::Key(key) => std::slice::from_ref(key),
+		};
+	}
+}
+
+/// Whether colors given as RGB values are shown as they are: true or
+/// false, or "auto" to go by what the terminal says it supports.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum TrueColor
+{
+	Set(bool),
+	Text(String),
 }
 
 /// A temporary struct used to deserialize keybinding data from the TOML
@@ -80,33 +287,66 @@ struct ConfigFromToml
 #[derive(Debug, Deserialize)]
 pub struct KeybindingsFromToml
 {
-	pub left: Option<Vec<String>>,
-	pub right: Option<Vec<String>>,
-	pub up: Option<Vec<String>>,
-	pub down: Option<Vec<String>>,
-	pub big_up: Option<Vec<String>>,
-	pub big_down: Option<Vec<String>>,
-	pub go_top: Option<Vec<String>>,
-	pub go_bot: Option<Vec<String>>,
-	pub page_up: Option<Vec<String>>,
-	pub page_down: Option<Vec<String>>,
-	pub add_feed: Option<Vec<String>>,
-	pub sync: Option<Vec<String>>,
-	pub sync_all: Option<Vec<String>>,
-	pub play: Option<Vec<String>>,
-	pub mark_played: Option<Vec<String>>,
-	pub mark_all_played: Option<Vec<String>>,
-	pub download: Option<Vec<String>>,
-	pub download_all: Option<Vec<String>>,
-	pub unmark_downloaded: Option<Vec<String>>,
-	pub delete: Option<Vec<String>>,
-	pub delete_all: Option<Vec<String>>,
-	pub remove: Option<Vec<String>>,
-	pub remove_all: Option<Vec<String>>,
-	pub filter_played: Option<Vec<String>>,
-	pub filter_downloaded: Option<Vec<String>>,
-	pub help: Option<Vec<String>>,
-	pub quit: Option<Vec<String>>,
+	pub left: Option<KeyList>,
+	pub right: Option<KeyList>,
+	pub up: Option<KeyList>,
+	pub down: Option<KeyList>,
+	pub big_up: Option<KeyList>,
+	pub big_down: Option<KeyList>,
+	pub go_top: Option<KeyList>,
+	pub go_bot: Option<KeyList>,
+	pub page_up: Option<KeyList>,
+	pub page_down: Option<KeyList>,
+	pub add_feed: Option<KeyList>,
+	pub sync: Option<KeyList>,
+	pub sync_all: Option<KeyList>,
+	pub sync_gpodder: Option<KeyList>,
+	pub rename: Option<KeyList>,
+	pub max_episodes: Option<KeyList>,
+	pub edit_tags: Option<KeyList>,
+	pub toggle_favorite: Option<KeyList>,
+	pub play: Option<KeyList>,
+	pub mark_played: Option<KeyList>,
+	pub mark_all_played: Option<KeyList>,
+	pub set_position: Option<KeyList>,
+	pub archive: Option<KeyList>,
+	pub archive_older: Option<KeyList>,
+	pub enqueue: Option<KeyList>,
+	pub show_queue: Option<KeyList>,
+	pub play_next: Option<KeyList>,
+	pub toggle_mark: Option<KeyList>,
+	pub mark_all: Option<KeyList>,
+	pub clear_marks: Option<KeyList>,
+	pub show_stats: Option<KeyList>,
+	pub show_log: Option<KeyList>,
+	pub open_link: Option<KeyList>,
+	pub copy_url: Option<KeyList>,
+	pub copy_path: Option<KeyList>,
+	pub export_feeds: Option<KeyList>,
+	pub download: Option<KeyList>,
+	pub download_all: Option<KeyList>,
+	pub unmark_downloaded: Option<KeyList>,
+	pub delete: Option<KeyList>,
+	pub delete_all: Option<KeyList>,
+	pub set_workers: Option<KeyList>,
+	pub remove: Option<KeyList>,
+	pub remove_all: Option<KeyList>,
+	pub undo_removal: Option<KeyList>,
+	pub filter_played: Option<KeyList>,
+	pub filter_downloaded: Option<KeyList>,
+	pub filter_view: Option<KeyList>,
+	pub filter_tag: Option<KeyList>,
+	pub filter_archived: Option<KeyList>,
+	pub filter_favorites: Option<KeyList>,
+	pub search: Option<KeyList>,
+	pub jump_to: Option<KeyList>,
+	pub sort_episodes: Option<KeyList>,
+	pub sort_podcasts: Option<KeyList>,
+	pub cycle_layout: Option<KeyList>,
+	pub reload_config: Option<KeyList>,
+	pub edit_config: Option<KeyList>,
+	pub help: Option<KeyList>,
+	pub quit: Option<KeyList>,
 }
 
 /// A temporary struct used to deserialize colors data from the TOML
@@ -125,6 +365,12 @@ pub struct AppColorsFromToml
 	pub highlighted_background: Option<String>,
 	pub error_foreground: Option<String>,
 	pub error_background: Option<String>,
+	pub played_foreground: Option<String>,
+	pub played_background: Option<String>,
+	pub downloaded_foreground: Option<String>,
+	pub downloaded_background: Option<String>,
+	pub new_foreground: Option<String>,
+	pub new_background: Option<String>,
 }
 
 
@@ -164,18 +410,51 @@ impl Config
 					add_feed: None,
 					sync: None,
 					sync_all: None,
+					sync_gpodder: None,
+					rename: None,
+					max_episodes: None,
+					edit_tags: None,
+					toggle_favorite: None,
 					play: None,
 					mark_played: None,
 					mark_all_played: None,
+					set_position: None,
+					archive: None,
+					archive_older: None,
+					enqueue: None,
+					show_queue: None,
+					play_next: None,
+					toggle_mark: None,
+					mark_all: None,
+					clear_marks: None,
+					show_stats: None,
+					show_log: None,
+					open_link: None,
+					copy_url: None,
+					copy_path: None,
+					export_feeds: None,
 					download: None,
 					download_all: None,
 					unmark_downloaded: None,
 					delete: None,
 					delete_all: None,
+					set_workers: None,
 					remove: None,
 					remove_all: None,
+					undo_removal: None,
 					filter_played: None,
 					filter_downloaded: None,
+					filter_view: None,
+					filter_tag: None,
+					filter_archived: None,
+					filter_favorites: None,
+					search: None,
+					jump_to: None,
+					sort_episodes: None,
+					sort_podcasts: None,
+					cycle_layout: None,
+					reload_config: None,
+					edit_config: None,
 					help: None,
 					quit: None,
 				};
@@ -191,17 +470,60 @@ impl Config
 					highlighted_background: None,
 					error_foreground: None,
 					error_background: None,
+					played_foreground: None,
+					played_background: None,
+					downloaded_foreground: None,
+					downloaded_background: None,
+					new_foreground: None,
+					new_background: None,
 				};
 				ConfigFromToml {
 					download_path: None,
+					db_path: None,
 					play_command: None,
+					play_command_audio: None,
+					play_command_video: None,
+					player_uses_terminal: None,
+					stream_episodes: None,
+					mark_played_on_exit: None,
+					browser_command: None,
+					clipboard_command: None,
+					editor_command: None,
+					mark_played_on_play: None,
 					download_new_episodes: None,
 					simultaneous_downloads: None,
+					sync_threads: None,
 					max_retries: None,
+					fetch_full_archive: None,
+					max_sync_failures: None,
+					same_host_delay: None,
+					max_episodes_per_feed: None,
+					auto_refresh_minutes: None,
+					undo_removal_minutes: None,
+					download_covers: None,
+					podcast_sort: None,
+					hide_played: None,
+					enable_mouse: None,
+					jump_by_letter: None,
+					key_hints: None,
+					layout: None,
+					podcast_width: None,
+					episode_columns: None,
+					wrap_list_titles: None,
+					relative_dates: None,
+					relative_dates_max_days: None,
+					date_format: None,
+					detail_date_format: None,
+					log_file: None,
+					log_level: None,
+					color: None,
+					truecolor: None,
+					theme: None,
 					keybindings: Some(keybindings),
 					colors: Some(colors),
 					filename_prefix: None,
 					filename_suffix: None,
+					gpodder: None,
 				}
 			}
 		};
@@ -210,7 +532,7 @@ impl Config
 		// contains invalid format specifiers
 		if let Some(prefix) = &config_toml.filename_prefix
 		{
-			if StrftimeItems::new(&prefix).any(|i| matches!(i, Item::Error))
+			if StrftimeItems::new(prefix).any(|i| matches!(i, Item::Error))
 			{
 				return Err(anyhow!(
 					"Invalid value \"{}\" for filename_prefix in config.toml",
@@ -220,7 +542,7 @@ impl Config
 		}
 		if let Some(suffix) = &config_toml.filename_suffix
 		{
-			if StrftimeItems::new(&suffix).any(|i| matches!(i, Item::Error))
+			if StrftimeItems::new(suffix).any(|i| matches!(i, Item::Error))
 			{
 				return Err(anyhow!(
 					"Invalid value \"{}\" for filename_suffix in config.toml",
@@ -229,8 +551,56 @@ impl Config
 			}
 		}
 
+		// the same goes for the formats of dates, which would otherwise
+		// fail only once a date is shown
+		for (name, format) in [
+			("date_format", &config_toml.date_format),
+			("detail_date_format", &config_toml.detail_date_format),
+		]
+		{
+			if let Some(format) = format
+			{
+				if StrftimeItems::new(format).any(|i| matches!(i, Item::Error))
+				{
+					return Err(anyhow!(
+						"Invalid value \"{}\" for {} in config.toml",
+						format,
+						name
+					));
+				}
+			}
+		}
+
 		return config_with_defaults(config_toml);
 	}
+
+	/// Returns the names of the options that differ between this config
+	/// and `new`, but are only read when shellcaster starts, so that
+	/// reloading the config file cannot change them.
+	pub fn restart_required(&self, new: &Config) -> Vec<&'static str>
+	{
+		let changes = [
+			("db_path", self.db_path != new.db_path),
+			("log_level", self.log_level != new.log_level),
+			("podcast_sort", self.podcast_sort != new.podcast_sort),
+			("hide_played", self.hide_played != new.hide_played),
+			("enable_mouse", self.enable_mouse != new.enable_mouse),
+			("jump_by_letter", self.jump_by_letter != new.jump_by_letter),
+			("key_hints", self.key_hints != new.key_hints),
+			("layout", self.layout != new.layout),
+			("podcast_width", self.podcast_width != new.podcast_width),
+			("episode_columns", self.episode_columns != new.episode_columns),
+			("wrap_list_titles", self.wrap_list_titles != new.wrap_list_titles),
+			("date_format", self.date_format != new.date_format),
+			("detail_date_format", self.detail_date_format != new.detail_date_format),
+			("clipboard_command", self.clipboard_command != new.clipboard_command),
+		];
+		return changes
+			.into_iter()
+			.filter(|(_, changed)| *changed)
+			.map(|(name, _)| name)
+			.collect();
+	}
 }
 
 /// Takes the deserialized TOML configuration, and creates a Config struct
@@ -241,20 +611,38 @@ fn config_with_defaults(config_toml: ConfigFromToml) -> Result<Config>
 	// specify keybindings
 	let keymap = match config_toml.keybindings
 	{
-		Some(kb) => Keybindings::from_config(kb),
+		Some(kb) => Keybindings::from_config(kb)?,
 		None => Keybindings::default(),
 	};
 
-	// specify app colors
-	let colors = match config_toml.colors
+	// specify app colors: those of the theme, with any colors set by the
+	// user on top
+	let mut colors = match config_toml.theme.as_deref()
 	{
-		Some(clrs) => {
-			let mut colors = AppColors::default();
-			colors.add_from_config(clrs);
-			colors
-		}
+		Some(theme) => AppColors::theme(theme)?,
 		None => AppColors::default(),
 	};
+	if let Some(clrs) = config_toml.colors
+	{
+		colors.add_from_config(clrs);
+	}
+	let truecolor = match config_toml.truecolor
+	{
+		Some(TrueColor::Set(truecolor)) => truecolor,
+		Some(TrueColor::Text(text)) => match text.as_str()
+		{
+			"true" => true,
+			"false" => false,
+			_ => crate::ui::colors::truecolor_supported(),
+		},
+		None => crate::ui::colors::truecolor_supported(),
+	};
+	if !truecolor
+	{
+		colors.limit_to_256_colors();
+	}
+	colors.monochrome = !config_toml.color.unwrap_or(true)
+		|| crate::ui::colors::no_color_requested();
 
 	// paths are set by user, or they resolve to OS-specific path as
 	// provided by dirs crate
@@ -264,11 +652,26 @@ fn config_with_defaults(config_toml: ConfigFromToml) -> Result<Config>
 			dirs::data_local_dir()
 		)?;
 
+	let db_path = match config_toml.db_path.as_deref()
+	{
+		Some(path) => Some(expand_path(path)?),
+		None => None,
+	};
+
 	let play_command = match config_toml.play_command.as_deref()
 	{
 		Some(cmd) => cmd.to_string(),
 		None => "vlc".to_string(),
 	};
+	// audio and video can each have a command of their own
+	let play_command_audio = config_toml.play_command_audio.unwrap_or_else(|| play_command.clone());
+	let play_command_video = config_toml.play_command_video.unwrap_or(play_command);
+
+	let player_uses_terminal = config_toml.player_uses_terminal.unwrap_or(false);
+	let stream_episodes = config_toml.stream_episodes.unwrap_or(true);
+
+	let mark_played_on_play = config_toml.mark_played_on_play.unwrap_or(true);
+	let mark_played_on_exit = config_toml.mark_played_on_exit.unwrap_or(true);
 
 	let download_new_episodes = match config_toml.download_new_episodes.as_deref()
 	{
@@ -286,6 +689,14 @@ fn config_with_defaults(config_toml: ConfigFromToml) -> Result<Config>
 		None => 3,
 	};
 
+	// podcasts used to be synced along with the downloads, so they are
+	// synced as many at a time unless set otherwise
+	let sync_threads = match config_toml.sync_threads
+	{
+		Some(num) if num > 0 => num,
+		Some(_) | None => simultaneous_downloads,
+	};
+
 	let max_retries = match config_toml.max_retries
 	{
 		Some(num) if num > 0 => num,
@@ -293,6 +704,93 @@ fn config_with_defaults(config_toml: ConfigFromToml) -> Result<Config>
 		None => 3,
 	};
 
+	let fetch_full_archive = config_toml.fetch_full_archive.unwrap_or(false);
+
+	let max_sync_failures = config_toml.max_sync_failures.unwrap_or(5);
+
+	let same_host_delay = config_toml.same_host_delay.unwrap_or(500);
+
+	let max_episodes_per_feed = config_toml.max_episodes_per_feed.unwrap_or(0);
+
+	let auto_refresh_minutes = config_toml.auto_refresh_minutes.unwrap_or(0);
+	let undo_removal_minutes = config_toml.undo_removal_minutes.unwrap_or(10);
+
+	let download_covers = config_toml.download_covers.unwrap_or(false);
+
+	let enable_mouse = config_toml.enable_mouse.unwrap_or(false);
+
+	let jump_by_letter = config_toml.jump_by_letter.unwrap_or(true);
+
+	let key_hints = config_toml.key_hints.unwrap_or(true);
+
+	let layout = match config_toml.layout.as_deref()
+	{
+		Some("two-pane") => Layout::TwoPane,
+		Some("three-pane") => Layout::ThreePane,
+		Some("zoomed") => Layout::Zoomed,
+		Some("auto") | Some(_) | None => Layout::Auto,
+	};
+
+	let podcast_width = config_toml
+		.podcast_width
+		.as_deref()
+		.and_then(PanelWidth::parse);
+
+	let wrap_list_titles = config_toml.wrap_list_titles.unwrap_or(false);
+
+	let date_format = DateFormat::new(config_toml.date_format.as_deref().unwrap_or("%F"));
+	let detail_date_format = DateFormat::new(
+		config_toml.detail_date_format.as_deref().unwrap_or("%B %-d, %Y")
+	);
+
+	let mut episode_columns = match config_toml.episode_columns
+	{
+		Some(names) => names
+			.iter()
+			.filter_map(|name| EpisodeColumn::parse(name, &date_format))
+			.collect(),
+		None => vec![
+			EpisodeColumn::Date(date_format.clone()),
+			EpisodeColumn::Duration,
+			EpisodeColumn::Size,
+		],
+	};
+	if config_toml.relative_dates == Some(true)
+	{
+		let max_days = config_toml
+			.relative_dates_max_days
+			.unwrap_or(RELATIVE_DATES_MAX_DAYS)
+			.max(0);
+		for column in episode_columns.iter_mut()
+		{
+			if let EpisodeColumn::Date(date_format) = column
+			{
+				*column = EpisodeColumn::RelativeDate(max_days, date_format.clone());
+			}
+		}
+	}
+
+	let log_file = match config_toml.log_file.as_deref()
+	{
+		Some(path) => Some(expand_path(path)?),
+		None => None,
+	};
+
+	let log_level = config_toml
+		.log_level
+		.as_deref()
+		.and_then(|level| LevelFilter::from_str(level).ok())
+		.unwrap_or(LevelFilter::Warn);
+
+	let podcast_sort = match config_toml.podcast_sort.as_deref()
+	{
+		Some("latest") => PodcastSort::Latest,
+		Some("unplayed") => PodcastSort::Unplayed,
+		Some("title") | Some(_) | None => PodcastSort::Title,
+	};
+
+	let hide_played = config_toml.hide_played.unwrap_or(false);
+
 	let filename_prefix = match config_toml.filename_prefix.as_deref()
 	{
 		Some(pref) => pref.to_string(),
@@ -305,20 +803,80 @@ fn config_with_defaults(config_toml: ConfigFromToml) -> Result<Config>
 		None => "".to_string(),
 	};
 
+	// syncing with gpodder.net is only set up once there is a username
+	let gpodder = config_toml.gpodder.and_then(|gpodder| {
+		let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+		return Some(GpodderConfig {
+			server: non_empty(gpodder.server)
+				.unwrap_or_else(|| DEFAULT_GPODDER_SERVER.to_string()),
+			username: non_empty(gpodder.username)?,
+			device: non_empty(gpodder.device)
+				.unwrap_or_else(|| DEFAULT_GPODDER_DEVICE.to_string()),
+			password: non_empty(gpodder.password),
+			token: non_empty(gpodder.token),
+		});
+	});
+
 	return Ok(Config {
 		download_path: download_path,
-		play_command: play_command,
+		db_path: db_path,
+		play_command_audio: play_command_audio,
+		play_command_video: play_command_video,
+		player_uses_terminal: player_uses_terminal,
+		stream_episodes: stream_episodes,
+		mark_played_on_exit: mark_played_on_exit,
+		browser_command: config_toml.browser_command,
+		clipboard_command: config_toml.clipboard_command,
+		editor_command: config_toml.editor_command,
+		mark_played_on_play: mark_played_on_play,
 		download_new_episodes: download_new_episodes,
 		simultaneous_downloads: simultaneous_downloads,
+		sync_threads: sync_threads,
 		max_retries: max_retries,
+		fetch_full_archive: fetch_full_archive,
+		max_sync_failures: max_sync_failures,
+		same_host_delay: same_host_delay,
+		max_episodes_per_feed: max_episodes_per_feed,
+		auto_refresh_minutes: auto_refresh_minutes,
+		undo_removal_minutes: undo_removal_minutes,
+		download_covers: download_covers,
+		podcast_sort: podcast_sort,
+		hide_played: hide_played,
+		enable_mouse: enable_mouse,
+		jump_by_letter: jump_by_letter,
+		key_hints: key_hints,
+		layout: layout,
+		podcast_width: podcast_width,
+		episode_columns: episode_columns,
+		wrap_list_titles: wrap_list_titles,
+		date_format: date_format,
+		detail_date_format: detail_date_format,
+		log_file: log_file,
+		log_level: log_level,
 		keybindings: keymap,
 		colors: colors,
 		filename_prefix: filename_prefix,
 		filename_suffix: filename_suffix,
+		gpodder: gpodder,
 	});
 }
 
 
+/// Helper function that expands any environment variables, ~ alias, etc.
+/// in a path set in config.toml.
+fn expand_path(path: &str) -> Result<PathBuf>
+{
+	return match shellexpand::full(path)
+	{
+		Ok(realpath) => Ok(PathBuf::from(realpath.as_ref())),
+		Err(err) => Err(anyhow!(
+			"Could not parse environment variable {} in config.toml. Reason: {}",
+			err.var_name,
+			err.cause
+		)),
+	};
+}
+
 /// Helper function that takes an (optionally specified) user directory
 /// and an (OS-dependent) default directory, expands any environment
 /// variables, ~ alias, etc. Returns a PathBuf. Panics if environment
@@ -329,23 +887,14 @@ fn parse_create_dir(user_dir: Option<&str>, default: Option<PathBuf>) -> Result<
 {
 	let final_path = match user_dir
 	{
-		Some(path) => match shellexpand::full(path)
-		{
-			Ok(realpath) => PathBuf::from(realpath.as_ref()),
-			Err(err) => {
-				return Err(anyhow!(
-					"Could not parse environment variable {} in config.toml. Reason: {}",
-					err.var_name,
-					err.cause
-				))
-			}
-		},
+		Some(path) => expand_path(path)?,
 		None => {
 			if let Some(mut path) = default
 			{
 				path.push("shellcaster");
 				path
-			} else
+			}
+			else
 			{
 				return Err(anyhow!(
 					"Could not identify a default directory for your OS. Please specify paths manually in config.toml."
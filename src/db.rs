@@ -1,15 +1,27 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
+use std::fs::File;
 use std::path::{Path, PathBuf};
 
 use ahash::AHashMap;
 use chrono::{DateTime, NaiveDateTime, Utc};
 use lazy_static::lazy_static;
 use regex::Regex;
-use rusqlite::{params, Connection};
+use fs2::FileExt;
+use rusqlite::{
+	params, Connection, DatabaseName, OpenFlags, OptionalExtension, Statement, Transaction,
+	TransactionBehavior,
+};
 use semver::Version;
 
+use crate::stats::PodcastStats;
 use crate::types::*;
 
+/// Statement used to insert a new episode; see Database::insert_episode.
+const INSERT_EPISODE: &str = "INSERT INTO episodes (podcast_id, title, url, guid,
+	link, description, pubdate, duration, season, episode_num, enclosure_size, played,
+	hidden, added_at)
+	VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);";
+
 lazy_static!
 {
 	/// Regex for removing "A", "An", and "The" from the beginning of
@@ -24,6 +36,50 @@ pub struct SyncResult
 	pub updated: Vec<i64>,
 }
 
+/// Struct holding the state of an episode in the database, used when
+/// merging a JSON export; see Database::merge_podcast.
+#[derive(Debug, Clone)]
+struct LocalEpisode
+{
+	id: i64,
+	guid: String,
+	url: String,
+	played: bool,
+	position: Option<i64>,
+	archived: bool,
+	path: Option<String>,
+}
+
+/// An episode whose played status or playback position was changed
+/// here, or whose file was downloaded, along with what other devices
+/// need to find it; see Database::get_episode_changes.
+#[derive(Debug, Clone)]
+pub struct EpisodeChange
+{
+	pub podcast_url: String,
+	pub url: String,
+	pub guid: String,
+	pub played: bool,
+	pub position: Option<i64>,
+	pub duration: Option<i64>,
+	pub changed_at: Option<DateTime<Utc>>,
+	pub downloaded_at: Option<DateTime<Utc>>,
+}
+
+/// A played status or playback position from another device, for the
+/// episode of a podcast with the given GUID or URL; see
+/// Database::apply_episode_updates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpisodeUpdate
+{
+	pub pod_id: i64,
+	pub url: String,
+	pub guid: Option<String>,
+	pub played: bool,
+	pub position: Option<i64>,
+	pub time: DateTime<Utc>,
+}
+
 /// Struct holding a sqlite database connection, with methods to interact
 /// with this connection.
 #[derive(Debug)]
@@ -35,20 +91,23 @@ pub struct Database
 
 impl Database
 {
-	/// Creates a new connection to the database (and creates database if
-	/// it does not already exist). Panics if database cannot be accessed.
+	/// Creates a new connection to the database file at `path` (and
+	/// creates database if it does not already exist), and brings its
+	/// schema up to date. Fails if the database was created by a newer
+	/// version of shellcaster. Panics if database cannot be accessed.
 	pub fn connect(path: &Path) -> Result<Database>
 	{
-		let mut db_path = path.to_path_buf();
-		std::fs::create_dir_all(&db_path)
-			.with_context(|| "Unable to create subdirectory for database.")?;
-		db_path.push("data.db");
-		let conn = Connection::open(&db_path)?;
-		let db_conn = Database {
-			path: db_path,
+		if let Some(dir) = path.parent()
+		{
+			std::fs::create_dir_all(dir)
+				.with_context(|| "Unable to create subdirectory for database.")?;
+		}
+		let conn = open_connection(path)?;
+		let mut db_conn = Database {
+			path: path.to_path_buf(),
 			conn: Some(conn),
 		};
-		db_conn.create()?;
+		db_conn.migrate()?;
 
 		{
 			let conn = db_conn
@@ -56,10 +115,6 @@ impl Database
 				.as_ref()
 				.expect("Error connecting to database.");
 
-			// SQLite defaults to foreign key support off
-			conn.execute("PRAGMA foreign_keys=ON;", params![])
-				.expect("Could not set database parameters.");
-
 			// get version number stored in database
 			let mut stmt = conn.prepare("SELECT version FROM version WHERE id = 1;")?;
 			let vstr: Result<String, rusqlite::Error> =
@@ -74,18 +129,6 @@ impl Database
 					let db_version = Version::parse(&vstr)?;
 					if db_version < curr_ver
 					{
-						// any version checks for DB migrations should
-						// go here first, before we update the version
-
-						// adding a column to capture episode guids
-						if db_version <= Version::parse("1.2.1")?
-						{
-							conn.execute(
-								"ALTER TABLE episodes ADD COLUMN guid TEXT;",
-								params![]
-							).expect("Could not run database migrations.");
-						}
-
 						db_conn.update_version(curr_ver, true)?;
 					}
 				}
@@ -96,67 +139,139 @@ impl Database
 		return Ok(db_conn);
 	}
 
-	/// Creates the necessary database tables, if they do not already
-	/// exist. Panics if database cannot be accessed, or if tables cannot
-	/// be created.
-	pub fn create(&self) -> Result<()>
+	/// Applies the migrations that the database is missing, each in its
+	/// own transaction. The schema version is stored in SQLite's
+	/// `user_version` and is the number of migrations applied so far.
+	/// Fails if the database has a schema version that this version of
+	/// shellcaster does not know about.
+	fn migrate(&mut self) -> Result<()>
 	{
-		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		let conn = self.conn.as_mut().expect("Error connecting to database.");
 
-		// create podcasts table
-		conn.execute(
-			"CREATE TABLE IF NOT EXISTS podcasts (
-				id INTEGER PRIMARY KEY NOT NULL,
-				title TEXT NOT NULL,
-				url TEXT NOT NULL UNIQUE,
-				description TEXT,
-				author TEXT,
-				explicit INTEGER,
-				last_checked INTEGER
-			);",
-			params![],
-		)
-		.with_context(|| "Could not create podcasts database table")?;
+		let schema_version: usize = conn
+			.query_row("PRAGMA user_version;", params![], |row| row.get(0))
+			.with_context(|| "Could not read database schema version")?;
+		if schema_version > MIGRATIONS.len()
+		{
+			return Err(anyhow!(
+				"The database at {} has schema version {}, but this version of shellcaster only supports up to version {}. Please upgrade shellcaster to use this database.",
+				self.path.to_string_lossy(),
+				schema_version,
+				MIGRATIONS.len()
+			));
+		}
 
-		// create episodes table
-		conn.execute(
-			"CREATE TABLE IF NOT EXISTS episodes (
-				id INTEGER PRIMARY KEY NOT NULL,
-				podcast_id INTEGER NOT NULL,
-				title TEXT NOT NULL,
-				url TEXT NOT NULL,
-				guid TEXT,
-				description TEXT,
-				pubdate INTEGER,
-				duration INTEGER,
-				played INTEGER,
-				hidden INTEGER,
-				FOREIGN KEY(podcast_id) REFERENCES podcasts(id) ON DELETE CASCADE
-			);",
-			params![],
-		)
-		.with_context(|| "Could not create episodes database table")?;
+		for (version, migration) in MIGRATIONS.iter().enumerate().skip(schema_version)
+		{
+			log::info!(
+				"Migrating {} to schema version {}",
+				self.path.to_string_lossy(),
+				version + 1
+			);
+			let tx = conn.transaction()?;
+			migration(&tx).with_context(|| {
+				format!("Could not migrate database to schema version {}", version + 1)
+			})?;
+			tx.execute(&format!("PRAGMA user_version = {};", version + 1), params![])?;
+			tx.commit()?;
+		}
+		return Ok(());
+	}
 
-		// create files table
-		conn.execute(
-			"CREATE TABLE IF NOT EXISTS files (
-				id INTEGER PRIMARY KEY NOT NULL,
-				episode_id INTEGER NOT NULL,
-				path TEXT NOT NULL UNIQUE,
-				FOREIGN KEY (episode_id) REFERENCES episodes(id) ON DELETE CASCADE
-			);",
-			params![],
-		)
-		.with_context(|| "Could not create files database table")?;
+	/// Takes a lock showing that the database at `path` is in use, which
+	/// lasts until the returned file is dropped. Any number of shared
+	/// locks can be held at the same time, but an exclusive lock can only
+	/// be taken while no other lock is held. Returns None if the lock
+	/// cannot be taken because of a lock held elsewhere. The lock is held
+	/// by the operating system, so it goes away if shellcaster crashes.
+	pub fn lock(path: &Path, exclusive: bool) -> Result<Option<File>>
+	{
+		let lock_file = lock_path(path);
+		let file = File::create(&lock_file).with_context(|| format!(
+			"Could not create lock file: {}",
+			lock_file.to_string_lossy()
+		))?;
+		let res = if exclusive
+		{
+			file.try_lock_exclusive()
+		}
+		else
+		{
+			FileExt::try_lock_shared(&file)
+		};
+		return match res
+		{
+			Ok(_) => Ok(Some(file)),
+			Err(_) => Ok(None),
+		};
+	}
 
-		conn.execute(
-			"CREATE TABLE IF NOT EXISTS version (
-				id INTEGER PRIMARY KEY NOT NULL,
-				version TEXT NOT NULL
-			);",
-			params![],
-		)
-		.with_context(|| "Could not create version database table")?;
+	/// Copies the database to the file at `dest`, using SQLite's online
+	/// backup, so that it works even while another instance of
+	/// shellcaster is using the database.
+	pub fn backup(&self, dest: &Path) -> Result<()>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		conn.backup(DatabaseName::Main, dest, None)
+			.with_context(|| format!("Could not back up database to {}", dest.to_string_lossy()))?;
+		return Ok(());
+	}
+
+	/// Returns the titles and URLs of the podcasts in the database at
+	/// `path`, without changing anything: the database is opened
+	/// read-only, is not brought up to date, and is not created if it
+	/// does not exist (in which case there are no podcasts). Used to see
+	/// what an import would do.
+	pub fn read_feeds(path: &Path) -> Result<Vec<(String, String)>>
+	{
+		if !path.exists()
+		{
+			return Ok(Vec::new());
+		}
+		let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+		let mut stmt = conn.prepare("SELECT * FROM podcasts;")?;
+		let feeds = stmt
+			.query_map(params![], |row| {
+				// older databases may not have these columns yet
+				let custom_title = row.get::<&str, Option<String>>("custom_title").ok().flatten();
+				let removed = row.get::<&str, Option<i64>>("removed_at").ok().flatten().is_some();
+				let title = match custom_title
+				{
+					Some(title) => title,
+					None => row.get("title")?,
+				};
+				Ok((title, row.get("url")?, removed))
+			})?
+			.collect::<Result<Vec<(String, String, bool)>, _>>()?;
+		return Ok(feeds
+			.into_iter()
+			.filter(|(_, _, removed)| !removed)
+			.map(|(title, url, _)| (title, url))
+			.collect());
+	}
+
+	/// Replaces the database at `path` with the backup at `source`. The
+	/// backup must be a shellcaster database that this version of
+	/// shellcaster can read, and the database must not be in use by the
+	/// UI.
+	pub fn restore(path: &Path, source: &Path) -> Result<()>
+	{
+		check_backup(source)?;
+		if let Some(dir) = path.parent()
+		{
+			std::fs::create_dir_all(dir)
+				.with_context(|| "Unable to create subdirectory for database.")?;
+		}
+		let _lock = Self::lock(path, true)?.ok_or_else(|| anyhow!(
+			"Cannot restore the database while shellcaster is running. Please close it first."
+		))?;
+
+		let mut conn = open_connection(path)?;
+		conn.restore(DatabaseName::Main, source, None::<fn(rusqlite::backup::Progress)>)
+			.with_context(|| format!(
+				"Could not restore database from {}",
+				source.to_string_lossy()
+			))?;
 		return Ok(());
 	}
 
@@ -190,21 +305,38 @@ impl Database
 	/// database.
 	pub fn insert_podcast(&self, podcast: PodcastNoId) -> Result<SyncResult>
 	{
-		let mut conn = Connection::open(&self.path).expect("Error connecting to database.");
+		// a podcast imported without its feed is filled in, rather than
+		// added a second time
+		if let Some(pod_id) = self.placeholder_id(&podcast.url)?
+		{
+			let (result, _) = self.write_podcast(pod_id, podcast, 0, &[])?;
+			return Ok(result);
+		}
+
+		let mut conn = open_connection(&self.path)?;
 		let tx = conn.transaction()?;
 		// let conn = self.conn.as_ref().expect("Error connecting to database.");
+
+		// a podcast that was removed but not purged yet makes way for
+		// the podcast that is added again
+		tx.execute(
+			"DELETE FROM podcasts WHERE url = ? AND removed_at IS NOT NULL;",
+			params![podcast.url],
+		)?;
 		{
 			let mut stmt = tx.prepare_cached(
-				"INSERT INTO podcasts (title, url, description, author,
-				explicit, last_checked)
-				VALUES (?, ?, ?, ?, ?, ?);",
+				"INSERT INTO podcasts (title, url, link, description, author,
+				explicit, image_url, last_checked)
+				VALUES (?, ?, ?, ?, ?, ?, ?, ?);",
 			)?;
 			stmt.execute(params![
 				podcast.title,
 				podcast.url,
+				podcast.link,
 				podcast.description,
 				podcast.author,
 				podcast.explicit,
+				podcast.image_url,
 				podcast.last_checked.timestamp()
 			])?;
 		}
@@ -215,18 +347,26 @@ impl Database
 			pod_id = stmt.query_row::<i64, _, _>(params![podcast.url], |row| row.get(0))?;
 		}
 		let mut ep_ids = Vec::new();
-		for ep in podcast.episodes.iter().rev()
-		{
-			let id = self.insert_episode(&tx, pod_id, ep)?;
-			let new_ep = NewEpisode {
-				id: id,
-				pod_id: pod_id,
-				title: ep.title.clone(),
-				pod_title: podcast.title.clone(),
-				selected: false,
-			};
-			ep_ids.push(new_ep);
+		{
+			let mut insert_stmt = tx.prepare(INSERT_EPISODE)?;
+			for ep in podcast.episodes.iter().rev()
+			{
+				let id = self.insert_episode(&mut insert_stmt, pod_id, ep)?;
+				let new_ep = NewEpisode {
+					id: id,
+					pod_id: pod_id,
+					title: ep.title.clone(),
+					pod_title: podcast.title.clone(),
+					selected: false,
+				};
+				ep_ids.push(new_ep);
+			}
 		}
+		// the episodes of a new podcast are not new to the user
+		tx.execute(
+			"UPDATE podcasts SET last_viewed = ? WHERE id = ?;",
+			params![Utc::now().timestamp(), pod_id],
+		)?;
 		tx.commit()?;
 
 		return Ok(SyncResult {
@@ -235,45 +375,282 @@ impl Database
 		});
 	}
 
-	/// Inserts a podcast episode into the database.
+	/// Adds a podcast with only its feed URL and a provisional title
+	/// (e.g., from an OPML file), without retrieving the feed. The
+	/// podcast is marked as never synced; its first sync fills in the
+	/// rest. Returns the ID of the new podcast.
+	pub fn insert_placeholder(&self, url: &str, title: &str) -> Result<i64>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		conn.execute(
+			"DELETE FROM podcasts WHERE url = ? AND removed_at IS NOT NULL;",
+			params![url],
+		)?;
+		conn.execute(
+			"INSERT INTO podcasts (title, url, last_checked, synced) VALUES (?, ?, ?, 0);",
+			params![title, url, Utc::now().timestamp()],
+		)?;
+		return Ok(conn.last_insert_rowid());
+	}
+
+	/// Returns the ID of the podcast with this feed URL, if it has never
+	/// been synced.
+	fn placeholder_id(&self, url: &str) -> Result<Option<i64>>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		let pod_id = conn
+			.query_row(
+				"SELECT id FROM podcasts WHERE url = ? AND synced = 0 AND removed_at IS NULL;",
+				params![url],
+				|row| row.get(0),
+			)
+			.optional()?;
+		return Ok(pod_id);
+	}
+
+	/// Inserts a podcast episode into the database, using `stmt`, a
+	/// statement prepared from INSERT_EPISODE, so that inserting many
+	/// episodes only prepares it once. Returns the ID of the new episode.
 	pub fn insert_episode(
 		&self,
-		conn: &Connection,
+		stmt: &mut Statement,
 		podcast_id: i64,
 		episode: &EpisodeNoId,
 	) -> Result<i64>
 	{
 		let pubdate = episode.pubdate.map(|dt| dt.timestamp());
 
-		let mut stmt = conn.prepare_cached(
-			"INSERT INTO episodes (podcast_id, title, url, guid,
-				description, pubdate, duration, played, hidden)
-				VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?);",
-		)?;
-		stmt.execute(params![
+		let id = stmt.insert(params![
 			podcast_id,
 			episode.title,
 			episode.url,
 			episode.guid,
+			episode.link,
 			episode.description,
 			pubdate,
 			episode.duration,
+			episode.season,
+			episode.episode_num,
+			episode.enclosure_size.map(|size| size as i64),
 			false,
 			false,
+			Utc::now().timestamp(),
 		])?;
-		return Ok(conn.last_insert_rowid());
+		return Ok(id);
+	}
+
+	/// Merges a podcast from a JSON export into the database, in a
+	/// single transaction. The podcast is matched by feed URL, and its
+	/// episodes by GUID or, failing that, by URL; podcasts and episodes
+	/// that are not in the database yet are added. Unless `replace` is
+	/// set, the state of the export never overrides local state: the
+	/// podcast only gets the tags of the export if it has none, and
+	/// episodes are only marked as played or archived, given a playback
+	/// position or a downloaded file if they do not have one already.
+	/// With `replace`, the tags, played status, playback positions and
+	/// archived status of the export replace those in the database, as
+	/// do its downloaded files. Downloaded files are only recorded if
+	/// they exist on this machine. Returns the number of podcasts and
+	/// episodes added or changed.
+	pub fn merge_podcast(&self, podcast: &PodcastExport, replace: bool) -> Result<usize>
+	{
+		let mut conn = open_connection(&self.path)?;
+		let tx = conn.transaction()?;
+		let mut changes = 0;
+
+		let existing_id = tx
+			.query_row(
+				"SELECT id FROM podcasts WHERE url = ?;",
+				params![podcast.url],
+				|row| row.get::<usize, i64>(0),
+			)
+			.optional()?;
+		let pod_id = match existing_id
+		{
+			Some(id) => id,
+			None => {
+				changes += 1;
+				tx.execute(
+					"INSERT INTO podcasts (title, url, link, description, author,
+					explicit, image_url, last_checked, custom_title, inactive)
+					VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?);",
+					params![
+						podcast.title,
+						podcast.url,
+						podcast.link,
+						podcast.description,
+						podcast.author,
+						podcast.explicit,
+						podcast.image_url,
+						podcast.last_checked.timestamp(),
+						podcast.custom_title,
+						podcast.inactive,
+					],
+				)?;
+				tx.last_insert_rowid()
+			}
+		};
+
+		// tags are only taken from the export if the podcast has none,
+		// unless they are replaced
+		let local_tags: Vec<String> = {
+			let mut stmt = tx.prepare(
+				"SELECT tag FROM podcast_tags WHERE podcast_id = ? ORDER BY tag;"
+			)?;
+			let rows = stmt.query_map(params![pod_id], |row| row.get(0))?;
+			rows.collect::<Result<_, _>>()?
+		};
+		let mut export_tags = podcast.tags.clone();
+		export_tags.sort();
+		export_tags.dedup();
+		if (local_tags.is_empty() || replace) && local_tags != export_tags
+		{
+			if existing_id.is_some()
+			{
+				changes += 1;
+			}
+			tx.execute("DELETE FROM podcast_tags WHERE podcast_id = ?;", params![pod_id])?;
+			let mut stmt = tx.prepare(
+				"INSERT OR IGNORE INTO podcast_tags (podcast_id, tag) VALUES (?, ?);"
+			)?;
+			for tag in podcast.tags.iter()
+			{
+				stmt.execute(params![pod_id, tag])?;
+			}
+		}
+
+		// every episode of the podcast, including hidden ones so that they
+		// are not added again
+		let mut local_episodes: Vec<LocalEpisode> = {
+			let mut stmt = tx.prepare(
+				"SELECT episodes.id, episodes.guid, episodes.url, episodes.played,
+					episodes.position_secs, episodes.archived, files.path FROM episodes
+					LEFT JOIN files ON episodes.id = files.episode_id
+					WHERE episodes.podcast_id = ?;",
+			)?;
+			let rows = stmt.query_map(params![pod_id], |row| {
+				Ok(LocalEpisode {
+					id: row.get(0)?,
+					guid: row.get::<usize, Option<String>>(1)?.unwrap_or_default(),
+					url: row.get(2)?,
+					played: row.get(3)?,
+					position: row.get(4)?,
+					archived: row.get::<usize, Option<bool>>(5)?.unwrap_or(false),
+					path: row.get(6)?,
+				})
+			})?;
+			rows.collect::<Result<_, _>>()?
+		};
+
+		{
+			let mut insert_stmt = tx.prepare(INSERT_EPISODE)?;
+			for ep in podcast.episodes.iter()
+			{
+				let found = local_episodes.iter().position(|local| {
+					if !ep.guid.is_empty() && !local.guid.is_empty()
+					{
+						return ep.guid == local.guid;
+					}
+					return ep.url == local.url;
+				});
+				let local = match found
+				{
+					Some(idx) => local_episodes[idx].clone(),
+					None => {
+						changes += 1;
+						let id = self.insert_episode(&mut insert_stmt, pod_id, &ep.to_episode_no_id())?;
+						let local = LocalEpisode {
+							id: id,
+							guid: ep.guid.clone(),
+							url: ep.url.clone(),
+							played: false,
+							position: None,
+							archived: false,
+							path: None,
+						};
+						local_episodes.push(local.clone());
+						local
+					}
+				};
+				let id = local.id;
+
+				let mut changed = false;
+				if ep.played && !local.played
+				{
+					tx.execute(
+						"UPDATE episodes SET played = 1, position_secs = NULL WHERE id = ?;",
+						params![id],
+					)?;
+					changed = true;
+				}
+				else if !ep.played
+					&& ((replace && (local.played || local.position != ep.position))
+						|| (!local.played && local.position.is_none() && ep.position.is_some()))
+				{
+					tx.execute(
+						"UPDATE episodes SET played = 0, position_secs = ? WHERE id = ?;",
+						params![ep.position, id],
+					)?;
+					changed = true;
+				}
+				if ep.archived != local.archived && (ep.archived || replace)
+				{
+					tx.execute(
+						"UPDATE episodes SET archived = ? WHERE id = ?;",
+						params![ep.archived, id],
+					)?;
+					changed = true;
+				}
+				if let Some(ep_path) = &ep.path
+				{
+					let same_path = local.path.as_deref() == ep_path.to_str();
+					if (local.path.is_none() || (replace && !same_path)) && ep_path.exists()
+					{
+						let size = std::fs::metadata(ep_path)
+							.ok()
+							.map(|meta| meta.len() as i64);
+						tx.execute("DELETE FROM files WHERE episode_id = ?;", params![id])?;
+						tx.execute(
+							"INSERT INTO files (episode_id, path, file_size) VALUES (?, ?, ?);",
+							params![id, ep_path.to_str(), size],
+						)?;
+						changed = true;
+					}
+				}
+				if changed && found.is_some()
+				{
+					changes += 1;
+				}
+			}
+		}
+		if existing_id.is_none()
+		{
+			tx.execute(
+				"UPDATE podcasts SET last_viewed = ? WHERE id = ?;",
+				params![Utc::now().timestamp(), pod_id],
+			)?;
+		}
+		tx.commit()?;
+		return Ok(changes);
 	}
 
-	/// Inserts a filepath to a downloaded episode.
-	pub fn insert_file(&self, episode_id: i64, path: &Path) -> Result<()>
+	/// Inserts a filepath to a downloaded episode, along with the size
+	/// of the file in bytes, if known. The current time is recorded as
+	/// the time of the download.
+	pub fn insert_file(&self, episode_id: i64, path: &Path, size: Option<u64>) -> Result<()>
 	{
 		let conn = self.conn.as_ref().expect("Error connecting to database.");
 
 		let mut stmt = conn.prepare_cached(
-			"INSERT INTO files (episode_id, path)
-				VALUES (?, ?);",
+			"INSERT INTO files (episode_id, path, downloaded_at, file_size)
+				VALUES (?, ?, ?, ?);",
 		)?;
-		stmt.execute(params![episode_id, path.to_str(),])?;
+		stmt.execute(params![
+			episode_id,
+			path.to_str(),
+			Utc::now().timestamp(),
+			size.map(|size| size as i64),
+		])?;
 		return Ok(());
 	}
 
@@ -287,6 +664,36 @@ impl Database
 		return Ok(());
 	}
 
+	/// Returns the episode ID and path of every downloaded file in the
+	/// database, including those of archived episodes and of podcasts
+	/// that are waiting to be purged.
+	pub fn get_files(&self) -> Result<Vec<(i64, PathBuf)>>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		let mut stmt = conn.prepare_cached("SELECT episode_id, path FROM files;")?;
+		let files = stmt
+			.query_map(params![], |row| {
+				Ok((row.get("episode_id")?, PathBuf::from(row.get::<&str, String>("path")?)))
+			})?
+			.collect::<Result<_, _>>()?;
+		return Ok(files);
+	}
+
+	/// Returns the paths of the cover art of all podcasts, including those
+	/// that are waiting to be purged.
+	pub fn get_cover_paths(&self) -> Result<Vec<PathBuf>>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		let mut stmt = conn.prepare_cached(
+			"SELECT cover_path FROM podcasts WHERE cover_path IS NOT NULL;"
+		)?;
+		let paths = stmt
+			.query_map(params![], |row| row.get::<usize, String>(0))?
+			.map(|path| path.map(PathBuf::from))
+			.collect::<Result<_, _>>()?;
+		return Ok(paths);
+	}
+
 	/// Removes all file listings for the selected episode ids.
 	pub fn remove_files(&self, episode_ids: &[i64]) -> Result<()>
 	{
@@ -316,29 +723,152 @@ impl Database
 
 	/// Updates an existing podcast in the database, where metadata is
 	/// changed if necessary, and episodes are updated (modified episodes
-	/// are updated, new episodes are inserted).
-	pub fn update_podcast(&self, pod_id: i64, podcast: PodcastNoId) -> Result<SyncResult>
+	/// are updated, new episodes are inserted). Afterwards, the oldest
+	/// episodes beyond the podcast's episode limit are hidden; the limit
+	/// set for the podcast takes precedence over `max_episodes`, and the
+	/// episodes in `downloading` are kept, along with the others that
+	/// `prune_episodes()` never hides. All of
+	/// this happens in a single transaction. On the first sync of a
+	/// podcast imported without its feed, its episodes are not new to the
+	/// user, just like those of a podcast that was just added, so none
+	/// are reported as added.
+	pub fn update_podcast(
+		&self,
+		pod_id: i64,
+		podcast: PodcastNoId,
+		max_episodes: usize,
+		downloading: &[i64],
+	) -> Result<SyncResult>
+	{
+		let (mut result, first_sync) =
+			self.write_podcast(pod_id, podcast, max_episodes, downloading)?;
+		if first_sync
+		{
+			result.added.clear();
+		}
+		return Ok(result);
+	}
+
+	/// Writes the data from a feed to an existing podcast; see
+	/// `update_podcast()`. Also returns whether the podcast had never
+	/// been synced before.
+	fn write_podcast(
+		&self,
+		pod_id: i64,
+		podcast: PodcastNoId,
+		max_episodes: usize,
+		downloading: &[i64],
+	) -> Result<(SyncResult, bool)>
 	{
+		// the episodes are read within the transaction, so that they cannot
+		// change before the new ones are written; the transaction takes
+		// the write lock right away, so that it is not refused later on
+		let mut conn = open_connection(&self.path)?;
+		let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+		let old_episodes = read_episodes(&tx, pod_id, true)?;
+		let first_sync = !tx.query_row(
+			"SELECT COALESCE(synced, 1) FROM podcasts WHERE id = ?;",
+			params![pod_id],
+			|row| row.get::<_, bool>(0),
+		)?;
 		{
-			let conn = self.conn.as_ref().expect("Error connecting to database.");
-			let mut stmt = conn.prepare_cached(
-				"UPDATE podcasts SET title = ?, url = ?, description = ?,
-			author = ?, explicit = ?, last_checked = ?
-			WHERE id = ?;",
+			let mut stmt = tx.prepare_cached(
+				"UPDATE podcasts SET title = ?, url = ?, link = ?, description = ?,
+			author = ?, explicit = ?, image_url = ?, last_checked = ?,
+			failures = 0, last_error = NULL, last_error_at = NULL,
+			inactive = 0, synced = 1 WHERE id = ?;",
 			)?;
 			stmt.execute(params![
 				podcast.title,
 				podcast.url,
+				podcast.link,
 				podcast.description,
 				podcast.author,
 				podcast.explicit,
+				podcast.image_url,
 				podcast.last_checked.timestamp(),
 				pod_id,
 			])?;
 		}
 
-		let result = self.update_episodes(pod_id, podcast.title, podcast.episodes)?;
-		return Ok(result);
+		let result = self.update_episodes(
+			&tx, pod_id, podcast.title, podcast.episodes, old_episodes
+		)?;
+		if first_sync
+		{
+			tx.execute(
+				"UPDATE podcasts SET last_viewed = ? WHERE id = ?;",
+				params![Utc::now().timestamp(), pod_id],
+			)?;
+		}
+		prune_episodes(&tx, pod_id, max_episodes, downloading)?;
+		tx.commit()?;
+		return Ok((result, first_sync));
+	}
+
+	/// Records the location of the cover art downloaded for a podcast.
+	pub fn set_cover_path(&self, pod_id: i64, path: &Path) -> Result<()>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+
+		let mut stmt = conn.prepare_cached(
+			"UPDATE podcasts SET cover_path = ? WHERE id = ?;"
+		)?;
+		stmt.execute(params![path.to_str(), pod_id])?;
+		return Ok(());
+	}
+
+	/// Sets the maximum number of episodes kept for a podcast, overriding
+	/// the global setting. Setting it to None reverts to the global
+	/// setting, while 0 keeps all episodes.
+	pub fn set_max_episodes(&self, pod_id: i64, max_episodes: Option<usize>) -> Result<()>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+
+		let mut stmt = conn.prepare_cached(
+			"UPDATE podcasts SET max_episodes = ? WHERE id = ?;"
+		)?;
+		stmt.execute(params![max_episodes.map(|num| num as i64), pod_id])?;
+		return Ok(());
+	}
+
+	/// Records a failed sync for a podcast, along with the error and the
+	/// time it happened; both are cleared by the next successful sync.
+	/// Once the podcast has failed to sync `max_failures` times in a row
+	/// (if `max_failures` is not zero), or right away if the feed is
+	/// `gone`, the podcast is flagged as inactive. Returns whether the
+	/// podcast is now inactive.
+	pub fn record_sync_failure(
+		&self,
+		pod_id: i64,
+		error: &str,
+		gone: bool,
+		max_failures: usize,
+	) -> Result<bool>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		conn.execute(
+			"UPDATE podcasts SET failures = COALESCE(failures, 0) + 1,
+				last_error = ?, last_error_at = ? WHERE id = ?;",
+			params![error, Utc::now().timestamp(), pod_id],
+		)?;
+		let failures: i64 = conn.query_row(
+			"SELECT failures FROM podcasts WHERE id = ?;",
+			params![pod_id],
+			|row| row.get(0),
+		)?;
+
+		let inactive = gone || (max_failures > 0 && failures >= max_failures as i64);
+		log::debug!("Podcast {pod_id} has failed to sync {failures} times in a row");
+		if inactive
+		{
+			log::debug!("Marking podcast {pod_id} as inactive");
+			conn.execute(
+				"UPDATE podcasts SET inactive = 1 WHERE id = ?;",
+				params![pod_id],
+			)?;
+		}
+		return Ok(inactive);
 	}
 
 	/// Updates metadata about episodes that already exist in database,
@@ -351,12 +881,13 @@ impl Database
 	/// database.
 	fn update_episodes(
 		&self,
+		tx: &Transaction,
 		podcast_id: i64,
 		podcast_title: String,
 		episodes: Vec<EpisodeNoId>,
+		old_episodes: Vec<Episode>,
 	) -> Result<SyncResult>
 	{
-		let old_episodes = self.get_episodes(podcast_id, true)?;
 		let mut old_ep_map = AHashMap::new();
 		for ep in old_episodes.iter()
 		{
@@ -366,8 +897,13 @@ impl Database
 			}
 		}
 
-		let mut conn = Connection::open(&self.path).expect("Error connecting to database.");
-		let tx = conn.transaction()?;
+		let mut insert_stmt = tx.prepare(INSERT_EPISODE)?;
+		let mut update_stmt = tx.prepare(
+			"UPDATE episodes SET title = ?, url = ?,
+				guid = ?, link = ?, description = ?, pubdate = ?,
+				duration = ?, season = ?, episode_num = ?, enclosure_size = ?
+				WHERE id = ?;",
+		)?;
 
 		let mut insert_ep = Vec::new();
 		let mut update_ep = Vec::new();
@@ -423,25 +959,24 @@ impl Database
 				Some(id) => {
 					if update
 					{
-						let mut stmt = tx.prepare_cached(
-							"UPDATE episodes SET title = ?, url = ?,
-								guid = ?, description = ?, pubdate = ?,
-								duration = ? WHERE id = ?;",
-						)?;
-						stmt.execute(params![
+						update_stmt.execute(params![
 							new_ep.title,
 							new_ep.url,
 							new_ep.guid,
+							new_ep.link,
 							new_ep.description,
 							new_pd,
 							new_ep.duration,
+							new_ep.season,
+							new_ep.episode_num,
+							new_ep.enclosure_size.map(|size| size as i64),
 							id,
 						])?;
 						update_ep.push(id);
 					}
 				}
 				None => {
-					let id = self.insert_episode(&tx, podcast_id, new_ep)?;
+					let id = self.insert_episode(&mut insert_stmt, podcast_id, new_ep)?;
 					let new_ep = NewEpisode {
 						id: id,
 						pod_id: podcast_id,
@@ -453,7 +988,6 @@ impl Database
 				}
 			}
 		}
-		tx.commit()?;
 		return Ok(SyncResult {
 			added: insert_ep,
 			updated: update_ep,
@@ -477,8 +1011,12 @@ impl Database
 		if !(new_ep.title == old_ep.title
 			&& new_ep.url == old_ep.url
 			&& new_ep.guid == old_ep.guid
+			&& new_ep.link == old_ep.link
 			&& new_ep.description == old_ep.description
 			&& new_ep.duration == old_ep.duration
+			&& new_ep.season == old_ep.season
+			&& new_ep.episode_num == old_ep.episode_num
+			&& new_ep.enclosure_size == old_ep.enclosure_size
 			&& pd_match)
 		{
 			return true;
@@ -486,131 +1024,1133 @@ impl Database
 		return false;
 	}
 
-	/// Updates an episode to mark it as played or unplayed.
-	pub fn set_played_status(&self, episode_id: i64, played: bool) -> Result<()>
+	/// Updates an episode to mark it as played or unplayed. Marking an
+	/// episode as played also clears its saved playback position, unless
+	/// `keep_position` is set, e.g., when it is marked as it starts
+	/// playing.
+	pub fn set_played_status(&self, episode_id: i64, played: bool, keep_position: bool) -> Result<()>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+
+		let mut stmt = conn.prepare_cached(
+			"UPDATE episodes SET played = ?,
+				position_secs = CASE WHEN ? THEN NULL ELSE position_secs END,
+				changed_at = ?
+				WHERE id = ?;"
+		)?;
+		let clear_position = played && !keep_position;
+		stmt.execute(params![played, clear_position, Utc::now().timestamp(), episode_id])?;
+		return Ok(());
+	}
+
+	/// Marks all episodes of a podcast that are shown in the list as
+	/// played or unplayed, in a single statement. Only the episodes whose
+	/// status actually changes are recorded as changed.
+	pub fn set_all_played_status(&self, pod_id: i64, played: bool) -> Result<()>
 	{
 		let conn = self.conn.as_ref().expect("Error connecting to database.");
 
-		let mut stmt = conn.prepare_cached("UPDATE episodes SET played = ? WHERE id = ?;")?;
-		stmt.execute(params![played, episode_id])?;
+		let mut stmt = conn.prepare_cached(
+			"UPDATE episodes SET played = ?,
+				position_secs = CASE WHEN ? THEN NULL ELSE position_secs END,
+				changed_at = CASE WHEN COALESCE(played, 0) != ? THEN ? ELSE changed_at END
+				WHERE podcast_id = ? AND hidden = 0;"
+		)?;
+		stmt.execute(params![played, played, played, Utc::now().timestamp(), pod_id])?;
+		return Ok(());
+	}
+
+	/// Saves the playback position of an episode, in seconds, or clears
+	/// it if `position` is None.
+	pub fn set_position(&self, episode_id: i64, position: Option<i64>) -> Result<()>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+
+		let mut stmt = conn.prepare_cached(
+			"UPDATE episodes SET position_secs = ?, changed_at = ? WHERE id = ?;"
+		)?;
+		stmt.execute(params![position, Utc::now().timestamp(), episode_id])?;
 		return Ok(());
 	}
 
 	/// Updates an episode to "remove" it by hiding it. "Removed"
 	/// episodes need to stay in the database so that they don't get
-	/// re-added when the podcast is synced again.
+	/// re-added when the podcast is synced again. Hidden episodes are
+	/// also taken out of the queue.
 	pub fn hide_episode(&self, episode_id: i64, hide: bool) -> Result<()>
 	{
 		let conn = self.conn.as_ref().expect("Error connecting to database.");
 
 		let mut stmt = conn.prepare_cached("UPDATE episodes SET hidden = ? WHERE id = ?;")?;
 		stmt.execute(params![hide, episode_id])?;
+		if hide
+		{
+			self.dequeue(episode_id)?;
+		}
 		return Ok(());
 	}
 
-	/// Generates list of all podcasts in database.
-	/// TODO: This should probably use a JOIN statement instead.
-	pub fn get_podcasts(&self) -> Result<Vec<Podcast>>
+	/// Archives or unarchives an episode. Archived episodes stay in the
+	/// list, but are only shown on request. Archiving an episode also
+	/// takes it out of the queue.
+	pub fn set_archived(&self, episode_id: i64, archived: bool) -> Result<()>
 	{
 		let conn = self.conn.as_ref().expect("Error connecting to database.");
-		let mut stmt = conn.prepare_cached("SELECT * FROM podcasts;")?;
-		let podcast_iter = stmt.query_map(params![], |row| {
-			let pod_id = row.get("id")?;
-			let episodes = match self.get_episodes(pod_id, false)
-			{
-				Ok(ep_list) => Ok(ep_list),
-				Err(_) => Err(rusqlite::Error::QueryReturnedNoRows),
-			}?;
-
-			// create a sort title that is lowercased and removes
-			// articles from the beginning
-			let title: String = row.get("title")?;
-			let title_lower = title.to_lowercase();
-			let sort_title = RE_ARTICLES.replace(&title_lower, "").to_string();
 
-			Ok(Podcast {
-				id: pod_id,
-				title: title,
-				sort_title: sort_title,
-				url: row.get("url")?,
-				description: row.get("description")?,
-				author: row.get("author")?,
-				explicit: row.get("explicit")?,
-				last_checked: convert_date(row.get("last_checked")).unwrap(),
-				episodes: LockVec::new(episodes),
-			})
-		})?;
-		let mut podcasts = Vec::new();
-		for pc in podcast_iter
+		let mut stmt = conn.prepare_cached("UPDATE episodes SET archived = ? WHERE id = ?;")?;
+		stmt.execute(params![archived, episode_id])?;
+		if archived
 		{
-			podcasts.push(pc?);
+			self.dequeue(episode_id)?;
 		}
-		podcasts.sort_unstable();
-
-		return Ok(podcasts);
+		return Ok(());
 	}
 
-	/// Generates list of episodes for a given podcast.
-	pub fn get_episodes(
-		&self,
-		pod_id: i64,
-		include_hidden: bool
-	) -> Result<Vec<Episode>>
+	/// Archives all episodes of a podcast that were published before the
+	/// given episode, and takes them out of the queue. Returns the number
+	/// of episodes archived.
+	pub fn archive_older(&self, pod_id: i64, episode_id: i64) -> Result<usize>
 	{
-		let conn = self.conn.as_ref().expect("Error connecting to database.");
-		let mut stmt = if include_hidden
-		{
-			conn.prepare_cached(
-				"SELECT * FROM episodes
-						LEFT JOIN files ON episodes.id = files.episode_id
-						WHERE episodes.podcast_id = ?
-						ORDER BY pubdate DESC;",
-			)?
-		}
-		else
+		let mut conn = open_connection(&self.path)?;
+		let tx = conn.transaction()?;
+		let condition = "podcast_id = ? AND hidden = 0 AND archived = 0
+			AND pubdate < (SELECT pubdate FROM episodes WHERE id = ?)";
+		tx.execute(
+			&format!(
+				"DELETE FROM queue WHERE episode_id IN
+					(SELECT id FROM episodes WHERE {condition});"
+			),
+			params![pod_id, episode_id],
+		)?;
+		let count = tx.execute(
+			&format!("UPDATE episodes SET archived = 1 WHERE {condition};"),
+			params![pod_id, episode_id],
+		)?;
+		tx.commit()?;
+		return Ok(count);
+	}
+
+	/// Records that an episode was listened to, with the time the player
+	/// started and how many seconds it ran for.
+	pub fn add_listen(&self, episode_id: i64, started_at: DateTime<Utc>, seconds: u64) -> Result<()>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+
+		let mut stmt = conn.prepare_cached(
+			"INSERT INTO listens (episode_id, started_at, seconds) VALUES (?, ?, ?);"
+		)?;
+		stmt.execute(params![episode_id, started_at.timestamp(), seconds as i64])?;
+		return Ok(());
+	}
+
+	/// Adds an episode at the end of the queue. Returns false if the
+	/// episode was already in the queue.
+	pub fn enqueue(&self, episode_id: i64) -> Result<bool>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+
+		let mut stmt = conn.prepare_cached(
+			"INSERT OR IGNORE INTO queue (episode_id, position)
+				SELECT ?, COALESCE(MAX(position), 0) + 1 FROM queue;",
+		)?;
+		let inserted = stmt.execute(params![episode_id])?;
+		return Ok(inserted > 0);
+	}
+
+	/// Takes an episode out of the queue.
+	pub fn dequeue(&self, episode_id: i64) -> Result<()>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+
+		let mut stmt = conn.prepare_cached("DELETE FROM queue WHERE episode_id = ?;")?;
+		stmt.execute(params![episode_id])?;
+		return Ok(());
+	}
+
+	/// Replaces the contents of the queue with the given episodes, in
+	/// that order.
+	pub fn set_queue(&self, episode_ids: &[i64]) -> Result<()>
+	{
+		let mut conn = open_connection(&self.path)?;
+		let tx = conn.transaction()?;
+		tx.execute("DELETE FROM queue;", params![])?;
 		{
-			conn.prepare_cached(
-				"SELECT * FROM episodes
-						LEFT JOIN files ON episodes.id = files.episode_id
-						WHERE episodes.podcast_id = ?
-						AND episodes.hidden = 0
-						ORDER BY pubdate DESC;",
-			)?
-		};
-		let episode_iter = stmt.query_map(params![pod_id], |row| {
-			let path = match row.get::<&str, String>("path")
+			let mut stmt = tx.prepare_cached(
+				"INSERT OR IGNORE INTO queue (episode_id, position) VALUES (?, ?);"
+			)?;
+			for (position, episode_id) in episode_ids.iter().enumerate()
+			{
+				stmt.execute(params![episode_id, position as i64 + 1])?;
+			}
+		}
+		tx.commit()?;
+		return Ok(());
+	}
+
+	/// Returns the podcast and episode IDs of the episodes in the queue,
+	/// in order.
+	pub fn get_queue(&self) -> Result<Vec<(i64, i64)>>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+
+		let mut stmt = conn.prepare_cached(
+			"SELECT episodes.podcast_id, queue.episode_id FROM queue
+				INNER JOIN episodes ON queue.episode_id = episodes.id
+				INNER JOIN podcasts ON episodes.podcast_id = podcasts.id
+				WHERE episodes.hidden = 0 AND podcasts.removed_at IS NULL
+				ORDER BY queue.position;",
+		)?;
+		let queue = stmt
+			.query_map(params![], |row| Ok((row.get(0)?, row.get(1)?)))?
+			.flatten()
+			.collect();
+		return Ok(queue);
+	}
+
+	/// Sets the title displayed for a podcast instead of the title from
+	/// its feed. Syncing the podcast does not change this title. Setting
+	/// it to None reverts to the title from the feed.
+	pub fn set_custom_title(&self, pod_id: i64, title: Option<&str>) -> Result<()>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+
+		let mut stmt = conn.prepare_cached(
+			"UPDATE podcasts SET custom_title = ? WHERE id = ?;"
+		)?;
+		stmt.execute(params![title, pod_id])?;
+		return Ok(());
+	}
+
+	/// Adds a podcast to the favorites, or removes it from them.
+	pub fn set_favorite(&self, pod_id: i64, favorite: bool) -> Result<()>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+
+		let mut stmt = conn.prepare_cached(
+			"UPDATE podcasts SET favorite = ? WHERE id = ?;"
+		)?;
+		stmt.execute(params![favorite, pod_id])?;
+		return Ok(());
+	}
+
+	/// Replaces the tags of a podcast, in a single transaction.
+	pub fn set_tags(&self, pod_id: i64, tags: &[String]) -> Result<()>
+	{
+		let mut conn = open_connection(&self.path)?;
+		let tx = conn.transaction()?;
+		tx.execute("DELETE FROM podcast_tags WHERE podcast_id = ?;", params![pod_id])?;
+		{
+			let mut stmt = tx.prepare_cached(
+				"INSERT OR IGNORE INTO podcast_tags (podcast_id, tag) VALUES (?, ?);"
+			)?;
+			for tag in tags.iter()
 			{
-				Ok(val) => Some(PathBuf::from(val)),
-				Err(_) => None,
-			};
-			Ok(Episode {
-				id: row.get("id")?,
-				pod_id: row.get("podcast_id")?,
-				title: row.get("title")?,
+				stmt.execute(params![pod_id, tag])?;
+			}
+		}
+		tx.commit()?;
+		return Ok(());
+	}
+
+	/// Returns the tags of a podcast, in alphabetical order.
+	pub fn get_tags(&self, pod_id: i64) -> Result<Vec<String>>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		let mut stmt = conn.prepare_cached(
+			"SELECT tag FROM podcast_tags WHERE podcast_id = ? ORDER BY tag;"
+		)?;
+		let tags = stmt
+			.query_map(params![pod_id], |row| row.get(0))?
+			.collect::<Result<_, _>>()?;
+		return Ok(tags);
+	}
+
+	/// Generates list of all podcasts in database.
+	/// TODO: This should probably use a JOIN statement instead.
+	pub fn get_podcasts(&self) -> Result<Vec<Podcast>>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		let mut stmt = conn.prepare_cached("SELECT * FROM podcasts WHERE removed_at IS NULL;")?;
+		let podcast_iter = stmt.query_map(params![], |row| {
+			let pod_id = row.get("id")?;
+
+			// a custom title set by the user takes precedence over
+			// the title from the feed
+			let feed_title: String = row.get("title")?;
+			let title = row.get::<&str, Option<String>>("custom_title")?
+				.unwrap_or_else(|| feed_title.clone());
+
+			// create a sort title that is lowercased and removes
+			// articles from the beginning
+			let title_lower = title.to_lowercase();
+			let sort_title = RE_ARTICLES.replace(&title_lower, "").to_string();
+
+			Ok(Podcast {
+				id: pod_id,
+				title: title,
+				feed_title: feed_title,
+				sort_title: sort_title,
 				url: row.get("url")?,
-				guid: row
-					.get::<&str, Option<String>>("guid")?
-					.unwrap_or_else(|| "".to_string()),
+				link: row.get("link")?,
 				description: row.get("description")?,
-				pubdate: convert_date(row.get("pubdate")),
-				duration: row.get("duration")?,
-				path: path,
-				played: row.get("played")?,
+				author: row.get("author")?,
+				explicit: row.get("explicit")?,
+				image_url: row.get("image_url")?,
+				cover_path: row.get::<&str, Option<String>>("cover_path")?
+					.map(PathBuf::from),
+				last_checked: convert_date(row.get("last_checked")).unwrap(),
+				inactive: row.get::<&str, Option<bool>>("inactive")?
+					.unwrap_or(false),
+				favorite: row.get::<&str, Option<bool>>("favorite")?
+					.unwrap_or(false),
+				synced: row.get::<&str, Option<bool>>("synced")?
+					.unwrap_or(true),
+				last_error: row.get("last_error")?,
+				last_error_at: convert_date(row.get("last_error_at")),
+				// filled in below, as errors from other queries cannot
+				// be returned from here
+				tags: Vec::new(),
+				episode_sort: EpisodeSort::from_name(
+					row.get::<&str, Option<String>>("episode_sort")?.as_deref()
+				),
+				episodes: LockVec::new(Vec::new()),
 			})
 		})?;
-		let episodes = episode_iter.flatten().collect();
-		return Ok(episodes);
+		let mut podcasts = Vec::new();
+		for pc in podcast_iter
+		{
+			let mut pc = pc?;
+			pc.tags = self
+				.get_tags(pc.id)
+				.with_context(|| format!("Could not read the tags of {}", pc.title))?;
+			let episodes = self
+				.get_episodes(pc.id, false)
+				.with_context(|| format!("Could not read the episodes of {}", pc.title))?;
+			pc.episodes = LockVec::new(episodes);
+			podcasts.push(pc);
+		}
+		podcasts.sort_unstable();
+
+		return Ok(podcasts);
+	}
+
+	/// Counts the episodes of each podcast that are shown in the list, the
+	/// ones played and the ones downloaded, and adds up the sizes of the
+	/// downloaded files of each podcast (including those of removed
+	/// episodes). Files whose size was not recorded when they were
+	/// downloaded are listed instead, so that their size can be taken
+	/// from the file system.
+	pub fn get_stats(&self) -> Result<Vec<PodcastStats>>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		let mut stmt = conn.prepare_cached(
+			"SELECT podcasts.id,
+				COALESCE(podcasts.custom_title, podcasts.title),
+				COUNT(episodes.id),
+				COALESCE(SUM(episodes.played), 0),
+				COUNT(files.id)
+				FROM podcasts
+				LEFT JOIN episodes ON episodes.podcast_id = podcasts.id
+					AND episodes.hidden = 0
+				LEFT JOIN files ON files.episode_id = episodes.id
+				WHERE podcasts.removed_at IS NULL
+				GROUP BY podcasts.id;",
+		)?;
+		let mut stats: Vec<PodcastStats> = stmt
+			.query_map(params![], |row| {
+				Ok(PodcastStats {
+					id: row.get(0)?,
+					title: row.get(1)?,
+					episodes: row.get::<usize, i64>(2)? as usize,
+					played: row.get::<usize, i64>(3)? as usize,
+					downloaded: row.get::<usize, i64>(4)? as usize,
+					disk_usage: 0,
+					files: Vec::new(),
+				})
+			})?
+			.collect::<Result<_, _>>()?;
+
+		let mut stmt = conn.prepare_cached(
+			"SELECT episodes.podcast_id, files.path, files.file_size FROM files
+				INNER JOIN episodes ON files.episode_id = episodes.id;",
+		)?;
+		let files = stmt.query_map(params![], |row| {
+			Ok((
+				row.get::<usize, i64>(0)?,
+				row.get::<usize, String>(1)?,
+				row.get::<usize, Option<i64>>(2)?,
+			))
+		})?;
+		for (pod_id, path, size) in files.flatten()
+		{
+			if let Some(pod) = stats.iter_mut().find(|pod| pod.id == pod_id)
+			{
+				match size
+				{
+					Some(size) => pod.disk_usage += size as u64,
+					None => pod.files.push(PathBuf::from(path)),
+				}
+			}
+		}
+
+		stats.sort_by_key(|pod| pod.title.to_lowercase());
+		return Ok(stats);
 	}
 
-	/// Deletes all rows in all tables
-	pub fn clear_db(&self) -> Result<()>
+	/// Sets the order in which the episodes of a podcast are listed.
+	pub fn set_episode_sort(&self, pod_id: i64, sort: EpisodeSort) -> Result<()>
 	{
 		let conn = self.conn.as_ref().expect("Error connecting to database.");
-		conn.execute("DELETE FROM files;", params![])?;
-		conn.execute("DELETE FROM episodes;", params![])?;
-		conn.execute("DELETE FROM podcasts;", params![])?;
+		conn.execute(
+			"UPDATE podcasts SET episode_sort = ? WHERE id = ?;",
+			params![sort.as_str(), pod_id],
+		)?;
+		return Ok(());
+	}
+
+	/// Marks a podcast as removed at the given time, or restores it if no
+	/// time is given. Removed podcasts are left out of the podcast list,
+	/// but stay in the database until they are purged, so that the
+	/// removal can be undone. `delete_files` records whether the
+	/// downloaded files should be deleted when the podcast is purged.
+	pub fn set_removed(
+		&self,
+		pod_id: i64,
+		removed_at: Option<DateTime<Utc>>,
+		delete_files: bool
+	) -> Result<()>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		conn.execute(
+			"UPDATE podcasts SET removed_at = ?, remove_files = ? WHERE id = ?;",
+			params![removed_at.map(|time| time.timestamp()), delete_files, pod_id],
+		)?;
+		return Ok(());
+	}
+
+	/// Returns the feed URLs that were subscribed to after the last sync
+	/// with a gpodder.net account, which is identified by the server,
+	/// username and device.
+	pub fn get_gpodder_subscriptions(&self, account: &str) -> Result<Vec<String>>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		let mut stmt = conn.prepare_cached(
+			"SELECT url FROM gpodder_subscriptions WHERE account = ? ORDER BY url;"
+		)?;
+		let urls = stmt
+			.query_map(params![account], |row| row.get(0))?
+			.collect::<Result<Vec<String>, _>>()?;
+		return Ok(urls);
+	}
+
+	/// Replaces the feed URLs that were subscribed to after the last
+	/// sync with a gpodder.net account.
+	pub fn set_gpodder_subscriptions(&self, account: &str, urls: &[String]) -> Result<()>
+	{
+		let mut conn = open_connection(&self.path)?;
+		let tx = conn.transaction()?;
+		tx.execute("DELETE FROM gpodder_subscriptions WHERE account = ?;", params![account])?;
+		{
+			let mut stmt = tx.prepare_cached(
+				"INSERT OR IGNORE INTO gpodder_subscriptions (account, url) VALUES (?, ?);"
+			)?;
+			for url in urls.iter()
+			{
+				stmt.execute(params![account, url])?;
+			}
+		}
+		tx.commit()?;
+		return Ok(());
+	}
+
+	/// Returns where the last sync of episode actions with a gpodder.net
+	/// account left off: the timestamp given by the server for the
+	/// actions retrieved so far (0 before the first sync), and the time
+	/// up to which changes made here have been uploaded, if ever.
+	pub fn get_episode_sync(&self, account: &str) -> Result<(i64, Option<DateTime<Utc>>)>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		let mut stmt = conn.prepare_cached(
+			"SELECT since, uploaded_until FROM gpodder_episode_sync WHERE account = ?;"
+		)?;
+		let mut rows = stmt.query(params![account])?;
+		return match rows.next()?
+		{
+			Some(row) => Ok((row.get(0)?, convert_date(row.get(1)))),
+			None => Ok((0, None)),
+		};
+	}
+
+	/// Returns the episodes of podcasts in the list whose played status
+	/// or playback position was changed here, or whose file was
+	/// downloaded, after `after` (or ever, if it is None) and up to
+	/// `until`.
+	pub fn get_episode_changes(
+		&self,
+		after: Option<DateTime<Utc>>,
+		until: DateTime<Utc>
+	) -> Result<Vec<EpisodeChange>>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		let mut stmt = conn.prepare_cached(
+			"SELECT podcasts.url, episodes.url, episodes.guid, episodes.played,
+				episodes.position_secs, episodes.duration, episodes.changed_at,
+				files.downloaded_at FROM episodes
+				INNER JOIN podcasts ON episodes.podcast_id = podcasts.id
+				LEFT JOIN files ON episodes.id = files.episode_id
+				WHERE podcasts.removed_at IS NULL
+				AND ((episodes.changed_at > ?1 AND episodes.changed_at <= ?2)
+					OR (files.downloaded_at > ?1 AND files.downloaded_at <= ?2))
+				ORDER BY episodes.id;",
+		)?;
+		let after = after.map(|time| time.timestamp()).unwrap_or(i64::MIN);
+		let changes = stmt
+			.query_map(params![after, until.timestamp()], |row| {
+				return Ok(EpisodeChange {
+					podcast_url: row.get(0)?,
+					url: row.get(1)?,
+					guid: row.get::<usize, Option<String>>(2)?.unwrap_or_default(),
+					played: row.get::<usize, Option<bool>>(3)?.unwrap_or(false),
+					position: row.get(4)?,
+					duration: row.get(5)?,
+					changed_at: convert_date(row.get(6)),
+					downloaded_at: convert_date(row.get(7)),
+				});
+			})?
+			.collect::<Result<Vec<EpisodeChange>, _>>()?;
+		return Ok(changes);
+	}
+
+	/// Applies the played statuses and playback positions from other
+	/// devices, and records `since`, the timestamp given by the server
+	/// along with them, all in one transaction, so that nothing is
+	/// skipped if they cannot be applied. An update is only applied if
+	/// the episode has not been changed here since. Returns the podcast
+	/// ID of each episode that changed.
+	pub fn apply_episode_updates(
+		&self,
+		account: &str,
+		updates: &[EpisodeUpdate],
+		since: i64
+	) -> Result<Vec<i64>>
+	{
+		let mut conn = open_connection(&self.path)?;
+		let tx = conn.transaction()?;
+		let mut changed = Vec::new();
+		{
+			let mut stmt = tx.prepare_cached(
+				"UPDATE episodes SET played = ?1, position_secs = ?2, changed_at = ?3
+					WHERE podcast_id = ?4 AND (url = ?5 OR (?6 != '' AND guid = ?6))
+					AND COALESCE(changed_at, 0) < ?3
+					AND (COALESCE(played, 0) != ?1 OR position_secs IS NOT ?2);"
+			)?;
+			for update in updates.iter()
+			{
+				let count = stmt.execute(params![
+					update.played,
+					update.position,
+					update.time.timestamp(),
+					update.pod_id,
+					update.url,
+					update.guid.as_deref().unwrap_or(""),
+				])?;
+				changed.extend(std::iter::repeat_n(update.pod_id, count));
+			}
+		}
+		tx.execute(
+			"INSERT OR IGNORE INTO gpodder_episode_sync (account) VALUES (?);",
+			params![account],
+		)?;
+		tx.execute(
+			"UPDATE gpodder_episode_sync SET since = ? WHERE account = ?;",
+			params![since, account],
+		)?;
+		tx.commit()?;
+		return Ok(changed);
+	}
+
+	/// Records that the changes made here up to `until` have been
+	/// uploaded to a gpodder.net account.
+	pub fn set_episodes_uploaded(&self, account: &str, until: DateTime<Utc>) -> Result<()>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		conn.execute(
+			"INSERT OR IGNORE INTO gpodder_episode_sync (account) VALUES (?);",
+			params![account],
+		)?;
+		conn.execute(
+			"UPDATE gpodder_episode_sync SET uploaded_until = ? WHERE account = ?;",
+			params![until.timestamp(), account],
+		)?;
+		return Ok(());
+	}
+
+	/// Returns the ID of the podcast that was removed most recently and
+	/// has not been purged yet, if any.
+	pub fn last_removed(&self) -> Result<Option<i64>>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		let pod_id = conn
+			.query_row(
+				"SELECT id FROM podcasts WHERE removed_at IS NOT NULL
+					ORDER BY removed_at DESC, id DESC LIMIT 1;",
+				params![],
+				|row| row.get(0),
+			)
+			.optional()?;
+		return Ok(pod_id);
+	}
+
+	/// Returns the podcasts that were removed at or before the given time,
+	/// along with whether their downloaded files should be deleted.
+	pub fn get_removed(&self, before: DateTime<Utc>) -> Result<Vec<(i64, bool)>>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		let mut stmt = conn.prepare_cached(
+			"SELECT id, remove_files FROM podcasts WHERE removed_at <= ?;"
+		)?;
+		let removed = stmt
+			.query_map(params![before.timestamp()], |row| {
+				Ok((row.get(0)?, row.get::<usize, Option<bool>>(1)?.unwrap_or(false)))
+			})?
+			.collect::<Result<_, _>>()?;
+		return Ok(removed);
+	}
+
+	/// Records the time at which the user last looked at the episodes of
+	/// a podcast. Episodes added after that are marked as unseen.
+	pub fn set_last_viewed(&self, pod_id: i64, time: DateTime<Utc>) -> Result<()>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		conn.execute(
+			"UPDATE podcasts SET last_viewed = ? WHERE id = ?;",
+			params![time.timestamp(), pod_id],
+		)?;
 		return Ok(());
 	}
+
+	/// Generates list of episodes for a given podcast, in the order
+	/// chosen for the podcast.
+	pub fn get_episodes(
+		&self,
+		pod_id: i64,
+		include_hidden: bool
+	) -> Result<Vec<Episode>>
+	{
+		let conn = self.conn.as_ref().expect("Error connecting to database.");
+		return read_episodes(conn, pod_id, include_hidden);
+	}
+}
+
+/// Reads the episodes of a podcast with `conn`, which may be a
+/// transaction; see `Database::get_episodes()`.
+fn read_episodes(conn: &Connection, pod_id: i64, include_hidden: bool) -> Result<Vec<Episode>>
+{
+	let mut stmt = if include_hidden
+	{
+		conn.prepare_cached(
+			"SELECT * FROM episodes
+					LEFT JOIN files ON episodes.id = files.episode_id
+					WHERE episodes.podcast_id = ?
+					ORDER BY pubdate DESC;",
+		)?
+	}
+	else
+	{
+		conn.prepare_cached(
+			"SELECT * FROM episodes
+					LEFT JOIN files ON episodes.id = files.episode_id
+					WHERE episodes.podcast_id = ?
+					AND episodes.hidden = 0
+					ORDER BY pubdate DESC;",
+		)?
+	};
+	let episode_iter = stmt.query_map(params![pod_id], |row| {
+		let path = match row.get::<&str, String>("path")
+		{
+			Ok(val) => Some(PathBuf::from(val)),
+			Err(_) => None,
+		};
+		Ok(Episode {
+			id: row.get("id")?,
+			pod_id: row.get("podcast_id")?,
+			title: row.get("title")?,
+			url: row.get("url")?,
+			guid: row
+				.get::<&str, Option<String>>("guid")?
+				.unwrap_or_else(|| "".to_string()),
+			link: row.get("link")?,
+			description: row.get("description")?,
+			pubdate: convert_date(row.get("pubdate")),
+			duration: row.get("duration")?,
+			season: row.get("season")?,
+			episode_num: row.get("episode_num")?,
+			enclosure_size: row
+				.get::<&str, Option<i64>>("enclosure_size")?
+				.map(|size| size as u64),
+			path: path,
+			downloaded_at: convert_date(row.get("downloaded_at")),
+			file_size: row
+				.get::<&str, Option<i64>>("file_size")?
+				.map(|size| size as u64),
+			played: row.get("played")?,
+			position: row.get("position_secs")?,
+			archived: row.get::<&str, Option<bool>>("archived")?
+				.unwrap_or(false),
+			added_at: convert_date(row.get("added_at")),
+			unseen: false,
+			downloading: false,
+		})
+	})?;
+	let mut episodes: Vec<Episode> = episode_iter.flatten().collect();
+
+	let (sort, last_viewed): (Option<String>, Option<DateTime<Utc>>) = conn
+		.query_row(
+			"SELECT episode_sort, last_viewed FROM podcasts WHERE id = ?;",
+			params![pod_id],
+			|row| Ok((row.get(0)?, convert_date(row.get(1)))),
+		)
+		.optional()?
+		.unwrap_or((None, None));
+	for ep in episodes.iter_mut()
+	{
+		ep.unseen = match (ep.added_at, last_viewed)
+		{
+			(Some(added), Some(viewed)) => added > viewed,
+			(Some(_), None) => true,
+			(None, _) => false,
+		};
+	}
+	EpisodeSort::from_name(sort.as_deref()).sort(&mut episodes);
+	return Ok(episodes);
+}
+
+/// Returns the path of the lock file of the database at `path`.
+fn lock_path(path: &Path) -> PathBuf
+{
+	return PathBuf::from(format!("{}.lock", path.to_string_lossy()));
+}
+
+/// Checks that the file at `path` is a shellcaster database that can be
+/// restored: it has all the tables of the original schema, and its schema
+/// version is not newer than the ones this version of shellcaster knows.
+fn check_backup(path: &Path) -> Result<()>
+{
+	if !path.is_file()
+	{
+		return Err(anyhow!("Could not find backup file: {}", path.to_string_lossy()));
+	}
+	let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+	let not_shellcaster = || anyhow!(
+		"{} is not a shellcaster database.", path.to_string_lossy()
+	);
+
+	let schema_version: usize = conn
+		.query_row("PRAGMA user_version;", params![], |row| row.get(0))
+		.map_err(|_| not_shellcaster())?;
+	if schema_version > MIGRATIONS.len()
+	{
+		return Err(anyhow!(
+			"{} was created by a newer version of shellcaster (schema version {}).",
+			path.to_string_lossy(),
+			schema_version
+		));
+	}
+
+	let mut stmt = conn
+		.prepare("SELECT name FROM sqlite_master WHERE type = 'table';")
+		.map_err(|_| not_shellcaster())?;
+	let tables: Vec<String> = stmt
+		.query_map(params![], |row| row.get(0))?
+		.flatten()
+		.collect();
+	for table in ["podcasts", "episodes", "files", "version"]
+	{
+		if !tables.iter().any(|name| name == table)
+		{
+			return Err(not_shellcaster());
+		}
+	}
+	return Ok(());
+}
+
+/// Opens a connection to the database. The database is put in WAL mode so
+/// that reading does not block writing, and the connection waits for a
+/// few seconds instead of failing when another process (e.g., a sync run
+/// from cron while the TUI is open) is writing to the database. Foreign
+/// keys, which SQLite leaves off unless each connection turns them on,
+/// are enforced, so that deleting a podcast deletes its episodes, files,
+/// tags and queue entries as well.
+fn open_connection(path: &Path) -> Result<Connection>
+{
+	let conn = Connection::open(path)?;
+	conn.busy_timeout(std::time::Duration::from_secs(5))?;
+	conn.pragma_update(None, "journal_mode", "WAL")?;
+	conn.pragma_update(None, "synchronous", "NORMAL")?;
+	conn.pragma_update(None, "foreign_keys", "ON")?;
+	return Ok(conn);
+}
+
+/// Ordered list of the migrations of the database schema. Migration `i`
+/// (counting from 1) brings the schema from version `i - 1` to version
+/// `i`. New migrations must be appended at the end; existing ones must
+/// never be changed, as databases out there have already applied them.
+const MIGRATIONS: &[fn(&Transaction) -> Result<()>] = &[
+	migrate_create_tables,
+	migrate_add_columns,
+	migrate_create_queue,
+	migrate_create_tags,
+	migrate_add_file_info,
+	migrate_add_archived,
+	migrate_add_error_time,
+	migrate_add_episode_sort,
+	migrate_add_links,
+	migrate_add_viewed,
+	migrate_add_removed,
+	migrate_add_enclosure_size,
+	migrate_add_favorite,
+	migrate_create_listens,
+	migrate_add_synced,
+	migrate_create_gpodder,
+	migrate_add_episode_sync,
+];
+
+/// Migration 1: creates the tables of the original schema. Databases
+/// created before schema versions were introduced already have them,
+/// although the oldest ones may miss the `guid` column.
+fn migrate_create_tables(tx: &Transaction) -> Result<()>
+{
+	tx.execute(
+		"CREATE TABLE IF NOT EXISTS podcasts (
+			id INTEGER PRIMARY KEY NOT NULL,
+			title TEXT NOT NULL,
+			url TEXT NOT NULL UNIQUE,
+			description TEXT,
+			author TEXT,
+			explicit INTEGER,
+			last_checked INTEGER
+		);",
+		params![],
+	)
+	.with_context(|| "Could not create podcasts database table")?;
+
+	tx.execute(
+		"CREATE TABLE IF NOT EXISTS episodes (
+			id INTEGER PRIMARY KEY NOT NULL,
+			podcast_id INTEGER NOT NULL,
+			title TEXT NOT NULL,
+			url TEXT NOT NULL,
+			guid TEXT,
+			description TEXT,
+			pubdate INTEGER,
+			duration INTEGER,
+			played INTEGER,
+			hidden INTEGER,
+			FOREIGN KEY(podcast_id) REFERENCES podcasts(id) ON DELETE CASCADE
+		);",
+		params![],
+	)
+	.with_context(|| "Could not create episodes database table")?;
+
+	tx.execute(
+		"CREATE TABLE IF NOT EXISTS files (
+			id INTEGER PRIMARY KEY NOT NULL,
+			episode_id INTEGER NOT NULL,
+			path TEXT NOT NULL UNIQUE,
+			FOREIGN KEY (episode_id) REFERENCES episodes(id) ON DELETE CASCADE
+		);",
+		params![],
+	)
+	.with_context(|| "Could not create files database table")?;
+
+	tx.execute(
+		"CREATE TABLE IF NOT EXISTS version (
+			id INTEGER PRIMARY KEY NOT NULL,
+			version TEXT NOT NULL
+		);",
+		params![],
+	)
+	.with_context(|| "Could not create version database table")?;
+
+	// episode guids were added in version 1.2.1
+	add_missing_column(tx, "episodes", "guid", "TEXT")?;
+	return Ok(());
+}
+
+/// Migration 2: adds the columns for season and episode numbers, sync
+/// failures, custom titles and episode limits, cover art, and playback
+/// positions. Databases created before schema versions were introduced
+/// may already have some of them.
+fn migrate_add_columns(tx: &Transaction) -> Result<()>
+{
+	add_missing_column(tx, "episodes", "season", "INTEGER")?;
+	add_missing_column(tx, "episodes", "episode_num", "INTEGER")?;
+	add_missing_column(tx, "episodes", "position_secs", "INTEGER")?;
+	add_missing_column(tx, "podcasts", "failures", "INTEGER DEFAULT 0")?;
+	add_missing_column(tx, "podcasts", "last_error", "TEXT")?;
+	add_missing_column(tx, "podcasts", "inactive", "INTEGER DEFAULT 0")?;
+	add_missing_column(tx, "podcasts", "custom_title", "TEXT")?;
+	add_missing_column(tx, "podcasts", "max_episodes", "INTEGER")?;
+	add_missing_column(tx, "podcasts", "image_url", "TEXT")?;
+	add_missing_column(tx, "podcasts", "cover_path", "TEXT")?;
+	return Ok(());
+}
+
+/// Migration 3: creates the table holding the playback queue.
+fn migrate_create_queue(tx: &Transaction) -> Result<()>
+{
+	tx.execute(
+		"CREATE TABLE IF NOT EXISTS queue (
+			episode_id INTEGER PRIMARY KEY NOT NULL,
+			position INTEGER NOT NULL,
+			FOREIGN KEY (episode_id) REFERENCES episodes(id) ON DELETE CASCADE
+		);",
+		params![],
+	)
+	.with_context(|| "Could not create queue database table")?;
+	return Ok(());
+}
+
+/// Migration 4: creates the table holding the tags of each podcast.
+fn migrate_create_tags(tx: &Transaction) -> Result<()>
+{
+	tx.execute(
+		"CREATE TABLE IF NOT EXISTS podcast_tags (
+			podcast_id INTEGER NOT NULL,
+			tag TEXT NOT NULL,
+			PRIMARY KEY (podcast_id, tag),
+			FOREIGN KEY (podcast_id) REFERENCES podcasts(id) ON DELETE CASCADE
+		);",
+		params![],
+	)
+	.with_context(|| "Could not create podcast_tags database table")?;
+	return Ok(());
+}
+
+/// Migration 5: adds the time at which each file was downloaded, and
+/// its size in bytes. Both are unknown for files downloaded before.
+fn migrate_add_file_info(tx: &Transaction) -> Result<()>
+{
+	add_missing_column(tx, "files", "downloaded_at", "INTEGER")?;
+	add_missing_column(tx, "files", "file_size", "INTEGER")?;
+	return Ok(());
+}
+
+/// Migration 6: adds the flag for archived episodes.
+fn migrate_add_archived(tx: &Transaction) -> Result<()>
+{
+	add_missing_column(tx, "episodes", "archived", "INTEGER DEFAULT 0")?;
+	return Ok(());
+}
+
+/// Migration 7: adds the time of the last failed sync of each podcast.
+fn migrate_add_error_time(tx: &Transaction) -> Result<()>
+{
+	add_missing_column(tx, "podcasts", "last_error_at", "INTEGER")?;
+	return Ok(());
+}
+
+/// Migration 8: adds the order in which the episodes of each podcast
+/// are listed. NULL means newest first.
+fn migrate_add_episode_sort(tx: &Transaction) -> Result<()>
+{
+	add_missing_column(tx, "podcasts", "episode_sort", "TEXT")?;
+	return Ok(());
+}
+
+/// Migration 9: adds the web pages that feeds link to for each podcast
+/// and episode. They are filled in at the next sync.
+fn migrate_add_links(tx: &Transaction) -> Result<()>
+{
+	add_missing_column(tx, "podcasts", "link", "TEXT")?;
+	add_missing_column(tx, "episodes", "link", "TEXT")?;
+	return Ok(());
+}
+
+/// Migration 10: adds the time at which each episode was added, and the
+/// time at which the episodes of each podcast were last looked at, so
+/// that episodes added since then can be marked. Both are unknown for
+/// existing episodes and podcasts.
+fn migrate_add_viewed(tx: &Transaction) -> Result<()>
+{
+	add_missing_column(tx, "episodes", "added_at", "INTEGER")?;
+	add_missing_column(tx, "podcasts", "last_viewed", "INTEGER")?;
+	return Ok(());
+}
+
+/// Migration 11: adds the time at which a podcast was removed, and
+/// whether its files should be deleted along with it, so that the
+/// removal can be undone until the podcast is purged.
+fn migrate_add_removed(tx: &Transaction) -> Result<()>
+{
+	add_missing_column(tx, "podcasts", "removed_at", "INTEGER")?;
+	add_missing_column(tx, "podcasts", "remove_files", "INTEGER DEFAULT 0")?;
+	return Ok(());
+}
+
+/// Migration 12: adds the size of each episode's file as given by the
+/// feed. It is filled in at the next sync.
+fn migrate_add_enclosure_size(tx: &Transaction) -> Result<()>
+{
+	add_missing_column(tx, "episodes", "enclosure_size", "INTEGER")?;
+	return Ok(());
+}
+
+/// Migration 13: adds the flag for favorite podcasts.
+fn migrate_add_favorite(tx: &Transaction) -> Result<()>
+{
+	add_missing_column(tx, "podcasts", "favorite", "INTEGER DEFAULT 0")?;
+	return Ok(());
+}
+
+/// Migration 14: creates the table holding the times episodes were
+/// listened to, i.e., played for long enough.
+fn migrate_create_listens(tx: &Transaction) -> Result<()>
+{
+	tx.execute(
+		"CREATE TABLE IF NOT EXISTS listens (
+			id INTEGER PRIMARY KEY NOT NULL,
+			episode_id INTEGER NOT NULL,
+			started_at INTEGER NOT NULL,
+			seconds INTEGER NOT NULL,
+			FOREIGN KEY (episode_id) REFERENCES episodes(id) ON DELETE CASCADE
+		);",
+		params![],
+	)
+	.with_context(|| "Could not create listens database table")?;
+	return Ok(());
+}
+
+/// Migration 15: adds the flag for podcasts that were imported without
+/// retrieving their feed, and have not been synced yet. Existing
+/// podcasts have all been synced.
+fn migrate_add_synced(tx: &Transaction) -> Result<()>
+{
+	add_missing_column(tx, "podcasts", "synced", "INTEGER DEFAULT 1")?;
+	return Ok(());
+}
+
+/// Migration 16: creates the table holding the subscriptions as they
+/// were after the last sync with each gpodder.net account.
+fn migrate_create_gpodder(tx: &Transaction) -> Result<()>
+{
+	tx.execute(
+		"CREATE TABLE IF NOT EXISTS gpodder_subscriptions (
+			account TEXT NOT NULL,
+			url TEXT NOT NULL,
+			PRIMARY KEY (account, url)
+		);",
+		params![],
+	)
+	.with_context(|| "Could not create gpodder_subscriptions database table")?;
+	return Ok(());
+}
+
+/// Migration 17: adds the time at which the played status or playback
+/// position of each episode was last changed, and creates the table
+/// holding where the last sync of episode actions with each gpodder.net
+/// account left off.
+fn migrate_add_episode_sync(tx: &Transaction) -> Result<()>
+{
+	add_missing_column(tx, "episodes", "changed_at", "INTEGER")?;
+	tx.execute(
+		"CREATE TABLE IF NOT EXISTS gpodder_episode_sync (
+			account TEXT PRIMARY KEY NOT NULL,
+			since INTEGER NOT NULL DEFAULT 0,
+			uploaded_until INTEGER
+		);",
+		params![],
+	)
+	.with_context(|| "Could not create gpodder_episode_sync database table")?;
+	return Ok(());
+}
+
+/// Hides the oldest episodes of a podcast so that at most
+/// `max_episodes` remain visible, unless the podcast has its own limit.
+/// Episodes that are played, downloaded, partly played or in the queue
+/// are never hidden, nor are the ones in `downloading`, so more
+/// episodes may remain if there are not enough others to hide. A limit
+/// of 0 keeps all episodes. Hidden episodes stay in the database, so
+/// that they are not added again at the next sync.
+fn prune_episodes(
+	conn: &Connection,
+	pod_id: i64,
+	max_episodes: usize,
+	downloading: &[i64],
+) -> Result<()>
+{
+	let pod_max: Option<i64> = conn.query_row(
+		"SELECT max_episodes FROM podcasts WHERE id = ?;",
+		params![pod_id],
+		|row| row.get(0),
+	)?;
+	let max_episodes = match pod_max
+	{
+		Some(num) => num.max(0),
+		None => max_episodes as i64,
+	};
+	if max_episodes == 0
+	{
+		return Ok(());
+	}
+
+	// the IDs are numbers, so they can be written into the statement
+	let downloading: Vec<String> = downloading.iter().map(|id| id.to_string()).collect();
+	let mut stmt = conn.prepare(&format!(
+		"UPDATE episodes SET hidden = 1 WHERE id IN (
+			SELECT episodes.id FROM episodes
+				LEFT JOIN files ON episodes.id = files.episode_id
+				LEFT JOIN queue ON episodes.id = queue.episode_id
+				WHERE episodes.podcast_id = ?1
+				AND episodes.hidden = 0
+				AND COALESCE(episodes.played, 0) = 0
+				AND episodes.position_secs IS NULL
+				AND files.id IS NULL
+				AND queue.episode_id IS NULL
+				AND episodes.id NOT IN ({})
+				ORDER BY episodes.pubdate ASC, episodes.id ASC
+				LIMIT MAX(0, (
+					SELECT COUNT(*) FROM episodes
+						WHERE podcast_id = ?1 AND hidden = 0
+				) - ?2)
+		);",
+		downloading.join(", ")
+	))?;
+	stmt.execute(params![pod_id, max_episodes])?;
+
+	// hidden episodes cannot be played from the queue anymore
+	let mut stmt = conn.prepare_cached(
+		"DELETE FROM queue WHERE episode_id IN (
+			SELECT id FROM episodes WHERE podcast_id = ? AND hidden = 1
+		);",
+	)?;
+	stmt.execute(params![pod_id])?;
+	return Ok(());
+}
+
+/// Helper function that adds a column to a table if the table does not
+/// have it yet, for databases created before the column was introduced.
+fn add_missing_column(
+	conn: &Connection,
+	table: &str,
+	column: &str,
+	definition: &str
+) -> Result<()>
+{
+	let mut stmt = conn.prepare(&format!("PRAGMA table_info({table});"))?;
+	let exists = stmt
+		.query_map(params![], |row| row.get::<&str, String>("name"))?
+		.flatten()
+		.any(|name| name == column);
+	if !exists
+	{
+		conn.execute(
+			&format!("ALTER TABLE {table} ADD COLUMN {column} {definition};"),
+			params![],
+		)
+		.with_context(|| "Could not run database migrations.")?;
+	}
+	return Ok(());
 }
 
 /// Helper function converting an (optional) Unix timestamp to a
@@ -626,3 +2166,832 @@ fn convert_date(result: Result<i64, rusqlite::Error>) -> Option<DateTime<Utc>>
 		Err(_) => None,
 	};
 }
+
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use chrono::TimeZone;
+
+	/// Returns a new, empty directory to hold a test database.
+	fn test_dir(name: &str) -> PathBuf
+	{
+		let dir = std::env::temp_dir().join(
+			format!("shellcaster-test-{}-{}", std::process::id(), name)
+		);
+		let _ = std::fs::remove_dir_all(&dir);
+		std::fs::create_dir_all(&dir).unwrap();
+		return dir;
+	}
+
+	/// Returns a podcast as read from the feed at `url`, with nothing
+	/// filled in besides its title and episodes.
+	fn test_podcast(title: &str, url: &str, episodes: Vec<EpisodeNoId>) -> PodcastNoId
+	{
+		return PodcastNoId {
+			title: title.to_string(),
+			url: url.to_string(),
+			link: None,
+			description: None,
+			author: None,
+			explicit: None,
+			image_url: None,
+			last_checked: Utc::now(),
+			episodes: episodes,
+		};
+	}
+
+	/// Returns the episode numbered `i`, published `i` hours after the
+	/// epoch, with a download URL and GUID of its own.
+	fn test_episode(i: i64) -> EpisodeNoId
+	{
+		return EpisodeNoId {
+			title: format!("Episode {i}"),
+			url: format!("https://example.com/{i}.mp3"),
+			guid: format!("guid-{i}"),
+			link: None,
+			description: String::new(),
+			pubdate: Some(Utc.timestamp(i * 3600, 0)),
+			duration: None,
+			season: None,
+			episode_num: None,
+			enclosure_size: None,
+		};
+	}
+
+	fn schema_version(db: &Database) -> usize
+	{
+		return db
+			.conn
+			.as_ref()
+			.unwrap()
+			.query_row("PRAGMA user_version;", params![], |row| row.get(0))
+			.unwrap();
+	}
+
+	fn has_column(db: &Database, table: &str, column: &str) -> bool
+	{
+		let conn = db.conn.as_ref().unwrap();
+		let mut stmt = conn.prepare(&format!("PRAGMA table_info({table});")).unwrap();
+		let names: Vec<String> = stmt
+			.query_map(params![], |row| row.get("name"))
+			.unwrap()
+			.flatten()
+			.collect();
+		return names.iter().any(|name| name == column);
+	}
+
+	#[test]
+	fn new_database()
+	{
+		let dir = test_dir("new");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		assert_eq!(schema_version(&db), MIGRATIONS.len());
+		assert!(has_column(&db, "episodes", "position_secs"));
+		assert!(db.get_queue().unwrap().is_empty());
+		let journal_mode: String = db
+			.conn
+			.as_ref()
+			.unwrap()
+			.query_row("PRAGMA journal_mode;", params![], |row| row.get(0))
+			.unwrap();
+		assert_eq!(journal_mode, "wal");
+
+		// connecting again has nothing left to apply
+		drop(db);
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		assert_eq!(schema_version(&db), MIGRATIONS.len());
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn upgrade_old_database()
+	{
+		// database in the format used before guids were added
+		let dir = test_dir("old");
+		{
+			let conn = Connection::open(dir.join("data.db")).unwrap();
+			conn.execute_batch(
+				"CREATE TABLE podcasts (
+					id INTEGER PRIMARY KEY NOT NULL,
+					title TEXT NOT NULL,
+					url TEXT NOT NULL UNIQUE,
+					description TEXT,
+					author TEXT,
+					explicit INTEGER,
+					last_checked INTEGER
+				);
+				CREATE TABLE episodes (
+					id INTEGER PRIMARY KEY NOT NULL,
+					podcast_id INTEGER NOT NULL,
+					title TEXT NOT NULL,
+					url TEXT NOT NULL,
+					description TEXT,
+					pubdate INTEGER,
+					duration INTEGER,
+					played INTEGER,
+					hidden INTEGER,
+					FOREIGN KEY(podcast_id) REFERENCES podcasts(id) ON DELETE CASCADE
+				);
+				CREATE TABLE files (
+					id INTEGER PRIMARY KEY NOT NULL,
+					episode_id INTEGER NOT NULL,
+					path TEXT NOT NULL UNIQUE,
+					FOREIGN KEY (episode_id) REFERENCES episodes(id) ON DELETE CASCADE
+				);
+				CREATE TABLE version (
+					id INTEGER PRIMARY KEY NOT NULL,
+					version TEXT NOT NULL
+				);
+				INSERT INTO version (id, version) VALUES (1, '1.2.0');
+				INSERT INTO podcasts (id, title, url, last_checked)
+					VALUES (1, 'The Podcast', 'https://example.com/feed', 0);
+				INSERT INTO episodes (id, podcast_id, title, url, description, played, hidden)
+					VALUES (1, 1, 'An Episode', 'https://example.com/1.mp3', '', 1, 0);"
+			).unwrap();
+		}
+
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		assert_eq!(schema_version(&db), MIGRATIONS.len());
+		assert!(has_column(&db, "episodes", "guid"));
+		assert!(has_column(&db, "episodes", "season"));
+		assert!(has_column(&db, "podcasts", "custom_title"));
+		assert!(has_column(&db, "podcasts", "link"));
+		assert!(has_column(&db, "podcasts", "last_viewed"));
+		assert!(has_column(&db, "podcasts", "removed_at"));
+		assert!(has_column(&db, "episodes", "enclosure_size"));
+
+		let podcasts = db.get_podcasts().unwrap();
+		assert_eq!(podcasts.len(), 1);
+		assert_eq!(podcasts[0].title, "The Podcast");
+		let episodes = db.get_episodes(1, false).unwrap();
+		assert_eq!(episodes.len(), 1);
+		assert!(episodes[0].played);
+		assert_eq!(episodes[0].position, None);
+
+		assert!(db.enqueue(1).unwrap());
+		assert_eq!(db.get_queue().unwrap(), vec![(1, 1)]);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn insert_many_episodes()
+	{
+		let dir = test_dir("many");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let make_episodes = |n: usize| -> Vec<EpisodeNoId> {
+			return (0..n)
+				.map(|i| EpisodeNoId {
+					duration: Some(1800),
+					episode_num: Some(i as i64),
+					..test_episode(i as i64)
+				})
+				.rev()
+				.collect();
+		};
+		let podcast = |episodes: Vec<EpisodeNoId>| {
+			return test_podcast("Many Episodes", "https://example.com/feed", episodes);
+		};
+
+		let result = db.insert_podcast(podcast(make_episodes(5000))).unwrap();
+		assert_eq!(result.added.len(), 5000);
+		let pod_id = result.added[0].pod_id;
+		assert_eq!(db.get_episodes(pod_id, false).unwrap().len(), 5000);
+
+		// syncing again only adds the new episodes
+		let result = db.update_podcast(pod_id, podcast(make_episodes(5100)), 0, &[]).unwrap();
+		assert_eq!(result.added.len(), 100);
+		assert!(result.updated.is_empty());
+		assert_eq!(db.get_episodes(pod_id, false).unwrap().len(), 5100);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn unseen_episodes()
+	{
+		let dir = test_dir("unseen");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let make_episodes = |n: usize| -> Vec<EpisodeNoId> {
+			return (0..n)
+				.map(|i| test_episode(i as i64))
+				.rev()
+				.collect();
+		};
+		let podcast = |episodes: Vec<EpisodeNoId>| {
+			return test_podcast("Unseen", "https://example.com/feed", episodes);
+		};
+		let unseen = |pod_id: i64| -> Vec<String> {
+			return db
+				.get_episodes(pod_id, false)
+				.unwrap()
+				.into_iter()
+				.filter(|ep| ep.unseen)
+				.map(|ep| ep.title)
+				.collect();
+		};
+
+		// the episodes of a new podcast are not marked
+		let result = db.insert_podcast(podcast(make_episodes(2))).unwrap();
+		let pod_id = result.added[0].pod_id;
+		assert!(unseen(pod_id).is_empty());
+
+		// episodes found by a later sync are, until the podcast is viewed;
+		// the first ones are made older, as if they were added earlier
+		db.conn.as_ref().unwrap().execute(
+			"UPDATE episodes SET added_at = added_at - 120;", params![]
+		).unwrap();
+		db.set_last_viewed(pod_id, Utc::now() - chrono::Duration::minutes(1)).unwrap();
+		db.update_podcast(pod_id, podcast(make_episodes(3)), 0, &[]).unwrap();
+		assert_eq!(unseen(pod_id), vec!["Episode 2".to_string()]);
+
+		db.set_last_viewed(pod_id, Utc::now()).unwrap();
+		assert!(unseen(pod_id).is_empty());
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn episode_limit_keeps_episodes_in_use()
+	{
+		let dir = test_dir("episode-limit");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let podcast = |n: i64| {
+			let episodes = (0..n).map(test_episode).rev().collect();
+			return test_podcast("Limited", "https://example.com/feed", episodes);
+		};
+		let visible = |pod_id: i64| -> Vec<String> {
+			return db
+				.get_episodes(pod_id, false)
+				.unwrap()
+				.into_iter()
+				.map(|ep| ep.title)
+				.collect();
+		};
+		let pod_id = db.insert_podcast(podcast(6)).unwrap().added[0].pod_id;
+		let episodes = db.get_episodes(pod_id, false).unwrap();
+		let id_of = |title: &str| episodes.iter().find(|ep| ep.title == title).unwrap().id;
+
+		// the oldest episodes are partly played, queued, being
+		// downloaded, and downloaded
+		db.set_position(id_of("Episode 0"), Some(60)).unwrap();
+		db.enqueue(id_of("Episode 1")).unwrap();
+		db.insert_file(id_of("Episode 3"), Path::new("Cargo.toml"), None).unwrap();
+		let downloading = [id_of("Episode 2")];
+
+		db.update_podcast(pod_id, podcast(7), 1, &downloading).unwrap();
+		assert_eq!(visible(pod_id), vec!["Episode 3", "Episode 2", "Episode 1", "Episode 0"]);
+		assert_eq!(db.get_queue().unwrap().len(), 1);
+
+		// once the download is over, the episode can be hidden
+		db.update_podcast(pod_id, podcast(7), 1, &[]).unwrap();
+		assert_eq!(visible(pod_id), vec!["Episode 3", "Episode 1", "Episode 0"]);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn newer_database_fails()
+	{
+		let dir = test_dir("newer");
+		{
+			let conn = Connection::open(dir.join("data.db")).unwrap();
+			conn.execute(
+				&format!("PRAGMA user_version = {};", MIGRATIONS.len() + 1),
+				params![]
+			).unwrap();
+		}
+
+		let err = Database::connect(&dir.join("data.db")).unwrap_err();
+		assert!(err.to_string().contains("upgrade shellcaster"));
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn backup_and_restore()
+	{
+		let dir = test_dir("backup");
+		let db_path = dir.join("data.db");
+		let backup_path = dir.join("backup.db");
+		let podcast = test_podcast("Backed Up", "https://example.com/feed", Vec::new());
+		{
+			let db = Database::connect(&db_path).unwrap();
+			db.insert_podcast(podcast).unwrap();
+			db.backup(&backup_path).unwrap();
+			db.remove_podcast(db.get_podcasts().unwrap()[0].id).unwrap();
+			assert!(db.get_podcasts().unwrap().is_empty());
+		}
+
+		Database::restore(&db_path, &backup_path).unwrap();
+		let db = Database::connect(&db_path).unwrap();
+		let podcasts = db.get_podcasts().unwrap();
+		assert_eq!(podcasts.len(), 1);
+		assert_eq!(podcasts[0].title, "Backed Up");
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn restore_checks_backup()
+	{
+		let dir = test_dir("restore-check");
+		let db_path = dir.join("data.db");
+		let other_path = dir.join("other.db");
+		{
+			let conn = Connection::open(&other_path).unwrap();
+			conn.execute("CREATE TABLE notes (id INTEGER PRIMARY KEY);", params![])
+				.unwrap();
+		}
+		assert!(Database::restore(&db_path, &other_path).is_err());
+		assert!(Database::restore(&db_path, &dir.join("missing.db")).is_err());
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn restore_refused_while_locked()
+	{
+		let dir = test_dir("restore-locked");
+		let db_path = dir.join("data.db");
+		let backup_path = dir.join("backup.db");
+		let db = Database::connect(&db_path).unwrap();
+		db.backup(&backup_path).unwrap();
+
+		let _lock = Database::lock(&db_path, true).unwrap().unwrap();
+		let err = Database::restore(&db_path, &backup_path).unwrap_err();
+		assert!(err.to_string().contains("while shellcaster is running"));
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn json_roundtrip()
+	{
+		let dir = test_dir("json");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let episodes = (0..3).map(test_episode).collect();
+		let podcast = test_podcast("Feed Title", "https://example.com/feed", episodes);
+		let pod_id = db.insert_podcast(podcast).unwrap().added[0].pod_id;
+		db.set_custom_title(pod_id, Some("Custom Title")).unwrap();
+		let episodes = db.get_episodes(pod_id, false).unwrap();
+		db.set_played_status(episodes[0].id, true, false).unwrap();
+		db.set_position(episodes[1].id, Some(90)).unwrap();
+		db.insert_file(episodes[2].id, Path::new("Cargo.toml"), None).unwrap();
+
+		let export = LibraryExport::new(&db.get_podcasts().unwrap());
+		let json = serde_json::to_string(&export).unwrap();
+		let library: LibraryExport = serde_json::from_str(&json).unwrap();
+
+		// importing into the same database changes nothing
+		assert_eq!(db.merge_podcast(&library.podcasts[0], false).unwrap(), 0);
+
+		// importing into an empty database restores the state
+		let other = Database::connect(&dir.join("other.db")).unwrap();
+		assert_eq!(other.merge_podcast(&library.podcasts[0], false).unwrap(), 4);
+		let podcasts = other.get_podcasts().unwrap();
+		assert_eq!(podcasts[0].title, "Custom Title");
+		assert_eq!(podcasts[0].feed_title, "Feed Title");
+		let imported = other.get_episodes(podcasts[0].id, false).unwrap();
+		assert_eq!(imported.len(), 3);
+		assert!(imported[0].played);
+		assert_eq!(imported[1].position, Some(90));
+		assert_eq!(imported[2].path, Some(PathBuf::from("Cargo.toml")));
+		assert_eq!(other.merge_podcast(&library.podcasts[0], false).unwrap(), 0);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn json_merge_keeps_local_state()
+	{
+		let dir = test_dir("json-merge");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let episodes = vec![test_episode(1)];
+		let podcast = test_podcast("Feed Title", "https://example.com/feed", episodes);
+		let pod_id = db.insert_podcast(podcast).unwrap().added[0].pod_id;
+		let ep_id = db.get_episodes(pod_id, false).unwrap()[0].id;
+		db.set_position(ep_id, Some(30)).unwrap();
+
+		let mut library = LibraryExport::new(&db.get_podcasts().unwrap());
+		{
+			let ep = &mut library.podcasts[0].episodes[0];
+			ep.position = Some(600);
+			ep.path = Some(PathBuf::from("does/not/exist.mp3"));
+		}
+		assert_eq!(db.merge_podcast(&library.podcasts[0], false).unwrap(), 0);
+		let ep = &db.get_episodes(pod_id, false).unwrap()[0];
+		assert_eq!(ep.position, Some(30));
+		assert_eq!(ep.path, None);
+
+		// an episode played elsewhere is marked as played here
+		library.podcasts[0].episodes[0].played = true;
+		assert_eq!(db.merge_podcast(&library.podcasts[0], false).unwrap(), 1);
+		let ep = &db.get_episodes(pod_id, false).unwrap()[0];
+		assert!(ep.played);
+		assert_eq!(ep.position, None);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn json_replace_overrides_local_state()
+	{
+		let dir = test_dir("json-replace");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let episodes = (0..2).map(test_episode).collect();
+		let podcast = test_podcast("Feed Title", "https://example.com/feed", episodes);
+		let pod_id = db.insert_podcast(podcast).unwrap().added[0].pod_id;
+		db.set_tags(pod_id, &["news".to_string()]).unwrap();
+		let episodes = db.get_episodes(pod_id, false).unwrap();
+		db.set_position(episodes[0].id, Some(30)).unwrap();
+		let mut library = LibraryExport::new(&db.get_podcasts().unwrap());
+
+		// the episodes are played further here after the export
+		db.set_position(episodes[0].id, Some(600)).unwrap();
+		db.set_played_status(episodes[1].id, true, false).unwrap();
+		db.insert_file(episodes[1].id, Path::new("does/not/exist.mp3"), None).unwrap();
+		db.set_tags(pod_id, &["comedy".to_string()]).unwrap();
+		assert_eq!(db.merge_podcast(&library.podcasts[0], false).unwrap(), 0);
+
+		library.podcasts[0].episodes[1].path = Some(PathBuf::from("Cargo.toml"));
+		assert_eq!(db.merge_podcast(&library.podcasts[0], true).unwrap(), 3);
+		let episodes = db.get_episodes(pod_id, false).unwrap();
+		assert_eq!(episodes[0].position, Some(30));
+		assert!(!episodes[1].played);
+		assert_eq!(episodes[1].path, Some(PathBuf::from("Cargo.toml")));
+		assert_eq!(db.get_podcasts().unwrap()[0].tags, vec!["news".to_string()]);
+
+		// replacing again with the same export changes nothing
+		assert_eq!(db.merge_podcast(&library.podcasts[0], true).unwrap(), 0);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn podcast_tags()
+	{
+		let dir = test_dir("tags");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let podcast = test_podcast("Tagged", "https://example.com/feed", Vec::new());
+		db.insert_podcast(podcast).unwrap();
+		let pod_id = db.get_podcasts().unwrap()[0].id;
+		assert!(db.get_tags(pod_id).unwrap().is_empty());
+
+		db.set_tags(pod_id, &parse_tags("tech, /News/, tech,")).unwrap();
+		assert_eq!(db.get_podcasts().unwrap()[0].tags, vec!["News", "tech"]);
+		db.set_tags(pod_id, &[]).unwrap();
+		assert!(db.get_tags(pod_id).unwrap().is_empty());
+
+		// tags are removed along with the podcast
+		db.set_tags(pod_id, &["fiction".to_string()]).unwrap();
+		db.remove_podcast(pod_id).unwrap();
+		assert!(db.get_tags(pod_id).unwrap().is_empty());
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn favorites_listed_first()
+	{
+		let dir = test_dir("favorites");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		for title in ["Alpha", "Beta", "Gamma"]
+		{
+			let url = format!("https://example.com/{title}");
+			db.insert_podcast(test_podcast(title, &url, Vec::new())).unwrap();
+		}
+		let gamma = db.get_podcasts().unwrap()[2].id;
+		db.set_favorite(gamma, true).unwrap();
+
+		let podcasts = LockVec::new(db.get_podcasts().unwrap());
+		podcasts.sort_podcasts(PodcastSort::Title);
+		let titles = podcasts.map(|pod| pod.title.clone(), false);
+		assert_eq!(titles, vec!["Gamma", "Alpha", "Beta"]);
+
+		db.set_favorite(gamma, false).unwrap();
+		assert!(!db.get_podcasts().unwrap()[2].favorite);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn file_info()
+	{
+		let dir = test_dir("file-info");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let episodes = (0..2).map(test_episode).collect();
+		let podcast = test_podcast("Downloads", "https://example.com/feed", episodes);
+		let pod_id = db.insert_podcast(podcast).unwrap().added[0].pod_id;
+		let episodes = db.get_episodes(pod_id, false).unwrap();
+		db.insert_file(episodes[0].id, &dir.join("0.mp3"), Some(61_000_000)).unwrap();
+		db.insert_file(episodes[1].id, Path::new("Cargo.toml"), None).unwrap();
+
+		let episodes = db.get_episodes(pod_id, false).unwrap();
+		assert_eq!(episodes[0].file_size, Some(61_000_000));
+		assert!(episodes[0].downloaded_at.is_some());
+		assert_eq!(episodes[1].file_size, None);
+
+		// only files of unknown size are left to the file system
+		let stats = db.get_stats().unwrap();
+		assert_eq!(stats[0].disk_usage, 61_000_000);
+		assert_eq!(stats[0].files, vec![PathBuf::from("Cargo.toml")]);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn listens_recorded()
+	{
+		let dir = test_dir("listens");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let episode = EpisodeNoId {
+			duration: Some(3600),
+			..test_episode(0)
+		};
+		let podcast = test_podcast("Listens", "https://example.com/feed", vec![episode]);
+		let pod_id = db.insert_podcast(podcast).unwrap().added[0].pod_id;
+		let ep_id = db.get_episodes(pod_id, false).unwrap()[0].id;
+		db.add_listen(ep_id, Utc.timestamp(1000, 0), 1800).unwrap();
+		db.add_listen(ep_id, Utc.timestamp(9000, 0), 600).unwrap();
+
+		let count_listens = |db: &Database| -> (i64, i64) {
+			return db
+				.conn
+				.as_ref()
+				.unwrap()
+				.query_row(
+					"SELECT COUNT(*), COALESCE(SUM(seconds), 0) FROM listens;",
+					params![],
+					|row| Ok((row.get(0)?, row.get(1)?)),
+				)
+				.unwrap();
+		};
+		assert_eq!(count_listens(&db), (2, 2400));
+
+		// listens go along with their podcast
+		db.remove_podcast(pod_id).unwrap();
+		assert_eq!(count_listens(&db), (0, 0));
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn shared_and_exclusive_locks()
+	{
+		let dir = test_dir("locks");
+		let db_path = dir.join("data.db");
+		{
+			let _first = Database::lock(&db_path, false).unwrap().unwrap();
+			assert!(Database::lock(&db_path, false).unwrap().is_some());
+			assert!(Database::lock(&db_path, true).unwrap().is_none());
+		}
+		let _exclusive = Database::lock(&db_path, true).unwrap().unwrap();
+		assert!(Database::lock(&db_path, false).unwrap().is_none());
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn archived_episodes_stay_archived()
+	{
+		let dir = test_dir("archive");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let make_episodes = |n: i64| -> Vec<EpisodeNoId> {
+			return (0..n)
+				.map(test_episode)
+				.rev()
+				.collect();
+		};
+		let podcast = |episodes: Vec<EpisodeNoId>| {
+			return test_podcast("Archive", "https://example.com/feed", episodes);
+		};
+		let pod_id = db.insert_podcast(podcast(make_episodes(5))).unwrap().added[0].pod_id;
+
+		// episodes are sorted newest first, so this is "Episode 2"
+		let episodes = db.get_episodes(pod_id, false).unwrap();
+		db.enqueue(episodes[4].id).unwrap();
+		assert_eq!(db.archive_older(pod_id, episodes[2].id).unwrap(), 2);
+		assert!(db.get_queue().unwrap().is_empty());
+		db.set_archived(episodes[0].id, true).unwrap();
+
+		// syncing neither adds them again nor unarchives them
+		let result = db.update_podcast(pod_id, podcast(make_episodes(6)), 0, &[]).unwrap();
+		assert_eq!(result.added.len(), 1);
+		let archived: Vec<String> = db
+			.get_episodes(pod_id, false)
+			.unwrap()
+			.into_iter()
+			.filter(|ep| ep.archived)
+			.map(|ep| ep.title)
+			.collect();
+		assert_eq!(archived, vec!["Episode 4", "Episode 1", "Episode 0"]);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn sync_errors_cleared_by_sync()
+	{
+		let dir = test_dir("sync_errors");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let podcast = || test_podcast("Failing", "https://example.com/feed", Vec::new());
+		db.insert_podcast(podcast()).unwrap();
+		let pod_id = db.get_podcasts().unwrap()[0].id;
+
+		let inactive = db.record_sync_failure(pod_id, "HTTP 404", false, 3).unwrap();
+		assert!(!inactive);
+		let pod = db.get_podcasts().unwrap().remove(0);
+		assert_eq!(pod.last_error.as_deref(), Some("HTTP 404"));
+		assert!(pod.last_error_at.is_some());
+		assert!(!pod.inactive);
+
+		db.update_podcast(pod_id, podcast(), 0, &[]).unwrap();
+		let pod = db.get_podcasts().unwrap().remove(0);
+		assert_eq!(pod.last_error, None);
+		assert_eq!(pod.last_error_at, None);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn placeholders_filled_in()
+	{
+		let dir = test_dir("placeholders");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let podcast = |url: &str| {
+			let episodes = (0..2)
+				.map(|i| EpisodeNoId {
+					url: format!("{url}/{i}.mp3"),
+					guid: format!("{url}-{i}"),
+					..test_episode(i)
+				})
+				.collect();
+			return test_podcast("Feed Title", url, episodes);
+		};
+
+		let pod_id = db.insert_placeholder("https://example.com/a", "From OPML").unwrap();
+		let pod = db.get_podcasts().unwrap().remove(0);
+		assert_eq!(pod.title, "From OPML");
+		assert!(!pod.synced && pod.episodes.is_empty());
+
+		// the first sync fills in the podcast, without any new episodes
+		let result = db.update_podcast(pod_id, podcast("https://example.com/a"), 0, &[]).unwrap();
+		assert!(result.added.is_empty());
+		let pod = db.get_podcasts().unwrap().remove(0);
+		assert_eq!(pod.title, "Feed Title");
+		assert!(pod.synced);
+		assert_eq!(pod.episodes.len(false), 2);
+		assert!(pod.episodes.map(|ep| ep.unseen, false).iter().all(|unseen| !unseen));
+
+		// adding the same feed again fills in the placeholder
+		db.insert_placeholder("https://example.com/b", "Other").unwrap();
+		let result = db.insert_podcast(podcast("https://example.com/b")).unwrap();
+		assert_eq!(result.added.len(), 2);
+		let podcasts = db.get_podcasts().unwrap();
+		assert_eq!(podcasts.len(), 2);
+		assert!(podcasts.iter().all(|pod| pod.synced));
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn episode_sort()
+	{
+		let dir = test_dir("episode_sort");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let titles = ["b second", "C third", "a first"];
+		let episodes = titles
+			.iter()
+			.enumerate()
+			.map(|(i, title)| EpisodeNoId {
+				title: title.to_string(),
+				..test_episode(i as i64)
+			})
+			.collect();
+		db.insert_podcast(test_podcast("Sorted", "https://example.com/feed", episodes)).unwrap();
+		let pod_id = db.get_podcasts().unwrap()[0].id;
+		let listed = |db: &Database| -> Vec<String> {
+			return db
+				.get_episodes(pod_id, false)
+				.unwrap()
+				.into_iter()
+				.map(|ep| ep.title)
+				.collect();
+		};
+		assert_eq!(db.get_podcasts().unwrap()[0].episode_sort, EpisodeSort::Newest);
+		assert_eq!(listed(&db), vec!["a first", "C third", "b second"]);
+
+		db.set_episode_sort(pod_id, EpisodeSort::Oldest).unwrap();
+		assert_eq!(listed(&db), vec!["b second", "C third", "a first"]);
+
+		// the order is kept when the database is opened again
+		db.set_episode_sort(pod_id, EpisodeSort::Title).unwrap();
+		drop(db);
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		assert_eq!(db.get_podcasts().unwrap()[0].episode_sort, EpisodeSort::Title);
+		assert_eq!(listed(&db), vec!["a first", "b second", "C third"]);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn removal_can_be_undone()
+	{
+		let dir = test_dir("removed");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let podcast = |title: &str, url: &str| test_podcast(title, url, Vec::new());
+		db.insert_podcast(podcast("First", "https://example.com/1")).unwrap();
+		db.insert_podcast(podcast("Second", "https://example.com/2")).unwrap();
+		let ids: Vec<i64> = db.get_podcasts().unwrap().iter().map(|pod| pod.id).collect();
+		assert_eq!(db.last_removed().unwrap(), None);
+
+		let earlier = Utc::now() - chrono::Duration::minutes(5);
+		db.set_removed(ids[0], Some(earlier), true).unwrap();
+		db.set_removed(ids[1], Some(Utc::now()), false).unwrap();
+		assert!(db.get_podcasts().unwrap().is_empty());
+		assert!(db.get_stats().unwrap().is_empty());
+		assert_eq!(db.last_removed().unwrap(), Some(ids[1]));
+		assert_eq!(
+			db.get_removed(Utc::now() - chrono::Duration::minutes(1)).unwrap(),
+			vec![(ids[0], true)]
+		);
+
+		// undoing restores the most recent removal first
+		db.set_removed(ids[1], None, false).unwrap();
+		assert_eq!(db.get_podcasts().unwrap()[0].title, "Second");
+		assert_eq!(db.last_removed().unwrap(), Some(ids[0]));
+
+		// adding a removed feed again replaces the removed podcast
+		db.insert_podcast(podcast("First again", "https://example.com/1")).unwrap();
+		assert_eq!(db.last_removed().unwrap(), None);
+		assert_eq!(db.get_podcasts().unwrap().len(), 2);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn removed_podcast_added_again()
+	{
+		let dir = test_dir("removed-again");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let podcast = || {
+			let episodes = (0..3).map(test_episode).collect();
+			return test_podcast("Again", "https://example.com/feed", episodes);
+		};
+		let pod_id = db.insert_podcast(podcast()).unwrap().added[0].pod_id;
+		let episodes = db.get_episodes(pod_id, false).unwrap();
+		db.insert_file(episodes[0].id, Path::new("Cargo.toml"), None).unwrap();
+		db.enqueue(episodes[1].id).unwrap();
+		db.set_removed(pod_id, Some(Utc::now()), false).unwrap();
+
+		// the removed podcast is deleted along with everything of its own
+		let pod_id = db.insert_podcast(podcast()).unwrap().added[0].pod_id;
+		let count = |table: &str| -> i64 {
+			return db
+				.conn
+				.as_ref()
+				.unwrap()
+				.query_row(&format!("SELECT COUNT(*) FROM {table};"), params![], |row| row.get(0))
+				.unwrap();
+		};
+		assert_eq!(count("podcasts"), 1);
+		assert_eq!(count("episodes"), 3);
+		assert_eq!(count("files"), 0);
+		assert_eq!(count("queue"), 0);
+		assert_eq!(db.get_episodes(pod_id, false).unwrap().len(), 3);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn episode_changes_synced()
+	{
+		let dir = test_dir("episode-sync");
+		let db = Database::connect(&dir.join("data.db")).unwrap();
+		let podcast = test_podcast("Feed Title", "https://example.com/feed", (0..3)
+			.map(|i| EpisodeNoId {
+				duration: Some(600),
+				..test_episode(i)
+			})
+			.collect());
+		let pod_id = db.insert_podcast(podcast).unwrap().added[0].pod_id;
+		let episodes = db.get_episodes(pod_id, false).unwrap();
+		let before = Utc::now() - chrono::Duration::seconds(5);
+		assert_eq!(db.get_episode_sync("account").unwrap(), (0, None));
+		assert!(db.get_episode_changes(None, Utc::now()).unwrap().is_empty());
+
+		db.set_position(episodes[0].id, Some(90)).unwrap();
+		db.insert_file(episodes[1].id, Path::new("Cargo.toml"), None).unwrap();
+		let until = Utc.timestamp(Utc::now().timestamp() + 1, 0);
+		let changes = db.get_episode_changes(None, until).unwrap();
+		assert_eq!(changes.len(), 2);
+		assert_eq!(changes[0].position, Some(90));
+		assert!(changes[0].changed_at.is_some() && changes[0].downloaded_at.is_none());
+		assert_eq!(changes[1].guid, episodes[1].guid);
+		assert!(changes[1].downloaded_at.is_some());
+		db.set_episodes_uploaded("account", until).unwrap();
+		assert!(db.get_episode_changes(Some(until), until).unwrap().is_empty());
+
+		// updates older than changes made here are not applied, and
+		// episodes can be found by their GUID
+		let update = |url: &str, guid: Option<&str>, played: bool, time| EpisodeUpdate {
+			pod_id: pod_id,
+			url: url.to_string(),
+			guid: guid.map(|guid| guid.to_string()),
+			played: played,
+			position: None,
+			time: time,
+		};
+		let updates = vec![
+			update(&episodes[0].url, None, true, before),
+			update("https://cdn.example.com/moved.mp3", Some(&episodes[2].guid), true, before),
+		];
+		assert_eq!(db.apply_episode_updates("account", &updates, 1234).unwrap(), vec![pod_id]);
+		let episodes = db.get_episodes(pod_id, false).unwrap();
+		assert_eq!((episodes[0].played, episodes[0].position), (false, Some(90)));
+		assert!(episodes[2].played);
+		assert_eq!(db.get_episode_sync("account").unwrap(), (1234, Some(until)));
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+}
@@ -1,4 +1,5 @@
 use std::fs::File;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
 use std::time::Duration;
@@ -6,7 +7,8 @@ use std::time::Duration;
 use chrono::{DateTime, Utc};
 use sanitize_filename::{sanitize_with_options, Options};
 
-use crate::threadpool::Threadpool;
+use crate::feeds::local_path;
+use crate::threadpool::{catch_panic, CancelFlag, Priority, Threadpool};
 use crate::types::Message;
 
 /// Enum used for communicating back to the main controller upon
@@ -19,6 +21,9 @@ pub enum DownloadMsg
 	ResponseError(EpData),
 	FileCreateError(EpData),
 	FileWriteError(EpData),
+	Cancelled(EpData),
+	Failed(EpData, String),
+	CoverComplete(i64, PathBuf),
 }
 
 /// Enum used to communicate relevant data to the threadpool.
@@ -35,14 +40,17 @@ pub struct EpData
 
 /// This is the function the main controller uses to indicate new
 /// files to download. It uses the threadpool to start jobs
-/// for every episode to be downloaded. New jobs can be requested
-/// by the user while there are still ongoing jobs.
+/// for every episode to be downloaded, with the given priority. New
+/// jobs can be requested by the user while there are still ongoing
+/// jobs.
+#[allow(clippy::too_many_arguments)]
 pub fn download_list(
 	episodes: Vec<EpData>,
 	dest: &Path,
 	max_retries: usize,
 	filename_prefix: &str,
 	filename_suffix: &str,
+	priority: Priority,
 	threadpool: &Threadpool,
 	tx_to_main: Sender<Message>,
 ) {
@@ -53,23 +61,60 @@ pub fn download_list(
 		let dest2 = dest.to_path_buf();
 		let prefix = filename_prefix.to_owned();
 		let suffix = filename_suffix.to_owned();
-		threadpool.execute(move || {
-			let result = download_file(ep, dest2, max_retries, prefix, suffix);
+		let cancel = threadpool.cancel_flag();
+		threadpool.execute(priority, move || {
+			let ep_data = ep.clone();
+			let result = catch_panic(|| {
+				return download_file(ep, dest2, max_retries, prefix, suffix, cancel);
+			})
+			.unwrap_or_else(|message| DownloadMsg::Failed(ep_data, message));
 			tx.send(Message::Dl(result))
 				.expect("Thread messaging error");
 		});
 	}
 }
 
-/// Downloads a file to a local filepath, returning DownloadMsg variant
-/// indicating success or failure.
-fn download_file(
-	mut ep_data: EpData,
-	dest: PathBuf,
-	mut max_retries: usize,
-	filename_prefix: String,
-	filename_suffix: String,
-) -> DownloadMsg
+/// Downloads the cover art of a podcast into the podcast's download
+/// directory `dest`, as cover.jpg. Only a successful download is reported
+/// back to the main controller; since cover art is not essential, errors
+/// are only logged, and the download waits for other jobs.
+pub fn download_cover(
+	pod_id: i64,
+	url: String,
+	dest: &Path,
+	max_retries: usize,
+	threadpool: &Threadpool,
+	tx_to_main: Sender<Message>,
+) {
+	let file_path = dest.join("cover.jpg");
+	threadpool.execute(Priority::Low, move || {
+		let result = match local_path(&url)
+		{
+			Some(source) => std::fs::copy(source, &file_path).map(|_| ()),
+			None => match get_response(&url, max_retries)
+			{
+				Some(response) => File::create(&file_path).and_then(|mut dst| {
+					return std::io::copy(&mut response.into_reader(), &mut dst).map(|_| ());
+				}),
+				None => return,
+			},
+		};
+		if let Err(err) = &result
+		{
+			log::warn!("Could not save cover art {url} to {}: {err}", file_path.display());
+		}
+		else
+		{
+			tx_to_main
+				.send(Message::Dl(DownloadMsg::CoverComplete(pod_id, file_path)))
+				.expect("Thread messaging error");
+		}
+	});
+}
+
+/// Sends a request for a file, retrying up to `max_retries` times.
+/// Returns None if there was no successful response.
+fn get_response(url: &str, mut max_retries: usize) -> Option<ureq::Response>
 {
 	let agent_builder = ureq::builder()
 		.timeout_connect(Duration::from_secs(10))
@@ -81,36 +126,145 @@ fn download_file(
 	let agent_builder = agent_builder.tls_connector(tls_connector);
 	let agent = agent_builder.build();
 
-	let request: Result<ureq::Response, ()> = loop
+	loop
 	{
-		let response = agent.get(&ep_data.url).call();
+		log::debug!("Requesting {url}");
+		let response = agent.get(url).call();
 		match response
 		{
-			Ok(resp) => break Ok(resp),
-			Err(_) => {
+			Ok(resp) => return Some(resp),
+			Err(err) => {
 				max_retries -= 1;
 				if max_retries == 0 {
-					break Err(());
+					log::error!("Could not download {url}: {err}");
+					return None;
 				}
+				log::warn!("Could not download {url}, retrying: {err}");
 			}
 		}
-	};
+	}
+}
 
-	if request.is_err()
+/// Downloads a file to a local filepath, returning DownloadMsg variant
+/// indicating success or failure. Episodes whose URL is a local file are
+/// copied into the download directory instead. If the jobs are
+/// cancelled (see `CancelFlag`), the download stops and the partial file
+/// is deleted.
+fn download_file(
+	mut ep_data: EpData,
+	dest: PathBuf,
+	max_retries: usize,
+	filename_prefix: String,
+	filename_suffix: String,
+	cancel: CancelFlag,
+) -> DownloadMsg
+{
+	if let Some(source) = local_path(&ep_data.url)
 	{
-		return DownloadMsg::ResponseError(ep_data);
-	};
+		if !source.is_file()
+		{
+			log::error!("Could not copy {}: file not found", source.display());
+			return DownloadMsg::ResponseError(ep_data);
+		}
+		let ext = get_file_ext(None, &ep_data.url).unwrap_or("mp3");
+		let file_path = episode_file_path(
+			&ep_data, dest, ext, &filename_prefix, &filename_suffix
+		);
+		let copied = std::fs::copy(&source, &file_path);
+		log_result(&ep_data.url, &file_path, &copied);
+		ep_data.file_path = Some(file_path);
+		return match copied
+		{
+			Ok(_) => DownloadMsg::Complete(ep_data),
+			Err(_) => DownloadMsg::FileWriteError(ep_data),
+		};
+	}
 
-	let response = request.unwrap();
+	let response = match get_response(&ep_data.url, max_retries)
+	{
+		Some(response) => response,
+		None => return DownloadMsg::ResponseError(ep_data),
+	};
 
 	// figure out the file type
-	let ext = match get_file_ext(response.header("content-type"), &ep_data.url)
+	// assume .mp3 unless we figure out otherwise
+	// TODO None case should print an error instead
+	let ext = get_file_ext(response.header("content-type"), &ep_data.url)
+		.unwrap_or("mp3");
+
+	let file_path = episode_file_path(
+		&ep_data, dest, ext, &filename_prefix, &filename_suffix
+	);
+	log::info!("Downloading {} to {}", ep_data.url, file_path.display());
+	let dst = File::create(&file_path);
+	if let Err(err) = &dst
+	{
+		log::error!("Could not create {}: {err}", file_path.display());
+		ep_data.file_path = Some(file_path);
+		return DownloadMsg::FileCreateError(ep_data);
+	};
+
+	let mut reader = Cancellable {
+		reader: response.into_reader(),
+		cancel: cancel.clone(),
+	};
+	let copied = std::io::copy(&mut reader, &mut dst.unwrap());
+	if cancel.is_cancelled()
 	{
-		Some(ext) => ext,
-		None => "mp3", // assume .mp3 unless we figure out otherwise
-		// TODO None case should print an error instead
+		log::info!("Cancelled download of {}", ep_data.url);
+		let _ = std::fs::remove_file(&file_path);
+		return DownloadMsg::Cancelled(ep_data);
+	}
+	log_result(&ep_data.url, &file_path, &copied);
+	ep_data.file_path = Some(file_path);
+	return match copied
+	{
+		Ok(_) => DownloadMsg::Complete(ep_data),
+		Err(_) => DownloadMsg::FileWriteError(ep_data),
 	};
+}
+
+/// Reader for the body of a download, which fails once the jobs are
+/// cancelled, so that the download stops at the next chunk of data.
+struct Cancellable<R>
+{
+	reader: R,
+	cancel: CancelFlag,
+}
+
+impl<R: Read> Read for Cancellable<R>
+{
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+	{
+		if self.cancel.is_cancelled()
+		{
+			return Err(io::Error::other("Download cancelled"));
+		}
+		return self.reader.read(buf);
+	}
+}
+
+/// Logs whether an episode was saved to `file_path`, and how many bytes
+/// were written.
+fn log_result(url: &str, file_path: &Path, result: &std::io::Result<u64>)
+{
+	match result
+	{
+		Ok(bytes) => log::info!("Saved {url} to {} ({bytes} bytes)", file_path.display()),
+		Err(err) => log::error!("Could not write {}: {err}", file_path.display()),
+	}
+}
 
+/// Builds the path of the file an episode will be saved to, inside the
+/// `dest` directory, from the episode's title and publication date.
+fn episode_file_path(
+	ep_data: &EpData,
+	dest: PathBuf,
+	ext: &str,
+	filename_prefix: &str,
+	filename_suffix: &str,
+) -> PathBuf
+{
 	let mut file_name = sanitize_with_options(&ep_data.title, Options {
 		truncate: true,
 		// for simplicity, we'll just use Windows-friendly paths for everyone
@@ -128,28 +282,15 @@ fn download_file(
 		// recommended to use DelayedFormat::fmt instead
 		file_name = format!(
 			"{}{}{}",
-			pubdate.format(&filename_prefix),
+			pubdate.format(filename_prefix),
 			file_name,
-			pubdate.format(&filename_suffix)
+			pubdate.format(filename_suffix)
 		);
 	}
 
 	let mut file_path = dest;
 	file_path.push(format!("{file_name}.{ext}"));
-
-	let dst = File::create(&file_path);
-	ep_data.file_path = Some(file_path);
-	if dst.is_err()
-	{
-		return DownloadMsg::FileCreateError(ep_data);
-	};
-
-	let mut reader = response.into_reader();
-	return match std::io::copy(&mut reader, &mut dst.unwrap())
-	{
-		Ok(_) => DownloadMsg::Complete(ep_data),
-		Err(_) => DownloadMsg::FileWriteError(ep_data),
-	};
+	return file_path;
 }
 
 /// Returns what the extension of a downloaded file should be, based first on
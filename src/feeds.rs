@@ -1,15 +1,21 @@
 use anyhow::{anyhow, Result};
+use std::collections::HashMap;
 use std::io::Read;
-use std::sync::mpsc;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use chrono::{DateTime, Utc};
+use encoding_rs::{Encoding, UTF_8};
 use lazy_static::lazy_static;
 use regex::{Match, Regex};
 use rfc822_sanitizer::parse_from_rfc2822_with_fallback;
 use rss::{Channel, Item};
+use url::Url;
 
-use crate::threadpool::Threadpool;
+use crate::config::{MAX_FEED_PAGES, MAX_RATE_LIMITED_RETRIES, MAX_RETRY_AFTER};
+use crate::threadpool::{catch_panic, Priority, Threadpool};
 use crate::types::*;
 
 lazy_static!
@@ -17,8 +23,63 @@ lazy_static!
 	/// Regex for parsing an episode "duration", which could take the form
 	/// of HH:MM:SS, MM:SS, or SS.
 	static ref RE_DURATION: Regex = Regex::new(
-		r"(\d+)(?::(\d+))?(?::(\d+))?"
+		r"^\s*(\d+)(?::(\d+))?(?::(\d+))?\s*$"
 	).expect("Regex error");
+
+	/// Regex for finding <link> tags in an HTML document
+	static ref RE_LINK_TAGS: Regex = Regex::new(
+		r"(?i)<link\b[^>]*>"
+	).expect("Regex error");
+
+	/// Regex for parsing the attributes of an HTML tag, where the value
+	/// may be double-quoted, single-quoted, or unquoted
+	static ref RE_HTML_ATTRS: Regex = Regex::new(
+		r#"(?i)\b([a-z-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#
+	).expect("Regex error");
+
+	/// Regex for finding the encoding given in the XML declaration at the
+	/// start of a document
+	static ref RE_XML_ENCODING: Regex = Regex::new(
+		r#"(?i)^(\s*<\?xml[^>]*?\bencoding\s*=\s*["'])([^"']*)(["'])"#
+	).expect("Regex error");
+
+	/// Regex for finding an `xml:base` attribute on the <rss>, <channel>
+	/// or <feed> tags of a feed
+	static ref RE_XML_BASE: Regex = Regex::new(
+		r#"(?i)<(?:rss|channel|feed)\b[^>]*\bxml:base\s*=\s*(?:"([^"]*)"|'([^']*)')"#
+	).expect("Regex error");
+
+	/// Regex for finding the <item> tags of a feed, along with their
+	/// `xml:base` attribute, if any
+	static ref RE_ITEM_BASE: Regex = Regex::new(
+		r#"(?i)<item\b(?:[^>]*\bxml:base\s*=\s*(?:"([^"]*)"|'([^']*)'))?[^>]*>"#
+	).expect("Regex error");
+}
+
+/// The URLs that relative links in a feed are resolved against: that of
+/// the feed as a whole, and that of each of its items, which may set a
+/// different one with their own `xml:base` attribute.
+#[derive(Debug, Default)]
+struct FeedBase
+{
+	channel: Option<Url>,
+	items: Vec<Option<Url>>,
+}
+
+impl FeedBase
+{
+	/// Returns the base URL of the item at `index` among the `count`
+	/// items of the feed. If as many <item> tags were not found (e.g.,
+	/// because a description quotes one), the items cannot be told apart,
+	/// so the base of the feed is used for all of them.
+	fn item(&self, index: usize, count: usize) -> Option<&Url>
+	{
+		if self.items.len() != count
+		{
+			return self.channel.as_ref();
+		}
+		return self.items[index].as_ref();
+	}
 }
 
 /// Enum for communicating back to the main thread after feed data has
@@ -28,17 +89,54 @@ pub enum FeedMsg
 {
 	NewData(PodcastNoId),
 	SyncData((i64, PodcastNoId)),
-	Error(PodcastFeed),
+	Candidates(PodcastFeed, Vec<String>),
+	Error(PodcastFeed, FeedError),
+}
+
+/// Describes why a feed could not be retrieved. `gone` is set when the
+/// server reported that the feed no longer exists (HTTP 404 or 410).
+#[derive(Debug, Clone)]
+pub struct FeedError
+{
+	pub message: String,
+	pub gone: bool,
+}
+
+/// Error used when the server responds to a request with an HTTP
+/// error status code.
+#[derive(Debug)]
+struct StatusError(u16);
+
+impl std::fmt::Display for StatusError
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		return write!(f, "Server responded with HTTP status {}", self.0);
+	}
+}
+
+impl std::error::Error for StatusError {}
+
+/// Data retrieved from a URL: either a parsed podcast feed, or, if the
+/// URL pointed to a web page advertising more than one feed, the list
+/// of candidate feed URLs.
+#[derive(Debug)]
+enum FeedData
+{
+	Podcast(PodcastNoId),
+	Candidates(Vec<String>),
 }
 
 /// Struct holding data about a podcast feed (subset of info about a
 /// podcast) for the purpose of passing back and forth between threads.
+/// `tags` holds the tags of a feed imported from an OPML file.
 #[derive(Debug, Clone)]
 pub struct PodcastFeed
 {
 	pub id: Option<i64>,
 	pub url: String,
 	pub title: Option<String>,
+	pub tags: Vec<String>,
 }
 
 impl PodcastFeed
@@ -49,40 +147,312 @@ impl PodcastFeed
 			id: id,
 			url: url,
 			title: title,
+			tags: Vec::new(),
 		};
 	}
 }
 
-/// Spawns a new thread to check a feed and retrieve podcast data.
+/// Spaces out requests sent to the same host, so that syncing many
+/// podcasts from the same network does not get us rate-limited. For each
+/// host, this keeps track of the earliest time at which the next request
+/// may be sent. Clones share the same state, so a single HostThrottle
+/// can be handed to every job in the threadpool.
+#[derive(Debug, Clone)]
+pub struct HostThrottle
+{
+	delay: Duration,
+	next_request: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl HostThrottle
+{
+	/// Creates a new HostThrottle, where requests to the same host are
+	/// spaced by at least `delay_ms` milliseconds.
+	pub fn new(delay_ms: u64) -> Self
+	{
+		return Self {
+			delay: Duration::from_millis(delay_ms),
+			next_request: Arc::new(Mutex::new(HashMap::new())),
+		};
+	}
+
+	/// Blocks until a request may be sent to the host of `url`, and
+	/// reserves that slot for the current request.
+	pub fn wait(&self, url: &str)
+	{
+		if self.delay.is_zero()
+		{
+			return;
+		}
+		let host = match Url::parse(url)
+		{
+			Ok(parsed) => match parsed.host_str()
+			{
+				Some(host) => host.to_lowercase(),
+				None => return,
+			},
+			Err(_) => return,
+		};
+
+		let now = Instant::now();
+		let slot = {
+			let mut next_request = self.next_request
+				.lock()
+				.expect("Mutex error");
+			let slot = match next_request.get(&host)
+			{
+				Some(next) if *next > now => *next,
+				_ => now,
+			};
+			next_request.insert(host, slot + self.delay);
+			slot
+		};
+		if slot > now
+		{
+			thread::sleep(slot - now);
+		}
+	}
+}
+
+/// Normalizes a feed URL for the purpose of finding podcasts that were
+/// added more than once under slightly different URLs. The scheme, a
+/// trailing slash, the fragment, and common tracking parameters in the
+/// query string (e.g., utm_source) are ignored, and the host is
+/// lowercased. Local paths and URLs that cannot be parsed are only
+/// trimmed.
+pub fn normalize_url(url: &str) -> String
+{
+	let url = url.trim();
+	if let Some(path) = local_path(url)
+	{
+		return path.to_string_lossy().to_string();
+	}
+	let parsed = match Url::parse(url)
+	{
+		Ok(parsed) => parsed,
+		Err(_) => return url.to_string(),
+	};
+	let host = match parsed.host_str()
+	{
+		Some(host) => host.to_lowercase(),
+		None => return url.to_string(),
+	};
+
+	let mut normalized = host;
+	if let Some(port) = parsed.port()
+	{
+		normalized.push_str(&format!(":{port}"));
+	}
+	normalized.push_str(parsed.path().trim_end_matches('/'));
+
+	let query: Vec<String> = parsed
+		.query_pairs()
+		.filter(|(name, _)| {
+			let name = name.to_lowercase();
+			!name.starts_with("utm_") &&
+				!matches!(name.as_str(), "fbclid" | "gclid" | "mc_cid" | "mc_eid")
+		})
+		.map(|(name, value)| format!("{name}={value}"))
+		.collect();
+	if !query.is_empty()
+	{
+		normalized.push('?');
+		normalized.push_str(&query.join("&"));
+	}
+	return normalized;
+}
+
+/// Reorders a list of feeds so that feeds from the same host are spread
+/// out, taking one feed from each host in turn. Since requests to the
+/// same host are spaced out by the HostThrottle, this keeps threads in
+/// the threadpool busy with other hosts instead of waiting in line.
+pub fn interleave_by_host(feeds: Vec<PodcastFeed>) -> Vec<PodcastFeed>
+{
+	let mut hosts: Vec<(String, Vec<PodcastFeed>)> = Vec::new();
+	for feed in feeds.into_iter()
+	{
+		let host = Url::parse(&feed.url)
+			.ok()
+			.and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+			.unwrap_or_default();
+		match hosts.iter_mut().find(|(h, _)| *h == host)
+		{
+			Some((_, list)) => list.push(feed),
+			None => hosts.push((host, vec![feed])),
+		}
+	}
+
+	let mut interleaved = Vec::new();
+	let mut index = 0;
+	loop
+	{
+		let mut added = false;
+		for (_, list) in hosts.iter()
+		{
+			if let Some(feed) = list.get(index)
+			{
+				interleaved.push(feed.clone());
+				added = true;
+			}
+		}
+		if !added
+		{
+			break;
+		}
+		index += 1;
+	}
+	return interleaved;
+}
+
+/// Spawns a new thread to check a feed and retrieve podcast data, with
+/// the given priority. If `full_archive` is set, older pages of a paged
+/// feed are retrieved as well; otherwise only the first page is checked.
 pub fn check_feed(
 	feed: PodcastFeed,
 	max_retries: usize,
+	full_archive: bool,
+	throttle: HostThrottle,
+	priority: Priority,
 	threadpool: &Threadpool,
 	tx_to_main: mpsc::Sender<Message>,
 ) {
-	threadpool.execute(move || match get_feed_data(feed.url.clone(), max_retries)
-	{
-		Ok(pod) => match feed.id
+	threadpool.execute(priority, move || {
+		let result = catch_panic(|| {
+			return get_feed_data(feed.url.clone(), max_retries, full_archive, &throttle);
+		});
+		let msg = match result.unwrap_or_else(|message| Err(anyhow!("Sync failed: {message}")))
 		{
-			Some(id) => {
-				tx_to_main
-					.send(Message::Feed(FeedMsg::SyncData((id, pod))))
-					.expect("Thread messaging error");
+			Ok(FeedData::Podcast(pod)) => {
+				log::info!("Retrieved feed {} ({} episodes)", feed.url, pod.episodes.len());
+				match feed.id
+				{
+					Some(id) => FeedMsg::SyncData((id, pod)),
+					None => FeedMsg::NewData(pod),
+				}
 			}
-			None => tx_to_main
-				.send(Message::Feed(FeedMsg::NewData(pod)))
-				.expect("Thread messaging error"),
-		},
-		Err(_err) => tx_to_main
-			.send(Message::Feed(FeedMsg::Error(feed)))
-			.expect("Thread messaging error"),
+			// an existing podcast should always point directly to a
+			// feed, so there is nothing to choose from when syncing
+			Ok(FeedData::Candidates(urls)) => match feed.id
+			{
+				Some(_) => {
+					log::error!("Could not sync {}: URL points to a web page, not a feed", feed.url);
+					FeedMsg::Error(feed, FeedError {
+						message: "URL points to a web page, not a feed".to_string(),
+						gone: false,
+					})
+				}
+				None => FeedMsg::Candidates(feed, urls),
+			},
+			Err(err) => {
+				match &feed.title
+				{
+					Some(title) => log::error!("Could not retrieve feed for {title} ({}): {err}", feed.url),
+					None => log::error!("Could not retrieve feed {}: {err}", feed.url),
+				}
+				let gone = matches!(
+					err.downcast_ref::<StatusError>(),
+					Some(StatusError(404)) | Some(StatusError(410))
+				);
+				FeedMsg::Error(feed, FeedError {
+					message: err.to_string(),
+					gone: gone,
+				})
+			}
+		};
+		tx_to_main
+			.send(Message::Feed(msg))
+			.expect("Thread messaging error");
 	});
 }
 
 /// Given a URL, this attempts to pull the data about a podcast and its
-/// episodes from an RSS feed.
-fn get_feed_data(url: String, mut max_retries: usize) -> Result<PodcastNoId>
+/// episodes from an RSS feed. If the URL instead points to an HTML web
+/// page, the page is searched for links to feeds: a single feed is
+/// retrieved directly, while multiple feeds are returned as candidates.
+fn get_feed_data(
+	url: String,
+	max_retries: usize,
+	full_archive: bool,
+	throttle: &HostThrottle
+) -> Result<FeedData>
+{
+	let (resp_data, is_html) = fetch_url(&url, max_retries, throttle)?;
+	return match Channel::read_from(&resp_data[..])
+	{
+		Ok(channel) => {
+			let base = feed_base_url(&resp_data, &url);
+			Ok(FeedData::Podcast(parse_feed_pages(
+				channel, &url, &base, max_retries, full_archive, throttle
+			)))
+		}
+		Err(err) => {
+			if !is_html
+			{
+				return Err(err.into());
+			}
+			let mut candidates =
+				find_feed_links(&String::from_utf8_lossy(&resp_data), &url);
+			match candidates.len()
+			{
+				0 => Err(anyhow!("No feeds found on web page")),
+				1 => {
+					let feed_url = candidates.remove(0);
+					let (feed_data, _) = fetch_url(&feed_url, max_retries, throttle)?;
+					let channel = Channel::read_from(&feed_data[..])?;
+					let base = feed_base_url(&feed_data, &feed_url);
+					Ok(FeedData::Podcast(parse_feed_pages(
+						channel, &feed_url, &base, max_retries,
+						full_archive, throttle
+					)))
+				}
+				_ => Ok(FeedData::Candidates(candidates)),
+			}
+		}
+	};
+}
+
+/// Returns the path of a local file, if `url` is a file:// URL or an
+/// absolute filesystem path rather than a web address.
+pub fn local_path(url: &str) -> Option<PathBuf>
 {
+	if url.get(..7).is_some_and(|scheme| scheme.eq_ignore_ascii_case("file://"))
+	{
+		return Url::parse(url).ok()?.to_file_path().ok();
+	}
+	let path = Path::new(url);
+	if path.is_absolute()
+	{
+		return Some(path.to_path_buf());
+	}
+	return None;
+}
+
+/// Retrieves the contents at a URL, retrying up to `max_retries` times.
+/// Requests wait for their turn with `throttle`, and if the server asks
+/// us to slow down (HTTP 429), the next attempt waits for the time given
+/// in its Retry-After header; such retries are counted separately, up to
+/// MAX_RATE_LIMITED_RETRIES. Local files are read directly from disk.
+/// The contents are converted to UTF-8. Also returns whether the response
+/// appears to be an HTML document, based on its content type or, failing
+/// that, the start of the body.
+fn fetch_url(
+	url: &str,
+	mut max_retries: usize,
+	throttle: &HostThrottle
+) -> Result<(Vec<u8>, bool)>
+{
+	if let Some(path) = local_path(url)
+	{
+		let data = std::fs::read(&path).map_err(|err| {
+			anyhow!("Could not read feed file {}: {}", path.display(), err)
+		})?;
+		let data = to_utf8(data, None);
+		let is_html = path.extension().is_some_and(|ext| {
+			ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm")
+		}) || starts_like_html(&data);
+		return Ok((data, is_html));
+	}
+
 	let agent_builder = ureq::builder()
 		.timeout_connect(Duration::from_secs(10))
 		.timeout_read(Duration::from_secs(20));
@@ -93,18 +463,39 @@ fn get_feed_data(url: String, mut max_retries: usize) -> Result<PodcastNoId>
 	let agent_builder = agent_builder.tls_connector(tls_connector);
 	let agent = agent_builder.build();
 
+	let mut rate_limited: usize = 0;
 	let request: Result<ureq::Response> = loop
 	{
-		let response = agent.get(&url).call();
+		throttle.wait(url);
+		log::debug!("Requesting {url}");
+		let response = agent.get(url).call();
 		match response
 		{
 			Ok(resp) => break Ok(resp),
-			Err(_) => {
+			Err(ureq::Error::Status(429, resp)) => {
+				rate_limited += 1;
+				if rate_limited > MAX_RATE_LIMITED_RETRIES
+				{
+					break Err(StatusError(429).into());
+				}
+				let wait = retry_after(resp.header("Retry-After"));
+				log::warn!(
+					"{url} responded with HTTP status 429, retrying in {} seconds",
+					wait.as_secs()
+				);
+				thread::sleep(wait);
+			}
+			// no point in retrying if the feed is gone
+			Err(ureq::Error::Status(code @ (404 | 410), _)) => {
+				break Err(StatusError(code).into());
+			}
+			Err(err) => {
 				max_retries -= 1;
 				if max_retries == 0
 				{
-					break Err(anyhow!("No response from feed"));
+					break Err(anyhow!("No response from feed: {}", err));
 				}
+				log::warn!("No response from {url}, retrying: {err}");
 			}
 		}
 	};
@@ -112,35 +503,349 @@ fn get_feed_data(url: String, mut max_retries: usize) -> Result<PodcastNoId>
 	return match request
 	{
 		Ok(resp) => {
+			let content_type = resp.content_type().to_lowercase();
+			let content_type_header = resp.header("content-type").map(|h| h.to_string());
 			let mut reader = resp.into_reader();
 			let mut resp_data = Vec::new();
 			reader.read_to_end(&mut resp_data)?;
+			log::debug!(
+				"{url} responded with {} bytes of {}",
+				resp_data.len(),
+				content_type_header.as_deref().unwrap_or("unknown content")
+			);
+			let resp_data = to_utf8(resp_data, content_type_header.as_deref());
 
-			let channel = Channel::read_from(&resp_data[..])?;
-			Ok(parse_feed_data(channel, &url))
+			let is_html = content_type.contains("html") || starts_like_html(&resp_data);
+			Ok((resp_data, is_html))
 		}
 		Err(err) => Err(err),
 	};
 }
 
+/// Converts a document to UTF-8. The encoding is taken from the byte
+/// order mark if there is one, then from the charset of the Content-Type
+/// header, then from the XML declaration, and defaults to UTF-8.
+/// Sequences that are invalid in that encoding are replaced rather than
+/// rejected. The XML declaration is updated to match the new encoding.
+fn to_utf8(data: Vec<u8>, content_type: Option<&str>) -> Vec<u8>
+{
+	let (encoding, bom_length) = match Encoding::for_bom(&data)
+	{
+		Some((encoding, length)) => (encoding, length),
+		None => {
+			let start = String::from_utf8_lossy(&data[..data.len().min(1024)])
+				.into_owned();
+			let label = content_type
+				.and_then(charset_from_content_type)
+				.or_else(|| {
+					RE_XML_ENCODING
+						.captures(&start)
+						.map(|cap| cap[2].trim().to_string())
+				});
+			let encoding = label
+				.and_then(|label| Encoding::for_label(label.as_bytes()))
+				.unwrap_or(UTF_8);
+			(encoding, 0)
+		}
+	};
+
+	if encoding == UTF_8 && bom_length == 0 && std::str::from_utf8(&data).is_ok()
+	{
+		return data;
+	}
+	let (text, _) = encoding.decode_without_bom_handling(&data[bom_length..]);
+	return RE_XML_ENCODING
+		.replace(&text, "${1}UTF-8${3}")
+		.into_owned()
+		.into_bytes();
+}
+
+/// Returns the charset parameter of a Content-Type header, if any.
+fn charset_from_content_type(content_type: &str) -> Option<String>
+{
+	return content_type
+		.split(';')
+		.skip(1)
+		.filter_map(|param| param.split_once('='))
+		.find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+		.map(|(_, value)| value.trim().trim_matches('"').to_string())
+		.filter(|value| !value.is_empty());
+}
+
+/// Checks whether the start of a document looks like HTML.
+fn starts_like_html(data: &[u8]) -> bool
+{
+	let start = String::from_utf8_lossy(&data[..data.len().min(512)])
+		.trim_start()
+		.to_lowercase();
+	return start.starts_with("<!doctype html") || start.starts_with("<html");
+}
+
+/// Converts the value of a Retry-After header, which is either a number
+/// of seconds or an HTTP date, into the time to wait before retrying.
+/// Defaults to a few seconds if the header is missing or invalid, and is
+/// capped at MAX_RETRY_AFTER seconds.
+fn retry_after(header: Option<&str>) -> Duration
+{
+	let secs = match header.map(|h| h.trim())
+	{
+		Some(value) => match value.parse::<u64>()
+		{
+			Ok(secs) => secs,
+			Err(_) => match DateTime::parse_from_rfc2822(value)
+			{
+				Ok(date) => (date.with_timezone(&Utc) - Utc::now())
+					.num_seconds()
+					.max(0) as u64,
+				Err(_) => 5,
+			},
+		},
+		None => 5,
+	};
+	return Duration::from_secs(secs.min(MAX_RETRY_AFTER));
+}
+
+/// Parses the first page of a feed into a podcast. If `full_archive` is
+/// set, this also follows the RFC 5005 `<atom:link rel="next">` links to
+/// older pages of the feed, up to MAX_FEED_PAGES pages in total, and
+/// adds their episodes to the podcast. If an older page cannot be
+/// retrieved, the episodes found so far are kept. Relative URLs in the
+/// first page are resolved against `base`.
+fn parse_feed_pages(
+	channel: Channel,
+	url: &str,
+	base: &FeedBase,
+	max_retries: usize,
+	full_archive: bool,
+	throttle: &HostThrottle
+) -> PodcastNoId
+{
+	let mut next_url = if full_archive
+	{
+		next_page_url(&channel, url)
+	}
+	else
+	{
+		None
+	};
+	let mut pod = parse_feed_data(channel, url, base);
+	let mut visited = vec![url.to_string()];
+
+	while let Some(page_url) = next_url
+	{
+		if visited.len() >= MAX_FEED_PAGES || visited.contains(&page_url)
+		{
+			break;
+		}
+		let (channel, page_base) = match fetch_url(&page_url, max_retries, throttle)
+		{
+			Ok((data, _)) => match Channel::read_from(&data[..])
+			{
+				Ok(channel) => (channel, feed_base_url(&data, &page_url)),
+				Err(err) => {
+					log::warn!("Could not read older page {page_url} of {url}: {err}");
+					break;
+				}
+			},
+			Err(err) => {
+				log::warn!("Could not retrieve older page {page_url} of {url}: {err}");
+				break;
+			}
+		};
+		next_url = next_page_url(&channel, &page_url);
+
+		// pages may overlap if new episodes were published while
+		// paging through the feed
+		let count = channel.items().len();
+		for (index, item) in channel.items().iter().enumerate()
+		{
+			let ep = parse_episode_data(item, page_base.item(index, count));
+			if !pod.episodes.iter().any(|other| same_episode(other, &ep))
+			{
+				pod.episodes.push(ep);
+			}
+		}
+		visited.push(page_url);
+	}
+	return pod;
+}
+
+/// Checks whether two episodes found on different pages of a feed are
+/// the same one. Episodes are matched by GUID, or if either has none, by
+/// enclosure URL, or failing that, by title.
+fn same_episode(a: &EpisodeNoId, b: &EpisodeNoId) -> bool
+{
+	if !a.guid.is_empty() && !b.guid.is_empty()
+	{
+		return a.guid == b.guid;
+	}
+	if !a.url.is_empty() && !b.url.is_empty()
+	{
+		return a.url == b.url;
+	}
+	return !a.title.is_empty() && a.title == b.title;
+}
+
+/// Returns the absolute URL of the next (older) page of a paged feed,
+/// if the feed has one.
+fn next_page_url(channel: &Channel, base_url: &str) -> Option<String>
+{
+	let link = channel
+		.atom_ext()?
+		.links()
+		.iter()
+		.find(|link| link.rel() == "next")?;
+	return resolve_url(Url::parse(base_url).ok().as_ref(), link.href());
+}
+
+/// Searches an HTML document for `<link rel="alternate">` tags pointing
+/// to RSS or Atom feeds, and returns the absolute URLs of all feeds
+/// found, in the order they appear in the document. Relative links are
+/// resolved against `base_url`.
+fn find_feed_links(html: &str, base_url: &str) -> Vec<String>
+{
+	let base = Url::parse(base_url).ok();
+	let mut links = Vec::new();
+	for tag in RE_LINK_TAGS.find_iter(html)
+	{
+		let mut rel = None;
+		let mut link_type = None;
+		let mut href = None;
+		for cap in RE_HTML_ATTRS.captures_iter(tag.as_str())
+		{
+			let value = cap.get(2)
+				.or_else(|| cap.get(3))
+				.or_else(|| cap.get(4))
+				.map(|m| m.as_str().trim().to_string());
+			match cap[1].to_lowercase().as_str()
+			{
+				"rel" => rel = value.map(|v| v.to_lowercase()),
+				"type" => link_type = value.map(|v| v.to_lowercase()),
+				"href" => href = value,
+				_ => (),
+			}
+		}
+
+		let is_alternate = rel.is_some_and(|r| {
+			r.split_whitespace().any(|r| r == "alternate")
+		});
+		let is_feed = matches!(
+			link_type.as_deref(),
+			Some("application/rss+xml") | Some("application/atom+xml")
+		);
+		if !is_alternate || !is_feed
+		{
+			continue;
+		}
+
+		if let Some(href) = href
+		{
+			let href = escaper::decode_html(&href).unwrap_or(href);
+			if let Some(link) = resolve_url(base.as_ref(), &href)
+			{
+				if !links.contains(&link)
+				{
+					links.push(link);
+				}
+			}
+		}
+	}
+	return links;
+}
+
+/// Returns the URLs that relative links in a feed should be resolved
+/// against. This is the URL the feed was retrieved from (as a file://
+/// URL for local feeds), unless the feed sets a different one with an
+/// `xml:base` attribute on its <rss>, <channel> or <feed> tag, or on
+/// one of its <item> tags for the links of that item. Nested `xml:base`
+/// attributes are themselves resolved against the base of the enclosing
+/// tag.
+fn feed_base_url(data: &[u8], url: &str) -> FeedBase
+{
+	let mut base = match local_path(url)
+	{
+		Some(path) => Url::from_file_path(path).ok(),
+		None => Url::parse(url).ok(),
+	};
+	let xml = String::from_utf8_lossy(data);
+	for cap in RE_XML_BASE.captures_iter(&xml)
+	{
+		let value = match cap.get(1).or_else(|| cap.get(2))
+		{
+			Some(value) => value.as_str().trim(),
+			None => continue,
+		};
+		let value = escaper::decode_html(value).unwrap_or_else(|_| value.to_string());
+		let joined = match &base
+		{
+			Some(base) => base.join(&value).ok(),
+			None => Url::parse(&value).ok(),
+		};
+		if joined.is_some()
+		{
+			base = joined;
+		}
+	}
+
+	let items = RE_ITEM_BASE
+		.captures_iter(&xml)
+		.map(|cap| match cap.get(1).or_else(|| cap.get(2))
+		{
+			Some(value) => {
+				let value = value.as_str().trim();
+				let value = escaper::decode_html(value).unwrap_or_else(|_| value.to_string());
+				match &base
+				{
+					Some(base) => base.join(&value).ok().or_else(|| Some(base.clone())),
+					None => Url::parse(&value).ok(),
+				}
+			}
+			None => base.clone(),
+		})
+		.collect();
+	return FeedBase {
+		channel: base,
+		items: items,
+	};
+}
+
+/// Resolves a link against `base`. Absolute URLs are returned untouched,
+/// while relative ones are joined onto the base. Returns None if the
+/// link is relative and there is no base to resolve it against.
+fn resolve_url(base: Option<&Url>, href: &str) -> Option<String>
+{
+	if Url::parse(href).is_ok()
+	{
+		return Some(href.to_string());
+	}
+	return base
+		.and_then(|base| base.join(href).ok())
+		.map(|u| u.to_string());
+}
+
 
 /// Given a Channel with the RSS feed data, this parses the data about a
 /// podcast and its episodes and returns a Podcast. There are existing
 /// specifications for podcast RSS feeds that a feed should adhere to, but
 /// this does try to make some attempt to account for the possibility that
-/// a feed might not be valid according to the spec.
-fn parse_feed_data(channel: Channel, url: &str) -> PodcastNoId
+/// a feed might not be valid according to the spec. Relative links,
+/// enclosure and image URLs are resolved against `feed_base`.
+fn parse_feed_data(channel: Channel, url: &str, feed_base: &FeedBase) -> PodcastNoId
 {
+	let base = feed_base.channel.as_ref();
 	let title = channel.title().to_string();
 	let url = url.to_string();
+	let link = parse_link(channel.link(), base);
 	let description = Some(channel.description().to_string());
 	let last_checked = Utc::now();
 
 	let mut author = None;
 	let mut explicit = None;
+	let mut image_url = None;
 	if let Some(itunes) = channel.itunes_ext()
 	{
 		author = itunes.author().map(|a| a.to_string());
+		image_url = itunes.image().map(|i| i.to_string());
 		explicit = match itunes.explicit()
 		{
 			None => None,
@@ -156,22 +861,31 @@ fn parse_feed_data(channel: Channel, url: &str) -> PodcastNoId
 		};
 	}
 
+	// fall back on the standard RSS channel image
+	let image_url = image_url
+		.or_else(|| channel.image().map(|i| i.url().to_string()))
+		.map(|i| i.trim().to_string())
+		.filter(|i| !i.is_empty())
+		.map(|i| resolve_url(base, &i).unwrap_or(i));
+
 	let mut episodes = Vec::new();
 	let items = channel.into_items();
 	if !items.is_empty()
 	{
-		for item in &items
+		for (index, item) in items.iter().enumerate()
 		{
-			episodes.push(parse_episode_data(item));
+			episodes.push(parse_episode_data(item, feed_base.item(index, items.len())));
 		}
 	}
 
 	return PodcastNoId {
 		title: title,
 		url: url,
+		link: link,
 		description: description,
 		author: author,
 		explicit: explicit,
+		image_url: image_url,
 		last_checked: last_checked,
 		episodes: episodes,
 	};
@@ -181,8 +895,9 @@ fn parse_feed_data(channel: Channel, url: &str) -> PodcastNoId
 /// and converts it to an Episode. There are existing specifications for
 /// podcast RSS feeds that a feed should adhere to, but this does try to
 /// make some attempt to account for the possibility that a feed might
-/// not be valid according to the spec.
-fn parse_episode_data(item: &Item) -> EpisodeNoId
+/// not be valid according to the spec. Relative enclosure URLs and
+/// links are resolved against `base`.
+fn parse_episode_data(item: &Item, base: Option<&Url>) -> EpisodeNoId
 {
 	let title = match item.title()
 	{
@@ -191,14 +906,24 @@ fn parse_episode_data(item: &Item) -> EpisodeNoId
 	};
 	let url = match item.enclosure()
 	{
-		Some(enc) => enc.url().to_string(),
-		None => "".to_string(),
+		Some(enc) if !enc.url().trim().is_empty() => {
+			let href = enc.url().trim();
+			resolve_url(base, href).unwrap_or_else(|| href.to_string())
+		}
+		Some(_) | None => "".to_string(),
 	};
+	// the length of the enclosure is often missing or set to a dummy
+	// value such as 0 or 1, which is no use as a size
+	let enclosure_size = item
+		.enclosure()
+		.and_then(|enc| enc.length().trim().parse::<u64>().ok())
+		.filter(|&size| size > 1);
 	let guid = match item.guid()
 	{
 		Some(guid) => guid.value().to_string(),
 		None => "".to_string(),
 	};
+	let link = item.link().and_then(|link| parse_link(link, base));
 	let description = match item.description()
 	{
 		Some(dsc) => dsc.to_string(),
@@ -222,26 +947,46 @@ fn parse_episode_data(item: &Item) -> EpisodeNoId
 	};
 
 	let mut duration = None;
+	let mut season = None;
+	let mut episode_num = None;
 	if let Some(itunes) = item.itunes_ext()
 	{
 		duration = duration_to_int(itunes.duration()).map(|dur| dur as i64);
+		season = itunes.season().and_then(|s| s.trim().parse::<i64>().ok());
+		episode_num = itunes.episode().and_then(|e| e.trim().parse::<i64>().ok());
 	}
 
 	return EpisodeNoId {
 		title: title,
 		url: url,
 		guid: guid,
+		link: link,
 		description: description,
 		pubdate: pubdate,
 		duration: duration,
+		season: season,
+		episode_num: episode_num,
+		enclosure_size: enclosure_size,
 	};
 }
 
+/// Cleans up the link to the web page of a podcast or episode, resolving
+/// it against `base` if it is relative. Returns None for empty links.
+fn parse_link(link: &str, base: Option<&Url>) -> Option<String>
+{
+	let link = link.trim();
+	if link.is_empty()
+	{
+		return None;
+	}
+	return Some(resolve_url(base, link).unwrap_or_else(|| link.to_string()));
+}
+
 /// Given a string representing an episode duration, this attempts to
 /// convert to an integer representing the duration in seconds. Covers
 /// formats HH:MM:SS, MM:SS, and SS. If the duration cannot be converted
 /// (covering numerous reasons), it will return None.
-fn duration_to_int(duration: Option<&str>) -> Option<i32>
+pub fn duration_to_int(duration: Option<&str>) -> Option<i32>
 {
 	match duration
 	{
@@ -278,16 +1023,19 @@ fn duration_to_int(duration: Option<&str>) -> Option<i32>
 						}
 					}
 
+					// values too large to fit are treated as malformed
 					return match counter
 					{
 						// HH:MM:SS
-						3 => Some(
-							times[0].unwrap() * 60 * 60
-								+ times[1].unwrap() * 60
-								+ times[2].unwrap(),
-						),
+						3 => times[0].unwrap().checked_mul(60 * 60)
+							.and_then(|h| {
+								times[1].unwrap().checked_mul(60)
+									.and_then(|m| h.checked_add(m))
+							})
+							.and_then(|hm| hm.checked_add(times[2].unwrap())),
 						// MM:SS
-						2 => Some(times[0].unwrap() * 60 + times[1].unwrap()),
+						2 => times[0].unwrap().checked_mul(60)
+							.and_then(|m| m.checked_add(times[1].unwrap())),
 						// SS
 						1 => times[0],
 						_ => None,
@@ -327,7 +1075,7 @@ mod tests
 	{
 		let path = "./tests/test_no_description.xml";
 		let channel = Channel::read_from(open_file(path)).unwrap();
-		let data = parse_feed_data(channel, "dummy_url");
+		let data = parse_feed_data(channel, "dummy_url", &FeedBase::default());
 		assert_eq!(data.description, Some("".to_string()));
 	}
 
@@ -336,7 +1084,7 @@ mod tests
 	{
 		let path = "./tests/test_inval_explicit.xml";
 		let channel = Channel::read_from(open_file(path)).unwrap();
-		let data = parse_feed_data(channel, "dummy_url");
+		let data = parse_feed_data(channel, "dummy_url", &FeedBase::default());
 		assert_eq!(data.explicit, None);
 	}
 
@@ -345,7 +1093,7 @@ mod tests
 	{
 		let path = "./tests/test_no_episodes.xml";
 		let channel = Channel::read_from(open_file(path)).unwrap();
-		let data = parse_feed_data(channel, "dummy_url");
+		let data = parse_feed_data(channel, "dummy_url", &FeedBase::default());
 		assert_eq!(data.episodes.len(), 0);
 	}
 
@@ -398,6 +1146,302 @@ mod tests
 		assert_eq!(duration_to_int(Some(&duration)), Some(522));
 	}
 
+	#[test]
+	fn season_episode()
+	{
+		let path = "./tests/test_paged.xml";
+		let channel = Channel::read_from(open_file(path)).unwrap();
+		let data = parse_feed_data(channel, "dummy_url", &FeedBase::default());
+		assert_eq!(data.episodes[0].season, Some(2));
+		assert_eq!(data.episodes[0].episode_num, Some(7));
+	}
+
+	#[test]
+	fn next_page_found()
+	{
+		let path = "./tests/test_paged.xml";
+		let channel = Channel::read_from(open_file(path)).unwrap();
+		let next = next_page_url(&channel, "https://example.org/feed.xml");
+		assert_eq!(next, Some("https://example.org/feed.xml?page=2".to_string()));
+	}
+
+	#[test]
+	fn next_page_none()
+	{
+		let path = "./tests/test.xml";
+		let channel = Channel::read_from(open_file(path)).unwrap();
+		let next = next_page_url(&channel, "https://example.org/feed.xml");
+		assert_eq!(next, None);
+	}
+
+	#[test]
+	fn same_episode_without_guid()
+	{
+		let xml = "<rss version=\"2.0\"><channel><title>Pages</title><link></link>\
+			<description></description>\
+			<item><title>Ep 2</title><guid>ep-2</guid><enclosure url=\"2.mp3\"/></item>\
+			<item><title>Ep 2</title><guid>ep-2b</guid><enclosure url=\"2.mp3\"/></item>\
+			<item><title>Ep 1</title><enclosure url=\"1.mp3\"/></item>\
+			<item><title>Ep 1 again</title><guid>ep-1</guid><enclosure url=\"1.mp3\"/></item>\
+			<item><title>Ep 0</title></item>\
+			<item><title>Ep 0</title><guid>ep-0</guid></item>\
+			<item><title>Ep 00</title></item>\
+			</channel></rss>";
+		let channel = Channel::read_from(xml.as_bytes()).unwrap();
+		let eps = parse_feed_data(channel, "dummy_url", &FeedBase::default()).episodes;
+		// different GUIDs are different episodes, even with the same URL
+		assert!(!same_episode(&eps[0], &eps[1]));
+		assert!(same_episode(&eps[2], &eps[3]));
+		assert!(!same_episode(&eps[2], &eps[0]));
+		assert!(same_episode(&eps[4], &eps[5]));
+		assert!(!same_episode(&eps[4], &eps[6]));
+	}
+
+	#[test]
+	fn relative_urls()
+	{
+		let path = "./tests/test_relative_urls.xml";
+		let data = std::fs::read(path).unwrap();
+		let url = "https://example.org/podcast/feed.xml";
+		let base = feed_base_url(&data, url);
+		let channel = Channel::read_from(&data[..]).unwrap();
+		let pod = parse_feed_data(channel, url, &base);
+		let urls: Vec<&str> = pod.episodes.iter().map(|ep| ep.url.as_str()).collect();
+		assert_eq!(urls, vec![
+			"https://example.org/podcast/media/ep2.mp3",
+			"https://example.org/audio/ep1.mp3",
+			"https://cdn.example.com/ep0.mp3?token=a%20b",
+		]);
+		assert_eq!(
+			pod.image_url,
+			Some("https://example.org/podcast/images/cover.png".to_string())
+		);
+	}
+
+	#[test]
+	fn relative_urls_xml_base()
+	{
+		let xml = "<rss version=\"2.0\" xml:base=\"https://media.example.net/shows/\">\
+			<channel xml:base=\"weekly/\"><title>Base</title><link></link>\
+			<description></description><item><title>Ep</title>\
+			<enclosure url=\"ep1.mp3\" length=\"1\" type=\"audio/mpeg\"/>\
+			</item></channel></rss>";
+		let url = "https://example.org/feed.xml";
+		let base = feed_base_url(xml.as_bytes(), url);
+		let channel = Channel::read_from(xml.as_bytes()).unwrap();
+		let pod = parse_feed_data(channel, url, &base);
+		assert_eq!(pod.episodes[0].url, "https://media.example.net/shows/weekly/ep1.mp3");
+	}
+
+	#[test]
+	fn links_and_images_xml_base()
+	{
+		let xml = "<rss version=\"2.0\"><channel xml:base=\"https://example.net/show/\">\
+			<title>Base</title><link>about</link><description></description>\
+			<image><url>cover.png</url><title>Base</title><link>about</link></image>\
+			<item xml:base=\"https://cdn.example.net/2024/\"><title>Ep 2</title>\
+			<link>ep2.html</link><enclosure url=\"ep2.mp3\" length=\"1\"/></item>\
+			<item><title>Ep 1</title><link>episodes/1</link>\
+			<enclosure url=\"ep1.mp3\" length=\"1\"/></item></channel></rss>";
+		let url = "https://example.org/feed.xml";
+		let base = feed_base_url(xml.as_bytes(), url);
+		let channel = Channel::read_from(xml.as_bytes()).unwrap();
+		let pod = parse_feed_data(channel, url, &base);
+		assert_eq!(pod.link, Some("https://example.net/show/about".to_string()));
+		assert_eq!(pod.image_url, Some("https://example.net/show/cover.png".to_string()));
+		assert_eq!(pod.episodes[0].link, Some("https://cdn.example.net/2024/ep2.html".to_string()));
+		assert_eq!(pod.episodes[0].url, "https://cdn.example.net/2024/ep2.mp3");
+		assert_eq!(pod.episodes[1].link, Some("https://example.net/show/episodes/1".to_string()));
+		assert_eq!(pod.episodes[1].url, "https://example.net/show/ep1.mp3");
+	}
+
+	#[test]
+	fn links()
+	{
+		let xml = "<rss version=\"2.0\"><channel><title>Links</title>\
+			<link> https://example.org/show/ </link><description></description>\
+			<item><title>Ep 2</title><link>episodes/2</link></item>\
+			<item><title>Ep 1</title><link></link></item></channel></rss>";
+		let url = "https://example.org/feed.xml";
+		let base = feed_base_url(xml.as_bytes(), url);
+		let channel = Channel::read_from(xml.as_bytes()).unwrap();
+		let pod = parse_feed_data(channel, url, &base);
+		assert_eq!(pod.link, Some("https://example.org/show/".to_string()));
+		assert_eq!(pod.episodes[0].link, Some("https://example.org/episodes/2".to_string()));
+		assert_eq!(pod.episodes[1].link, None);
+	}
+
+	#[test]
+	fn enclosure_sizes()
+	{
+		let xml = "<rss version=\"2.0\"><channel><title>Sizes</title><link></link>\
+			<description></description>\
+			<item><title>Ep 3</title><enclosure url=\"3.mp3\" length=\" 52428800 \"/></item>\
+			<item><title>Ep 2</title><enclosure url=\"2.mp3\" length=\"0\"/></item>\
+			<item><title>Ep 1</title><enclosure url=\"1.mp3\" length=\"unknown\"/></item>\
+			</channel></rss>";
+		let url = "https://example.org/feed.xml";
+		let channel = Channel::read_from(xml.as_bytes()).unwrap();
+		let pod = parse_feed_data(channel, url, &FeedBase::default());
+		let sizes: Vec<Option<u64>> = pod.episodes.iter().map(|ep| ep.enclosure_size).collect();
+		assert_eq!(sizes, vec![Some(52428800), None, None]);
+	}
+
+	#[test]
+	fn local_paths()
+	{
+		assert_eq!(
+			local_path("file:///home/user/feed.xml"),
+			Some(PathBuf::from("/home/user/feed.xml"))
+		);
+		assert_eq!(
+			local_path("/home/user/My%20Feed.xml"),
+			Some(PathBuf::from("/home/user/My%20Feed.xml"))
+		);
+		assert_eq!(local_path("https://example.org/feed.xml"), None);
+		assert_eq!(local_path("feed.xml"), None);
+	}
+
+	#[test]
+	fn local_feed_missing()
+	{
+		let throttle = HostThrottle::new(0);
+		let result = fetch_url("/nonexistent/shellcaster/feed.xml", 3, &throttle);
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn latin1_feed()
+	{
+		let data = std::fs::read("./tests/test_latin1.xml").unwrap();
+		let channel = Channel::read_from(&to_utf8(data, None)[..]).unwrap();
+		let pod = parse_feed_data(channel, "dummy_url", &FeedBase::default());
+		assert_eq!(pod.title, "Le Café Sonore");
+		assert_eq!(pod.episodes[0].title, "Épisode 1 : À bientôt");
+	}
+
+	#[test]
+	fn charset_header_overrides_declaration()
+	{
+		let data = b"<?xml version=\"1.0\" encoding=\"UTF-8\"?><rss version=\"2.0\">\
+			<channel><title>Caf\xe9</title><link></link><description></description>\
+			</channel></rss>".to_vec();
+		let data = to_utf8(data, Some("application/rss+xml; charset=\"ISO-8859-1\""));
+		let channel = Channel::read_from(&data[..]).unwrap();
+		assert_eq!(channel.title(), "Café");
+	}
+
+	#[test]
+	fn invalid_utf8_replaced()
+	{
+		let data = b"\xef\xbb\xbf<?xml version=\"1.0\" encoding=\"UTF-8\"?><rss version=\"2.0\">\
+			<channel><title>Caf\xe9</title><link></link><description></description>\
+			</channel></rss>".to_vec();
+		let channel = Channel::read_from(&to_utf8(data, None)[..]).unwrap();
+		assert_eq!(channel.title(), "Caf\u{fffd}");
+	}
+
+	#[test]
+	fn normalize_similar_urls()
+	{
+		let urls = [
+			"https://example.org/feed/",
+			"http://EXAMPLE.org/feed",
+			"https://example.org/feed?utm_source=website&utm_medium=link",
+			"https://example.org/feed#latest",
+			" https://example.org/feed/?fbclid=abc123 ",
+		];
+		for url in urls.iter()
+		{
+			assert_eq!(normalize_url(url), "example.org/feed", "{}", url);
+		}
+	}
+
+	#[test]
+	fn normalize_different_urls()
+	{
+		assert_ne!(
+			normalize_url("https://example.org/feed?show=1"),
+			normalize_url("https://example.org/feed?show=2")
+		);
+		assert_ne!(
+			normalize_url("https://example.org/feed"),
+			normalize_url("https://example.org:8080/feed")
+		);
+		assert_eq!(
+			normalize_url("https://example.org/feed?utm_campaign=x&id=5"),
+			"example.org/feed?id=5"
+		);
+	}
+
+	#[test]
+	fn retry_after_seconds()
+	{
+		assert_eq!(retry_after(Some("12")), Duration::from_secs(12));
+		assert_eq!(retry_after(Some("86400")), Duration::from_secs(MAX_RETRY_AFTER));
+		assert_eq!(retry_after(None), Duration::from_secs(5));
+	}
+
+	#[test]
+	fn retry_after_past_date()
+	{
+		let header = "Wed, 21 Oct 2015 07:28:00 GMT";
+		assert_eq!(retry_after(Some(header)), Duration::from_secs(0));
+	}
+
+	#[test]
+	fn interleave_hosts()
+	{
+		let urls = [
+			"https://a.example/1", "https://a.example/2", "https://a.example/3",
+			"https://b.example/1", "https://c.example/1", "https://b.example/2",
+		];
+		let feeds = urls
+			.iter()
+			.map(|u| PodcastFeed::new(None, u.to_string(), None))
+			.collect();
+		let order: Vec<String> = interleave_by_host(feeds)
+			.into_iter()
+			.map(|f| f.url)
+			.collect();
+		assert_eq!(order, vec![
+			"https://a.example/1", "https://b.example/1", "https://c.example/1",
+			"https://a.example/2", "https://b.example/2", "https://a.example/3",
+		]);
+	}
+
+	#[test]
+	fn throttle_same_host()
+	{
+		let throttle = HostThrottle::new(100);
+		let start = Instant::now();
+		throttle.wait("https://example.org/feed1.xml");
+		throttle.wait("https://other.example.org/feed.xml");
+		assert!(start.elapsed() < Duration::from_millis(100));
+		throttle.wait("https://EXAMPLE.org/feed2.xml");
+		assert!(start.elapsed() >= Duration::from_millis(100));
+	}
+
+	#[test]
+	fn feed_links_found()
+	{
+		let html = std::fs::read_to_string("./tests/test_feed_links.html").unwrap();
+		let links = find_feed_links(&html, "https://example.org/podcast/");
+		assert_eq!(links, vec![
+			"https://example.org/feed/mp3.xml".to_string(),
+			"https://example.org/feed/atom?format=ogg&size=full".to_string(),
+			"https://example.org/podcast/feed/mp3.xml".to_string(),
+		]);
+	}
+
+	#[test]
+	fn feed_links_none()
+	{
+		let html = "<html><head><link rel=\"icon\" href=\"/favicon.ico\"></head></html>";
+		let links = find_feed_links(html, "https://example.org/");
+		assert!(links.is_empty());
+	}
+
 	#[test]
 	fn duration_mss()
 	{
@@ -425,4 +1469,18 @@ mod tests
 		let duration = String::from("8");
 		assert_eq!(duration_to_int(Some(&duration)), Some(8));
 	}
+
+	#[test]
+	fn duration_malformed()
+	{
+		let duration = String::from("about an hour");
+		assert_eq!(duration_to_int(Some(&duration)), None);
+	}
+
+	#[test]
+	fn duration_overflow()
+	{
+		let duration = String::from("999999:00:00");
+		assert_eq!(duration_to_int(Some(&duration)), None);
+	}
 }
@@ -0,0 +1,670 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::config::GpodderConfig;
+use crate::db::{EpisodeChange, EpisodeUpdate};
+use crate::feeds;
+
+/// Format of the times of episode actions, which are in UTC
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Messages sent back to the main controller while syncing with
+/// gpodder.net: the list of subscriptions on the server, once it has
+/// been retrieved, whether the new list could be uploaded, and the
+/// outcome of exchanging episode actions.
+#[derive(Debug)]
+pub enum GpodderMsg
+{
+	Subscriptions(Result<Vec<String>>),
+	Uploaded(Result<()>),
+	EpisodeActions(ActionExchange),
+}
+
+/// Kinds of episode actions. Only "play" and "new" change anything
+/// here; the others are only recorded on the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionKind
+{
+	Download,
+	Delete,
+	Play,
+	New,
+	#[serde(other)]
+	Other,
+}
+
+/// An episode action, as exchanged with the server: something done with
+/// an episode, which is identified by the URL of its feed and of its
+/// file, and by its GUID if there is one. For "play" actions,
+/// `position` is where playback stopped and `total` the length of the
+/// episode, both in seconds; a position at the end marks the episode
+/// as played.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpisodeAction
+{
+	pub podcast: String,
+	pub episode: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub guid: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub device: Option<String>,
+	pub action: ActionKind,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub timestamp: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub started: Option<i64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub position: Option<i64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub total: Option<i64>,
+}
+
+/// Episode actions retrieved from the server, along with the timestamp
+/// to retrieve the next ones from.
+#[derive(Debug, Deserialize)]
+pub struct RemoteActions
+{
+	pub actions: Vec<EpisodeAction>,
+	pub timestamp: i64,
+}
+
+/// Outcome of exchanging episode actions with the server; see
+/// `GpodderClient::exchange_actions()`. Each half can fail on its own,
+/// and only the halves that succeeded are recorded, so that the others
+/// are tried again at the next sync. `until` is the time up to which
+/// the changes made here were uploaded.
+#[derive(Debug)]
+pub struct ActionExchange
+{
+	pub remote: Result<RemoteActions>,
+	pub uploaded: Result<usize>,
+	pub until: DateTime<Utc>,
+}
+
+/// Changes to make to the subscriptions, found by comparing the lists
+/// of feed URLs here and on the server with the list after the last
+/// sync. `add` are the feeds subscribed to elsewhere since then, and
+/// `remove` those unsubscribed from elsewhere that are still subscribed
+/// to here. `kept` are the feeds subscribed to here that stay.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SubscriptionChanges
+{
+	pub add: Vec<String>,
+	pub remove: Vec<String>,
+	pub kept: Vec<String>,
+}
+
+impl SubscriptionChanges
+{
+	/// Compares the lists of feed URLs. Feeds that were unsubscribed from
+	/// here since the last sync are left out of `kept`, so that they are
+	/// removed from the server as well. URLs that differ only slightly
+	/// (see `feeds::normalize_url()`) are taken for the same feed, and
+	/// local files are left out, as other devices cannot read them.
+	pub fn compare(synced: &[String], local: &[String], remote: &[String]) -> Self
+	{
+		let keys = |urls: &[String]| -> HashSet<String> {
+			return urls.iter().map(|url| feeds::normalize_url(url)).collect();
+		};
+		let synced_keys = keys(synced);
+		let local_keys = keys(local);
+		let remote_keys = keys(remote);
+		let is_web = |url: &&String| feeds::local_path(url).is_none();
+
+		let mut changes = Self::default();
+		let mut added = HashSet::new();
+		for url in remote.iter().filter(is_web)
+		{
+			let key = feeds::normalize_url(url);
+			if !synced_keys.contains(&key) && !local_keys.contains(&key) && added.insert(key)
+			{
+				changes.add.push(url.clone());
+			}
+		}
+		for url in local.iter().filter(is_web)
+		{
+			let key = feeds::normalize_url(url);
+			if synced_keys.contains(&key) && !remote_keys.contains(&key)
+			{
+				changes.remove.push(url.clone());
+			}
+			else
+			{
+				changes.kept.push(url.clone());
+			}
+		}
+		return changes;
+	}
+
+	/// Returns the list of feed URLs to upload to the server once the
+	/// changes have been made. Feeds whose removal was skipped are left
+	/// out, so that they stay removed on the server.
+	pub fn upload_list(&self) -> Vec<String>
+	{
+		return self.kept.iter().chain(self.add.iter()).cloned().collect();
+	}
+
+	/// Returns the list of feed URLs to remember as synced once the new
+	/// list has been uploaded, given the feeds whose removal was skipped.
+	/// Those are kept, so that their removal comes up again at the next
+	/// sync. Added feeds are only remembered once they have been
+	/// subscribed to here, so that feeds that could not be added are
+	/// tried again.
+	pub fn synced_list(&self, skipped: &[String]) -> Vec<String>
+	{
+		return self.kept.iter().chain(skipped.iter()).cloned().collect();
+	}
+}
+
+/// Returns the episode actions for the changes made here after `after`
+/// (or ever, if it is None): a "download" action for each file
+/// downloaded, and a "play" action with the playback position or, for
+/// episodes marked as played, with the position at the end. Episodes
+/// marked as unplayed get a "new" action. Episodes of local files, and
+/// played episodes whose length is unknown, are left out.
+pub fn local_actions(changes: &[EpisodeChange], after: Option<DateTime<Utc>>) -> Vec<EpisodeAction>
+{
+	let is_new = |time: Option<DateTime<Utc>>| match (time, after)
+	{
+		(Some(time), Some(after)) => time > after,
+		(Some(_), None) => true,
+		(None, _) => false,
+	};
+	let mut actions = Vec::new();
+	for change in changes.iter().filter(|change| feeds::local_path(&change.podcast_url).is_none())
+	{
+		let action = |kind: ActionKind, time: DateTime<Utc>| EpisodeAction {
+			podcast: change.podcast_url.clone(),
+			episode: change.url.clone(),
+			guid: (!change.guid.is_empty()).then(|| change.guid.clone()),
+			device: None,
+			action: kind,
+			timestamp: Some(time.format(TIME_FORMAT).to_string()),
+			started: None,
+			position: None,
+			total: None,
+		};
+		if let Some(time) = change.downloaded_at.filter(|_| is_new(change.downloaded_at))
+		{
+			actions.push(action(ActionKind::Download, time));
+		}
+		let time = match change.changed_at.filter(|_| is_new(change.changed_at))
+		{
+			Some(time) => time,
+			None => continue,
+		};
+		let duration = change.duration.filter(|duration| *duration > 0);
+		if change.played
+		{
+			match duration
+			{
+				Some(duration) => actions.push(EpisodeAction {
+					started: Some(0),
+					position: Some(duration),
+					total: Some(duration),
+					..action(ActionKind::Play, time)
+				}),
+				None => log::debug!("Not uploading played status of {}: length unknown", change.url),
+			}
+		}
+		else if let Some(position) = change.position
+		{
+			actions.push(EpisodeAction {
+				position: Some(position),
+				total: duration,
+				..action(ActionKind::Play, time)
+			});
+		}
+		else
+		{
+			actions.push(action(ActionKind::New, time));
+		}
+	}
+	return actions;
+}
+
+/// Turns the episode actions of other devices into updates for the
+/// podcasts here, given as their IDs and feed URLs. Only the latest
+/// "play" or "new" action for each episode is kept. Actions of this
+/// device, actions without a valid time, and actions for podcasts that
+/// are not subscribed to are left out.
+pub fn remote_updates(actions: &[EpisodeAction], device: &str, podcasts: &[(i64, String)])
+	-> Vec<EpisodeUpdate>
+{
+	let pod_ids: HashMap<String, i64> = podcasts
+		.iter()
+		.map(|(pod_id, url)| (feeds::normalize_url(url), *pod_id))
+		.collect();
+	let mut latest: HashMap<(i64, &str), EpisodeUpdate> = HashMap::new();
+	for action in actions.iter()
+	{
+		if action.device.as_deref() == Some(device)
+		{
+			continue;
+		}
+		let (played, position) = match (action.action, action.position)
+		{
+			(ActionKind::Play, Some(position)) => {
+				match action.total
+				{
+					Some(total) if total > 0 && position >= total => (true, None),
+					_ => (false, Some(position.max(0))),
+				}
+			}
+			(ActionKind::New, _) => (false, None),
+			_ => continue,
+		};
+		let (pod_id, time) = match (
+			pod_ids.get(&feeds::normalize_url(&action.podcast)),
+			action.timestamp.as_deref().and_then(parse_time),
+		)
+		{
+			(Some(pod_id), Some(time)) => (*pod_id, time),
+			_ => continue,
+		};
+		let key = (pod_id, action.episode.as_str());
+		if latest.get(&key).is_some_and(|update| update.time > time)
+		{
+			continue;
+		}
+		latest.insert(key, EpisodeUpdate {
+			pod_id: pod_id,
+			url: action.episode.clone(),
+			guid: action.guid.clone().filter(|guid| !guid.is_empty()),
+			played: played,
+			position: position,
+			time: time,
+		});
+	}
+	let mut updates: Vec<EpisodeUpdate> = latest.into_values().collect();
+	updates.sort_by(|a, b| a.time.cmp(&b.time).then_with(|| a.url.cmp(&b.url)));
+	return updates;
+}
+
+/// Parses the time of an episode action. Fractions of seconds and a
+/// trailing "Z", which some servers add, are accepted.
+fn parse_time(time: &str) -> Option<DateTime<Utc>>
+{
+	return NaiveDateTime::parse_from_str(time.trim_end_matches('Z'), "%Y-%m-%dT%H:%M:%S%.f")
+		.ok()
+		.map(|time| DateTime::from_utc(time, Utc));
+}
+
+/// Client for the API of gpodder.net, or of another server with the
+/// same API, which reads and replaces the list of subscriptions of a
+/// device, and exchanges episode actions. The password or token is
+/// sent in a header, so that it does not appear in URLs, which may end
+/// up in error messages and logs.
+pub struct GpodderClient
+{
+	config: GpodderConfig,
+	agent: ureq::Agent,
+}
+
+impl GpodderClient
+{
+	/// Creates a new GpodderClient. Fails if the config has neither a
+	/// password nor a token.
+	pub fn new(config: GpodderConfig) -> Result<Self>
+	{
+		if config.password.is_none() && config.token.is_none()
+		{
+			return Err(anyhow!(
+				"Set a password or a token in the [gpodder] section of config.toml to sync with {}.",
+				config.server
+			));
+		}
+		let agent_builder = ureq::builder()
+			.timeout_connect(Duration::from_secs(10))
+			.timeout_read(Duration::from_secs(20));
+
+		#[cfg(feature = "native_tls")]
+		let tls_connector = std::sync::Arc::new(native_tls::TlsConnector::new().unwrap());
+		#[cfg(feature = "native_tls")]
+		let agent_builder = agent_builder.tls_connector(tls_connector);
+
+		return Ok(Self {
+			config: config,
+			agent: agent_builder.build(),
+		});
+	}
+
+	/// Returns the address of the server.
+	pub fn server(&self) -> &str
+	{
+		return &self.config.server;
+	}
+
+	/// Returns a name for the account and device, used to keep track of
+	/// what was synced with each of them.
+	pub fn account(&self) -> String
+	{
+		return account_name(&self.config);
+	}
+
+	/// Retrieves the list of feed URLs subscribed to on the device.
+	pub fn subscriptions(&self) -> Result<Vec<String>>
+	{
+		let url = self.url()?;
+		log::debug!("Requesting subscriptions from {url}");
+		let body = self
+			.authorize(self.agent.get(&url))
+			.call()
+			.map_err(|err| self.request_error(err))?
+			.into_string()?;
+		let urls: Vec<String> = serde_json::from_str(&body)
+			.map_err(|err| anyhow!("Unexpected response from {}: {err}", self.config.server))?;
+		log::info!("Retrieved {} subscriptions from {}", urls.len(), self.config.server);
+		return Ok(urls);
+	}
+
+	/// Replaces the list of feed URLs subscribed to on the device. The
+	/// device is created on the server if it does not exist yet.
+	pub fn upload(&self, urls: &[String]) -> Result<()>
+	{
+		let url = self.url()?;
+		log::debug!("Uploading {} subscriptions to {url}", urls.len());
+		self.authorize(self.agent.put(&url))
+			.set("Content-Type", "application/json")
+			.send_string(&serde_json::to_string(urls)?)
+			.map_err(|err| self.request_error(err))?;
+		log::info!("Uploaded {} subscriptions to {}", urls.len(), self.config.server);
+		return Ok(());
+	}
+
+	/// Retrieves the episode actions of all devices of the account since
+	/// `since`, a timestamp given by the server at the last sync, or 0.
+	pub fn episode_actions(&self, since: i64) -> Result<RemoteActions>
+	{
+		let url = self.episodes_url()?;
+		log::debug!("Requesting episode actions since {since} from {url}");
+		let body = self
+			.authorize(self.agent.get(&url))
+			.query("since", &since.to_string())
+			.call()
+			.map_err(|err| self.request_error(err))?
+			.into_string()?;
+		let remote: RemoteActions = serde_json::from_str(&body)
+			.map_err(|err| anyhow!("Unexpected response from {}: {err}", self.config.server))?;
+		log::info!(
+			"Retrieved {} episode actions from {}",
+			remote.actions.len(),
+			self.config.server
+		);
+		return Ok(remote);
+	}
+
+	/// Uploads episode actions as actions of this device.
+	pub fn upload_actions(&self, actions: &[EpisodeAction]) -> Result<()>
+	{
+		let actions: Vec<EpisodeAction> = actions
+			.iter()
+			.map(|action| EpisodeAction {
+				device: Some(self.config.device.clone()),
+				..action.clone()
+			})
+			.collect();
+		let url = self.episodes_url()?;
+		log::debug!("Uploading {} episode actions to {url}", actions.len());
+		self.authorize(self.agent.post(&url))
+			.set("Content-Type", "application/json")
+			.send_string(&serde_json::to_string(&actions)?)
+			.map_err(|err| self.request_error(err))?;
+		log::info!("Uploaded {} episode actions to {}", actions.len(), self.config.server);
+		return Ok(());
+	}
+
+	/// Retrieves the episode actions of other devices since `since`, and
+	/// uploads the actions for the changes made here up to `until`. The
+	/// upload is tried even if the actions could not be retrieved.
+	pub fn exchange_actions(&self, since: i64, upload: &[EpisodeAction], until: DateTime<Utc>)
+		-> ActionExchange
+	{
+		let remote = self.episode_actions(since);
+		let uploaded = if upload.is_empty()
+		{
+			Ok(0)
+		}
+		else
+		{
+			self.upload_actions(upload).map(|_| upload.len())
+		};
+		return ActionExchange {
+			remote: remote,
+			uploaded: uploaded,
+			until: until,
+		};
+	}
+
+	/// Returns the URL of the list of subscriptions of the device.
+	fn url(&self) -> Result<String>
+	{
+		let device = format!("{}.json", self.config.device);
+		return self.api_url(&["subscriptions", &self.config.username, &device]);
+	}
+
+	/// Returns the URL of the episode actions of the account.
+	fn episodes_url(&self) -> Result<String>
+	{
+		let user = format!("{}.json", self.config.username);
+		return self.api_url(&["api", "2", "episodes", &user]);
+	}
+
+	/// Returns the URL of an endpoint of the API, given the segments of
+	/// its path after the address of the server.
+	fn api_url(&self, segments: &[&str]) -> Result<String>
+	{
+		let mut url = Url::parse(&self.config.server)
+			.map_err(|err| anyhow!("Invalid gpodder server {}: {err}", self.config.server))?;
+		url.path_segments_mut()
+			.map_err(|_| anyhow!("Invalid gpodder server {}", self.config.server))?
+			.pop_if_empty()
+			.extend(segments);
+		return Ok(url.to_string());
+	}
+
+	/// Adds the password or the token to a request.
+	fn authorize(&self, request: ureq::Request) -> ureq::Request
+	{
+		return match (&self.config.token, &self.config.password)
+		{
+			(Some(token), _) => request.set("Authorization", &format!("Bearer {token}")),
+			(None, Some(password)) => {
+				let credentials = format!("{}:{password}", self.config.username);
+				request.set("Authorization", &format!("Basic {}", base64::encode(credentials)))
+			}
+			(None, None) => request,
+		};
+	}
+
+	/// Describes an error from a request to the server.
+	fn request_error(&self, err: ureq::Error) -> anyhow::Error
+	{
+		let server = &self.config.server;
+		return match err
+		{
+			ureq::Error::Status(401, _) | ureq::Error::Status(403, _) => anyhow!(
+				"{server} did not accept the username and password or token for {}",
+				self.config.username
+			),
+			ureq::Error::Status(code, _) => {
+				anyhow!("{server} responded with HTTP status {code}")
+			}
+			ureq::Error::Transport(err) => anyhow!("No response from {server}: {err}"),
+		};
+	}
+}
+
+/// Returns a name for the account and device in a config, e.g.,
+/// "user@https://gpodder.net/shellcaster".
+pub fn account_name(config: &GpodderConfig) -> String
+{
+	return format!(
+		"{}@{}/{}",
+		config.username,
+		config.server.trim_end_matches('/'),
+		config.device
+	);
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use chrono::TimeZone;
+
+	fn urls(list: &[&str]) -> Vec<String>
+	{
+		return list.iter().map(|url| format!("https://example.com/{url}")).collect();
+	}
+
+	#[test]
+	fn changes_merged_both_ways()
+	{
+		// the first sync only adds feeds, on both sides
+		let changes = SubscriptionChanges::compare(&[], &urls(&["a", "b"]), &urls(&["b", "c"]));
+		assert_eq!(changes.add, urls(&["c"]));
+		assert!(changes.remove.is_empty());
+		assert_eq!(changes.upload_list(), urls(&["a", "b", "c"]));
+
+		// "a" was unsubscribed from here and "b" elsewhere, "d" was
+		// added here and "e" elsewhere
+		let synced = urls(&["a", "b", "c"]);
+		let mut local = urls(&["b", "c", "d"]);
+		local.push("/home/user/local.xml".to_string());
+		let remote = vec![
+			"https://example.com/a".to_string(),
+			"http://EXAMPLE.com/c/".to_string(),
+			"https://example.com/e".to_string(),
+			"https://example.com/e".to_string(),
+		];
+		let changes = SubscriptionChanges::compare(&synced, &local, &remote);
+		assert_eq!(changes, SubscriptionChanges {
+			add: urls(&["e"]),
+			remove: urls(&["b"]),
+			kept: urls(&["c", "d"]),
+		});
+		assert_eq!(changes.upload_list(), urls(&["c", "d", "e"]));
+		assert_eq!(changes.synced_list(&urls(&["b"])), urls(&["c", "d", "b"]));
+	}
+
+	#[test]
+	fn episode_actions_exchanged()
+	{
+		let time = |secs: i64| Utc.timestamp(1_600_000_000 + secs, 0);
+		let change = |url: &str, played: bool, position: Option<i64>, duration: Option<i64>| {
+			EpisodeChange {
+				podcast_url: "https://example.com/feed".to_string(),
+				url: format!("https://example.com/{url}"),
+				guid: String::new(),
+				played: played,
+				position: position,
+				duration: duration,
+				changed_at: Some(time(10)),
+				downloaded_at: None,
+			}
+		};
+		let mut changes = vec![
+			change("played.mp3", true, None, Some(600)),
+			change("unknown-length.mp3", true, None, None),
+			change("started.mp3", false, Some(90), None),
+			change("unplayed.mp3", false, None, Some(600)),
+			EpisodeChange {
+				changed_at: Some(time(0)),
+				downloaded_at: Some(time(20)),
+				guid: "guid".to_string(),
+				..change("downloaded.mp3", false, Some(30), None)
+			},
+		];
+		changes.push(EpisodeChange {
+			podcast_url: "/home/user/feed.xml".to_string(),
+			..changes[0].clone()
+		});
+		let actions = local_actions(&changes, Some(time(5)));
+		let summary: Vec<_> = actions
+			.iter()
+			.map(|action| (action.episode.as_str(), action.action, action.position, action.total))
+			.collect();
+		assert_eq!(summary, vec![
+			("https://example.com/played.mp3", ActionKind::Play, Some(600), Some(600)),
+			("https://example.com/started.mp3", ActionKind::Play, Some(90), None),
+			("https://example.com/unplayed.mp3", ActionKind::New, None, None),
+			("https://example.com/downloaded.mp3", ActionKind::Download, None, None),
+		]);
+		assert_eq!(actions[0].timestamp.as_deref(), Some("2020-09-13T12:26:50"));
+		assert_eq!(actions[3].guid.as_deref(), Some("guid"));
+
+		let json = r#"{"timestamp": 12, "actions": [
+			{"podcast": "http://EXAMPLE.com/feed/", "episode": "https://example.com/1.mp3",
+				"device": "phone", "action": "play", "timestamp": "2020-09-13T12:26:40",
+				"started": 0, "position": 120, "total": 600},
+			{"podcast": "https://example.com/feed", "episode": "https://example.com/1.mp3",
+				"device": "phone", "action": "play", "timestamp": "2020-09-13T12:27:40Z",
+				"position": 600, "total": 600},
+			{"podcast": "https://example.com/feed", "episode": "https://example.com/2.mp3",
+				"guid": "two", "action": "new", "timestamp": "2020-09-13T12:26:40.5"},
+			{"podcast": "https://example.com/feed", "episode": "https://example.com/3.mp3",
+				"device": "laptop", "action": "play", "timestamp": "2020-09-13T12:26:40",
+				"position": 60},
+			{"podcast": "https://example.com/feed", "episode": "https://example.com/4.mp3",
+				"action": "download", "timestamp": "2020-09-13T12:26:40"},
+			{"podcast": "https://example.com/other", "episode": "https://example.com/5.mp3",
+				"action": "new", "timestamp": "2020-09-13T12:26:40"},
+			{"podcast": "https://example.com/feed", "episode": "https://example.com/6.mp3",
+				"action": "flattr", "timestamp": "2020-09-13T12:26:40"}
+		]}"#;
+		let remote: RemoteActions = serde_json::from_str(json).unwrap();
+		assert_eq!(remote.timestamp, 12);
+		let podcasts = vec![(7, "https://example.com/feed".to_string())];
+		let updates = remote_updates(&remote.actions, "laptop", &podcasts);
+		assert_eq!(updates, vec![
+			EpisodeUpdate {
+				pod_id: 7,
+				url: "https://example.com/2.mp3".to_string(),
+				guid: Some("two".to_string()),
+				played: false,
+				position: None,
+				time: time(0) + chrono::Duration::milliseconds(500),
+			},
+			EpisodeUpdate {
+				pod_id: 7,
+				url: "https://example.com/1.mp3".to_string(),
+				guid: None,
+				played: true,
+				position: None,
+				time: time(60),
+			},
+		]);
+	}
+
+	#[test]
+	fn subscriptions_url()
+	{
+		let client = GpodderClient::new(GpodderConfig {
+			server: "https://gpodder.example.com/".to_string(),
+			username: "jo doe".to_string(),
+			device: "laptop".to_string(),
+			password: None,
+			token: Some("secret".to_string()),
+		})
+		.unwrap();
+		assert_eq!(
+			client.url().unwrap(),
+			"https://gpodder.example.com/subscriptions/jo%20doe/laptop.json"
+		);
+		assert_eq!(
+			client.episodes_url().unwrap(),
+			"https://gpodder.example.com/api/2/episodes/jo%20doe.json"
+		);
+		assert_eq!(client.account(), "jo doe@https://gpodder.example.com/laptop");
+		assert!(!format!("{:?}", client.config).contains("secret"));
+	}
+}
@@ -1,7 +1,8 @@
 use ahash::AHashMap;
+use anyhow::{anyhow, Result};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-use crate::config::KeybindingsFromToml;
+use crate::config::{KeyList, KeybindingsFromToml};
 
 /// Enum delineating all actions that may be performed by the user, and
 /// thus have keybindings associated with them.
@@ -23,38 +24,212 @@ pub enum UserAction
 	AddFeed,
 	Sync,
 	SyncAll,
+	SyncGpodder,
+	Rename,
+	MaxEpisodes,
+	EditTags,
+	ToggleFavorite,
 
 	Play,
 	MarkPlayed,
 	MarkAllPlayed,
+	SetPosition,
+	Archive,
+	ArchiveOlder,
+
+	Enqueue,
+	ShowQueue,
+	PlayNext,
+
+	ToggleMark,
+	MarkAll,
+	ClearMarks,
+
+	ShowStats,
+	ShowLog,
+	OpenLink,
+	CopyUrl,
+	CopyPath,
+	ExportFeeds,
 
 	Download,
 	DownloadAll,
 	UnmarkDownloaded,
 	Delete,
 	DeleteAll,
+	SetWorkers,
 	Remove,
 	RemoveAll,
+	UndoRemoval,
 
 	FilterPlayed,
 	FilterDownloaded,
+	FilterView,
+	FilterTag,
+	FilterArchived,
+	FilterFavorites,
+	Search,
+	JumpTo,
+	SortEpisodes,
+	SortPodcasts,
+	CycleLayout,
+	ReloadConfig,
+	EditConfig,
 
 	Help,
 	Quit,
 }
 
-/// Wrapper around a hash map that keeps track of all keybindings. Multiple
-/// keys may perform the same action, but each key may only perform one
-/// action.
+/// Names of the special keys, as returned by `input_to_str()`. They can
+/// be written in any case in the config file.
+const KEY_NAMES: [&str; 15] = [
+	"Backspace", "Enter", "Left", "Right", "Up", "Down", "Home", "End", "PgUp", "PgDn",
+	"Tab", "Del", "Ins", "Esc", "Space",
+];
+
+/// Other names accepted for some of the special keys in the config file
+const KEY_ALIASES: [(&str, &str); 7] = [
+	("Return", "Enter"),
+	("PageUp", "PgUp"),
+	("PageDown", "PgDn"),
+	("Delete", "Del"),
+	("Insert", "Ins"),
+	("Escape", "Esc"),
+	("BS", "Backspace"),
+];
+
+/// Ways of writing the modifiers of a key in the config file, in full or
+/// in short, as in vim and emacs
+const MODIFIER_PREFIXES: [(&str, KeyModifiers); 10] = [
+	("ctrl+", KeyModifiers::CONTROL),
+	("ctrl-", KeyModifiers::CONTROL),
+	("c-", KeyModifiers::CONTROL),
+	("alt+", KeyModifiers::ALT),
+	("alt-", KeyModifiers::ALT),
+	("a-", KeyModifiers::ALT),
+	("m-", KeyModifiers::ALT),
+	("shift+", KeyModifiers::SHIFT),
+	("shift-", KeyModifiers::SHIFT),
+	("s-", KeyModifiers::SHIFT),
+];
+
+/// Keys that terminals send as other keys, as they share the same
+/// control character
+const TERMINAL_ALIASES: [(&str, &str); 3] = [
+	("Ctrl+m", "Enter"),
+	("Ctrl+i", "Tab"),
+	("Ctrl+[", "Esc"),
+];
+
+/// What the keys typed so far do: perform an action, start a sequence
+/// of keys that may yet perform one, or nothing at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMatch
+{
+	Action(UserAction),
+	Prefix,
+	Unbound,
+}
+
+/// Node of the prefix tree of keybindings. Each node is reached by
+/// typing a sequence of keys, and either performs an action or leads to
+/// further keys, but not both, as there would be no telling whether
+/// the user is done typing.
+#[derive(Debug, Clone, Default)]
+struct KeyNode
+{
+	action: Option<UserAction>,
+	next: AHashMap<String, KeyNode>,
+}
+
+impl KeyNode
+{
+	/// Adds the sequences of keys under this node that perform `action`
+	/// to `found`, each one after the keys in `typed`.
+	fn collect_keys(&self, action: UserAction, typed: &mut Vec<String>, found: &mut Vec<String>)
+	{
+		if self.action == Some(action)
+		{
+			found.push(typed.join(" "));
+		}
+		for (key, node) in self.next.iter()
+		{
+			typed.push(key.clone());
+			node.collect_keys(action, typed, found);
+			typed.pop();
+		}
+	}
+}
+
+/// Keybinding from the config file, or a default one, as it is checked
+/// before being added to the keymap. `code` is the key as written by
+/// the user, and `keys` the sequence of keys it stands for, with
+/// `aliased` set if the terminal sends one of them as another key.
+struct Binding<'a>
+{
+	name: &'a str,
+	code: String,
+	keys: Vec<String>,
+	action: UserAction,
+	aliased: bool,
+	default: bool,
+}
+
+/// Builds the error for two keybindings that are the same key, where the
+/// first one comes from the config file.
+fn key_conflict(binding: &Binding, other: &Binding) -> anyhow::Error
+{
+	let (binding, other) = if other.aliased && !binding.aliased && !other.default
+	{
+		(other, binding)
+	}
+	else
+	{
+		(binding, other)
+	};
+	if binding.aliased
+	{
+		return anyhow!(
+			"Keybinding \"{}\" for {} is sent by terminals as \"{}\", which is bound to {}",
+			binding.code,
+			binding.name,
+			other.keys.join(" "),
+			other.name
+		);
+	}
+	if other.default
+	{
+		return anyhow!(
+			"Keybinding \"{}\" for {} is also the default key for {}; set {} to other keys or to \"none\" in config.toml",
+			binding.code,
+			binding.name,
+			other.name,
+			other.name
+		);
+	}
+	return anyhow!(
+		"Keybinding \"{}\" for {} is the same key as \"{}\" for {} in config.toml",
+		binding.code,
+		binding.name,
+		other.code,
+		other.name
+	);
+}
+
+/// Prefix tree that keeps track of all keybindings. An action is
+/// performed by a single key (e.g., "d") or by a sequence of keys,
+/// written with spaces in between (e.g., "g g"). Multiple keys may
+/// perform the same action, but each key may only perform one action,
+/// and a key that performs an action cannot also start a sequence.
 #[derive(Debug, Clone)]
-pub struct Keybindings(AHashMap<String, UserAction>);
+pub struct Keybindings(KeyNode);
 
 impl Keybindings
 {
 	/// Returns a new Keybindings struct.
 	pub fn new() -> Self
 	{
-		return Self(AHashMap::new());
+		return Self(KeyNode::default());
 	}
 
 	/// Returns a Keybindings struct with all default values set.
@@ -72,65 +247,240 @@ impl Keybindings
 	/// Given a struct deserialized from config.toml (for which any or
 	/// all fields may be missing), create a Keybindings struct using
 	/// user-defined keys where specified, and default values otherwise.
-	pub fn from_config(config: KeybindingsFromToml) -> Self
+	pub fn from_config(config: KeybindingsFromToml) -> Result<Self>
 	{
-		let config_actions: Vec<(Option<Vec<String>>, UserAction)> = vec![
-			(config.left, UserAction::Left),
-			(config.right, UserAction::Right),
-			(config.up, UserAction::Up),
-			(config.down, UserAction::Down),
-			(config.big_up, UserAction::BigUp),
-			(config.big_down, UserAction::BigDown),
-			(config.page_up, UserAction::PageUp),
-			(config.page_down, UserAction::PageDown),
-			(config.go_top, UserAction::GoTop),
-			(config.go_bot, UserAction::GoBot),
-			(config.add_feed, UserAction::AddFeed),
-			(config.sync, UserAction::Sync),
-			(config.sync_all, UserAction::SyncAll),
-			(config.play, UserAction::Play),
-			(config.mark_played, UserAction::MarkPlayed),
-			(config.mark_all_played, UserAction::MarkAllPlayed),
-			(config.download, UserAction::Download),
-			(config.download_all, UserAction::DownloadAll),
-			(config.unmark_downloaded, UserAction::UnmarkDownloaded),
-			(config.delete, UserAction::Delete),
-			(config.delete_all, UserAction::DeleteAll),
-			(config.remove, UserAction::Remove),
-			(config.remove_all, UserAction::RemoveAll),
-			(config.filter_played, UserAction::FilterPlayed),
-			(config.filter_downloaded, UserAction::FilterDownloaded),
-			(config.help, UserAction::Help),
-			(config.quit, UserAction::Quit),
+		let config_actions: Vec<(&str, Option<KeyList>, UserAction)> = vec![
+			("left", config.left, UserAction::Left),
+			("right", config.right, UserAction::Right),
+			("up", config.up, UserAction::Up),
+			("down", config.down, UserAction::Down),
+			("big_up", config.big_up, UserAction::BigUp),
+			("big_down", config.big_down, UserAction::BigDown),
+			("page_up", config.page_up, UserAction::PageUp),
+			("page_down", config.page_down, UserAction::PageDown),
+			("go_top", config.go_top, UserAction::GoTop),
+			("go_bot", config.go_bot, UserAction::GoBot),
+			("add_feed", config.add_feed, UserAction::AddFeed),
+			("sync", config.sync, UserAction::Sync),
+			("sync_all", config.sync_all, UserAction::SyncAll),
+			("sync_gpodder", config.sync_gpodder, UserAction::SyncGpodder),
+			("rename", config.rename, UserAction::Rename),
+			("max_episodes", config.max_episodes, UserAction::MaxEpisodes),
+			("edit_tags", config.edit_tags, UserAction::EditTags),
+			("toggle_favorite", config.toggle_favorite, UserAction::ToggleFavorite),
+			("play", config.play, UserAction::Play),
+			("mark_played", config.mark_played, UserAction::MarkPlayed),
+			("mark_all_played", config.mark_all_played, UserAction::MarkAllPlayed),
+			("set_position", config.set_position, UserAction::SetPosition),
+			("archive", config.archive, UserAction::Archive),
+			("archive_older", config.archive_older, UserAction::ArchiveOlder),
+			("enqueue", config.enqueue, UserAction::Enqueue),
+			("show_queue", config.show_queue, UserAction::ShowQueue),
+			("play_next", config.play_next, UserAction::PlayNext),
+			("toggle_mark", config.toggle_mark, UserAction::ToggleMark),
+			("mark_all", config.mark_all, UserAction::MarkAll),
+			("clear_marks", config.clear_marks, UserAction::ClearMarks),
+			("show_stats", config.show_stats, UserAction::ShowStats),
+			("show_log", config.show_log, UserAction::ShowLog),
+			("open_link", config.open_link, UserAction::OpenLink),
+			("copy_url", config.copy_url, UserAction::CopyUrl),
+			("copy_path", config.copy_path, UserAction::CopyPath),
+			("export_feeds", config.export_feeds, UserAction::ExportFeeds),
+			("download", config.download, UserAction::Download),
+			("download_all", config.download_all, UserAction::DownloadAll),
+			("unmark_downloaded", config.unmark_downloaded, UserAction::UnmarkDownloaded),
+			("delete", config.delete, UserAction::Delete),
+			("delete_all", config.delete_all, UserAction::DeleteAll),
+			("set_workers", config.set_workers, UserAction::SetWorkers),
+			("remove", config.remove, UserAction::Remove),
+			("remove_all", config.remove_all, UserAction::RemoveAll),
+			("undo_removal", config.undo_removal, UserAction::UndoRemoval),
+			("filter_played", config.filter_played, UserAction::FilterPlayed),
+			("filter_downloaded", config.filter_downloaded, UserAction::FilterDownloaded),
+			("filter_view", config.filter_view, UserAction::FilterView),
+			("filter_tag", config.filter_tag, UserAction::FilterTag),
+			("filter_archived", config.filter_archived, UserAction::FilterArchived),
+			("filter_favorites", config.filter_favorites, UserAction::FilterFavorites),
+			("search", config.search, UserAction::Search),
+			("jump_to", config.jump_to, UserAction::JumpTo),
+			("sort_episodes", config.sort_episodes, UserAction::SortEpisodes),
+			("sort_podcasts", config.sort_podcasts, UserAction::SortPodcasts),
+			("cycle_layout", config.cycle_layout, UserAction::CycleLayout),
+			("reload_config", config.reload_config, UserAction::ReloadConfig),
+			("edit_config", config.edit_config, UserAction::EditConfig),
+			("help", config.help, UserAction::Help),
+			("quit", config.quit, UserAction::Quit),
 		];
 
-		let mut keymap = Self::default();
-		for (config, action) in config_actions.into_iter()
+		// the user's keys, split into sequences, and written the way
+		// `input_to_str()` does. Keys set for an action replace its
+		// default keys, so that an empty list (or "none") unbinds it.
+		let mut bindings: Vec<Binding> = Vec::new();
+		let mut configured = Vec::new();
+		for (name, config, action) in config_actions.iter()
 		{
-			if let Some(config) = config
+			let list = match config
+			{
+				Some(list) => list,
+				None => continue,
+			};
+			configured.push(*action);
+			for code in list.keys()
 			{
-				keymap.insert_from_vec(config, action);
+				let mut keys = Vec::new();
+				let mut aliased = false;
+				for key in code.split_whitespace()
+				{
+					let key = parse_key(key).map_err(|err| {
+						anyhow!("Invalid keybinding \"{}\" for {} in config.toml: {}", code, name, err)
+					})?;
+					match terminal_key(&key)
+					{
+						Some(actual) => {
+							aliased = true;
+							keys.push(actual);
+						}
+						None => keys.push(key),
+					}
+				}
+				if keys.is_empty()
+				{
+					return Err(anyhow!("Empty keybinding for {} in config.toml", name));
+				}
+				bindings.push(Binding {
+					name: name,
+					code: code.clone(),
+					keys: keys,
+					action: *action,
+					aliased: aliased,
+					default: false,
+				});
 			}
 		}
-		return keymap;
+
+		// a key cannot both perform an action and start a sequence, as
+		// there would be no telling which one the user meant
+		for binding in bindings.iter()
+		{
+			for other in bindings.iter()
+			{
+				if other.keys.len() > binding.keys.len() && other.keys.starts_with(&binding.keys)
+				{
+					return Err(anyhow!(
+						"Keybinding \"{}\" for {} is also the start of \"{}\" for {} in config.toml",
+						binding.code,
+						binding.name,
+						other.code,
+						other.name
+					));
+				}
+			}
+		}
+
+		// the default keys of the other actions, except those that get
+		// in the way of the user's sequences
+		let user_count = bindings.len();
+		for (action, codes) in Self::_defaults().into_iter()
+		{
+			if configured.contains(&action)
+			{
+				continue;
+			}
+			let name = config_actions
+				.iter()
+				.find(|(_, _, a)| *a == action)
+				.map_or("", |(name, _, _)| name);
+			for code in codes.into_iter()
+			{
+				let keys = vec![code.clone()];
+				let overlaps = bindings[..user_count].iter().any(|binding| {
+					binding.keys.len() > 1 && binding.keys.starts_with(&keys)
+				});
+				if !overlaps
+				{
+					bindings.push(Binding {
+						name: name,
+						code: code,
+						keys: keys,
+						action: action,
+						aliased: false,
+						default: true,
+					});
+				}
+			}
+		}
+
+		// the terminal is in raw mode, so Ctrl+c does not stop the
+		// program; without a single key to quit, there would be no way out
+		// of a popup window, or of the program
+		let can_quit = bindings
+			.iter()
+			.any(|binding| binding.action == UserAction::Quit && binding.keys.len() == 1);
+		if !can_quit
+		{
+			return Err(anyhow!(
+				"Keybindings for quit in config.toml must include a single key, such as \"q\""
+			));
+		}
+
+		// no key may do two actions, as only one of them would work
+		for (index, binding) in bindings.iter().enumerate()
+		{
+			if let Some(other) = bindings[index + 1..]
+				.iter()
+				.find(|other| other.keys == binding.keys && other.action != binding.action)
+			{
+				return Err(key_conflict(binding, other));
+			}
+		}
+
+		let mut keymap = Self::new();
+		for binding in bindings.into_iter()
+		{
+			keymap.insert_keys(binding.keys, binding.action);
+		}
+		return Ok(keymap);
 	}
 
-	/// Takes an Input object from crossterm and returns the associated
-	/// user action, if one exists.
-	pub fn get_from_input(&self, input: KeyEvent) -> Option<&UserAction>
+	/// Looks up the sequence of keys typed so far, as returned by
+	/// `input_to_str()`.
+	pub fn get_from_keys(&self, keys: &[String]) -> KeyMatch
 	{
-		match input_to_str(input)
+		let mut node = &self.0;
+		for key in keys
 		{
-			Some(code) => self.0.get(&code),
-			None => None,
+			node = match node.next.get(key)
+			{
+				Some(next) => next,
+				None => return KeyMatch::Unbound,
+			};
 		}
+		return match node.action
+		{
+			Some(action) => KeyMatch::Action(action),
+			None if node.next.is_empty() => KeyMatch::Unbound,
+			None => KeyMatch::Prefix,
+		};
 	}
 
-	/// Inserts a new keybinding into the hash map. Will overwrite the
+	/// Inserts a new keybinding into the prefix tree, where `code` is a
+	/// key or a sequence of keys separated by spaces. Will overwrite the
 	/// value of a key if it already exists.
 	pub fn insert(&mut self, code: String, action: UserAction)
 	{
-		self.0.insert(code, action);
+		let keys = code.split_whitespace().map(str::to_string).collect();
+		self.insert_keys(keys, action);
+	}
+
+	/// Inserts a new keybinding for a sequence of keys.
+	fn insert_keys(&mut self, keys: Vec<String>, action: UserAction)
+	{
+		let mut node = &mut self.0;
+		for key in keys.into_iter()
+		{
+			node = node.next.entry(key).or_default();
+		}
+		node.action = Some(action);
 	}
 
 	/// Inserts a set of new keybindings into the hash map, each one
@@ -145,20 +495,24 @@ impl Keybindings
 	}
 
 	/// Returns a Vec with all of the keys mapped to a particular user
-	/// action.
+	/// action, with the keys of a sequence separated by spaces, in the
+	/// same form as in the config file.
 	pub fn keys_for_action(&self, action: UserAction) -> Vec<String>
 	{
-		return self
-			.0
-			.iter()
-			.filter_map(|(key, &val)| {
-				if val == action {
-					Some(key.clone())
-				} else {
-					None
-				}
-			})
-			.collect();
+		let mut found = Vec::new();
+		self.0.collect_keys(action, &mut Vec::new(), &mut found);
+		found.sort();
+		return found;
+	}
+
+	/// Returns the shortest of the keys mapped to a particular user
+	/// action, as it is usually the easiest to remember (e.g., "p"
+	/// rather than "Enter"), or None if no key is mapped to it.
+	pub fn key_for_action(&self, action: UserAction) -> Option<String>
+	{
+		let mut keys = self.keys_for_action(action);
+		keys.sort_by_key(|key| (key.chars().count(), key.clone()));
+		return keys.into_iter().next();
 	}
 
 	fn _defaults() -> Vec<(UserAction, Vec<String>)>
@@ -173,31 +527,159 @@ impl Keybindings
 			(UserAction::Down, vec!["Down".to_string(), "j".to_string()]),
 			(UserAction::BigUp, vec!["K".to_string()]),
 			(UserAction::BigDown, vec!["J".to_string()]),
-			(UserAction::PageUp, vec!["PgUp".to_string()]),
-			(UserAction::PageDown, vec!["PgDn".to_string()]),
-			(UserAction::GoTop, vec!["g".to_string()]),
-			(UserAction::GoBot, vec!["G".to_string()]),
+			(UserAction::PageUp, vec!["PgUp".to_string(), "Ctrl+b".to_string()]),
+			(UserAction::PageDown, vec!["PgDn".to_string(), "Ctrl+f".to_string()]),
+			(UserAction::GoTop, vec!["Home".to_string(), "g".to_string()]),
+			(UserAction::GoBot, vec!["End".to_string(), "G".to_string()]),
 			(UserAction::AddFeed, vec!["a".to_string()]),
 			(UserAction::Sync, vec!["s".to_string()]),
 			(UserAction::SyncAll, vec!["S".to_string()]),
+			(UserAction::SyncGpodder, vec!["Ctrl+g".to_string()]),
+			(UserAction::Rename, vec!["e".to_string()]),
+			(UserAction::MaxEpisodes, vec!["E".to_string()]),
+			(UserAction::EditTags, vec!["T".to_string()]),
+			(UserAction::ToggleFavorite, vec!["*".to_string()]),
 			(UserAction::Play, vec!["Enter".to_string(), "p".to_string()]),
 			(UserAction::MarkPlayed, vec!["m".to_string()]),
 			(UserAction::MarkAllPlayed, vec!["M".to_string()]),
+			(UserAction::SetPosition, vec!["t".to_string()]),
+			(UserAction::Archive, vec!["z".to_string()]),
+			(UserAction::ArchiveOlder, vec!["Z".to_string()]),
+			(UserAction::Enqueue, vec!["+".to_string()]),
+			(UserAction::ShowQueue, vec!["Q".to_string()]),
+			(UserAction::PlayNext, vec!["n".to_string()]),
+			(UserAction::ToggleMark, vec!["Space".to_string()]),
+			(UserAction::MarkAll, vec!["Ctrl+a".to_string()]),
+			(UserAction::ClearMarks, vec!["Esc".to_string()]),
+			(UserAction::ShowStats, vec!["i".to_string()]),
+			(UserAction::ShowLog, vec!["L".to_string()]),
+			(UserAction::OpenLink, vec!["o".to_string()]),
+			(UserAction::CopyUrl, vec!["y".to_string()]),
+			(UserAction::CopyPath, vec!["Y".to_string()]),
+			(UserAction::ExportFeeds, vec!["W".to_string()]),
 			(UserAction::Download, vec!["d".to_string()]),
 			(UserAction::DownloadAll, vec!["D".to_string()]),
 			(UserAction::UnmarkDownloaded, vec!["u".to_string()]),
 			(UserAction::Delete, vec!["x".to_string()]),
 			(UserAction::DeleteAll, vec!["X".to_string()]),
+			(UserAction::SetWorkers, vec!["Ctrl+w".to_string()]),
 			(UserAction::Remove, vec!["r".to_string()]),
 			(UserAction::RemoveAll, vec!["R".to_string()]),
-			(UserAction::FilterPlayed, vec!["1".to_string()]),
-			(UserAction::FilterDownloaded, vec!["2".to_string()]),
+			(UserAction::UndoRemoval, vec!["U".to_string()]),
+			(UserAction::FilterPlayed, vec!["F1".to_string()]),
+			(UserAction::FilterDownloaded, vec!["F2".to_string()]),
+			(UserAction::FilterView, vec!["f".to_string()]),
+			(UserAction::FilterTag, vec!["F3".to_string()]),
+			(UserAction::FilterArchived, vec!["F4".to_string()]),
+			(UserAction::FilterFavorites, vec!["F5".to_string()]),
+			(UserAction::Search, vec!["/".to_string()]),
+			(UserAction::JumpTo, vec!["'".to_string()]),
+			(UserAction::SortEpisodes, vec!["O".to_string()]),
+			(UserAction::SortPodcasts, vec!["Ctrl+o".to_string()]),
+			(UserAction::CycleLayout, vec!["v".to_string()]),
+			(UserAction::ReloadConfig, vec!["Ctrl+r".to_string()]),
+			(UserAction::EditConfig, vec!["Ctrl+e".to_string()]),
 			(UserAction::Help, vec!["?".to_string()]),
 			(UserAction::Quit, vec!["q".to_string()]),
 		];
 	}
 }
 
+/// Converts a key written in the config file to the string returned by
+/// `input_to_str()` for it. Modifiers can be written either in full
+/// (e.g., "Ctrl+d", "Alt+x", "Shift+Tab") or in short, as in vim and
+/// emacs (e.g., "C-d", "A-x" or "M-x", "S-Tab"), and the names of the
+/// special keys in any case.
+pub fn parse_key(key: &str) -> Result<String>
+{
+	let mut modifiers = KeyModifiers::NONE;
+	let mut rest = key;
+	'modifiers: loop
+	{
+		for (prefix, modifier) in MODIFIER_PREFIXES
+		{
+			// the key itself may be "+" or "-", as in "Ctrl+-"
+			if rest.len() > prefix.len()
+				&& rest.is_char_boundary(prefix.len())
+				&& rest[..prefix.len()].eq_ignore_ascii_case(prefix)
+			{
+				modifiers |= modifier;
+				rest = &rest[prefix.len()..];
+				continue 'modifiers;
+			}
+		}
+		break;
+	}
+	let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+	let alt = modifiers.contains(KeyModifiers::ALT);
+	let mut shift = modifiers.contains(KeyModifiers::SHIFT);
+
+	let mut chars = rest.chars();
+	let name = match (chars.next(), chars.next())
+	{
+		(Some(c), None) => {
+			// Shift is part of the character typed, rather than a
+			// modifier
+			let c = match (shift, c.is_alphabetic())
+			{
+				(true, true) => c.to_uppercase().collect(),
+				(true, false) => {
+					return Err(anyhow!("use the character typed with Shift instead of \"{}\"", key));
+				}
+				_ => c.to_string(),
+			};
+			shift = false;
+			match c.as_str()
+			{
+				" " => "Space".to_string(),
+				_ => c,
+			}
+		}
+		_ => {
+			let known = KEY_NAMES
+				.iter()
+				.find(|name| name.eq_ignore_ascii_case(rest))
+				.or_else(|| KEY_ALIASES
+					.iter()
+					.find(|(alias, _)| alias.eq_ignore_ascii_case(rest))
+					.map(|(_, name)| name));
+			match known
+			{
+				Some(name) => name.to_string(),
+				None => match rest.strip_prefix(['F', 'f']).map(str::parse::<u8>)
+				{
+					Some(Ok(num)) if num > 0 => format!("F{num}"),
+					_ => return Err(anyhow!("unknown key \"{}\"", rest)),
+				},
+			}
+		}
+	};
+
+	let ctrl = if ctrl { "Ctrl+" } else { "" };
+	let alt = if alt { "Alt+" } else { "" };
+	let shift = if shift { "Shift+" } else { "" };
+	return Ok(format!("{ctrl}{alt}{shift}{name}"));
+}
+
+/// Returns the key that terminals send instead of `key`, if they cannot
+/// tell them apart: Ctrl with some characters gives the same control
+/// character as another key, and Ctrl with a letter is sent the same way
+/// whether Shift is held or not.
+fn terminal_key(key: &str) -> Option<String>
+{
+	if let Some((_, actual)) = TERMINAL_ALIASES.iter().find(|(alias, _)| *alias == key)
+	{
+		return Some(actual.to_string());
+	}
+	return match key.strip_prefix("Ctrl+")
+	{
+		Some(c) if c.chars().count() == 1 && c.to_ascii_lowercase() != c => {
+			Some(format!("Ctrl+{}", c.to_ascii_lowercase()))
+		}
+		_ => None,
+	};
+}
+
 /// Helper function converting a crossterm KeyEvent object to a unique
 /// string representing that input.
 pub fn input_to_str(input: KeyEvent) -> Option<String>
@@ -231,7 +713,7 @@ pub fn input_to_str(input: KeyEvent) -> Option<String>
 		KeyCode::PageUp => Some(format!("{ctrl}{alt}{shift}PgUp")),
 		KeyCode::PageDown => Some(format!("{ctrl}{alt}{shift}PgDn")),
 		KeyCode::Tab => Some(format!("{ctrl}{alt}{shift}Tab")),
-		KeyCode::BackTab => Some(format!("{ctrl}{alt}{shift}Tab")),
+		KeyCode::BackTab => Some(format!("{ctrl}{alt}Shift+Tab")),
 		KeyCode::Delete => Some(format!("{ctrl}{alt}{shift}Del")),
 		KeyCode::Insert => Some(format!("{ctrl}{alt}{shift}Ins")),
 		KeyCode::Esc => Some(format!("{ctrl}{alt}{shift}Esc")),
@@ -253,13 +735,165 @@ pub fn input_to_str(input: KeyEvent) -> Option<String>
 			{
 				Some(format!("{ctrl}{alt}{shift}Tab"))
 			}
+			else if c == ' '
+			{
+				Some(format!("{ctrl}{alt}{shift}Space"))
+			}
 			else
 			{
 				// here we don't include "shift" because that will
-				// already be encoded in the character itself
+				// already be encoded in the character itself; with Ctrl,
+				// letters are sent in lowercase by most terminals anyway
+				let c = if ctrl.is_empty() { c } else { c.to_ascii_lowercase() };
 				Some(format!("{}{}{}", ctrl, alt, c.encode_utf8(&mut tmp)))
 			}
 		}
 		_ => None,
 	};
 }
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	fn keys(code: &str) -> Vec<String>
+	{
+		return code.split_whitespace().map(str::to_string).collect();
+	}
+
+	#[test]
+	fn key_sequences()
+	{
+		let config: KeybindingsFromToml = toml::from_str(
+			"go_top = [ \"g g\" ]\ndownload_all = [ \"d a\" ]\nenqueue = [ \"Space q\" ]"
+		).unwrap();
+		let keymap = Keybindings::from_config(config).unwrap();
+		assert_eq!(keymap.get_from_keys(&keys("g")), KeyMatch::Prefix);
+		assert_eq!(keymap.get_from_keys(&keys("g g")), KeyMatch::Action(UserAction::GoTop));
+		assert_eq!(keymap.get_from_keys(&keys("d a")), KeyMatch::Action(UserAction::DownloadAll));
+		assert_eq!(keymap.get_from_keys(&keys("d x")), KeyMatch::Unbound);
+		assert_eq!(keymap.get_from_keys(&keys("Space q")), KeyMatch::Action(UserAction::Enqueue));
+		assert_eq!(keymap.get_from_keys(&keys("G")), KeyMatch::Action(UserAction::GoBot));
+
+		// "u" already unmarks downloads, so removals are undone with "U"
+		assert_eq!(keymap.get_from_keys(&keys("u")), KeyMatch::Action(UserAction::UnmarkDownloaded));
+		assert_eq!(keymap.key_for_action(UserAction::UndoRemoval), Some("U".to_string()));
+
+		// the default keys that started the sequences are dropped
+		assert_eq!(keymap.keys_for_action(UserAction::Download), Vec::<String>::new());
+		assert_eq!(keymap.keys_for_action(UserAction::ToggleMark), Vec::<String>::new());
+		assert_eq!(keymap.keys_for_action(UserAction::GoTop), vec!["g g"]);
+	}
+
+	#[test]
+	fn keys_parsed()
+	{
+		for (written, parsed) in [
+			("d", "d"),
+			("C-d", "Ctrl+d"),
+			("ctrl+d", "Ctrl+d"),
+			("A-x", "Alt+x"),
+			("M-x", "Alt+x"),
+			("C-A-x", "Ctrl+Alt+x"),
+			("S-Tab", "Shift+Tab"),
+			("S-a", "A"),
+			("F5", "F5"),
+			("S-f12", "Shift+F12"),
+			("pagedown", "PgDn"),
+			("Escape", "Esc"),
+			("C--", "Ctrl+-"),
+			("+", "+"),
+			("-", "-"),
+		]
+		{
+			assert_eq!(parse_key(written).unwrap(), parsed, "{written}");
+		}
+		for written in ["Foo", "F0", "S-1", "C-", "éé"]
+		{
+			assert!(parse_key(written).is_err(), "{written}");
+		}
+		assert_eq!(terminal_key("Ctrl+m").as_deref(), Some("Enter"));
+		assert_eq!(terminal_key("Ctrl+D").as_deref(), Some("Ctrl+d"));
+		assert_eq!(terminal_key("Ctrl+d"), None);
+	}
+
+	#[test]
+	fn ambiguous_sequences_rejected()
+	{
+		let config: KeybindingsFromToml = toml::from_str(
+			"go_top = [ \"g g\" ]\ngo_bot = [ \"g\" ]"
+		).unwrap();
+		let err = Keybindings::from_config(config).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"Keybinding \"g\" for go_bot is also the start of \"g g\" for go_top in config.toml"
+		);
+
+		// keys that the terminal cannot tell apart do not silently take
+		// over another action
+		let config: KeybindingsFromToml = toml::from_str("sync = [ \"C-m\" ]").unwrap();
+		let err = Keybindings::from_config(config).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"Keybinding \"C-m\" for sync is sent by terminals as \"Enter\", which is bound to play"
+		);
+		let config: KeybindingsFromToml = toml::from_str("page_down = [ \"C-d\", \"PgDn\" ]").unwrap();
+		let keymap = Keybindings::from_config(config).unwrap();
+		assert_eq!(keymap.keys_for_action(UserAction::PageDown), vec!["Ctrl+d", "PgDn"]);
+	}
+
+	#[test]
+	fn defaults_unbound_and_conflicts_rejected()
+	{
+		let config: KeybindingsFromToml = toml::from_str(
+			"remove = []\nremove_all = \"none\"\nundo_removal = \"u\"\nunmark_downloaded = \"none\""
+		).unwrap();
+		let keymap = Keybindings::from_config(config).unwrap();
+		assert_eq!(keymap.keys_for_action(UserAction::Remove), Vec::<String>::new());
+		assert_eq!(keymap.keys_for_action(UserAction::RemoveAll), Vec::<String>::new());
+		assert_eq!(keymap.get_from_keys(&keys("r")), KeyMatch::Unbound);
+		assert_eq!(keymap.get_from_keys(&keys("u")), KeyMatch::Action(UserAction::UndoRemoval));
+		assert_eq!(keymap.get_from_keys(&keys("d")), KeyMatch::Action(UserAction::Download));
+
+		// the digits are left free for counts
+		assert_eq!(keymap.get_from_keys(&keys("1")), KeyMatch::Unbound);
+		assert_eq!(keymap.get_from_keys(&keys("F4")), KeyMatch::Action(UserAction::FilterArchived));
+
+		for (config, message) in [
+			(
+				"delete = [ \"x\", \"d\" ]",
+				"Keybinding \"d\" for delete is also the default key for download; set download to other keys or to \"none\" in config.toml",
+			),
+			(
+				"delete = [ \"C-x\" ]\nsync = [ \"Ctrl+x\" ]",
+				"Keybinding \"Ctrl+x\" for sync is the same key as \"C-x\" for delete in config.toml",
+			),
+		]
+		{
+			let config: KeybindingsFromToml = toml::from_str(config).unwrap();
+			let err = Keybindings::from_config(config).unwrap_err();
+			assert_eq!(err.to_string(), message);
+		}
+	}
+
+	#[test]
+	fn quit_needs_single_key()
+	{
+		for config in ["quit = []", "quit = \"none\"", "quit = [ \"q q\" ]"]
+		{
+			let config: KeybindingsFromToml = toml::from_str(config).unwrap();
+			let err = Keybindings::from_config(config).unwrap_err();
+			assert_eq!(
+				err.to_string(),
+				"Keybindings for quit in config.toml must include a single key, such as \"q\""
+			);
+		}
+		let config: KeybindingsFromToml =
+			toml::from_str("quit = [ \"q q\", \"Ctrl+q\" ]").unwrap();
+		let keymap = Keybindings::from_config(config).unwrap();
+		assert_eq!(keymap.keys_for_action(UserAction::Quit), vec!["Ctrl+q", "q q"]);
+	}
+}
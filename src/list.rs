@@ -0,0 +1,271 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+
+use crate::types::*;
+
+/// Filters of the `list` subcommand. An episode is listed if it is
+/// unplayed when `unplayed` is set, downloaded when `downloaded` is set,
+/// and published on or after `since`, if given. A podcast is listed if
+/// any of its episodes are, or always if no filter is set.
+#[derive(Debug, Default)]
+pub struct ListFilter
+{
+	pub unplayed: bool,
+	pub downloaded: bool,
+	pub since: Option<NaiveDate>,
+}
+
+impl ListFilter
+{
+	/// Returns true if any of the filters is set.
+	pub fn is_set(&self) -> bool
+	{
+		return self.unplayed || self.downloaded || self.since.is_some();
+	}
+
+	/// Returns true if the episode passes all of the filters. Episodes
+	/// without a publication date are left out when `since` is set.
+	pub fn matches(&self, ep: &Episode) -> bool
+	{
+		return (!self.unplayed || !ep.played)
+			&& (!self.downloaded || ep.path.is_some())
+			&& self.since.is_none_or(|since| {
+				ep.pubdate.is_some_and(|date| date.naive_utc().date() >= since)
+			});
+	}
+
+	/// Returns true if the podcast is to be listed: if no filter is set,
+	/// or if any of its episodes pass the filters.
+	pub fn matches_podcast(&self, pod: &Podcast) -> bool
+	{
+		return !self.is_set() || pod.episodes.map(|ep| self.matches(ep), false).contains(&true);
+	}
+}
+
+/// Reads the date given with `--since`, as YYYY-MM-DD.
+pub fn parse_date(date: &str) -> Result<NaiveDate>
+{
+	return NaiveDate::parse_from_str(date, "%Y-%m-%d")
+		.with_context(|| format!("Invalid date: {date} (expected YYYY-MM-DD)"));
+}
+
+/// Sorts the podcasts by title and, if `podcast` is given, keeps only
+/// the one with that title (ignoring case) or ID. It is an error if
+/// no podcast has that title or ID.
+pub fn select_podcasts(mut podcasts: Vec<Podcast>, podcast: Option<&str>) -> Result<Vec<Podcast>>
+{
+	podcasts.sort_by(|a, b| a.sort_title.cmp(&b.sort_title));
+	if let Some(podcast) = podcast
+	{
+		let id = podcast.parse::<i64>().ok();
+		podcasts.retain(|pod| Some(pod.id) == id || pod.title.eq_ignore_ascii_case(podcast));
+		if podcasts.is_empty()
+		{
+			return Err(anyhow!("No podcast with the title or ID: {podcast}"));
+		}
+	}
+	return Ok(podcasts);
+}
+
+/// Returns the row listing a podcast: its ID, title, the date it was
+/// last checked and its feed URL.
+pub fn podcast_row(pod: &Podcast) -> String
+{
+	return tsv_row(&[
+		pod.id.to_string(),
+		pod.title.clone(),
+		pod.last_checked.format("%Y-%m-%d").to_string(),
+		pod.url.clone(),
+	]);
+}
+
+/// Returns the row listing an episode: its ID, title, publication date,
+/// URL and the path to the downloaded file. The date and path are empty
+/// if the episode has none.
+pub fn episode_row(ep: &Episode) -> String
+{
+	return tsv_row(&[
+		ep.id.to_string(),
+		ep.title.clone(),
+		ep.pubdate
+			.map(|date| date.format("%Y-%m-%d").to_string())
+			.unwrap_or_default(),
+		ep.url.clone(),
+		ep.path
+			.as_ref()
+			.map(|path| path.to_string_lossy().to_string())
+			.unwrap_or_default(),
+	]);
+}
+
+/// Joins the columns of a row with tabs. Tabs and line breaks within the
+/// columns are replaced with spaces, so that each row stays on one line
+/// with the same number of columns.
+fn tsv_row(columns: &[String]) -> String
+{
+	return columns
+		.iter()
+		.map(|column| column.replace(['\t', '\n', '\r'], " "))
+		.collect::<Vec<String>>()
+		.join("\t");
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use std::path::PathBuf;
+
+	use chrono::{TimeZone, Utc};
+
+	use super::*;
+
+	fn episode(id: i64, played: bool, path: Option<&str>, day: Option<u32>) -> Episode
+	{
+		return Episode {
+			id: id,
+			pod_id: 1,
+			title: format!("Episode {id}"),
+			url: format!("https://example.com/{id}.mp3"),
+			guid: format!("guid-{id}"),
+			link: None,
+			description: String::new(),
+			pubdate: day.map(|day| Utc.ymd(2024, 3, day).and_hms(12, 0, 0)),
+			duration: None,
+			season: None,
+			episode_num: None,
+			enclosure_size: None,
+			path: path.map(PathBuf::from),
+			downloaded_at: None,
+			file_size: None,
+			played: played,
+			position: None,
+			archived: false,
+			added_at: None,
+			unseen: false,
+			downloading: false,
+		};
+	}
+
+	fn podcast(id: i64, title: &str, episodes: Vec<Episode>) -> Podcast
+	{
+		return Podcast {
+			id: id,
+			title: title.to_string(),
+			feed_title: title.to_string(),
+			sort_title: title.to_lowercase(),
+			url: format!("https://example.com/{id}"),
+			link: None,
+			description: None,
+			author: None,
+			explicit: None,
+			image_url: None,
+			cover_path: None,
+			last_checked: Utc.ymd(2024, 3, 20).and_hms(8, 0, 0),
+			inactive: false,
+			favorite: false,
+			synced: true,
+			last_error: None,
+			last_error_at: None,
+			tags: Vec::new(),
+			episode_sort: EpisodeSort::Newest,
+			episodes: LockVec::new(episodes),
+		};
+	}
+
+	#[test]
+	fn episode_filters()
+	{
+		let played = episode(1, true, Some("1.mp3"), Some(10));
+		let unplayed = episode(2, false, None, Some(1));
+		let undated = episode(3, false, Some("3.mp3"), None);
+
+		let filter = ListFilter::default();
+		assert!(!filter.is_set());
+		assert!(filter.matches(&played) && filter.matches(&unplayed) && filter.matches(&undated));
+
+		let filter = ListFilter { unplayed: true, ..Default::default() };
+		assert!(!filter.matches(&played) && filter.matches(&unplayed));
+
+		let filter = ListFilter { downloaded: true, ..Default::default() };
+		assert!(filter.matches(&played) && !filter.matches(&unplayed) && filter.matches(&undated));
+
+		// the date itself is included, and undated episodes are not
+		let since = parse_date("2024-03-10").unwrap();
+		let filter = ListFilter { since: Some(since), ..Default::default() };
+		assert!(filter.matches(&played) && !filter.matches(&unplayed) && !filter.matches(&undated));
+
+		let filter = ListFilter { unplayed: true, downloaded: true, since: None };
+		assert!(filter.matches(&undated) && !filter.matches(&played));
+	}
+
+	#[test]
+	fn podcast_filters()
+	{
+		let empty = podcast(1, "Empty", Vec::new());
+		let played = podcast(2, "Played", vec![episode(1, true, None, Some(1))]);
+		assert!(ListFilter::default().matches_podcast(&empty));
+
+		let filter = ListFilter { unplayed: true, ..Default::default() };
+		assert!(!filter.matches_podcast(&empty));
+		assert!(!filter.matches_podcast(&played));
+		played.episodes.replace(1, episode(1, false, None, Some(1)));
+		assert!(filter.matches_podcast(&played));
+	}
+
+	#[test]
+	fn podcast_by_title_or_id()
+	{
+		let podcasts = || vec![
+			podcast(1, "Zebra", Vec::new()),
+			podcast(2, "planet money", Vec::new()),
+			podcast(3, "Alpha", Vec::new()),
+		];
+		let ids = |podcasts: Vec<Podcast>| -> Vec<i64> {
+			return podcasts.iter().map(|pod| pod.id).collect();
+		};
+		assert_eq!(ids(select_podcasts(podcasts(), None).unwrap()), vec![3, 2, 1]);
+		assert_eq!(ids(select_podcasts(podcasts(), Some("Planet Money")).unwrap()), vec![2]);
+		assert_eq!(ids(select_podcasts(podcasts(), Some("1")).unwrap()), vec![1]);
+		assert!(select_podcasts(podcasts(), Some("Missing")).is_err());
+	}
+
+	#[test]
+	fn rows()
+	{
+		let pod = podcast(7, "Tabs\tand\nlines", Vec::new());
+		assert_eq!(
+			podcast_row(&pod),
+			"7\tTabs and lines\t2024-03-20\thttps://example.com/7"
+		);
+		assert_eq!(
+			episode_row(&episode(4, false, Some("dir/4.mp3"), Some(5))),
+			"4\tEpisode 4\t2024-03-05\thttps://example.com/4.mp3\tdir/4.mp3"
+		);
+		assert_eq!(
+			episode_row(&episode(5, false, None, None)),
+			"5\tEpisode 5\t\thttps://example.com/5.mp3\t"
+		);
+	}
+
+	#[test]
+	fn json_has_ids()
+	{
+		let pod = podcast(7, "Podcast", vec![episode(4, false, None, None)]);
+		let json = serde_json::to_value(&pod).unwrap();
+		assert_eq!(json["id"], 7);
+		assert!(json.get("episodes").is_none() && json.get("sort_title").is_none());
+
+		let json = serde_json::to_value(pod.episodes.map(|ep| ep.clone(), false)).unwrap();
+		assert_eq!((json[0]["id"].clone(), json[0]["pod_id"].clone()), (4.into(), 1.into()));
+		assert!(json[0].get("downloading").is_none());
+	}
+
+	#[test]
+	fn invalid_date()
+	{
+		assert!(parse_date("2024-02-30").is_err());
+		assert!(parse_date("10/03/2024").is_err());
+	}
+}
@@ -0,0 +1,229 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use chrono::Local;
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::config::{DEBUG_LOGS_KEPT, MAX_DEBUG_LOG_SIZE};
+
+/// Name of the debug log, which is kept in the same directory as the
+/// database
+pub const DEBUG_LOG_NAME: &str = "debug.log";
+
+/// Writes the records of the `log` crate to the debug log, one per line,
+/// along with the time and level. Once the log reaches `max_size` bytes,
+/// it is moved aside and a new one is started. Records can also be
+/// printed to stderr, which is only done by subcommands, as the UI owns
+/// the terminal otherwise. Only records from shellcaster itself are
+/// kept, unless the level is "trace".
+pub struct Logger
+{
+	level: LevelFilter,
+	path: PathBuf,
+	max_size: u64,
+	stderr: bool,
+	file: Mutex<Option<LogFile>>,
+}
+
+/// The debug log, once it has been opened, and its size so far.
+struct LogFile
+{
+	file: File,
+	size: u64,
+}
+
+/// Sets up the logger for the whole program. Nothing is logged if
+/// `level` is "off".
+pub fn init(path: &Path, level: LevelFilter, stderr: bool) -> Result<()>
+{
+	let logger = Logger::new(path.to_path_buf(), level, MAX_DEBUG_LOG_SIZE, stderr);
+	log::set_boxed_logger(Box::new(logger))?;
+	log::set_max_level(level);
+	return Ok(());
+}
+
+impl Logger
+{
+	/// Creates a new Logger. The file is only opened once something is
+	/// logged.
+	pub fn new(path: PathBuf, level: LevelFilter, max_size: u64, stderr: bool) -> Self
+	{
+		return Self {
+			level: level,
+			path: path,
+			max_size: max_size,
+			stderr: stderr,
+			file: Mutex::new(None),
+		};
+	}
+
+	/// Appends a line to the log, starting a new log first if it would
+	/// grow past its maximum size.
+	fn write_line(&self, line: &str) -> io::Result<()>
+	{
+		let mut guard = self.file.lock().expect("Mutex error");
+		if let Some(log_file) = guard.as_ref()
+		{
+			if log_file.size > 0 && log_file.size + line.len() as u64 > self.max_size
+			{
+				*guard = None;
+				rotate(&self.path, DEBUG_LOGS_KEPT)?;
+			}
+		}
+		if guard.is_none()
+		{
+			if let Some(dir) = self.path.parent()
+			{
+				fs::create_dir_all(dir)?;
+			}
+			let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+			let size = file.metadata()?.len();
+			*guard = Some(LogFile {
+				file: file,
+				size: size,
+			});
+		}
+		let log_file = guard.as_mut().expect("Log file not open");
+		log_file.file.write_all(line.as_bytes())?;
+		log_file.size += line.len() as u64;
+		return Ok(());
+	}
+}
+
+impl Log for Logger
+{
+	fn enabled(&self, metadata: &Metadata) -> bool
+	{
+		return metadata.level() <= self.level &&
+			(self.level == LevelFilter::Trace || is_own_target(metadata.target()));
+	}
+
+	fn log(&self, record: &Record)
+	{
+		if !self.enabled(record.metadata())
+		{
+			return;
+		}
+		let message = format_record(record);
+		if self.stderr
+		{
+			eprintln!("{message}");
+		}
+		// there is nowhere to report a log that cannot be written
+		let _ = self.write_line(&format!(
+			"{} {message}\n",
+			Local::now().format("%Y-%m-%d %H:%M:%S")
+		));
+	}
+
+	fn flush(&self)
+	{
+		if let Some(log_file) = self.file.lock().expect("Mutex error").as_mut()
+		{
+			let _ = log_file.file.flush();
+		}
+	}
+}
+
+/// Returns whether a record comes from shellcaster, rather than from one
+/// of the crates it uses.
+fn is_own_target(target: &str) -> bool
+{
+	let name = env!("CARGO_CRATE_NAME");
+	return target == name ||
+		target.strip_prefix(name).is_some_and(|rest| rest.starts_with("::"));
+}
+
+/// Formats a record as "LEVEL module: message", leaving out the name of
+/// the crate for shellcaster's own modules.
+fn format_record(record: &Record) -> String
+{
+	let target = record.target();
+	let module = target
+		.strip_prefix(concat!(env!("CARGO_CRATE_NAME"), "::"))
+		.unwrap_or(target);
+	if module == env!("CARGO_CRATE_NAME")
+	{
+		return format!("{} {}", record.level(), record.args());
+	}
+	return format!("{} {module}: {}", record.level(), record.args());
+}
+
+/// Moves the log at `path` aside, as `path.1`, after moving the older
+/// logs along (`path.1` becomes `path.2`, and so on). Only `kept` of the
+/// older logs are kept.
+fn rotate(path: &Path, kept: usize) -> io::Result<()>
+{
+	let numbered = |n: usize| {
+		let mut name = path.as_os_str().to_os_string();
+		name.push(format!(".{n}"));
+		return PathBuf::from(name);
+	};
+	if kept == 0
+	{
+		return fs::remove_file(path);
+	}
+	let _ = fs::remove_file(numbered(kept));
+	for n in (1..kept).rev()
+	{
+		let older = numbered(n);
+		if older.exists()
+		{
+			fs::rename(&older, numbered(n + 1))?;
+		}
+	}
+	return fs::rename(path, numbered(1));
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use log::Level;
+
+	#[test]
+	fn log_rotated_when_full()
+	{
+		let dir = std::env::temp_dir().join(
+			format!("shellcaster-test-{}-logger", std::process::id())
+		);
+		let _ = fs::remove_dir_all(&dir);
+		let path = dir.join(DEBUG_LOG_NAME);
+		let logger = Logger::new(path.clone(), LevelFilter::Info, 60, false);
+		for n in 0..(DEBUG_LOGS_KEPT + 2)
+		{
+			logger.log(&Record::builder()
+				.level(Level::Info)
+				.target(concat!(env!("CARGO_CRATE_NAME"), "::feeds"))
+				.args(format_args!("Retrieved feed {n}, {}", "x".repeat(20)))
+				.build());
+		}
+		// records below the level or from other crates are left out
+		logger.log(&Record::builder()
+			.level(Level::Debug)
+			.target(env!("CARGO_CRATE_NAME"))
+			.args(format_args!("left out"))
+			.build());
+		logger.log(&Record::builder()
+			.level(Level::Error)
+			.target("ureq::unit")
+			.args(format_args!("left out"))
+			.build());
+
+		let current = fs::read_to_string(&path).unwrap();
+		assert!(current.ends_with(" INFO feeds: Retrieved feed 4, xxxxxxxxxxxxxxxxxxxx\n"));
+		assert_eq!(current.lines().count(), 1);
+		for n in 1..=DEBUG_LOGS_KEPT
+		{
+			let older = fs::read_to_string(dir.join(format!("{DEBUG_LOG_NAME}.{n}"))).unwrap();
+			assert!(older.contains(&format!("Retrieved feed {}", 4 - n)));
+		}
+		assert!(!dir.join(format!("{DEBUG_LOG_NAME}.{}", DEBUG_LOGS_KEPT + 1)).exists());
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
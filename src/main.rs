@@ -1,33 +1,59 @@
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process;
 use std::sync::mpsc;
 
 use anyhow::{anyhow, Context, Result};
-use clap::{Arg, Command};
+use chrono::Utc;
+use clap::{value_parser, Arg, Command, ValueHint};
+use clap_complete::Shell;
+use serde::Serialize;
 
+mod cleanup;
+mod clipboard;
 mod config;
 mod db;
 mod downloads;
 mod feeds;
+mod gpodder;
 mod keymap;
+mod list;
+mod logger;
 mod main_controller;
+#[cfg(feature = "mpris")]
+mod mpris;
 mod opml;
 mod play_file;
+mod stats;
 mod threadpool;
 mod types;
 mod ui;
 
 use crate::config::Config;
 use crate::db::Database;
-use crate::feeds::{FeedMsg, PodcastFeed};
+use crate::stats::format_size;
+use crate::feeds::{FeedMsg, HostThrottle, PodcastFeed};
+use crate::gpodder::{GpodderClient, SubscriptionChanges};
+use crate::list::ListFilter;
 use crate::main_controller::{MainController, MainMessage};
-use crate::threadpool::Threadpool;
+use crate::stats::LibraryStats;
+use crate::threadpool::{Priority, Threadpool};
 use crate::types::*;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Exit code of `shellcaster sync` when some of the feeds could not be
+/// synced, which is often a passing network problem
+const EXIT_SYNC_FAILED: i32 = 1;
+
+/// Exit code of `shellcaster sync` when the database could not be
+/// locked, opened, read or written to, so that some or all of the
+/// feeds were not saved. This is not 2, which clap uses for invalid
+/// arguments.
+const EXIT_DATABASE_ERROR: i32 = 3;
+
 /// Main controller for shellcaster program.
 ///
 /// *Main command:*
@@ -45,22 +71,178 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// Connects to the sqlite database, then initiates a full sync of all
 /// podcasts. No UI is created for this, as the intention is to be used
 /// in a programmatic way (e.g., setting up a cron job to sync
-/// regularly.)
+/// regularly.) The results can be printed as JSON, and the exit code
+/// tells failed feeds apart from a database that could not be opened.
 ///
 /// *Import subcommand:*
 /// Reads in an OPML file and adds feeds to the database that do not
-/// already exist. If the `-r` option is used, the database is wiped
-/// first.
+/// already exist, or merges a JSON export of the library (including
+/// episode state) into the database. If the `-r` option is used, the
+/// podcasts that are not in the file are removed, and the episode
+/// state in a JSON export replaces the state in the database.
 ///
 /// *Export subcommand:*
-/// Connects to the sqlite database, and reads all podcasts into an OPML
-/// file, with the location specified from the command line arguments.
+/// Connects to the sqlite database, and reads all podcasts (or only
+/// those with the given tags or IDs) into an OPML file, or the whole
+/// library into a JSON file, with the location specified from the
+/// command line arguments.
+///
+/// *Backup subcommand:*
+/// Copies the sqlite database to a file, even while another instance of
+/// shellcaster is running.
+///
+/// *Restore subcommand:*
+/// Replaces the sqlite database with a backup, after checking that the
+/// backup is a shellcaster database. Refuses to run while the UI is open.
+///
+/// *Stats subcommand:*
+/// Connects to the sqlite database, and prints statistics about the
+/// podcasts and episodes in it, as a table or as JSON.
+///
+/// *List subcommand:*
+/// Connects to the sqlite database, and prints the podcasts or episodes
+/// in it, one per line with tab-separated columns, or as JSON, for use
+/// in scripts.
+///
+/// *Cleanup subcommand:*
+/// Compares the download directory with the files recorded in the
+/// database, and lists files that nothing refers to and episodes whose
+/// file is gone. These are only deleted or cleared when asked to.
+///
+/// *Completions subcommand:*
+/// Prints a completion script for the given shell, generated from the
+/// same definition of the command line arguments that is used to parse
+/// them.
 fn main() -> Result<()>
 {
 	// SETUP -----------------------------------------------------------
 
 	// set up the possible command line arguments and subcommands
-	let args = Command::new(clap::crate_name!())
+	let args = build_cli().get_matches();
+
+	// completions are printed before anything is set up, so that they
+	// work without a config file or database
+	if let Some(("completions", sub_args)) = args.subcommand()
+	{
+		let shell = sub_args
+			.get_one::<Shell>("shell")
+			.copied()
+			.expect("Missing shell");
+		clap_complete::generate(shell, &mut build_cli(), env!("CARGO_BIN_NAME"), &mut io::stdout());
+		return Ok(());
+	}
+
+	// figure out where config file is located -- either specified from
+	// command line args, set via $SHELLCASTER_CONFIG, or using default
+	// config location for OS
+	let config_path = get_config_path(args.value_of("config"))
+		.unwrap_or_else(|| {
+			eprintln!(
+				"Could not identify your operating system's default directory to store configuration files. Please specify paths manually using config.toml and use `-c` or `--config` flag to specify where config.toml is located when launching the program."
+			);
+			process::exit(1);
+		});
+	let config = Config::new(&config_path)?;
+	let db_path = get_db_path(args.value_of("db"), &config, &config_path)?;
+
+	// the debug log is kept next to the database; it is only printed to
+	// stderr by `sync`, as the UI owns the terminal otherwise
+	let log_level = match args.value_of("log-level")
+	{
+		Some(level) => level.parse().expect("Invalid log level"),
+		None => config.log_level,
+	};
+	let log_path = db_path.with_file_name(logger::DEBUG_LOG_NAME);
+	logger::init(&log_path, log_level, matches!(args.subcommand(), Some(("sync", _))))?;
+
+	// keep other instances of shellcaster from using the database at
+	// the same time: the UI and `import --replace` need it to themselves,
+	// while syncing, importing and exporting can happen side by side
+	let lock_mode = match args.subcommand()
+	{
+		Some(("import", sub_args)) if sub_args.is_present("dry-run") => None,
+		Some(("import", sub_args)) => Some(sub_args.is_present("replace")),
+		// files that the UI is still downloading would look orphaned
+		Some(("cleanup", _)) => Some(true),
+		Some(("sync", _)) | Some(("export", _)) => Some(false),
+		Some(_) => None,
+		None => Some(true),
+	};
+	let _lock = match lock_mode
+	{
+		Some(exclusive) if !args.is_present("force") => match lock_database(&db_path, exclusive)
+		{
+			Ok(lock) => Some(lock),
+			Err(err) if matches!(args.subcommand(), Some(("sync", _))) => {
+				exit_with_error(err, EXIT_DATABASE_ERROR)
+			}
+			Err(err) => return Err(err),
+		},
+		_ => None,
+	};
+
+	return match args.subcommand()
+	{
+		// SYNC SUBCOMMAND ----------------------------------------------
+		Some(("sync", sub_args)) if sub_args.is_present("gpodder") => {
+			sync_gpodder(&db_path, config, sub_args)
+		}
+		Some(("sync", sub_args)) => sync_podcasts(&db_path, config, sub_args),
+
+		// IMPORT SUBCOMMAND --------------------------------------------
+		Some(("import", sub_args)) => import(&db_path, config, sub_args),
+
+		// EXPORT SUBCOMMAND --------------------------------------------
+		Some(("export", sub_args)) => export(&db_path, sub_args),
+
+		// BACKUP SUBCOMMAND --------------------------------------------
+		Some(("backup", sub_args)) => backup(&db_path, sub_args),
+
+		// RESTORE SUBCOMMAND -------------------------------------------
+		Some(("restore", sub_args)) => restore(&db_path, sub_args),
+
+		// STATS SUBCOMMAND ---------------------------------------------
+		Some(("stats", sub_args)) => stats(&db_path, sub_args),
+
+		// LIST SUBCOMMAND ----------------------------------------------
+		Some(("list", sub_args)) => list(&db_path, sub_args),
+
+		// CLEANUP SUBCOMMAND -------------------------------------------
+		Some(("cleanup", sub_args)) => cleanup(&db_path, &config, sub_args),
+
+		// MAIN COMMAND -------------------------------------------------
+		_ => {
+			install_panic_hook();
+			let mut main_ctrl = match MainController::new(config, &config_path, &db_path)
+			{
+				Ok(main_ctrl) => main_ctrl,
+				Err(err) => {
+					ui::restore_terminal();
+					return Err(err);
+				}
+			};
+
+			main_ctrl.loop_msgs(); // main loop
+
+			// the UI thread is already gone if it panicked
+			let _ = main_ctrl.tx_to_ui.send(MainMessage::UiTearDown);
+			// wait for UI thread to finish teardown
+			main_ctrl
+				.ui_thread
+				.join()
+				.map_err(|_| anyhow!("The user interface stopped unexpectedly"))?;
+			Ok(())
+		}
+	};
+}
+
+
+/// Sets up the command line arguments and subcommands. This is used
+/// both to parse the arguments and to generate shell completions, so
+/// that the two cannot drift apart.
+fn build_cli() -> Command<'static>
+{
+	return Command::new(clap::crate_name!())
 		.version(clap::crate_version!())
 		// .author(clap::crate_authors!(", "))
 		.author(
@@ -75,25 +257,79 @@ fn main() -> Result<()>
 			.global(true)
 			.takes_value(true)
 			.value_name("FILE")
+			.value_hint(ValueHint::FilePath)
 			.help(
 				"Sets a custom config file location. Can also be set with environment variable."
 			)
 		)
+		.arg(Arg::new("force")
+			.long("force")
+			.global(true)
+			.help(
+				"Uses the database even if another instance of shellcaster seems to be using it."
+			)
+		)
+		.arg(Arg::new("db")
+			.long("db")
+			.env("SHELLCASTER_DB")
+			.global(true)
+			.takes_value(true)
+			.value_name("FILE")
+			.value_hint(ValueHint::FilePath)
+			.help(
+				"Sets a custom database file location. Can also be set with environment variable."
+			)
+		)
+		.arg(Arg::new("log-level")
+			.long("log-level")
+			.global(true)
+			.takes_value(true)
+			.value_name("LEVEL")
+			.possible_values(["off", "error", "warn", "info", "debug", "trace"])
+			.help(
+				"Sets how much is written to debug.log, next to the database, overriding the config file. `sync` also prints these messages."
+			)
+		)
 		.subcommand(Command::new("sync")
 			.about("Syncs all podcasts in database")
 			.arg(Arg::new("quiet")
 				.short('q')
 				.long("quiet")
-				.help("Suppresses output messages to stdout.")))
+				.help("Suppresses output messages to stdout."))
+			.arg(Arg::new("json")
+				.long("json")
+				.help(
+					"Prints the result for each feed as a JSON object on a line of its own, followed by a summary. Other messages are printed to stderr."
+				)
+			)
+			.arg(Arg::new("gpodder")
+				.long("gpodder")
+				.conflicts_with("json")
+				.help(
+					"Syncs the list of subscriptions, played episodes and playback positions with gpodder.net (or the server in the [gpodder] section of config.toml) instead of syncing the podcasts."
+				)
+			)
+			.after_help(
+				"Exits with code 1 if some feeds could not be synced, and with code 3 if the database could not be opened."
+			))
 		.subcommand(Command::new("import")
-			.about("Imports podcasts from an OPML file")
+			.about("Imports podcasts from an OPML, JSON or text file")
 			.arg(Arg::new("file")
 				.short('f')
 				.long("file")
 				.takes_value(true)
 				.value_name("FILE")
+				.value_hint(ValueHint::FilePath)
 				.help(
-					"Specifies the filepath to the OPML file to be imported. If this flag is not set, the command will read from stdin."
+					"Specifies the filepath to the file to be imported. If this flag is not set, the command will read from stdin."
+				)
+			)
+			.arg(Arg::new("format")
+				.long("format")
+				.takes_value(true)
+				.possible_values(["opml", "json", "text"])
+				.help(
+					"Specifies the format of the file to be imported. JSON files created by `shellcaster export --format json` also include the episodes and their state; text files have one feed URL per line. If this flag is not set, the format is detected from the file."
 				)
 			)
 			.arg(Arg::new("replace")
@@ -101,7 +337,19 @@ fn main() -> Result<()>
 				.long("replace")
 				.takes_value(false)
 				.help(
-					"If set, the contents of the OPML file will replace all existing data in the shellcaster database."
+					"Replaces the list of subscriptions with the feeds in the file: podcasts that are not in it are removed, along with their episodes (downloaded files are left on disk). Podcasts that are in both keep their episodes; with a JSON file, the played status, playback positions, archived status, tags and downloaded files in the file replace those in the database. Use --dry-run to see which podcasts would be removed."
+				)
+			)
+			.arg(Arg::new("dry-run")
+				.long("dry-run")
+				.help(
+					"Prints which feeds in an OPML or text file would be added, which are already subscribed to, and which would be skipped, without fetching anything or changing the database."
+				)
+			)
+			.arg(Arg::new("lazy")
+				.long("lazy")
+				.help(
+					"Adds the feeds in an OPML or text file without fetching them, using the titles in the OPML file until they are synced. Works offline."
 				)
 			)
 			.arg(Arg::new("quiet")
@@ -109,66 +357,147 @@ fn main() -> Result<()>
 				.long("quiet")
 				.help("Suppresses output messages to stdout.")))
 		.subcommand(Command::new("export")
-			.about("Exports podcasts to an OPML file")
+			.about("Exports podcasts to an OPML or JSON file")
 			.arg(Arg::new("file")
 				.short('f')
 				.long("file")
 				.takes_value(true)
 				.value_name("FILE")
+				.value_hint(ValueHint::FilePath)
 				.help(
-					"Specifies the filepath for where the OPML file will be exported. If this flag is not set, the command will print to stdout."
+					"Specifies the filepath for where the file will be exported. If this flag is not set, the command will print to stdout."
+				)
+			)
+			.arg(Arg::new("format")
+				.long("format")
+				.takes_value(true)
+				.possible_values(["opml", "json"])
+				.default_value("opml")
+				.help(
+					"Specifies the format of the exported file. JSON also includes the episodes and their state (played, downloaded, playback position)."
+				)
+			)
+			.arg(Arg::new("tag")
+				.long("tag")
+				.takes_value(true)
+				.multiple_occurrences(true)
+				.value_name("TAG")
+				.help(
+					"Only exports the podcasts with this tag. Can be given more than once, along with --podcast."
+				)
+			)
+			.arg(Arg::new("podcast")
+				.short('p')
+				.long("podcast")
+				.takes_value(true)
+				.multiple_occurrences(true)
+				.value_name("PODCAST")
+				.help(
+					"Only exports the podcast with this ID or title. Can be given more than once, along with --tag."
+				)
+			)
+			.arg(Arg::new("grouped")
+				.long("grouped")
+				.help(
+					"Puts the podcasts in folders by tag in the OPML file, with a tag like \"Tech/News\" making a folder within a folder."
 				)
 			)
 		)
-		.get_matches();
-
-	// figure out where config file is located -- either specified from
-	// command line args, set via $SHELLCASTER_CONFIG, or using default
-	// config location for OS
-	let config_path = get_config_path(args.value_of("config"))
-		.unwrap_or_else(|| {
-			eprintln!(
-				"Could not identify your operating system's default directory to store configuration files. Please specify paths manually using config.toml and use `-c` or `--config` flag to specify where config.toml is located when launching the program."
-			);
-			process::exit(1);
-		});
-	let config = Config::new(&config_path)?;
-
-	let mut db_path = config_path;
-	if !db_path.pop()
-	{
-		return Err(anyhow!(
-			"Could not correctly parse the config file location. Please specify a valid path to the config file."
-		));
-	}
-
-
-	return match args.subcommand()
-	{
-		// SYNC SUBCOMMAND ----------------------------------------------
-		Some(("sync", sub_args)) => sync_podcasts(&db_path, config, sub_args),
-
-		// IMPORT SUBCOMMAND --------------------------------------------
-		Some(("import", sub_args)) => import(&db_path, config, sub_args),
-
-		// EXPORT SUBCOMMAND --------------------------------------------
-		Some(("export", sub_args)) => export(&db_path, sub_args),
-
-		// MAIN COMMAND -------------------------------------------------
-		_ => {
-			let mut main_ctrl = MainController::new(config, &db_path)?;
-
-			main_ctrl.loop_msgs(); // main loop
-
-			main_ctrl.tx_to_ui.send(MainMessage::UiTearDown).unwrap();
-			// wait for UI thread to finish teardown
-			main_ctrl.ui_thread.join().unwrap();
-			Ok(())
-		}
-	};
+		.subcommand(Command::new("backup")
+			.about("Backs up the database to a file")
+			.arg(Arg::new("file")
+				.short('f')
+				.long("file")
+				.takes_value(true)
+				.required(true)
+				.value_name("FILE")
+				.value_hint(ValueHint::FilePath)
+				.help("Specifies the filepath where the backup will be saved.")
+			)
+			.arg(Arg::new("quiet")
+				.short('q')
+				.long("quiet")
+				.help("Suppresses output messages to stdout.")))
+		.subcommand(Command::new("restore")
+			.about("Replaces the database with a backup")
+			.arg(Arg::new("file")
+				.short('f')
+				.long("file")
+				.takes_value(true)
+				.required(true)
+				.value_name("FILE")
+				.value_hint(ValueHint::FilePath)
+				.help("Specifies the filepath to the backup to be restored.")
+			)
+			.arg(Arg::new("quiet")
+				.short('q')
+				.long("quiet")
+				.help("Suppresses output messages to stdout.")))
+		.subcommand(Command::new("stats")
+			.about("Prints statistics about podcasts in database")
+			.arg(Arg::new("json")
+				.long("json")
+				.help("Prints statistics as JSON.")))
+		.subcommand(Command::new("list")
+			.about("Lists podcasts or episodes in database, for use in scripts")
+			.arg(Arg::new("what")
+				.required(true)
+				.possible_values(["podcasts", "episodes"])
+				.help(
+					"What to list. Each is printed on a line of its own, with tab-separated columns: the ID, title, date (last checked for podcasts, published for episodes), URL and, for episodes, the path to the downloaded file."
+				)
+			)
+			.arg(Arg::new("podcast")
+				.short('p')
+				.long("podcast")
+				.takes_value(true)
+				.value_name("PODCAST")
+				.help("Only lists the podcast with this title or ID, or its episodes.")
+			)
+			.arg(Arg::new("unplayed")
+				.long("unplayed")
+				.help("Only lists unplayed episodes, or podcasts that have any.")
+			)
+			.arg(Arg::new("downloaded")
+				.long("downloaded")
+				.help("Only lists downloaded episodes, or podcasts that have any.")
+			)
+			.arg(Arg::new("since")
+				.long("since")
+				.takes_value(true)
+				.value_name("DATE")
+				.help(
+					"Only lists episodes published on or after this date (as YYYY-MM-DD), or podcasts that have any."
+				)
+			)
+			.arg(Arg::new("json")
+				.long("json")
+				.help("Prints the list as JSON, with all of the details of each podcast or episode.")))
+		.subcommand(Command::new("cleanup")
+			.about("Finds downloaded files that are missing from the database, and the reverse")
+			.arg(Arg::new("delete-orphans")
+				.long("delete-orphans")
+				.help("Deletes files in the download directory that no episode or podcast refers to.")
+			)
+			.arg(Arg::new("clear-missing")
+				.long("clear-missing")
+				.help("Marks episodes whose downloaded file no longer exists as not downloaded.")
+			)
+			.arg(Arg::new("dry-run")
+				.long("dry-run")
+				.help("Prints what --delete-orphans and --clear-missing would do, without doing it.")
+			)
+			.after_help(
+				"Without --delete-orphans or --clear-missing, nothing is changed; the differences are only listed."
+			))
+		.subcommand(Command::new("completions")
+			.about("Prints a shell completion script to stdout")
+			.arg(Arg::new("shell")
+				.required(true)
+				.value_parser(value_parser!(Shell))
+				.help("Shell to print the completion script for.")));
 }
 
-
 /// Gets the path to the config file if one is specified in the command-
 /// line arguments, or else returns the default config path for the
 /// user's operating system.
@@ -197,101 +526,510 @@ fn get_config_path(config: Option<&str>) -> Option<PathBuf>
 	};
 }
 
+/// Makes sure that a panic anywhere in the program first restores the
+/// terminal, so that the panic message and backtrace are printed to the
+/// normal screen instead of being lost along with the alternate screen.
+fn install_panic_hook()
+{
+	let default_hook = std::panic::take_hook();
+	std::panic::set_hook(Box::new(move |info| {
+		// the threadpool catches the panics of its jobs and reports them
+		// itself, so the UI keeps running
+		if threadpool::is_worker_thread()
+		{
+			return;
+		}
+		ui::restore_terminal();
+		default_hook(info);
+	}));
+}
+
+/// Reports the panics of jobs in the threadpool as messages, so that the
+/// loops waiting for the results of the jobs count them as failed,
+/// rather than waiting for them forever.
+fn report_panics(threadpool: &Threadpool, tx_to_main: &mpsc::Sender<Message>)
+{
+	let tx_to_main = tx_to_main.clone();
+	threadpool.set_panic_handler(move |message| {
+		let _ = tx_to_main.send(Message::TaskFailed(message));
+	});
+}
+
+/// Gets the path to the database file: the one specified in the command-
+/// line arguments (or $SHELLCASTER_DB), else the one set in config.toml,
+/// else the default data directory for the user's operating system.
+///
+/// Older versions stored the database next to config.toml; when using
+/// the default location, a database found there is moved over.
+fn get_db_path(db: Option<&str>, config: &Config, config_path: &Path) -> Result<PathBuf>
+{
+	if let Some(path) = db
+	{
+		return Ok(PathBuf::from(path));
+	}
+	if let Some(path) = &config.db_path
+	{
+		return Ok(path.clone());
+	}
+
+	let mut db_path = dirs::data_dir().ok_or_else(|| anyhow!(
+		"Could not identify your operating system's default directory to store data files. Please specify the database location with `db_path` in config.toml or the `--db` flag."
+	))?;
+	db_path.push("shellcaster");
+	db_path.push("data.db");
+
+	let old_path = match config_path.parent()
+	{
+		Some(dir) => dir.join("data.db"),
+		None => return Ok(db_path),
+	};
+	if !db_path.exists() && old_path.is_file() && old_path != db_path
+	{
+		move_database(&old_path, &db_path)?;
+		// printed to stderr, so as not to mix with an export to stdout
+		eprintln!(
+			"Moved the database from {} to {}.",
+			old_path.to_string_lossy(),
+			db_path.to_string_lossy()
+		);
+	}
+	return Ok(db_path);
+}
+
+/// Takes the lock on the database (see Database::lock), explaining how
+/// to get around it if another instance of shellcaster is holding it.
+fn lock_database(db_path: &Path, exclusive: bool) -> Result<File>
+{
+	return Database::lock(db_path, exclusive)?.ok_or_else(|| {
+		anyhow!(
+			"Another instance of shellcaster is using the database at {}. Please wait for it to finish or close it first. If no other instance is running, use --force to continue anyway.",
+			db_path.to_string_lossy()
+		)
+	});
+}
+
+/// Moves a database file, along with its write-ahead log if there is
+/// one. Falls back to copying the files if they cannot be renamed (e.g.,
+/// across file systems), in which case the old files are left in place.
+fn move_database(from: &Path, to: &Path) -> Result<()>
+{
+	if let Some(dir) = to.parent()
+	{
+		std::fs::create_dir_all(dir)
+			.with_context(|| "Unable to create subdirectory for database.")?;
+	}
+	for suffix in ["", "-wal", "-shm"]
+	{
+		let source = PathBuf::from(format!("{}{suffix}", from.to_string_lossy()));
+		let dest = PathBuf::from(format!("{}{suffix}", to.to_string_lossy()));
+		if !source.is_file()
+		{
+			continue;
+		}
+		if std::fs::rename(&source, &dest).is_err()
+		{
+			std::fs::copy(&source, &dest).with_context(|| format!(
+				"Could not move the database from {} to {}",
+				source.to_string_lossy(),
+				dest.to_string_lossy()
+			))?;
+		}
+	}
+	return Ok(());
+}
+
+
+/// Result of syncing a feed, as printed by `shellcaster sync --json`.
+/// `status` is "synced", "failed", or "skipped" for inactive podcasts.
+#[derive(Debug, Serialize)]
+struct FeedReport
+{
+	#[serde(rename = "type")]
+	kind: &'static str,
+	id: Option<i64>,
+	url: String,
+	title: String,
+	status: &'static str,
+	new_episodes: usize,
+	updated_episodes: usize,
+	error: Option<String>,
+}
+
+impl FeedReport
+{
+	/// Creates the report of a feed that has not been synced yet.
+	fn new(id: Option<i64>, url: String, title: String, status: &'static str) -> Self
+	{
+		return Self {
+			kind: "feed",
+			id: id,
+			url: url,
+			title: title,
+			status: status,
+			new_episodes: 0,
+			updated_episodes: 0,
+			error: None,
+		};
+	}
+}
+
+/// Totals printed at the end of `shellcaster sync --json`.
+#[derive(Debug, Default, Serialize)]
+struct SyncSummary
+{
+	#[serde(rename = "type")]
+	kind: &'static str,
+	synced: usize,
+	failed: usize,
+	skipped: usize,
+	new_episodes: usize,
+}
+
+/// Prints an error the same way as returning it from `main()` would,
+/// but exits with the given code.
+fn exit_with_error(err: anyhow::Error, code: i32) -> !
+{
+	eprintln!("Error: {err:?}");
+	process::exit(code);
+}
 
 /// Synchronizes RSS feed data for all podcasts, without setting up a UI.
+/// With `--json`, the result for each feed and a summary are printed to
+/// stdout as JSON objects, one per line, and the other messages go to
+/// stderr. Exits with `EXIT_DATABASE_ERROR` if the database cannot be
+/// read, or once done if it could not be written to, or else with
+/// `EXIT_SYNC_FAILED` once done if any feed failed.
 fn sync_podcasts(
 	db_path: &Path,
 	config: Config,
 	args: &clap::ArgMatches
 ) -> Result<()>
 {
-	let db_inst = Database::connect(db_path)?;
-	let podcast_list = db_inst.get_podcasts()?;
-
-	if podcast_list.is_empty()
+	let connected = Database::connect(db_path).and_then(|db_inst| {
+		let podcasts = db_inst.get_podcasts()?;
+		return Ok((db_inst, podcasts));
+	});
+	let (db_inst, podcasts) = match connected
 	{
-		if !args.is_present("quiet")
+		Ok(connected) => connected,
+		Err(err) => exit_with_error(err, EXIT_DATABASE_ERROR),
+	};
+	let (inactive, podcast_list): (Vec<Podcast>, Vec<Podcast>) =
+		podcasts.into_iter().partition(|pod| pod.inactive);
+
+	let json = args.is_present("json");
+	let quiet = args.is_present("quiet");
+	// stdout is kept for the JSON, if it is asked for
+	let say = |message: String| {
+		if quiet
 		{
-			println!("No podcasts to sync.");
+			return;
 		}
-		return Ok(());
-	}
+		if json
+		{
+			eprintln!("{message}");
+		}
+		else
+		{
+			println!("{message}");
+		}
+	};
+	let report = |feed: &FeedReport| {
+		if json
+		{
+			println!("{}", serde_json::to_string(feed).expect("Could not convert to JSON"));
+		}
+	};
 
-	let threadpool = Threadpool::new(config.simultaneous_downloads);
-	let (tx_to_main, rx_to_main) = mpsc::channel();
+	// problems are printed by the logger, at the level that was asked
+	// for, and also appended to the log file, if there is one, so they
+	// are not lost when syncing in the background
+	let log_file = |message: &str| {
+		if let Some(path) = &config.log_file
+		{
+			let _ = LogEntry::new(message.to_string(), true).append_to(path);
+		}
+	};
+	let log_error = |message: String| {
+		log::error!("{message}");
+		log_file(&message);
+	};
+	let mut summary = SyncSummary {
+		kind: "summary",
+		..Default::default()
+	};
+	// errors from the database are told apart from those of the feeds
+	let mut db_failed = false;
 
-	for pod in podcast_list.iter()
+	// inactive podcasts are skipped, along with the reason they failed
+	for pod in inactive.iter()
 	{
-		let feed = PodcastFeed::new(
-			Some(pod.id),
-			pod.url.clone(),
-			Some(pod.title.clone())
-		);
-		feeds::check_feed(
-			feed,
-			config.max_retries,
-			&threadpool,
-			tx_to_main.clone()
-		);
+		let message = match &pod.last_error
+		{
+			Some(err) => format!("Skipping inactive podcast {}: {}", pod.title, err),
+			None => format!("Skipping inactive podcast {}", pod.title),
+		};
+		log::warn!("{message} ({})", pod.url);
+		log_file(&message);
+		let mut skipped =
+			FeedReport::new(Some(pod.id), pod.url.clone(), pod.title.clone(), "skipped");
+		skipped.error = pod.last_error.clone();
+		report(&skipped);
+		summary.skipped += 1;
 	}
 
-	let mut msg_counter: usize = 0;
-	let mut failure = false;
-	while let Some(message) = rx_to_main.iter().next()
+	if podcast_list.is_empty()
 	{
-		match message
+		say("No podcasts to sync.".to_string());
+	}
+	else
+	{
+		let threadpool = Threadpool::new(config.sync_threads);
+		let host_throttle = HostThrottle::new(config.same_host_delay);
+		let (tx_to_main, rx_to_main) = mpsc::channel();
+		report_panics(&threadpool, &tx_to_main);
+
+		let feed_list = podcast_list
+			.iter()
+			.map(|pod| PodcastFeed::new(
+				Some(pod.id),
+				pod.url.clone(),
+				Some(pod.title.clone())
+			))
+			.collect();
+		for feed in feeds::interleave_by_host(feed_list).into_iter()
 		{
-			Message::Feed(FeedMsg::SyncData((pod_id, pod))) => {
-				let title = pod.title.clone();
-				let db_result = db_inst.update_podcast(pod_id, pod);
-				match db_result
-				{
-					Ok(_) => {
-						if !args.is_present("quiet")
-						{
-							println!("Synced {title}");
+			feeds::check_feed(
+				feed,
+				config.max_retries,
+				false,
+				host_throttle.clone(),
+				Priority::Low,
+				&threadpool,
+				tx_to_main.clone()
+			);
+		}
+
+		let mut msg_counter: usize = 0;
+		while let Some(message) = rx_to_main.iter().next()
+		{
+			match message
+			{
+				Message::Feed(FeedMsg::SyncData((pod_id, pod))) => {
+					let title = pod.title.clone();
+					let mut synced =
+						FeedReport::new(Some(pod_id), pod.url.clone(), title.clone(), "synced");
+					let db_result = db_inst.update_podcast(
+						pod_id, pod, config.max_episodes_per_feed, &[]
+					);
+					match db_result
+					{
+						Ok(result) => {
+							let added = result.added.len();
+							summary.synced += 1;
+							summary.new_episodes += added;
+							synced.new_episodes = added;
+							synced.updated_episodes = result.updated.len();
+							match result.updated.len()
+							{
+								0 => say(format!("Synced {title} ({added} new)")),
+								updated => say(format!(
+									"Synced {title} ({added} new, {updated} updated)"
+								)),
+							}
+						}
+						Err(err) => {
+							summary.failed += 1;
+							db_failed = true;
+							synced.status = "failed";
+							synced.error = Some(err.to_string());
+							log_error(format!("Error synchronizing {title}: {err}"));
 						}
 					}
-					Err(_err) => {
-						failure = true;
-						eprintln!("Error synchronizing {title}");
+					report(&synced);
+				}
+
+				Message::Feed(FeedMsg::Error(feed, err)) => {
+					summary.failed += 1;
+					let title = feed.title.unwrap_or_else(|| feed.url.clone());
+					// the reason was already logged along with the URL
+					log_file(&format!("Error retrieving RSS feed for {}: {}", title, err.message));
+					let mut failed = FeedReport::new(feed.id, feed.url, title.clone(), "failed");
+					failed.error = Some(err.message.clone());
+					report(&failed);
+					if let Some(pod_id) = feed.id
+					{
+						// the other feeds are still synced if this cannot
+						// be recorded
+						match db_inst.record_sync_failure(
+							pod_id, &err.message, err.gone, config.max_sync_failures
+						)
+						{
+							Ok(true) => log_error(format!("{title} has been marked as inactive.")),
+							Ok(false) => (),
+							Err(err) => {
+								db_failed = true;
+								log_error(format!("Could not record the failure of {title}: {err}"));
+							}
+						}
 					}
 				}
+
+				// the panic was already logged by the threadpool
+				Message::TaskFailed(_) => summary.failed += 1,
+				_ => (),
 			}
 
-			Message::Feed(FeedMsg::Error(feed)) => {
-				failure = true;
-				match feed.title
-				{
-					Some(t) => eprintln!("Error retrieving RSS feed for {}.", t),
-					None => eprintln!("Error retrieving RSS feed."),
-				}
-			}
-			_ => (),
-		}
+			msg_counter += 1;
+			if msg_counter >= podcast_list.len()
+			{
+				break;
+			}
+		}
+	}
+
+	if json
+	{
+		println!("{}", serde_json::to_string(&summary).expect("Could not convert to JSON"));
+	}
+	if db_failed
+	{
+		exit_with_error(
+			anyhow!("Could not save some of the feeds to the database."),
+			EXIT_DATABASE_ERROR
+		);
+	}
+	else if summary.failed > 0
+	{
+		exit_with_error(anyhow!("Process finished with errors."), EXIT_SYNC_FAILED);
+	}
+	else if !podcast_list.is_empty()
+	{
+		say(format!("Sync successful: {} new episodes.", summary.new_episodes));
+	}
+	return Ok(());
+}
+
+
+/// Syncs the list of subscriptions with gpodder.net: feeds subscribed to
+/// on other devices since the last sync are added, those unsubscribed
+/// from are removed, and the list of subscriptions here is uploaded.
+/// Podcasts with downloaded episodes are kept, with a warning, so that
+/// the files are not lost; they can be removed in the UI. Then episode
+/// actions are exchanged, so that played episodes and playback
+/// positions are shared with other devices.
+fn sync_gpodder(db_path: &Path, config: Config, args: &clap::ArgMatches) -> Result<()>
+{
+	let gpodder_config = config.gpodder.clone().ok_or_else(|| {
+		anyhow!("Syncing with gpodder.net is not set up. Add a [gpodder] section with your username to config.toml.")
+	})?;
+	let server = gpodder_config.server.clone();
+	let device = gpodder_config.device.clone();
+	let quiet = args.is_present("quiet");
+	let client = GpodderClient::new(gpodder_config)?;
+	let account = client.account();
+	let db_inst = Database::connect(db_path)?;
+	let podcasts = db_inst.get_podcasts()?;
+	let local: Vec<String> = podcasts.iter().map(|pod| pod.url.clone()).collect();
+	let remote = client.subscriptions()?;
+	let changes = SubscriptionChanges::compare(
+		&db_inst.get_gpodder_subscriptions(&account)?,
+		&local,
+		&remote,
+	);
 
-		msg_counter += 1;
-		if msg_counter >= podcast_list.len()
+	let mut skipped = Vec::new();
+	let mut removed: usize = 0;
+	for pod in podcasts.iter().filter(|pod| changes.remove.contains(&pod.url))
+	{
+		if pod.num_downloaded() > 0
 		{
-			break;
+			log::warn!(
+				"{} was unsubscribed from on {server}, but was kept as it has downloaded episodes",
+				pod.title
+			);
+			skipped.push(pod.url.clone());
+			continue;
+		}
+		db_inst.set_removed(pod.id, Some(Utc::now()), false)?;
+		removed += 1;
+		if !quiet
+		{
+			println!("Removed {}", pod.title);
 		}
 	}
+	let new_feeds = changes
+		.add
+		.iter()
+		.map(|url| PodcastFeed::new(None, url.clone(), None))
+		.collect();
+	let (added, failed) = add_feeds(&db_inst, &config, new_feeds, quiet)?;
 
-	if failure
+	client.upload(&changes.upload_list())?;
+	db_inst.set_gpodder_subscriptions(&account, &changes.synced_list(&skipped))?;
+	if !quiet
 	{
-		return Err(anyhow!("Process finished with errors."));
+		println!(
+			"Synced subscriptions with {server}: {added} added, {removed} removed, {} kept, {failed} failed.",
+			skipped.len()
+		);
+	}
+
+	let (since, uploaded_until) = db_inst.get_episode_sync(&account)?;
+	// changes made during the current second are left for the next sync,
+	// so that none of them are missed
+	let until = Utc::now() - chrono::Duration::seconds(1);
+	let upload = gpodder::local_actions(
+		&db_inst.get_episode_changes(uploaded_until, until)?,
+		uploaded_until,
+	);
+	let exchange = client.exchange_actions(since, &upload, until);
+	let mut errors = failed > 0;
+	let mut updated = 0;
+	match exchange.remote
+	{
+		Ok(remote) => {
+			let podcasts: Vec<(i64, String)> = db_inst
+				.get_podcasts()?
+				.into_iter()
+				.map(|pod| (pod.id, pod.url))
+				.collect();
+			let updates = gpodder::remote_updates(&remote.actions, &device, &podcasts);
+			updated = db_inst.apply_episode_updates(&account, &updates, remote.timestamp)?.len();
+		}
+		Err(err) => {
+			log::error!("Could not retrieve episode actions: {err}");
+			errors = true;
+		}
+	}
+	let uploaded = match exchange.uploaded
+	{
+		Ok(uploaded) => {
+			db_inst.set_episodes_uploaded(&account, exchange.until)?;
+			uploaded
+		}
+		Err(err) => {
+			log::error!("Could not upload episode actions: {err}");
+			errors = true;
+			0
+		}
+	};
+	if !quiet
+	{
+		println!("Synced episodes with {server}: {updated} updated, {uploaded} actions uploaded.");
 	}
-	else if !args.is_present("quiet")
+	if errors
 	{
-		println!("Sync successful.");
+		return Err(anyhow!("Process finished with errors."));
 	}
 	return Ok(());
 }
 
 
-/// Imports a list of podcasts from OPML format, either reading from a
-/// file or from stdin. If the `replace` flag is set, this replaces all
-/// existing data in the database.
+/// Imports a list of podcasts from OPML, JSON or text format (one feed
+/// URL per line), either reading from a file or from stdin. If the
+/// `replace` flag is set, the podcasts that are not in the file are
+/// removed first.
 fn import(
 	db_path: &Path,
 	config: Config,
@@ -304,12 +1042,12 @@ fn import(
 		Some(filepath) => {
 			let mut f = File::open(filepath)
 				.with_context(|| format!(
-					"Could not open OPML file: {filepath}"
+					"Could not open import file: {filepath}"
 				))?;
 			let mut contents = String::new();
 			f.read_to_string(&mut contents)
 				.with_context(|| format!(
-					"Failed to read from OPML file: {filepath}"
+					"Failed to read from import file: {filepath}"
 				))?;
 			contents
 		}
@@ -317,16 +1055,55 @@ fn import(
 			let mut contents = String::new();
 			std::io::stdin()
 				.read_to_string(&mut contents)
-				.with_context(|| "Failed to read OPML file from stdin")?;
+				.with_context(|| "Failed to read import file from stdin")?;
 			contents
 		}
 	};
 
-	let mut podcast_list = opml::import(xml).with_context(|| {
-		"Could not properly parse OPML file -- file may be formatted improperly or corrupted."
-	})?;
+	let dry_run = args.is_present("dry-run");
+	let format = args.value_of("format").unwrap_or_else(|| detect_format(&xml));
+	if format == "json"
+	{
+		if dry_run || args.is_present("lazy")
+		{
+			return Err(anyhow!("--dry-run and --lazy do not work with JSON files."));
+		}
+		return import_json(db_path, &xml, args);
+	}
 
-	if podcast_list.is_empty()
+	let mut plan = if format == "text"
+	{
+		opml::import_text(&xml)
+	}
+	else
+	{
+		opml::import(xml).with_context(|| {
+			"Could not properly parse OPML file -- file may be formatted improperly or corrupted."
+		})?
+	};
+
+	if dry_run
+	{
+		let existing = Database::read_feeds(db_path)?;
+		let removed: Vec<String> = if args.is_present("replace")
+		{
+			let urls: Vec<&str> = plan.new.iter().map(|feed| feed.url.as_str()).collect();
+			existing
+				.iter()
+				.filter(|(_, url)| !is_listed(url, &urls))
+				.map(|(title, url)| format!("{title} ({url})"))
+				.collect()
+		}
+		else
+		{
+			Vec::new()
+		};
+		plan.filter_existing(&existing);
+		print_import_plan(&plan, &removed);
+		return Ok(());
+	}
+
+	if plan.new.is_empty() && plan.skipped.is_empty()
 	{
 		if !args.is_present("quiet")
 		{
@@ -337,32 +1114,26 @@ fn import(
 
 	let db_inst = Database::connect(db_path)?;
 
-	// delete database if we are replacing the data
 	if args.is_present("replace")
 	{
-		db_inst
-			.clear_db()
-			.with_context(|| "Error clearing database")?;
+		let urls: Vec<&str> = plan.new.iter().map(|feed| feed.url.as_str()).collect();
+		remove_unlisted(&db_inst, &urls, args.is_present("quiet"))?;
 	}
-	else
+	let existing: Vec<(String, String)> = db_inst
+		.get_podcasts()?
+		.into_iter()
+		.map(|pod| (pod.title, pod.url))
+		.collect();
+	plan.filter_existing(&existing);
+	if !args.is_present("quiet")
 	{
-		let old_podcasts = db_inst.get_podcasts()?;
-
-		// if URL is already in database, remove it from import
-		podcast_list = podcast_list
-			.into_iter()
-			.filter(|pod| {
-				for op in &old_podcasts
-				{
-					if pod.url == op.url
-					{
-						return false;
-					}
-				}
-				return true;
-			})
-			.collect();
+		for skipped in plan.skipped.iter()
+		{
+			eprintln!("Skipping {skipped}");
+		}
 	}
+	let skipped = plan.subscribed.len() + plan.skipped.len();
+	let podcast_list = plan.new;
 
 	// check again, now that we may have removed feeds after looking at
 	// the database
@@ -370,28 +1141,94 @@ fn import(
 	{
 		if !args.is_present("quiet")
 		{
-			println!("No podcasts to import.");
+			println!("No podcasts to import ({skipped} skipped).");
 		}
 		return Ok(());
 	}
 
+	// the feeds are only retrieved by the next sync, which fills in the
+	// podcasts
+	if args.is_present("lazy")
+	{
+		let mut added: usize = 0;
+		let mut failed: usize = 0;
+		for feed in podcast_list.iter()
+		{
+			let title = feed.title.clone().unwrap_or_else(|| feed.url.clone());
+			let inserted = db_inst
+				.insert_placeholder(&feed.url, &title)
+				.and_then(|pod_id| db_inst.set_tags(pod_id, &feed.tags));
+			match inserted
+			{
+				Ok(_) => {
+					added += 1;
+					if !args.is_present("quiet")
+					{
+						println!("Added {title}");
+					}
+				}
+				Err(err) => {
+					failed += 1;
+					eprintln!("Error adding {title}: {err}");
+				}
+			}
+		}
+		if added > 0 && !args.is_present("quiet")
+		{
+			println!("Run `shellcaster sync` or sync in the UI to retrieve the episodes.");
+		}
+		return finish_import(added, failed, skipped, args.is_present("quiet"));
+	}
+
 	println!("Importing {} podcasts...", podcast_list.len());
+	let (added, failed) = add_feeds(&db_inst, &config, podcast_list, args.is_present("quiet"))?;
+	return finish_import(added, failed, skipped, args.is_present("quiet"));
+}
+
+/// Retrieves a list of feeds and adds them to the database, printing
+/// each podcast that is added unless `quiet` is set. Tags set on the
+/// feeds are added once the podcasts are. Returns how many podcasts
+/// were added, and how many could not be.
+fn add_feeds(
+	db_inst: &Database,
+	config: &Config,
+	podcast_list: Vec<PodcastFeed>,
+	quiet: bool
+) -> Result<(usize, usize)>
+{
+	if podcast_list.is_empty()
+	{
+		return Ok((0, 0));
+	}
+	// the categories of the OPML file become tags once the podcasts
+	// have been added
+	let tags_by_url: HashMap<String, Vec<String>> = podcast_list
+		.iter()
+		.filter(|pod| !pod.tags.is_empty())
+		.map(|pod| (pod.url.clone(), pod.tags.clone()))
+		.collect();
 
-	let threadpool = Threadpool::new(config.simultaneous_downloads);
+	let threadpool = Threadpool::new(config.sync_threads);
+	let host_throttle = HostThrottle::new(config.same_host_delay);
 	let (tx_to_main, rx_to_main) = mpsc::channel();
+	report_panics(&threadpool, &tx_to_main);
 
-	for pod in podcast_list.iter()
+	for feed in feeds::interleave_by_host(podcast_list.clone()).into_iter()
 	{
 		feeds::check_feed(
-			pod.clone(),
+			feed,
 			config.max_retries,
+			config.fetch_full_archive,
+			host_throttle.clone(),
+			Priority::Low,
 			&threadpool,
 			tx_to_main.clone(),
 		);
 	}
 
 	let mut msg_counter: usize = 0;
-	let mut failure = false;
+	let mut added: usize = 0;
+	let mut failed: usize = 0;
 	while let Some(message) = rx_to_main.iter().next()
 	{
 		match message
@@ -402,29 +1239,44 @@ fn import(
 				match db_result
 				{
 					Ok(_) => {
-						if !args.is_present("quiet")
+						added += 1;
+						if !quiet
 						{
 							println!("Added {title}");
 						}
 					}
 					Err(_err) => {
-						failure = true;
+						failed += 1;
 						eprintln!("Error adding {title}");
 					}
 				}
 			}
 
-			Message::Feed(FeedMsg::Error(feed)) => {
-				failure = true;
+			Message::Feed(FeedMsg::Candidates(feed, urls)) => {
+				failed += 1;
+				eprintln!(
+					"Multiple feeds found at {}; import one of these instead: {}",
+					feed.url,
+					urls.join(", ")
+				);
+			}
+
+			Message::Feed(FeedMsg::Error(feed, err)) => {
+				failed += 1;
 				if let Some(t) = feed.title
 				{
-					eprintln!("Error retrieving RSS feed: {t}");
+					eprintln!("Error retrieving RSS feed for {t}: {}", err.message);
 				}
 				else
 				{
-					eprintln!("Error retrieving RSS feed");
+					eprintln!("Error retrieving RSS feed {}: {}", feed.url, err.message);
 				}
 			}
+
+			Message::TaskFailed(message) => {
+				failed += 1;
+				eprintln!("Error adding a podcast: {message}");
+			}
 			_ => (),
 		}
 
@@ -435,29 +1287,196 @@ fn import(
 		}
 	}
 
-	if failure
+	if !tags_by_url.is_empty()
+	{
+		for pod in db_inst.get_podcasts()?.iter()
+		{
+			if let Some(tags) = tags_by_url.get(&pod.url)
+			{
+				db_inst.set_tags(pod.id, tags)?;
+			}
+		}
+	}
+	return Ok((added, failed));
+}
+
+/// Prints how many podcasts an import added, failed to add and skipped,
+/// and fails if any could not be added.
+fn finish_import(added: usize, failed: usize, skipped: usize, quiet: bool) -> Result<()>
+{
+	if !quiet
+	{
+		println!("Import finished: {added} added, {failed} failed, {skipped} skipped.");
+	}
+	if failed > 0
 	{
 		return Err(anyhow!("Process finished with errors."));
 	}
-	else if !args.is_present("quiet")
+	return Ok(());
+}
+
+/// Guesses the format of a file to import from its first character:
+/// OPML files start with an XML tag or declaration, and JSON files with
+/// an object. Anything else is taken for a list of feed URLs.
+fn detect_format(contents: &str) -> &'static str
+{
+	return match contents.trim_start_matches('\u{feff}').trim_start().chars().next()
+	{
+		Some('<') => "opml",
+		Some('{') => "json",
+		_ => "text",
+	};
+}
+
+/// Checks whether a podcast's feed URL is among the `urls` of an
+/// imported file, including URLs that differ only slightly (see
+/// `feeds::normalize_url()`).
+fn is_listed(url: &str, urls: &[&str]) -> bool
+{
+	let normalized = feeds::normalize_url(url);
+	return urls.iter().any(|other| feeds::normalize_url(other) == normalized);
+}
+
+/// Removes the podcasts whose feeds are not among the `urls` of an
+/// imported file, for `import --replace`, printing each of them unless
+/// `quiet` is set. Their downloaded files are left on disk.
+fn remove_unlisted(db_inst: &Database, urls: &[&str], quiet: bool) -> Result<()>
+{
+	for pod in db_inst.get_podcasts()?.iter().filter(|pod| !is_listed(&pod.url, urls))
+	{
+		db_inst
+			.remove_podcast(pod.id)
+			.with_context(|| format!("Error removing {}", pod.title))?;
+		if !quiet
+		{
+			println!("Removed {}", pod.title);
+		}
+	}
+	return Ok(());
+}
+
+/// Prints what importing an OPML file would do: the feeds that would be
+/// added, those that are already subscribed to, and the entries that
+/// would be skipped, as well as the podcasts that would be `removed`
+/// with `replace`.
+fn print_import_plan(plan: &opml::ImportPlan, removed: &[String])
+{
+	let new: Vec<String> = plan
+		.new
+		.iter()
+		.map(|feed| match &feed.title
+		{
+			Some(title) => format!("{title} ({})", feed.url),
+			None => feed.url.clone(),
+		})
+		.collect();
+	let sections = [
+		("Would add", &new),
+		("Already subscribed", &plan.subscribed),
+		("Would skip", &plan.skipped),
+	];
+	for (heading, lines) in sections.iter()
+	{
+		println!("{heading} ({}):", lines.len());
+		for line in lines.iter()
+		{
+			println!("  {line}");
+		}
+	}
+	// podcasts are only removed with `replace`
+	if !removed.is_empty()
+	{
+		println!("Would remove ({}):", removed.len());
+		for line in removed.iter()
+		{
+			println!("  {line}");
+		}
+	}
+	println!("Dry run: nothing was fetched or changed.");
+}
+
+
+/// Imports the library from a JSON export, merging it with the
+/// existing data (see Database::merge_podcast). If the `replace` flag is
+/// set, the podcasts that are not in the export are removed first, and
+/// the state in the export overrides local state. No feeds are fetched,
+/// so this works offline.
+fn import_json(db_path: &Path, json: &str, args: &clap::ArgMatches) -> Result<()>
+{
+	let library: LibraryExport = serde_json::from_str(json).with_context(|| {
+		"Could not properly parse JSON file -- file may be formatted improperly or corrupted."
+	})?;
+	if library.version > LIBRARY_FORMAT_VERSION
+	{
+		return Err(anyhow!(
+			"The JSON file was created by a newer version of shellcaster (format version {}). Please upgrade shellcaster to import it.",
+			library.version
+		));
+	}
+
+	let db_inst = Database::connect(db_path)?;
+	if args.is_present("replace")
+	{
+		let urls: Vec<&str> = library.podcasts.iter().map(|pod| pod.url.as_str()).collect();
+		remove_unlisted(&db_inst, &urls, args.is_present("quiet"))?;
+	}
+
+	let mut changes = 0;
+	for podcast in library.podcasts.iter()
+	{
+		changes += db_inst
+			.merge_podcast(podcast, args.is_present("replace"))
+			.with_context(|| format!("Error importing {}", podcast.url))?;
+	}
+
+	if !args.is_present("quiet")
 	{
-		println!("Import successful.");
+		if changes == 0
+		{
+			println!("Nothing to import: the library is already up to date.");
+		}
+		else
+		{
+			println!("Import successful: {changes} podcasts and episodes added or updated.");
+		}
 	}
 	return Ok(());
 }
 
 
-/// Exports all podcasts to OPML format, either printing to stdout or
-/// exporting to a file.
+/// Exports all podcasts to OPML or JSON format, either printing to stdout
+/// or exporting to a file. With `--tag` or `--podcast`, only the podcasts
+/// that match are exported, and with `--grouped`, they are put in
+/// folders by tag.
 fn export(db_path: &Path, args: &clap::ArgMatches) -> Result<()> {
+	let json = args.value_of("format") == Some("json");
+	if json && args.is_present("grouped")
+	{
+		return Err(anyhow!("--grouped only works with OPML files."));
+	}
 	let db_inst = Database::connect(db_path)?;
-	let podcast_list = db_inst.get_podcasts()?;
-	let opml = opml::export(podcast_list);
+	let tags: Vec<&str> = args.values_of("tag").map(Iterator::collect).unwrap_or_default();
+	let podcasts: Vec<&str> = args.values_of("podcast").map(Iterator::collect).unwrap_or_default();
+	let podcast_list = opml::select_podcasts(db_inst.get_podcasts()?, &tags, &podcasts);
+	// an empty export is still a valid file, e.g., for a tag that has
+	// no podcasts yet
+	if podcast_list.is_empty() && !(tags.is_empty() && podcasts.is_empty())
+	{
+		eprintln!("Warning: no podcasts match the given tags or podcasts; the export is empty.");
+	}
 
-	let xml = opml
-		.to_string()
-		.map_err(|err| anyhow!(err))
-		.with_context(|| "Could not create OPML format")?;
+	let xml = if json
+	{
+		serde_json::to_string_pretty(&LibraryExport::new(&podcast_list))
+			.with_context(|| "Could not create JSON format")?
+	}
+	else
+	{
+		opml::export(podcast_list, args.is_present("grouped"))
+			.to_string()
+			.map_err(|err| anyhow!(err))
+			.with_context(|| "Could not create OPML format")?
+	};
 
 	match args.value_of("file")
 	{
@@ -469,7 +1488,7 @@ fn export(db_path: &Path, args: &clap::ArgMatches) -> Result<()> {
 				))?;
 			dst.write_all(xml.as_bytes())
 				.with_context(|| format!(
-					"Could not copy OPML data to output file: {file}"
+					"Could not copy data to output file: {file}"
 				))?;
 		}
 		// print to stdout
@@ -477,3 +1496,226 @@ fn export(db_path: &Path, args: &clap::ArgMatches) -> Result<()> {
 	}
 	return Ok(());
 }
+
+
+/// Backs up the database to the file given in the command line arguments.
+fn backup(db_path: &Path, args: &clap::ArgMatches) -> Result<()>
+{
+	let file = args.value_of("file").expect("Missing backup file");
+	let db_inst = Database::connect(db_path)?;
+	db_inst.backup(Path::new(file))?;
+	if !args.is_present("quiet")
+	{
+		println!("Backed up {} to {file}.", db_path.to_string_lossy());
+	}
+	return Ok(());
+}
+
+/// Replaces the database with the backup given in the command line
+/// arguments.
+fn restore(db_path: &Path, args: &clap::ArgMatches) -> Result<()>
+{
+	let file = args.value_of("file").expect("Missing backup file");
+	Database::restore(db_path, Path::new(file))?;
+	if !args.is_present("quiet")
+	{
+		println!("Restored {} from {file}.", db_path.to_string_lossy());
+	}
+	return Ok(());
+}
+
+/// Prints statistics about the podcasts and episodes in the database,
+/// either as a table or as JSON.
+fn stats(db_path: &Path, args: &clap::ArgMatches) -> Result<()>
+{
+	let db_inst = Database::connect(db_path)?;
+	let mut stats = LibraryStats::new(db_inst.get_stats()?);
+	stats.compute_disk_usage();
+
+	if args.is_present("json")
+	{
+		let json = serde_json::to_string_pretty(&stats)
+			.with_context(|| "Could not convert statistics to JSON")?;
+		println!("{json}");
+	}
+	else
+	{
+		print!("{}", stats.to_table());
+	}
+	return Ok(());
+}
+
+
+/// Prints the podcasts or episodes in the database that match the
+/// filters in the command line arguments, either as tab-separated
+/// columns or as JSON. Nothing is printed if none match, which is not
+/// an error.
+fn list(db_path: &Path, args: &clap::ArgMatches) -> Result<()>
+{
+	let filter = ListFilter {
+		unplayed: args.is_present("unplayed"),
+		downloaded: args.is_present("downloaded"),
+		since: match args.value_of("since")
+		{
+			Some(date) => Some(list::parse_date(date)?),
+			None => None,
+		},
+	};
+
+	let db_inst = Database::connect(db_path)?;
+	let mut podcasts = list::select_podcasts(db_inst.get_podcasts()?, args.value_of("podcast"))?;
+	let json = args.is_present("json");
+
+	let output = if args.value_of("what") == Some("podcasts")
+	{
+		podcasts.retain(|pod| filter.matches_podcast(pod));
+		if json
+		{
+			serde_json::to_string_pretty(&podcasts)
+				.with_context(|| "Could not convert podcasts to JSON")?
+		}
+		else
+		{
+			podcasts.iter().map(list::podcast_row).collect::<Vec<String>>().join("\n")
+		}
+	}
+	else
+	{
+		let episodes: Vec<Episode> = podcasts
+			.iter()
+			.flat_map(|pod| pod.episodes.map(|ep| ep.clone(), false))
+			.filter(|ep| filter.matches(ep))
+			.collect();
+		if json
+		{
+			serde_json::to_string_pretty(&episodes)
+				.with_context(|| "Could not convert episodes to JSON")?
+		}
+		else
+		{
+			episodes.iter().map(list::episode_row).collect::<Vec<String>>().join("\n")
+		}
+	};
+
+	// the list is often piped to another program, which may stop
+	// reading before the end of it (e.g., `head`)
+	let mut stdout = std::io::stdout().lock();
+	let result = if output.is_empty() && !json
+	{
+		Ok(())
+	}
+	else
+	{
+		writeln!(stdout, "{output}").and_then(|_| stdout.flush())
+	};
+	return match result
+	{
+		Err(err) if err.kind() != std::io::ErrorKind::BrokenPipe => {
+			Err(anyhow!(err)).with_context(|| "Could not print list")
+		}
+		_ => Ok(()),
+	};
+}
+
+/// Lists the files in the download directory that are not in the
+/// database, and the downloaded episodes whose file no longer exists.
+/// Orphaned files are deleted with `--delete-orphans`, and missing files
+/// cleared from the database with `--clear-missing`; `--dry-run` only
+/// says what those would do.
+fn cleanup(db_path: &Path, config: &Config, args: &clap::ArgMatches) -> Result<()>
+{
+	let db_inst = Database::connect(db_path)?;
+	let report = cleanup::scan(
+		&config.download_path,
+		db_path,
+		&db_inst.get_files()?,
+		&db_inst.get_cover_paths()?,
+	)?;
+	let dry_run = args.is_present("dry-run");
+	let delete_orphans = args.is_present("delete-orphans");
+	let clear_missing = args.is_present("clear-missing");
+
+	if !report.orphans.is_empty()
+	{
+		println!("Files that are not in the database:");
+		for (path, size) in report.orphans.iter()
+		{
+			println!("  {} ({})", path.to_string_lossy(), format_size(*size));
+		}
+	}
+	if !report.missing.is_empty()
+	{
+		println!("Downloaded episodes whose file no longer exists:");
+		for (_, path) in report.missing.iter()
+		{
+			println!("  {}", path.to_string_lossy());
+		}
+	}
+
+	let orphans = report.orphans.len();
+	let bytes = format_size(report.orphan_bytes());
+	let missing = report.missing.len();
+	let mut failures = 0;
+	if orphans > 0
+	{
+		if !delete_orphans
+		{
+			println!("{orphans} files ({bytes}) can be deleted with --delete-orphans.");
+		}
+		else if dry_run
+		{
+			println!("Would delete {orphans} files, reclaiming {bytes}.");
+		}
+		else
+		{
+			let mut reclaimed = 0;
+			for (path, size) in report.orphans.iter()
+			{
+				match std::fs::remove_file(path)
+				{
+					Ok(_) => reclaimed += size,
+					Err(err) => {
+						failures += 1;
+						eprintln!("Could not delete {}: {err}", path.to_string_lossy());
+					}
+				}
+			}
+			println!(
+				"Deleted {} files, reclaiming {}.",
+				orphans - failures,
+				format_size(reclaimed)
+			);
+		}
+	}
+	if missing > 0
+	{
+		if !clear_missing
+		{
+			println!("{missing} missing files can be cleared from the database with --clear-missing.");
+		}
+		else if dry_run
+		{
+			println!("Would clear {missing} missing files from the database.");
+		}
+		else
+		{
+			for (ep_id, _) in report.missing.iter()
+			{
+				db_inst
+					.remove_file(*ep_id)
+					.with_context(|| "Could not clear missing files from the database")?;
+			}
+			println!("Cleared {missing} missing files from the database.");
+		}
+	}
+	if orphans == 0 && missing == 0
+	{
+		println!("Nothing to clean up: the download directory matches the database.");
+	}
+
+	if failures > 0
+	{
+		return Err(anyhow!("Could not delete {failures} files."));
+	}
+	return Ok(());
+}
@@ -1,18 +1,26 @@
 use anyhow::Result;
-use std::collections::HashSet;
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Utc};
 use sanitize_filename::{sanitize_with_options, Options};
 
-use crate::config::{Config, DownloadNewEpisodes};
+use crate::config::{Config, DownloadNewEpisodes, SHUTDOWN_CANCEL_SECS, SHUTDOWN_WAIT_SECS};
 use crate::db::{Database, SyncResult};
 use crate::downloads::{self, DownloadMsg, EpData};
-use crate::feeds::{self, FeedMsg, PodcastFeed};
+use crate::feeds::{self, FeedError, FeedMsg, HostThrottle, PodcastFeed};
+use crate::gpodder::{self, ActionExchange, GpodderClient, GpodderMsg, SubscriptionChanges};
+use crate::keymap::{Keybindings, UserAction};
+use crate::opml;
 use crate::play_file;
-use crate::threadpool::Threadpool;
+use crate::stats::LibraryStats;
+use crate::threadpool::{catch_panic, Priority, Shutdown, Threadpool};
 use crate::types::*;
+use crate::ui::colors::AppColors;
 use crate::ui::{Ui, UiMsg};
 
 /// Enum used for communicating with other threads.
@@ -25,22 +33,59 @@ pub enum MainMessage
 	UiSpawnPersistentNotif(String, bool),
 	UiClearPersistentNotif,
 	UiSpawnDownloadPopup(Vec<NewEpisode>, bool),
+	UiSpawnFeedPopup(Vec<String>),
+	UiUpdateQueue(Vec<QueueItem>),
+	UiSpawnStatsPopup(LibraryStats),
+	UiSpawnLogPopup(Vec<LogEntry>),
+	UiConfirmGpodderRemovals(Vec<(i64, String)>),
+	UiConfirmQuit(usize),
+	UiPodcastFilter(Option<String>),
+	UiEpisodeFilter(Option<String>),
+	UiRunPlayer(play_file::Playback),
+	UiRunEditor(Vec<String>),
+	UiReloadConfig(Keybindings, AppColors),
 	UiTearDown,
 }
 
+/// A sync with gpodder.net that is in progress: first of the list of
+/// subscriptions, then of the episode actions. Once the list on the
+/// server has been retrieved, `changes` holds what to change here, and
+/// `conflicts` the podcasts to remove that have downloaded episodes,
+/// with their IDs, for which the user is asked first. `synced` is the
+/// list to remember once the new list has been uploaded.
+#[derive(Debug, Default)]
+struct GpodderSync
+{
+	changes: SubscriptionChanges,
+	conflicts: Vec<(i64, String)>,
+	synced: Vec<String>,
+	kept: usize,
+}
+
 /// Main application controller, holding all of the main application
 /// state and mechanisms for communicatingg with the rest of the app.
 /// Used in the main function in main.rs.
 pub struct MainController
 {
 	config: Config,
+	config_path: PathBuf,
 	db: Database,
 	threadpool: Threadpool,
+	sync_threadpool: Threadpool,
+	host_throttle: HostThrottle,
 	podcasts: LockVec<Podcast>,
 	filters: Filters,
+	podcast_sort: PodcastSort,
 	sync_counter: usize,
-	sync_tracker: Vec<SyncResult>,
+	sync_total: usize,
+	sync_tracker: Vec<(String, SyncResult)>,
+	auto_refresh: bool,
+	last_refresh: Instant,
 	download_tracker: HashSet<i64>,
+	gpodder_sync: Option<GpodderSync>,
+	log: RefCell<VecDeque<LogEntry>>,
+	#[cfg(feature = "mpris")]
+	now_playing: Option<crate::mpris::NowPlaying>,
 	pub ui_thread: std::thread::JoinHandle<()>,
 	pub tx_to_ui: mpsc::Sender<MainMessage>,
 	pub tx_to_main: mpsc::Sender<Message>,
@@ -52,7 +97,7 @@ impl MainController
 	/// Instantiates the main controller (used during app startup), which
 	/// sets up the connection to the database, download manager, and UI
 	/// thread, and reads the list of podcasts from the database.
-	pub fn new(config: Config, db_path: &Path) -> Result<MainController>
+	pub fn new(config: Config, config_path: &Path, db_path: &Path) -> Result<MainController>
 	{
 		// create transmitters and receivers for
 		// passing messages between threads
@@ -62,8 +107,18 @@ impl MainController
 		// get connection to the database
 		let db_inst = Database::connect(db_path)?;
 
-		// set up threadpool
+		// set up threadpools, one for downloads and one for syncing, so
+		// that neither can hold up the other
 		let threadpool = Threadpool::new(config.simultaneous_downloads);
+		let sync_threadpool = Threadpool::new(config.sync_threads);
+		for pool in [&threadpool, &sync_threadpool]
+		{
+			let tx_panic = tx_to_main.clone();
+			pool.set_panic_handler(move |message| {
+				let _ = tx_panic.send(Message::TaskFailed(message));
+			});
+		}
+		let host_throttle = HostThrottle::new(config.same_host_delay);
 
 		// create vector of podcasts, where references are checked at
 		// runtime; this is necessary because we want main.rs to hold the
@@ -71,6 +126,14 @@ impl MainController
 		// UI needs to check this list and update the screen when
 		// necessary
 		let podcast_list = LockVec::new(db_inst.get_podcasts()?);
+		let mut filters = Filters::default();
+		if config.hide_played
+		{
+			filters.played = FilterStatus::NegativeCases;
+		}
+		let podcast_sort = config.podcast_sort;
+		podcast_list.sort_podcasts(podcast_sort);
+		filters.apply(&podcast_list);
 
 		// set up UI in new thread
 		let tx_ui_to_main = mpsc::Sender::clone(&tx_to_main);
@@ -81,17 +144,33 @@ impl MainController
 			tx_ui_to_main,
 		);
 		// TODO: Can we do this without cloning the config?
+		tx_to_ui
+			.send(MainMessage::UiEpisodeFilter(filters.episode_label()))
+			.expect("Thread messaging error");
 
 		return Ok(MainController {
 			config: config,
+			config_path: config_path.to_path_buf(),
 			db: db_inst,
 			threadpool: threadpool,
+			sync_threadpool: sync_threadpool,
+			host_throttle: host_throttle,
 			podcasts: podcast_list,
-			filters: Filters::default(),
+			filters: filters,
+			podcast_sort: podcast_sort,
 			ui_thread: ui_thread,
 			sync_counter: 0,
+			sync_total: 0,
 			sync_tracker: Vec::new(),
+			auto_refresh: false,
+			last_refresh: Instant::now(),
 			download_tracker: HashSet::new(),
+			gpodder_sync: None,
+			log: RefCell::new(VecDeque::new()),
+			// there is no session bus over SSH, among others, so failing
+			// to connect just means that nothing is published
+			#[cfg(feature = "mpris")]
+			now_playing: crate::mpris::NowPlaying::connect().ok(),
 			tx_to_ui: tx_to_ui,
 			tx_to_main: tx_to_main,
 			rx_to_main: rx_to_main,
@@ -100,14 +179,48 @@ impl MainController
 
 	/// Initiates the main loop where the controller waits for
 	/// messages coming in from the UI and other threads, and
-	/// processes them.
+	/// processes them. In between messages, this also checks whether
+	/// it is time to refresh the podcasts automatically, or to purge
+	/// podcasts that were removed a while ago. Removed podcasts that are
+	/// left are purged on exit. If downloads are in progress when the
+	/// user quits, the user is asked whether to wait for them, cancel
+	/// them, or keep running.
 	pub fn loop_msgs(&mut self)
 	{
-		while let Some(message) = self.rx_to_main.iter().next()
+		let mut shutdown = Shutdown::Cancel;
+		self.update_queue();
+		loop
 		{
+			// without the UI thread (e.g., after it panicked), there is no
+			// way for the user to quit
+			if self.ui_thread.is_finished()
+			{
+				break;
+			}
+			self.check_auto_refresh();
+			self.check_purge();
+			let message = match self.rx_to_main.recv_timeout(Duration::from_secs(1))
+			{
+				Ok(message) => message,
+				Err(mpsc::RecvTimeoutError::Timeout) => continue,
+				Err(mpsc::RecvTimeoutError::Disconnected) => break,
+			};
 			match message
 			{
-				Message::Ui(UiMsg::Quit) => break,
+				Message::Ui(UiMsg::Quit) => {
+					if self.download_tracker.is_empty()
+					{
+						break;
+					}
+					self.tx_to_ui
+						.send(MainMessage::UiConfirmQuit(self.download_tracker.len()))
+						.expect("Thread messaging error");
+				}
+
+				Message::Ui(UiMsg::Shutdown(mode)) => {
+					shutdown = mode;
+					break;
+				}
 
 				Message::Ui(UiMsg::AddFeed(url)) => self.add_podcast(url),
 
@@ -115,18 +228,63 @@ impl MainController
 					self.add_or_sync_data(pod, None)
 				},
 
-				Message::Feed(FeedMsg::Error(feed)) => match feed.title
-				{
-					Some(t) => {
-						self.notif_to_ui(
-							format!("Error retrieving RSS feed for {t}."),
+				Message::Feed(FeedMsg::Candidates(_feed, urls)) => {
+					self.tx_to_ui
+						.send(MainMessage::UiSpawnFeedPopup(urls))
+						.expect("Thread messaging error");
+				},
+
+				Message::Feed(FeedMsg::Error(feed, err)) => {
+					match &feed.title
+					{
+						Some(t) => {
+							self.notif_to_ui(
+								format!("Error retrieving RSS feed for {t}."),
+								true
+							)
+						}
+						None => self.notif_to_ui(
+							"Error retrieving RSS feed.".to_string(),
 							true
-						)
+						),
+					}
+					if let Some(pod_id) = feed.id
+					{
+						self.record_sync_failure(pod_id, feed.title, err);
+						self.sync_failed();
+					}
+				},
+
+				Message::Ui(UiMsg::RenamePodcast(pod_id, title)) => {
+					self.rename_podcast(pod_id, title)
+				}
+
+				Message::Ui(UiMsg::SetTags(pod_id, tags)) => {
+					self.set_tags(pod_id, tags)
+				},
+
+				Message::Ui(UiMsg::SetFavorite(pod_id, favorite)) => {
+					self.set_favorite(pod_id, favorite)
+				},
+
+				Message::Ui(UiMsg::SetMaxEpisodes(pod_id, max_episodes)) => {
+					let message = match max_episodes
+					{
+						Some(0) => "Keeping all episodes".to_string(),
+						Some(num) => format!("Keeping the {num} most recent episodes"),
+						None => "Using the global episode limit".to_string(),
+					};
+					match self.db.set_max_episodes(pod_id, max_episodes)
+					{
+						Ok(_) => self.notif_to_ui(
+							format!("{message}; this will apply at the next sync."),
+							false
+						),
+						Err(_) => self.notif_to_ui(
+							"Could not update podcast in database".to_string(),
+							true
+						),
 					}
-					None => self.notif_to_ui(
-						"Error retrieving RSS feed.".to_string(),
-						true
-					),
 				},
 
 				Message::Ui(UiMsg::Sync(pod_id)) => self.sync(Some(pod_id)),
@@ -135,37 +293,146 @@ impl MainController
 					self.add_or_sync_data(pod, Some(id))
 				},
 
-				Message::Ui(UiMsg::SyncAll) => self.sync(None),
+				Message::Ui(UiMsg::SyncAll) => {
+					// a sync already in progress keeps its own mode
+					if self.sync_counter == 0
+					{
+						self.auto_refresh = false;
+					}
+					self.sync(None)
+				},
 
 				Message::Ui(UiMsg::Play(pod_id, ep_id)) => {
 					self.play_file(pod_id, ep_id)
 				},
 
 				Message::Ui(UiMsg::MarkPlayed(pod_id, ep_id, played)) => {
-					self.mark_played(pod_id, ep_id, played)
+					self.mark_played(pod_id, ep_id, played, false)
+				},
+
+				Message::Ui(UiMsg::MarkPlayedMulti(pod_id, ep_ids, played)) => {
+					for ep_id in ep_ids.iter()
+					{
+						self.mark_played(pod_id, *ep_id, played, false);
+					}
+					let count = ep_ids.len();
+					let plural = if count > 1 { "s" } else { "" };
+					let status = if played { "played" } else { "unplayed" };
+					self.notif_to_ui(
+						format!("Marked {count} episode{plural} as {status}"),
+						false
+					);
 				},
 
 				Message::Ui(UiMsg::MarkAllPlayed(pod_id, played)) => {
 					self.mark_all_played(pod_id, played)
 				},
 
+				Message::Ui(UiMsg::Archive(pod_id, ep_id, archived)) => {
+					self.archive(pod_id, ep_id, archived)
+				},
+
+				Message::Ui(UiMsg::ArchiveMulti(pod_id, ep_ids, archived)) => {
+					for ep_id in ep_ids.iter()
+					{
+						self.archive(pod_id, *ep_id, archived);
+					}
+					let count = ep_ids.len();
+					let plural = if count > 1 { "s" } else { "" };
+					let status = if archived { "Archived" } else { "Unarchived" };
+					self.notif_to_ui(format!("{status} {count} episode{plural}"), false);
+				},
+
+				Message::Ui(UiMsg::ArchiveOlder(pod_id, ep_id)) => {
+					self.archive_older(pod_id, ep_id)
+				},
+
+				Message::Ui(UiMsg::SetPosition(pod_id, ep_id, position)) => {
+					self.set_position(pod_id, ep_id, position)
+				},
+
+				Message::Ui(UiMsg::Enqueue(pod_id, ep_id)) => self.enqueue(pod_id, ep_id),
+
+				Message::Ui(UiMsg::SetQueue(ep_ids)) => {
+					if self.db.set_queue(&ep_ids).is_err()
+					{
+						self.notif_to_ui("Could not update queue in database".to_string(), true);
+					}
+					self.update_queue();
+				},
+
+				Message::Ui(UiMsg::PlayNext) => self.play_next(),
+
+				Message::Ui(UiMsg::ShowStats) => self.compute_stats(),
+
+				Message::Ui(UiMsg::ShowLog) => self.show_log(),
+
+				Message::Ui(UiMsg::OpenLink(pod_id, ep_id)) => {
+					self.open_link(pod_id, ep_id)
+				},
+
+				Message::Ui(UiMsg::ExportFeeds(path)) => self.export_feeds(&path),
+
+				Message::Ui(UiMsg::PodcastViewed(pod_id, time)) => {
+					self.podcast_viewed(pod_id, time)
+				},
+
+				Message::Ui(UiMsg::SyncGpodder) => self.sync_gpodder(),
+
+				Message::Gpodder(GpodderMsg::Subscriptions(remote)) => {
+					self.gpodder_subscriptions(remote)
+				}
+
+				Message::Ui(UiMsg::GpodderRemovals(pod_ids)) => self.gpodder_removals(pod_ids),
+
+				Message::Gpodder(GpodderMsg::Uploaded(result)) => self.gpodder_uploaded(result),
+
+				Message::Gpodder(GpodderMsg::EpisodeActions(exchange)) => {
+					self.gpodder_episodes(exchange)
+				}
+
+				Message::TaskFailed(message) => {
+					self.notif_to_ui(format!("Background task failed: {message}"), true)
+				}
+
+				Message::Stats(stats) => {
+					self.tx_to_ui
+						.send(MainMessage::UiSpawnStatsPopup(stats))
+						.expect("Thread messaging error");
+				},
+
+				Message::PlayerExit(exit) => self.player_exited(exit),
+
+				Message::EditorExit(error) => match error
+				{
+					Some(err) => self.notif_to_ui(format!("Config not reloaded: {err}"), true),
+					None => self.reload_config(),
+				},
+
 				Message::Ui(UiMsg::Download(pod_id, ep_id)) => {
-					self.download(pod_id, Some(ep_id))
+					self.download(pod_id, Some(ep_id), Priority::High)
 				},
 
 				Message::Ui(UiMsg::DownloadMulti(vec)) => {
+					let before = self.download_tracker.len();
 					for (pod_id, ep_id) in vec.into_iter()
 					{
-						self.download(pod_id, Some(ep_id));
+						self.download(pod_id, Some(ep_id), Priority::High);
+					}
+					let queued = self.download_tracker.len() - before;
+					if queued > 0
+					{
+						let plural = if queued > 1 { "s" } else { "" };
+						self.notif_to_ui(format!("Queued {queued} download{plural}"), false);
 					}
 				},
 
 				Message::Ui(UiMsg::DownloadAll(pod_id)) => {
-					self.download(pod_id, None)
+					self.download(pod_id, None, Priority::High)
 				},
 
 				Message::Ui(UiMsg::UnmarkDownloaded(pod_id, ep_id)) => {
-					if let Err(_) = self.unmark_downloaded(pod_id, ep_id)
+					if self.unmark_downloaded(pod_id, ep_id).is_err()
 					{
 						self.notif_to_ui(
 							"Error unmarking episode as downloaded".to_string(),
@@ -178,13 +445,15 @@ impl MainController
 				Message::Dl(DownloadMsg::Complete(ep_data)) => {
 					self.download_complete(ep_data)
 				},
-				Message::Dl(DownloadMsg::ResponseError(_)) => {
+				Message::Dl(DownloadMsg::ResponseError(ep_data)) => {
+					self.download_failed(&ep_data);
 					self.notif_to_ui(
-						"Error sending download request.".to_string(),
+						format!("Error sending download request for \"{}\".", ep_data.title),
 						true
 					)
 				},
 				Message::Dl(DownloadMsg::FileCreateError(ep_data)) => {
+					self.download_failed(&ep_data);
 					match ep_data.file_path
 					{
 						Some(path) => self.notif_to_ui(
@@ -197,9 +466,23 @@ impl MainController
 						)
 					}
 				},
-				Message::Dl(DownloadMsg::FileWriteError(_)) => {
+				Message::Dl(DownloadMsg::CoverComplete(pod_id, path)) => {
+					self.cover_complete(pod_id, path)
+				},
+				Message::Dl(DownloadMsg::Cancelled(ep_data)) => {
+					self.download_failed(&ep_data)
+				},
+				Message::Dl(DownloadMsg::Failed(ep_data, message)) => {
+					self.download_failed(&ep_data);
+					self.notif_to_ui(
+						format!("Error downloading \"{}\": {message}", ep_data.title),
+						true
+					)
+				},
+				Message::Dl(DownloadMsg::FileWriteError(ep_data)) => {
+					self.download_failed(&ep_data);
 					self.notif_to_ui(
-						"Error downloading episode.".to_string(),
+						format!("Error downloading episode \"{}\".", ep_data.title),
 						true
 					)
 				},
@@ -208,14 +491,26 @@ impl MainController
 					self.delete_file(pod_id, ep_id)
 				},
 
+				Message::Ui(UiMsg::DeleteMulti(pod_id, ep_ids)) => {
+					self.delete_multi(pod_id, &ep_ids)
+				},
+
 				Message::Ui(UiMsg::DeleteAll(pod_id)) => {
 					self.delete_files(pod_id)
 				},
 
+				Message::Ui(UiMsg::SetWorkers(num)) => self.set_workers(num),
+
+				Message::Ui(UiMsg::ReloadConfig) => self.reload_config(),
+
+				Message::Ui(UiMsg::EditConfig) => self.edit_config(),
+
 				Message::Ui(UiMsg::RemovePodcast(pod_id, delete_files)) => {
 					self.remove_podcast(pod_id, delete_files)
 				}
 
+				Message::Ui(UiMsg::UndoRemoval) => self.undo_removal(),
+
 				Message::Ui(UiMsg::RemoveEpisode(pod_id, ep_id, delete_files)) => {
 					self.remove_episode(pod_id, ep_id, delete_files)
 				}
@@ -242,15 +537,15 @@ impl MainController
 							{
 								FilterStatus::All => {
 									new_filter = FilterStatus::NegativeCases;
-									message = "Unplayed only";
+									message = "Unplayed only".to_string();
 								}
 								FilterStatus::NegativeCases => {
 									new_filter = FilterStatus::PositiveCases;
-									message = "Played only";
+									message = "Played only".to_string();
 								}
 								FilterStatus::PositiveCases => {
 									new_filter = FilterStatus::All;
-									message = "Played and unplayed";
+									message = "Played and unplayed".to_string();
 								}
 							}
 							self.filters.played = new_filter;
@@ -260,33 +555,174 @@ impl MainController
 							{
 								FilterStatus::All => {
 									new_filter = FilterStatus::PositiveCases;
-									message = "Downloaded only";
+									message = "Downloaded only".to_string();
 								}
 								FilterStatus::PositiveCases => {
 									new_filter = FilterStatus::NegativeCases;
-									message = "Undownloaded only";
+									message = "Undownloaded only".to_string();
 								}
 								FilterStatus::NegativeCases => {
 									new_filter = FilterStatus::All;
-									message = "Downloaded and undownloaded";
+									message = "Downloaded and undownloaded".to_string();
 								}
 							}
 							self.filters.downloaded = new_filter;
 						}
+						FilterType::View => {
+							self.filters.next_view();
+							message = match self.filters.episode_label()
+							{
+								Some(label) => label.replace(", ", " and ") + " only",
+								None => "All episodes".to_string(),
+							};
+						}
+						FilterType::Archived => {
+							self.filters.show_archived = !self.filters.show_archived;
+							message = if self.filters.show_archived
+							{
+								"Archived episodes shown".to_string()
+							}
+							else
+							{
+								"Archived episodes hidden".to_string()
+							};
+						}
+						FilterType::Tag => {
+							self.filters.tag = self.next_tag();
+							message = match &self.filters.tag
+							{
+								Some(tag) => format!("Tag \"{tag}\" only"),
+								None => "All tags".to_string(),
+							};
+						}
+						FilterType::Favorites => {
+							self.filters.favorites_only = !self.filters.favorites_only;
+							message = if self.filters.favorites_only
+							{
+								"Favorites only".to_string()
+							}
+							else
+							{
+								"All podcasts".to_string()
+							};
+						}
 					}
+					self.tx_to_ui
+						.send(MainMessage::UiPodcastFilter(self.filters.podcast_label()))
+						.expect("Thread messaging error");
+					self.tx_to_ui
+						.send(MainMessage::UiEpisodeFilter(self.filters.episode_label()))
+						.expect("Thread messaging error");
 					self.notif_to_ui(format!("Filter: {message}"), false);
-					self.update_filters(self.filters, true);
+					self.update_filters(&self.filters, true);
+				}
+
+				Message::Ui(UiMsg::SearchPodcasts(search)) => {
+					self.filters.podcast_search = search;
+					self.update_filters(&self.filters, true);
+				}
+				Message::Ui(UiMsg::SearchEpisodes(search)) => {
+					self.filters.episode_search = search;
+					self.update_filters(&self.filters, true);
+				}
+				Message::Ui(UiMsg::SetEpisodeSort(pod_id, sort)) => {
+					self.set_episode_sort(pod_id, sort)
+				}
+				Message::Ui(UiMsg::SetPodcastSort(sort)) => {
+					self.podcast_sort = sort;
+					self.notif_to_ui(format!("Sorting podcasts {}", sort.label()), false);
+					self.update_filters(&self.filters, true);
 				}
 
 				Message::Ui(UiMsg::Noop) => (),
 			}
 		}
+		self.stop_jobs(shutdown);
+		self.purge_removed(Utc::now());
+	}
+
+	/// Stops the threadpools on exit. With `Shutdown::Finish`, the
+	/// downloads that have started are finished and recorded, while those
+	/// still waiting are dropped; with `Shutdown::Cancel`, the downloads
+	/// are stopped and their partial files deleted. Either way, the jobs
+	/// are only waited for so long. While waiting for downloads to
+	/// finish, the user can still cancel them from the UI.
+	fn stop_jobs(&mut self, mut mode: Shutdown)
+	{
+		// without the UI thread, there is nothing to tell the user, and no
+		// menus to update
+		let ui_running = !self.ui_thread.is_finished();
+		let timeout = match mode
+		{
+			Shutdown::Finish => {
+				if ui_running
+				{
+					self.persistent_notif_to_ui(
+						"Waiting for downloads to finish... (press q or c to cancel them)".to_string(),
+						false,
+					);
+				}
+				Duration::from_secs(SHUTDOWN_WAIT_SECS)
+			}
+			Shutdown::Cancel => Duration::from_secs(SHUTDOWN_CANCEL_SECS),
+		};
+		self.sync_threadpool.close(mode);
+		self.threadpool.close(mode);
+		let mut deadline = Instant::now() + timeout;
+		while mode == Shutdown::Finish
+			&& ui_running
+			&& Instant::now() < deadline
+			&& !(self.threadpool.is_stopped() && self.sync_threadpool.is_stopped())
+		{
+			match self.rx_to_main.recv_timeout(Duration::from_millis(100))
+			{
+				Ok(Message::Ui(UiMsg::Shutdown(Shutdown::Cancel))) => {
+					mode = Shutdown::Cancel;
+					self.persistent_notif_to_ui("Cancelling downloads...".to_string(), false);
+					self.sync_threadpool.close(mode);
+					self.threadpool.close(mode);
+					deadline = deadline.min(Instant::now() + Duration::from_secs(SHUTDOWN_CANCEL_SECS));
+				}
+				Ok(Message::Dl(DownloadMsg::Complete(ep_data))) => self.download_complete(ep_data),
+				Ok(Message::Dl(DownloadMsg::CoverComplete(pod_id, path))) => {
+					self.cover_complete(pod_id, path)
+				}
+				Ok(_) | Err(mpsc::RecvTimeoutError::Timeout) => (),
+				Err(mpsc::RecvTimeoutError::Disconnected) => break,
+			}
+		}
+		let timeout = deadline.saturating_duration_since(Instant::now());
+		if !self.threadpool.shutdown(mode, timeout)
+		{
+			log::warn!("Some downloads did not stop in time and may have left partial files");
+		}
+		let remaining = deadline.saturating_duration_since(Instant::now());
+		if !self.sync_threadpool.shutdown(mode, remaining)
+		{
+			log::warn!("Some podcasts did not finish syncing in time");
+		}
+		if !ui_running
+		{
+			return;
+		}
+		while let Ok(message) = self.rx_to_main.try_recv()
+		{
+			match message
+			{
+				Message::Dl(DownloadMsg::Complete(ep_data)) => self.download_complete(ep_data),
+				Message::Dl(DownloadMsg::CoverComplete(pod_id, path)) => {
+					self.cover_complete(pod_id, path)
+				}
+				_ => (),
+			}
+		}
 	}
 
 	/// Sends the specified notification to the UI, which will display at
 	/// the bottom of the screen.
 	pub fn notif_to_ui(&self, message: String, error: bool)
 	{
+		self.log_message(message.clone(), error);
 		self.tx_to_ui
 			.send(MainMessage::UiSpawnNotif(
 				message,
@@ -296,6 +732,58 @@ impl MainController
 			.expect("Thread messaging error");
 	}
 
+	/// Adds a message to the message log, dropping the oldest message
+	/// once the log is full. Messages also go to the debug log, and
+	/// errors are appended to the log file, if one is set in the config.
+	fn log_message(&self, message: String, error: bool)
+	{
+		let entry = LogEntry::new(message, error);
+		if error
+		{
+			log::error!("{}", entry.message);
+			if let Some(path) = &self.config.log_file
+			{
+				// there is nowhere left to report a failure here
+				let _ = entry.append_to(path);
+			}
+		}
+		else
+		{
+			log::info!("{}", entry.message);
+		}
+		let mut log = self.log.borrow_mut();
+		log.push_back(entry);
+		while log.len() > crate::config::MAX_LOG_ENTRIES
+		{
+			log.pop_front();
+		}
+	}
+
+	/// Sends the messages in the message log to the UI, to show them in
+	/// a popup window.
+	pub fn show_log(&self)
+	{
+		let entries: Vec<LogEntry> = self
+			.log
+			.borrow()
+			.iter()
+			.enumerate()
+			.map(|(i, entry)| {
+				let mut entry = entry.clone();
+				entry.id = i as i64;
+				entry
+			})
+			.collect();
+		if entries.is_empty()
+		{
+			self.notif_to_ui("The log is empty".to_string(), false);
+			return;
+		}
+		self.tx_to_ui
+			.send(MainMessage::UiSpawnLogPopup(entries))
+			.expect("Thread messaging error");
+	}
+
 	/// Sends a persistent notification to the UI, which will display at
 	/// the bottom of the screen until cleared.
 	pub fn persistent_notif_to_ui(&self, message: String, error: bool)
@@ -319,20 +807,25 @@ impl MainController
 	{
 		let sync_len = self.sync_counter;
 		let dl_len = self.download_tracker.len();
-		let sync_plural = if sync_len > 1 { "s" } else { "" };
 		let dl_plural = if dl_len > 1 { "s" } else { "" };
+		let syncing = if self.sync_total > 1
+		{
+			let done = self.sync_total - sync_len;
+			format!("Syncing {done}/{} podcasts", self.sync_total)
+		}
+		else
+		{
+			"Syncing 1 podcast".to_string()
+		};
 
 		if sync_len > 0 && dl_len > 0
 		{
-			let notif = format!(
-				"Syncing {sync_len} podcast{sync_plural}, downloading {dl_len} episode{dl_plural}..."
-			);
+			let notif = format!("{syncing}, downloading {dl_len} episode{dl_plural}...");
 			self.persistent_notif_to_ui(notif, false);
 		}
 		else if sync_len > 0
 		{
-			let notif = format!("Syncing {sync_len} podcast{sync_plural}...");
-			self.persistent_notif_to_ui(notif, false);
+			self.persistent_notif_to_ui(format!("{syncing}..."), false);
 		}
 		else if dl_len > 0
 		{
@@ -352,206 +845,1144 @@ impl MainController
 		feeds::check_feed(
 			feed,
 			self.config.max_retries,
-			&self.threadpool,
+			self.config.fetch_full_archive,
+			self.host_throttle.clone(),
+			Priority::High,
+			&self.sync_threadpool,
 			self.tx_to_main.clone(),
 		);
 	}
 
-	/// Synchronize RSS feed data for one or more podcasts.
-	pub fn sync(&mut self, pod_id: Option<i64>)
+	/// Starts syncing the list of subscriptions with gpodder.net, by
+	/// retrieving the list on the server in the threadpool.
+	pub fn sync_gpodder(&mut self)
 	{
-		// We pull out the data we need here first, so we can
-		// stop borrowing the podcast list as quickly as possible.
-		// Slightly less efficient (two loops instead of
-		// one), but then it won't block other tasks that
-		// need to access the list.
-		let mut pod_data = Vec::new();
-		match pod_id
+		if self.gpodder_sync.is_some()
 		{
-			// just grab one podcast
-			Some(id) => pod_data.push(
-				self.podcasts
-					.map_single(id, |pod| {
-						PodcastFeed::new(
-							Some(pod.id),
-							pod.url.clone(),
-							Some(pod.title.clone())
-						)
-					})
-					.unwrap(),
-			),
-			// get all of 'em!
-			None => {
-				pod_data = self.podcasts.map(
-					|pod| PodcastFeed::new(
-						Some(pod.id),
-						pod.url.clone(),
-						Some(pod.title.clone())
-					),
-					false,
-				)
-			}
+			self.notif_to_ui("Already syncing subscriptions".to_string(), false);
+			return;
 		}
-		for feed in pod_data.into_iter()
+		let client = match self.gpodder_client()
 		{
-			self.sync_counter += 1;
-			feeds::check_feed(
-				feed,
-				self.config.max_retries,
-				&self.threadpool,
-				self.tx_to_main.clone(),
-			)
-		}
-		self.update_tracker_notif();
+			Ok(client) => client,
+			Err(err) => {
+				self.notif_to_ui(err.to_string(), true);
+				return;
+			}
+		};
+		self.gpodder_sync = Some(GpodderSync::default());
+		self.notif_to_ui(format!("Syncing subscriptions with {}...", client.server()), false);
+		let tx_to_main = self.tx_to_main.clone();
+		self.sync_threadpool.execute(Priority::High, move || {
+			let remote = catch_panic(|| client.subscriptions())
+				.unwrap_or_else(|message| Err(anyhow::anyhow!("Sync failed: {message}")));
+			tx_to_main
+				.send(Message::Gpodder(GpodderMsg::Subscriptions(remote)))
+				.expect("Thread messaging error");
+		});
 	}
 
-	/// Handles the application logic for adding a new podcast, or
-	/// synchronizing data from the RSS feed of an existing podcast.
-	/// `pod_id` will be None if a new podcast is being added (i.e.,
-	/// the database has not given it an id yet).
-	pub fn add_or_sync_data(&mut self, pod: PodcastNoId, pod_id: Option<i64>)
+	/// Returns a client for the gpodder.net account in the config.
+	fn gpodder_client(&self) -> Result<GpodderClient>
 	{
-		let title = pod.title.clone();
-		let db_result;
-		let failure;
+		return match self.config.gpodder.clone()
+		{
+			Some(config) => GpodderClient::new(config),
+			None => Err(anyhow::anyhow!(
+				"Syncing with gpodder.net is not set up; add a [gpodder] section to config.toml."
+			)),
+		};
+	}
 
-		if let Some(id) = pod_id
+	/// Merges the list of subscriptions on the server with the one here:
+	/// feeds subscribed to elsewhere since the last sync are added, and
+	/// those unsubscribed from are removed. The user is asked first about
+	/// podcasts with downloaded episodes; otherwise, the new list is
+	/// uploaded right away.
+	pub fn gpodder_subscriptions(&mut self, remote: Result<Vec<String>>)
+	{
+		let remote = match remote
 		{
-			db_result = self.db.update_podcast(id, pod);
-			failure = format!("Error synchronizing {title}.");
+			Ok(remote) => remote,
+			Err(err) => {
+				self.gpodder_sync = None;
+				self.notif_to_ui(format!("Could not sync subscriptions: {err}"), true);
+				return;
+			}
+		};
+		let account = match &self.config.gpodder
+		{
+			Some(config) => gpodder::account_name(config),
+			None => return,
+		};
+		let synced = match self.db.get_gpodder_subscriptions(&account)
+		{
+			Ok(synced) => synced,
+			Err(_) => {
+				self.gpodder_sync = None;
+				self.notif_to_ui("Could not read subscriptions from database".to_string(), true);
+				return;
+			}
+		};
+		let local = self.podcasts.map(|pod| pod.url.clone(), false);
+		let changes = SubscriptionChanges::compare(&synced, &local, &remote);
+
+		for url in changes.add.iter()
+		{
+			self.add_podcast(url.clone());
 		}
-		else
+		let removed = self.podcasts.filter_map(|pod| {
+			return changes
+				.remove
+				.contains(&pod.url)
+				.then(|| (pod.id, pod.url.clone(), pod.title.clone(), pod.num_downloaded()));
+		});
+		let mut conflicts = Vec::new();
+		let mut titles = Vec::new();
+		for (pod_id, url, title, downloaded) in removed.into_iter()
 		{
-			db_result = self.db.insert_podcast(pod);
-			failure = "Error adding podcast to database.".to_string();
+			if downloaded > 0
+			{
+				conflicts.push((pod_id, url));
+				titles.push((pod_id, title));
+			}
+			else
+			{
+				self.remove_podcast(pod_id, false);
+			}
 		}
-		match db_result
+
+		self.gpodder_sync = Some(GpodderSync {
+			changes: changes,
+			conflicts: conflicts,
+			..Default::default()
+		});
+		if titles.is_empty()
 		{
-			Ok(result) => {
-				{
-					self.podcasts.replace_all(
+			self.upload_gpodder(Vec::new());
+		}
+		else
+		{
+			self.tx_to_ui
+				.send(MainMessage::UiConfirmGpodderRemovals(titles))
+				.expect("Thread messaging error");
+		}
+	}
+
+	/// Removes the podcasts with downloaded episodes that the user chose
+	/// to remove, along with their files, and uploads the new list of
+	/// subscriptions. The others are kept.
+	pub fn gpodder_removals(&mut self, pod_ids: Vec<i64>)
+	{
+		let conflicts = match &self.gpodder_sync
+		{
+			Some(sync) => sync.conflicts.clone(),
+			None => return,
+		};
+		let mut skipped = Vec::new();
+		for (pod_id, url) in conflicts.into_iter()
+		{
+			if pod_ids.contains(&pod_id) && self.podcasts.map_single(pod_id, |_| ()).is_some()
+			{
+				self.remove_podcast(pod_id, true);
+			}
+			else
+			{
+				skipped.push(url);
+			}
+		}
+		self.upload_gpodder(skipped);
+	}
+
+	/// Uploads the new list of subscriptions in the threadpool, given the
+	/// feeds whose removal was skipped.
+	fn upload_gpodder(&mut self, skipped: Vec<String>)
+	{
+		let client = match self.gpodder_client()
+		{
+			Ok(client) => client,
+			Err(err) => {
+				self.gpodder_sync = None;
+				self.notif_to_ui(err.to_string(), true);
+				return;
+			}
+		};
+		let upload = match self.gpodder_sync.as_mut()
+		{
+			Some(sync) => {
+				sync.synced = sync.changes.synced_list(&skipped);
+				sync.kept = skipped.len();
+				sync.changes.upload_list()
+			}
+			None => return,
+		};
+		let tx_to_main = self.tx_to_main.clone();
+		self.sync_threadpool.execute(Priority::High, move || {
+			let result = catch_panic(|| client.upload(&upload))
+				.unwrap_or_else(|message| Err(anyhow::anyhow!("Upload failed: {message}")));
+			tx_to_main
+				.send(Message::Gpodder(GpodderMsg::Uploaded(result)))
+				.expect("Thread messaging error");
+		});
+	}
+
+	/// Finishes syncing the list of subscriptions once the new list has
+	/// been uploaded, by remembering it for the next sync.
+	pub fn gpodder_uploaded(&mut self, result: Result<()>)
+	{
+		let sync = match self.gpodder_sync.take()
+		{
+			Some(sync) => sync,
+			None => return,
+		};
+		if let Err(err) = result
+		{
+			self.notif_to_ui(format!("Could not upload subscriptions: {err}"), true);
+			return;
+		}
+		let account = match &self.config.gpodder
+		{
+			Some(config) => gpodder::account_name(config),
+			None => return,
+		};
+		if self.db.set_gpodder_subscriptions(&account, &sync.synced).is_err()
+		{
+			self.notif_to_ui("Could not save subscriptions to database".to_string(), true);
+			return;
+		}
+		self.notif_to_ui(
+			format!(
+				"Synced subscriptions: {} added, {} removed, {} kept",
+				sync.changes.add.len(),
+				sync.changes.remove.len() - sync.kept,
+				sync.kept
+			),
+			false,
+		);
+		self.sync_episode_actions();
+	}
+
+	/// Exchanges episode actions with gpodder.net in the threadpool: the
+	/// actions of other devices since the last sync are retrieved, and
+	/// those for the changes made here since the last upload are
+	/// uploaded.
+	fn sync_episode_actions(&mut self)
+	{
+		let client = match self.gpodder_client()
+		{
+			Ok(client) => client,
+			Err(err) => {
+				self.notif_to_ui(err.to_string(), true);
+				return;
+			}
+		};
+		let account = client.account();
+		// changes made during the current second are left for the next
+		// sync, so that none of them are missed
+		let until = Utc::now() - chrono::Duration::seconds(1);
+		let (since, upload) = match self.db.get_episode_sync(&account).and_then(|(since, after)| {
+			let changes = self.db.get_episode_changes(after, until)?;
+			return Ok((since, gpodder::local_actions(&changes, after)));
+		})
+		{
+			Ok(found) => found,
+			Err(_) => {
+				self.notif_to_ui("Could not read episodes from database".to_string(), true);
+				return;
+			}
+		};
+		self.gpodder_sync = Some(GpodderSync::default());
+		let tx_to_main = self.tx_to_main.clone();
+		self.sync_threadpool.execute(Priority::High, move || {
+			let exchange = catch_panic(|| client.exchange_actions(since, &upload, until))
+				.unwrap_or_else(|message| ActionExchange {
+					remote: Err(anyhow::anyhow!("Sync failed: {message}")),
+					uploaded: Err(anyhow::anyhow!("Sync failed: {message}")),
+					until: until,
+				});
+			tx_to_main
+				.send(Message::Gpodder(GpodderMsg::EpisodeActions(exchange)))
+				.expect("Thread messaging error");
+		});
+	}
+
+	/// Applies the episode actions of other devices, and records how far
+	/// the sync got, so that the actions that could not be exchanged are
+	/// tried again at the next sync.
+	pub fn gpodder_episodes(&mut self, exchange: ActionExchange)
+	{
+		self.gpodder_sync = None;
+		let (account, device) = match &self.config.gpodder
+		{
+			Some(config) => (gpodder::account_name(config), config.device.clone()),
+			None => return,
+		};
+		// the upload is recorded first, so that it is not repeated if the
+		// actions of other devices could not be retrieved
+		let uploaded = match exchange.uploaded
+		{
+			Ok(uploaded) => {
+				if self.db.set_episodes_uploaded(&account, exchange.until).is_err()
+				{
+					self.notif_to_ui("Could not save episode sync to database".to_string(), true);
+					return;
+				}
+				Some(uploaded)
+			}
+			Err(err) => {
+				self.notif_to_ui(format!("Could not upload episode actions: {err}"), true);
+				None
+			}
+		};
+		let remote = match exchange.remote
+		{
+			Ok(remote) => remote,
+			Err(err) => {
+				self.notif_to_ui(format!("Could not retrieve episode actions: {err}"), true);
+				return;
+			}
+		};
+		let podcasts = self.podcasts.map(|pod| (pod.id, pod.url.clone()), false);
+		let updates = gpodder::remote_updates(&remote.actions, &device, &podcasts);
+		let updated = match self.db.apply_episode_updates(&account, &updates, remote.timestamp)
+		{
+			Ok(pod_ids) => {
+				let updated = pod_ids.len();
+				self.reload_episodes(pod_ids);
+				updated
+			}
+			Err(_) => {
+				self.notif_to_ui("Could not update episodes in database".to_string(), true);
+				return;
+			}
+		};
+		if let Some(uploaded) = uploaded
+		{
+			self.notif_to_ui(
+				format!("Synced episodes: {updated} updated, {uploaded} actions uploaded"),
+				false,
+			);
+		}
+	}
+
+	/// Reads the episodes of the given podcasts from the database again,
+	/// after they were changed there, and updates the menus.
+	fn reload_episodes(&self, mut pod_ids: Vec<i64>)
+	{
+		pod_ids.sort_unstable();
+		pod_ids.dedup();
+		if pod_ids.is_empty()
+		{
+			return;
+		}
+		for pod_id in pod_ids.into_iter()
+		{
+			if let Some(podcast) = self.podcasts.clone_podcast(pod_id)
+			{
+				if let Ok(episodes) = self.db.get_episodes(pod_id, false)
+				{
+					podcast.episodes.replace_all(episodes);
+					self.podcasts.replace(pod_id, podcast);
+				}
+			}
+		}
+		self.update_filters(&self.filters, true);
+	}
+
+	/// Syncs all podcasts if automatic refreshing is turned on, the
+	/// configured time has passed since podcasts were last synced, and no
+	/// other sync is in progress.
+	pub fn check_auto_refresh(&mut self)
+	{
+		let minutes = self.config.auto_refresh_minutes;
+		if minutes == 0 || self.sync_counter > 0
+		{
+			return;
+		}
+		if self.last_refresh.elapsed() >= Duration::from_secs(minutes * 60)
+		{
+			self.auto_refresh = true;
+			self.sync(None);
+			if self.sync_counter == 0
+			{
+				self.auto_refresh = false;
+			}
+		}
+	}
+
+	/// Synchronize RSS feed data for one or more podcasts. This is
+	/// refused while another batch of syncs is still in progress.
+	pub fn sync(&mut self, pod_id: Option<i64>)
+	{
+		if self.sync_counter > 0
+		{
+			self.notif_to_ui(
+				"Already syncing; wait for the current sync to finish.".to_string(),
+				false,
+			);
+			return;
+		}
+
+		// We pull out the data we need here first, so we can
+		// stop borrowing the podcast list as quickly as possible.
+		// Slightly less efficient (two loops instead of
+		// one), but then it won't block other tasks that
+		// need to access the list.
+		let mut pod_data = Vec::new();
+		match pod_id
+		{
+			// just grab one podcast
+			Some(id) => pod_data.push(
+				self.podcasts
+					.map_single(id, |pod| {
+						PodcastFeed::new(
+							Some(pod.id),
+							pod.url.clone(),
+							Some(pod.title.clone())
+						)
+					})
+					.unwrap(),
+			),
+			// get all of 'em! (except inactive ones)
+			None => {
+				self.last_refresh = Instant::now();
+				pod_data = self.podcasts.filter_map(|pod| {
+					if pod.inactive
+					{
+						return None;
+					}
+					return Some(PodcastFeed::new(
+						Some(pod.id),
+						pod.url.clone(),
+						Some(pod.title.clone())
+					));
+				})
+			}
+		}
+		// a single podcast is synced right away, while syncing all of them
+		// waits for what the user asked for
+		let priority = match pod_id
+		{
+			Some(_) => Priority::High,
+			None => Priority::Low,
+		};
+		self.sync_total = pod_data.len();
+		for feed in feeds::interleave_by_host(pod_data).into_iter()
+		{
+			self.sync_counter += 1;
+			feeds::check_feed(
+				feed,
+				self.config.max_retries,
+				false,
+				self.host_throttle.clone(),
+				priority,
+				&self.sync_threadpool,
+				self.tx_to_main.clone(),
+			)
+		}
+		self.update_tracker_notif();
+	}
+
+	/// Handles the application logic for adding a new podcast, or
+	/// synchronizing data from the RSS feed of an existing podcast.
+	/// `pod_id` will be None if a new podcast is being added (i.e.,
+	/// the database has not given it an id yet).
+	pub fn add_or_sync_data(&mut self, pod: PodcastNoId, pod_id: Option<i64>)
+	{
+		let title = pod.title.clone();
+		let url = pod.url.clone();
+		let old_image_url = pod_id.and_then(|id| {
+			self.podcasts.map_single(id, |pod| pod.image_url.clone()).flatten()
+		});
+		let db_result;
+		let failure;
+
+		if let Some(id) = pod_id
+		{
+			// episodes being downloaded are not hidden from under the download
+			let downloading: Vec<i64> = self.download_tracker.iter().copied().collect();
+			db_result = self.db.update_podcast(
+				id, pod, self.config.max_episodes_per_feed, &downloading
+			);
+			failure = format!("Error synchronizing {title}.");
+		}
+		else
+		{
+			db_result = self.db.insert_podcast(pod);
+			failure = "Error adding podcast to database.".to_string();
+		}
+		match db_result
+		{
+			Ok(result) => {
+				{
+					self.podcasts.replace_all(
 						self.db
 							.get_podcasts()
 							.expect("Error retrieving info from database."),
 					);
 				}
-				self.update_filters(self.filters, true);
+				self.update_filters(&self.filters, true);
+				self.download_cover(&url, old_image_url);
 
 				if pod_id.is_some()
 				{
-					self.sync_tracker.push(result);
+					self.sync_tracker.push((title, result));
 					self.sync_counter -= 1;
 					self.update_tracker_notif();
 
 					if self.sync_counter == 0
 					{
-						// count up total new episodes and updated
-						// episodes when sync process is finished
-						let mut added = 0;
-						let mut updated = 0;
-						let mut new_eps = Vec::new();
-						for res in self.sync_tracker.iter() {
-							added += res.added.len();
-							updated += res.updated.len();
-							new_eps.extend(res.added.clone());
-						}
-						self.sync_tracker = Vec::new();
-						self.notif_to_ui(
-							format!(
-								"Sync complete: Added {added}, updated {updated} episodes."
-							),
-							false,
-						);
-
-						// deal with new episodes once syncing is
-						// complete, based on user preferences
-						if !new_eps.is_empty()
-						{
-							match self.config.download_new_episodes
-							{
-								DownloadNewEpisodes::Always => {
-									for ep in new_eps.into_iter()
-									{
-										self.download(ep.pod_id, Some(ep.id));
-									}
-								}
-								DownloadNewEpisodes::AskSelected => {
-									self.tx_to_ui
-										.send(MainMessage::UiSpawnDownloadPopup(new_eps, true))
-										.expect("Thread messaging error");
-								}
-								DownloadNewEpisodes::AskUnselected => {
-									self.tx_to_ui
-										.send(MainMessage::UiSpawnDownloadPopup(new_eps, false))
-										.expect("Thread messaging error");
-								}
-								_ => (),
-							}
-						}
+						self.sync_complete();
 					}
 				}
 				else
 				{
 					self.notif_to_ui(
-						format!("Successfully added {} episodes.", result.added.len()),
+						format!(
+							"Successfully added {} episodes from {url}.",
+							result.added.len()
+						),
 						false,
 					);
 				}
 			}
-			Err(_err) => self.notif_to_ui(failure, true),
+			Err(_err) => {
+				self.notif_to_ui(failure, true);
+				if pod_id.is_some()
+				{
+					self.sync_failed();
+				}
+			}
+		}
+	}
+
+	/// Keeps track of a failed sync for a podcast in the database, and
+	/// lets the user know if the podcast has now been marked as
+	/// inactive. The error is kept on the podcast either way, so that it
+	/// shows up in the details panel.
+	pub fn record_sync_failure(
+		&mut self,
+		pod_id: i64,
+		title: Option<String>,
+		err: FeedError
+	) {
+		match self.db.record_sync_failure(
+			pod_id, &err.message, err.gone, self.config.max_sync_failures
+		) {
+			Ok(true) => {
+				self.podcasts.replace_all(
+					self.db
+						.get_podcasts()
+						.expect("Error retrieving info from database."),
+				);
+				self.update_filters(&self.filters, true);
+				let title = title.unwrap_or_else(|| "Podcast".to_string());
+				self.notif_to_ui(
+					format!("{title} could not be synced and has been marked as inactive."),
+					true
+				);
+			}
+			Ok(false) => {
+				// keep the error around for the details panel
+				if let Some(mut podcast) = self.podcasts.clone_podcast(pod_id)
+				{
+					podcast.last_error = Some(err.message);
+					podcast.last_error_at = Some(Utc::now());
+					self.podcasts.replace(pod_id, podcast);
+				}
+			}
+			Err(_) => self.notif_to_ui(
+				"Error updating podcast in database.".to_string(),
+				true
+			),
+		}
+	}
+
+	/// Called when a podcast in the current batch of syncs could not be
+	/// synced, so that the batch can still finish.
+	pub fn sync_failed(&mut self)
+	{
+		if self.sync_counter > 0
+		{
+			self.sync_counter -= 1;
+			self.update_tracker_notif();
+			if self.sync_counter == 0
+			{
+				self.sync_complete();
+			}
+		}
+	}
+
+	/// Once all podcasts in a batch of syncs have been processed, this
+	/// reports how many new episodes were found, and deals with the new
+	/// episodes based on user preferences. After an automatic refresh,
+	/// only new episodes are reported, and no popup is opened.
+	pub fn sync_complete(&mut self)
+	{
+		let auto_refresh = self.auto_refresh;
+		self.auto_refresh = false;
+		self.sync_total = 0;
+
+		let mut added = 0;
+		let mut pods_with_new = 0;
+		let mut new_eps = Vec::new();
+		for (_, res) in self.sync_tracker.iter()
+		{
+			added += res.added.len();
+			pods_with_new += !res.added.is_empty() as usize;
+			new_eps.extend(res.added.clone());
+		}
+
+		let message = if auto_refresh
+		{
+			let ep_plural = if added > 1 { "s" } else { "" };
+			format!("{added} new episode{ep_plural}")
+		}
+		else if self.sync_tracker.len() == 1
+		{
+			let (title, res) = &self.sync_tracker[0];
+			match res.updated.len()
+			{
+				0 => format!("Synced {title} ({added} new)."),
+				updated => format!("Synced {title} ({added} new, {updated} updated)."),
+			}
+		}
+		else if added > 0
+		{
+			let ep_plural = if added > 1 { "s" } else { "" };
+			let pod_plural = if pods_with_new > 1 { "s" } else { "" };
+			format!(
+				"Sync complete: {added} new episode{ep_plural} across {pods_with_new} podcast{pod_plural}."
+			)
+		}
+		else
+		{
+			"Sync complete: no new episodes.".to_string()
+		};
+		self.sync_tracker = Vec::new();
+		if !auto_refresh || added > 0
+		{
+			self.notif_to_ui(message, false);
+		}
+
+		// deal with new episodes once syncing is complete, based on
+		// user preferences
+		if !new_eps.is_empty()
+		{
+			match self.config.download_new_episodes
+			{
+				DownloadNewEpisodes::Always => {
+					for ep in new_eps.into_iter()
+					{
+						self.download(ep.pod_id, Some(ep.id), Priority::Low);
+					}
+				}
+				// don't interrupt the user with a popup for syncs they
+				// did not ask for
+				DownloadNewEpisodes::AskSelected
+				| DownloadNewEpisodes::AskUnselected if auto_refresh => (),
+				DownloadNewEpisodes::AskSelected => {
+					self.tx_to_ui
+						.send(MainMessage::UiSpawnDownloadPopup(new_eps, true))
+						.expect("Thread messaging error");
+				}
+				DownloadNewEpisodes::AskUnselected => {
+					self.tx_to_ui
+						.send(MainMessage::UiSpawnDownloadPopup(new_eps, false))
+						.expect("Thread messaging error");
+				}
+				_ => (),
+			}
 		}
 	}
 
 	/// Attempts to execute the play command on the given podcast
-	/// episode.
+	/// episode, marking it as played if the user wants to.
 	pub fn play_file(&self, pod_id: i64, ep_id: i64)
 	{
-		self.mark_played(pod_id, ep_id, true);
 		let episode = self.podcasts.clone_episode(pod_id, ep_id).unwrap();
+		// the position is kept until the episode has been listened to
+		if self.config.mark_played_on_play
+		{
+			self.mark_played(pod_id, ep_id, true, true);
+		}
+		if let Some(position) = episode.format_position()
+		{
+			self.notif_to_ui(format!("Resuming at {position}"), false);
+		}
 
-		match episode.path
+		match &episode.path
 		{
 			// if there is a local file, try to play that
 			Some(path) => match path.to_str()
 			{
 				Some(p) => {
-					if play_file::execute(&self.config.play_command, p).is_err()
+					if let Err(err) = self.play(pod_id, &episode, p)
 					{
-						self.notif_to_ui(
-							"Error: Could not play file. Check configuration.".to_string(),
-							true,
-						);
+						self.notif_to_ui(format!("Error: {err}"), true);
 					}
 				}
 				None => self.notif_to_ui("Error: Filepath is not valid Unicode.".to_string(), true),
 			},
 			// otherwise, try to stream the URL
+			None if !self.config.stream_episodes => self.notif_to_ui(
+				"Error: Episode is not downloaded, and streaming is turned off.".to_string(),
+				true,
+			),
 			None => {
-				if play_file::execute(&self.config.play_command, &episode.url).is_err()
+				if let Err(err) = self.play(pod_id, &episode, &episode.url)
 				{
-					self.notif_to_ui("Error: Could not stream URL.".to_string(), true);
+					self.notif_to_ui(format!("Error: {err}"), true);
 				}
 			}
 		}
 	}
 
-	/// Given a podcast and episode, it marks the given episode as
-	/// played/unplayed, sending this info to the database and updating
-	/// in self.podcasts
-	pub fn mark_played(&self, pod_id: i64, ep_id: i64, played: bool)
+	/// Runs the play command on an episode, given the file path or URL
+	/// to play. A player that uses the terminal is handed to the UI
+	/// thread, which gives up the terminal while it runs; no key presses
+	/// reach this thread in the meantime, as the UI thread is busy
+	/// waiting on the player. Other players run in the background. Either
+	/// way, a `Message::PlayerExit` comes back here once the player exits.
+	fn play(&self, pod_id: i64, episode: &Episode, file: &str) -> Result<()>
+	{
+		let podcast = self
+			.podcasts
+			.map_single(pod_id, |pod| pod.title.clone())
+			.unwrap_or_default();
+		let command = match play_file::media_kind(file)
+		{
+			play_file::MediaKind::Audio => &self.config.play_command_audio,
+			play_file::MediaKind::Video => &self.config.play_command_video,
+		};
+		let target = play_file::PlayTarget {
+			file: file,
+			url: &episode.url,
+			title: &episode.title,
+			podcast: &podcast,
+			position: episode.position,
+		};
+		// half of what is left of the episode counts as listening to it,
+		// down to a minimum for short episodes or unknown durations
+		let min_seconds = match episode.duration
+		{
+			Some(duration) => ((duration - episode.position.unwrap_or(0)).max(0) / 2) as u64,
+			None => 0,
+		};
+		let playback = play_file::Playback {
+			pod_id: pod_id,
+			ep_id: episode.id,
+			args: play_file::command_args(command, &target)?,
+			stream: episode.path.is_none(),
+			min_seconds: min_seconds.max(crate::config::MIN_LISTEN_SECONDS),
+		};
+		if self.config.player_uses_terminal
+		{
+			#[cfg(feature = "mpris")]
+			self.publish_now_playing(pod_id, episode);
+			self.tx_to_ui
+				.send(MainMessage::UiRunPlayer(playback))
+				.expect("Thread messaging error");
+			return Ok(());
+		}
+		play_file::spawn(playback, self.tx_to_main.clone())?;
+		#[cfg(feature = "mpris")]
+		self.publish_now_playing(pod_id, episode);
+		return Ok(());
+	}
+
+	/// Publishes an episode as playing over MPRIS, with the cover of its
+	/// podcast if there is one.
+	#[cfg(feature = "mpris")]
+	fn publish_now_playing(&self, pod_id: i64, episode: &Episode)
+	{
+		let now_playing = match &self.now_playing
+		{
+			Some(now_playing) => now_playing,
+			None => return,
+		};
+		let (podcast, art_url) = self
+			.podcasts
+			.map_single(pod_id, |pod| {
+				let cover = pod.cover_path.as_ref().map(|path| format!("file://{}", path.display()));
+				(pod.title.clone(), cover.or_else(|| pod.image_url.clone()))
+			})
+			.unwrap_or_default();
+		let track = crate::mpris::Track {
+			pod_id: pod_id,
+			ep_id: episode.id,
+			title: episode.title.clone(),
+			podcast: podcast,
+			url: episode.url.clone(),
+			art_url: art_url,
+			duration: episode.duration,
+		};
+		if let Err(err) = now_playing.started(track)
+		{
+			self.log_message(format!("Could not publish episode over MPRIS: {err}"), true);
+		}
+	}
+
+	/// Handles a player exiting. The player's error output goes to the
+	/// message log, and if it failed, the error is shown. If the player
+	/// ran for long enough, the listen is recorded, the episode is taken
+	/// out of the queue, and it is marked as played if the user wants to;
+	/// the playback position of a played episode is then cleared.
+	pub fn player_exited(&self, exit: play_file::PlayerExit)
+	{
+		let playback = &exit.playback;
+		#[cfg(feature = "mpris")]
+		if let Some(now_playing) = &self.now_playing
+		{
+			if let Err(err) = now_playing.stopped(playback.pod_id, playback.ep_id)
+			{
+				self.log_message(format!("Could not publish episode over MPRIS: {err}"), true);
+			}
+		}
+		for line in exit.output.iter().filter(|line| !line.trim().is_empty())
+		{
+			self.log_message(format!("Player: {line}"), exit.error.is_some());
+		}
+		if let Some(err) = &exit.error
+		{
+			self.notif_to_ui(format!("Error: {err}"), true);
+		}
+		if exit.seconds < playback.min_seconds
+		{
+			return;
+		}
+		// the episode may have been removed while it was playing
+		let episode = match self.podcasts.clone_episode(playback.pod_id, playback.ep_id)
+		{
+			Some(episode) => episode,
+			None => return,
+		};
+		if self.db.add_listen(episode.id, exit.started_at, exit.seconds).is_err()
+		{
+			self.notif_to_ui("Could not record listen in database".to_string(), true);
+		}
+		if self.db.dequeue(episode.id).is_err()
+		{
+			self.notif_to_ui("Could not update queue in database".to_string(), true);
+		}
+		self.update_queue();
+		if self.config.mark_played_on_exit && !episode.played
+		{
+			self.mark_played(playback.pod_id, playback.ep_id, true, false);
+			self.notif_to_ui(format!("Marked as played: {}", episode.title), false);
+		}
+		// it was marked as played when it started
+		else if episode.played && episode.position.is_some()
+		{
+			self.mark_played(playback.pod_id, playback.ep_id, true, false);
+		}
+	}
+
+	/// Opens the web page that the feed links to for an episode, or for
+	/// the podcast if no episode is given, with the browser command.
+	pub fn open_link(&self, pod_id: i64, ep_id: Option<i64>)
+	{
+		let link = match ep_id
+		{
+			Some(ep_id) => self.podcasts.clone_episode(pod_id, ep_id).and_then(|ep| ep.link),
+			None => self.podcasts.map_single(pod_id, |pod| pod.link.clone()).flatten(),
+		};
+		match link
+		{
+			Some(link) => {
+				if let Err(err) = play_file::open_link(self.config.browser_command.as_deref(), &link)
+				{
+					self.notif_to_ui(format!("Error: Could not open web page: {err}"), true);
+				}
+			}
+			None => {
+				let item = if ep_id.is_some() { "episode" } else { "podcast" };
+				self.notif_to_ui(format!("This {item} does not link to a web page."), false);
+			}
+		}
+	}
+
+	/// Writes the podcasts shown with the current filters to an OPML file
+	/// at `path`, which may start with "~". If no podcasts are shown, the
+	/// file is still written, and a warning is shown.
+	pub fn export_feeds(&self, path: &str)
+	{
+		let podcasts = self.podcasts.map(|pod| pod.clone(), true);
+		let count = podcasts.len();
+		let path = shellexpand::tilde(path).to_string();
+		let written = opml::export(podcasts, false)
+			.to_string()
+			.map_err(|err| anyhow::anyhow!(err))
+			.and_then(|xml| fs::write(&path, xml).map_err(anyhow::Error::from));
+		match written
+		{
+			Ok(_) if count == 0 => self.notif_to_ui(
+				format!("Warning: no podcasts are shown, so {path} has no podcasts in it."),
+				false,
+			),
+			Ok(_) => self.notif_to_ui(format!("Exported {count} podcasts to {path}."), false),
+			Err(err) => self.notif_to_ui(format!("Error: could not export to {path}: {err}"), true),
+		}
+	}
+
+	/// Given a podcast and episode, it marks the given episode as
+	/// played/unplayed, sending this info to the database and updating
+	/// in self.podcasts. Marking it as played clears its playback
+	/// position, unless `keep_position` is set.
+	pub fn mark_played(&self, pod_id: i64, ep_id: i64, played: bool, keep_position: bool)
+	{
+		let podcast = self.podcasts.clone_podcast(pod_id).unwrap();
+
+		// TODO: Try to find a way to do this without having
+		// to clone the episode...
+		let mut episode = podcast.episodes.clone_episode(ep_id).unwrap();
+		episode.played = played;
+		if played && !keep_position
+		{
+			episode.position = None;
+		}
+
+		let _ = self.db.set_played_status(episode.id, played, keep_position);
+		podcast.episodes.replace(ep_id, episode);
+
+		self.podcasts.replace(pod_id, podcast);
+		self.update_filters(&self.filters, true);
+	}
+
+	/// Archives or unarchives an episode, sending this info to the
+	/// database and updating in self.podcasts
+	pub fn archive(&self, pod_id: i64, ep_id: i64, archived: bool)
+	{
+		if self.db.set_archived(ep_id, archived).is_err()
+		{
+			self.notif_to_ui("Could not update episode in database".to_string(), true);
+			return;
+		}
+		let podcast = self.podcasts.clone_podcast(pod_id).unwrap();
+		let mut episode = podcast.episodes.clone_episode(ep_id).unwrap();
+		episode.archived = archived;
+		podcast.episodes.replace(ep_id, episode);
+		self.podcasts.replace(pod_id, podcast);
+		self.update_filters(&self.filters, true);
+	}
+
+	/// Archives all episodes of a podcast published before the given
+	/// episode.
+	pub fn archive_older(&self, pod_id: i64, ep_id: i64)
+	{
+		let count = match self.db.archive_older(pod_id, ep_id)
+		{
+			Ok(count) => count,
+			Err(_) => {
+				self.notif_to_ui("Could not update episodes in database".to_string(), true);
+				return;
+			}
+		};
+		{
+			let mut borrowed_map = self.podcasts.borrow_map();
+			let podcast = borrowed_map.get_mut(&pod_id).unwrap();
+			podcast.episodes.replace_all(
+				self.db
+					.get_episodes(pod_id, false)
+					.expect("Error retrieving info from database."),
+			);
+		}
+		let plural = if count == 1 { "" } else { "s" };
+		self.notif_to_ui(format!("Archived {count} episode{plural}"), false);
+		self.update_filters(&self.filters, true);
+	}
+
+	/// Saves or clears the playback position of an episode, sending this
+	/// info to the database and updating in self.podcasts
+	pub fn set_position(&self, pod_id: i64, ep_id: i64, position: Option<i64>)
+	{
+		let podcast = self.podcasts.clone_podcast(pod_id).unwrap();
+		let mut episode = podcast.episodes.clone_episode(ep_id).unwrap();
+		episode.position = position;
+
+		if self.db.set_position(ep_id, position).is_err()
+		{
+			self.notif_to_ui("Could not update episode in database".to_string(), true);
+			return;
+		}
+		let message = match episode.format_position()
+		{
+			Some(pos) => format!("Position saved at {pos}"),
+			None => "Position cleared".to_string(),
+		};
+		podcast.episodes.replace(ep_id, episode);
+		self.podcasts.replace(pod_id, podcast);
+		self.notif_to_ui(message, false);
+	}
+
+	/// Adds an episode at the end of the playback queue.
+	pub fn enqueue(&self, pod_id: i64, ep_id: i64)
+	{
+		let title = match self.podcasts.clone_episode(pod_id, ep_id)
+		{
+			Some(ep) => ep.title,
+			None => return,
+		};
+		match self.db.enqueue(ep_id)
+		{
+			Ok(true) => {
+				let len = self.db.get_queue().map(|queue| queue.len()).unwrap_or(0);
+				self.notif_to_ui(format!("Added \"{title}\" to the queue ({len} queued)"), false);
+				self.update_queue();
+			}
+			Ok(false) => self.notif_to_ui(format!("\"{title}\" is already in the queue"), false),
+			Err(_) => self.notif_to_ui("Could not update queue in database".to_string(), true),
+		}
+	}
+
+	/// Sends the episodes in the queue to the UI, for the queue panel.
+	/// This is done whenever the queue may have changed, including when
+	/// episodes or podcasts are removed.
+	pub fn update_queue(&self)
+	{
+		let queue = self.db.get_queue().unwrap_or_default();
+		let items: Vec<QueueItem> = queue
+			.into_iter()
+			.filter_map(|(pod_id, ep_id)| {
+				let pod_title = self.podcasts.map_single(pod_id, |pod| pod.title.clone())?;
+				let ep = self.podcasts.clone_episode(pod_id, ep_id)?;
+				Some(QueueItem {
+					id: ep.id,
+					pod_id: pod_id,
+					title: ep.title,
+					pod_title: pod_title,
+				})
+			})
+			.collect();
+		self.tx_to_ui
+			.send(MainMessage::UiUpdateQueue(items))
+			.expect("Thread messaging error");
+	}
+
+	/// Plays the first episode in the queue. It stays in the queue until
+	/// it has been listened to, as in `player_exited()`.
+	pub fn play_next(&self)
+	{
+		match self.db.get_queue()
+		{
+			Ok(queue) => match queue.first()
+			{
+				Some(&(pod_id, ep_id)) => self.play_file(pod_id, ep_id),
+				None => self.notif_to_ui("The queue is empty".to_string(), false),
+			},
+			Err(_) => self.notif_to_ui("Could not read queue from database".to_string(), true),
+		}
+	}
+
+	/// Changes the number of files downloaded at the same time.
+	/// Downloads that are running are finished even if there are now too
+	/// many of them.
+	pub fn set_workers(&mut self, num: usize)
+	{
+		self.threadpool.resize(num);
+		self.config.simultaneous_downloads = num;
+		let size = self.threadpool.size();
+		let count = self.threadpool.worker_count();
+		let plural = if size > 1 { "s" } else { "" };
+		let notif = if count.active > size
+		{
+			format!(
+				"Downloading up to {size} file{plural} at a time, after the {} running now.",
+				count.active
+			)
+		}
+		else
+		{
+			format!("Downloading up to {size} file{plural} at a time ({} busy).", count.active)
+		};
+		self.notif_to_ui(notif, false);
+	}
+
+	/// Reads the config file again and applies the new settings: the
+	/// number of downloads and syncs at a time, the delay between
+	/// requests to the same host, and the keybindings and colors in the
+	/// UI. Everything else read from the config is looked up as needed,
+	/// apart from the options read only at startup, for which the user is
+	/// told to restart. If the file has an error, the current settings
+	/// are kept.
+	pub fn reload_config(&mut self)
+	{
+		let new = match Config::new(&self.config_path)
+		{
+			Ok(config) => config,
+			Err(err) => {
+				self.notif_to_ui(format!("Could not reload config: {err:#}"), true);
+				return;
+			}
+		};
+
+		if new.simultaneous_downloads != self.threadpool.size()
+		{
+			self.threadpool.resize(new.simultaneous_downloads);
+		}
+		if new.sync_threads != self.sync_threadpool.size()
+		{
+			self.sync_threadpool.resize(new.sync_threads);
+		}
+		if new.same_host_delay != self.config.same_host_delay
+		{
+			self.host_throttle = HostThrottle::new(new.same_host_delay);
+		}
+		let restart = self.config.restart_required(&new);
+
+		self.tx_to_ui
+			.send(MainMessage::UiReloadConfig(new.keybindings.clone(), new.colors.clone()))
+			.expect("Thread messaging error");
+		self.config = new;
+
+		if restart.is_empty()
+		{
+			self.notif_to_ui("Reloaded config.toml.".to_string(), false);
+		}
+		else
+		{
+			self.notif_to_ui(
+				format!("Reloaded config.toml; restart to apply {}.", restart.join(", ")),
+				false,
+			);
+		}
+	}
+
+	/// Opens the config file in an editor, which the UI runs in the
+	/// foreground. Once the editor exits, a `Message::EditorExit` comes
+	/// back here, and the config file is reloaded unless the editor
+	/// failed.
+	pub fn edit_config(&self)
 	{
-		let podcast = self.podcasts.clone_podcast(pod_id).unwrap();
-
-		// TODO: Try to find a way to do this without having
-		// to clone the episode...
-		let mut episode = podcast.episodes.clone_episode(ep_id).unwrap();
-		episode.played = played;
-
-		let _ = self.db.set_played_status(episode.id, played);
-		podcast.episodes.replace(ep_id, episode);
+		match play_file::editor_args(self.config.editor_command.as_deref(), &self.config_path)
+		{
+			Ok(args) => self.tx_to_ui
+				.send(MainMessage::UiRunEditor(args))
+				.expect("Thread messaging error"),
+			Err(err) => self.notif_to_ui(format!("Error: {err}"), true),
+		}
+	}
 
-		self.podcasts.replace(pod_id, podcast);
-		self.update_filters(self.filters, true);
+	/// Gathers statistics about the library from the database. The disk
+	/// usage of downloaded files is added up in the threadpool, which
+	/// sends the statistics back when done.
+	pub fn compute_stats(&self)
+	{
+		let mut stats = match self.db.get_stats()
+		{
+			Ok(stats) => LibraryStats::new(stats),
+			Err(_) => {
+				self.notif_to_ui("Could not read statistics from database".to_string(), true);
+				return;
+			}
+		};
+		let tx_to_main = self.tx_to_main.clone();
+		self.threadpool.execute(Priority::High, move || {
+			stats.compute_disk_usage();
+			tx_to_main
+				.send(Message::Stats(stats))
+				.expect("Thread messaging error");
+		});
 	}
 
 	/// Given a podcast, it marks all episodes for that podcast as
@@ -560,12 +1991,13 @@ impl MainController
 	pub fn mark_all_played(&self, pod_id: i64, played: bool)
 	{
 		let podcast = self.podcasts.clone_podcast(pod_id).unwrap();
+		if self.db.set_all_played_status(pod_id, played).is_err()
 		{
-			let borrowed_ep_list = podcast.episodes.borrow_order();
-			for ep in borrowed_ep_list.iter()
-			{
-				let _ = self.db.set_played_status(*ep, played);
-			}
+			self.notif_to_ui(
+				"Could not update episodes in database".to_string(),
+				true
+			);
+			return;
 		}
 		podcast.episodes.replace_all(
 			self.db
@@ -574,14 +2006,16 @@ impl MainController
 		);
 
 		self.podcasts.replace(pod_id, podcast);
-		self.update_filters(self.filters, true);
+		self.update_filters(&self.filters, true);
 	}
 
 	/// Given a podcast index (and not an episode index), this will send
 	/// a vector of jobs to the threadpool to download all episodes in
 	/// the podcast. If given an episode index as well, it will download
-	/// just that episode.
-	pub fn download(&mut self, pod_id: i64, ep_id: Option<i64>)
+	/// just that episode. Downloads the user asked for have high
+	/// priority, while automatic downloads of new episodes have low
+	/// priority.
+	pub fn download(&mut self, pod_id: i64, ep_id: Option<i64>, priority: Priority)
 	{
 		let pod_title;
 		let mut ep_data = Vec::new();
@@ -647,26 +2081,23 @@ impl MainController
 		if !ep_data.is_empty()
 		{
 			// add directory for podcast, create if it does not exist
-			let dir_name = sanitize_with_options(&pod_title, Options {
-				truncate: true,
-				// for simplicity, we'll just use
-				// Windows-friendly paths for everyone
-				windows: true,
-				replacement: "",
-			});
-			match self.create_podcast_dir(dir_name)
+			match self.create_podcast_dir(podcast_dir_name(&pod_title))
 			{
 				Ok(path) => {
 					for ep in ep_data.iter()
 					{
 						self.download_tracker.insert(ep.id);
 					}
+					let ep_ids: Vec<i64> = ep_data.iter().map(|ep| ep.id).collect();
+					self.set_downloading(pod_id, &ep_ids, true);
+					self.update_filters(&self.filters, true);
 					downloads::download_list(
 						ep_data,
 						&path,
 						self.config.max_retries,
 						&self.config.filename_prefix,
 						&self.config.filename_suffix,
+						priority,
 						&self.threadpool,
 						self.tx_to_main.clone(),
 					);
@@ -680,11 +2111,68 @@ impl MainController
 		}
 	}
 
+	/// If cover art downloads are turned on, downloads the cover art of
+	/// the podcast with the given feed URL, unless it has already been
+	/// downloaded and its URL has not changed since (`old_image_url`).
+	pub fn download_cover(&self, url: &str, old_image_url: Option<String>)
+	{
+		if !self.config.download_covers
+		{
+			return;
+		}
+		let pod_data = self.podcasts.filter_map(|pod| {
+			if pod.url == url
+			{
+				Some((pod.id, pod.title.clone(), pod.image_url.clone(), pod.cover_path.clone()))
+			}
+			else
+			{
+				None
+			}
+		});
+		let (pod_id, pod_title, image_url, cover_path) = match pod_data.into_iter().next()
+		{
+			Some((id, title, Some(image_url), cover_path)) => (id, title, image_url, cover_path),
+			_ => return,
+		};
+		let have_cover = cover_path.is_some_and(|path| path.exists());
+		if have_cover && old_image_url.as_ref() == Some(&image_url)
+		{
+			return;
+		}
+		if let Ok(path) = self.create_podcast_dir(podcast_dir_name(&pod_title))
+		{
+			downloads::download_cover(
+				pod_id,
+				image_url,
+				&path,
+				self.config.max_retries,
+				&self.threadpool,
+				self.tx_to_main.clone(),
+			);
+		}
+	}
+
+	/// Records the location of cover art once it has been downloaded.
+	pub fn cover_complete(&mut self, pod_id: i64, path: PathBuf)
+	{
+		if self.db.set_cover_path(pod_id, &path).is_err()
+		{
+			return;
+		}
+		if let Some(mut podcast) = self.podcasts.clone_podcast(pod_id)
+		{
+			podcast.cover_path = Some(path);
+			self.podcasts.replace(pod_id, podcast);
+		}
+	}
+
 	/// Handles logic for what to do when a download successfully completes.
 	pub fn download_complete(&mut self, ep_data: EpData)
 	{
 		let file_path = ep_data.file_path.unwrap();
-		let res = self.db.insert_file(ep_data.id, &file_path);
+		let file_size = fs::metadata(&file_path).ok().map(|meta| meta.len());
+		let res = self.db.insert_file(ep_data.id, &file_path, file_size);
 		if res.is_err()
 		{
 			self.notif_to_ui(
@@ -701,6 +2189,9 @@ impl MainController
 			let podcast = self.podcasts.clone_podcast(ep_data.pod_id).unwrap();
 			let mut episode = podcast.episodes.clone_episode(ep_data.id).unwrap();
 			episode.path = Some(file_path);
+			episode.downloaded_at = Some(Utc::now());
+			episode.file_size = file_size;
+			episode.downloading = false;
 			podcast.episodes.replace(ep_data.id, episode);
 		}
 
@@ -711,7 +2202,35 @@ impl MainController
 			self.notif_to_ui("Downloads complete.".to_string(), false);
 		}
 
-		self.update_filters(self.filters, true);
+		self.update_filters(&self.filters, true);
+	}
+
+	/// Handles logic for what to do when a download fails: the episode
+	/// is no longer shown as being downloaded, and can be downloaded
+	/// again.
+	pub fn download_failed(&mut self, ep_data: &EpData)
+	{
+		self.set_downloading(ep_data.pod_id, &[ep_data.id], false);
+		self.download_tracker.remove(&ep_data.id);
+		self.update_tracker_notif();
+		self.update_filters(&self.filters, true);
+	}
+
+	/// Marks episodes of a podcast as being downloaded or not, so that
+	/// this can be shown in the episode menu.
+	fn set_downloading(&self, pod_id: i64, ep_ids: &[i64], downloading: bool)
+	{
+		if let Some(podcast) = self.podcasts.clone_podcast(pod_id)
+		{
+			for ep_id in ep_ids
+			{
+				if let Some(mut episode) = podcast.episodes.clone_episode(*ep_id)
+				{
+					episode.downloading = downloading;
+					podcast.episodes.replace(*ep_id, episode);
+				}
+			}
+		}
 	}
 
 	/// Given a podcast title, creates a download directory for that
@@ -741,7 +2260,7 @@ impl MainController
 		podcast.episodes.replace(ep_id, episode);
 
 		self.podcasts.replace(pod_id, podcast);
-		self.update_filters(self.filters, true);
+		self.update_filters(&self.filters, true);
 
 		Ok(())
 	}
@@ -772,7 +2291,7 @@ impl MainController
 					episode.path = None;
 					podcast.episodes.replace(ep_id, episode);
 
-					self.update_filters(self.filters, true);
+					self.update_filters(&self.filters, true);
 					self.notif_to_ui(format!("Deleted \"{title}\""), false);
 				}
 				Err(_) => self.notif_to_ui(
@@ -817,7 +2336,7 @@ impl MainController
 		{
 			success = false;
 		}
-		self.update_filters(self.filters, true);
+		self.update_filters(&self.filters, true);
 
 		if success
 		{
@@ -829,17 +2348,62 @@ impl MainController
 		}
 	}
 
-	/// Removes a podcast from the list, optionally deleting local files
-	/// first
-	pub fn remove_podcast(&mut self, pod_id: i64, delete_files: bool)
+	/// Deletes the downloaded files of several episodes of a podcast from
+	/// the user's local system.
+	pub fn delete_multi(&self, pod_id: i64, ep_ids: &[i64])
 	{
-		if delete_files
+		let mut eps_to_remove = Vec::new();
+		let mut success = true;
 		{
-			self.delete_files(pod_id);
+			let borrowed_map = self.podcasts.borrow_map();
+			let podcast = borrowed_map.get(&pod_id).unwrap();
+			let mut borrowed_ep_map = podcast.episodes.borrow_map();
+
+			for ep_id in ep_ids.iter()
+			{
+				if let Some(ep) = borrowed_ep_map.get_mut(ep_id)
+				{
+					if let Some(path) = ep.path.clone()
+					{
+						match fs::remove_file(path)
+						{
+							Ok(_) => {
+								eps_to_remove.push(ep.id);
+								ep.path = None;
+							}
+							Err(_) => success = false,
+						}
+					}
+				}
+			}
+		}
+
+		if self.db.remove_files(&eps_to_remove).is_err()
+		{
+			success = false;
+		}
+		self.update_filters(&self.filters, true);
+
+		if success
+		{
+			let count = eps_to_remove.len();
+			let plural = if count == 1 { "" } else { "s" };
+			self.notif_to_ui(format!("Deleted {count} file{plural}"), false);
+		}
+		else
+		{
+			self.notif_to_ui("Error while deleting files".to_string(), true);
 		}
+	}
 
-		let pod_id = self.podcasts.map_single(pod_id, |pod| pod.id).unwrap();
-		let res = self.db.remove_podcast(pod_id);
+	/// Removes a podcast from the list, optionally deleting local files
+	/// first
+	pub fn remove_podcast(&mut self, pod_id: i64, delete_files: bool)
+	{
+		// the podcast is only marked as removed, so that the removal can
+		// be undone; local files are deleted when it is purged
+		let title = self.podcasts.map_single(pod_id, |pod| pod.title.clone()).unwrap();
+		let res = self.db.set_removed(pod_id, Some(Utc::now()), delete_files);
 		if res.is_err()
 		{
 			self.notif_to_ui(
@@ -855,9 +2419,256 @@ impl MainController
 					.expect("Error retrieving info from database."),
 			);
 		}
-		self.tx_to_ui
-			.send(MainMessage::UiUpdateMenus)
-			.expect("Thread messaging error");
+		self.update_filters(&self.filters, true);
+		let message = match self.config.keybindings.key_for_action(UserAction::UndoRemoval)
+		{
+			Some(key) => format!("Removed \"{title}\" — press '{key}' to undo"),
+			None => format!("Removed \"{title}\""),
+		};
+		self.notif_to_ui(message, false);
+	}
+
+	/// Restores the podcast that was removed most recently, if it has
+	/// not been purged yet.
+	pub fn undo_removal(&mut self)
+	{
+		let pod_id = match self.db.last_removed()
+		{
+			Ok(Some(pod_id)) => pod_id,
+			Ok(None) => {
+				self.notif_to_ui("No removed podcast to restore".to_string(), false);
+				return;
+			}
+			Err(_) => {
+				self.notif_to_ui("Could not read podcasts from database".to_string(), true);
+				return;
+			}
+		};
+		if self.db.set_removed(pod_id, None, false).is_err()
+		{
+			self.notif_to_ui("Could not restore podcast in database".to_string(), true);
+			return;
+		}
+		{
+			self.podcasts.replace_all(
+				self.db
+					.get_podcasts()
+					.expect("Error retrieving info from database."),
+			);
+		}
+		self.update_filters(&self.filters, true);
+		if let Some(title) = self.podcasts.map_single(pod_id, |pod| pod.title.clone())
+		{
+			self.notif_to_ui(format!("Restored \"{title}\""), false);
+		}
+	}
+
+	/// Purges the podcasts that were removed long enough ago that the
+	/// removal can no longer be undone. With `undo_removal_minutes` set
+	/// to 0, they are only purged on exit.
+	pub fn check_purge(&self)
+	{
+		let minutes = self.config.undo_removal_minutes;
+		if minutes > 0
+		{
+			self.purge_removed(Utc::now() - chrono::Duration::minutes(minutes as i64));
+		}
+	}
+
+	/// Deletes the podcasts that were removed at or before the given time
+	/// from the database, along with their local files if the user asked
+	/// for that when removing them.
+	pub fn purge_removed(&self, before: DateTime<Utc>)
+	{
+		let removed = match self.db.get_removed(before)
+		{
+			Ok(removed) => removed,
+			Err(_) => return,
+		};
+		for (pod_id, delete_files) in removed
+		{
+			if delete_files
+			{
+				let episodes = self.db.get_episodes(pod_id, true).unwrap_or_default();
+				for path in episodes.into_iter().filter_map(|ep| ep.path)
+				{
+					let _ = fs::remove_file(path);
+				}
+			}
+			if self.db.remove_podcast(pod_id).is_err()
+			{
+				self.notif_to_ui(
+					"Could not remove podcast from database".to_string(),
+					true
+				);
+			}
+		}
+	}
+
+	/// Sets a custom title for a podcast. Using the title from the feed
+	/// removes the custom title, so that the podcast follows any changes
+	/// to the feed title again.
+	pub fn rename_podcast(&mut self, pod_id: i64, title: String)
+	{
+		let feed_title = match self.podcasts.map_single(pod_id, |pod| pod.feed_title.clone())
+		{
+			Some(feed_title) => feed_title,
+			None => return,
+		};
+		let custom_title = if title == feed_title
+		{
+			None
+		}
+		else
+		{
+			Some(title.as_str())
+		};
+		if self.db.set_custom_title(pod_id, custom_title).is_err()
+		{
+			self.notif_to_ui(
+				"Could not rename podcast in database".to_string(),
+				true
+			);
+			return;
+		}
+		self.podcasts.replace_all(
+			self.db
+				.get_podcasts()
+				.expect("Error retrieving info from database."),
+		);
+		self.update_filters(&self.filters, true);
+	}
+
+	/// Replaces the tags of a podcast.
+	pub fn set_tags(&mut self, pod_id: i64, tags: Vec<String>)
+	{
+		if self.db.set_tags(pod_id, &tags).is_err()
+		{
+			self.notif_to_ui(
+				"Could not save tags in database".to_string(),
+				true
+			);
+			return;
+		}
+		self.podcasts.replace_all(
+			self.db
+				.get_podcasts()
+				.expect("Error retrieving info from database."),
+		);
+		self.update_filters(&self.filters, true);
+	}
+
+	/// Adds a podcast to the favorites, which are listed first, or
+	/// removes it from them.
+	pub fn set_favorite(&mut self, pod_id: i64, favorite: bool)
+	{
+		let title = match self.podcasts.map_single(pod_id, |pod| pod.title.clone())
+		{
+			Some(title) => title,
+			None => return,
+		};
+		if self.db.set_favorite(pod_id, favorite).is_err()
+		{
+			self.notif_to_ui(
+				"Could not save favorite in database".to_string(),
+				true
+			);
+			return;
+		}
+		let message = if favorite
+		{
+			format!("Added {title} to favorites")
+		}
+		else
+		{
+			format!("Removed {title} from favorites")
+		};
+		self.notif_to_ui(message, false);
+		self.podcasts.replace_all(
+			self.db
+				.get_podcasts()
+				.expect("Error retrieving info from database."),
+		);
+		self.update_filters(&self.filters, true);
+	}
+
+	/// Records when the user started looking at the episodes of a
+	/// podcast, so that the episodes added before then are no longer
+	/// marked as unseen.
+	pub fn podcast_viewed(&mut self, pod_id: i64, time: DateTime<Utc>)
+	{
+		let podcast = match self.podcasts.clone_podcast(pod_id)
+		{
+			Some(podcast) if podcast.has_unseen() => podcast,
+			Some(_) | None => return,
+		};
+		let episodes = match self
+			.db
+			.set_last_viewed(pod_id, time)
+			.and_then(|_| self.db.get_episodes(pod_id, false))
+		{
+			Ok(episodes) => episodes,
+			Err(_) => {
+				self.notif_to_ui(
+					"Could not update podcast in database".to_string(),
+					true
+				);
+				return;
+			}
+		};
+		podcast.episodes.replace_all(episodes);
+		self.podcasts.replace(pod_id, podcast);
+		self.update_filters(&self.filters, true);
+	}
+
+	/// Changes the order in which the episodes of a podcast are listed,
+	/// and lists them again in that order.
+	pub fn set_episode_sort(&mut self, pod_id: i64, sort: EpisodeSort)
+	{
+		let mut podcast = match self.podcasts.clone_podcast(pod_id)
+		{
+			Some(podcast) => podcast,
+			None => return,
+		};
+		let episodes = match self
+			.db
+			.set_episode_sort(pod_id, sort)
+			.and_then(|_| self.db.get_episodes(pod_id, false))
+		{
+			Ok(episodes) => episodes,
+			Err(_) => {
+				self.notif_to_ui(
+					"Could not update podcast in database".to_string(),
+					true
+				);
+				return;
+			}
+		};
+		podcast.episodes.replace_all(episodes);
+		podcast.episode_sort = sort;
+		self.podcasts.replace(pod_id, podcast);
+		self.notif_to_ui(format!("Sorting episodes {}", sort.label()), false);
+		self.update_filters(&self.filters, true);
+	}
+
+	/// Returns the tag that comes after the current tag filter, in
+	/// alphabetical order, out of the tags of all podcasts. Returns None
+	/// (no tag filter) after the last tag, or if no podcast has tags.
+	fn next_tag(&self) -> Option<String>
+	{
+		let mut tags: Vec<String> = self
+			.podcasts
+			.map(|pod| pod.tags.clone(), false)
+			.into_iter()
+			.flatten()
+			.collect();
+		tags.sort();
+		tags.dedup();
+		return match &self.filters.tag
+		{
+			Some(current) => tags.into_iter().find(|tag| tag > current),
+			None => tags.into_iter().next(),
+		};
 	}
 
 	/// Removes an episode from the list, optionally deleting local files
@@ -879,9 +2690,7 @@ impl MainController
 					.expect("Error retrieving info from database."),
 			);
 		}
-		self.tx_to_ui
-			.send(MainMessage::UiUpdateMenus)
-			.expect("Thread messaging error");
+		self.update_filters(&self.filters, true);
 	}
 
 	/// Removes all episodes for a podcast from the list, optionally
@@ -903,57 +2712,39 @@ impl MainController
 		podcast.episodes = LockVec::new(Vec::new());
 		self.podcasts.replace(pod_id, podcast);
 
-		self.tx_to_ui
-			.send(MainMessage::UiUpdateMenus)
-			.expect("Thread messaging error");
+		self.update_filters(&self.filters, true);
 	}
 
-	/// Updates the user-selected filters to show only played/unplayed
-	/// or downloaded/not downloaded episodes.
-	pub fn update_filters(&self, filters: Filters, update_menus: bool)
+	/// Sorts the podcasts, and updates the user-selected filters to show
+	/// only played/unplayed or downloaded/not downloaded episodes, with
+	/// or without archived episodes, only the podcasts with the selected
+	/// tag, and only the podcasts and episodes matching the search text.
+	pub fn update_filters(&self, filters: &Filters, update_menus: bool)
 	{
-		{
-			let (pod_map, pod_order, mut pod_filtered_order) = self.podcasts.borrow();
-			let mut new_filtered_pods = Vec::new();
-			for pod_id in pod_order.iter()
-			{
-				let pod = pod_map.get(pod_id).unwrap();
-				let new_filter = pod.episodes.filter_map(|ep| {
-					let play_filter = match filters.played
-					{
-						FilterStatus::All => false,
-						FilterStatus::PositiveCases => !ep.is_played(),
-						FilterStatus::NegativeCases => ep.is_played(),
-					};
-					let download_filter = match filters.downloaded
-					{
-						FilterStatus::All => false,
-						FilterStatus::PositiveCases => ep.path.is_none(),
-						FilterStatus::NegativeCases => ep.path.is_some(),
-					};
-					if !(play_filter | download_filter)
-					{
-						return Some(ep.id);
-					}
-					else
-					{
-						return None;
-					}
-				});
-				if !new_filter.is_empty()
-				{
-					new_filtered_pods.push(pod.id);
-				}
-				let mut filtered_order = pod.episodes.borrow_filtered_order();
-				*filtered_order = new_filter;
-			}
-			*pod_filtered_order = new_filtered_pods;
-		}
+		self.podcasts.sort_podcasts(self.podcast_sort);
+		filters.apply(&self.podcasts);
 		if update_menus
 		{
 			self.tx_to_ui
 				.send(MainMessage::UiUpdateMenus)
 				.expect("Thread messaging error");
+			// episodes may have left the queue, e.g., if they were
+			// removed, and titles may have changed
+			self.update_queue();
 		}
 	}
 }
+
+
+/// Returns the name of the directory that episodes of a podcast are
+/// downloaded to, based on the podcast title.
+fn podcast_dir_name(pod_title: &str) -> String
+{
+	return sanitize_with_options(pod_title, Options {
+		truncate: true,
+		// for simplicity, we'll just use
+		// Windows-friendly paths for everyone
+		windows: true,
+		replacement: "",
+	});
+}
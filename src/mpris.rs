@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use zbus::blocking::{Connection, ConnectionBuilder};
+use zbus::dbus_interface;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+/// Name taken on the session bus. If another instance of shellcaster
+/// already has it, a name with the process ID added is used instead, as
+/// the MPRIS specification suggests.
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.shellcaster";
+
+/// Path of the object with the MPRIS interfaces
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+
+/// Name of the interface with the details of what is playing
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// Track ID to use when nothing is playing
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+/// Details of an episode that is playing, as shown by desktop widgets.
+#[derive(Debug, Clone)]
+pub struct Track
+{
+	pub pod_id: i64,
+	pub ep_id: i64,
+	pub title: String,
+	pub podcast: String,
+	pub url: String,
+	pub art_url: Option<String>,
+	pub duration: Option<i64>,
+}
+
+/// Publishes the episode that is playing on the session bus, with the
+/// MPRIS interface, so that desktop widgets and media keys can show
+/// it. Shellcaster does not control the players it starts, so only the
+/// metadata and whether anything is playing are published; playback
+/// cannot be controlled through it.
+pub struct NowPlaying
+{
+	connection: Connection,
+}
+
+impl NowPlaying
+{
+	/// Connects to the session bus and publishes the MPRIS interfaces,
+	/// with nothing playing. Returns an error if there is no session bus,
+	/// e.g., over SSH.
+	pub fn connect() -> Result<Self>
+	{
+		let connection = match build_connection(BUS_NAME.to_string())
+		{
+			Ok(connection) => connection,
+			Err(_) => build_connection(format!("{BUS_NAME}.instance{}", std::process::id()))?,
+		};
+		return Ok(Self {
+			connection: connection,
+		});
+	}
+
+	/// Publishes an episode as playing. If other players are running
+	/// already, this episode is shown until its player exits.
+	pub fn started(&self, track: Track) -> Result<()>
+	{
+		let iface = self.connection.object_server().interface::<_, Player>(OBJECT_PATH)?;
+		iface.get_mut().tracks.push(track);
+		return self.changed(&iface.get());
+	}
+
+	/// Removes an episode whose player has exited. Once no players are
+	/// left, the status changes to "Stopped".
+	pub fn stopped(&self, pod_id: i64, ep_id: i64) -> Result<()>
+	{
+		let iface = self.connection.object_server().interface::<_, Player>(OBJECT_PATH)?;
+		{
+			let mut player = iface.get_mut();
+			match player.tracks.iter().position(|t| t.pod_id == pod_id && t.ep_id == ep_id)
+			{
+				Some(index) => {
+					player.tracks.remove(index);
+				}
+				None => return Ok(()),
+			}
+		}
+		return self.changed(&iface.get());
+	}
+
+	/// Signals that the status and metadata of the player have changed.
+	fn changed(&self, player: &Player) -> Result<()>
+	{
+		let mut properties: HashMap<&str, Value> = HashMap::new();
+		properties.insert("PlaybackStatus", Value::from(player.playback_status()));
+		properties.insert("Metadata", Value::from(player.metadata()));
+		self.connection.emit_signal(
+			None::<&str>,
+			OBJECT_PATH,
+			"org.freedesktop.DBus.Properties",
+			"PropertiesChanged",
+			&(PLAYER_INTERFACE, properties, Vec::<&str>::new()),
+		)?;
+		return Ok(());
+	}
+}
+
+/// Connects to the session bus under the given name, and serves the
+/// MPRIS interfaces.
+fn build_connection(name: String) -> zbus::Result<Connection>
+{
+	return ConnectionBuilder::session()?
+		.name(name)?
+		.serve_at(OBJECT_PATH, Root)?
+		.serve_at(OBJECT_PATH, Player::default())?
+		.build();
+}
+
+/// Returns the MPRIS metadata of an episode, or of nothing playing.
+fn track_metadata(track: Option<&Track>) -> HashMap<String, Value<'static>>
+{
+	let mut metadata = HashMap::new();
+	let track = match track
+	{
+		Some(track) => track,
+		None => {
+			metadata.insert("mpris:trackid".to_string(), Value::from(object_path(NO_TRACK)));
+			return metadata;
+		}
+	};
+	let track_id = format!("/org/shellcaster/episode/{}", track.ep_id);
+	metadata.insert("mpris:trackid".to_string(), Value::from(object_path(&track_id)));
+	metadata.insert("xesam:title".to_string(), Value::from(track.title.clone()));
+	metadata.insert("xesam:album".to_string(), Value::from(track.podcast.clone()));
+	metadata.insert("xesam:artist".to_string(), Value::from(vec![track.podcast.clone()]));
+	metadata.insert("xesam:url".to_string(), Value::from(track.url.clone()));
+	if let Some(art_url) = &track.art_url
+	{
+		metadata.insert("mpris:artUrl".to_string(), Value::from(art_url.clone()));
+	}
+	if let Some(duration) = track.duration
+	{
+		// in microseconds
+		metadata.insert("mpris:length".to_string(), Value::from(duration * 1_000_000));
+	}
+	return metadata;
+}
+
+/// Makes an object path that is known to be valid.
+fn object_path(path: &str) -> ObjectPath<'static>
+{
+	return ObjectPath::try_from(path.to_string()).expect("Invalid object path");
+}
+
+/// The org.mpris.MediaPlayer2 interface, which describes shellcaster
+/// itself.
+struct Root;
+
+#[dbus_interface(name = "org.mpris.MediaPlayer2")]
+impl Root
+{
+	fn raise(&self) {}
+
+	fn quit(&self) {}
+
+	#[dbus_interface(property)]
+	fn can_quit(&self) -> bool
+	{
+		return false;
+	}
+
+	#[dbus_interface(property)]
+	fn can_raise(&self) -> bool
+	{
+		return false;
+	}
+
+	#[dbus_interface(property)]
+	fn has_track_list(&self) -> bool
+	{
+		return false;
+	}
+
+	#[dbus_interface(property)]
+	fn identity(&self) -> String
+	{
+		return "Shellcaster".to_string();
+	}
+
+	#[dbus_interface(property)]
+	fn supported_uri_schemes(&self) -> Vec<String>
+	{
+		return Vec::new();
+	}
+
+	#[dbus_interface(property)]
+	fn supported_mime_types(&self) -> Vec<String>
+	{
+		return Vec::new();
+	}
+}
+
+/// The org.mpris.MediaPlayer2.Player interface, with the episodes that
+/// are playing, most recently started last. None of the controls are
+/// supported, so the methods do nothing.
+#[derive(Default)]
+struct Player
+{
+	tracks: Vec<Track>,
+}
+
+#[dbus_interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player
+{
+	fn next(&self) {}
+
+	fn previous(&self) {}
+
+	fn pause(&self) {}
+
+	fn play_pause(&self) {}
+
+	fn stop(&self) {}
+
+	fn play(&self) {}
+
+	fn seek(&self, _offset: i64) {}
+
+	fn set_position(&self, _track_id: ObjectPath<'_>, _position: i64) {}
+
+	fn open_uri(&self, _uri: &str) {}
+
+	#[dbus_interface(property)]
+	fn playback_status(&self) -> String
+	{
+		if self.tracks.is_empty()
+		{
+			return "Stopped".to_string();
+		}
+		return "Playing".to_string();
+	}
+
+	#[dbus_interface(property)]
+	fn metadata(&self) -> HashMap<String, OwnedValue>
+	{
+		return track_metadata(self.tracks.last())
+			.into_iter()
+			.map(|(key, value)| (key, OwnedValue::from(value)))
+			.collect();
+	}
+
+	#[dbus_interface(property)]
+	fn rate(&self) -> f64
+	{
+		return 1.0;
+	}
+
+	#[dbus_interface(property)]
+	fn minimum_rate(&self) -> f64
+	{
+		return 1.0;
+	}
+
+	#[dbus_interface(property)]
+	fn maximum_rate(&self) -> f64
+	{
+		return 1.0;
+	}
+
+	#[dbus_interface(property)]
+	fn volume(&self) -> f64
+	{
+		return 1.0;
+	}
+
+	#[dbus_interface(property)]
+	fn position(&self) -> i64
+	{
+		return 0;
+	}
+
+	#[dbus_interface(property)]
+	fn can_go_next(&self) -> bool
+	{
+		return false;
+	}
+
+	#[dbus_interface(property)]
+	fn can_go_previous(&self) -> bool
+	{
+		return false;
+	}
+
+	#[dbus_interface(property)]
+	fn can_play(&self) -> bool
+	{
+		return false;
+	}
+
+	#[dbus_interface(property)]
+	fn can_pause(&self) -> bool
+	{
+		return false;
+	}
+
+	#[dbus_interface(property)]
+	fn can_seek(&self) -> bool
+	{
+		return false;
+	}
+
+	#[dbus_interface(property)]
+	fn can_control(&self) -> bool
+	{
+		return false;
+	}
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn metadata_of_latest_track()
+	{
+		let mut player = Player::default();
+		assert_eq!(player.playback_status(), "Stopped");
+		assert_eq!(player.metadata().len(), 1);
+
+		for (ep_id, art_url) in [(1, None), (2, Some("file:///tmp/cover.jpg".to_string()))]
+		{
+			player.tracks.push(Track {
+				pod_id: 1,
+				ep_id: ep_id,
+				title: format!("Episode {ep_id}"),
+				podcast: "Podcast".to_string(),
+				url: format!("https://example.com/{ep_id}.mp3"),
+				art_url: art_url,
+				duration: Some(90),
+			});
+		}
+		assert_eq!(player.playback_status(), "Playing");
+		let metadata = track_metadata(player.tracks.last());
+		assert_eq!(metadata["xesam:title"], Value::from("Episode 2"));
+		assert_eq!(metadata["mpris:artUrl"], Value::from("file:///tmp/cover.jpg"));
+		assert_eq!(metadata["mpris:length"], Value::from(90_000_000_i64));
+		assert!(!track_metadata(player.tracks.first()).contains_key("mpris:artUrl"));
+	}
+}
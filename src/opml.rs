@@ -1,48 +1,248 @@
+use std::collections::BTreeMap;
+
 use anyhow::{anyhow, Result};
 use chrono::Utc;
 use opml::{Body, Head, Outline, OPML};
+use url::Url;
 
-use crate::feeds::PodcastFeed;
+use crate::feeds::{self, PodcastFeed};
 use crate::types::*;
 
+/// Feeds read from an OPML file, sorted out for importing: `new` are the
+/// feeds to add, `subscribed` describes the feeds that are already in
+/// the database, and `skipped` the entries that cannot be imported,
+/// along with the reason, one line each.
+#[derive(Debug, Default)]
+pub struct ImportPlan
+{
+	pub new: Vec<PodcastFeed>,
+	pub subscribed: Vec<String>,
+	pub skipped: Vec<String>,
+}
+
+impl ImportPlan
+{
+	/// Moves the feeds that are already subscribed to out of the ones to
+	/// add, given the titles and URLs of the existing podcasts. URLs that
+	/// differ only slightly from an existing one (see
+	/// `feeds::normalize_url()`) are skipped, as are feeds that appear
+	/// more than once in the file.
+	pub fn filter_existing(&mut self, existing: &[(String, String)])
+	{
+		let existing: Vec<(&str, &str, String)> = existing
+			.iter()
+			.map(|(title, url)| (title.as_str(), url.as_str(), feeds::normalize_url(url)))
+			.collect();
+		let mut seen = Vec::new();
+		let mut new = Vec::new();
+		for feed in std::mem::take(&mut self.new).into_iter()
+		{
+			let normalized = feeds::normalize_url(&feed.url);
+			if let Some((title, _, _)) = existing.iter().find(|(_, url, _)| *url == feed.url)
+			{
+				self.subscribed.push(format!("{} ({title})", feed.url));
+			}
+			else if let Some((title, url, _)) =
+				existing.iter().find(|(_, _, other)| *other == normalized)
+			{
+				self.skipped.push(format!("{}: already subscribed to {title} as {url}", feed.url));
+			}
+			else if seen.contains(&normalized)
+			{
+				self.skipped.push(format!("{}: listed more than once", feed.url));
+			}
+			else
+			{
+				seen.push(normalized);
+				new.push(feed);
+			}
+		}
+		self.new = new;
+	}
+}
+
 /// Import a list of podcast feeds from an OPML file. Supports
-/// v1.0, v1.1, and v2.0 OPML files.
-pub fn import(xml: String) -> Result<Vec<PodcastFeed>>
+/// v1.0, v1.1, and v2.0 OPML files. The `category` attribute of each
+/// feed is used for its tags. Feeds may be grouped in folders (outlines
+/// without a feed URL that contain other outlines), as many podcast apps
+/// do; each feed is also tagged with the path of its folder, e.g.,
+/// "Tech/News". A feed listed in several folders gets the tags of all of
+/// them, and is only imported once. Entries without a feed URL
+/// (`xmlUrl`) or any outlines in them are skipped, as are those whose
+/// URL cannot be retrieved; the reasons are kept in the plan.
+pub fn import(xml: String) -> Result<ImportPlan>
 {
 	return match OPML::from_str(&xml)
 	{
 		Err(err) => Err(anyhow!(err)),
 		Ok(opml) => {
-			let mut feeds = Vec::new();
-			for pod in opml.body.outlines.into_iter()
-			{
-				if pod.xml_url.is_some()
+			let mut plan = ImportPlan::default();
+			read_outlines(opml.body.outlines, None, &mut plan);
+			Ok(plan)
+		}
+	};
+}
+
+/// Import a list of podcast feeds from a text file with one feed URL per
+/// line, as many tools write them. Blank lines and lines starting with
+/// "#" are left out. Lines that are not a feed URL are skipped, along
+/// with their line number, as are feeds listed more than once.
+pub fn import_text(text: &str) -> ImportPlan
+{
+	let mut plan = ImportPlan::default();
+	for (n, line) in text.lines().enumerate()
+	{
+		let url = line.trim().trim_start_matches('\u{feff}');
+		if url.is_empty() || url.starts_with('#')
+		{
+			continue;
+		}
+		if let Err(reason) = check_url(url)
+		{
+			plan.skipped.push(format!("Line {}: {reason} ({url})", n + 1));
+		}
+		else if plan.new.iter().any(|feed| feed.url == url)
+		{
+			plan.skipped.push(format!("{url}: listed more than once"));
+		}
+		else
+		{
+			plan.new.push(PodcastFeed::new(None, url.to_string(), None));
+		}
+	}
+	return plan;
+}
+
+/// Adds the feeds in a list of outlines to the import plan, going into
+/// folders as well. `folder` is the path of the folder the outlines are
+/// in, if any.
+fn read_outlines(outlines: Vec<Outline>, folder: Option<&str>, plan: &mut ImportPlan)
+{
+	for pod in outlines.into_iter()
+	{
+		let title = outline_title(&pod);
+		let xml_url = pod.xml_url.as_deref().map(str::trim).filter(|url| !url.is_empty());
+		match xml_url
+		{
+			Some(xml_url) => {
+				let name = title.as_deref().unwrap_or(xml_url);
+				if let Err(reason) = check_url(xml_url)
 				{
-					// match against title attribute first -- if this is
-					// not set or empty, then match against the text
-					// attribute; this must be set, but can be empty
-					let temp_title = pod.title.filter(|t| !t.is_empty());
-					let title = match temp_title
-					{
-						Some(t) => Some(t),
-						None => {
-							if pod.text.is_empty() {
-								None
-							} else {
-								Some(pod.text)
-							}
-						}
-					};
-					feeds.push(PodcastFeed::new(None, pod.xml_url.unwrap(), title));
+					plan.skipped.push(format!("{name}: {reason} ({xml_url})"));
+					continue;
 				}
+				let mut tags = pod.category.as_deref().map(parse_tags).unwrap_or_default();
+				tags.extend(folder.map(str::to_string));
+				// the same feed may be listed in several folders; it is
+				// only imported once, with the tags of each listing
+				let index = match plan.new.iter().position(|feed| feed.url == xml_url)
+				{
+					Some(index) => {
+						plan.skipped.push(format!("{xml_url}: listed more than once"));
+						index
+					}
+					None => {
+						plan.new.push(PodcastFeed::new(None, xml_url.to_string(), title.clone()));
+						plan.new.len() - 1
+					}
+				};
+				let feed = &mut plan.new[index];
+				feed.tags.extend(tags);
+				feed.tags.sort();
+				feed.tags.dedup();
+			}
+			None if !pod.outlines.is_empty() => {
+				// folders without a name add nothing to the path
+				let path = match (folder, title.as_deref().map(folder_name))
+				{
+					(Some(parent), Some(name)) if !name.is_empty() => Some(format!("{parent}/{name}")),
+					(None, Some(name)) if !name.is_empty() => Some(name),
+					_ => folder.map(str::to_string),
+				};
+				read_outlines(pod.outlines, path.as_deref(), plan);
 			}
-			Ok(feeds)
+			None => plan.skipped.push(format!(
+				"{}: no feed URL (xmlUrl)",
+				title.as_deref().unwrap_or("Untitled entry")
+			)),
+		}
+	}
+}
+
+/// Checks that a feed URL can be retrieved: it must be a web address
+/// (http or https) or a local file. Returns the reason otherwise.
+fn check_url(url: &str) -> Result<(), String>
+{
+	if feeds::local_path(url).is_some()
+	{
+		return Ok(());
+	}
+	return match Url::parse(url)
+	{
+		Err(err) => Err(format!("invalid feed URL: {err}")),
+		Ok(parsed) if !matches!(parsed.scheme(), "http" | "https") => {
+			Err(format!("unsupported feed URL scheme \"{}\"", parsed.scheme()))
 		}
+		Ok(parsed) if parsed.host_str().is_none() => Err("feed URL has no host".to_string()),
+		Ok(_) => Ok(()),
 	};
 }
 
-/// Converts the current set of podcast feeds to the OPML format
-pub fn export(podcasts: Vec<Podcast>) -> OPML
+/// Returns the title of an outline: its title attribute, or if this is
+/// not set or empty, its text attribute, which must be set but can be
+/// empty.
+fn outline_title(outline: &Outline) -> Option<String>
+{
+	return outline
+		.title
+		.clone()
+		.filter(|t| !t.is_empty())
+		.or_else(|| Some(outline.text.clone()).filter(|t| !t.is_empty()));
+}
+
+/// Turns the title of a folder into a part of a tag. Commas and slashes
+/// are replaced, as they separate tags and the parts of a folder's path.
+fn folder_name(title: &str) -> String
+{
+	return title
+		.replace([',', '/'], " ")
+		.split_whitespace()
+		.collect::<Vec<&str>>()
+		.join(" ");
+}
+
+/// Keeps the podcasts to export: those with any of the given tags, along
+/// with those whose ID or title is in `podcasts`. Tags and titles are
+/// compared without regard to case. All podcasts are kept if both lists
+/// are empty.
+pub fn select_podcasts(all: Vec<Podcast>, tags: &[&str], podcasts: &[&str]) -> Vec<Podcast>
+{
+	if tags.is_empty() && podcasts.is_empty()
+	{
+		return all;
+	}
+	return all
+		.into_iter()
+		.filter(|pod| {
+			let tagged = pod.tags.iter().any(|tag| {
+				return tags.iter().any(|wanted| wanted.trim().eq_ignore_ascii_case(tag));
+			});
+			let chosen = podcasts.iter().any(|wanted| {
+				return wanted.parse::<i64>().ok() == Some(pod.id) ||
+					pod.title.eq_ignore_ascii_case(wanted);
+			});
+			return tagged || chosen;
+		})
+		.collect();
+}
+
+/// Converts the current set of podcast feeds to the OPML format. With no
+/// podcasts, the body is left empty. If `grouped` is set, podcasts are
+/// put in folders by tag, with a tag like "Tech/News" making a "News"
+/// folder within "Tech", so that the folders of an imported file are
+/// kept. A podcast with several tags is listed in each of their
+/// folders, and podcasts without tags are listed after the folders.
+pub fn export(podcasts: Vec<Podcast>, grouped: bool) -> OPML
 {
 	let date = Utc::now();
 	let mut opml = OPML {
@@ -54,22 +254,243 @@ pub fn export(podcasts: Vec<Podcast>) -> OPML
 		..Default::default()
 	};
 
-	let mut outlines = Vec::new();
-
+	let mut root = Folder::default();
 	for pod in podcasts.iter()
 	{
-		// opml.add_feed(&pod.title, &pod.url);
-		outlines.push(Outline {
-			text: pod.title.clone(),
-			r#type: Some("rss".to_string()),
-			xml_url: Some(pod.url.clone()),
-			title: Some(pod.title.clone()),
-			..Outline::default()
-		});
+		if !grouped || pod.tags.is_empty()
+		{
+			root.feeds.push(feed_outline(pod));
+			continue;
+		}
+		for tag in pod.tags.iter()
+		{
+			let mut folder = &mut root;
+			for name in tag.split('/').map(str::trim).filter(|name| !name.is_empty())
+			{
+				folder = folder.folders.entry(name.to_string()).or_default();
+			}
+			folder.feeds.push(feed_outline(pod));
+		}
 	}
 
 	opml.body = Body {
-		outlines: outlines,
+		outlines: root.into_outlines(),
 	};
 	return opml;
 }
+
+/// Returns the outline for a podcast's feed.
+fn feed_outline(pod: &Podcast) -> Outline
+{
+	// the text keeps the title from the feed, while the title holds
+	// the one displayed in shellcaster, which may be a custom title
+	return Outline {
+		text: pod.feed_title.clone(),
+		r#type: Some("rss".to_string()),
+		xml_url: Some(pod.url.clone()),
+		title: Some(pod.title.clone()),
+		category: if pod.tags.is_empty() {
+			None
+		} else {
+			Some(pod.tags.join(","))
+		},
+		..Outline::default()
+	};
+}
+
+/// A folder of feeds in an exported OPML file, with the folders within
+/// it, sorted by name.
+#[derive(Debug, Default)]
+struct Folder
+{
+	feeds: Vec<Outline>,
+	folders: BTreeMap<String, Folder>,
+}
+
+impl Folder
+{
+	/// Returns the outlines of the folders within this one, followed by
+	/// those of its feeds.
+	fn into_outlines(self) -> Vec<Outline>
+	{
+		let mut outlines: Vec<Outline> = self
+			.folders
+			.into_iter()
+			.map(|(name, folder)| {
+				return Outline {
+					text: name.clone(),
+					title: Some(name),
+					outlines: folder.into_outlines(),
+					..Outline::default()
+				};
+			})
+			.collect();
+		outlines.extend(self.feeds);
+		return outlines;
+	}
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	fn podcast(id: i64, title: &str, tags: &[&str]) -> Podcast
+	{
+		return Podcast {
+			id: id,
+			title: title.to_string(),
+			feed_title: title.to_string(),
+			sort_title: title.to_lowercase(),
+			url: format!("https://example.com/{id}"),
+			link: None,
+			description: None,
+			author: None,
+			explicit: None,
+			image_url: None,
+			cover_path: None,
+			last_checked: Utc::now(),
+			inactive: false,
+			favorite: false,
+			synced: true,
+			last_error: None,
+			last_error_at: None,
+			tags: tags.iter().map(|tag| tag.to_string()).collect(),
+			episode_sort: EpisodeSort::Newest,
+			episodes: LockVec::new(Vec::new()),
+		};
+	}
+
+	#[test]
+	fn import_plan_sorts_out_feeds()
+	{
+		let xml = r#"<?xml version="1.0"?>
+<opml version="2.0"><head><title>Feeds</title></head><body>
+<outline type="rss" text="New" xmlUrl="https://example.com/new"/>
+<outline type="rss" text="Same" xmlUrl="https://example.com/same"/>
+<outline type="rss" text="Close" xmlUrl="http://example.com/close/?utm_source=x"/>
+<outline type="rss" text="New again" xmlUrl="https://example.com/new/"/>
+<outline type="rss" text="New" xmlUrl=" https://example.com/new "/>
+<outline text="Folder"/>
+<outline type="rss" text="Relative" xmlUrl="feeds/podcast.xml"/>
+<outline type="rss" text="FTP" xmlUrl="ftp://example.com/feed"/>
+<outline type="rss" text="Local" xmlUrl="file:///srv/podcast.xml"/>
+</body></opml>"#;
+		let mut plan = import(xml.to_string()).unwrap();
+		assert_eq!(plan.new.len(), 5);
+		assert_eq!(plan.skipped, vec![
+			"https://example.com/new: listed more than once",
+			"Folder: no feed URL (xmlUrl)",
+			"Relative: invalid feed URL: relative URL without a base (feeds/podcast.xml)",
+			"FTP: unsupported feed URL scheme \"ftp\" (ftp://example.com/feed)",
+		]);
+		plan.skipped.clear();
+		plan.new.pop();
+
+		let existing = vec![
+			("Same".to_string(), "https://example.com/same".to_string()),
+			("Close".to_string(), "https://example.com/close".to_string()),
+		];
+		plan.filter_existing(&existing);
+		let urls: Vec<&str> = plan.new.iter().map(|feed| feed.url.as_str()).collect();
+		assert_eq!(urls, vec!["https://example.com/new"]);
+		assert_eq!(plan.subscribed, vec!["https://example.com/same (Same)"]);
+		assert_eq!(plan.skipped, vec![
+			"http://example.com/close/?utm_source=x: already subscribed to Close as https://example.com/close",
+			"https://example.com/new/: listed more than once",
+		]);
+	}
+
+	#[test]
+	fn text_list_imported()
+	{
+		let text = "# my feeds\nhttps://example.com/1\n\n  https://example.com/2  \n\
+			example.com/3\nhttps://example.com/1\n/srv/podcast.xml\n";
+		let plan = import_text(text);
+		let urls: Vec<&str> = plan.new.iter().map(|feed| feed.url.as_str()).collect();
+		assert_eq!(urls, vec!["https://example.com/1", "https://example.com/2", "/srv/podcast.xml"]);
+		assert_eq!(plan.skipped, vec![
+			"Line 5: invalid feed URL: relative URL without a base (example.com/3)",
+			"https://example.com/1: listed more than once",
+		]);
+	}
+
+	#[test]
+	fn podcasts_selected_by_tag_or_id()
+	{
+		let all = vec![
+			podcast(1, "Linux News", &["tech"]),
+			podcast(2, "Cooking", &["food", "tech talk"]),
+			podcast(3, "History", &[]),
+		];
+		let ids = |pods: Vec<Podcast>| pods.iter().map(|pod| pod.id).collect::<Vec<i64>>();
+		assert_eq!(ids(select_podcasts(all.clone(), &[], &[])), vec![1, 2, 3]);
+		assert_eq!(ids(select_podcasts(all.clone(), &["Tech"], &[])), vec![1]);
+		assert_eq!(ids(select_podcasts(all.clone(), &["tech"], &["3", "cooking"])), vec![1, 2, 3]);
+		assert!(select_podcasts(all.clone(), &["music"], &["4"]).is_empty());
+
+		// nothing selected still makes a valid file
+		let xml = export(Vec::new(), false).to_string().unwrap();
+		assert!(xml.contains("<body"));
+	}
+
+	#[test]
+	fn folders_kept_as_tags()
+	{
+		let xml = r#"<?xml version="1.0"?>
+<opml version="2.0"><head><title>Feeds</title></head><body>
+<outline type="rss" text="Flat" xmlUrl="https://example.com/1" category="talk"/>
+<outline text="Tech">
+  <outline type="rss" text="Linux" xmlUrl="https://example.com/2"/>
+  <outline text="News, daily">
+    <outline type="rss" text="Headlines" xmlUrl="https://example.com/3"/>
+  </outline>
+</outline>
+<outline title="Favourites" text="">
+  <outline type="rss" text="Linux" xmlUrl="https://example.com/2"/>
+</outline>
+<outline text="Empty"/>
+</body></opml>"#;
+		let plan = import(xml.to_string()).unwrap();
+		let tags: Vec<(&str, Vec<String>)> = plan
+			.new
+			.iter()
+			.map(|feed| (feed.url.as_str(), feed.tags.clone()))
+			.collect();
+		assert_eq!(tags, vec![
+			("https://example.com/1", vec!["talk".to_string()]),
+			("https://example.com/2", vec!["Favourites".to_string(), "Tech".to_string()]),
+			("https://example.com/3", vec!["Tech/News daily".to_string()]),
+		]);
+		assert_eq!(plan.skipped, vec![
+			"https://example.com/2: listed more than once",
+			"Empty: no feed URL (xmlUrl)",
+		]);
+
+		// the folders come back out when grouped
+		let podcasts = vec![
+			podcast(1, "Flat", &["talk"]),
+			podcast(2, "Linux", &["Favourites", "Tech"]),
+			podcast(3, "Headlines", &["Tech/News"]),
+			podcast(4, "Untagged", &[]),
+		];
+		let opml = export(podcasts.clone(), true);
+		let names = |outlines: &[Outline]| {
+			return outlines.iter().map(|o| o.text.clone()).collect::<Vec<String>>();
+		};
+		let outlines = &opml.body.outlines;
+		assert_eq!(names(outlines), vec!["Favourites", "Tech", "talk", "Untagged"]);
+		assert_eq!(names(&outlines[1].outlines), vec!["News", "Linux"]);
+		assert_eq!(names(&outlines[1].outlines[0].outlines), vec!["Headlines"]);
+		let plan = import(opml.to_string().unwrap()).unwrap();
+		assert_eq!(plan.new.len(), 4);
+		for pod in podcasts.iter()
+		{
+			let feed = plan.new.iter().find(|feed| feed.url == pod.url).unwrap();
+			assert_eq!(feed.tags, pod.tags);
+		}
+		assert_eq!(export(podcasts, false).body.outlines.len(), 4);
+	}
+}
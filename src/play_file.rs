@@ -1,28 +1,674 @@
 use anyhow::{anyhow, Result};
-use std::process::{Command, Stdio};
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
-/// Execute an external shell command to play an episode file and/or URL.
-pub fn execute(command: &str, path: &str) -> Result<()>
+use crate::types::Message;
+
+/// Number of lines of a player's error output kept for the message log;
+/// only the last ones are kept.
+const OUTPUT_LINES: usize = 20;
+
+/// Maximum amount of time in milliseconds to wait for the rest of a
+/// player's error output once it has exited
+const OUTPUT_WAIT: u64 = 200;
+
+/// Placeholders that make the play command run directly, with its
+/// arguments split up like shell words, rather than through a shell.
+const PLACEHOLDERS: [&str; 4] = ["{file}", "{url}", "{title}", "{podcast}"];
+
+/// File extensions of video files, in lowercase. Files with any other
+/// extension, or none, are taken to be audio.
+const VIDEO_EXTENSIONS: [&str; 15] = [
+	"3g2", "3gp", "avi", "flv", "m4v", "mk3d", "mkv", "mov", "mp4", "mpeg", "mpg", "ogv",
+	"ts", "webm", "wmv",
+];
+
+/// Kind of media of an episode, which decides which play command is
+/// used for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind
+{
+	Audio,
+	Video,
+}
+
+/// Details of the episode to play, which are filled in for the
+/// placeholders in the play command.
+///
+/// * `file` is the path of the downloaded file, or the URL of the episode
+///   if it has not been downloaded, i.e., whatever is to be played.
+/// * `url` is the URL of the episode, even if it has been downloaded.
+/// * `position` is the number of seconds to start playback at.
+#[derive(Debug)]
+pub struct PlayTarget<'a>
+{
+	pub file: &'a str,
+	pub url: &'a str,
+	pub title: &'a str,
+	pub podcast: &'a str,
+	pub position: Option<i64>,
+}
+
+/// An episode to be played, with the arguments of the play command from
+/// `command_args()`. `stream` is set if the player is given a URL rather
+/// than a file, and `min_seconds` is how long the player must run for the
+/// episode to count as listened to.
+#[derive(Debug)]
+pub struct Playback
+{
+	pub pod_id: i64,
+	pub ep_id: i64,
+	pub args: Vec<String>,
+	pub stream: bool,
+	pub min_seconds: u64,
+}
+
+/// Describes a player that has exited: the episode it played, when it
+/// started, how many seconds it ran for, the error it exited with, if
+/// any, and the last lines of its error output.
+#[derive(Debug)]
+pub struct PlayerExit
+{
+	pub playback: Playback,
+	pub started_at: DateTime<Utc>,
+	pub seconds: u64,
+	pub error: Option<String>,
+	pub output: Vec<String>,
+}
+
+/// Builds the arguments of the command to play an episode, with the
+/// program to run first.
+///
+/// If the command contains any of the placeholders "{file}", "{url}",
+/// "{title}" or "{podcast}", it is split into words as a shell would,
+/// and the placeholders are replaced within each word, so that titles
+/// with spaces or quotes stay a single argument. The file is added at
+/// the end if neither "{file}" nor "{url}" is used.
+///
+/// Otherwise, the command is run through a shell, as it always has been:
+/// "%s" is replaced with the file, or the file is added at the end of
+/// the command in quotes. "{position}" is replaced in both cases.
+pub fn command_args(command: &str, target: &PlayTarget) -> Result<Vec<String>>
+{
+	let position = target.position.unwrap_or(0).to_string();
+	if !PLACEHOLDERS.iter().any(|placeholder| command.contains(placeholder))
+	{
+		let cmd_string = command.replace("{position}", &position);
+		let cmd_string = if cmd_string.contains("%s")
+		{
+			// if command contains "%s", replace the path with that value
+			cmd_string.replace("%s", target.file)
+		}
+		else
+		{
+			// otherwise, add path to the end of the command
+			format!("{} \"{}\"", cmd_string, target.file)
+		};
+		return Ok(vec!["/bin/sh".to_string(), "-c".to_string(), cmd_string]);
+	}
+
+	let mut args = split_words(command)?;
+	if args.is_empty()
+	{
+		return Err(anyhow!("Play command is empty"));
+	}
+	let has_file = command.contains("{file}") || command.contains("{url}");
+	for arg in args.iter_mut()
+	{
+		// replaced in a single pass, so that a title containing e.g.
+		// "{file}" is left as it is
+		let mut out = String::new();
+		let mut rest = arg.as_str();
+		while let Some(start) = rest.find('{')
+		{
+			out.push_str(&rest[..start]);
+			rest = &rest[start..];
+			let value = [
+				("{file}", target.file),
+				("{url}", target.url),
+				("{title}", target.title),
+				("{podcast}", target.podcast),
+				("{position}", position.as_str()),
+			]
+			.into_iter()
+			.find(|(placeholder, _)| rest.starts_with(placeholder));
+			match value
+			{
+				Some((placeholder, value)) => {
+					out.push_str(value);
+					rest = &rest[placeholder.len()..];
+				}
+				None => {
+					out.push('{');
+					rest = &rest[1..];
+				}
+			}
+		}
+		out.push_str(rest);
+		*arg = out;
+	}
+	if !has_file
+	{
+		args.push(target.file.to_string());
+	}
+	return Ok(args);
+}
+
+/// Decides whether the file path or URL of an episode is audio or video,
+/// based on its extension. The extension of a downloaded file reflects
+/// the type of the download, as given by the server. For URLs, the query
+/// string is ignored. Anything that is not known to be video is audio,
+/// as most podcasts are.
+pub fn media_kind(file: &str) -> MediaKind
+{
+	let path = file.split(['?', '#']).next().unwrap_or(file);
+	let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+	let ext = match name.rsplit_once('.')
+	{
+		Some((_, ext)) => ext.to_lowercase(),
+		None => return MediaKind::Audio,
+	};
+	if VIDEO_EXTENSIONS.contains(&ext.as_str())
+	{
+		return MediaKind::Video;
+	}
+	return MediaKind::Audio;
+}
+
+/// Splits a command into words as a POSIX shell would: words are
+/// separated by whitespace, and can be quoted with single or double
+/// quotes, or have characters escaped with a backslash. Other shell
+/// syntax, such as pipes or variables, is not supported.
+fn split_words(command: &str) -> Result<Vec<String>>
+{
+	let mut words = Vec::new();
+	let mut word: Option<String> = None;
+	let mut chars = command.chars();
+	while let Some(c) = chars.next()
+	{
+		match c
+		{
+			c if c.is_whitespace() => {
+				if let Some(word) = word.take()
+				{
+					words.push(word);
+				}
+			}
+			'\'' => {
+				let word = word.get_or_insert_with(String::new);
+				loop
+				{
+					match chars.next()
+					{
+						Some('\'') => break,
+						Some(c) => word.push(c),
+						None => return Err(anyhow!("Unclosed quote in play command")),
+					}
+				}
+			}
+			'"' => {
+				let word = word.get_or_insert_with(String::new);
+				loop
+				{
+					match chars.next()
+					{
+						Some('"') => break,
+						Some('\\') => match chars.next()
+						{
+							Some(c) if matches!(c, '"' | '\\' | '$' | '`') => word.push(c),
+							Some(c) => {
+								word.push('\\');
+								word.push(c);
+							}
+							None => return Err(anyhow!("Unclosed quote in play command")),
+						},
+						Some(c) => word.push(c),
+						None => return Err(anyhow!("Unclosed quote in play command")),
+					}
+				}
+			}
+			'\\' => {
+				let word = word.get_or_insert_with(String::new);
+				if let Some(c) = chars.next()
+				{
+					word.push(c);
+				}
+			}
+			c => word.get_or_insert_with(String::new).push(c),
+		}
+	}
+	if let Some(word) = word
+	{
+		words.push(word);
+	}
+	return Ok(words);
+}
+
+/// Builds the command to run from the arguments from `command_args()`.
+fn build_command(args: &[String]) -> Result<Command>
+{
+	let (program, rest) = args.split_first().ok_or_else(|| anyhow!("Play command is empty"))?;
+	let mut cmd = Command::new(program);
+	cmd.args(rest);
+	return Ok(cmd);
+}
+
+/// Starts a player in the background, along with a thread that waits
+/// for it to exit and then sends a `Message::PlayerExit` back to the
+/// main thread. Each player has a thread of its own, so any number of
+/// them can be followed at once. What the player writes to its error
+/// output is kept for the message log, rather than being written over
+/// the UI. Returns an error if the player could not be started.
+pub fn spawn(playback: Playback, tx_to_main: mpsc::Sender<Message>) -> Result<()>
+{
+	let mut child = execute(&playback.args)?;
+	let started_at = Utc::now();
+	let start = Instant::now();
+
+	// the output is read on a thread of its own, so that a process the
+	// player leaves behind cannot hold up the exit by keeping it open
+	let (tx_output, rx_output) = mpsc::channel();
+	if let Some(stderr) = child.stderr.take()
+	{
+		std::thread::spawn(move || {
+			for line in BufReader::new(stderr).lines().map_while(|line| line.ok())
+			{
+				if tx_output.send(line).is_err()
+				{
+					break;
+				}
+			}
+		});
+	}
+
+	std::thread::spawn(move || {
+		let result = child
+			.wait()
+			.map_err(|err| anyhow!(err))
+			.and_then(|status| check_status(status, &playback));
+		let seconds = start.elapsed().as_secs();
+
+		let mut output = VecDeque::new();
+		while let Ok(line) = rx_output.recv_timeout(Duration::from_millis(OUTPUT_WAIT))
+		{
+			output.push_back(line);
+			if output.len() > OUTPUT_LINES
+			{
+				output.pop_front();
+			}
+		}
+		let exit = PlayerExit {
+			playback: playback,
+			started_at: started_at,
+			seconds: seconds,
+			error: result.err().map(|err| err.to_string()),
+			output: output.into(),
+		};
+		let _ = tx_to_main.send(Message::PlayerExit(exit));
+	});
+	return Ok(());
+}
+
+/// Execute an external command to play an episode file and/or URL, in
+/// the background, given the arguments from `command_args()`. Returns
+/// the running player, with its error output piped.
+fn execute(args: &[String]) -> Result<Child>
+{
+	let mut cmd = build_command(args)?;
+	cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::piped());
+	return cmd.spawn().map_err(|err| launch_error(args, err));
+}
+
+/// Runs a player in the foreground, with access to the terminal, and
+/// waits for it to exit. This is for players with a terminal interface
+/// of their own, e.g., mpv; the UI must give up the terminal while they
+/// run. The error, if any, is from the player not starting, or exiting
+/// with an error, as for `spawn()`.
+pub fn run_in_terminal(playback: Playback) -> PlayerExit
+{
+	let started_at = Utc::now();
+	let start = Instant::now();
+	let result = build_command(&playback.args)
+		.and_then(|mut cmd| cmd.status().map_err(|err| launch_error(&playback.args, err)))
+		.and_then(|status| check_status(status, &playback));
+	return PlayerExit {
+		started_at: started_at,
+		seconds: start.elapsed().as_secs(),
+		error: result.err().map(|err| err.to_string()),
+		output: Vec::new(),
+		playback: playback,
+	};
+}
+
+/// Builds the arguments of the command to edit a file: the editor
+/// command from the config if there is one, or else $VISUAL or $EDITOR,
+/// or vi. The command is split into words, e.g., "code --wait", and the
+/// path is added at the end.
+pub fn editor_args(command: Option<&str>, path: &Path) -> Result<Vec<String>>
+{
+	let command = match command
+	{
+		Some(command) => command.to_string(),
+		None => ["VISUAL", "EDITOR"]
+			.iter()
+			.filter_map(|var| std::env::var(var).ok())
+			.find(|value| !value.trim().is_empty())
+			.unwrap_or_else(|| "vi".to_string()),
+	};
+	let mut args = split_words(&command)
+		.map_err(|_| anyhow!("Unclosed quote in editor command"))?;
+	if args.is_empty()
+	{
+		return Err(anyhow!("Editor command is empty"));
+	}
+	args.push(path.to_string_lossy().into_owned());
+	return Ok(args);
+}
+
+/// Runs an editor in the foreground, with access to the terminal, and
+/// waits for it to exit; as with players run this way, the UI must give
+/// up the terminal in the meantime. Returns an error if the editor could
+/// not be started, or exited with an error.
+pub fn run_editor(args: &[String]) -> Result<()>
+{
+	let status = build_command(args)?
+		.status()
+		.map_err(|err| launch_error(args, err))?;
+	return match status.code()
+	{
+		_ if status.success() => Ok(()),
+		Some(code) => Err(anyhow!("Editor exited with status {code}")),
+		None => Err(anyhow!("Editor was stopped by a signal")),
+	};
+}
+
+/// Returns the name of the program that the play command runs, i.e.,
+/// the first word of it, for error messages.
+fn program_name(args: &[String]) -> String
+{
+	let command = if is_shell(args) { &args[2] } else { args.first().map_or("", |arg| arg) };
+	return command.split_whitespace().next().unwrap_or(command).to_string();
+}
+
+/// Returns whether the arguments from `command_args()` run the play
+/// command through a shell.
+fn is_shell(args: &[String]) -> bool
+{
+	return args.len() == 3 && args[0] == "/bin/sh" && args[1] == "-c";
+}
+
+/// Describes an error starting a player, e.g., "Failed to launch 'mpvv':
+/// No such file or directory".
+fn launch_error(args: &[String], err: std::io::Error) -> anyhow::Error
+{
+	let message = err.to_string();
+	// the number of the OS error is of no help to anyone
+	let message = match message.rsplit_once(" (os error")
+	{
+		Some((message, _)) => message.to_string(),
+		None => message,
+	};
+	return anyhow!("Failed to launch '{}': {message}", program_name(args));
+}
+
+/// Turns the exit status of a player into an error if it failed. Where
+/// the command is run through a shell, the shell's statuses for commands
+/// that cannot be run are described as such. The exit status is all
+/// there is to go on otherwise, so if the player was streaming a URL,
+/// the error mentions that it may not be able to.
+fn check_status(status: ExitStatus, playback: &Playback) -> Result<()>
+{
+	if status.success()
+	{
+		return Ok(());
+	}
+	let shell = is_shell(&playback.args);
+	let hint = if playback.stream { " (it may not be able to stream URLs)" } else { "" };
+	return match status.code()
+	{
+		Some(127) if shell => Err(anyhow!(
+			"Failed to launch '{}': command not found", program_name(&playback.args)
+		)),
+		Some(126) if shell => Err(anyhow!(
+			"Failed to launch '{}': permission denied", program_name(&playback.args)
+		)),
+		Some(code) => Err(anyhow!("Player exited with status {code}{hint}")),
+		None => Err(anyhow!("Player was stopped by a signal")),
+	};
+}
+
+/// Opens a web page with an external command, which defaults to the
+/// usual way of opening URLs on each platform. Unlike `execute`, the
+/// command is not run through a shell, so that a URL from a feed cannot
+/// run anything else; for the same reason, only http and https URLs are
+/// opened, rather than, e.g., local files. The command is waited on in
+/// the background, so that it does not linger once it exits.
+pub fn open_link(command: Option<&str>, url: &str) -> Result<()>
+{
+	let scheme = url.split_once("://").map(|(scheme, _)| scheme.to_lowercase());
+	if !matches!(scheme.as_deref(), Some("http") | Some("https"))
+	{
+		return Err(anyhow!("Not a web address: {url}"));
+	}
+
+	let mut cmd = match command
+	{
+		Some(command) => {
+			let mut args: Vec<String> = command.split_whitespace().map(String::from).collect();
+			if args.is_empty()
+			{
+				return Err(anyhow!("Browser command is empty"));
+			}
+			if args.iter().any(|arg| arg.contains("%s"))
+			{
+				args = args.iter().map(|arg| arg.replace("%s", url)).collect();
+			}
+			else
+			{
+				args.push(url.to_string());
+			}
+			let mut cmd = Command::new(&args[0]);
+			cmd.args(&args[1..]);
+			cmd
+		}
+		None if cfg!(target_os = "macos") => {
+			let mut cmd = Command::new("open");
+			cmd.arg(url);
+			cmd
+		}
+		// `cmd /C start` would parse the URL as a command line, so that
+		// characters such as "&" in it would run other commands
+		None if cfg!(target_os = "windows") => {
+			let mut cmd = Command::new("rundll32");
+			cmd.arg("url.dll,FileProtocolHandler").arg(url);
+			cmd
+		}
+		None => {
+			let mut cmd = Command::new("xdg-open");
+			cmd.arg(url);
+			cmd
+		}
+	};
+
+	cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+	let mut child = cmd.spawn()?;
+	std::thread::spawn(move || child.wait());
+	return Ok(());
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
 {
-	let mut cmd_string = command.to_string();
-	if cmd_string.contains("%s")
+	use super::*;
+
+	fn target() -> PlayTarget<'static>
 	{
-		// if command contains "%s", replace the path with that value
-		cmd_string = cmd_string.replace("%s", &format!("{}", path));
+		return PlayTarget {
+			file: "/tmp/the file.mp3",
+			url: "https://example.com/ep.mp3",
+			title: "It's a \"quoted\" {file}",
+			podcast: "News; rm -rf",
+			position: Some(90),
+		};
 	}
-	else
+
+	#[test]
+	fn placeholders_split_into_args()
 	{
-		// otherwise, add path to the end of the command
-		cmd_string = format!("{} \"{}\"", cmd_string, path);
+		let args = command_args(
+			"mpv --speed=1.5 --start={position} --title='{podcast}: {title}' {file}",
+			&target(),
+		)
+		.unwrap();
+		assert_eq!(args, vec![
+			"mpv",
+			"--speed=1.5",
+			"--start=90",
+			"--title=News; rm -rf: It's a \"quoted\" {file}",
+			"/tmp/the file.mp3",
+		]);
+
+		// the file is added at the end if it is not in the command
+		let args = command_args("mpv \"--title={title}\" --x=\\ y", &target()).unwrap();
+		assert_eq!(args, vec![
+			"mpv",
+			"--title=It's a \"quoted\" {file}",
+			"--x= y",
+			"/tmp/the file.mp3",
+		]);
+		assert_eq!(command_args("cvlc {url}", &target()).unwrap()[1], target().url);
+		assert!(command_args("mpv '{file}", &target()).is_err());
 	}
 
-	let mut cmd = Command::new("/bin/sh");
-	cmd.arg("-c").arg(cmd_string);
+	fn playback(args: &[&str], stream: bool) -> Playback
+	{
+		return Playback {
+			pod_id: 1,
+			ep_id: 1,
+			args: args.iter().map(|arg| arg.to_string()).collect(),
+			stream: stream,
+			min_seconds: 0,
+		};
+	}
+
+	#[test]
+	#[cfg(unix)]
+	fn exit_status_reported()
+	{
+		use std::os::unix::process::ExitStatusExt;
+		let mpv = playback(&["mpv", "/tmp/ep.mp3"], true);
+		assert!(check_status(ExitStatus::from_raw(0), &mpv).is_ok());
+		let err = check_status(ExitStatus::from_raw(2 << 8), &mpv).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"Player exited with status 2 (it may not be able to stream URLs)"
+		);
+		// without a shell, 127 is just another status
+		let err = check_status(ExitStatus::from_raw(127 << 8), &mpv).unwrap_err();
+		assert!(err.to_string().starts_with("Player exited with status 127"));
+
+		// a shell reports commands that are not found with status 127
+		let shell = playback(&["/bin/sh", "-c", "mpvv \"/tmp/ep.mp3\""], false);
+		let err = check_status(ExitStatus::from_raw(127 << 8), &shell).unwrap_err();
+		assert_eq!(err.to_string(), "Failed to launch 'mpvv': command not found");
+		let err = check_status(ExitStatus::from_raw(1 << 8), &shell).unwrap_err();
+		assert_eq!(err.to_string(), "Player exited with status 1");
+	}
+
+	#[test]
+	#[cfg(unix)]
+	fn launch_failure_reported()
+	{
+		let args = vec!["shellcaster-no-such-player".to_string(), "ep.mp3".to_string()];
+		let err = execute(&args).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"Failed to launch 'shellcaster-no-such-player': No such file or directory"
+		);
+
+		// the player's error output ends up with the exit, not on screen
+		let (tx, rx) = mpsc::channel();
+		let failing = playback(&["/bin/sh", "-c", "echo one >&2; echo two >&2; exit 3"], false);
+		spawn(failing, tx).unwrap();
+		let exit = match rx.recv_timeout(Duration::from_secs(10)).unwrap()
+		{
+			Message::PlayerExit(exit) => exit,
+			message => panic!("Unexpected message: {message:?}"),
+		};
+		assert_eq!(exit.error.as_deref(), Some("Player exited with status 3"));
+		assert_eq!(exit.output, vec!["one", "two"]);
+	}
+
+	#[test]
+	fn media_kinds()
+	{
+		let cases = [
+			("/home/me/podcasts/Show/20240101_Episode.mp3", MediaKind::Audio),
+			("/home/me/podcasts/Show/Episode.M4A", MediaKind::Audio),
+			("/tmp/episode.opus", MediaKind::Audio),
+			("/tmp/episode.ogg", MediaKind::Audio),
+			("/tmp/episode.weba", MediaKind::Audio),
+			("/tmp/episode.mp4", MediaKind::Video),
+			("/tmp/episode.MKV", MediaKind::Video),
+			("/tmp/episode.m4v", MediaKind::Video),
+			("/tmp/episode.webm", MediaKind::Video),
+			("/tmp/episode.mov", MediaKind::Video),
+			("https://cdn.example.com/ep.mp4?token=a.mp3", MediaKind::Video),
+			("https://cdn.example.com/ep.mp3?format=.mp4", MediaKind::Audio),
+			("https://cdn.example.com/v1.2/episode", MediaKind::Audio),
+			("https://cdn.example.com/episode.mov#t=30", MediaKind::Video),
+			("/tmp/no extension", MediaKind::Audio),
+			("/tmp/unknown.xyz", MediaKind::Audio),
+			("", MediaKind::Audio),
+		];
+		for (file, kind) in cases
+		{
+			assert_eq!(media_kind(file), kind, "{file}");
+		}
+	}
+
+	#[test]
+	fn commands_without_placeholders_use_shell()
+	{
+		assert_eq!(command_args("vlc", &target()).unwrap(), vec![
+			"/bin/sh",
+			"-c",
+			"vlc \"/tmp/the file.mp3\"",
+		]);
+		assert_eq!(
+			command_args("cat '%s' | mpv --start={position} -", &target()).unwrap()[2],
+			"cat '/tmp/the file.mp3' | mpv --start=90 -"
+		);
+	}
+
+	#[test]
+	fn editor_command_split()
+	{
+		let path = Path::new("/tmp/my config.toml");
+		assert_eq!(editor_args(Some("code --wait"), path).unwrap(), vec![
+			"code",
+			"--wait",
+			"/tmp/my config.toml",
+		]);
+		assert!(editor_args(Some("  "), path).is_err());
+		assert!(editor_args(Some("vim '-u"), path).is_err());
+	}
 
-	cmd.stdout(Stdio::null()).stderr(Stdio::null());
-	match cmd.spawn()
+	#[test]
+	#[cfg(unix)]
+	fn only_web_links_opened()
 	{
-		Ok(_) => Ok(()),
-		Err(err) => Err(anyhow!(err)),
+		assert!(open_link(Some("true"), "https://example.com/?a=1&b=2").is_ok());
+		assert!(open_link(Some("true"), "HTTP://example.com").is_ok());
+		assert!(open_link(Some("true"), "file:///etc/passwd").is_err());
+		assert!(open_link(Some("true"), "C:\\Windows\\notepad.exe").is_err());
+		assert!(open_link(Some("true"), "example.com").is_err());
 	}
 }
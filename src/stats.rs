@@ -0,0 +1,245 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::types::*;
+
+/// Struct holding statistics about a single podcast. `files` holds the
+/// paths of the downloaded files of the podcast whose size is not known
+/// from the database, which are added to `disk_usage` by
+/// `LibraryStats::compute_disk_usage`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PodcastStats
+{
+	pub id: i64,
+	pub title: String,
+	pub episodes: usize,
+	pub played: usize,
+	pub downloaded: usize,
+	pub disk_usage: u64,
+	#[serde(skip)]
+	pub files: Vec<PathBuf>,
+}
+
+impl Menuable for PodcastStats
+{
+	/// Returns the database ID for the podcast.
+	fn get_id(&self) -> i64
+	{
+		return self.id;
+	}
+
+	/// Returns the title of the podcast followed by its statistics, up
+	/// to `length` columns wide.
+	fn get_title(&self, length: usize) -> String
+	{
+		let meta_str = format!(
+			"{} episodes, {} played, {} downloaded ({})",
+			self.episodes,
+			self.played,
+			self.downloaded,
+			format_size(self.disk_usage)
+		);
+		let meta_len = meta_str.chars().count();
+		if length > meta_len + 10
+		{
+			let out = self.title.truncate_width(length - meta_len - 3);
+			return format!(
+				" {out} {meta_str:>width$} ",
+				width = length - out.display_width() - 3
+			);
+		}
+		else
+		{
+			return format!(" {} ", self.title.truncate_width(length.saturating_sub(2)));
+		}
+	}
+
+	fn is_played(&self) -> bool
+	{
+		return true;
+	}
+}
+
+/// Struct holding statistics about the whole library: totals over all
+/// podcasts, and the statistics of each podcast.
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryStats
+{
+	pub podcasts: usize,
+	pub episodes: usize,
+	pub played: usize,
+	pub downloaded: usize,
+	pub disk_usage: u64,
+	pub per_podcast: Vec<PodcastStats>,
+}
+
+impl LibraryStats
+{
+	/// Computes the totals over the statistics of each podcast.
+	pub fn new(per_podcast: Vec<PodcastStats>) -> Self
+	{
+		return Self {
+			podcasts: per_podcast.len(),
+			episodes: per_podcast.iter().map(|pod| pod.episodes).sum(),
+			played: per_podcast.iter().map(|pod| pod.played).sum(),
+			downloaded: per_podcast.iter().map(|pod| pod.downloaded).sum(),
+			disk_usage: per_podcast.iter().map(|pod| pod.disk_usage).sum(),
+			per_podcast: per_podcast,
+		};
+	}
+
+	/// Adds the sizes of the downloaded files whose size is not known
+	/// from the database to the disk usage of each podcast. Files that
+	/// cannot be found are skipped. This goes through the file system,
+	/// so it can be slow and should not be run on the UI thread.
+	pub fn compute_disk_usage(&mut self)
+	{
+		for pod in self.per_podcast.iter_mut()
+		{
+			pod.disk_usage += pod
+				.files
+				.drain(..)
+				.filter_map(|path| std::fs::metadata(path).ok())
+				.map(|meta| meta.len())
+				.sum::<u64>();
+		}
+		self.disk_usage = self.per_podcast.iter().map(|pod| pod.disk_usage).sum();
+	}
+
+	/// Returns a one-line summary of the totals.
+	pub fn summary(&self) -> String
+	{
+		return format!(
+			"{} podcasts, {} episodes, {} played, {} downloaded ({})",
+			self.podcasts,
+			self.episodes,
+			self.played,
+			self.downloaded,
+			format_size(self.disk_usage)
+		);
+	}
+
+	/// Formats the statistics as a plain-text table, with one row per
+	/// podcast and a row for the totals.
+	pub fn to_table(&self) -> String
+	{
+		let title_width = self
+			.per_podcast
+			.iter()
+			.map(|pod| pod.title.display_width())
+			.max()
+			.unwrap_or(0)
+			.clamp(7, 40);
+		let mut table = format!(
+			"{:<title_width$}  {:>8}  {:>8}  {:>10}  {:>10}\n",
+			"Podcast", "Episodes", "Played", "Downloaded", "Disk usage"
+		);
+		for pod in self.per_podcast.iter()
+		{
+			let title = pod.title.truncate_width(title_width);
+			table += &format!(
+				"{}{}  {:>8}  {:>8}  {:>10}  {:>10}\n",
+				title,
+				" ".repeat(title_width - title.display_width()),
+				pod.episodes,
+				pod.played,
+				pod.downloaded,
+				format_size(pod.disk_usage)
+			);
+		}
+		table += &format!(
+			"{:<title_width$}  {:>8}  {:>8}  {:>10}  {:>10}\n",
+			"Total",
+			self.episodes,
+			self.played,
+			self.downloaded,
+			format_size(self.disk_usage)
+		);
+		return table;
+	}
+}
+
+/// Formats a number of bytes in a human-readable way, e.g., "1.2 GB".
+pub fn format_size(bytes: u64) -> String
+{
+	let units = ["B", "KB", "MB", "GB", "TB"];
+	let mut size = bytes as f64;
+	let mut unit = 0;
+	while size >= 1000.0 && unit < units.len() - 1
+	{
+		size /= 1000.0;
+		unit += 1;
+	}
+	return if unit == 0
+	{
+		format!("{bytes} B")
+	}
+	else
+	{
+		format!("{size:.1} {}", units[unit])
+	};
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	fn podcast(id: i64, title: &str, episodes: usize, disk_usage: u64) -> PodcastStats
+	{
+		return PodcastStats {
+			id: id,
+			title: title.to_string(),
+			episodes: episodes,
+			played: 1,
+			downloaded: 1,
+			disk_usage: disk_usage,
+			files: Vec::new(),
+		};
+	}
+
+	#[test]
+	fn totals()
+	{
+		let stats = LibraryStats::new(vec![
+			podcast(1, "First", 10, 1_500_000),
+			podcast(2, "Second", 5, 500_000),
+		]);
+		assert_eq!(stats.podcasts, 2);
+		assert_eq!(stats.episodes, 15);
+		assert_eq!(stats.played, 2);
+		assert_eq!(stats.downloaded, 2);
+		assert_eq!(stats.disk_usage, 2_000_000);
+		assert_eq!(
+			stats.summary(),
+			"2 podcasts, 15 episodes, 2 played, 2 downloaded (2.0 MB)"
+		);
+	}
+
+	#[test]
+	fn missing_files_skipped()
+	{
+		let mut pod = podcast(1, "First", 1, 0);
+		pod.files = vec![
+			PathBuf::from("Cargo.toml"),
+			PathBuf::from("does/not/exist.mp3"),
+		];
+		let mut stats = LibraryStats::new(vec![pod]);
+		stats.compute_disk_usage();
+		let size = std::fs::metadata("Cargo.toml").unwrap().len();
+		assert_eq!(stats.disk_usage, size);
+		assert_eq!(stats.per_podcast[0].disk_usage, size);
+	}
+
+	#[test]
+	fn sizes()
+	{
+		assert_eq!(format_size(0), "0 B");
+		assert_eq!(format_size(999), "999 B");
+		assert_eq!(format_size(1_234_567), "1.2 MB");
+		assert_eq!(format_size(3_500_000_000), "3.5 GB");
+	}
+}
@@ -1,17 +1,87 @@
-use std::sync::{mpsc, Arc, Mutex};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
 // Much of the threadpool implementation here was taken directly from
 // the Rust Book: https://doc.rust-lang.org/book/ch20-02-multithreaded.html
 // and https://doc.rust-lang.org/book/ch20-03-graceful-shutdown-and-cleanup.html
 
+/// Number of high-priority jobs that are started in a row while
+/// low-priority jobs are waiting, before one of those is started, so
+/// that they are not held back forever.
+const HIGH_PRIORITY_STREAK: usize = 4;
+
+/// Name of the threads of the workers, by which the panic hook of the
+/// UI recognizes panics that the threadpool catches itself
+const WORKER_NAME: &str = "threadpool worker";
+
+/// Priority of a job in the threadpool. High-priority jobs, for things
+/// the user asked for directly, are started before low-priority ones,
+/// for things done in the background, such as syncing all podcasts or
+/// downloading new episodes automatically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority
+{
+	High,
+	Low,
+}
+
+/// How to stop the threadpool on exit; see `Threadpool::shutdown()`.
+/// Either way, jobs that have not started yet are dropped. With
+/// `Finish`, the jobs that are running are finished; with `Cancel`,
+/// they are asked to stop early through the cancellation flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shutdown
+{
+	Finish,
+	Cancel,
+}
+
+/// Flag shared by the threadpool with its jobs, which is set once the
+/// jobs are cancelled. Long jobs, such as downloads, check it from time
+/// to time, and stop early if it is set.
+#[derive(Debug, Clone, Default)]
+pub struct CancelFlag(Arc<AtomicBool>);
+
+impl CancelFlag
+{
+	/// Returns whether the jobs have been cancelled.
+	pub fn is_cancelled(&self) -> bool
+	{
+		return self.0.load(Ordering::Relaxed);
+	}
+
+	/// Asks the jobs to stop.
+	fn cancel(&self)
+	{
+		self.0.store(true, Ordering::Relaxed);
+	}
+}
+
+/// Number of workers of a threadpool that are running a job (`active`)
+/// or waiting for one (`idle`). Workers that are set to stop after
+/// their current job count as active until then.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkerCount
+{
+	pub active: usize,
+	pub idle: usize,
+}
+
 /// Manages a threadpool of a given size, sending jobs to workers as
-/// necessary. Implements Drop trait to allow threads to complete
-/// their current jobs before being stopped.
+/// necessary. Jobs wait in a queue until a worker is free; see
+/// `JobQueue` for the order in which they are started. The number of
+/// workers can be changed with `resize()`. Implements Drop trait to
+/// allow threads to complete their current jobs before being stopped.
 pub struct Threadpool
 {
 	workers: Vec<Worker>,
-	sender: mpsc::Sender<JobMessage>,
+	queue: Arc<(Mutex<JobQueue>, Condvar)>,
+	cancel: CancelFlag,
 }
 
 impl Threadpool
@@ -19,46 +89,175 @@ impl Threadpool
 	/// Creates a new Threadpool of a given size.
 	pub fn new(n_threads: usize) -> Threadpool
 	{
-		let (sender, receiver) = mpsc::channel();
-		let receiver_lock = Arc::new(Mutex::new(receiver));
+		let queue = Arc::new((Mutex::new(JobQueue::default()), Condvar::new()));
 
 		let mut workers = Vec::with_capacity(n_threads);
 
 		for _ in 0..n_threads
 		{
-			workers.push(Worker::new(Arc::clone(&receiver_lock)));
+			workers.push(Worker::new(Arc::clone(&queue)));
 		}
+		queue.0.lock().expect("Threadpool error").workers = n_threads;
 
 		return Threadpool {
 			workers: workers,
-			sender: sender,
+			queue: queue,
+			cancel: CancelFlag::default(),
+		};
+	}
+
+	/// Changes the number of workers to `n_threads`. New workers are
+	/// started right away; surplus ones stop once they are done with
+	/// their current job, so that no download is cut short.
+	pub fn resize(&mut self, n_threads: usize)
+	{
+		let (lock, ready) = &*self.queue;
+		let mut jobs = lock.lock().expect("Threadpool error");
+		let size = jobs.workers - jobs.surplus;
+		if n_threads < size
+		{
+			jobs.surplus += size - n_threads;
+			ready.notify_all();
+			return;
+		}
+
+		// workers that have not stopped yet are kept rather than
+		// replaced
+		let kept = jobs.surplus.min(n_threads - size);
+		jobs.surplus -= kept;
+		let added = n_threads - size - kept;
+		jobs.workers += added;
+		drop(jobs);
+
+		self.workers.retain(|worker| worker.is_running());
+		for _ in 0..added
+		{
+			self.workers.push(Worker::new(Arc::clone(&self.queue)));
+		}
+	}
+
+	/// Returns the number of workers the threadpool is set to have,
+	/// leaving out those that are about to stop.
+	pub fn size(&self) -> usize
+	{
+		let jobs = self.queue.0.lock().expect("Threadpool error");
+		return jobs.workers - jobs.surplus;
+	}
+
+	/// Returns how many workers are busy with a job, and how many are
+	/// waiting for one.
+	pub fn worker_count(&self) -> WorkerCount
+	{
+		let jobs = self.queue.0.lock().expect("Threadpool error");
+		return WorkerCount {
+			active: jobs.active,
+			idle: jobs.workers - jobs.active,
 		};
 	}
 
 	/// Adds a new job to the threadpool, passing closure to first
-	/// available worker.
-	pub fn execute<F>(&self, func: F)
+	/// available worker, after the jobs of the same priority that are
+	/// already waiting.
+	pub fn execute<F>(&self, priority: Priority, func: F)
 	where F: FnOnce() + Send + 'static
 	{
-		let job = Box::new(func);
-		self.sender
-			.send(JobMessage::NewJob(job))
-			.expect("Thread messaging error");
+		let (lock, ready) = &*self.queue;
+		lock.lock().expect("Threadpool error").push(priority, Box::new(func));
+		ready.notify_one();
+	}
+
+	/// Sets the function called with the message of a job that panicked,
+	/// e.g., to report it to the user. The panic is logged either way,
+	/// and the worker goes on with the next job.
+	pub fn set_panic_handler<F>(&self, handler: F)
+	where F: Fn(String) + Send + 'static
+	{
+		let (lock, _) = &*self.queue;
+		lock.lock().expect("Threadpool error").on_panic = Some(Box::new(handler));
+	}
+
+	/// Returns the flag that tells the jobs of this threadpool whether
+	/// they have been cancelled.
+	pub fn cancel_flag(&self) -> CancelFlag
+	{
+		return self.cancel.clone();
+	}
+
+	/// Stops the threadpool from starting any more jobs: jobs that have
+	/// not started yet are dropped, and the running ones are finished or
+	/// cancelled, depending on `mode`. Does not wait for the workers to
+	/// stop, so that several threadpools can be closed at once before
+	/// waiting for them with `shutdown()`.
+	pub fn close(&self, mode: Shutdown)
+	{
+		{
+			let (lock, ready) = &*self.queue;
+			let mut jobs = lock.lock().expect("Threadpool error");
+			jobs.high.clear();
+			jobs.low.clear();
+			jobs.closed = true;
+			ready.notify_all();
+		}
+		if mode == Shutdown::Cancel
+		{
+			self.cancel.cancel();
+		}
+	}
+
+	/// Returns whether all of the workers have stopped, e.g., once the
+	/// threadpool has been closed and the running jobs are done.
+	pub fn is_stopped(&self) -> bool
+	{
+		return !self.workers.iter().any(|worker| worker.is_running());
+	}
+
+	/// Stops the threadpool: jobs that have not started yet are dropped,
+	/// and the running ones are finished or cancelled, depending on
+	/// `mode`. Waits at most `timeout` for the workers to stop, so that a
+	/// job that is stuck, e.g., on a network read, cannot keep the
+	/// program from exiting. If that time runs out, the jobs are
+	/// cancelled and the workers that are left are abandoned. Returns
+	/// whether all workers stopped in time.
+	pub fn shutdown(&mut self, mode: Shutdown, timeout: Duration) -> bool
+	{
+		self.close(mode);
+
+		let deadline = Instant::now() + timeout;
+		while self.workers.iter().any(|worker| worker.is_running())
+		{
+			if Instant::now() >= deadline
+			{
+				self.cancel.cancel();
+				// dropping the handles detaches the threads, which end
+				// along with the program
+				for worker in &mut self.workers
+				{
+					worker.thread.take();
+				}
+				return false;
+			}
+			thread::sleep(Duration::from_millis(50));
+		}
+		for worker in &mut self.workers
+		{
+			if let Some(thread) = worker.thread.take()
+			{
+				thread.join().expect("Error dropping threads");
+			}
+		}
+		return true;
 	}
 }
 
 impl Drop for Threadpool
 {
-	/// Upon going out of scope, Threadpool sends terminate message to
-	/// all workers but allows them to complete current jobs.
+	/// Upon going out of scope, Threadpool tells all workers to stop
+	/// once the jobs in the queue are done, and waits for them.
 	fn drop(&mut self)
 	{
-		for _ in &self.workers
-		{
-			self.sender
-				.send(JobMessage::Terminate)
-				.expect("Thread messaging error");
-		}
+		let (lock, ready) = &*self.queue;
+		lock.lock().expect("Threadpool error").closed = true;
+		ready.notify_all();
 
 		for worker in &mut self.workers
 		{
@@ -72,12 +271,90 @@ impl Drop for Threadpool
 }
 
 type Job = Box<dyn FnOnce() + Send + 'static>;
+type PanicHandler = Box<dyn Fn(String) + Send + 'static>;
+
+/// Returns whether the current thread is a worker of a threadpool.
+pub fn is_worker_thread() -> bool
+{
+	return thread::current().name() == Some(WORKER_NAME);
+}
 
-/// Messages used by Threadpool to communicate with Workers.
-enum JobMessage
+/// Runs part of a job, catching a panic in it, so that the job can
+/// still report back that it failed in the usual way; otherwise, the
+/// main thread would keep waiting for the outcome of the job. The panic
+/// is logged, and its message returned as the error.
+pub fn catch_panic<T>(func: impl FnOnce() -> T) -> Result<T, String>
 {
-	NewJob(Job),
-	Terminate,
+	return panic::catch_unwind(AssertUnwindSafe(func)).map_err(|payload| {
+		let message = panic_message(payload.as_ref());
+		log::error!("Background task panicked: {message}");
+		return message;
+	});
+}
+
+/// Returns the message a job panicked with, which is a string unless
+/// the job panicked with some other value.
+fn panic_message(payload: &(dyn Any + Send)) -> String
+{
+	if let Some(message) = payload.downcast_ref::<&str>()
+	{
+		return message.to_string();
+	}
+	if let Some(message) = payload.downcast_ref::<String>()
+	{
+		return message.clone();
+	}
+	return "unknown error".to_string();
+}
+
+/// Jobs waiting for a worker, by priority. High-priority jobs are
+/// started first, except that after `HIGH_PRIORITY_STREAK` of them in a
+/// row, a waiting low-priority job is started. Jobs of the same
+/// priority are started in the order they were added. Once `closed`,
+/// workers stop as soon as the queue is empty. The workers, which share
+/// the queue, also keep track here of how many of them are running
+/// (`workers`), how many of those are busy with a job (`active`), and
+/// how many should stop to shrink the threadpool (`surplus`); the
+/// function to call when a job panics (`on_panic`) is kept here as well.
+#[derive(Default)]
+struct JobQueue
+{
+	high: VecDeque<Job>,
+	low: VecDeque<Job>,
+	streak: usize,
+	closed: bool,
+	workers: usize,
+	active: usize,
+	surplus: usize,
+	on_panic: Option<PanicHandler>,
+}
+
+impl JobQueue
+{
+	/// Adds a job at the end of the queue for its priority.
+	fn push(&mut self, priority: Priority, job: Job)
+	{
+		match priority
+		{
+			Priority::High => self.high.push_back(job),
+			Priority::Low => self.low.push_back(job),
+		}
+	}
+
+	/// Takes the next job to start, if any.
+	fn pop(&mut self) -> Option<Job>
+	{
+		if self.low.is_empty() || (!self.high.is_empty() && self.streak < HIGH_PRIORITY_STREAK)
+		{
+			if let Some(job) = self.high.pop_front()
+			{
+				self.streak += 1;
+				return Some(job);
+			}
+		}
+		self.streak = 0;
+		return self.low.pop_front();
+	}
 }
 
 /// Used by Threadpool to complete jobs. Each Worker manages a single
@@ -89,27 +366,233 @@ struct Worker
 
 impl Worker
 {
-	/// Creates a new Worker, which waits for Jobs to be passed by the
-	/// Threadpool.
-	fn new(receiver: Arc<Mutex<mpsc::Receiver<JobMessage>>>) -> Worker
+	/// Creates a new Worker, which waits for Jobs to be added to the
+	/// queue by the Threadpool. A job that panics is stopped there, so
+	/// that the worker stays available for the next ones.
+	fn new(queue: Arc<(Mutex<JobQueue>, Condvar)>) -> Worker
 	{
-		let thread = thread::spawn(move || loop
+		let builder = thread::Builder::new().name(WORKER_NAME.to_string());
+		let thread = builder.spawn(move || loop
 		{
-			let message = receiver
-				.lock()
-				.expect("Threadpool error")
-				.recv()
-				.expect("Thread messaging error");
-
-			match message
+			let job = {
+				let (lock, ready) = &*queue;
+				let mut jobs = lock.lock().expect("Threadpool error");
+				loop
+				{
+					if jobs.surplus > 0
+					{
+						jobs.surplus -= 1;
+						jobs.workers -= 1;
+						return;
+					}
+					if let Some(job) = jobs.pop()
+					{
+						jobs.active += 1;
+						break job;
+					}
+					if jobs.closed
+					{
+						jobs.workers -= 1;
+						return;
+					}
+					jobs = ready.wait(jobs).expect("Threadpool error");
+				}
+			};
+			// jobs share nothing with the worker that a panic could leave
+			// half-updated
+			let result = panic::catch_unwind(AssertUnwindSafe(job));
+			let (lock, _) = &*queue;
+			let mut jobs = lock.lock().expect("Threadpool error");
+			jobs.active -= 1;
+			if let Err(payload) = result
 			{
-				JobMessage::NewJob(job) => job(),
-				JobMessage::Terminate => break,
+				let message = panic_message(payload.as_ref());
+				log::error!("Background task panicked: {message}");
+				if let Some(on_panic) = &jobs.on_panic
+				{
+					on_panic(message);
+				}
 			}
-		});
+		})
+		.expect("Could not start threadpool worker");
 
 		return Worker {
 			thread: Some(thread),
 		};
 	}
+
+	/// Returns whether the thread of the worker is still running.
+	fn is_running(&self) -> bool
+	{
+		return self.thread.as_ref().is_some_and(|thread| !thread.is_finished());
+	}
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use std::sync::mpsc;
+
+	#[test]
+	fn high_priority_jobs_first()
+	{
+		let (tx, rx) = mpsc::channel();
+		let mut queue = JobQueue::default();
+		for (priority, name) in [
+			(Priority::Low, "low 1"),
+			(Priority::Low, "low 2"),
+			(Priority::High, "high 1"),
+			(Priority::High, "high 2"),
+			(Priority::High, "high 3"),
+			(Priority::High, "high 4"),
+			(Priority::High, "high 5"),
+		]
+		{
+			let tx = tx.clone();
+			queue.push(priority, Box::new(move || tx.send(name).unwrap()));
+		}
+		while let Some(job) = queue.pop()
+		{
+			job();
+		}
+		// a low-priority job gets its turn after a streak of high ones
+		let order: Vec<&str> = rx.try_iter().collect();
+		assert_eq!(order, vec![
+			"high 1", "high 2", "high 3", "high 4", "low 1", "high 5", "low 2"
+		]);
+
+		// jobs still in the queue are done before the pool is dropped
+		let pool = Threadpool::new(2);
+		let (tx, rx) = mpsc::channel();
+		for n in 0..10
+		{
+			let tx = tx.clone();
+			pool.execute(Priority::Low, move || tx.send(n).unwrap());
+		}
+		drop(pool);
+		assert_eq!(rx.try_iter().count(), 10);
+	}
+
+	#[test]
+	fn resize_keeps_running_jobs()
+	{
+		let mut pool = Threadpool::new(3);
+		let (started_tx, started_rx) = mpsc::channel();
+		let (done_tx, done_rx) = mpsc::channel();
+		let (finish_tx, finish_rx) = mpsc::channel::<()>();
+		let finish_rx = Arc::new(Mutex::new(finish_rx));
+		for n in 0..2
+		{
+			let started_tx = started_tx.clone();
+			let done_tx = done_tx.clone();
+			let finish_rx = Arc::clone(&finish_rx);
+			pool.execute(Priority::High, move || {
+				started_tx.send(()).unwrap();
+				finish_rx.lock().unwrap().recv().unwrap();
+				done_tx.send(n).unwrap();
+			});
+		}
+		started_rx.recv().unwrap();
+		started_rx.recv().unwrap();
+		assert_eq!(pool.worker_count(), WorkerCount { active: 2, idle: 1 });
+
+		// the idle worker stops right away, the busy one after its job
+		pool.resize(1);
+		assert_eq!(pool.size(), 1);
+		while pool.worker_count().idle > 0
+		{
+			thread::sleep(Duration::from_millis(5));
+		}
+		assert_eq!(pool.worker_count(), WorkerCount { active: 2, idle: 0 });
+		finish_tx.send(()).unwrap();
+		finish_tx.send(()).unwrap();
+		let mut done: Vec<i32> = vec![done_rx.recv().unwrap(), done_rx.recv().unwrap()];
+		done.sort();
+		assert_eq!(done, vec![0, 1]);
+		while pool.worker_count() != (WorkerCount { active: 0, idle: 1 })
+		{
+			thread::sleep(Duration::from_millis(5));
+		}
+
+		// growing again starts new workers, which take on queued jobs
+		pool.resize(4);
+		assert_eq!(pool.size(), 4);
+		assert_eq!(pool.worker_count(), WorkerCount { active: 0, idle: 4 });
+		for n in 0..8
+		{
+			let done_tx = done_tx.clone();
+			pool.execute(Priority::Low, move || done_tx.send(n).unwrap());
+		}
+		assert_eq!(done_rx.iter().take(8).count(), 8);
+	}
+
+	#[test]
+	fn worker_survives_panic()
+	{
+		let pool = Threadpool::new(1);
+		let (tx, rx) = mpsc::channel();
+		let panic_tx = tx.clone();
+		pool.set_panic_handler(move |message| panic_tx.send(message).unwrap());
+		pool.execute(Priority::High, || panic!("weird feed"));
+		pool.execute(Priority::High, move || tx.send("done".to_string()).unwrap());
+		assert_eq!(rx.recv().unwrap(), "weird feed");
+		assert_eq!(rx.recv().unwrap(), "done");
+	}
+
+	#[test]
+	fn job_reports_own_panic()
+	{
+		let pool = Threadpool::new(1);
+		let (tx, rx) = mpsc::channel();
+		let panic_tx = tx.clone();
+		pool.set_panic_handler(move |message| panic_tx.send(format!("pool: {message}")).unwrap());
+		pool.execute(Priority::High, move || {
+			let result: Result<(), String> = catch_panic(|| panic!("weird feed"));
+			tx.send(format!("job: {}", result.unwrap_err())).unwrap();
+		});
+		// the job gets to report the failure, and the pool sees no panic
+		assert_eq!(rx.recv().unwrap(), "job: weird feed");
+		assert_eq!(catch_panic(|| 42), Ok(42));
+		drop(pool);
+		assert!(rx.try_recv().is_err());
+	}
+
+	#[test]
+	fn shutdown_drops_queued_jobs()
+	{
+		let (tx, rx) = mpsc::channel();
+		let (started_tx, started_rx) = mpsc::channel();
+		let mut pool = Threadpool::new(1);
+		let cancel = pool.cancel_flag();
+		pool.execute(Priority::High, move || {
+			started_tx.send(()).unwrap();
+			while !cancel.is_cancelled()
+			{
+				thread::sleep(Duration::from_millis(5));
+			}
+		});
+		let tx2 = tx.clone();
+		pool.execute(Priority::High, move || tx2.send("queued").unwrap());
+		started_rx.recv().unwrap();
+
+		// the running job stops once cancelled, and the queued one never
+		// starts
+		assert!(pool.shutdown(Shutdown::Cancel, Duration::from_secs(5)));
+		drop(tx);
+		assert_eq!(rx.iter().count(), 0);
+
+		// a job that does not stop is abandoned once the time runs out
+		let mut pool = Threadpool::new(1);
+		let (started_tx, started_rx) = mpsc::channel();
+		pool.execute(Priority::Low, move || {
+			started_tx.send(()).unwrap();
+			thread::sleep(Duration::from_secs(2));
+		});
+		started_rx.recv().unwrap();
+		assert!(!pool.shutdown(Shutdown::Finish, Duration::from_millis(100)));
+		assert!(pool.cancel_flag().is_cancelled());
+	}
 }
@@ -1,16 +1,22 @@
-use std::cmp::Ordering;
+use std::cmp::{Ordering, Reverse};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, MutexGuard};
 use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, TimeZone, Utc};
 use lazy_static::lazy_static;
 use nohash_hasher::BuildNoHashHasher;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 use crate::downloads::DownloadMsg;
 use crate::feeds::FeedMsg;
+use crate::gpodder::GpodderMsg;
+use crate::stats::LibraryStats;
 use crate::ui::UiMsg;
 
 lazy_static! {
@@ -25,32 +31,137 @@ pub trait Menuable {
 	fn get_id(&self) -> i64;
 	fn get_title(&self, length: usize) -> String;
 	fn is_played(&self) -> bool;
+
+	/// Returns the row shown for the item in a menu, `length` columns
+	/// wide, along with the given extra columns for items that have
+	/// them. Only episodes have extra columns.
+	fn get_row(&self, length: usize, _columns: &[EpisodeColumn]) -> String
+	{
+		return self.get_title(length);
+	}
+
+	/// Returns the lines shown for the item in a menu, as with
+	/// `get_row()`, but with a long title wrapped onto up to `max_lines`
+	/// lines. Each line is `length` columns wide.
+	fn get_rows(&self, length: usize, columns: &[EpisodeColumn], _max_lines: usize) -> Vec<String>
+	{
+		return vec![self.get_row(length, columns)];
+	}
+
+	/// Indicates whether the item should be grayed out in menus.
+	fn is_inactive(&self) -> bool
+	{
+		return false;
+	}
+
+	/// Returns the state of the item, which decides how it is styled in
+	/// menus.
+	fn state(&self) -> ItemState
+	{
+		if self.is_inactive()
+		{
+			return ItemState::Inactive;
+		}
+		else if self.is_played()
+		{
+			return ItemState::Normal;
+		}
+		return ItemState::Unplayed;
+	}
+}
+
+/// State of an item in a menu, which decides how it is styled. Where an
+/// episode is in more than one state, e.g., a downloaded episode that has
+/// been played, the state listed first here takes precedence. Items
+/// other than episodes are either inactive, unplayed, or normal, except
+/// for podcasts that were never synced and error messages in the
+/// message log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemState
+{
+	Inactive,
+	NotSynced,
+	Downloading,
+	Downloaded,
+	Played,
+	New,
+	Unplayed,
+	Normal,
+	Error,
 }
 
 /// Struct holding data about an individual podcast feed. This includes a
-/// (possibly empty) vector of episodes.
-#[derive(Debug, Clone)]
+/// (possibly empty) vector of episodes. `title` is the title displayed to
+/// the user, which is either a custom title set by the user, or the title
+/// from the feed, kept separately in `feed_title`. Favorite podcasts are
+/// listed before the others. Podcasts imported without retrieving their
+/// feed are not `synced` until their first sync.
+#[derive(Debug, Clone, Serialize)]
 pub struct Podcast {
 	pub id: i64,
 	pub title: String,
+	pub feed_title: String,
+	#[serde(skip)]
 	pub sort_title: String,
 	pub url: String,
+	pub link: Option<String>,
 	pub description: Option<String>,
 	pub author: Option<String>,
 	pub explicit: Option<bool>,
+	pub image_url: Option<String>,
+	pub cover_path: Option<PathBuf>,
 	pub last_checked: DateTime<Utc>,
+	pub inactive: bool,
+	pub favorite: bool,
+	pub synced: bool,
+	pub last_error: Option<String>,
+	pub last_error_at: Option<DateTime<Utc>>,
+	pub tags: Vec<String>,
+	pub episode_sort: EpisodeSort,
+	#[serde(skip)]
 	pub episodes: LockVec<Episode>,
 }
 
 impl Podcast {
-	/// Counts and returns the number of unplayed episodes in the podcast.
-	fn num_unplayed(&self) -> usize {
+	/// Counts and returns the number of unplayed episodes in the podcast,
+	/// leaving out archived episodes.
+	pub fn num_unplayed(&self) -> usize {
+		return self
+			.episodes
+			.map(|ep| (!ep.is_played() && !ep.archived) as usize, false)
+			.iter()
+			.sum();
+	}
+
+	/// Counts and returns the number of episodes in the podcast that have
+	/// been downloaded.
+	pub fn num_downloaded(&self) -> usize {
 		return self
 			.episodes
-			.map(|ep| !ep.is_played() as usize, false)
+			.map(|ep| ep.path.is_some() as usize, false)
 			.iter()
 			.sum();
 	}
+
+	/// Checks whether any episodes were added since the user last looked
+	/// at the podcast, leaving out archived episodes.
+	pub fn has_unseen(&self) -> bool {
+		return self
+			.episodes
+			.map(|ep| ep.unseen && !ep.archived, false)
+			.contains(&true);
+	}
+
+	/// Returns the publication date of the most recent episode of the
+	/// podcast, if any episode has one.
+	pub fn latest_pubdate(&self) -> Option<DateTime<Utc>> {
+		return self
+			.episodes
+			.map(|ep| ep.pubdate, false)
+			.into_iter()
+			.flatten()
+			.max();
+	}
 }
 
 impl Menuable for Podcast {
@@ -59,33 +170,100 @@ impl Menuable for Podcast {
 		return self.id;
 	}
 
-	/// Returns the title for the podcast, up to length characters.
+	/// Returns the title for the podcast, up to `length` columns wide.
 	fn get_title(&self, length: usize) -> String {
-		let mut title_length = length;
+		return self.get_rows(length, &[], 1).remove(0);
+	}
+
+	/// Returns the title for the podcast, up to `length` columns wide,
+	/// wrapped onto up to `max_lines` lines if it is too long. The
+	/// unplayed data is shown on the first line.
+	fn get_rows(&self, length: usize, _columns: &[EpisodeColumn], max_lines: usize) -> Vec<String> {
+		let mut title = if self.has_unseen()
+		{
+			format!("* {}", self.title)
+		}
+		else
+		{
+			self.title.clone()
+		};
+		if self.favorite
+		{
+			title = format!("★ {title}");
+		}
 
 		// if the size available is big enough, we add the unplayed data
-		// to the end
+		// to the end; like the episode list, it leaves out the episodes
+		// hidden by the filters
 		if length > crate::config::PODCAST_UNPLAYED_TOTALS_LENGTH {
+			let shown = self.episodes.map(|ep| ep.is_played(), true);
 			let meta_str = format!(
 				"({}/{})",
-				self.num_unplayed(), self.episodes.len(false)
+				shown.iter().filter(|played| !**played).count(), shown.len()
 			);
-			title_length = length - meta_str.chars().count() - 3;
+			let title_length = length - meta_str.chars().count() - 3;
 
-			let out = self.title.substr(0, title_length);
-
-			return format!(
+			let mut lines = title.wrap_width(title_length, max_lines);
+			let out = lines.remove(0);
+			let mut rows = vec![format!(
 				" {out} {meta_str:>width$} ",
-				width = length - out.grapheme_len() - 3
-			); // this pads spaces between title and totals
+				width = length - out.display_width() - 3
+			)]; // this pads spaces between title and totals
+			for line in lines
+			{
+				rows.push(format!(" {line}{} ", " ".repeat(length - line.display_width() - 2)));
+			}
+			return rows;
 		} else {
-			return format!(" {} ", self.title.substr(0, title_length - 2));
+			return title
+				.wrap_width(length.saturating_sub(2), max_lines)
+				.iter()
+				.map(|line| format!(" {line} "))
+				.collect();
 		}
 	}
 
 	fn is_played(&self) -> bool {
 		return self.num_unplayed() == 0;
 	}
+
+	fn is_inactive(&self) -> bool {
+		return self.inactive;
+	}
+
+	/// Podcasts that have never been synced have no episodes yet, and
+	/// are set apart from the others.
+	fn state(&self) -> ItemState {
+		if self.inactive
+		{
+			return ItemState::Inactive;
+		}
+		else if !self.synced
+		{
+			return ItemState::NotSynced;
+		}
+		else if self.is_played()
+		{
+			return ItemState::Normal;
+		}
+		return ItemState::Unplayed;
+	}
+}
+
+/// Splits a comma-separated list of tags, as entered by the user or
+/// found in the `category` attribute of an OPML file. Tags are trimmed,
+/// along with any slashes around them (OPML categories look like
+/// "/News/Politics"); empty and duplicate tags are dropped, and the rest
+/// are sorted.
+pub fn parse_tags(input: &str) -> Vec<String> {
+	let mut tags: Vec<String> = input
+		.split(',')
+		.map(|tag| tag.trim().trim_matches('/').trim().to_string())
+		.filter(|tag| !tag.is_empty())
+		.collect();
+	tags.sort();
+	tags.dedup();
+	return tags;
 }
 
 impl PartialEq for Podcast {
@@ -110,35 +288,138 @@ impl Ord for Podcast {
 
 /// Struct holding data about an individual podcast episode. Most of this
 /// is metadata, but if the episode has been downloaded to the local
-/// machine, the filepath will be included here as well. `played`
-/// indicates whether the podcast has been marked as played or unplayed.
-#[derive(Debug, Clone)]
+/// machine, the filepath will be included here as well, along with the
+/// time of the download and the size of the file when they are known.
+/// `played` indicates whether the podcast has been marked as played or
+/// unplayed, and `archived` whether it is hidden from the list unless
+/// archived episodes are shown. `unseen` is set for episodes added since
+/// the user last looked at the podcast. `downloading` is only kept in
+/// memory, while the episode is being downloaded.
+#[derive(Debug, Clone, Serialize)]
 pub struct Episode {
 	pub id: i64,
 	pub pod_id: i64,
 	pub title: String,
 	pub url: String,
 	pub guid: String,
+	pub link: Option<String>,
 	pub description: String,
 	pub pubdate: Option<DateTime<Utc>>,
 	pub duration: Option<i64>,
+	pub season: Option<i64>,
+	pub episode_num: Option<i64>,
+	pub enclosure_size: Option<u64>,
 	pub path: Option<PathBuf>,
+	pub downloaded_at: Option<DateTime<Utc>>,
+	pub file_size: Option<u64>,
 	pub played: bool,
+	pub position: Option<i64>,
+	pub archived: bool,
+	pub added_at: Option<DateTime<Utc>>,
+	pub unseen: bool,
+	#[serde(skip)]
+	pub downloading: bool,
 }
 
 impl Episode {
-	/// Formats the duration in seconds into an HH:MM:SS format.
+	/// Formats the date and size of the download, e.g.,
+	/// "2024-05-02, 61.0 MB", leaving out what is unknown.
+	pub fn format_download(&self, date_format: &DateFormat) -> String {
+		let mut parts = Vec::new();
+		if let Some(date) = self.downloaded_at
+		{
+			parts.push(date_format.format(&date));
+		}
+		if let Some(size) = self.file_size
+		{
+			parts.push(crate::stats::format_size(size));
+		}
+		if parts.is_empty()
+		{
+			return "Yes".to_string();
+		}
+		return parts.join(", ");
+	}
+
+	/// Formats the duration in seconds into a short human-readable
+	/// format, e.g., "1h 23m", "42m", or "30s".
 	pub fn format_duration(&self) -> String {
 		return match self.duration {
 			Some(dur) => {
-				let mut seconds = dur;
-				let hours = seconds / 3600;
-				seconds -= hours * 3600;
-				let minutes = seconds / 60;
-				seconds -= minutes * 60;
-				format!("{hours:02}:{minutes:02}:{seconds:02}")
+				let hours = dur / 3600;
+				let minutes = (dur % 3600) / 60;
+				let seconds = dur % 60;
+				if hours > 0
+				{
+					format!("{hours}h {minutes}m")
+				}
+				else if minutes > 0
+				{
+					format!("{minutes}m")
+				}
+				else
+				{
+					format!("{seconds}s")
+				}
+			}
+			None => "--".to_string(),
+		};
+	}
+
+	/// Formats the saved playback position like a clock, e.g., "42:17"
+	/// or "1:02:05". Returns None if there is no saved position.
+	pub fn format_position(&self) -> Option<String> {
+		return self.position.map(|pos| {
+			let hours = pos / 3600;
+			let minutes = (pos % 3600) / 60;
+			let seconds = pos % 60;
+			if hours > 0
+			{
+				format!("{hours}:{minutes:02}:{seconds:02}")
 			}
-			None => "--:--:--".to_string(),
+			else
+			{
+				format!("{minutes}:{seconds:02}")
+			}
+		});
+	}
+
+	/// Formats the value shown for the episode in one of the extra
+	/// columns of the episode menu, which may be empty.
+	pub fn format_column(&self, column: &EpisodeColumn) -> String {
+		return match column {
+			EpisodeColumn::Date(date_format) => match self.pubdate {
+				Some(pubdate) => date_format.format(&pubdate),
+				None => String::new(),
+			},
+			EpisodeColumn::RelativeDate(max_days, date_format) => match self.pubdate {
+				Some(pubdate) => relative_date(
+					&pubdate.with_timezone(&Local),
+					&Local::now(),
+					*max_days,
+				)
+				.unwrap_or_else(|| date_format.format(&pubdate)),
+				None => String::new(),
+			},
+			EpisodeColumn::Duration => match self.duration {
+				Some(_) => self.format_duration(),
+				None => String::new(),
+			},
+			EpisodeColumn::Size => match self.file_size {
+				Some(size) => crate::stats::format_size(size),
+				None => String::new(),
+			},
+		};
+	}
+
+	/// Formats the season and episode numbers, e.g., "S2E07". Returns
+	/// None if the feed did not provide either of them.
+	pub fn format_season_episode(&self) -> Option<String> {
+		return match (self.season, self.episode_num) {
+			(Some(season), Some(num)) => Some(format!("S{season}E{num:02}")),
+			(Some(season), None) => Some(format!("S{season}")),
+			(None, Some(num)) => Some(format!("E{num:02}")),
+			(None, None) => None,
 		};
 	}
 }
@@ -149,68 +430,109 @@ impl Menuable for Episode {
 		return self.id;
 	}
 
-	/// Returns the title for the episode, up to length characters.
+	/// Returns the title for the episode, `length` columns wide, without
+	/// any of the extra columns.
 	fn get_title(&self, length: usize) -> String
 	{
-		let out = match self.path
+		return self.get_row(length, &[]);
+	}
+
+	/// Returns the title for the episode followed by the given columns,
+	/// `length` columns wide. The title takes up the space that is left,
+	/// and is cut short with an ellipsis if it does not fit; columns are
+	/// dropped from the end of the list if there would be less than
+	/// `EPISODE_TITLE_LENGTH` columns left for the title.
+	fn get_row(&self, length: usize, columns: &[EpisodeColumn]) -> String
+	{
+		return self.get_rows(length, columns, 1).remove(0);
+	}
+
+	/// Returns the row for the episode as with `get_row()`, but with a
+	/// long title wrapped onto up to `max_lines` lines. The columns are
+	/// shown on the first line.
+	fn get_rows(&self, length: usize, columns: &[EpisodeColumn], max_lines: usize) -> Vec<String>
+	{
+		let mut title = match self.format_season_episode()
 		{
-			Some(_) => {
-				let title = self.title.substr(0, length - 4);
-				format!("[D] {title}")
-			}
-			None => self.title.substr(0, length),
+			Some(se) => format!("{se} {}", self.title),
+			None => self.title.clone(),
 		};
-		if length > crate::config::EPISODE_PUBDATE_LENGTH
+		if self.path.is_some()
+		{
+			title.insert_str(0, "[D] ");
+		}
+		if self.unseen
 		{
-			let dur = self.format_duration();
-			let meta_dur = format!("[{dur}]");
+			title.insert_str(0, "* ");
+		}
 
-			if let Some(pubdate) = self.pubdate
-			{
-				// print pubdate and duration
-				let pd = pubdate.format("%F");
-				let meta_str = format!("({pd}) {meta_dur}");
-				let added_len = meta_str.chars().count();
-
-				let out_added = out.substr(0, length - added_len - 3);
-				return format!(
-					" {out_added} {meta_str:>width$} ",
-					width = length - out_added.grapheme_len() - 3
-				);
-			}
-			else
-			{
-				// just print duration
-				let out_added = out.substr(
-					0,
-					length - meta_dur.chars().count() - 3
-				);
-				return format!(
-					" {out_added} {meta_dur:>width$} ",
-					width = length - out_added.grapheme_len() - 3
-				);
-			}
+		// one space at each end of the row, and one before each column
+		let inner = length.saturating_sub(2);
+		let mut columns = columns;
+		let mut columns_width: usize = columns.iter().map(|col| col.width() + 1).sum();
+		while !columns.is_empty()
+			&& columns_width + crate::config::EPISODE_TITLE_LENGTH > inner
+		{
+			columns_width -= columns[columns.len() - 1].width() + 1;
+			columns = &columns[..columns.len() - 1];
 		}
-		else if length > crate::config::EPISODE_DURATION_LENGTH
+
+		let title_width = inner.saturating_sub(columns_width);
+		let mut lines = title.wrap_width(title_width, max_lines);
+		let title = lines.remove(0);
+		let mut row = format!(
+			" {title}{}",
+			" ".repeat(title_width - title.display_width())
+		);
+		for col in columns
 		{
-			let dur = self.format_duration();
-			let meta_dur = format!("[{dur}]");
-			let out_added = out.substr(0, length - meta_dur.chars().count() - 3);
-			return format!(
-				" {out_added} {meta_dur:>width$} ",
-				width = length - out_added.grapheme_len() - 3
-			);
+			row.push_str(&format!(" {:>width$}", self.format_column(col), width = col.width()));
 		}
-		else
+		row.push(' ');
+
+		let mut rows = vec![row];
+		for line in lines
 		{
-			return format!(" {} ", out.substr(0, length - 2));
+			rows.push(format!(" {line}{} ", " ".repeat(inner - line.display_width())));
 		}
+		return rows;
 	}
 
 	fn is_played(&self) -> bool
 	{
 		return self.played;
 	}
+
+	fn is_inactive(&self) -> bool
+	{
+		return self.archived;
+	}
+
+	fn state(&self) -> ItemState
+	{
+		let new_since = Utc::now() - chrono::Duration::days(crate::config::NEW_EPISODE_DAYS);
+		if self.archived
+		{
+			return ItemState::Inactive;
+		}
+		else if self.downloading
+		{
+			return ItemState::Downloading;
+		}
+		else if self.path.is_some()
+		{
+			return ItemState::Downloaded;
+		}
+		else if self.played
+		{
+			return ItemState::Played;
+		}
+		else if self.unseen || self.pubdate.is_some_and(|date| date > new_since)
+		{
+			return ItemState::New;
+		}
+		return ItemState::Unplayed;
+	}
 }
 
 
@@ -222,9 +544,11 @@ pub struct PodcastNoId
 {
 	pub title: String,
 	pub url: String,
+	pub link: Option<String>,
 	pub description: Option<String>,
 	pub author: Option<String>,
 	pub explicit: Option<bool>,
+	pub image_url: Option<String>,
 	pub last_checked: DateTime<Utc>,
 	pub episodes: Vec<EpisodeNoId>,
 }
@@ -237,9 +561,166 @@ pub struct EpisodeNoId
 	pub title: String,
 	pub url: String,
 	pub guid: String,
+	pub link: Option<String>,
+	pub description: String,
+	pub pubdate: Option<DateTime<Utc>>,
+	pub duration: Option<i64>,
+	pub season: Option<i64>,
+	pub episode_num: Option<i64>,
+	pub enclosure_size: Option<u64>,
+}
+
+/// Version of the JSON format written by `shellcaster export --format
+/// json`. This should be increased whenever the format changes in a way
+/// that older versions of shellcaster cannot read.
+pub const LIBRARY_FORMAT_VERSION: u32 = 1;
+
+/// Struct holding the whole library, as exported to and imported from
+/// JSON. Unlike OPML, this includes the episodes of each podcast and
+/// their state, so that it can be moved to another machine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryExport
+{
+	pub version: u32,
+	pub podcasts: Vec<PodcastExport>,
+}
+
+impl LibraryExport
+{
+	/// Creates an export of the given podcasts, using the current
+	/// format version.
+	pub fn new(podcasts: &[Podcast]) -> Self
+	{
+		return Self {
+			version: LIBRARY_FORMAT_VERSION,
+			podcasts: podcasts.iter().map(PodcastExport::from).collect(),
+		};
+	}
+}
+
+/// Struct holding data about a podcast in a JSON export. `title` is the
+/// title from the feed; `custom_title` is the title set by the user, if
+/// any.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PodcastExport
+{
+	pub title: String,
+	#[serde(default)]
+	pub custom_title: Option<String>,
+	pub url: String,
+	#[serde(default)]
+	pub link: Option<String>,
+	pub description: Option<String>,
+	pub author: Option<String>,
+	pub explicit: Option<bool>,
+	pub image_url: Option<String>,
+	pub last_checked: DateTime<Utc>,
+	#[serde(default)]
+	pub inactive: bool,
+	#[serde(default)]
+	pub tags: Vec<String>,
+	pub episodes: Vec<EpisodeExport>,
+}
+
+impl From<&Podcast> for PodcastExport
+{
+	fn from(podcast: &Podcast) -> Self
+	{
+		let custom_title = if podcast.title != podcast.feed_title
+		{
+			Some(podcast.title.clone())
+		}
+		else
+		{
+			None
+		};
+		return Self {
+			title: podcast.feed_title.clone(),
+			custom_title: custom_title,
+			url: podcast.url.clone(),
+			link: podcast.link.clone(),
+			description: podcast.description.clone(),
+			author: podcast.author.clone(),
+			explicit: podcast.explicit,
+			image_url: podcast.image_url.clone(),
+			last_checked: podcast.last_checked,
+			inactive: podcast.inactive,
+			tags: podcast.tags.clone(),
+			episodes: podcast.episodes.map(|ep| EpisodeExport::from(ep), false),
+		};
+	}
+}
+
+/// Struct holding data about an episode in a JSON export, including its
+/// state: whether it has been played, where it was downloaded to, and
+/// the saved playback position.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpisodeExport
+{
+	pub title: String,
+	pub url: String,
+	pub guid: String,
+	#[serde(default)]
+	pub link: Option<String>,
 	pub description: String,
 	pub pubdate: Option<DateTime<Utc>>,
 	pub duration: Option<i64>,
+	pub season: Option<i64>,
+	pub episode_num: Option<i64>,
+	#[serde(default)]
+	pub enclosure_size: Option<u64>,
+	#[serde(default)]
+	pub path: Option<PathBuf>,
+	#[serde(default)]
+	pub played: bool,
+	#[serde(default)]
+	pub position: Option<i64>,
+	#[serde(default)]
+	pub archived: bool,
+}
+
+impl From<&Episode> for EpisodeExport
+{
+	fn from(episode: &Episode) -> Self
+	{
+		return Self {
+			title: episode.title.clone(),
+			url: episode.url.clone(),
+			guid: episode.guid.clone(),
+			link: episode.link.clone(),
+			description: episode.description.clone(),
+			pubdate: episode.pubdate,
+			duration: episode.duration,
+			season: episode.season,
+			episode_num: episode.episode_num,
+			enclosure_size: episode.enclosure_size,
+			path: episode.path.clone(),
+			played: episode.played,
+			position: episode.position,
+			archived: episode.archived,
+		};
+	}
+}
+
+impl EpisodeExport
+{
+	/// Returns the episode metadata, without its state, for inserting
+	/// it into the database.
+	pub fn to_episode_no_id(&self) -> EpisodeNoId
+	{
+		return EpisodeNoId {
+			title: self.title.clone(),
+			url: self.url.clone(),
+			guid: self.guid.clone(),
+			link: self.link.clone(),
+			description: self.description.clone(),
+			pubdate: self.pubdate,
+			duration: self.duration,
+			season: self.season,
+			episode_num: self.episode_num,
+			enclosure_size: self.enclosure_size,
+		};
+	}
 }
 
 /// Struct holding data about an individual podcast episode, specifically
@@ -263,28 +744,150 @@ impl Menuable for NewEpisode
 		return self.id;
 	}
 
-	/// Returns the title for the episode, up to length characters.
+	/// Returns the title for the episode, `length` columns wide.
 	fn get_title(&self, length: usize) -> String
 	{
 		let selected = if self.selected { "✓" } else { " " };
+		let inner = length.saturating_sub(2);
+		let text = format!("[{}] {} ({})", selected, self.title, self.pod_title)
+			.truncate_width(inner);
+		return format!(" {text}{} ", " ".repeat(inner - text.display_width()));
+	}
 
-		let title_len = self.title.grapheme_len();
-		let pod_title_len = self.pod_title.grapheme_len();
-		let empty_string = if length > title_len + pod_title_len + 9
-		{
-			let empty = vec![" "; length - title_len - pod_title_len - 9];
-			empty.join("")
-		}
-		else
-		{
-			"".to_string()
+	fn is_played(&self) -> bool
+	{
+		return true;
+	}
+}
+
+/// Struct holding data about an episode in the playback queue, for the
+/// popup window that shows the queue.
+#[derive(Debug, Clone)]
+pub struct QueueItem
+{
+	pub id: i64,
+	pub pod_id: i64,
+	pub title: String,
+	pub pod_title: String,
+}
+
+impl Menuable for QueueItem
+{
+	/// Returns the database ID for the episode.
+	fn get_id(&self) -> i64
+	{
+		return self.id;
+	}
+
+	/// Returns the title for the episode and its podcast, up to `length`
+	/// columns wide.
+	fn get_title(&self, length: usize) -> String
+	{
+		let text = format!("{} ({})", self.title, self.pod_title);
+		return format!(" {} ", text.truncate_width(length.saturating_sub(2)));
+	}
+
+	fn is_played(&self) -> bool
+	{
+		return true;
+	}
+}
+
+/// Struct holding a notification shown to the user, kept in the message
+/// log along with the time it was shown.
+#[derive(Debug, Clone)]
+pub struct LogEntry
+{
+	pub id: i64,
+	pub time: DateTime<Utc>,
+	pub message: String,
+	pub error: bool,
+}
+
+impl LogEntry
+{
+	/// Creates a new log entry for a message shown at the current time.
+	pub fn new(message: String, error: bool) -> Self
+	{
+		return Self {
+			id: 0,
+			time: Utc::now(),
+			message: message,
+			error: error,
 		};
+	}
+
+	/// Appends the entry as a single line to the file at `path`,
+	/// creating the file if it does not exist yet.
+	pub fn append_to(&self, path: &Path) -> std::io::Result<()>
+	{
+		let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+		let level = if self.error { "ERROR" } else { "INFO" };
+		return writeln!(
+			file,
+			"{} {} {}",
+			self.time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+			level,
+			self.message
+		);
+	}
+}
 
-		let full_string = format!(
-			" [{}] {} ({}){} ",
-			selected, self.title, self.pod_title, empty_string
+impl Menuable for LogEntry
+{
+	/// Returns the position of the entry in the log.
+	fn get_id(&self) -> i64
+	{
+		return self.id;
+	}
+
+	/// Returns the time and the message, up to `length` columns wide.
+	fn get_title(&self, length: usize) -> String
+	{
+		let text = format!(
+			"{} {}",
+			self.time.with_timezone(&Local).format("%H:%M:%S"),
+			self.message
 		);
-		return full_string.substr(0, length);
+		return format!(" {} ", text.truncate_width(length.saturating_sub(2)));
+	}
+
+	fn is_played(&self) -> bool
+	{
+		return true;
+	}
+
+	fn state(&self) -> ItemState
+	{
+		if self.error
+		{
+			return ItemState::Error;
+		}
+		return ItemState::Normal;
+	}
+}
+
+/// Struct holding the URL of a candidate feed, used when a web page
+/// links to more than one feed and the user has to pick one.
+#[derive(Debug, Clone)]
+pub struct FeedCandidate
+{
+	pub id: i64,
+	pub url: String,
+}
+
+impl Menuable for FeedCandidate
+{
+	/// Returns the position of the feed in the list of candidates.
+	fn get_id(&self) -> i64
+	{
+		return self.id;
+	}
+
+	/// Returns the URL of the feed, up to `length` columns wide.
+	fn get_title(&self, length: usize) -> String
+	{
+		return format!(" {} ", self.url.truncate_width(length.saturating_sub(2)));
 	}
 
 	fn is_played(&self) -> bool
@@ -339,19 +942,19 @@ impl<T: Clone + Menuable> LockVec<T>
 	}
 
 	/// Lock the LockVec hashmap for reading/writing.
-	pub fn borrow_map(&self) -> MutexGuard<HashMap<i64, T, BuildNoHashHasher<i64>>>
+	pub fn borrow_map(&self) -> MutexGuard<'_, HashMap<i64, T, BuildNoHashHasher<i64>>>
 	{
 		return self.data.lock().expect("Mutex error");
 	}
 
 	/// Lock the LockVec order vector for reading/writing.
-	pub fn borrow_order(&self) -> MutexGuard<Vec<i64>>
+	pub fn borrow_order(&self) -> MutexGuard<'_, Vec<i64>>
 	{
 		return self.order.lock().expect("Mutex error");
 	}
 
 	/// Lock the LockVec filtered order vector for reading/writing.
-	pub fn borrow_filtered_order(&self) -> MutexGuard<Vec<i64>>
+	pub fn borrow_filtered_order(&self) -> MutexGuard<'_, Vec<i64>>
 	{
 		return self.filtered_order.lock().expect("Mutex error");
 	}
@@ -361,9 +964,9 @@ impl<T: Clone + Menuable> LockVec<T>
 	pub fn borrow(
 		&self,
 	) -> (
-		MutexGuard<HashMap<i64, T, BuildNoHashHasher<i64>>>,
-		MutexGuard<Vec<i64>>,
-		MutexGuard<Vec<i64>>,
+		MutexGuard<'_, HashMap<i64, T, BuildNoHashHasher<i64>>>,
+		MutexGuard<'_, Vec<i64>>,
+		MutexGuard<'_, Vec<i64>>,
 	) {
 		return (
 			self.data.lock().expect("Mutex error"),
@@ -502,6 +1105,30 @@ impl<T: Clone + Menuable> Clone for LockVec<T>
 
 impl LockVec<Podcast>
 {
+	/// Sorts the podcasts in the given order, with the favorites first.
+	/// The filtered order must be updated afterwards for the new order
+	/// to show in menus.
+	pub fn sort_podcasts(&self, sort: PodcastSort)
+	{
+		let (map, mut order, _unused) = self.borrow();
+		drop(_unused);
+		let podcast = |id: &i64| map.get(id).expect("Index error in LockVec");
+		match sort
+		{
+			PodcastSort::Title => order.sort_by(|a, b| podcast(a).cmp(podcast(b))),
+			PodcastSort::Latest => order.sort_by_cached_key(|id| {
+				let pod = podcast(id);
+				(Reverse(pod.latest_pubdate()), pod.sort_title.clone())
+			}),
+			PodcastSort::Unplayed => order.sort_by_cached_key(|id| {
+				let pod = podcast(id);
+				(Reverse(pod.num_unplayed()), pod.sort_title.clone())
+			}),
+		}
+		// the sort is stable, so the favorites keep the order above
+		order.sort_by_key(|id| !podcast(id).favorite);
+	}
+
 	/// This clones the podcast with the given id.
 	pub fn clone_podcast(&self, id: i64) -> Option<Podcast>
 	{
@@ -545,6 +1172,333 @@ pub enum Message
 	Ui(UiMsg),
 	Feed(FeedMsg),
 	Dl(DownloadMsg),
+	Stats(LibraryStats),
+	Gpodder(GpodderMsg),
+	PlayerExit(crate::play_file::PlayerExit),
+	EditorExit(Option<String>),
+	TaskFailed(String),
+}
+
+
+/// Order in which podcasts are listed: by title, by the date of their
+/// most recent episode, or by their number of unplayed episodes. The
+/// latter two list podcasts with the same date or number by title.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PodcastSort
+{
+	#[default]
+	Title,
+	Latest,
+	Unplayed,
+}
+
+impl PodcastSort
+{
+	/// Returns the order that comes after this one when cycling through
+	/// them.
+	pub fn next(self) -> Self
+	{
+		return match self
+		{
+			PodcastSort::Title => PodcastSort::Latest,
+			PodcastSort::Latest => PodcastSort::Unplayed,
+			PodcastSort::Unplayed => PodcastSort::Title,
+		};
+	}
+
+	/// Returns a short description of the order, for the user.
+	pub fn label(self) -> &'static str
+	{
+		return match self
+		{
+			PodcastSort::Title => "by title",
+			PodcastSort::Latest => "by latest episode",
+			PodcastSort::Unplayed => "by unplayed episodes",
+		};
+	}
+}
+
+/// Extra column shown after the title of each episode in the episode
+/// menu, if there is room for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EpisodeColumn
+{
+	Date(DateFormat),
+	/// Date shown relative to today, e.g., "3 days ago", for dates up
+	/// to the given number of days away, and in the given format for
+	/// dates further away.
+	RelativeDate(i64, DateFormat),
+	Duration,
+	Size,
+}
+
+impl EpisodeColumn
+{
+	/// Parses the name of a column as used in the config file. Dates are
+	/// shown in the given format.
+	pub fn parse(name: &str, date_format: &DateFormat) -> Option<Self>
+	{
+		return match name
+		{
+			"date" => Some(EpisodeColumn::Date(date_format.clone())),
+			"duration" => Some(EpisodeColumn::Duration),
+			"size" => Some(EpisodeColumn::Size),
+			_ => None,
+		};
+	}
+
+	/// Returns the number of columns taken up by the column, which fits
+	/// the longest values, e.g., "2022-01-31", "11 months ago", "12h 34m",
+	/// or "123.4 MB".
+	pub fn width(&self) -> usize
+	{
+		return match self
+		{
+			EpisodeColumn::Date(date_format) => date_format.width(),
+			EpisodeColumn::RelativeDate(_, date_format) => date_format.width().max(13),
+			EpisodeColumn::Duration => 7,
+			EpisodeColumn::Size => 8,
+		};
+	}
+}
+
+/// Format in which dates are shown, as a chrono format string (e.g.,
+/// "%F" for "2024-03-15"), along with the most columns that a date in
+/// this format takes up. The format must have been checked for invalid
+/// specifiers, as formatting a date with them panics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateFormat
+{
+	format: String,
+	width: usize,
+}
+
+impl DateFormat
+{
+	/// Creates a new DateFormat. Its width is found by formatting every
+	/// day of a leap year, which covers the names of all of the months
+	/// and weekdays.
+	pub fn new(format: &str) -> Self
+	{
+		let start = Utc.ymd(2024, 1, 1).and_hms(12, 0, 0);
+		let width = (0..366)
+			.map(|day| {
+				let date = start + chrono::Duration::days(day);
+				return date.format(format).to_string().display_width();
+			})
+			.max()
+			.unwrap_or(0);
+		return Self {
+			format: format.to_string(),
+			width: width,
+		};
+	}
+
+	/// Formats a date.
+	pub fn format(&self, date: &DateTime<Utc>) -> String
+	{
+		return date.format(&self.format).to_string();
+	}
+
+	/// Returns the most columns that a date takes up in this format.
+	pub fn width(&self) -> usize
+	{
+		return self.width;
+	}
+}
+
+/// Describes how far `date` is from `now` in calendar days, e.g.,
+/// "today", "yesterday", "3 weeks ago", or "in 2 days". Both dates should
+/// be in the timezone whose midnight counts as the start of a day.
+/// Returns None if the date is more than `max_days` days away.
+pub fn relative_date<Tz: TimeZone>(
+	date: &DateTime<Tz>,
+	now: &DateTime<Tz>,
+	max_days: i64,
+) -> Option<String>
+{
+	let days = (now.date().naive_local() - date.date().naive_local()).num_days();
+	if days.abs() > max_days
+	{
+		return None;
+	}
+	let span = match days.abs()
+	{
+		0 => return Some("today".to_string()),
+		1 if days > 0 => return Some("yesterday".to_string()),
+		1 => return Some("tomorrow".to_string()),
+		n if n < 7 => format!("{n} days"),
+		n if n < 14 => "1 week".to_string(),
+		n if n < 30 => format!("{} weeks", n / 7),
+		n if n < 60 => "1 month".to_string(),
+		n if n < 365 => format!("{} months", n / 30),
+		n if n < 730 => "1 year".to_string(),
+		n => format!("{} years", n / 365),
+	};
+	if days > 0
+	{
+		return Some(format!("{span} ago"));
+	}
+	return Some(format!("in {span}"));
+}
+
+/// Arrangement of the main panels on the screen. The automatic layout
+/// shows the details panel next to the podcasts and episodes only if the
+/// terminal is wide enough, while the zoomed layout shows only the panel
+/// that has the focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout
+{
+	#[default]
+	Auto,
+	TwoPane,
+	ThreePane,
+	Zoomed,
+}
+
+impl Layout
+{
+	/// Returns the layout that comes after this one when cycling through
+	/// them.
+	pub fn next(self) -> Self
+	{
+		return match self
+		{
+			Layout::Auto => Layout::TwoPane,
+			Layout::TwoPane => Layout::ThreePane,
+			Layout::ThreePane => Layout::Zoomed,
+			Layout::Zoomed => Layout::Auto,
+		};
+	}
+
+	/// Returns a short description of the layout, for the user.
+	pub fn label(self) -> &'static str
+	{
+		return match self
+		{
+			Layout::Auto => "automatic",
+			Layout::TwoPane => "podcasts and episodes",
+			Layout::ThreePane => "podcasts, episodes and details",
+			Layout::Zoomed => "zoomed",
+		};
+	}
+}
+
+/// Width of the podcast panel set by the user, either as a percentage of
+/// the width of the terminal, or as a number of columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanelWidth
+{
+	Percent(u16),
+	Columns(u16),
+}
+
+impl PanelWidth
+{
+	/// Parses a width such as "40%" or "30", returning None if it is
+	/// not a valid width.
+	pub fn parse(width: &str) -> Option<Self>
+	{
+		let width = width.trim();
+		return match width.strip_suffix('%')
+		{
+			Some(percent) => match percent.trim().parse::<u16>()
+			{
+				Ok(percent) if percent > 0 && percent < 100 => Some(PanelWidth::Percent(percent)),
+				_ => None,
+			},
+			None => match width.parse::<u16>()
+			{
+				Ok(columns) if columns > 0 => Some(PanelWidth::Columns(columns)),
+				_ => None,
+			},
+		};
+	}
+
+	/// Returns the number of columns for a terminal `n_col` columns wide.
+	pub fn columns(self, n_col: u16) -> u16
+	{
+		return match self
+		{
+			PanelWidth::Percent(percent) => (n_col as u32 * percent as u32 / 100) as u16,
+			PanelWidth::Columns(columns) => columns,
+		};
+	}
+}
+
+/// Order in which the episodes of a podcast are listed. Newest first is
+/// the default; the order chosen for a podcast is stored in the
+/// database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EpisodeSort
+{
+	#[default]
+	Newest,
+	Oldest,
+	Title,
+}
+
+impl EpisodeSort
+{
+	/// Returns the order that comes after this one when cycling through
+	/// them.
+	pub fn next(self) -> Self
+	{
+		return match self
+		{
+			EpisodeSort::Newest => EpisodeSort::Oldest,
+			EpisodeSort::Oldest => EpisodeSort::Title,
+			EpisodeSort::Title => EpisodeSort::Newest,
+		};
+	}
+
+	/// Returns the name of the order, as stored in the database.
+	pub fn as_str(self) -> &'static str
+	{
+		return match self
+		{
+			EpisodeSort::Newest => "newest",
+			EpisodeSort::Oldest => "oldest",
+			EpisodeSort::Title => "title",
+		};
+	}
+
+	/// Parses the name of an order as stored in the database. Anything
+	/// unknown falls back to newest first.
+	pub fn from_name(name: Option<&str>) -> Self
+	{
+		return match name
+		{
+			Some("oldest") => EpisodeSort::Oldest,
+			Some("title") => EpisodeSort::Title,
+			_ => EpisodeSort::Newest,
+		};
+	}
+
+	/// Returns a short description of the order, for the user.
+	pub fn label(self) -> &'static str
+	{
+		return match self
+		{
+			EpisodeSort::Newest => "newest first",
+			EpisodeSort::Oldest => "oldest first",
+			EpisodeSort::Title => "by title",
+		};
+	}
+
+	/// Sorts a list of episodes that is ordered newest first (as they
+	/// come from the database) in this order. Episodes with the same
+	/// title stay newest first.
+	pub fn sort(self, episodes: &mut [Episode])
+	{
+		match self
+		{
+			EpisodeSort::Newest => (),
+			EpisodeSort::Oldest => episodes.reverse(),
+			EpisodeSort::Title => episodes.sort_by_cached_key(|ep| ep.title.to_lowercase()),
+		}
+	}
 }
 
 
@@ -564,14 +1518,174 @@ pub enum FilterType
 {
 	Played,
 	Downloaded,
+	View,
+	Tag,
+	Archived,
+	Favorites,
 }
 
-/// Struct holding information about all active filters.
-#[derive(Debug, Clone, Copy)]
+/// Struct holding information about all active filters. `tag` limits
+/// the podcasts shown to the ones with that tag, `favorites_only` to the
+/// favorite podcasts, and archived episodes
+/// are only shown if `show_archived` is set. `podcast_search` and
+/// `episode_search` limit the podcasts or episodes shown to the ones
+/// whose titles contain the search text, ignoring case.
+#[derive(Debug, Clone)]
 pub struct Filters
 {
 	pub played: FilterStatus,
 	pub downloaded: FilterStatus,
+	pub tag: Option<String>,
+	pub favorites_only: bool,
+	pub show_archived: bool,
+	pub podcast_search: Option<String>,
+	pub episode_search: Option<String>,
+}
+
+impl Filters
+{
+	/// Updates the filtered order of the podcasts, and of the episodes
+	/// of each podcast, to show only the items that pass the filters.
+	/// Podcasts without any episodes left are hidden, unless it is only
+	/// the episode search that leaves them empty, so that searching the
+	/// episodes of one podcast does not change the list of podcasts.
+	pub fn apply(&self, podcasts: &LockVec<Podcast>)
+	{
+		let podcast_search = self.podcast_search.as_ref().map(|s| s.to_lowercase());
+		let episode_search = self.episode_search.as_ref().map(|s| s.to_lowercase());
+
+		let (pod_map, pod_order, mut pod_filtered_order) = podcasts.borrow();
+		let mut new_filtered_pods = Vec::new();
+		for pod_id in pod_order.iter()
+		{
+			let pod = pod_map.get(pod_id).unwrap();
+			let mut any_shown = false;
+			let new_filter = pod.episodes.filter_map(|ep| {
+				let play_filter = match self.played
+				{
+					FilterStatus::All => false,
+					FilterStatus::PositiveCases => !ep.is_played(),
+					FilterStatus::NegativeCases => ep.is_played(),
+				};
+				let download_filter = match self.downloaded
+				{
+					FilterStatus::All => false,
+					FilterStatus::PositiveCases => ep.path.is_none(),
+					FilterStatus::NegativeCases => ep.path.is_some(),
+				};
+				let archive_filter = ep.archived && !self.show_archived;
+				if play_filter | download_filter | archive_filter
+				{
+					return None;
+				}
+				any_shown = true;
+				if matches_search(&ep.title, episode_search.as_deref())
+				{
+					return Some(ep.id);
+				}
+				else
+				{
+					return None;
+				}
+			});
+			let tag_filter = match &self.tag
+			{
+				Some(tag) => pod.tags.contains(tag),
+				None => true,
+			};
+			// podcasts without any episodes (e.g., newly added feeds
+			// that are still syncing) are not hidden by the episode
+			// filters
+			let episodes_shown = any_shown || pod.episodes.is_empty();
+			let favorite_filter = pod.favorite || !self.favorites_only;
+			if episodes_shown && tag_filter && favorite_filter &&
+				matches_search(&pod.title, podcast_search.as_deref())
+			{
+				new_filtered_pods.push(pod.id);
+			}
+			let mut filtered_order = pod.episodes.borrow_filtered_order();
+			*filtered_order = new_filter;
+		}
+		*pod_filtered_order = new_filtered_pods;
+	}
+
+	/// Moves on to the next of the common combinations of the played and
+	/// downloaded filters: all episodes, unplayed ones, downloaded ones,
+	/// and downloaded ones that are unplayed. Other combinations, set
+	/// with the separate filters, go back to all episodes.
+	pub fn next_view(&mut self)
+	{
+		let (played, downloaded) = match (self.played, self.downloaded)
+		{
+			(FilterStatus::All, FilterStatus::All) => {
+				(FilterStatus::NegativeCases, FilterStatus::All)
+			}
+			(FilterStatus::NegativeCases, FilterStatus::All) => {
+				(FilterStatus::All, FilterStatus::PositiveCases)
+			}
+			(FilterStatus::All, FilterStatus::PositiveCases) => {
+				(FilterStatus::NegativeCases, FilterStatus::PositiveCases)
+			}
+			_ => (FilterStatus::All, FilterStatus::All),
+		};
+		self.played = played;
+		self.downloaded = downloaded;
+	}
+
+	/// Describes the podcasts left by the favorites and tag filters, e.g.,
+	/// "favorites, news". Returns None if neither is active.
+	pub fn podcast_label(&self) -> Option<String>
+	{
+		let mut parts = Vec::new();
+		if self.favorites_only
+		{
+			parts.push("favorites");
+		}
+		if let Some(tag) = &self.tag
+		{
+			parts.push(tag);
+		}
+		if parts.is_empty()
+		{
+			return None;
+		}
+		return Some(parts.join(", "));
+	}
+
+	/// Describes the episodes left by the played and downloaded filters,
+	/// e.g., "downloaded, unplayed". Returns None if neither is active.
+	pub fn episode_label(&self) -> Option<String>
+	{
+		let mut parts = Vec::new();
+		match self.downloaded
+		{
+			FilterStatus::PositiveCases => parts.push("downloaded"),
+			FilterStatus::NegativeCases => parts.push("not downloaded"),
+			FilterStatus::All => (),
+		}
+		match self.played
+		{
+			FilterStatus::PositiveCases => parts.push("played"),
+			FilterStatus::NegativeCases => parts.push("unplayed"),
+			FilterStatus::All => (),
+		}
+		if parts.is_empty()
+		{
+			return None;
+		}
+		return Some(parts.join(", "));
+	}
+}
+
+/// Checks whether a title contains the (lowercase) search text,
+/// ignoring case. Everything matches if there is no search text.
+fn matches_search(title: &str, search: Option<&str>) -> bool
+{
+	return match search
+	{
+		Some(search) => title.to_lowercase().contains(search),
+		None => true,
+	};
 }
 
 impl Default for Filters
@@ -582,6 +1696,11 @@ impl Default for Filters
 		{
 			played: FilterStatus::All,
 			downloaded: FilterStatus::All,
+			tag: None,
+			favorites_only: false,
+			show_archived: false,
+			podcast_search: None,
+			episode_search: None,
 		};
 	}
 }
@@ -592,6 +1711,9 @@ pub trait StringUtils
 {
 	fn substr(&self, start: usize, length: usize) -> String;
 	fn grapheme_len(&self) -> usize;
+	fn display_width(&self) -> usize;
+	fn truncate_width(&self, width: usize) -> String;
+	fn wrap_width(&self, width: usize, max_lines: usize) -> Vec<String>;
 }
 
 impl StringUtils for String
@@ -612,4 +1734,62 @@ impl StringUtils for String
 	{
 		return self.graphemes(true).count();
 	}
+
+	/// Counts the number of columns the String takes up in the
+	/// terminal, e.g., 2 for most emoji and CJK characters.
+	fn display_width(&self) -> usize
+	{
+		return self.as_str().width();
+	}
+
+	/// Cuts the String short at a Unicode grapheme boundary so that it
+	/// takes up no more than `width` columns in the terminal, ending it
+	/// with an ellipsis if anything was cut.
+	fn truncate_width(&self, width: usize) -> String
+	{
+		if self.display_width() <= width
+		{
+			return self.clone();
+		}
+		let mut out = String::new();
+		let mut used = 0;
+		for grapheme in self.graphemes(true)
+		{
+			let grapheme_width = grapheme.width();
+			// leave room for the ellipsis
+			if used + grapheme_width + 1 > width
+			{
+				break;
+			}
+			out.push_str(grapheme);
+			used += grapheme_width;
+		}
+		if width > 0
+		{
+			out.push('…');
+		}
+		return out;
+	}
+
+	/// Splits the String into lines of no more than `width` columns,
+	/// breaking at spaces where possible, using up to `max_lines` lines.
+	/// If the text does not fit, the last line is cut short as with
+	/// `truncate_width()`. Always returns at least one line.
+	fn wrap_width(&self, width: usize, max_lines: usize) -> Vec<String>
+	{
+		if max_lines <= 1 || width == 0 || self.display_width() <= width
+		{
+			return vec![self.truncate_width(width)];
+		}
+		let mut lines: Vec<String> = textwrap::wrap(self, width)
+			.iter()
+			.map(|line| line.to_string())
+			.collect();
+		if lines.len() > max_lines
+		{
+			let rest = lines.split_off(max_lines - 1).join(" ");
+			lines.push(rest.truncate_width(width));
+		}
+		return lines;
+	}
 }
@@ -1,11 +1,15 @@
 use anyhow::{anyhow, Result};
 
-use crossterm::style::Color;
+use crossterm::style::{Color, ContentStyle, Stylize};
 use lazy_static::lazy_static;
 use regex::Regex;
 
 use crate::config::AppColorsFromToml;
 
+/// Names of the built-in color themes, which can be chosen with the
+/// `theme` option in the config file.
+pub const THEMES: [&str; 4] = ["default", "light", "solarized-dark", "gruvbox-dark"];
+
 lazy_static!
 {
 	/// Regex for parsing a color specified as hex code.
@@ -21,7 +25,10 @@ lazy_static!
 
 
 /// Holds information about the colors to use in the application. Tuple
-/// values represent (foreground, background), respectively.
+/// values represent (foreground, background), respectively. If
+/// `monochrome` is set, no colors are used at all, and only attributes
+/// that any terminal supports (bold, underline and reverse video) set
+/// things apart.
 #[derive(Debug, Clone)]
 pub struct AppColors
 {
@@ -30,6 +37,10 @@ pub struct AppColors
 	pub highlighted_active: (Color, Color),
 	pub highlighted: (Color, Color),
 	pub error: (Color, Color),
+	pub played: (Color, Color),
+	pub downloaded: (Color, Color),
+	pub new: (Color, Color),
+	pub monochrome: bool,
 }
 
 impl AppColors
@@ -43,9 +54,107 @@ impl AppColors
 			highlighted_active: (Color::Black, Color::DarkYellow),
 			highlighted: (Color::Black, Color::Grey),
 			error: (Color::Red, Color::Black),
+			played: (Color::DarkGrey, Color::Reset),
+			downloaded: (Color::Cyan, Color::Reset),
+			new: (Color::Green, Color::Reset),
+			monochrome: false,
+		};
+	}
+
+	/// Creates an AppColors struct with the colors of one of the built-in
+	/// themes listed in `THEMES`. Returns an error, listing the available
+	/// themes, if there is no theme with that name.
+	pub fn theme(name: &str) -> Result<Self>
+	{
+		let rgb = |hex: &str| Self::color_from_str(hex).expect("Invalid theme color");
+		return match name
+		{
+			"default" => Ok(Self::default()),
+			"light" => Ok(Self {
+				normal: (Color::Black, Color::Reset),
+				bold: (Color::DarkBlue, Color::Reset),
+				highlighted_active: (Color::White, Color::DarkBlue),
+				highlighted: (Color::Black, Color::Grey),
+				error: (Color::White, Color::DarkRed),
+				played: (Color::DarkGrey, Color::Reset),
+				downloaded: (Color::DarkCyan, Color::Reset),
+				new: (Color::DarkGreen, Color::Reset),
+				monochrome: false,
+			}),
+			"solarized-dark" => {
+				let base03 = rgb("#002b36");
+				Ok(Self {
+					normal: (rgb("#839496"), base03),
+					bold: (rgb("#93a1a1"), base03),
+					highlighted_active: (base03, rgb("#b58900")),
+					highlighted: (rgb("#93a1a1"), rgb("#073642")),
+					error: (rgb("#dc322f"), rgb("#073642")),
+					played: (rgb("#586e75"), base03),
+					downloaded: (rgb("#2aa198"), base03),
+					new: (rgb("#859900"), base03),
+					monochrome: false,
+				})
+			}
+			"gruvbox-dark" => {
+				let bg = rgb("#282828");
+				Ok(Self {
+					normal: (rgb("#d5c4a1"), bg),
+					bold: (rgb("#fbf1c7"), bg),
+					highlighted_active: (bg, rgb("#fabd2f")),
+					highlighted: (rgb("#ebdbb2"), rgb("#504945")),
+					error: (rgb("#fb4934"), rgb("#3c3836")),
+					played: (rgb("#928374"), bg),
+					downloaded: (rgb("#8ec07c"), bg),
+					new: (rgb("#b8bb26"), bg),
+					monochrome: false,
+				})
+			}
+			_ => Err(anyhow!(
+				"Unknown theme \"{}\" in config.toml; available themes are: {}",
+				name,
+				THEMES.join(", ")
+			)),
 		};
 	}
 
+	/// Returns a style with the given (foreground, background) colors, or
+	/// a style without any colors in monochrome mode, so that no colors
+	/// are ever sent to the terminal.
+	pub fn style(&self, colors: (Color, Color)) -> ContentStyle
+	{
+		if self.monochrome
+		{
+			return ContentStyle::new();
+		}
+		return ContentStyle::new().with(colors.0).on(colors.1);
+	}
+
+	/// Replaces the RGB colors with the nearest of the 256 colors that
+	/// most terminals can show, for terminals that cannot show any RGB
+	/// color (a.k.a. truecolor).
+	pub fn limit_to_256_colors(&mut self)
+	{
+		for (fg, bg) in [
+			&mut self.normal,
+			&mut self.bold,
+			&mut self.highlighted_active,
+			&mut self.highlighted,
+			&mut self.error,
+			&mut self.played,
+			&mut self.downloaded,
+			&mut self.new,
+		]
+		{
+			for color in [fg, bg]
+			{
+				if let Color::Rgb { r, g, b } = *color
+				{
+					*color = Color::AnsiValue(nearest_ansi_value(r, g, b));
+				}
+			}
+		}
+	}
+
 	/// Reading in values that were set in the config file, this changes
 	/// the associated colors. Note that this only modifies colors that
 	/// were set in the config, so this is most useful in conjunction
@@ -123,6 +232,48 @@ impl AppColors
 				self.error.1 = v;
 			}
 		}
+		if let Some(val) = config.played_foreground
+		{
+			if let Ok(v) = Self::color_from_str(&val)
+			{
+				self.played.0 = v;
+			}
+		}
+		if let Some(val) = config.played_background
+		{
+			if let Ok(v) = Self::color_from_str(&val)
+			{
+				self.played.1 = v;
+			}
+		}
+		if let Some(val) = config.downloaded_foreground
+		{
+			if let Ok(v) = Self::color_from_str(&val)
+			{
+				self.downloaded.0 = v;
+			}
+		}
+		if let Some(val) = config.downloaded_background
+		{
+			if let Ok(v) = Self::color_from_str(&val)
+			{
+				self.downloaded.1 = v;
+			}
+		}
+		if let Some(val) = config.new_foreground
+		{
+			if let Ok(v) = Self::color_from_str(&val)
+			{
+				self.new.0 = v;
+			}
+		}
+		if let Some(val) = config.new_background
+		{
+			if let Ok(v) = Self::color_from_str(&val)
+			{
+				self.new.1 = v;
+			}
+		}
 	}
 
 	/// Parses a string that specifies a color either in hex format
@@ -183,6 +334,61 @@ impl AppColors
 	}
 }
 
+/// Checks whether colors were turned off with the `NO_COLOR` environment
+/// variable (see https://no-color.org), which counts as set if it is not
+/// empty.
+pub fn no_color_requested() -> bool
+{
+	return std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+}
+
+/// Checks whether the terminal says that it can show any RGB color, as
+/// most terminals that can do so set `COLORTERM` to "truecolor" or
+/// "24bit".
+pub fn truecolor_supported() -> bool
+{
+	return matches!(
+		std::env::var("COLORTERM").as_deref(),
+		Ok("truecolor") | Ok("24bit")
+	);
+}
+
+/// Returns the index of the color closest to the given RGB value among
+/// the 6x6x6 color cube and the 24 shades of grey of the 256-color
+/// palette. The first 16 colors are left out, as terminals often change
+/// them.
+fn nearest_ansi_value(r: u8, g: u8, b: u8) -> u8
+{
+	const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+	let nearest_level = |value: u8| {
+		return (0..6)
+			.min_by_key(|i| (LEVELS[*i] as i32 - value as i32).abs())
+			.unwrap_or(0);
+	};
+	let distance = |(r2, g2, b2): (u8, u8, u8)| {
+		return [(r, r2), (g, g2), (b, b2)]
+			.iter()
+			.map(|(a, b)| (*a as i32 - *b as i32).pow(2))
+			.sum::<i32>();
+	};
+
+	let (ri, gi, bi) = (nearest_level(r), nearest_level(g), nearest_level(b));
+	let cube = (LEVELS[ri], LEVELS[gi], LEVELS[bi]);
+	let cube_index = 16 + 36 * ri + 6 * gi + bi;
+
+	// greys go from 8 to 238 in steps of 10
+	let average = (r as i32 + g as i32 + b as i32) / 3;
+	let grey_step = ((average - 8 + 5) / 10).clamp(0, 23);
+	let grey = (8 + 10 * grey_step) as u8;
+	let grey_index = 232 + grey_step as usize;
+
+	if distance((grey, grey, grey)) < distance(cube)
+	{
+		return grey_index as u8;
+	}
+	return cube_index as u8;
+}
+
 
 // TESTS -----------------------------------------------------------------
 #[cfg(test)]
@@ -243,6 +449,30 @@ mod tests
 		});
 	}
 
+	#[test]
+	fn themes()
+	{
+		for name in THEMES
+		{
+			assert!(AppColors::theme(name).is_ok());
+		}
+		let err = AppColors::theme("gruvbox").unwrap_err().to_string();
+		assert!(err.contains("default, light, solarized-dark, gruvbox-dark"));
+	}
+
+	#[test]
+	fn nearest_256_colors()
+	{
+		assert_eq!(nearest_ansi_value(255, 0, 0), 196);
+		assert_eq!(nearest_ansi_value(0, 0, 0), 16);
+		assert_eq!(nearest_ansi_value(0x28, 0x28, 0x28), 235);
+		assert_eq!(nearest_ansi_value(0xfa, 0xbd, 0x2f), 214);
+
+		let mut colors = AppColors::theme("gruvbox-dark").unwrap();
+		colors.limit_to_256_colors();
+		assert_eq!(colors.normal.1, Color::AnsiValue(235));
+	}
+
 	#[test]
 	fn color_rgb_no_space()
 	{
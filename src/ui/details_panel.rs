@@ -1,3 +1,4 @@
+use std::cmp::min;
 use std::rc::Rc;
 
 use chrono::{DateTime, Utc};
@@ -21,18 +22,41 @@ pub enum DetailsLine
 
 
 /// Struct holding the raw data used for building the details panel.
+/// If `is_podcast` is set, the details describe the podcast itself
+/// rather than one of its episodes.
 #[derive(Debug)]
 pub struct Details
 {
+	pub is_podcast: bool,
 	pub pod_title: Option<String>,
 	pub ep_title: Option<String>,
-	pub pubdate: Option<DateTime<Utc>>,
+	pub author: Option<String>,
+	pub feed_url: Option<String>,
+	pub pubdate: Option<String>,
+	pub last_checked: Option<DateTime<Utc>>,
+	pub last_error: Option<String>,
+	pub last_error_at: Option<DateTime<Utc>>,
+	pub inactive: bool,
+	pub synced: bool,
+	pub counts: Option<EpisodeCounts>,
+	pub cover: Option<String>,
 	pub duration: Option<String>,
+	pub downloaded: Option<String>,
 	pub explicit: Option<bool>,
 	pub description: Option<String>,
 }
 
+/// Numbers of episodes in a podcast, shown in the details of the podcast.
+#[derive(Debug, Clone, Copy)]
+pub struct EpisodeCounts
+{
+	pub total: usize,
+	pub downloaded: usize,
+	pub unplayed: usize,
+}
+
 #[derive(Debug)]
+#[allow(dead_code)]
 pub struct DetailsPanel
 {
 	pub panel: Panel,
@@ -94,58 +118,54 @@ impl DetailsPanel
 
 	/// Scrolls the details panel up or down by `lines` lines.
 	///
-	/// This function examines the new selected value, ensures it does
-	/// not fall out of bounds, and then updates the panel to
-	/// represent the new visible list.
+	/// This function examines the new top row, ensures it does not fall
+	/// out of bounds, and then updates the panel to represent the new
+	/// visible section of the details.
 	pub fn scroll(&mut self, lines: Scroll)
 	{
-		if self.content.is_empty()
-		{
-			return;
-		}
-		let total_rows = self.content.len() as u16;
 		let old_top_row = self.top_row;
-
-		match lines
+		self.top_row = match lines
 		{
-			Scroll::Up(v) => {
-				if let Some(top) = self.top_row.checked_sub(v)
-				{
-					self.top_row = top;
-				}
-				else
-				{
-					self.top_row = 0;
-				}
-				if self.top_row != old_top_row
-				{
-					self.panel.clear_inner();
-					// self.details_template(self.top_row);
-					self.write_details();
-				}
-			}
-			Scroll::Down(v) => {
-				let n_row = self.panel.get_rows();
-				// can't scroll if details are shorter than screen
-				if total_rows <= n_row
-				{
-					return;
-				}
-				let move_dist = std::cmp::min(
-					v,
-					total_rows - self.top_row - n_row
-				);
-				self.top_row += move_dist;
-				if self.top_row != old_top_row
-				{
-					self.panel.clear_inner();
-					// self.details_template(self.top_row);
-					self.write_details();
-				}
-			}
+			Scroll::Up(v) => self.top_row.saturating_sub(v),
+			Scroll::Down(v) => min(self.top_row.saturating_add(v), self.max_top_row()),
+		};
+		if self.top_row != old_top_row
+		{
+			self.update_footer();
+			self.redraw();
+			self.write_details();
 		}
 	}
 
+	/// Returns the last row that can be at the top of the panel, so that
+	/// the end of the details is at the bottom of the panel. This is 0 if
+	/// the details are shorter than the panel.
+	/// Returns the number of rows of details that fit in the panel.
+	pub fn get_rows(&self) -> u16
+	{
+		return self.panel.get_rows();
+	}
+
+	fn max_top_row(&self) -> u16
+	{
+		return (self.content.len() as u16).saturating_sub(self.panel.get_rows());
+	}
+
+	/// Shows which rows of the details are visible at the bottom of the
+	/// panel, e.g., "3-20/45", if they do not all fit at once.
+	fn update_footer(&mut self)
+	{
+		let total = self.content.len();
+		let n_row = self.panel.get_rows() as usize;
+		let footer = if total > n_row {
+			let first = self.top_row as usize + 1;
+			Some(format!("{}-{}/{}", first, min(first + n_row - 1, total), total))
+		} else {
+			None
+		};
+		self.panel.set_footer(footer);
+	}
+
 	/// Format the details content to fit the panel as currently sized
 	/// and save it as Strings. This needs to be done to allow the
 	/// content to be scrollable.
@@ -154,13 +174,9 @@ impl DetailsPanel
 		if let Some(details) = &self.details
 		{
 			let num_cols = self.panel.get_cols() as usize;
-			let bold = style::ContentStyle::new()
-				.with(self.panel.colors.bold.0)
-				.on(self.panel.colors.bold.1)
+			let bold = self.panel.colors.style(self.panel.colors.bold)
 				.attribute(style::Attribute::Bold);
-			let underlined = style::ContentStyle::new()
-				.with(self.panel.colors.normal.0)
-				.on(self.panel.colors.normal.1)
+			let underlined = self.panel.colors.style(self.panel.colors.normal)
 				.attribute(style::Attribute::Underlined);
 
 			self.content.clear();
@@ -179,26 +195,108 @@ impl DetailsPanel
 			}
 
 			// episode title
-			let text = match &details.ep_title
+			if !details.is_podcast
 			{
-				Some(t) => t,
-				None => "No title",
-			};
-			let wrapper = textwrap::wrap(text, num_cols);
-			for line in wrapper
-			{
-				self.content
-					.push(DetailsLine::Line(line.to_string(), Some(bold)));
+				let text = match &details.ep_title
+				{
+					Some(t) => t,
+					None => "No title",
+				};
+				let wrapper = textwrap::wrap(text, num_cols);
+				for line in wrapper
+				{
+					self.content
+						.push(DetailsLine::Line(line.to_string(), Some(bold)));
+				}
 			}
 
 			self.content.push(DetailsLine::Blank); // blank line
 
+			// author
+			if let Some(author) = &details.author
+			{
+				self.content.push(DetailsLine::KeyValueLine(
+					("Author".to_string(), Some(underlined)),
+					(author.clone(), None),
+				));
+			}
+
+			// address of the feed
+			if let Some(url) = &details.feed_url
+			{
+				self.content.push(DetailsLine::KeyValueLine(
+					("Feed".to_string(), Some(underlined)),
+					(url.clone(), None),
+				));
+			}
+
+			// numbers of episodes
+			if let Some(counts) = details.counts
+			{
+				for (key, count) in [
+					("Episodes", counts.total),
+					("Downloaded", counts.downloaded),
+					("Unplayed", counts.unplayed),
+				]
+				{
+					self.content.push(DetailsLine::KeyValueLine(
+						(key.to_string(), Some(underlined)),
+						(count.to_string(), None),
+					));
+				}
+			}
+
 			// published date
-			if let Some(date) = details.pubdate
+			if let Some(date) = &details.pubdate
 			{
 				self.content.push(DetailsLine::KeyValueLine(
 					("Published".to_string(), Some(underlined)),
-					(format!("{}", date.format("%B %-d, %Y")), None),
+					(date.clone(), None),
+				));
+			}
+
+			// last synced
+			if let Some(date) = details.last_checked.filter(|_| details.synced)
+			{
+				self.content.push(DetailsLine::KeyValueLine(
+					("Last synced".to_string(), Some(underlined)),
+					(time_since(date, Utc::now()), None),
+				));
+			}
+
+			// podcast that is no longer synced, or was imported and has
+			// not been synced yet
+			if details.inactive || !details.synced
+			{
+				let status = if details.inactive { "Inactive" } else { "Not yet synced" };
+				self.content.push(DetailsLine::KeyValueLine(
+					("Status".to_string(), Some(underlined)),
+					(status.to_string(), None),
+				));
+			}
+
+			// error from the last sync, if it failed
+			if let Some(err) = &details.last_error
+			{
+				let text = match details.last_error_at
+				{
+					Some(date) => format!(
+						"Last sync failed {}: {}", time_since(date, Utc::now()), err
+					),
+					None => format!("Last sync failed: {err}"),
+				};
+				for line in textwrap::wrap(&text, num_cols)
+				{
+					self.content.push(DetailsLine::Line(line.to_string(), None));
+				}
+			}
+
+			// location of the downloaded cover art
+			if let Some(cover) = &details.cover
+			{
+				self.content.push(DetailsLine::KeyValueLine(
+					("Cover".to_string(), Some(underlined)),
+					(cover.clone(), None),
 				));
 			}
 
@@ -211,6 +309,15 @@ impl DetailsPanel
 				));
 			}
 
+			// date and size of the download
+			if let Some(downloaded) = &details.downloaded
+			{
+				self.content.push(DetailsLine::KeyValueLine(
+					("Downloaded".to_string(), Some(underlined)),
+					(downloaded.clone(), None),
+				));
+			}
+
 			// explicit
 			if let Some(exp) = details.explicit
 			{
@@ -261,6 +368,11 @@ impl DetailsPanel
 				}
 			}
 		}
+
+		// the details may now take up fewer rows, e.g., if the panel
+		// got wider
+		self.top_row = min(self.top_row, self.max_top_row());
+		self.update_footer();
 	}
 
 	/// Write the details content to the screen.
@@ -269,8 +381,13 @@ impl DetailsPanel
 		if !self.content.is_empty()
 		{
 			let mut row = 0;
+			let n_row = self.panel.get_rows();
 			for line in self.content.iter().skip(self.top_row as usize)
 			{
+				if row >= n_row
+				{
+					break;
+				}
 				match line
 				{
 					DetailsLine::Blank => row += 1,
@@ -295,3 +412,240 @@ impl DetailsPanel
 		}
 	}
 }
+
+/// Describes how long ago `date` was, relative to `now`, in a short
+/// human-readable form, e.g., "2 hours ago".
+fn time_since(date: DateTime<Utc>, now: DateTime<Utc>) -> String
+{
+	let elapsed = now - date;
+	let (num, unit) = if elapsed.num_days() > 0
+	{
+		(elapsed.num_days(), "day")
+	}
+	else if elapsed.num_hours() > 0
+	{
+		(elapsed.num_hours(), "hour")
+	}
+	else if elapsed.num_minutes() > 0
+	{
+		(elapsed.num_minutes(), "minute")
+	}
+	else
+	{
+		return "just now".to_string();
+	};
+	let plural = if num > 1 { "s" } else { "" };
+	return format!("{num} {unit}{plural} ago");
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::types::StringUtils;
+	use chrono::Duration;
+
+	fn create_details_panel(n_row: u16, n_col: u16) -> DetailsPanel
+	{
+		let colors = Rc::new(AppColors::default());
+		return DetailsPanel::new(
+			"Details".to_string(),
+			2,
+			colors,
+			n_row,
+			n_col,
+			0,
+			(0, 0, 0, 0),
+		);
+	}
+
+	#[test]
+	fn podcast_details()
+	{
+		let mut det = create_details_panel(20, 43);
+		det.change_details(Details {
+			is_podcast: true,
+			pod_title: Some("A Podcast".to_string()),
+			ep_title: None,
+			author: Some("Some Author".to_string()),
+			feed_url: Some("https://example.com/feed.xml".to_string()),
+			pubdate: None,
+			last_checked: Some(Utc::now() - Duration::hours(2)),
+			last_error: None,
+			last_error_at: None,
+			inactive: false,
+			synced: true,
+			counts: Some(EpisodeCounts {
+				total: 42,
+				downloaded: 3,
+				unplayed: 10,
+			}),
+			cover: Some("/podcasts/A Podcast/cover.jpg".to_string()),
+			duration: None,
+			downloaded: None,
+			explicit: Some(false),
+			description: Some("Why I subscribed.".to_string()),
+		});
+
+		assert_eq!(det.panel.get_row(0), "A Podcast");
+		assert_eq!(det.panel.get_row(1), "");
+		assert_eq!(det.panel.get_row(2), "Author: Some Author");
+		assert_eq!(det.panel.get_row(3), "Feed: https://example.com/feed.xml");
+		assert_eq!(det.panel.get_row(4), "Episodes: 42");
+		assert_eq!(det.panel.get_row(5), "Downloaded: 3");
+		assert_eq!(det.panel.get_row(6), "Unplayed: 10");
+		assert_eq!(det.panel.get_row(7), "Last synced: 2 hours ago");
+		assert_eq!(det.panel.get_row(8), "Cover: /podcasts/A Podcast/cover.jpg");
+		assert_eq!(det.panel.get_row(9), "Explicit: No");
+		assert_eq!(det.panel.get_row(11), "Description:");
+		assert_eq!(det.panel.get_row(12), "Why I subscribed.");
+	}
+
+	#[test]
+	fn wide_details()
+	{
+		let mut det = create_details_panel(20, 23);
+		det.change_details(Details {
+			is_podcast: true,
+			pod_title: Some("ポッドキャスト".to_string()),
+			ep_title: None,
+			author: Some("作者の名前はとても長いです".to_string()),
+			feed_url: None,
+			pubdate: None,
+			last_checked: None,
+			last_error: None,
+			last_error_at: None,
+			inactive: false,
+			synced: true,
+			counts: None,
+			cover: Some("/podcasts/🎧🎧🎧/cover.jpg".to_string()),
+			duration: None,
+			downloaded: None,
+			explicit: None,
+			description: None,
+		});
+
+		let cols = det.panel.get_cols() as usize;
+		assert_eq!(det.panel.get_row(2), "Author: 作者の名前…");
+		assert!(det.panel.get_row(2).display_width() <= cols);
+		assert!(det.panel.get_row(3).display_width() <= cols);
+	}
+
+	#[test]
+	fn podcast_sync_error()
+	{
+		let mut det = create_details_panel(20, 43);
+		det.change_details(Details {
+			is_podcast: true,
+			pod_title: Some("A Podcast".to_string()),
+			ep_title: None,
+			author: None,
+			feed_url: None,
+			pubdate: None,
+			last_checked: Some(Utc::now() - Duration::days(2)),
+			last_error: Some("HTTP 404".to_string()),
+			last_error_at: Some(Utc::now() - Duration::hours(3)),
+			inactive: true,
+			synced: true,
+			counts: None,
+			cover: None,
+			duration: None,
+			downloaded: None,
+			explicit: None,
+			description: None,
+		});
+
+		assert_eq!(det.panel.get_row(2), "Last synced: 2 days ago");
+		assert_eq!(det.panel.get_row(3), "Status: Inactive");
+		assert_eq!(det.panel.get_row(4), "Last sync failed 3 hours ago: HTTP 404");
+	}
+
+	#[test]
+	fn episode_details()
+	{
+		let mut det = create_details_panel(20, 43);
+		det.change_details(Details {
+			is_podcast: false,
+			pod_title: Some("A Podcast".to_string()),
+			ep_title: Some("An Episode".to_string()),
+			author: None,
+			feed_url: None,
+			pubdate: None,
+			last_checked: None,
+			last_error: None,
+			last_error_at: None,
+			inactive: false,
+			synced: true,
+			counts: None,
+			cover: None,
+			duration: Some("1h 23m".to_string()),
+			downloaded: Some("2024-05-02, 61.0 MB".to_string()),
+			explicit: None,
+			description: None,
+		});
+
+		assert_eq!(det.panel.get_row(0), "A Podcast");
+		assert_eq!(det.panel.get_row(1), "An Episode");
+		assert_eq!(det.panel.get_row(3), "Duration: 1h 23m");
+		assert_eq!(det.panel.get_row(4), "Downloaded: 2024-05-02, 61.0 MB");
+		assert_eq!(det.panel.get_row(6), "No description.");
+	}
+
+	#[test]
+	fn scroll_long_description()
+	{
+		let mut det = create_details_panel(12, 43);
+		let lines: Vec<String> = (1..=20).map(|i| format!("Line {i}")).collect();
+		det.change_details(Details {
+			is_podcast: false,
+			pod_title: Some("A Podcast".to_string()),
+			ep_title: Some("An Episode".to_string()),
+			author: None,
+			feed_url: None,
+			pubdate: None,
+			last_checked: None,
+			last_error: None,
+			last_error_at: None,
+			inactive: false,
+			synced: true,
+			counts: None,
+			cover: None,
+			duration: None,
+			downloaded: None,
+			explicit: None,
+			description: Some(lines.join("\n")),
+		});
+		// titles, two blank lines, "Description:", and 20 lines
+		assert_eq!(det.panel.footer.as_deref(), Some("1-10/25"));
+
+		det.scroll(Scroll::Down(4));
+		assert_eq!(det.panel.get_row(0), "Description:");
+		assert_eq!(det.panel.footer.as_deref(), Some("5-14/25"));
+
+		// scrolling past either end stops there
+		det.scroll(Scroll::Down(u16::MAX));
+		assert_eq!(det.top_row, 15);
+		assert_eq!(det.panel.get_row(9), "Line 20");
+		assert_eq!(det.panel.footer.as_deref(), Some("16-25/25"));
+		det.scroll(Scroll::Up(100));
+		assert_eq!(det.top_row, 0);
+
+		// the panel grows taller than the details
+		det.scroll(Scroll::Down(u16::MAX));
+		det.resize(40, 43, 0);
+		assert_eq!(det.top_row, 0);
+		assert_eq!(det.panel.footer, None);
+	}
+
+	#[test]
+	fn time_since_units()
+	{
+		let now = Utc::now();
+		assert_eq!(time_since(now - Duration::seconds(30), now), "just now");
+		assert_eq!(time_since(now - Duration::minutes(1), now), "1 minute ago");
+		assert_eq!(time_since(now - Duration::hours(5), now), "5 hours ago");
+		assert_eq!(time_since(now - Duration::days(3), now), "3 days ago");
+	}
+}
@@ -0,0 +1,163 @@
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use crossterm::{cursor, queue, style};
+
+use super::AppColors;
+use crate::keymap::{Keybindings, UserAction};
+use crate::types::StringUtils;
+
+/// A one-line bar above the message window, showing the keys for the
+/// most useful actions in the current panel, e.g., "d:download  p:play".
+/// The keys are looked up in the keymap, so that changed keybindings
+/// show up here as well.
+#[derive(Debug)]
+pub struct HintBar
+{
+	colors: Rc<AppColors>,
+	start_y: u16,
+	total_cols: u16,
+	hints: Vec<String>,
+}
+
+impl HintBar
+{
+	/// Creates a new HintBar, without any hints yet.
+	pub fn new(colors: Rc<AppColors>, start_y: u16, total_cols: u16) -> Self
+	{
+		return Self {
+			colors: colors,
+			start_y: start_y,
+			total_cols: total_cols,
+			hints: Vec::new(),
+		};
+	}
+
+	/// Sets the hints to show, given as actions along with a short
+	/// description, in order of importance. Actions that are not bound
+	/// to any key are left out. The bar is only drawn again if the hints
+	/// have changed.
+	pub fn set_hints(&mut self, keymap: &Keybindings, actions: &[(UserAction, &str)])
+	{
+		let hints = hint_labels(keymap, actions);
+		if hints != self.hints
+		{
+			self.hints = hints;
+			self.redraw();
+			io::stdout().flush().unwrap();
+		}
+	}
+
+	/// Replaces the colors used for the bar, which only shows once it
+	/// is redrawn.
+	pub fn set_colors(&mut self, colors: Rc<AppColors>)
+	{
+		self.colors = colors;
+	}
+
+	/// Draws the hints that fit on the bar.
+	pub fn redraw(&self)
+	{
+		let width = self.total_cols as usize;
+		let line = fit_hints(&self.hints, width.saturating_sub(2));
+		let padding = width.saturating_sub(line.display_width() + 1);
+		queue!(
+			io::stdout(),
+			cursor::MoveTo(0, self.start_y),
+			style::PrintStyledContent(
+				self.colors
+					.style(self.colors.normal)
+					.apply(format!(" {line}{}", " ".repeat(padding)))
+			),
+		)
+		.unwrap();
+	}
+
+	/// Updates the location and width of the bar, and draws it again.
+	pub fn resize(&mut self, start_y: u16, total_cols: u16)
+	{
+		self.start_y = start_y;
+		self.total_cols = total_cols;
+		self.redraw();
+	}
+}
+
+/// Returns the hints for the given actions, as "key:description", with
+/// the shortest of the keys bound to each action.
+fn hint_labels(keymap: &Keybindings, actions: &[(UserAction, &str)]) -> Vec<String>
+{
+	return actions
+		.iter()
+		.filter_map(|(action, label)| {
+			return keymap
+				.key_for_action(*action)
+				.map(|key| format!("{key}:{label}"));
+		})
+		.collect();
+}
+
+/// Joins as many of the hints as fit in `width` columns, leaving out the
+/// least important ones (those at the end of the list) first. The last
+/// hint, which is for the help window, is kept as long as there is room
+/// for it, as it leads to all of the others.
+fn fit_hints(hints: &[String], width: usize) -> String
+{
+	let (help, others) = match hints.split_last()
+	{
+		Some(split) => split,
+		None => return String::new(),
+	};
+	let mut shown: Vec<&str> = Vec::new();
+	let mut used = help.display_width();
+	if used > width
+	{
+		return String::new();
+	}
+	for hint in others
+	{
+		let hint_width = hint.display_width() + 2;
+		if used + hint_width > width
+		{
+			break;
+		}
+		shown.push(hint);
+		used += hint_width;
+	}
+	shown.push(help);
+	return shown.join("  ");
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn hints_dropped_when_narrow()
+	{
+		let hints: Vec<String> = ["d:download", "p:play", "m:played", "?:help"]
+			.iter()
+			.map(|hint| hint.to_string())
+			.collect();
+		assert_eq!(fit_hints(&hints, 80), "d:download  p:play  m:played  ?:help");
+		assert_eq!(fit_hints(&hints, 26), "d:download  p:play  ?:help");
+		assert_eq!(fit_hints(&hints, 10), "?:help");
+		assert_eq!(fit_hints(&hints, 5), "");
+		assert_eq!(fit_hints(&[], 80), "");
+	}
+
+	#[test]
+	fn hints_from_keymap()
+	{
+		let actions = [(UserAction::Play, "play"), (UserAction::Download, "download")];
+		let keymap = Keybindings::default();
+		assert_eq!(hint_labels(&keymap, &actions), vec!["p:play", "d:download"]);
+
+		// remapped keys show up, and unbound actions are left out
+		let mut keymap = Keybindings::new();
+		keymap.insert("Enter".to_string(), UserAction::Play);
+		assert_eq!(hint_labels(&keymap, &actions), vec!["Enter:play"]);
+	}
+}
@@ -0,0 +1,216 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_width::UnicodeWidthChar;
+
+/// A single line of editable text, used for the prompts at the bottom of
+/// the screen. Keeps track of the position of the cursor in the text,
+/// as well as how far the text is scrolled horizontally when it does not
+/// fit in the space available for it.
+#[derive(Debug, Default)]
+pub struct LineEditor
+{
+	text: Vec<char>,
+	cursor: usize,
+	scroll: usize,
+}
+
+impl LineEditor
+{
+	/// Creates a new, empty LineEditor.
+	pub fn new() -> Self
+	{
+		return Self::default();
+	}
+
+	/// Returns the text that has been entered so far.
+	pub fn text(&self) -> String
+	{
+		return self.text.iter().collect();
+	}
+
+	/// Handles a key press: moving the cursor, deleting text or typing a
+	/// character at the cursor. Returns false if the key does nothing in
+	/// the editor (e.g., Enter or Esc, which are up to the caller).
+	pub fn handle_key(&mut self, input: KeyEvent) -> bool
+	{
+		let control = input.modifiers.intersects(KeyModifiers::CONTROL);
+		match input.code
+		{
+			KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+			KeyCode::Right => self.cursor = (self.cursor + 1).min(self.text.len()),
+			KeyCode::Home => self.cursor = 0,
+			KeyCode::End => self.cursor = self.text.len(),
+			KeyCode::Backspace | KeyCode::Char('\u{7f}') => {
+				if self.cursor > 0
+				{
+					self.cursor -= 1;
+					self.text.remove(self.cursor);
+				}
+			}
+			KeyCode::Delete => {
+				if self.cursor < self.text.len()
+				{
+					self.text.remove(self.cursor);
+				}
+			}
+			KeyCode::Char('u') if control => {
+				self.text.clear();
+				self.cursor = 0;
+			}
+			KeyCode::Char('w') if control => self.delete_word(),
+			KeyCode::Char(c) if !control => self.insert(c),
+			_ => return false,
+		}
+		return true;
+	}
+
+	/// Inserts text at the cursor, e.g., from a paste. Line breaks and
+	/// other control characters are dropped, as the text is a single
+	/// line.
+	pub fn insert_str(&mut self, text: &str)
+	{
+		for c in text.chars()
+		{
+			self.insert(c);
+		}
+	}
+
+	/// Inserts a character at the cursor, and moves the cursor past it.
+	fn insert(&mut self, c: char)
+	{
+		if c.is_control()
+		{
+			return;
+		}
+		self.text.insert(self.cursor, c);
+		self.cursor += 1;
+	}
+
+	/// Deletes the word before the cursor, along with any spaces between
+	/// it and the cursor.
+	fn delete_word(&mut self)
+	{
+		let mut start = self.cursor;
+		while start > 0 && self.text[start - 1].is_whitespace()
+		{
+			start -= 1;
+		}
+		while start > 0 && !self.text[start - 1].is_whitespace()
+		{
+			start -= 1;
+		}
+		self.text.drain(start..self.cursor);
+		self.cursor = start;
+	}
+
+	/// Returns the part of the text that fits in `width` columns, along
+	/// with the column of the cursor within it. The text is scrolled so
+	/// that the cursor is always visible, and scrolled back as far as
+	/// possible when there is room for it.
+	pub fn view(&mut self, width: usize) -> (String, usize)
+	{
+		let width = width.max(1);
+		let widths: Vec<usize> = self.text.iter().map(|c| c.width().unwrap_or(0)).collect();
+		// the cursor takes up a column of its own when it is at the end
+		let cursor_width = |cursor: usize| widths.get(cursor).copied().unwrap_or(1).max(1);
+
+		if self.cursor < self.scroll
+		{
+			self.scroll = self.cursor;
+		}
+		while self.scroll < self.cursor &&
+			widths[self.scroll..self.cursor].iter().sum::<usize>() + cursor_width(self.cursor) > width
+		{
+			self.scroll += 1;
+		}
+		while self.scroll > 0
+		{
+			let needed: usize = widths[self.scroll - 1..].iter().sum::<usize>()
+				+ if self.cursor == self.text.len() { 1 } else { 0 };
+			if needed > width
+			{
+				break;
+			}
+			self.scroll -= 1;
+		}
+
+		let mut visible = String::new();
+		let mut used = 0;
+		for (c, w) in self.text[self.scroll..].iter().zip(&widths[self.scroll..])
+		{
+			if used + w > width
+			{
+				break;
+			}
+			visible.push(*c);
+			used += w;
+		}
+		let column = widths[self.scroll..self.cursor].iter().sum();
+		return (visible, column);
+	}
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	fn key(code: KeyCode) -> KeyEvent
+	{
+		return KeyEvent::new(code, KeyModifiers::NONE);
+	}
+
+	fn ctrl(c: char) -> KeyEvent
+	{
+		return KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+	}
+
+	#[test]
+	fn editing_keys()
+	{
+		let mut editor = LineEditor::new();
+		editor.insert_str("htp://example.com/feed\n");
+		assert_eq!(editor.text(), "htp://example.com/feed");
+
+		editor.handle_key(key(KeyCode::Home));
+		editor.handle_key(key(KeyCode::Right));
+		editor.handle_key(key(KeyCode::Char('t')));
+		assert_eq!(editor.text(), "http://example.com/feed");
+
+		editor.handle_key(key(KeyCode::Delete));
+		editor.handle_key(key(KeyCode::Backspace));
+		assert_eq!(editor.text(), "hp://example.com/feed");
+
+		editor.handle_key(key(KeyCode::End));
+		editor.insert_str(" two words  ");
+		editor.handle_key(ctrl('w'));
+		assert_eq!(editor.text(), "hp://example.com/feed two ");
+		assert!(!editor.handle_key(ctrl('x')));
+		editor.handle_key(ctrl('u'));
+		assert_eq!(editor.text(), "");
+	}
+
+	#[test]
+	fn horizontal_scrolling()
+	{
+		let mut editor = LineEditor::new();
+		editor.insert_str("0123456789");
+		// the cursor at the end takes up a column
+		assert_eq!(editor.view(5), ("6789".to_string(), 4));
+
+		editor.handle_key(key(KeyCode::Home));
+		assert_eq!(editor.view(5), ("01234".to_string(), 0));
+		for _ in 0..6
+		{
+			editor.handle_key(key(KeyCode::Right));
+		}
+		assert_eq!(editor.view(5), ("23456".to_string(), 4));
+
+		// once the text is short enough, it scrolls back into place
+		editor.handle_key(key(KeyCode::End));
+		editor.handle_key(ctrl('w'));
+		editor.insert_str("abc");
+		assert_eq!(editor.view(5), ("abc".to_string(), 3));
+	}
+}
@@ -1,9 +1,11 @@
 use std::cmp::min;
 use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 
 use crossterm::style::{self, Stylize};
 
-use super::{Panel, Scroll};
+use super::{AppColors, Panel, Scroll};
+use crate::keymap::UserAction;
 use crate::types::*;
 
 /// Generic struct holding details about a list menu. These menus are
@@ -23,10 +25,27 @@ use crate::types::*;
 ///   i.e., it will be a value between 0 and items.len()
 /// * `selected` indicates which item on screen is currently highlighted.
 ///   It is calculated relative to the panel, i.e., a value between
-///   0 and (n_row - 1)
+///   0 and (n_row - 1). Where titles are wrapped, it counts items rather
+///   than rows, so the n-th item shown is at `start_row + n` even if
+///   items above it take up two rows
 /// * `active` indicates whether the menu is currently interactive, e.g.,
 ///   if the user scrolls up or down, this is the menu that will receive
 ///   those events.
+/// * `search` is the text that the items of the menu are searched for,
+///   if any, which is shown at the bottom of the panel. The search
+///   itself is applied along with the other filters.
+/// * `columns` are the extra columns shown after the title of each item,
+///   for menus of episodes.
+/// * `marked` holds the IDs of the items the user has marked, so that an
+///   action can apply to all of them at once. Marked items are shown
+///   with a ">" in front of them.
+/// * `wrap_titles` indicates whether long titles are wrapped onto a
+///   second row, rather than cut short.
+/// * `empty_message` is shown in the middle of the panel when there are
+///   no items to show, e.g., to explain how to add some.
+/// * `drawn_order` holds the IDs of the items shown when the menu was
+///   last drawn, so that the same item can be selected again after the
+///   list changes.
 #[derive(Debug)]
 pub struct Menu<T>
 where T: Clone + Menuable
@@ -38,6 +57,12 @@ where T: Clone + Menuable
 	pub top_row: u16,   // top row of text shown in window
 	pub selected: u16,  // which line of text is highlighted
 	pub active: bool,
+	pub search: Option<String>,
+	pub columns: Vec<EpisodeColumn>,
+	pub marked: HashSet<i64>,
+	pub wrap_titles: bool,
+	pub empty_message: Option<String>,
+	drawn_order: Vec<i64>,
 }
 
 impl<T: Clone + Menuable> Menu<T>
@@ -53,9 +78,33 @@ impl<T: Clone + Menuable> Menu<T>
 			top_row: 0,
 			selected: 0,
 			active: false,
+			search: None,
+			columns: Vec::new(),
+			marked: HashSet::new(),
+			wrap_titles: false,
+			empty_message: None,
+			drawn_order: Vec::new(),
 		};
 	}
 
+	/// Sets the text that the menu is searched for, and shows it at the
+	/// bottom of the panel. If `editing` is set, it is shown as a prompt
+	/// that the user is still typing. The menu must be redrawn for the
+	/// change to show.
+	pub fn set_search(&mut self, search: Option<String>, editing: bool)
+	{
+		let footer = if editing
+		{
+			Some(format!("/{}_", search.as_deref().unwrap_or("")))
+		}
+		else
+		{
+			search.as_ref().map(|text| format!("Search: {text}"))
+		};
+		self.panel.set_footer(footer);
+		self.search = search;
+	}
+
 	/// Clears the terminal, and then prints the list of visible items
 	/// to the terminal.
 	pub fn redraw(&mut self)
@@ -73,65 +122,64 @@ impl<T: Clone + Menuable> Menu<T>
 			self.selected = self.start_row;
 		}
 
-		let (map, _unused, order) = self.items.borrow();
-		drop(_unused);
-		if !order.is_empty()
+		let list_len = self.items.len(true);
+		if list_len > 0
 		{
 			// update selected item if list has gotten shorter
 			let current_selected = self.get_menu_idx(self.selected);
-			let list_len = order.len();
 			if current_selected >= list_len
 			{
 				self.selected = (
 					self.selected as usize - (current_selected - list_len) - 1
 				) as u16;
 			}
+			if self.wrap_titles
+			{
+				// wrapped items above it may have pushed the selected
+				// item off the bottom of the panel
+				self.show_index(self.get_menu_idx(self.selected) as u16);
+			}
+		}
 
-			// for visible rows, print strings from list
-			for i in self.start_row..self.panel.get_rows()
+		let (map, _unused, order) = self.items.borrow();
+		drop(_unused);
+		self.drawn_order.clone_from(&order);
+		if !order.is_empty()
+		{
+			// for visible rows, print strings from list; an item that
+			// only partly fits at the bottom is cut short
+			let n_row = self.panel.get_rows();
+			let mut y = self.start_row;
+			for elem_id in order.iter().skip(self.top_row as usize)
 			{
-				if let Some(elem_id) = order.get(self.get_menu_idx(i))
+				if y >= n_row
 				{
-					let elem = map.get(elem_id).expect(
-						"Could not retrieve menu item."
-					);
+					break;
+				}
+				let elem = map.get(elem_id).expect(
+					"Could not retrieve menu item."
+				);
 
-					if i == self.selected || !elem.is_played()
-					{
-						let style = if !elem.is_played()
-						{
-							style::ContentStyle::new()
-								.with(self.panel.colors.bold.0)
-								.on(self.panel.colors.bold.1)
-								.attribute(style::Attribute::Bold)
-						}
-						else
-						{
-							style::ContentStyle::new()
-								.with(self.panel.colors.normal.0)
-								.on(self.panel.colors.normal.1)
-						};
-						self.panel.write_line(
-							i,
-							elem.get_title(self.panel.get_cols() as usize),
-							Some(style),
-						);
-					}
-					else
+				let style = item_style(&self.panel.colors, elem.state(), None);
+				for line in self.item_lines(elem)
+				{
+					if y >= n_row
 					{
-						self.panel.write_line(
-							i,
-							elem.get_title(self.panel.get_cols() as usize),
-							None,
-						);
+						break;
 					}
-				}
-				else
-				{
-					break;
+					self.panel.write_line(y, line, Some(style));
+					y += 1;
 				}
 			}
 		}
+		else if let Some(message) = &self.empty_message
+		{
+			// centered vertically in the rows below the header
+			let rows = self.panel.get_rows().saturating_sub(self.start_row);
+			let lines = textwrap::wrap(message, self.panel.get_cols() as usize).len() as u16;
+			let row = self.start_row + rows.saturating_sub(lines) / 2;
+			self.panel.write_wrap_line(row, message, None);
+		}
 	}
 
 	/// If a header exists, prints lines of text to the panel to appear
@@ -156,72 +204,46 @@ impl<T: Clone + Menuable> Menu<T>
 	pub fn scroll(&mut self, lines: Scroll)
 	{
 		let list_len = self.items.len(true) as u16;
-		if list_len == 0
+		if list_len == 0 || self.page_rows() == 0
 		{
 			return;
 		}
 
+		let current = self.get_menu_idx(self.selected) as u16;
 		match lines
 		{
 			Scroll::Up(v) => {
-				let selected_adj = self.selected - self.start_row;
-				if v <= selected_adj
+				let target = current.saturating_sub(v);
+				if target >= self.top_row
 				{
 					self.unhighlight_item(self.selected);
-					self.selected -= v;
+					self.selected = self.start_row + target - self.top_row;
 				}
 				else
 				{
-					let list_scroll_amount = v - selected_adj;
-					if let Some(top) = self.top_row.checked_sub(list_scroll_amount)
-					{
-						self.top_row = top;
-					}
-					else
-					{
-						self.top_row = 0;
-					}
-					self.selected = self.start_row;
+					self.show_index(target);
 					self.panel.clear_inner();
 					self.update_items();
 				}
 				self.highlight_item(self.selected, self.active);
 			}
 			Scroll::Down(v) => {
-				if self.get_menu_idx(self.selected) >= list_len as usize - 1
+				if current >= list_len - 1
 				{
 					// we're at the bottom of the list
 					return;
 				}
 
-				let n_row = self.panel.get_rows();
-				let select_max = if list_len < n_row - self.start_row
-				{
-					self.start_row + list_len - 1
-				}
-				else
-				{
-					n_row - 1
-				};
-
-				if v <= (select_max - self.selected)
+				// can't scroll list if the rest of it is already on screen
+				let target = min(current.saturating_add(v), list_len - 1);
+				if target < self.top_row + self.visible_items()
 				{
 					self.unhighlight_item(self.selected);
-					self.selected += v;
+					self.selected = self.start_row + target - self.top_row;
 				}
 				else
 				{
-					let list_scroll_amount = v - (n_row - self.selected - 1);
-					let visible_rows = n_row - self.start_row;
-					// can't scroll list if list is shorter than full screen
-					if list_len > visible_rows
-					{
-						self.top_row = min(
-							self.top_row + list_scroll_amount,
-							list_len - visible_rows
-						);
-					}
-					self.selected = select_max;
+					self.show_index(target);
 					self.panel.clear_inner();
 					self.update_items();
 				}
@@ -230,72 +252,237 @@ impl<T: Clone + Menuable> Menu<T>
 		}
 	}
 
-	/// Highlights the item in the menu, given a y-value.
-	pub fn highlight_item(&mut self, item_y: u16, active: bool)
+	/// Scrolls the menu as asked for by `action`, if it is one of the
+	/// actions that move up or down the list. Other actions are ignored.
+	pub fn scroll_for_action(&mut self, action: UserAction)
 	{
-		// if list is empty, will return None
-		let el_details = self
+		if let Some(scroll) = Scroll::from_action(action, self.page_rows())
+		{
+			self.scroll(scroll);
+		}
+	}
+
+	/// Returns the lines of text shown for an item: one, or two if its
+	/// title is wrapped. The first line has a marker in front of it if
+	/// the item is marked.
+	fn item_lines(&self, item: &T) -> Vec<String>
+	{
+		let max_lines = if self.wrap_titles { 2 } else { 1 };
+		let mut lines = item.get_rows(self.panel.get_cols() as usize, &self.columns, max_lines);
+		if self.marked.contains(&item.get_id()) && lines[0].starts_with(' ')
+		{
+			lines[0] = format!(">{}", &lines[0][1..]);
+		}
+		return lines;
+	}
+
+	/// Returns the number of rows taken up by the item at `index` in the
+	/// list.
+	fn item_height(&self, index: usize) -> u16
+	{
+		if !self.wrap_titles
+		{
+			return 1;
+		}
+		return self
 			.items
-			.map_single_by_index(self.get_menu_idx(item_y), |el| {
-				(el.get_title(self.panel.get_cols() as usize), el.is_played())
-			});
+			.map_single_by_index(index, |el| self.item_lines(el).len() as u16)
+			.unwrap_or(1);
+	}
+
+	/// Returns the row of the panel where the item at `item_y` (counted
+	/// in items, as with `selected`) starts.
+	fn item_start_row(&self, item_y: u16) -> u16
+	{
+		let first = self.top_row as usize;
+		return self.start_row
+			+ (first..self.get_menu_idx(item_y))
+				.map(|index| self.item_height(index))
+				.sum::<u16>();
+	}
 
-		if let Some((title, is_played)) = el_details
+	/// Returns the number of items, starting from `top_row`, that fit on
+	/// the panel in full.
+	pub fn visible_items(&self) -> u16
+	{
+		let list_len = self.items.len(true);
+		let mut rows = self.page_rows();
+		let mut count = 0;
+		for index in self.top_row as usize..list_len
 		{
-			let mut style = style::ContentStyle::new();
-			if active
+			let height = self.item_height(index);
+			if height > rows
 			{
-				style = style
-					.with(self.panel.colors.highlighted_active.0)
-					.on(self.panel.colors.highlighted_active.1);
+				break;
 			}
-			else
+			rows -= height;
+			count += 1;
+		}
+		return count;
+	}
+
+	/// Given a row of the panel, returns the item shown on it, as a value
+	/// of `selected`, or None if there is no item on that row.
+	pub fn item_at_row(&self, row: u16) -> Option<u16>
+	{
+		if row < self.start_row || row >= self.panel.get_rows()
+		{
+			return None;
+		}
+		let list_len = self.items.len(true);
+		let mut y = self.start_row;
+		for index in self.top_row as usize..list_len
+		{
+			y += self.item_height(index);
+			if row < y
 			{
-				style = style
-					.with(self.panel.colors.highlighted.0)
-					.on(self.panel.colors.highlighted.1);
+				return Some(self.start_row + index as u16 - self.top_row);
 			}
-			style = if is_played
+		}
+		return None;
+	}
+
+	/// Returns the highest `top_row` that still fills the panel, i.e.,
+	/// with the last of the `list_len` items at the bottom.
+	fn last_top_row(&self, list_len: usize) -> u16
+	{
+		let mut rows = self.page_rows();
+		let mut first = list_len;
+		while first > 0
+		{
+			let height = self.item_height(first - 1);
+			if height > rows
 			{
-				style.attribute(style::Attribute::NormalIntensity)
+				break;
 			}
-			else
+			rows -= height;
+			first -= 1;
+		}
+		return first.min(list_len.saturating_sub(1)) as u16;
+	}
+
+	/// Selects the item at `index` in the list, scrolling the list just
+	/// far enough for all of its rows to be shown, if they are not
+	/// already. The menu must be redrawn for the change to show.
+	fn show_index(&mut self, index: u16)
+	{
+		if index < self.top_row
+		{
+			self.top_row = index;
+		}
+		else
+		{
+			let rows = self.page_rows();
+			let mut first = index;
+			let mut used = self.item_height(index as usize);
+			while first > self.top_row
 			{
-				style.attribute(style::Attribute::Bold)
-			};
-			self.panel.write_line(item_y, title, Some(style));
+				let height = self.item_height(first as usize - 1);
+				if used + height > rows
+				{
+					break;
+				}
+				first -= 1;
+				used += height;
+			}
+			self.top_row = first;
 		}
+		self.selected = self.start_row + index - self.top_row;
 	}
 
-	/// Removes highlight on the item in the menu, given a y-value.
-	pub fn unhighlight_item(&mut self, item_y: u16)
+	/// Toggles the mark on an item. Returns true if it is now marked.
+	pub fn toggle_mark(&mut self, id: i64) -> bool
+	{
+		if self.marked.remove(&id)
+		{
+			return false;
+		}
+		self.marked.insert(id);
+		return true;
+	}
+
+	/// Returns the marked items that are currently shown, in the order
+	/// they are listed.
+	pub fn marked_items(&self) -> Vec<i64>
+	{
+		return self
+			.items
+			.borrow_filtered_order()
+			.iter()
+			.filter(|id| self.marked.contains(id))
+			.copied()
+			.collect();
+	}
+
+	/// Returns the number of rows available for items, below the header.
+	pub fn page_rows(&self) -> u16
+	{
+		return self.panel.get_rows().saturating_sub(self.start_row);
+	}
+
+	/// Highlights the item in the menu, given a y-value.
+	pub fn highlight_item(&mut self, item_y: u16, active: bool)
+	{
+		self.write_item(item_y, Some(active));
+		self.update_position();
+	}
+
+	/// Writes all of the rows of the item at the given y-value to the
+	/// panel, with the style for `highlighted` as in `item_style()`.
+	fn write_item(&mut self, item_y: u16, highlighted: Option<bool>)
 	{
 		// if list is empty, will return None
 		let el_details = self
 			.items
 			.map_single_by_index(self.get_menu_idx(item_y), |el| {
-				(el.get_title(self.panel.get_cols() as usize), el.is_played())
+				(self.item_lines(el), el.state())
 			});
 
-		if let Some((title, is_played)) = el_details
+		if let Some((lines, state)) = el_details
 		{
-			let style = if is_played
+			let style = item_style(&self.panel.colors, state, highlighted);
+			let n_row = self.panel.get_rows();
+			let start = self.item_start_row(item_y);
+			for (y, line) in (start..n_row).zip(lines)
 			{
-				style::ContentStyle::new()
-					.with(self.panel.colors.normal.0)
-					.on(self.panel.colors.normal.1)
+				self.panel.write_line(y, line, Some(style));
 			}
-			else
-			{
-				style::ContentStyle::new()
-					.with(self.panel.colors.bold.0)
-					.on(self.panel.colors.bold.1)
-					.attribute(style::Attribute::Bold)
-			};
-			self.panel.write_line(item_y, title, Some(style));
 		}
 	}
 
+	/// Shows the position of the selected item in the list in the bottom
+	/// right corner of the panel, e.g., "123/600", if the list does not
+	/// fit in the panel.
+	fn update_position(&mut self)
+	{
+		let total = self.items.len(true);
+		// with wrapped titles, items can take up more than one row each
+		let fits = if self.wrap_titles
+		{
+			self.top_row == 0 && self.visible_items() as usize >= total
+		}
+		else
+		{
+			total <= self.page_rows() as usize
+		};
+		let position = if !fits
+		{
+			Some(format!("{}/{}", self.get_menu_idx(self.selected) + 1, total))
+		}
+		else
+		{
+			None
+		};
+		self.panel.set_position(position);
+		self.panel.draw_bottom();
+	}
+
+	/// Removes highlight on the item in the menu, given a y-value.
+	pub fn unhighlight_item(&mut self, item_y: u16)
+	{
+		self.write_item(item_y, None);
+	}
+
 	/// Highlights the currently selected item in the menu, based on
 	/// whether the menu is currently active or not.
 	pub fn highlight_selected(&mut self)
@@ -315,16 +502,85 @@ impl<T: Clone + Menuable> Menu<T>
 	pub fn resize(&mut self, n_row: u16, n_col: u16, start_x: u16)
 	{
 		self.panel.resize(n_row, n_col, start_x);
-		let n_row = self.panel.get_rows();
 
 		// if resizing moves selected item off screen, scroll the list
 		// upwards to keep same item selected
-		if self.selected > (n_row - 1)
+		self.show_index(self.get_menu_idx(self.selected) as u16);
+		self.redraw();
+	}
+
+	/// Selects the item with the given ID, if it is in the list. The
+	/// menu stays where it is if the item is already on screen, and
+	/// scrolls just far enough to show the item otherwise. The menu
+	/// must be redrawn for the change to show.
+	pub fn select_id(&mut self, id: i64)
+	{
+		let index = match self.items.borrow_filtered_order().iter().position(|x| *x == id)
+		{
+			Some(index) => index as u16,
+			None => return,
+		};
+		self.show_index(index);
+	}
+
+	/// After the list has changed, e.g., after a sync, selects the item
+	/// that was selected when the menu was last drawn, keeping it on the
+	/// same row of the panel where possible. If that item is no longer
+	/// shown, the nearest item after it that is still shown is selected
+	/// instead, or else the nearest item before it. Returns whether a
+	/// different item is selected now. The menu must be redrawn for the
+	/// change to show.
+	pub fn restore_selection(&mut self) -> bool
+	{
+		let old_index = self.get_menu_idx(self.selected);
+		if old_index >= self.drawn_order.len()
 		{
-			self.top_row = self.top_row + self.selected - (n_row - 1);
-			self.selected = n_row - 1;
+			return false;
 		}
-		self.redraw();
+		let order = self.items.borrow_filtered_order();
+		let new_indices: HashMap<i64, usize> = order
+			.iter()
+			.enumerate()
+			.map(|(index, id)| (*id, index))
+			.collect();
+		let found = self.drawn_order[old_index..]
+			.iter()
+			.chain(self.drawn_order[..old_index].iter().rev())
+			.find_map(|id| new_indices.get(id).map(|index| (*id, *index as u16)));
+		let list_len = order.len();
+		drop(order);
+		let (new_id, new_index) = match found
+		{
+			Some(found) => found,
+			None => return true,
+		};
+
+		let row = self.selected - self.start_row;
+		self.top_row = new_index.saturating_sub(row).min(self.last_top_row(list_len));
+		self.show_index(new_index);
+		return new_id != self.drawn_order[old_index];
+	}
+
+	/// Returns the index of the first item whose title starts with
+	/// `prefix`, looking from the selected item onwards and wrapping
+	/// around at the end of the list. If `skip_selected` is set, the
+	/// selected item is checked last, so that typing the same letter
+	/// again moves on to the next match. Titles are compared ignoring
+	/// case and a leading "The ".
+	pub fn find_prefix<F>(&self, prefix: &str, skip_selected: bool, title: F) -> Option<usize>
+	where F: FnMut(&T) -> String
+	{
+		let titles = self.items.map(title, true);
+		if titles.is_empty()
+		{
+			return None;
+		}
+		let prefix = prefix.to_lowercase();
+		let current = self.get_menu_idx(self.selected).min(titles.len() - 1);
+		let start = if skip_selected { current + 1 } else { current };
+		return (start..start + titles.len())
+			.map(|i| i % titles.len())
+			.find(|i| jump_key(&titles[*i]).starts_with(&prefix));
 	}
 
 	/// Given a row on the panel, this translates it into the
@@ -388,6 +644,17 @@ impl Menu<Episode>
 	}
 }
 
+impl Menu<QueueItem>
+{
+	/// Controls how the window changes when it is inactive (i.e., not
+	/// available for user input to modify state).
+	pub fn deactivate(&mut self)
+	{
+		self.active = false;
+		self.unhighlight_item(self.selected);
+	}
+}
+
 impl Menu<NewEpisode>
 {
 	/// Changes the status of the currently highlighted episode -- if it
@@ -461,13 +728,74 @@ impl Menu<NewEpisode>
 	}
 }
 
+/// Returns the style of a menu item in the given state. `highlighted`
+/// is `Some(true)` for the selected item of the active menu, and
+/// `Some(false)` for the selected item of an inactive menu; highlighted
+/// items always use the highlight colors, and only keep the attributes
+/// of their state. In monochrome mode, only bold is kept, and the
+/// highlight is shown in reverse video (underlined in an inactive menu).
+pub fn item_style(
+	colors: &AppColors,
+	state: ItemState,
+	highlighted: Option<bool>,
+) -> style::ContentStyle
+{
+	let (state_colors, attribute) = match state
+	{
+		ItemState::Inactive => (colors.normal, style::Attribute::Dim),
+		ItemState::NotSynced => (colors.normal, style::Attribute::Italic),
+		ItemState::Downloading => (colors.downloaded, style::Attribute::Italic),
+		ItemState::Downloaded => (colors.downloaded, style::Attribute::NormalIntensity),
+		ItemState::Played => (colors.played, style::Attribute::NormalIntensity),
+		ItemState::New => (colors.new, style::Attribute::Bold),
+		ItemState::Unplayed => (colors.bold, style::Attribute::Bold),
+		ItemState::Normal => (colors.normal, style::Attribute::NormalIntensity),
+		ItemState::Error => (colors.error, style::Attribute::NormalIntensity),
+	};
+	if colors.monochrome
+	{
+		let mut content_style = style::ContentStyle::new();
+		if attribute == style::Attribute::Bold
+		{
+			content_style = content_style.attribute(attribute);
+		}
+		return match highlighted
+		{
+			Some(true) => content_style.attribute(style::Attribute::Reverse),
+			Some(false) => content_style.attribute(style::Attribute::Underlined),
+			None => content_style,
+		};
+	}
+	let (fg, bg) = match highlighted
+	{
+		Some(true) => colors.highlighted_active,
+		Some(false) => colors.highlighted,
+		None => state_colors,
+	};
+	return style::ContentStyle::new().with(fg).on(bg).attribute(attribute);
+}
+
+/// Returns the part of a title that is matched when jumping to an item
+/// by typing its first letters: the title in lowercase, without a
+/// leading "The ".
+fn jump_key(title: &str) -> String
+{
+	let title = title.trim_start().to_lowercase();
+	return match title.strip_prefix("the ")
+	{
+		Some(rest) => rest.to_string(),
+		None => title,
+	};
+}
+
 
 // TESTS ----------------------------------------------------------------
 #[cfg(test)]
 mod tests
 {
 	use super::*;
-	use chrono::Utc;
+	use crate::types::relative_date;
+	use chrono::{FixedOffset, TimeZone, Utc};
 	use std::rc::Rc;
 
 	fn create_menu(
@@ -478,7 +806,7 @@ mod tests
 	) -> Menu<Episode>
 	{
 		let colors = Rc::new(crate::ui::AppColors::default());
-		let titles = vec![
+		let titles = [
 			"A Very Cool Episode",
 			"This is a very long episode title but we'll get through it together",
 			"An episode with le Unicodé",
@@ -497,11 +825,22 @@ mod tests
 				title: t.to_string(),
 				url: String::new(),
 				guid: String::new(),
+				link: None,
 				description: String::new(),
 				pubdate: Some(Utc::now()),
 				duration: Some(12345),
+				season: None,
+				episode_num: None,
+				enclosure_size: None,
 				path: None,
+				downloaded_at: None,
+				file_size: None,
 				played: played,
+				position: None,
+				archived: false,
+				added_at: None,
+				unseen: false,
+				downloading: false,
 			});
 		}
 
@@ -522,6 +861,12 @@ mod tests
 			top_row: top_row,
 			selected: selected,
 			active: true,
+			search: None,
+			columns: Vec::new(),
+			marked: HashSet::new(),
+			wrap_titles: false,
+			empty_message: None,
+			drawn_order: Vec::new(),
 		};
 	}
 
@@ -571,6 +916,505 @@ mod tests
 		assert_eq!(menu.panel.get_row(4), expected_bot);
 	}
 
+	#[test]
+	fn scroll_to_ends()
+	{
+		let real_rows = 5;
+		let real_cols = 65;
+		let mut menu = create_menu(real_rows + 2, real_cols + 3, 0, 0);
+		menu.update_items();
+
+		menu.scroll(Scroll::Down(u16::MAX));
+		assert_eq!(menu.top_row, 2);
+		assert_eq!(menu.get_menu_idx(menu.selected), 6);
+		let expected_bot = menu
+			.items
+			.map_single_by_index(6, |ep| ep.get_title(real_cols as usize))
+			.unwrap();
+		assert_eq!(menu.panel.get_row(4), expected_bot);
+
+		menu.scroll(Scroll::Up(u16::MAX));
+		assert_eq!(menu.top_row, 0);
+		assert_eq!(menu.selected, 0);
+	}
+
+	#[test]
+	fn position_indicator()
+	{
+		let real_rows = 5;
+		let real_cols = 65;
+		let mut menu = create_menu(real_rows + 2, real_cols + 3, 0, 0);
+		menu.update_items();
+		menu.highlight_selected();
+		assert_eq!(menu.panel.position.as_deref(), Some("1/7"));
+
+		menu.scroll(Scroll::Down(u16::MAX));
+		assert_eq!(menu.panel.position.as_deref(), Some("7/7"));
+
+		// no indicator once the whole list fits in the panel
+		menu.panel.resize(12, real_cols + 3, 0);
+		menu.update_items();
+		menu.highlight_selected();
+		assert_eq!(menu.panel.position, None);
+	}
+
+	#[test]
+	fn jump_to_prefix()
+	{
+		let mut menu = create_menu(7, 68, 0, 0);
+		menu.update_items();
+		let title = |ep: &Episode| ep.title.clone();
+		assert_eq!(menu.find_prefix("H", true, title), Some(3));
+		assert_eq!(menu.find_prefix("he", false, title), Some(4));
+		assert_eq!(menu.find_prefix("x", true, title), None);
+
+		// typing the same letter again moves on, then wraps around
+		menu.scroll(Scroll::Down(4));
+		assert_eq!(menu.find_prefix("h", true, title), Some(3));
+		assert_eq!(menu.find_prefix("h", false, title), Some(4));
+
+		assert_eq!(jump_key("The Daily"), "daily");
+		assert_eq!(jump_key("Theory of Everything"), "theory of everything");
+	}
+
+	#[test]
+	fn resize_tiny()
+	{
+		let real_rows = 5;
+		let real_cols = 65;
+		let mut menu = create_menu(real_rows + 2, real_cols + 3, 0, 4);
+		menu.update_items();
+
+		// sizes with no room for the borders must not underflow
+		for (n_row, n_col) in [(0, 0), (1, 1), (2, 3), (3, 4)]
+		{
+			menu.resize(n_row, n_col, 0);
+			menu.scroll(Scroll::Down(1));
+			menu.scroll(Scroll::Up(1));
+		}
+		menu.resize(real_rows + 2, real_cols + 3, 0);
+		assert_eq!(menu.get_menu_idx(menu.selected), 4);
+	}
+
+	#[test]
+	fn page_down_short_list()
+	{
+		// the list is shorter than the panel, so paging down selects the
+		// last item without scrolling
+		let real_rows = 10;
+		let real_cols = 65;
+		let mut menu = create_menu(real_rows + 2, real_cols + 3, 0, 1);
+		menu.update_items();
+
+		menu.scroll(Scroll::Down(menu.page_rows()));
+		assert_eq!(menu.top_row, 0);
+		assert_eq!(menu.selected, 6);
+
+		// scrolling past the last item, but by less than the panel height
+		menu.scroll(Scroll::Up(u16::MAX));
+		menu.scroll(Scroll::Down(8));
+		assert_eq!(menu.top_row, 0);
+		assert_eq!(menu.selected, 6);
+	}
+
+	/// Wraps the episodes of a menu in a podcast, so that filters can
+	/// be applied to them.
+	fn podcast_list(episodes: &LockVec<Episode>) -> LockVec<Podcast>
+	{
+		return LockVec::new(vec![Podcast {
+			id: 1,
+			title: "A Podcast".to_string(),
+			feed_title: "A Podcast".to_string(),
+			sort_title: "podcast".to_string(),
+			url: String::new(),
+			link: None,
+			description: None,
+			author: None,
+			explicit: None,
+			image_url: None,
+			cover_path: None,
+			last_checked: Utc::now(),
+			inactive: false,
+			favorite: false,
+			synced: true,
+			last_error: None,
+			last_error_at: None,
+			tags: Vec::new(),
+			episode_sort: EpisodeSort::Newest,
+			episodes: episodes.clone(),
+		}]);
+	}
+
+	#[test]
+	fn search_narrows()
+	{
+		let real_rows = 5;
+		let real_cols = 65;
+		let mut menu = create_menu(real_rows + 2, real_cols + 3, 0, 4);
+		menu.update_items();
+
+		let podcasts = podcast_list(&menu.items);
+		let filters = Filters {
+			episode_search: Some("EPISODE".to_string()),
+			..Filters::default()
+		};
+		filters.apply(&podcasts);
+		assert_eq!(podcasts.len(true), 1);
+
+		menu.set_search(Some("EPISODE".to_string()), true);
+		menu.panel.clear_inner();
+		menu.update_items();
+
+		// the selection moves up as the list gets shorter
+		assert_eq!(menu.items.len(true), 4);
+		assert_eq!(menu.selected, 3);
+		assert_eq!(menu.panel.footer.as_deref(), Some("/EPISODE_"));
+		for (row, idx) in [0, 1, 2, 3].iter().enumerate()
+		{
+			let expected = menu
+				.items
+				.map_single(*idx, |ep| ep.get_title(real_cols as usize))
+				.unwrap();
+			assert_eq!(menu.panel.get_row(row), expected);
+		}
+		assert_eq!(menu.panel.get_row(4), "");
+
+		// scrolling stays within the narrowed list
+		menu.scroll(Scroll::Down(1));
+		assert_eq!(menu.selected, 3);
+		menu.scroll(Scroll::Up(u16::MAX));
+		assert_eq!(menu.selected, 0);
+	}
+
+	#[test]
+	fn marked_items()
+	{
+		let real_rows = 5;
+		let real_cols = 65;
+		let mut menu = create_menu(real_rows + 2, real_cols + 3, 0, 0);
+		menu.update_items();
+
+		assert!(menu.toggle_mark(3));
+		assert!(menu.toggle_mark(1));
+		assert!(menu.toggle_mark(5));
+		assert!(!menu.toggle_mark(5));
+		assert_eq!(menu.marked_items(), vec![1, 3]);
+
+		menu.panel.clear_inner();
+		menu.update_items();
+		let unmarked = menu
+			.items
+			.map_single(0, |ep| ep.get_title(real_cols as usize))
+			.unwrap();
+		assert_eq!(menu.panel.get_row(0), unmarked);
+		let marked = menu.panel.get_row(1);
+		assert!(marked.starts_with('>'));
+		let title = menu
+			.items
+			.map_single(1, |ep| ep.get_title(real_cols as usize))
+			.unwrap();
+		assert_eq!(marked[1..], title[1..]);
+
+		// marks on episodes that are not shown are left out
+		let podcasts = podcast_list(&menu.items);
+		let filters = Filters {
+			episode_search: Some("title".to_string()),
+			..Filters::default()
+		};
+		filters.apply(&podcasts);
+		assert_eq!(menu.marked_items(), vec![1]);
+	}
+
+	#[test]
+	fn search_cleared()
+	{
+		let real_rows = 3;
+		let real_cols = 65;
+		let mut menu = create_menu(real_rows + 2, real_cols + 3, 0, 0);
+		let podcasts = podcast_list(&menu.items);
+		let filters = Filters {
+			episode_search: Some("title".to_string()),
+			..Filters::default()
+		};
+		filters.apply(&podcasts);
+		menu.scroll(Scroll::Down(1));
+		assert_eq!(menu.items.map_single_by_index(1, |ep| ep.id), Some(4));
+
+		// the same episode is selected in the full list again
+		Filters::default().apply(&podcasts);
+		menu.select_id(4);
+		assert_eq!(menu.top_row, 2);
+		assert_eq!(menu.selected, 2);
+
+		menu.select_id(6);
+		assert_eq!(menu.top_row, 4);
+		assert_eq!(menu.selected, 2);
+	}
+
+	#[test]
+	fn empty_message()
+	{
+		let mut menu = create_menu(7, 38, 0, 0);
+		let episodes = menu.items.clone();
+		menu.items = LockVec::new(Vec::new());
+		menu.empty_message = Some("No episodes — press 's' to sync the feed.".to_string());
+		menu.update_items();
+		assert_eq!(menu.panel.get_row(1), "No episodes — press 's' to sync");
+		assert_eq!(menu.panel.get_row(2), "the feed.");
+
+		// the message is wrapped again to fit a wider panel
+		menu.resize(7, 68, 0);
+		menu.update_items();
+		assert_eq!(menu.panel.get_row(2), "No episodes — press 's' to sync the feed.");
+
+		menu.items = episodes;
+		menu.panel.clear_inner();
+		menu.update_items();
+		let title = menu.items.map_single(2, |ep| ep.get_title(65)).unwrap();
+		assert_eq!(menu.panel.get_row(2), title);
+	}
+
+	#[test]
+	fn selection_kept_after_sync()
+	{
+		let mut menu = create_menu(7, 68, 2, 2);
+		menu.update_items();
+		let selected_id = |menu: &Menu<Episode>| {
+			menu.items.map_single_by_index(menu.get_menu_idx(menu.selected), |ep| ep.id)
+		};
+		assert_eq!(selected_id(&menu), Some(4));
+
+		// a new episode at the top of the list moves the others down, but
+		// the same episode stays selected, on the same row
+		let mut episodes = menu.items.map(|ep| ep.clone(), false);
+		let mut new_episode = episodes[0].clone();
+		new_episode.id = 100;
+		episodes.insert(0, new_episode);
+		menu.items = LockVec::new(episodes.clone());
+		assert!(!menu.restore_selection());
+		assert_eq!(selected_id(&menu), Some(4));
+		assert_eq!((menu.top_row, menu.selected), (3, 2));
+
+		// if the selected episode is gone, the next one is selected
+		menu.update_items();
+		episodes.retain(|ep| ep.id != 4 && ep.id != 100);
+		menu.items = LockVec::new(episodes.clone());
+		assert!(menu.restore_selection());
+		assert_eq!(selected_id(&menu), Some(5));
+		assert_eq!((menu.top_row, menu.selected), (1, 3));
+
+		// or the one before it, at the end of the list
+		menu.update_items();
+		menu.scroll(Scroll::Down(u16::MAX));
+		episodes.retain(|ep| ep.id != 6);
+		menu.items = LockVec::new(episodes);
+		assert!(menu.restore_selection());
+		assert_eq!(selected_id(&menu), Some(5));
+	}
+
+	#[test]
+	fn played_hidden()
+	{
+		let selected_id = |menu: &Menu<Episode>| {
+			menu.items.map_single_by_index(menu.get_menu_idx(menu.selected), |ep| ep.id)
+		};
+		let mut menu = create_menu(7, 68, 0, 0);
+		let podcasts = podcast_list(&menu.items);
+		let filters = Filters {
+			played: FilterStatus::NegativeCases,
+			..Filters::default()
+		};
+		filters.apply(&podcasts);
+		menu.update_items();
+		assert_eq!(menu.items.len(true), 3);
+		let badge = podcasts.map_single(1, |pod| pod.get_title(40)).unwrap();
+		assert!(badge.ends_with(" (3/3) "));
+
+		// marking the selected episode as played moves on to the next one
+		let mut played = menu.items.clone_episode(1).unwrap();
+		played.played = true;
+		menu.items.replace(1, played);
+		filters.apply(&podcasts);
+		menu.update_items();
+		assert_eq!(selected_id(&menu), Some(3));
+		let badge = podcasts.map_single(1, |pod| pod.get_title(40)).unwrap();
+		assert!(badge.ends_with(" (2/2) "));
+
+		// or back to the one before, at the end of the list
+		menu.scroll(Scroll::Down(1));
+		let mut played = menu.items.clone_episode(5).unwrap();
+		played.played = true;
+		menu.items.replace(5, played);
+		filters.apply(&podcasts);
+		menu.update_items();
+		assert_eq!(selected_id(&menu), Some(3));
+	}
+
+	#[test]
+	fn episode_views()
+	{
+		let mut menu = create_menu(7, 68, 0, 0);
+		let podcasts = podcast_list(&menu.items);
+		let mut downloaded = menu.items.clone_episode(1).unwrap();
+		downloaded.path = Some(std::path::PathBuf::from("/tmp/episode.mp3"));
+		menu.items.replace(1, downloaded);
+
+		let mut filters = Filters::default();
+		let mut labels = Vec::new();
+		let mut shown = Vec::new();
+		for _ in 0..4
+		{
+			filters.next_view();
+			filters.apply(&podcasts);
+			labels.push(filters.episode_label());
+			shown.push(menu.items.len(true));
+		}
+		assert_eq!(labels, vec![
+			Some("unplayed".to_string()),
+			Some("downloaded".to_string()),
+			Some("downloaded, unplayed".to_string()),
+			None,
+		]);
+		assert_eq!(shown, vec![3, 1, 1, 7]);
+
+		// the selection is taken from the filtered list
+		filters.next_view();
+		filters.apply(&podcasts);
+		menu.update_items();
+		assert_eq!(menu.items.map_single_by_index(0, |ep| ep.id), Some(1));
+	}
+
+	#[test]
+	fn podcasts_sorted()
+	{
+		let menu = create_menu(7, 68, 0, 0);
+		let mut latest = menu.items.clone_episode(1).unwrap();
+		latest.pod_id = 3;
+		latest.pubdate = Some(Utc::now() + chrono::Duration::days(1));
+
+		let first = podcast_list(&menu.items).clone_podcast(1).unwrap();
+		let mut second = first.clone();
+		second.id = 2;
+		second.sort_title = "second".to_string();
+		second.episodes = LockVec::new(Vec::new());
+		let mut third = first.clone();
+		third.id = 3;
+		third.sort_title = "third".to_string();
+		third.episodes = LockVec::new(vec![latest]);
+		let podcasts = LockVec::new(vec![third, first, second]);
+
+		podcasts.sort_podcasts(PodcastSort::Title);
+		assert_eq!(*podcasts.borrow_order(), vec![1, 2, 3]);
+		// podcasts without episodes come last
+		podcasts.sort_podcasts(PodcastSort::Latest);
+		assert_eq!(*podcasts.borrow_order(), vec![3, 1, 2]);
+		podcasts.sort_podcasts(PodcastSort::Unplayed);
+		assert_eq!(*podcasts.borrow_order(), vec![1, 3, 2]);
+	}
+
+	#[test]
+	fn episode_styles()
+	{
+		let menu = create_menu(7, 68, 0, 0);
+		let state = |ep_id: i64, change: fn(&mut Episode)| {
+			let mut ep = menu.items.clone_episode(ep_id).unwrap();
+			change(&mut ep);
+			return ep.state();
+		};
+		assert_eq!(state(0, |_| ()), ItemState::Played);
+		assert_eq!(state(1, |_| ()), ItemState::New);
+		assert_eq!(state(1, |ep| ep.pubdate = None), ItemState::Unplayed);
+		assert_eq!(state(0, |ep| ep.path = Some("ep.mp3".into())), ItemState::Downloaded);
+		assert_eq!(state(1, |ep| ep.downloading = true), ItemState::Downloading);
+		assert_eq!(state(1, |ep| ep.archived = true), ItemState::Inactive);
+
+		let colors = crate::ui::AppColors::default();
+		let style = item_style(&colors, ItemState::Played, None);
+		assert_eq!(style.foreground_color, Some(colors.played.0));
+		assert!(!style.attributes.has(style::Attribute::Bold));
+		let style = item_style(&colors, ItemState::New, None);
+		assert_eq!(style.foreground_color, Some(colors.new.0));
+		assert!(style.attributes.has(style::Attribute::Bold));
+
+		// the highlight colors win, but the attributes are kept
+		let style = item_style(&colors, ItemState::New, Some(true));
+		assert_eq!(style.foreground_color, Some(colors.highlighted_active.0));
+		assert_eq!(style.background_color, Some(colors.highlighted_active.1));
+		assert!(style.attributes.has(style::Attribute::Bold));
+		let style = item_style(&colors, ItemState::Downloaded, Some(false));
+		assert_eq!(style.foreground_color, Some(colors.highlighted.0));
+
+		// without colors, only attributes set the items apart
+		let mut colors = colors;
+		colors.monochrome = true;
+		let style = item_style(&colors, ItemState::New, None);
+		assert_eq!(style.foreground_color, None);
+		assert_eq!(style.background_color, None);
+		assert!(style.attributes.has(style::Attribute::Bold));
+		let style = item_style(&colors, ItemState::Downloading, Some(true));
+		assert_eq!(style.foreground_color, None);
+		assert!(style.attributes.has(style::Attribute::Reverse));
+		assert!(!style.attributes.has(style::Attribute::Italic));
+		let style = item_style(&colors, ItemState::Played, Some(false));
+		assert!(style.attributes.has(style::Attribute::Underlined));
+		assert!(!style.attributes.has(style::Attribute::Reverse));
+	}
+
+	#[test]
+	fn log_entries()
+	{
+		let info = LogEntry::new("Synced 3 podcasts".to_string(), false);
+		let error = LogEntry::new("Error retrieving RSS feed".to_string(), true);
+		assert_eq!(info.state(), ItemState::Normal);
+		assert_eq!(error.state(), ItemState::Error);
+
+		// the time comes before the message, and the title is truncated
+		let title = info.get_title(40);
+		assert!(title.ends_with(" Synced 3 podcasts "));
+		assert_eq!(info.get_title(10).chars().count(), 10);
+	}
+
+	#[test]
+	fn wrapped_titles()
+	{
+		// the second title is too long for one row, so five rows fit the
+		// first four items
+		let mut menu = create_menu(7, 68, 0, 0);
+		menu.wrap_titles = true;
+		menu.update_items();
+		assert_eq!(
+			menu.panel.get_row(1).trim_end(),
+			" This is a very long episode title but we'll get through it"
+		);
+		assert_eq!(menu.panel.get_row(2).trim_end(), " together");
+		assert_eq!(menu.panel.get_row(2).display_width(), 65);
+		assert_eq!(menu.panel.get_row(3).trim_end(), " An episode with le Unicodé");
+		assert_eq!(menu.visible_items(), 4);
+		assert_eq!(menu.item_at_row(2), Some(1));
+		assert_eq!(menu.item_at_row(3), Some(2));
+
+		// moving past the bottom scrolls just far enough for the item to
+		// fit, counting the wrapped item as two rows
+		menu.scroll(Scroll::Down(3));
+		assert_eq!((menu.top_row, menu.selected), (0, 3));
+		menu.scroll(Scroll::Down(1));
+		assert_eq!((menu.top_row, menu.selected), (1, 3));
+		assert_eq!(menu.panel.get_row(1).trim_end(), " together");
+		assert_eq!(menu.panel.get_row(4).trim_end(), " Here's another title");
+		assert_eq!(menu.item_at_row(4), Some(3));
+
+		menu.scroll(Scroll::Down(u16::MAX));
+		assert_eq!(menu.get_menu_idx(menu.selected), 6);
+		assert_eq!(menu.item_at_row(4), Some(menu.selected));
+		menu.scroll(Scroll::Up(u16::MAX));
+		assert_eq!((menu.top_row, menu.selected), (0, 0));
+
+		// titles that do not fit on two rows are cut short
+		let long = "word ".repeat(40);
+		assert_eq!(long.wrap_width(20, 2), vec!["word word word word", "word word word word…"]);
+		assert_eq!(long.wrap_width(20, 1), vec![long.truncate_width(20)]);
+	}
+
 	#[test]
 	fn resize_bigger()
 	{
@@ -617,6 +1461,144 @@ mod tests
 		assert_eq!(non_empty.len(), (real_rows - 2) as usize);
 	}
 
+	#[test]
+	fn date_formats()
+	{
+		// the column fits the longest names of months and weekdays
+		assert_eq!(DateFormat::new("%F").width(), 10);
+		assert_eq!(DateFormat::new("%d %b %Y").width(), 11);
+		assert_eq!(DateFormat::new("%A %-d %B").width(), "Wednesday 30 September".len());
+
+		let mut menu = create_menu(7, 68, 0, 0);
+		let date_format = DateFormat::new("%a %d %b %Y");
+		menu.columns = vec![EpisodeColumn::Date(date_format.clone())];
+		menu.update_items();
+		let date = date_format.format(&menu.items.map_single(0, |ep| ep.pubdate.unwrap()).unwrap());
+		assert!(menu.panel.get_row(0).ends_with(&format!(" {date} ")));
+	}
+
+	#[test]
+	fn episode_columns()
+	{
+		let real_rows = 5;
+		let real_cols = 80;
+		let mut menu = create_menu(real_rows + 2, real_cols + 3, 0, 0);
+		menu.columns = vec![
+			EpisodeColumn::Date(DateFormat::new("%F")),
+			EpisodeColumn::Duration,
+			EpisodeColumn::Size,
+		];
+		menu.items.borrow_map().get_mut(&0).unwrap().file_size = Some(61_000_000);
+		menu.update_items();
+
+		// the title flexes, and the columns are right-aligned at the end
+		let date = Utc::now().format("%F").to_string();
+		let row = menu.panel.get_row(0);
+		assert!(row.starts_with(" A Very Cool Episode "));
+		assert!(row.ends_with(&format!(" {date}  3h 25m  61.0 MB ")));
+		assert_eq!(row.display_width(), real_cols as usize);
+		let row = menu.panel.get_row(1);
+		assert!(row.ends_with(&format!(" {date}  3h 25m          ")));
+		assert!(row.starts_with(" This is a very long episode title but we'll get t… "));
+
+		// wide characters count as two columns
+		let row = menu.panel.get_row(3);
+		assert!(row.starts_with(" How does an episode with emoji sound? 😉 "));
+		assert_eq!(row.display_width(), real_cols as usize);
+
+		// the columns listed last are dropped first in narrow panels
+		menu.resize(real_rows + 2, 50 + 3, 0);
+		let row = menu.panel.get_row(0);
+		assert!(row.ends_with(&format!(" {date} ")));
+		assert!(!row.contains("3h 25m"));
+		assert_eq!(row.display_width(), 50);
+	}
+
+	#[test]
+	fn relative_dates()
+	{
+		let offset = FixedOffset::east(2 * 3600);
+		let at = |s: &str| offset.datetime_from_str(s, "%F %T").unwrap();
+		let now = at("2024-03-01 00:05:00");
+
+		// days start at midnight, however close the times are
+		assert_eq!(relative_date(&at("2024-03-01 00:00:00"), &now, 30).unwrap(), "today");
+		assert_eq!(relative_date(&at("2024-02-29 23:55:00"), &now, 30).unwrap(), "yesterday");
+		let late = at("2024-03-01 23:59:59");
+		assert_eq!(relative_date(&at("2024-03-01 00:00:00"), &late, 30).unwrap(), "today");
+
+		// across the end of a month, in leap and common years
+		assert_eq!(relative_date(&at("2024-02-27 12:00:00"), &now, 30).unwrap(), "3 days ago");
+		let common = at("2023-03-01 00:05:00");
+		assert_eq!(relative_date(&at("2023-02-27 12:00:00"), &common, 30).unwrap(), "2 days ago");
+
+		// weeks and months, up to the limit
+		assert_eq!(relative_date(&at("2024-02-23 12:00:00"), &now, 30).unwrap(), "1 week ago");
+		assert_eq!(relative_date(&at("2024-02-10 12:00:00"), &now, 30).unwrap(), "2 weeks ago");
+		assert_eq!(relative_date(&at("2024-01-31 12:00:00"), &now, 30).unwrap(), "1 month ago");
+		assert_eq!(relative_date(&at("2024-01-30 12:00:00"), &now, 30), None);
+		assert_eq!(relative_date(&at("2023-11-01 12:00:00"), &now, 365).unwrap(), "4 months ago");
+
+		// episodes dated in the future
+		assert_eq!(relative_date(&at("2024-03-02 00:00:00"), &now, 30).unwrap(), "tomorrow");
+		assert_eq!(relative_date(&at("2024-03-03 08:00:00"), &now, 30).unwrap(), "in 2 days");
+		assert_eq!(relative_date(&at("2024-04-03 08:00:00"), &now, 30), None);
+
+		// the same moments can fall on different days in other timezones
+		let utc_date = Utc.ymd(2024, 2, 29).and_hms(22, 30, 0);
+		let utc_now = Utc.ymd(2024, 3, 1).and_hms(0, 30, 0);
+		assert_eq!(relative_date(&utc_date, &utc_now, 30).unwrap(), "yesterday");
+		let west = FixedOffset::west(5 * 3600);
+		assert_eq!(
+			relative_date(&utc_date.with_timezone(&west), &utc_now.with_timezone(&west), 30)
+				.unwrap(),
+			"today"
+		);
+	}
+
+	#[test]
+	fn wide_titles()
+	{
+		let titles = [
+			"日本語のポッドキャスト番組",
+			"Mixed 中文 and emoji 🎧🎧 title",
+			"한국어 에피소드 😉",
+		];
+		let mut episodes = create_menu(7, 68, 0, 0);
+		for (id, title) in titles.iter().enumerate()
+		{
+			let mut ep = episodes.items.clone_episode(id as i64).unwrap();
+			ep.title = title.to_string();
+			episodes.items.replace(id as i64, ep);
+		}
+		let mut podcast = podcast_list(&episodes.items).clone_podcast(1).unwrap();
+		let podcasts: Vec<Podcast> = titles.iter().enumerate().map(|(id, title)| {
+			podcast.id = id as i64;
+			podcast.title = title.to_string();
+			return podcast.clone();
+		}).collect();
+		let colors = Rc::new(crate::ui::AppColors::default());
+		let panel = Panel::new("Podcasts".to_string(), 0, colors, 7, 68, 0, (0, 0, 0, 0));
+		let mut podcasts = Menu::new(panel, None, LockVec::new(podcasts));
+
+		for n_col in 8..60
+		{
+			podcasts.resize(7, n_col, 0);
+			podcasts.update_items();
+			episodes.resize(7, n_col, 0);
+			episodes.update_items();
+			for row in 0..titles.len()
+			{
+				let cols = podcasts.panel.get_cols() as usize;
+				assert!(podcasts.panel.get_row(row).display_width() <= cols);
+				assert!(episodes.panel.get_row(row).display_width() <= cols);
+			}
+		}
+		podcasts.resize(7, 28, 0);
+		podcasts.update_items();
+		assert_eq!(podcasts.panel.get_row(0), " 日本語のポッドキャスト… ");
+	}
+
 	#[test]
 	fn chop_accent()
 	{
@@ -625,7 +1607,7 @@ mod tests
 		let mut menu = create_menu(real_rows + 2, real_cols + 5, 0, 0);
 		menu.update_items();
 
-		let expected = " An episode with le Unicod ".to_string();
+		let expected = " An episode with le Unico… ".to_string();
 
 		assert_eq!(menu.panel.get_row(2), expected);
 	}
@@ -638,7 +1620,7 @@ mod tests
 		let mut menu = create_menu(real_rows + 2, real_cols + 5, 0, 0);
 		menu.update_items();
 
-		let expected = " How does an episode with emoji sound?  ".to_string();
+		let expected = " How does an episode with emoji sound?… ".to_string();
 
 		assert_eq!(menu.panel.get_row(3), expected);
 	}
@@ -3,19 +3,24 @@ use std::rc::Rc;
 use crossterm::style;
 
 use super::AppColors;
+use crate::types::StringUtils;
 
 /// Struct holding the raw data used for building the details panel.
 #[derive(Debug)]
+#[allow(dead_code)]
 pub struct Panel
 {
 	pub buffer: Vec<String>,
 	pub screen_pos: usize,
 	pub colors: Rc<AppColors>,
 	pub title: String,
+	pub footer: Option<String>,
+	pub position: Option<String>,
 	pub start_x: u16,
 	pub n_row: u16,
 	pub n_col: u16,
 	pub margins: (u16, u16, u16, u16),
+	pub visible: bool,
 }
 
 impl Panel
@@ -32,20 +37,45 @@ impl Panel
 	{
 		// we represent the window as a vector of Strings instead of
 		// printing to the terminal buffer
-		let buffer = vec![String::new(); (n_row - 2) as usize];
+		let buffer = vec![String::new(); n_row.saturating_sub(2) as usize];
 
 		return Panel {
 			buffer: buffer,
 			screen_pos: screen_pos,
 			colors: colors,
 			title: title,
+			footer: None,
+			position: None,
 			start_x: start_x,
 			n_row: n_row,
 			n_col: n_col,
 			margins: margins,
+			visible: true,
 		};
 	}
 
+	pub fn set_title(&mut self, title: String)
+	{
+		self.title = title;
+	}
+
+	pub fn set_footer(&mut self, footer: Option<String>)
+	{
+		self.footer = footer;
+	}
+
+	pub fn set_position(&mut self, position: Option<String>)
+	{
+		self.position = position;
+	}
+
+	pub fn draw_bottom(&self) {}
+
+	pub fn set_visible(&mut self, visible: bool)
+	{
+		self.visible = visible;
+	}
+
 	pub fn redraw(&self) {}
 
 	// pub fn clear(&mut self) {
@@ -54,7 +84,7 @@ impl Panel
 
 	pub fn clear_inner(&mut self)
 	{
-		self.buffer = vec![String::new(); (self.n_row - 2) as usize];
+		self.buffer = vec![String::new(); self.n_row.saturating_sub(2) as usize];
 	}
 
 	pub fn write_line(
@@ -71,7 +101,10 @@ impl Panel
 		_key_style: Option<style::ContentStyle>,
 		_value_style: Option<style::ContentStyle>,
 	) {
-		self.buffer[y as usize] = format!("{key}: {value}");
+		let cols = self.get_cols() as usize;
+		let key = format!("{key}:").truncate_width(cols);
+		let value = format!(" {value}").truncate_width(cols - key.display_width());
+		self.buffer[y as usize] = format!("{key}{value}");
 	}
 
 	pub fn write_wrap_line(
@@ -83,7 +116,7 @@ impl Panel
 	{
 		let mut row = start_y;
 		let max_row = self.get_rows();
-		let wrapper = textwrap::wrap(&string, self.get_cols() as usize);
+		let wrapper = textwrap::wrap(string, self.get_cols() as usize);
 		for line in wrapper
 		{
 			self.write_line(row, line.to_string(), None);
@@ -103,7 +136,7 @@ impl Panel
 		self.n_col = n_col;
 		self.start_x = start_x;
 
-		let new_len = (n_row - 2) as usize;
+		let new_len = n_row.saturating_sub(2) as usize;
 		let len = self.buffer.len();
 		if new_len < len
 		{
@@ -111,7 +144,7 @@ impl Panel
 		}
 		else if new_len > len
 		{
-			for _ in (new_len - len)..new_len
+			for _ in len..new_len
 			{
 				self.buffer.push(String::new());
 			}
@@ -121,13 +154,28 @@ impl Panel
 	pub fn get_rows(&self) -> u16
 	{
 		// 2 for border on top and bottom
-		return self.n_row - self.margins.0 - self.margins.2 - 2;
+		return self.n_row.saturating_sub(self.margins.0 + self.margins.2 + 2);
 	}
 
 	pub fn get_cols(&self) -> u16
 	{
 		// 2 for border, and 1 extra for some reason...
-		return self.n_col - self.margins.1 - self.margins.3 - 3;
+		return self.n_col.saturating_sub(self.margins.1 + self.margins.3 + 3);
+	}
+
+	pub fn rel_coords(&self, x: u16, y: u16) -> Option<(u16, u16)>
+	{
+		if !self.visible
+		{
+			return None;
+		}
+		let rel_x = x.checked_sub(self.start_x + self.margins.3 + 1)?;
+		let rel_y = y.checked_sub(self.margins.0 + 1)?;
+		if rel_x < self.get_cols() && rel_y < self.get_rows()
+		{
+			return Some((rel_x, rel_y));
+		}
+		return None;
 	}
 
 	pub fn get_row(&self, row: usize) -> String
@@ -1,14 +1,20 @@
 use std::io::{self, Write};
+use std::path::Path;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crossterm::{
 	self, cursor,
-	event::{self, Event},
-	execute, terminal,
+	event::{
+		self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton,
+		MouseEvent, MouseEventKind,
+	},
+	execute, style, terminal,
 };
+use chrono::{DateTime, Utc};
 use lazy_static::lazy_static;
 use regex::Regex;
 
@@ -18,25 +24,51 @@ mod panel;
 
 pub mod colors;
 mod details_panel;
+mod hint_bar;
+mod line_editor;
 mod menu;
 mod notification;
 mod popup;
 
 use self::colors::AppColors;
-use self::details_panel::{Details, DetailsPanel};
+use self::details_panel::{Details, DetailsPanel, EpisodeCounts};
+use self::hint_bar::HintBar;
 use self::menu::Menu;
 use self::notification::NotifWin;
 use self::panel::Panel;
 use self::popup::PopupWin;
 
 use super::MainMessage;
+use crate::clipboard;
 use crate::config::Config;
-use crate::keymap::{Keybindings, UserAction};
+use crate::keymap::{self, KeyMatch, Keybindings, UserAction};
+use crate::play_file;
+use crate::threadpool::Shutdown;
 use crate::types::*;
 
 /// Amount of time in milliseconds between ticks in the event loop
 const TICK_RATE: u64 = 20;
 
+/// Maximum amount of time in milliseconds between two clicks on the same
+/// episode for them to count as a double-click
+const DOUBLE_CLICK_TIME: u64 = 500;
+
+/// Largest count that can be typed before a command, e.g., the "15" in
+/// "15j"
+const MAX_COUNT: usize = 9999;
+
+/// Maximum amount of time in milliseconds between two letters typed to
+/// jump to an item for them to count as one prefix
+const JUMP_TIME: u64 = 1000;
+
+/// Maximum amount of time in milliseconds to wait for the next key of a
+/// sequence, e.g., the second "g" of "g g", before dropping it
+const SEQUENCE_TIME: u64 = 1000;
+
+/// Whether the UI has put the terminal into raw mode and the alternate
+/// screen, and it still needs to be restored
+static TERMINAL_TAKEN: AtomicBool = AtomicBool::new(false);
+
 lazy_static!
 {
 	/// Regex for finding <br/> tags -- also captures any surrounding
@@ -45,9 +77,48 @@ lazy_static!
 		r"((\r\n)|\r|\n)*<br */?>((\r\n)|\r|\n)*"
 	).expect("Regex error");
 
-	/// Regex for finding HTML tags
+	/// Regex for finding HTML tags -- a "<" that is not followed by a tag
+	/// name is left alone
 	static ref RE_HTML_TAGS: Regex = Regex::new(
-		r"<[^<>]*>"
+		r"</?[a-zA-Z!][^<>]*>"
+	).expect("Regex error");
+
+	/// Regex for finding HTML comments, and <script> and <style> tags
+	/// along with their contents
+	static ref RE_HTML_HIDDEN: Regex = Regex::new(
+		r"(?is)<!--.*?-->|<script\b.*?</script\s*>|<style\b.*?</style\s*>"
+	).expect("Regex error");
+
+	/// Regex for finding <a> tags with an href attribute -- captures the
+	/// link target and the text of the link
+	static ref RE_LINK_TAGS: Regex = Regex::new(
+		r#"(?is)<a\b[^<>]*?\bhref\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'<>]+))[^<>]*>(.*?)</a\s*>"#
+	).expect("Regex error");
+
+	/// Regex for finding list item tags
+	static ref RE_LIST_ITEMS: Regex = Regex::new(
+		r"(?i)<li\b[^<>]*>"
+	).expect("Regex error");
+
+	/// Regex for finding opening and closing tags of block elements, which
+	/// are separated from the surrounding text by a blank line
+	static ref RE_BLOCK_TAGS: Regex = Regex::new(
+		r"(?i)</?(p|div|ul|ol|blockquote|pre|table|tr|section|article|h[1-6])\b[^<>]*>"
+	).expect("Regex error");
+
+	/// Regex for finding HTML entities (e.g., &amp; or &#8217;)
+	static ref RE_ENTITIES: Regex = Regex::new(
+		r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z][a-zA-Z0-9]*);"
+	).expect("Regex error");
+
+	/// Regex for finding runs of whitespace, including line breaks
+	static ref RE_WHITESPACE: Regex = Regex::new(
+		r"\s+"
+	).expect("Regex error");
+
+	/// Regex for finding spaces around line breaks
+	static ref RE_LINE_SPACES: Regex = Regex::new(
+		r"[ \t]*\n[ \t]*"
 	).expect("Regex error");
 
 	/// Regex for finding more than two line breaks
@@ -65,21 +136,50 @@ lazy_static!
 pub enum UiMsg
 {
 	AddFeed(String),
+	RenamePodcast(i64, String),
+	SetTags(i64, Vec<String>),
+	SetFavorite(i64, bool),
+	SetMaxEpisodes(i64, Option<usize>),
 	Play(i64, i64),
 	MarkPlayed(i64, i64, bool),
 	MarkAllPlayed(i64, bool),
+	Archive(i64, i64, bool),
+	ArchiveOlder(i64, i64),
+	SetPosition(i64, i64, Option<i64>),
+	Enqueue(i64, i64),
+	SetQueue(Vec<i64>),
+	PlayNext,
+	ShowStats,
+	ShowLog,
+	OpenLink(i64, Option<i64>),
+	ExportFeeds(String),
+	PodcastViewed(i64, DateTime<Utc>),
 	Sync(i64),
 	SyncAll,
+	SyncGpodder,
+	GpodderRemovals(Vec<i64>),
+	Shutdown(Shutdown),
 	Download(i64, i64),
 	DownloadMulti(Vec<(i64, i64)>),
+	MarkPlayedMulti(i64, Vec<i64>, bool),
+	ArchiveMulti(i64, Vec<i64>, bool),
+	DeleteMulti(i64, Vec<i64>),
 	DownloadAll(i64),
 	UnmarkDownloaded(i64, i64),
 	Delete(i64, i64),
 	DeleteAll(i64),
+	SetWorkers(usize),
+	ReloadConfig,
+	EditConfig,
 	RemovePodcast(i64, bool),
+	UndoRemoval,
 	RemoveEpisode(i64, i64, bool),
 	RemoveAllEpisodes(i64, bool),
 	FilterChange(FilterType),
+	SearchPodcasts(Option<String>),
+	SearchEpisodes(Option<String>),
+	SetEpisodeSort(i64, EpisodeSort),
+	SetPodcastSort(PodcastSort),
 	Quit,
 	Noop,
 }
@@ -92,31 +192,130 @@ pub enum Scroll
 	Down(u16),
 }
 
-/// Simple enum to identify which menu is currently active.
-#[derive(Debug)]
+impl Scroll
+{
+	/// Returns how far a navigation action scrolls a panel showing
+	/// `page_rows` rows at once, or None for actions that do not scroll.
+	/// Going to the top or bottom scrolls as far as possible; the panel
+	/// stops at the first or last item.
+	pub fn from_action(action: UserAction, page_rows: u16) -> Option<Scroll>
+	{
+		let page = page_rows.max(1);
+		let big = (page_rows / crate::config::BIG_SCROLL_AMOUNT).max(1);
+		return match action
+		{
+			UserAction::Up => Some(Scroll::Up(1)),
+			UserAction::Down => Some(Scroll::Down(1)),
+			UserAction::BigUp => Some(Scroll::Up(big)),
+			UserAction::BigDown => Some(Scroll::Down(big)),
+			UserAction::PageUp => Some(Scroll::Up(page)),
+			UserAction::PageDown => Some(Scroll::Down(page)),
+			UserAction::GoTop => Some(Scroll::Up(u16::MAX)),
+			UserAction::GoBot => Some(Scroll::Down(u16::MAX)),
+			_ => None,
+		};
+	}
+
+	/// Returns the scroll repeated `times` times, e.g., for a count typed
+	/// before a movement key.
+	pub fn repeat(self, times: usize) -> Scroll
+	{
+		let times = times.min(u16::MAX as usize) as u16;
+		return match self
+		{
+			Scroll::Up(v) => Scroll::Up(v.saturating_mul(times)),
+			Scroll::Down(v) => Scroll::Down(v.saturating_mul(times)),
+		};
+	}
+}
+
+/// Position and width of one of the main panels on the screen. Panels
+/// hidden by the zoomed layout keep a size, but are not drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PanelPos
+{
+	start_x: u16,
+	n_col: u16,
+	visible: bool,
+}
+
+/// Simple enum to identify which menu is currently active. The queue
+/// panel, when it is shown, takes the place of the details panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ActivePanel
 {
 	PodcastMenu,
 	EpisodeMenu,
 	DetailsPanel,
+	QueuePanel,
+}
+
+/// Holds the state of the search prompt while the user is typing: the
+/// text typed so far, whether the episode menu is being searched rather
+/// than the podcast menu, and the positions of the podcast and episode
+/// menus from before the prompt was opened, which are restored if the
+/// search is cancelled.
+#[derive(Debug)]
+struct SearchPrompt
+{
+	text: String,
+	episodes: bool,
+	positions: [MenuPosition; 2],
+}
+
+/// Position of a menu on screen, along with the ID of the selected item,
+/// so that the same item can be selected again once the list changes.
+#[derive(Debug, Clone, Copy)]
+struct MenuPosition
+{
+	top_row: u16,
+	selected: u16,
+	id: Option<i64>,
 }
 
 /// Struct containing all interface elements of the TUI. Functionally,
 /// it encapsulates the terminal menus and panels, and holds data about
-/// the size of the screen.
+/// the size of the screen. While the terminal is smaller than the
+/// minimum size, `too_small` is set and the panels keep the last size
+/// that fit. While `show_queue` is set, the queue panel is shown on the
+/// right instead of the details panel.
 #[derive(Debug)]
 pub struct Ui<'a>
 {
 	n_row: u16,
 	n_col: u16,
-	keymap: &'a Keybindings,
+	keymap: Rc<Keybindings>,
 	colors: Rc<AppColors>,
 	podcast_menu: Menu<Podcast>,
 	episode_menu: Menu<Episode>,
 	details_panel: Option<DetailsPanel>,
+	queue_menu: Menu<QueueItem>,
+	show_queue: bool,
 	active_panel: ActivePanel,
 	notif_win: NotifWin,
-	popup_win: PopupWin<'a>,
+	popup_win: PopupWin,
+	hint_bar: Option<HintBar>,
+	search_prompt: Option<SearchPrompt>,
+	restore_positions: Option<[MenuPosition; 2]>,
+	selected_pod_id: Option<i64>,
+	podcast_sort: PodcastSort,
+	podcast_filter: Option<String>,
+	episode_filter: Option<String>,
+	viewing: Option<(i64, DateTime<Utc>)>,
+	last_click: Option<(Instant, i64)>,
+	count: Option<usize>,
+	pending_keys: Option<(Vec<String>, Instant)>,
+	jump_by_letter: bool,
+	jump_mode_until: Option<Instant>,
+	jump_prefix: Option<(String, Instant)>,
+	layout: Layout,
+	podcast_width: Option<PanelWidth>,
+	clipboard_command: Option<&'a str>,
+	enable_mouse: bool,
+	date_format: DateFormat,
+	detail_date_format: DateFormat,
+	too_small: bool,
+	waiting_to_quit: bool,
 }
 
 impl<'a> Ui<'a>
@@ -141,7 +340,16 @@ impl<'a> Ui<'a>
 			{
 				ui.notif_win.check_notifs();
 
-				match ui.getch()
+				let input = ui.getch();
+				ui.update_hints();
+				let quit = matches!(input, UiMsg::Quit);
+				if let Some(viewed) = ui.check_viewing(quit)
+				{
+					tx_to_main
+						.send(Message::Ui(viewed))
+						.expect("Thread messaging error");
+				}
+				match input
 				{
 					UiMsg::Noop => (),
 					input => tx_to_main
@@ -168,6 +376,54 @@ impl<'a> Ui<'a>
 						MainMessage::UiSpawnDownloadPopup(episodes, selected) => {
 							ui.popup_win.spawn_download_win(episodes, selected);
 						}
+						MainMessage::UiSpawnFeedPopup(urls) => {
+							ui.popup_win.spawn_feed_win(urls);
+						}
+						MainMessage::UiUpdateQueue(items) => ui.update_queue(items),
+						MainMessage::UiPodcastFilter(label) => ui.set_podcast_filter(label),
+						MainMessage::UiEpisodeFilter(label) => ui.set_episode_filter(label),
+						MainMessage::UiSpawnStatsPopup(stats) => {
+							ui.popup_win.spawn_stats_win(stats);
+						}
+						MainMessage::UiSpawnLogPopup(entries) => {
+							ui.popup_win.spawn_log_win(entries);
+						}
+						MainMessage::UiConfirmGpodderRemovals(podcasts) => {
+							let removed = ui.confirm_gpodder_removals(podcasts);
+							tx_to_main
+								.send(Message::Ui(UiMsg::GpodderRemovals(removed)))
+								.expect("Thread messaging error");
+						}
+						MainMessage::UiConfirmQuit(downloads) => {
+							if let Some(mode) = ui.confirm_quit(downloads)
+							{
+								ui.waiting_to_quit = mode == Shutdown::Finish;
+								tx_to_main
+									.send(Message::Ui(UiMsg::Shutdown(mode)))
+									.expect("Thread messaging error");
+							}
+						}
+						MainMessage::UiReloadConfig(keymap, colors) => {
+							ui.reload_config(keymap, colors);
+						}
+						MainMessage::UiRunEditor(args) => {
+							let error = ui.run_editor(&args);
+							tx_to_main
+								.send(Message::EditorExit(error))
+								.expect("Thread messaging error");
+						}
+						MainMessage::UiRunPlayer(playback) => {
+							let exit = ui.run_player(playback);
+							tx_to_main
+								.send(Message::PlayerExit(exit))
+								.expect("Thread messaging error");
+						}
+					}
+					// anything drawn for the message goes over the
+					// message about the terminal size
+					if ui.too_small
+					{
+						ui.draw_too_small();
 					}
 				}
 
@@ -184,21 +440,24 @@ impl<'a> Ui<'a>
 	/// manipulation.
 	pub fn new(config: &'a Config, items: LockVec<Podcast>) -> Ui<'a>
 	{
-		terminal::enable_raw_mode().expect("Terminal can't run in raw mode.");
-		execute!(
-			io::stdout(),
-			terminal::EnterAlternateScreen,
-			terminal::Clear(terminal::ClearType::All),
-			cursor::Hide
-		)
-		.expect("Can't draw to screen.");
+		take_terminal(config.enable_mouse);
 
+		let keymap = Rc::new(config.keybindings.clone());
 		let colors = Rc::new(config.colors.clone());
 
+		// the panels are laid out for at least the minimum size, even if
+		// the terminal starts out smaller than that
 		let (n_col, n_row) = terminal::size().expect("Can't get terminal size");
-		let (pod_col, ep_col, det_col) = Self::calculate_sizes(n_col);
+		let too_small = is_too_small(n_col, n_row);
+		let n_col = n_col.max(crate::config::MIN_TERMINAL_COLS);
+		let n_row = n_row.max(crate::config::MIN_TERMINAL_ROWS);
+		// the message window, and the hint bar above it if it is on
+		let bottom_rows = if config.key_hints { 2 } else { 1 };
+		let (pod_pos, ep_pos, det_pos) = Self::calculate_sizes(
+			n_col, config.layout, config.podcast_width, ActivePanel::PodcastMenu, false
+		);
 
-		let first_pod = match items.borrow_filtered_order().get(0)
+		let first_pod = match items.borrow_filtered_order().first()
 		{
 			Some(first_id) => match items.borrow_map().get(first_id)
 			{
@@ -208,62 +467,103 @@ impl<'a> Ui<'a>
 			None => LockVec::new(Vec::new()),
 		};
 
-		let podcast_panel = Panel::new(
+		let mut podcast_panel = Panel::new(
 			"Podcasts".to_string(),
 			0,
 			colors.clone(),
-			n_row - 1,
-			pod_col,
-			0,
+			n_row - bottom_rows,
+			pod_pos.n_col,
+			pod_pos.start_x,
 			(0, 0, 0, 0),
 		);
-		let podcast_menu = Menu::new(podcast_panel, None, items);
+		podcast_panel.set_visible(pod_pos.visible);
+		let mut podcast_menu = Menu::new(podcast_panel, None, items);
+		podcast_menu.wrap_titles = config.wrap_list_titles;
 
-		let episode_panel = Panel::new(
+		let mut episode_panel = Panel::new(
 			"Episodes".to_string(),
 			1,
 			colors.clone(),
-			n_row - 1,
-			ep_col,
-			pod_col - 1,
+			n_row - bottom_rows,
+			ep_pos.n_col,
+			ep_pos.start_x,
 			(0, 0, 0, 0),
 		);
+		episode_panel.set_visible(ep_pos.visible);
 
-		let episode_menu = Menu::new(episode_panel, None, first_pod);
+		let mut episode_menu = Menu::new(episode_panel, None, first_pod);
+		episode_menu.columns = config.episode_columns.clone();
+		episode_menu.wrap_titles = config.wrap_list_titles;
 
-		let details_panel = if n_col > crate::config::DETAILS_PANEL_LENGTH
-		{
-			Some(DetailsPanel::new(
+		let details_panel = det_pos.map(|det_pos| {
+			let mut det = DetailsPanel::new(
 				"Details".to_string(),
 				2,
 				colors.clone(),
-				n_row - 1,
-				det_col,
-				pod_col + ep_col - 2,
+				n_row - bottom_rows,
+				det_pos.n_col,
+				det_pos.start_x,
 				(0, 1, 0, 1),
-			))
-		}
-		else
-		{
-			None
-		};
+			);
+			det.panel.set_visible(det_pos.visible);
+			det
+		});
+
+		// the queue panel is hidden until the user asks for it, and then
+		// laid out in `resize()`
+		let mut queue_panel = Panel::new(
+			"Queue".to_string(),
+			2,
+			colors.clone(),
+			n_row - bottom_rows,
+			crate::config::MIN_PANEL_WIDTH,
+			0,
+			(0, 0, 0, 0),
+		);
+		queue_panel.set_visible(false);
+		let queue_menu = Menu::new(queue_panel, None, LockVec::new(Vec::new()));
 
 		let notif_win = NotifWin::new(colors.clone(), n_row - 1, n_row, n_col);
 		let popup_win = PopupWin::new(
-			&config.keybindings, colors.clone(), n_row, n_col
+			keymap.clone(), colors.clone(), n_row + 1 - bottom_rows, n_col
 		);
+		let hint_bar = config.key_hints.then(|| HintBar::new(colors.clone(), n_row - 2, n_col));
 
 		return Ui {
 			n_row: n_row,
 			n_col: n_col,
-			keymap: &config.keybindings,
+			keymap: keymap,
 			colors: colors,
 			podcast_menu: podcast_menu,
 			episode_menu: episode_menu,
 			details_panel: details_panel,
+			queue_menu: queue_menu,
+			show_queue: false,
 			active_panel: ActivePanel::PodcastMenu,
 			notif_win: notif_win,
 			popup_win: popup_win,
+			hint_bar: hint_bar,
+			search_prompt: None,
+			restore_positions: None,
+			selected_pod_id: None,
+			podcast_sort: config.podcast_sort,
+			podcast_filter: None,
+			episode_filter: None,
+			viewing: None,
+			last_click: None,
+			count: None,
+			pending_keys: None,
+			jump_by_letter: config.jump_by_letter,
+			jump_mode_until: None,
+			jump_prefix: None,
+			layout: config.layout,
+			podcast_width: config.podcast_width,
+			clipboard_command: config.clipboard_command.as_deref(),
+			enable_mouse: config.enable_mouse,
+			date_format: config.date_format.clone(),
+			detail_date_format: config.detail_date_format.clone(),
+			too_small: too_small,
+			waiting_to_quit: false,
 		};
 	}
 
@@ -271,18 +571,29 @@ impl<'a> Ui<'a>
 	/// to draw everything to the screen.
 	pub fn init(&mut self)
 	{
+		self.update_podcast_title();
+		self.update_podcast_message();
 		self.podcast_menu.redraw();
+		self.selected_pod_id = self.get_current_ids().0;
+		self.update_episode_title();
+		self.update_episode_message();
 		self.episode_menu.redraw();
 		self.podcast_menu.activate();
 		self.update_details_panel();
+		self.update_queue_message();
 
 		self.notif_win.redraw();
+		self.update_hints();
 
 		// welcome screen if user does not have any podcasts yet
 		if self.podcast_menu.items.is_empty()
 		{
 			self.popup_win.spawn_welcome_win();
 		}
+		if self.too_small
+		{
+			self.draw_too_small();
+		}
 		io::stdout().flush().unwrap();
 	}
 
@@ -294,17 +605,85 @@ impl<'a> Ui<'a>
 	/// some greater degree of abstraction; for example, input to add a
 	/// new podcast feed spawns a UI window to capture the feed URL, and
 	/// only then passes this data back to the main controller.
+	// the match on actions has no catch-all arm, so that no new action
+	// can be left out; match guards would need one
+	#[allow(clippy::collapsible_match)]
 	pub fn getch(&mut self) -> UiMsg
 	{
 		if !event::poll(Duration::from_secs(0)).expect("Can't poll for inputs")
 		{
+			if self.pending_keys.as_ref().is_some_and(
+				|(_, time)| time.elapsed() > Duration::from_millis(SEQUENCE_TIME)
+			)
+			{
+				self.pending_keys = None;
+				self.show_pending();
+			}
 			return UiMsg::Noop;
 		}
 		
 		// Else, if event::poll return Some(true)
-		match event::read().expect("Can't read inputs")
+		let event = event::read().expect("Can't read inputs");
+		if let Event::Key(KeyEvent { kind: KeyEventKind::Release, .. }) = event
+		{
+			return UiMsg::Noop;
+		}
+
+		// while waiting for downloads to finish before quitting, the only
+		// thing left to do is to cancel them
+		if self.waiting_to_quit
+		{
+			match event
+			{
+				Event::Resize(n_col, n_row) => self.resize(n_col, n_row),
+				Event::Key(input) => {
+					let cancel = matches!(input.code, KeyCode::Char('c') | KeyCode::Char('C'))
+						|| self.key_match(input) == KeyMatch::Action(UserAction::Quit);
+					if cancel
+					{
+						self.waiting_to_quit = false;
+						return UiMsg::Shutdown(Shutdown::Cancel);
+					}
+				}
+				_ => (),
+			}
+			return UiMsg::Noop;
+		}
+
+		// while the terminal is too small, only resizing and quitting do
+		// anything
+		if self.too_small
+		{
+			match event
+			{
+				Event::Resize(n_col, n_row) => self.resize(n_col, n_row),
+				Event::Key(input) => {
+					let key_match = self.key_match(input);
+					// the keys of a sequence are shown over the message
+					self.draw_too_small();
+					if key_match == KeyMatch::Action(UserAction::Quit)
+					{
+						return UiMsg::Quit;
+					}
+				}
+				_ => (),
+			}
+			return UiMsg::Noop;
+		}
+
+		match event
 		{
 			Event::Resize(n_col, n_row) => self.resize(n_col, n_row),
+			Event::Mouse(mouse) => return self.mouse_input(mouse),
+			// pasted text only goes to the search prompt, rather than
+			// being taken as a string of commands
+			Event::Paste(text) => {
+				if self.search_prompt.is_some() && !self.popup_win.is_non_welcome_popup_active()
+				{
+					return self.search_paste(&text);
+				}
+			}
+			Event::FocusGained | Event::FocusLost => (),
 			Event::Key(input) => {
 				let (curr_pod_id, curr_ep_id) = self.get_current_ids();
 
@@ -321,7 +700,12 @@ impl<'a> Ui<'a>
 				// redirect user input there
 				if self.popup_win.is_non_welcome_popup_active()
 				{
-					let popup_msg = self.popup_win.handle_input(input);
+					let popup_msg = match self.key_match(input)
+					{
+						KeyMatch::Action(action) => self.popup_win.handle_input(Some(action)),
+						KeyMatch::Prefix => return UiMsg::Noop,
+						KeyMatch::Unbound => self.popup_win.handle_input(None),
+					};
 
 					// need to check if popup window is still active,
 					// as handling character input above may involve
@@ -337,9 +721,76 @@ impl<'a> Ui<'a>
 					}
 					return popup_msg;
 				}
+
+				// while the search prompt is open, all keys go to it
+				if self.search_prompt.is_some()
+				{
+					return self.search_input(input);
+				}
 				
+				// the keys after the first one of a sequence only go
+				// towards the sequence
+				let action = if self.pending_keys.is_some()
+				{
+					match self.sequence_input(input)
+					{
+						Some(action) => Some(action),
+						None => return UiMsg::Noop,
+					}
+				}
+				else
+				{
+					let key = keymap::input_to_str(input);
+					let key_match = match &key
+					{
+						Some(key) => self.keymap.get_from_keys(std::slice::from_ref(key)),
+						None => KeyMatch::Unbound,
+					};
+					let bound = key_match != KeyMatch::Unbound;
+					if self.count.is_none() && self.jump_input(input, bound, curr_pod_id)
+					{
+						return UiMsg::Noop;
+					}
+					if self.count_input(input, bound)
+					{
+						return UiMsg::Noop;
+					}
+					match key_match
+					{
+						KeyMatch::Action(action) => Some(action),
+						KeyMatch::Prefix => {
+							self.pending_keys = key.map(|key| (vec![key], Instant::now()));
+							self.show_pending();
+							return UiMsg::Noop;
+						}
+						KeyMatch::Unbound => None,
+					}
+				};
+				let count = self.count;
+				if count.is_some()
+				{
+					self.set_count(None);
+				}
+
+				if let (Some(a), ActivePanel::QueuePanel) = (action, self.active_panel)
+				{
+					if let Some(ui_msg) = self.queue_action(a)
+					{
+						return ui_msg;
+					}
+				}
+
+				// with episodes marked, some actions apply to all of them
+				if let Some(a) = &action
+				{
+					if let Some(ui_msg) = self.marked_action(a, curr_pod_id)
+					{
+						return ui_msg;
+					}
+				}
+
 				// Else
-				match self.keymap.get_from_input(input)
+				match action
 				{
 					Some(a @ UserAction::Down)
 					| Some(a @ UserAction::Up)
@@ -351,12 +802,12 @@ impl<'a> Ui<'a>
 					| Some(a @ UserAction::BigDown)
 					| Some(a @ UserAction::GoTop)
 					| Some(a @ UserAction::GoBot) => {
-						self.move_cursor(a, curr_pod_id, curr_ep_id)
+						self.move_cursor(&a, count, curr_pod_id, curr_ep_id)
 					}
 
 					Some(UserAction::AddFeed) => {
 						let url = &self.spawn_input_notif("Feed URL: ");
-						if !url.is_empty()
+						if !url.is_empty() && self.confirm_new_feed(url)
 						{
 							return UiMsg::AddFeed(url.to_string());
 						}
@@ -374,6 +825,69 @@ impl<'a> Ui<'a>
 							return UiMsg::SyncAll;
 						}
 					}
+					Some(UserAction::SyncGpodder) => return UiMsg::SyncGpodder,
+					Some(UserAction::Rename) => {
+						if let Some(pod_id) = curr_pod_id
+						{
+							let title = self.spawn_input_notif("New title: ");
+							if !title.trim().is_empty()
+							{
+								return UiMsg::RenamePodcast(pod_id, title.trim().to_string());
+							}
+						}
+					}
+					Some(UserAction::EditTags) => {
+						if let Some(pod_id) = curr_pod_id
+						{
+							let current = self
+								.podcast_menu
+								.items
+								.map_single(pod_id, |pod| pod.tags.join(", "))
+								.unwrap_or_default();
+							let input = self.spawn_input_notif(&format!(
+								"Tags, comma-separated (\"none\" to clear) [{current}]: "
+							));
+							match input.trim()
+							{
+								"" => (),
+								"none" => return UiMsg::SetTags(pod_id, Vec::new()),
+								tags => return UiMsg::SetTags(pod_id, parse_tags(tags)),
+							}
+						}
+					}
+					Some(UserAction::ToggleFavorite) => {
+						if let Some(pod_id) = curr_pod_id
+						{
+							let favorite = self
+								.podcast_menu
+								.items
+								.map_single(pod_id, |pod| pod.favorite)
+								.unwrap_or(false);
+							return UiMsg::SetFavorite(pod_id, !favorite);
+						}
+					}
+					Some(UserAction::MaxEpisodes) => {
+						if let Some(pod_id) = curr_pod_id
+						{
+							let input = self.spawn_input_notif(
+								"Episodes to keep (0 for all, \"default\" for global setting): "
+							);
+							match input.trim()
+							{
+								"" => (),
+								"default" => return UiMsg::SetMaxEpisodes(pod_id, None),
+								num => match num.parse::<usize>()
+								{
+									Ok(num) => return UiMsg::SetMaxEpisodes(pod_id, Some(num)),
+									Err(_) => self.notif_win.timed_notif(
+										format!("Invalid number of episodes: {num}"),
+										crate::config::MESSAGE_TIME,
+										true
+									),
+								},
+							}
+						}
+					}
 
 					Some(UserAction::Play) => {
 						if let Some(pod_id) = curr_pod_id
@@ -400,20 +914,163 @@ impl<'a> Ui<'a>
 							return ui_msg;
 						}
 					}
+					Some(UserAction::Archive) => {
+						if let ActivePanel::EpisodeMenu = self.active_panel
+						{
+							if let (Some(pod_id), Some(ep_id)) = (curr_pod_id, curr_ep_id)
+							{
+								if let Some(archived) = self
+									.episode_menu
+									.items
+									.map_single(ep_id, |ep| ep.archived)
+								{
+									return UiMsg::Archive(pod_id, ep_id, !archived);
+								}
+							}
+						}
+					}
+					Some(UserAction::ArchiveOlder) => {
+						if let ActivePanel::EpisodeMenu = self.active_panel
+						{
+							if let Some(ui_msg) = self.archive_older(curr_pod_id, curr_ep_id)
+							{
+								return ui_msg;
+							}
+						}
+					}
+					Some(UserAction::SetPosition) => {
+						if let ActivePanel::EpisodeMenu = self.active_panel
+						{
+							if let (Some(pod_id), Some(ep_id)) = (curr_pod_id, curr_ep_id)
+							{
+								let input = self.spawn_input_notif(
+									"Playback position (HH:MM:SS, 0 to clear): "
+								);
+								match input.trim()
+								{
+									"" => (),
+									"0" => return UiMsg::SetPosition(pod_id, ep_id, None),
+									pos => match crate::feeds::duration_to_int(Some(pos))
+									{
+										Some(secs) => return UiMsg::SetPosition(
+											pod_id, ep_id, Some(secs as i64)
+										),
+										None => self.notif_win.timed_notif(
+											format!("Invalid position: {pos}"),
+											crate::config::MESSAGE_TIME,
+											true
+										),
+									},
+								}
+							}
+						}
+					}
+
+					Some(UserAction::Enqueue) => {
+						if let ActivePanel::EpisodeMenu = self.active_panel
+						{
+							if let (Some(pod_id), Some(ep_id)) = (curr_pod_id, curr_ep_id)
+							{
+								return UiMsg::Enqueue(pod_id, ep_id);
+							}
+						}
+					}
+					Some(UserAction::ToggleMark) => {
+						if let ActivePanel::EpisodeMenu = self.active_panel
+						{
+							if let Some(ep_id) = curr_ep_id
+							{
+								self.episode_menu.toggle_mark(ep_id);
+								self.episode_menu.update_items();
+								self.move_cursor(&UserAction::Down, None, curr_pod_id, curr_ep_id);
+								self.episode_menu.highlight_selected();
+							}
+						}
+					}
+					Some(UserAction::MarkAll) => {
+						if let ActivePanel::EpisodeMenu = self.active_panel
+						{
+							let ids = self.episode_menu.items.borrow_filtered_order().clone();
+							self.episode_menu.marked.extend(ids);
+							self.episode_menu.update_items();
+							self.episode_menu.highlight_selected();
+						}
+					}
+					Some(UserAction::ClearMarks) => self.clear_marks(),
+					Some(UserAction::ShowQueue) => self.toggle_queue(),
+					Some(UserAction::PlayNext) => return UiMsg::PlayNext,
+					Some(UserAction::ShowStats) => return UiMsg::ShowStats,
+					Some(UserAction::ShowLog) => return UiMsg::ShowLog,
+					Some(UserAction::OpenLink) => {
+						if let Some(pod_id) = curr_pod_id
+						{
+							let ep_id = match self.active_panel
+							{
+								ActivePanel::PodcastMenu => None,
+								_ => curr_ep_id,
+							};
+							return UiMsg::OpenLink(pod_id, ep_id);
+						}
+					}
+					Some(UserAction::CopyUrl) => self.copy_url(curr_pod_id, curr_ep_id),
+					Some(UserAction::ExportFeeds) => {
+						let path = self.spawn_input_notif("Export shown podcasts to OPML file: ");
+						let path = path.trim();
+						let exists = Path::new(shellexpand::tilde(path).as_ref()).is_file();
+						let question = format!("{path} already exists. Overwrite it?");
+						if exists && !self.ask_for_confirmation(&question)
+						{
+							self.notif_win.timed_notif(
+								"Export cancelled.".to_string(),
+								crate::config::MESSAGE_TIME,
+								false
+							);
+						}
+						else if !path.is_empty()
+						{
+							return UiMsg::ExportFeeds(path.to_string());
+						}
+					}
+					Some(UserAction::CopyPath) => {
+						if let ActivePanel::EpisodeMenu = self.active_panel
+						{
+							self.copy_path(curr_ep_id);
+						}
+					}
 
 					Some(UserAction::Download) => {
 						if let Some(pod_id) = curr_pod_id
 						{
 							if let Some(ep_id) = curr_ep_id
 							{
-								return UiMsg::Download(pod_id, ep_id);
+								return match count
+								{
+									Some(count) if count > 1 => {
+										UiMsg::DownloadMulti(self.next_episodes(count))
+									}
+									_ => UiMsg::Download(pod_id, ep_id),
+								};
 							}
 						}
 					}
 					Some(UserAction::DownloadAll) => {
 						if let Some(pod_id) = curr_pod_id
 						{
-							return UiMsg::DownloadAll(pod_id);
+							let sizes = self
+								.podcast_menu
+								.items
+								.map_single(pod_id, |pod| {
+									pod.episodes.map(
+										|ep| (ep.path.is_none() && !ep.downloading)
+											.then_some(ep.enclosure_size),
+										false,
+									)
+								})
+								.unwrap_or_default();
+							if self.confirm_downloads(sizes.into_iter().flatten().collect())
+							{
+								return UiMsg::DownloadAll(pod_id);
+							}
 						}
 					}
 					Some(UserAction::UnmarkDownloaded) => {
@@ -447,6 +1104,22 @@ impl<'a> Ui<'a>
 							return UiMsg::DeleteAll(pod_id);
 						}
 					}
+					Some(UserAction::SetWorkers) => {
+						let input = self.spawn_input_notif("Simultaneous downloads: ");
+						match input.trim()
+						{
+							"" => (),
+							num => match num.parse::<usize>()
+							{
+								Ok(num) if num > 0 => return UiMsg::SetWorkers(num),
+								_ => self.notif_win.timed_notif(
+									format!("Invalid number of downloads: {num}"),
+									crate::config::MESSAGE_TIME,
+									true
+								),
+							},
+						}
+					}
 
 					Some(UserAction::Remove) => match self.active_panel
 					{
@@ -482,6 +1155,7 @@ impl<'a> Ui<'a>
 							return ui_msg;
 						}
 					}
+					Some(UserAction::UndoRemoval) => return UiMsg::UndoRemoval,
 
 					Some(UserAction::FilterPlayed) => {
 						return UiMsg::FilterChange(FilterType::Played);
@@ -489,6 +1163,76 @@ impl<'a> Ui<'a>
 					Some(UserAction::FilterDownloaded) => {
 						return UiMsg::FilterChange(FilterType::Downloaded);
 					}
+					Some(UserAction::FilterView) => {
+						return UiMsg::FilterChange(FilterType::View);
+					}
+					Some(UserAction::FilterTag) => {
+						return UiMsg::FilterChange(FilterType::Tag);
+					}
+					Some(UserAction::FilterArchived) => {
+						return UiMsg::FilterChange(FilterType::Archived);
+					}
+					Some(UserAction::FilterFavorites) => {
+						return UiMsg::FilterChange(FilterType::Favorites);
+					}
+					Some(UserAction::Search) => self.spawn_search_prompt(curr_pod_id),
+					Some(UserAction::JumpTo) => {
+						if matches!(
+							self.active_panel,
+							ActivePanel::PodcastMenu | ActivePanel::EpisodeMenu
+						)
+						{
+							// letters jump for as long as the message is
+							// shown, and then JUMP_TIME after each one
+							self.jump_mode_until = Some(
+								Instant::now() + Duration::from_millis(crate::config::MESSAGE_TIME)
+							);
+							self.jump_prefix = None;
+							self.notif_win.timed_notif(
+								"Type the first letters of a title".to_string(),
+								crate::config::MESSAGE_TIME,
+								false
+							);
+						}
+					}
+					Some(UserAction::SortEpisodes) => {
+						if let Some(pod_id) = curr_pod_id
+						{
+							if let Some(sort) = self
+								.podcast_menu
+								.items
+								.map_single(pod_id, |pod| pod.episode_sort.next())
+							{
+								// keep the same episode selected once the
+								// list has been sorted again
+								self.restore_positions = Some(self.menu_positions());
+								return UiMsg::SetEpisodeSort(pod_id, sort);
+							}
+						}
+					}
+					Some(UserAction::SortPodcasts) => {
+						// keep the same podcast selected once the list has
+						// been sorted again
+						self.restore_positions = Some(self.menu_positions());
+						self.podcast_sort = self.podcast_sort.next();
+						self.update_podcast_title();
+						self.podcast_menu.redraw();
+						self.highlight_items();
+						return UiMsg::SetPodcastSort(self.podcast_sort);
+					}
+
+					Some(UserAction::CycleLayout) => {
+						self.layout = self.layout.next();
+						self.resize(self.n_col, self.n_row);
+						self.notif_win.timed_notif(
+							format!("Layout: {}", self.layout.label()),
+							crate::config::MESSAGE_TIME,
+							false,
+						);
+					}
+
+					Some(UserAction::ReloadConfig) => return UiMsg::ReloadConfig,
+					Some(UserAction::EditConfig) => return UiMsg::EditConfig,
 
 					Some(UserAction::Help) => self.popup_win.spawn_help_win(),
 
@@ -496,30 +1240,62 @@ impl<'a> Ui<'a>
 					None => (),
 				} // end of input match
 			},
-			_ => (),
 		}
 
 		return UiMsg::Noop;
 	}
 
-	/// Resize all the windows on the screen and redraw them.
+	/// Resize all the windows on the screen and redraw them. If the
+	/// terminal is smaller than the minimum size, a message saying so is
+	/// shown instead, until the terminal grows again.
 	pub fn resize(&mut self, n_col: u16, n_row: u16)
 	{
+		if is_too_small(n_col, n_row)
+		{
+			self.too_small = true;
+			self.draw_too_small();
+			return;
+		}
+		if self.too_small
+		{
+			self.too_small = false;
+			execute!(io::stdout(), terminal::Clear(terminal::ClearType::All)).unwrap();
+		}
 		self.n_row = n_row;
 		self.n_col = n_col;
+		let bottom_rows = if self.hint_bar.is_some() { 2 } else { 1 };
+
+		let (pod_pos, ep_pos, det_pos) = Self::calculate_sizes(
+			n_col, self.layout, self.podcast_width, self.active_panel, self.show_queue
+		);
 
-		let (pod_col, ep_col, det_col) = Self::calculate_sizes(n_col);
+		self.podcast_menu.panel.set_visible(pod_pos.visible);
+		self.podcast_menu.resize(n_row - bottom_rows, pod_pos.n_col, pod_pos.start_x);
+		self.episode_menu.panel.set_visible(ep_pos.visible);
+		self.episode_menu.resize(n_row - bottom_rows, ep_pos.n_col, ep_pos.start_x);
 
-		self.podcast_menu.resize(n_row - 1, pod_col, 0);
-		self.episode_menu.resize(n_row - 1, ep_col, pod_col - 1);
+		// the queue panel takes the place of the details panel
+		let det_pos = match det_pos
+		{
+			Some(queue_pos) if self.show_queue => {
+				self.queue_menu.panel.set_visible(queue_pos.visible);
+				self.queue_menu.resize(n_row - bottom_rows, queue_pos.n_col, queue_pos.start_x);
+				None
+			}
+			det_pos => {
+				self.queue_menu.panel.set_visible(false);
+				det_pos
+			}
+		};
 		self.highlight_items();
 
 		if self.details_panel.is_some()
 		{
-			if det_col > 0
+			if let Some(det_pos) = det_pos
 			{
 				let det = self.details_panel.as_mut().unwrap();
-				det.resize(n_row - 1, det_col, pod_col + ep_col - 2);
+				det.panel.set_visible(det_pos.visible);
+				det.resize(n_row - bottom_rows, det_pos.n_col, det_pos.start_x);
 				// resizing the menus may change which item is selected
 				self.update_details_panel();
 			}
@@ -536,41 +1312,162 @@ impl<'a> Ui<'a>
 				}
 			}
 		}
-		else if det_col > 0
+		else if let Some(det_pos) = det_pos
 		{
-			self.details_panel = Some(DetailsPanel::new(
+			let mut det = DetailsPanel::new(
 				"Details".to_string(),
 				2,
 				self.colors.clone(),
-				n_row - 1,
-				det_col,
-				pod_col + ep_col - 2,
+				n_row - bottom_rows,
+				det_pos.n_col,
+				det_pos.start_x,
 				(0, 1, 0, 1),
-			));
+			);
+			det.panel.set_visible(det_pos.visible);
+			self.details_panel = Some(det);
 			self.update_details_panel();
 		}
 
-		self.popup_win.resize(n_row, n_col);
+		self.popup_win.resize(n_row + 1 - bottom_rows, n_col);
 		self.notif_win.resize(n_row, n_col);
+		if let Some(hint_bar) = &mut self.hint_bar
+		{
+			hint_bar.resize(n_row - 2, n_col);
+		}
 	}
 
-	/// Move the menu cursor around and redraw menus when necessary.
-	pub fn move_cursor(
-		&mut self,
-		action: &UserAction,
-		curr_pod_id: Option<i64>,
-		curr_ep_id: Option<i64>,
-	) {
-		match action
+	/// Switches to the keybindings and colors of a reloaded config file,
+	/// and draws everything again with the new colors. A sequence or
+	/// count being typed is dropped, as the keys may mean something else
+	/// now.
+	pub fn reload_config(&mut self, keymap: Keybindings, colors: AppColors)
+	{
+		self.keymap = Rc::new(keymap);
+		self.colors = Rc::new(colors);
+		self.pending_keys = None;
+		self.count = None;
+		self.show_pending();
+
+		self.podcast_menu.panel.colors = self.colors.clone();
+		self.episode_menu.panel.colors = self.colors.clone();
+		self.queue_menu.panel.colors = self.colors.clone();
+		self.update_queue_message();
+		if let Some(det) = &mut self.details_panel
 		{
-			UserAction::Down => {
-				self.scroll_current_window(curr_pod_id, Scroll::Down(1));
-			}
+			det.panel.colors = self.colors.clone();
+		}
+		self.popup_win.set_config(self.keymap.clone(), self.colors.clone());
+		self.notif_win.set_colors(self.colors.clone());
+		if let Some(hint_bar) = &mut self.hint_bar
+		{
+			hint_bar.set_colors(self.colors.clone());
+		}
+
+		if self.too_small
+		{
+			return;
+		}
+		self.resize(self.n_col, self.n_row);
+		self.update_hints();
+	}
 
-			UserAction::Up => {
-				self.scroll_current_window(curr_pod_id, Scroll::Up(1));
+	/// Shows the keys for the most useful actions in the focused panel in
+	/// the hint bar, if it is turned on. While a popup window is open,
+	/// only the key to close it is shown.
+	pub fn update_hints(&mut self)
+	{
+		if self.too_small
+		{
+			return;
+		}
+		let actions: &[(UserAction, &str)] = if self.popup_win.is_non_welcome_popup_active()
+		{
+			&[(UserAction::Quit, "close")]
+		}
+		else
+		{
+			match self.active_panel
+			{
+				ActivePanel::PodcastMenu => &[
+					(UserAction::AddFeed, "add feed"),
+					(UserAction::Sync, "sync"),
+					(UserAction::SyncAll, "sync all"),
+					(UserAction::Search, "search"),
+					(UserAction::Remove, "remove"),
+					(UserAction::Quit, "quit"),
+					(UserAction::Help, "help"),
+				],
+				ActivePanel::EpisodeMenu => &[
+					(UserAction::Play, "play"),
+					(UserAction::Download, "download"),
+					(UserAction::MarkPlayed, "played"),
+					(UserAction::Search, "search"),
+					(UserAction::Enqueue, "queue"),
+					(UserAction::Archive, "archive"),
+					(UserAction::Quit, "quit"),
+					(UserAction::Help, "help"),
+				],
+				ActivePanel::DetailsPanel => &[
+					(UserAction::Play, "play"),
+					(UserAction::Download, "download"),
+					(UserAction::OpenLink, "open link"),
+					(UserAction::Quit, "quit"),
+					(UserAction::Help, "help"),
+				],
+				ActivePanel::QueuePanel => &[
+					(UserAction::Play, "play"),
+					(UserAction::BigUp, "move up"),
+					(UserAction::BigDown, "move down"),
+					(UserAction::Remove, "remove"),
+					(UserAction::ShowQueue, "hide queue"),
+					(UserAction::Quit, "quit"),
+					(UserAction::Help, "help"),
+				],
 			}
+		};
+		if let Some(hint_bar) = &mut self.hint_bar
+		{
+			hint_bar.set_hints(&self.keymap, actions);
+		}
+	}
 
+	/// Clears the screen and shows a message in the middle of it, asking
+	/// for a bigger terminal. This is drawn again whenever something else
+	/// may have drawn over it.
+	fn draw_too_small(&self)
+	{
+		let (n_col, n_row) = terminal::size().unwrap_or((0, 0));
+		let msg = format!(
+			"Terminal too small (min {}x{})",
+			crate::config::MIN_TERMINAL_COLS,
+			crate::config::MIN_TERMINAL_ROWS
+		);
+		let msg = msg.substr(0, n_col as usize);
+		let x = (n_col as usize).saturating_sub(msg.grapheme_len()) / 2;
+		execute!(
+			io::stdout(),
+			terminal::Clear(terminal::ClearType::All),
+			cursor::MoveTo(x as u16, n_row / 2),
+			style::Print(msg),
+		)
+		.unwrap();
+	}
+
+	/// Move the menu cursor around and redraw menus when necessary. If a
+	/// count was typed before the movement key, the movement is repeated
+	/// that many times, except that going to the top or the bottom goes
+	/// to the item with that number instead, e.g., "5G" goes to the
+	/// fifth item.
+	pub fn move_cursor(
+		&mut self,
+		action: &UserAction,
+		count: Option<usize>,
+		curr_pod_id: Option<i64>,
+		curr_ep_id: Option<i64>,
+	) {
+		let before = self.active_panel;
+		match action
+		{
 			UserAction::Left => {
 				if curr_pod_id.is_some()
 				{
@@ -581,11 +1478,16 @@ impl<'a> Ui<'a>
 							self.active_panel = ActivePanel::PodcastMenu;
 							self.podcast_menu.activate();
 							self.episode_menu.deactivate(false);
+							self.update_details_panel();
 						}
 						ActivePanel::DetailsPanel => {
 							self.active_panel = ActivePanel::EpisodeMenu;
 							self.episode_menu.activate();
 						}
+						ActivePanel::QueuePanel => {
+							self.queue_menu.deactivate();
+							self.focus_menus();
+						}
 					}
 				}
 			}
@@ -599,59 +1501,602 @@ impl<'a> Ui<'a>
 							self.active_panel = ActivePanel::EpisodeMenu;
 							self.podcast_menu.deactivate();
 							self.episode_menu.activate();
+							self.update_details_panel();
 						}
 						ActivePanel::EpisodeMenu => {
-							if self.details_panel.is_some()
+							if self.show_queue
+							{
+								self.active_panel = ActivePanel::QueuePanel;
+								self.episode_menu.deactivate(true);
+								self.queue_menu.activate();
+							}
+							else if self.details_panel.is_some()
 							{
 								self.active_panel = ActivePanel::DetailsPanel;
 								self.episode_menu.deactivate(true);
 							}
 						}
-						ActivePanel::DetailsPanel => (),
+						ActivePanel::DetailsPanel | ActivePanel::QueuePanel => (),
 					}
 				}
 			}
 
-			UserAction::PageUp => {
-				self.scroll_current_window(
-					curr_pod_id,
-					Scroll::Up(self.n_row - 3)
-				);
+			// Up, Down, BigUp, BigDown, PageUp, PageDown, GoTop and
+			// GoBot scroll the active panel, by a number of rows that
+			// depends on the height of that panel
+			_ => match (action, count)
+			{
+				(UserAction::GoTop | UserAction::GoBot, Some(count)) => {
+					self.scroll_current_window(curr_pod_id, Scroll::Up(u16::MAX));
+					self.scroll_current_window(
+						curr_pod_id,
+						Scroll::Down(1).repeat(count - 1)
+					);
+				}
+				_ => {
+					if let Some(scroll) = Scroll::from_action(*action, self.page_rows())
+					{
+						let scroll = scroll.repeat(count.unwrap_or(1));
+						self.scroll_current_window(curr_pod_id, scroll);
+					}
+				}
+			},
+		}
+
+		// in the zoomed layout, moving the focus shows another panel
+		if self.active_panel != before
+			&& Self::fitting_layout(self.n_col, self.layout, self.show_queue) == Layout::Zoomed
+		{
+			self.resize(self.n_col, self.n_row);
+		}
+	}
+
+	/// Handles the digits of a count typed before a command, e.g., the
+	/// "15" in "15j". A digit that is bound to an action performs that
+	/// action instead, unless a count has already been started. Esc
+	/// clears the count. Returns true if the input was used up here.
+	fn count_input(&mut self, input: KeyEvent, bound: bool) -> bool
+	{
+		if self.count.is_some()
+			&& matches!(input.code, KeyCode::Esc | KeyCode::Char('\u{1b}'))
+		{
+			self.set_count(None);
+			return true;
+		}
+		if let Some(digit) = count_digit(input, bound, self.count)
+		{
+			self.set_count(push_digit(self.count, digit));
+			return true;
+		}
+		return false;
+	}
+
+	/// Handles a key typed to jump to an item by the first letters of its
+	/// title. Letters that are not bound to an action jump right away
+	/// (unless `jump_by_letter` is turned off); after the key for
+	/// `UserAction::JumpTo`, any letter or digit does, until another key
+	/// is pressed or no letter is typed for a while. Letters typed in
+	/// quick succession make up a longer prefix, while typing the same
+	/// letter again moves on to the next match. Returns true if the key
+	/// was used up.
+	fn jump_input(&mut self, input: KeyEvent, bound: bool, curr_pod_id: Option<i64>) -> bool
+	{
+		let now = Instant::now();
+		if self.jump_mode_until.is_some_and(|until| now > until)
+		{
+			self.jump_mode_until = None;
+		}
+		let c = match input.code
+		{
+			KeyCode::Char(c) if c.is_alphanumeric()
+				&& !input.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => c,
+			_ => {
+				self.jump_mode_until = None;
+				return false;
 			}
+		};
+		if self.jump_mode_until.is_none() && (bound || !self.jump_by_letter || !c.is_alphabetic())
+		{
+			return false;
+		}
+		if matches!(self.active_panel, ActivePanel::DetailsPanel | ActivePanel::QueuePanel)
+		{
+			return false;
+		}
 
-			UserAction::PageDown => {
-				self.scroll_current_window(
-					curr_pod_id,
-					Scroll::Down(self.n_row - 3)
-				);
+		if self.jump_mode_until.is_some()
+		{
+			self.jump_mode_until = Some(now + Duration::from_millis(JUMP_TIME));
+		}
+		let typed: String = c.to_lowercase().collect();
+		let (prefix, skip_selected) = match self.jump_prefix.take()
+		{
+			Some((prefix, time)) if now - time <= Duration::from_millis(JUMP_TIME) => {
+				if prefix == typed
+				{
+					(prefix, true)
+				}
+				else
+				{
+					(prefix + &typed, false)
+				}
 			}
+			_ => (typed, true),
+		};
+		let (found, current) = match self.active_panel
+		{
+			ActivePanel::PodcastMenu => (
+				self.podcast_menu.find_prefix(&prefix, skip_selected, |pod| pod.title.clone()),
+				self.podcast_menu.get_menu_idx(self.podcast_menu.selected),
+			),
+			_ => (
+				self.episode_menu.find_prefix(&prefix, skip_selected, |ep| ep.title.clone()),
+				self.episode_menu.get_menu_idx(self.episode_menu.selected),
+			),
+		};
+		self.jump_prefix = Some((prefix, now));
 
-			UserAction::BigUp => {
-				self.scroll_current_window(
-					curr_pod_id,
-					Scroll::Up(self.n_row / crate::config::BIG_SCROLL_AMOUNT),
-				);
+		if let Some(index) = found
+		{
+			if index > current
+			{
+				self.scroll_current_window(curr_pod_id, Scroll::Down((index - current) as u16));
+			}
+			else if index < current
+			{
+				self.scroll_current_window(curr_pod_id, Scroll::Up((current - index) as u16));
 			}
+		}
+		return true;
+	}
 
-			UserAction::BigDown => {
-				self.scroll_current_window(
-					curr_pod_id,
-					Scroll::Down(self.n_row / crate::config::BIG_SCROLL_AMOUNT),
-				);
+	/// Handles a key typed after the first keys of a sequence, e.g., the
+	/// second "g" of "g g". Returns the action once the sequence is
+	/// complete, or None while it goes on, and if it turns out not to be
+	/// bound to anything. Esc drops the keys typed so far.
+	fn sequence_input(&mut self, input: KeyEvent) -> Option<UserAction>
+	{
+		let (mut keys, _) = self.pending_keys.take()?;
+		let found = match keymap::input_to_str(input)
+		{
+			Some(key) if key != "Esc" => {
+				keys.push(key);
+				self.keymap.get_from_keys(&keys)
+			}
+			_ => KeyMatch::Unbound,
+		};
+		let action = match found
+		{
+			KeyMatch::Action(action) => Some(action),
+			KeyMatch::Prefix => {
+				self.pending_keys = Some((keys, Instant::now()));
+				None
+			}
+			KeyMatch::Unbound => None,
+		};
+		self.show_pending();
+		return action;
+	}
+
+	/// Looks up a key, along with the keys typed before it if it goes on
+	/// with a sequence, for the windows where counts and jumping to a
+	/// title do not apply. While the sequence is not complete, the keys
+	/// are kept and `KeyMatch::Prefix` is returned.
+	fn key_match(&mut self, input: KeyEvent) -> KeyMatch
+	{
+		if self.pending_keys.is_some()
+		{
+			return match self.sequence_input(input)
+			{
+				Some(action) => KeyMatch::Action(action),
+				None if self.pending_keys.is_some() => KeyMatch::Prefix,
+				None => KeyMatch::Unbound,
+			};
+		}
+		let key = match keymap::input_to_str(input)
+		{
+			Some(key) => key,
+			None => return KeyMatch::Unbound,
+		};
+		let key_match = self.keymap.get_from_keys(std::slice::from_ref(&key));
+		if key_match == KeyMatch::Prefix
+		{
+			self.pending_keys = Some((vec![key], Instant::now()));
+			self.show_pending();
+		}
+		return key_match;
+	}
+
+	/// Sets the pending count, and shows it in the message bar.
+	fn set_count(&mut self, count: Option<usize>)
+	{
+		self.count = count;
+		self.show_pending();
+	}
+
+	/// Shows the pending count and keys of a sequence, if any, in the
+	/// message bar.
+	fn show_pending(&mut self)
+	{
+		let count = self.count.map(|count| count.to_string());
+		let keys = self.pending_keys.as_ref().map(|(keys, _)| keys.join(" "));
+		let pending = match (count, keys)
+		{
+			(Some(count), Some(keys)) => Some(format!("{count} {keys}")),
+			(count, keys) => count.or(keys),
+		};
+		self.notif_win.set_pending(pending);
+	}
+
+	/// Builds the message for an action on the marked episodes, and
+	/// clears the marks. Returns None if no episodes are marked or the
+	/// action does not apply to several episodes at once, in which case
+	/// the action is handled as usual.
+	fn marked_action(&mut self, action: &UserAction, curr_pod_id: Option<i64>) -> Option<UiMsg>
+	{
+		if let ActivePanel::PodcastMenu = self.active_panel
+		{
+			return None;
+		}
+		let pod_id = curr_pod_id?;
+		let ids = self.episode_menu.marked_items();
+		if ids.is_empty()
+		{
+			return None;
+		}
+		let any = |f: fn(&Episode) -> bool| {
+			ids.iter().any(|id| self.episode_menu.items.map_single(*id, f).unwrap_or(false))
+		};
+		let ui_msg = match action
+		{
+			UserAction::Download => {
+				let sizes = ids
+					.iter()
+					.filter_map(|id| {
+						self.episode_menu.items.map_single(*id, |ep| {
+							(ep.path.is_none() && !ep.downloading).then_some(ep.enclosure_size)
+						})
+					})
+					.flatten()
+					.collect();
+				if !self.confirm_downloads(sizes)
+				{
+					// the marks are kept, so that the user can change them
+					return Some(UiMsg::Noop);
+				}
+				UiMsg::DownloadMulti(ids.iter().map(|id| (pod_id, *id)).collect())
+			}
+			UserAction::MarkPlayed => {
+				// as with marking all as played, only mark them as unplayed
+				// if they are all played already
+				let played = any(|ep| !ep.is_played());
+				UiMsg::MarkPlayedMulti(pod_id, ids, played)
+			}
+			UserAction::Archive => {
+				let archived = any(|ep| !ep.archived);
+				UiMsg::ArchiveMulti(pod_id, ids, archived)
+			}
+			UserAction::Delete => UiMsg::DeleteMulti(pod_id, ids),
+			_ => return None,
+		};
+		self.clear_marks();
+		return Some(ui_msg);
+	}
+
+	/// Asks the user to confirm downloading several episodes, given the
+	/// sizes of the episodes that are not downloaded yet, as far as the
+	/// feed gives them. Nothing is asked if there are no such episodes.
+	fn confirm_downloads(&self, sizes: Vec<Option<u64>>) -> bool
+	{
+		if sizes.is_empty()
+		{
+			return true;
+		}
+		return self.ask_for_confirmation(&format!("Download {}?", download_summary(&sizes)));
+	}
+
+	/// Removes the marks from all episodes.
+	fn clear_marks(&mut self)
+	{
+		if !self.episode_menu.marked.is_empty()
+		{
+			self.episode_menu.marked.clear();
+			self.episode_menu.update_items();
+			self.highlight_items();
+		}
+	}
+
+	/// Returns the IDs of up to `count` episodes that are not downloaded
+	/// yet, going down the episode menu from the selected episode.
+	fn next_episodes(&self, count: usize) -> Vec<(i64, i64)>
+	{
+		let start = self.episode_menu.get_menu_idx(self.episode_menu.selected);
+		let ids: Vec<i64> = self
+			.episode_menu
+			.items
+			.borrow_filtered_order()
+			.iter()
+			.skip(start)
+			.copied()
+			.collect();
+		let map = self.episode_menu.items.borrow_map();
+		return ids
+			.iter()
+			.filter_map(|id| map.get(id))
+			.filter(|ep| ep.path.is_none() && !ep.downloading)
+			.take(count)
+			.map(|ep| (ep.pod_id, ep.id))
+			.collect();
+	}
+
+	/// Shows or hides the queue panel. Showing it moves the focus there,
+	/// while hiding it moves the focus back to the episodes if it was in
+	/// the queue panel.
+	fn toggle_queue(&mut self)
+	{
+		self.show_queue = !self.show_queue;
+		if self.show_queue
+		{
+			match self.active_panel
+			{
+				ActivePanel::PodcastMenu => self.podcast_menu.deactivate(),
+				ActivePanel::EpisodeMenu | ActivePanel::DetailsPanel => {
+					self.episode_menu.deactivate(true)
+				}
+				ActivePanel::QueuePanel => (),
+			}
+			self.active_panel = ActivePanel::QueuePanel;
+			self.queue_menu.active = true;
+		}
+		else if self.active_panel == ActivePanel::QueuePanel
+		{
+			self.queue_menu.active = false;
+			self.focus_menus();
+		}
+		self.resize(self.n_col, self.n_row);
+	}
+
+	/// Moves the focus to the episode menu, or to the podcast menu if
+	/// there are no episodes to show, e.g., when leaving the queue panel.
+	fn focus_menus(&mut self)
+	{
+		if self.get_current_ids().1.is_some()
+		{
+			self.active_panel = ActivePanel::EpisodeMenu;
+			self.episode_menu.activate();
+		}
+		else
+		{
+			self.active_panel = ActivePanel::PodcastMenu;
+			self.podcast_menu.activate();
+		}
+	}
+
+	/// Handles the actions that apply to the selected episode of the
+	/// queue panel while it has the focus: playing it, moving it up or
+	/// down the queue with the keys for `BigUp` and `BigDown`, and taking
+	/// it out of the queue. Returns None for other actions, which are
+	/// handled as in the other panels.
+	fn queue_action(&mut self, action: UserAction) -> Option<UiMsg>
+	{
+		if !matches!(
+			action,
+			UserAction::Play | UserAction::BigUp | UserAction::BigDown | UserAction::Remove
+		)
+		{
+			return None;
+		}
+		let mut items = self.queue_menu.items.map(|item| item.clone(), false);
+		let index = self.queue_menu.get_menu_idx(self.queue_menu.selected);
+		if index >= items.len()
+		{
+			return Some(UiMsg::Noop);
+		}
+		match action
+		{
+			UserAction::Play => {
+				return Some(UiMsg::Play(items[index].pod_id, items[index].id));
+			}
+			UserAction::BigUp if index > 0 => {
+				items.swap(index - 1, index);
+				self.queue_menu.items.replace_all(items.clone());
+				self.queue_menu.update_items();
+				self.queue_menu.scroll(Scroll::Up(1));
+			}
+			UserAction::BigDown if index + 1 < items.len() => {
+				items.swap(index, index + 1);
+				self.queue_menu.items.replace_all(items.clone());
+				self.queue_menu.update_items();
+				self.queue_menu.scroll(Scroll::Down(1));
+			}
+			UserAction::Remove => {
+				items.remove(index);
+				self.queue_menu.items.replace_all(items.clone());
+				self.queue_menu.redraw();
+				self.queue_menu.highlight_selected();
+			}
+			_ => return Some(UiMsg::Noop),
+		}
+		return Some(UiMsg::SetQueue(items.iter().map(|item| item.id).collect()));
+	}
+
+	/// Replaces the episodes in the queue panel, e.g., after an episode
+	/// was added to the queue or listened to, and draws the panel again
+	/// if it is shown.
+	pub fn update_queue(&mut self, items: Vec<QueueItem>)
+	{
+		self.queue_menu.items.replace_all(items);
+		if self.show_queue && !self.too_small
+		{
+			self.queue_menu.redraw();
+			if self.queue_menu.active
+			{
+				self.queue_menu.highlight_selected();
 			}
+		}
+	}
+
+	/// Sets the message shown in the queue panel while the queue is
+	/// empty. The panel must be redrawn for the change to show.
+	fn update_queue_message(&mut self)
+	{
+		let message = match self.keymap.key_for_action(UserAction::Enqueue)
+		{
+			Some(key) => format!("The queue is empty — press '{key}' to add the selected episode."),
+			None => "The queue is empty.".to_string(),
+		};
+		self.queue_menu.empty_message = Some(message);
+	}
+
+	/// Returns the number of rows shown at once in the active panel, or
+	/// the number of items for menus, whose items may take up two rows.
+	fn page_rows(&self) -> u16
+	{
+		return match self.active_panel
+		{
+			ActivePanel::PodcastMenu => self.podcast_menu.visible_items(),
+			ActivePanel::EpisodeMenu => self.episode_menu.visible_items(),
+			ActivePanel::DetailsPanel => match &self.details_panel
+			{
+				Some(det) => det.get_rows(),
+				None => self.episode_menu.page_rows(),
+			},
+			ActivePanel::QueuePanel => self.queue_menu.visible_items(),
+		};
+	}
+
+	/// Handles mouse input: clicking on an item selects it, scrolling the
+	/// mouse wheel scrolls the panel under the cursor, and double-clicking
+	/// on an episode plays it, like the "play" key. Clicking on or
+	/// scrolling a panel also moves the focus to that panel.
+	fn mouse_input(&mut self, mouse: MouseEvent) -> UiMsg
+	{
+		// popup windows and the search prompt only take keyboard input
+		if self.popup_win.is_popup_active() || self.search_prompt.is_some()
+		{
+			return UiMsg::Noop;
+		}
+		let (panel, row) = match self.panel_at(mouse.column, mouse.row)
+		{
+			Some(found) => found,
+			None => return UiMsg::Noop,
+		};
+		let scroll = match mouse.kind
+		{
+			MouseEventKind::Down(MouseButton::Left) => None,
+			MouseEventKind::ScrollUp => Some(Scroll::Up(1)),
+			MouseEventKind::ScrollDown => Some(Scroll::Down(1)),
+			_ => return UiMsg::Noop,
+		};
+
+		self.activate_panel(panel);
+		if self.active_panel != panel
+		{
+			return UiMsg::Noop;
+		}
+		let curr_pod_id = self.get_current_ids().0;
+		if let Some(scroll) = scroll
+		{
+			self.scroll_current_window(curr_pod_id, scroll);
+			return UiMsg::Noop;
+		}
+
+		// move the selection to the item that was clicked on, if any
+		let (clicked, selected) = match panel
+		{
+			ActivePanel::PodcastMenu => (
+				self.podcast_menu.item_at_row(row),
+				self.podcast_menu.selected,
+			),
+			ActivePanel::EpisodeMenu => (
+				self.episode_menu.item_at_row(row),
+				self.episode_menu.selected,
+			),
+			ActivePanel::QueuePanel => (
+				self.queue_menu.item_at_row(row),
+				self.queue_menu.selected,
+			),
+			ActivePanel::DetailsPanel => return UiMsg::Noop,
+		};
+		let clicked = match clicked
+		{
+			Some(clicked) => clicked,
+			None => return UiMsg::Noop,
+		};
+		if clicked > selected
+		{
+			self.scroll_current_window(curr_pod_id, Scroll::Down(clicked - selected));
+		}
+		else if clicked < selected
+		{
+			self.scroll_current_window(curr_pod_id, Scroll::Up(selected - clicked));
+		}
 
-			UserAction::GoTop => {
-				self.scroll_current_window(curr_pod_id, Scroll::Up(u16::MAX));
+		if let (ActivePanel::EpisodeMenu, (Some(pod_id), Some(ep_id))) =
+			(panel, self.get_current_ids())
+		{
+			let now = Instant::now();
+			let double_click = matches!(
+				self.last_click,
+				Some((time, id)) if id == ep_id
+					&& now - time <= Duration::from_millis(DOUBLE_CLICK_TIME)
+			);
+			if double_click
+			{
+				self.last_click = None;
+				return UiMsg::Play(pod_id, ep_id);
 			}
+			self.last_click = Some((now, ep_id));
+		}
+		return UiMsg::Noop;
+	}
 
-			UserAction::GoBot => {
-				self.scroll_current_window(curr_pod_id, Scroll::Down(u16::MAX));
+	/// Returns the panel at the given position on the screen, if any,
+	/// along with the row of that position relative to the panel. Borders
+	/// and margins do not belong to any panel.
+	fn panel_at(&self, x: u16, y: u16) -> Option<(ActivePanel, u16)>
+	{
+		if let Some((_, row)) = self.podcast_menu.panel.rel_coords(x, y)
+		{
+			return Some((ActivePanel::PodcastMenu, row));
+		}
+		if let Some((_, row)) = self.episode_menu.panel.rel_coords(x, y)
+		{
+			return Some((ActivePanel::EpisodeMenu, row));
+		}
+		if let Some(det) = &self.details_panel
+		{
+			if let Some((_, row)) = det.panel.rel_coords(x, y)
+			{
+				return Some((ActivePanel::DetailsPanel, row));
 			}
+		}
+		if let Some((_, row)) = self.queue_menu.panel.rel_coords(x, y)
+		{
+			return Some((ActivePanel::QueuePanel, row));
+		}
+		return None;
+	}
 
-			// this shouldn't occur because we only trigger this
-			// function when the UserAction is Up, Down, Left, Right,
-			// BigUp, BigDown, PageUp, PageDown, GoBot and GoTop
-			_ => (),
+	/// Moves the focus to the given panel, through the panels in between,
+	/// in the same way as moving left or right with the keyboard. The
+	/// focus may not move all the way, e.g., if there are no episodes.
+	fn activate_panel(&mut self, panel: ActivePanel)
+	{
+		while self.active_panel != panel
+		{
+			let before = self.active_panel;
+			let action = if (before as u8) < (panel as u8) {
+				UserAction::Right
+			} else {
+				UserAction::Left
+			};
+			let (curr_pod_id, curr_ep_id) = self.get_current_ids();
+			self.move_cursor(&action, None, curr_pod_id, curr_ep_id);
+			if self.active_panel == before
+			{
+				break;
+			}
 		}
 	}
 
@@ -665,12 +2110,16 @@ impl<'a> Ui<'a>
 				if pod_id.is_some()
 				{
 					self.podcast_menu.scroll(scroll);
+					self.selected_pod_id = self.get_current_ids().0;
 
 					self.episode_menu.top_row = 0;
 					self.episode_menu.selected = 0;
+					self.episode_menu.marked.clear();
 
 					// update episodes menu with new list
 					self.episode_menu.items = self.podcast_menu.get_episodes();
+					self.update_episode_title();
+					self.update_episode_message();
 					self.episode_menu.redraw();
 					self.update_details_panel();
 				}
@@ -688,6 +2137,157 @@ impl<'a> Ui<'a>
 					det.scroll(scroll);
 				}
 			}
+			ActivePanel::QueuePanel => self.queue_menu.scroll(scroll),
+		}
+	}
+
+	/// Opens the search prompt at the bottom of the active menu, starting
+	/// from the text that the menu is already searched for, if any.
+	fn spawn_search_prompt(&mut self, curr_pod_id: Option<i64>)
+	{
+		let episodes = match self.active_panel
+		{
+			ActivePanel::PodcastMenu => false,
+			ActivePanel::EpisodeMenu => true,
+			ActivePanel::DetailsPanel | ActivePanel::QueuePanel => return,
+		};
+		let available = if episodes {
+			curr_pod_id.is_some()
+		} else {
+			!self.podcast_menu.items.is_empty()
+		};
+		if !available
+		{
+			return;
+		}
+
+		let menu_search = if episodes {
+			self.episode_menu.search.clone()
+		} else {
+			self.podcast_menu.search.clone()
+		};
+		self.search_prompt = Some(SearchPrompt {
+			text: menu_search.clone().unwrap_or_default(),
+			episodes: episodes,
+			positions: self.menu_positions(),
+		});
+		if episodes
+		{
+			self.episode_menu.set_search(menu_search, true);
+			self.episode_menu.redraw();
+		}
+		else
+		{
+			self.podcast_menu.set_search(menu_search, true);
+			self.podcast_menu.redraw();
+		}
+		self.highlight_items();
+	}
+
+	/// Handles user input while the search prompt is open. Every change
+	/// to the search text is sent to the main controller so that the
+	/// menu narrows as the user types, starting again from the top of
+	/// the list. Enter closes the prompt and keeps the search, while Esc
+	/// clears the search and returns to the positions from before the
+	/// prompt was opened.
+	fn search_input(&mut self, input: KeyEvent) -> UiMsg
+	{
+		let mut prompt = self.search_prompt.take().unwrap();
+		match input.code
+		{
+			KeyCode::Esc | KeyCode::Char('\u{1b}') => {
+				self.restore_positions = Some(prompt.positions);
+				return self.set_search(prompt.episodes, None, false);
+			}
+			KeyCode::Enter | KeyCode::Char('\n') => {
+				let search = Some(prompt.text).filter(|text| !text.is_empty());
+				if prompt.episodes
+				{
+					self.episode_menu.set_search(search, false);
+					self.episode_menu.redraw();
+				}
+				else
+				{
+					self.podcast_menu.set_search(search, false);
+					self.podcast_menu.redraw();
+				}
+				self.highlight_items();
+				return UiMsg::Noop;
+			}
+			KeyCode::Backspace | KeyCode::Char('\u{7f}') => {
+				prompt.text.pop();
+			}
+			KeyCode::Char(c) if !input.modifiers.intersects(KeyModifiers::CONTROL) => {
+				prompt.text.push(c);
+			}
+			_ => {
+				self.search_prompt = Some(prompt);
+				return UiMsg::Noop;
+			}
+		}
+		return self.search_changed(prompt);
+	}
+
+	/// Adds pasted text to the end of the search, leaving out line breaks
+	/// and other control characters.
+	fn search_paste(&mut self, text: &str) -> UiMsg
+	{
+		let mut prompt = self.search_prompt.take().unwrap();
+		prompt.text.extend(text.chars().filter(|c| !c.is_control()));
+		return self.search_changed(prompt);
+	}
+
+	/// Applies the search text after it was changed in the search prompt.
+	fn search_changed(&mut self, prompt: SearchPrompt) -> UiMsg
+	{
+		// the list changes, so start again from the top
+		self.episode_menu.top_row = 0;
+		self.episode_menu.selected = 0;
+		if !prompt.episodes
+		{
+			self.podcast_menu.top_row = 0;
+			self.podcast_menu.selected = 0;
+			self.selected_pod_id = None;
+		}
+		let search = Some(prompt.text.clone()).filter(|text| !text.is_empty());
+		let episodes = prompt.episodes;
+		self.search_prompt = Some(prompt);
+		return self.set_search(episodes, search, true);
+	}
+
+	/// Returns the current positions of the podcast and episode menus,
+	/// so that the same items can be selected again once the lists have
+	/// been updated; see `update_menus()`.
+	fn menu_positions(&self) -> [MenuPosition; 2]
+	{
+		let (curr_pod_id, curr_ep_id) = self.get_current_ids();
+		return [
+			MenuPosition {
+				top_row: self.podcast_menu.top_row,
+				selected: self.podcast_menu.selected,
+				id: curr_pod_id,
+			},
+			MenuPosition {
+				top_row: self.episode_menu.top_row,
+				selected: self.episode_menu.selected,
+				id: curr_ep_id,
+			},
+		];
+	}
+
+	/// Sets the search text of the podcast or episode menu, and returns
+	/// the message asking the main controller to apply it.
+	fn set_search(&mut self, episodes: bool, search: Option<String>, editing: bool) -> UiMsg
+	{
+		if episodes
+		{
+			self.episode_menu.set_search(search.clone(), editing);
+			return UiMsg::SearchEpisodes(search);
+		}
+		else
+		{
+			self.podcast_menu.set_search(search.clone(), editing);
+			return UiMsg::SearchPodcasts(search);
 		}
 	}
 
@@ -712,24 +2312,150 @@ impl<'a> Ui<'a>
 				}
 			}
 		}
-		return None;
+		return None;
+	}
+
+	/// Mark all episodes for a given podcast as played or unplayed. If
+	/// there are any unplayed episodes, this will convert all episodes
+	/// to played; if all are played already, only then will it convert
+	/// all to unplayed. The user is asked to confirm first.
+	pub fn mark_all_played(&mut self, curr_pod_id: Option<i64>) -> Option<UiMsg>
+	{
+		if let Some(pod_id) = curr_pod_id
+		{
+			if let Some((played, title, count)) = self
+				.podcast_menu
+				.items
+				.map_single(pod_id, |pod| {
+					let played = pod.is_played();
+					let count = if played
+					{
+						pod.episodes.len(false)
+					}
+					else
+					{
+						pod.num_unplayed()
+					};
+					(played, pod.title.clone(), count)
+				})
+			{
+				if count == 0
+				{
+					return None;
+				}
+				let plural = if count > 1 { "s" } else { "" };
+				let status = if played { "unplayed" } else { "played" };
+				if self.ask_for_confirmation(&format!(
+					"Mark {count} episode{plural} of {title} as {status}?"
+				)) {
+					return Some(UiMsg::MarkAllPlayed(pod_id, !played));
+				}
+			}
+		}
+		return None;
+	}
+
+	/// Copies the URL of the selected episode to the clipboard, or the
+	/// feed URL if the podcast menu is active.
+	fn copy_url(&mut self, curr_pod_id: Option<i64>, curr_ep_id: Option<i64>)
+	{
+		let url = match self.active_panel
+		{
+			ActivePanel::PodcastMenu => curr_pod_id.and_then(|pod_id| {
+				self.podcast_menu.items.map_single(pod_id, |pod| pod.url.clone())
+			}),
+			_ => curr_ep_id.and_then(|ep_id| {
+				self.episode_menu.items.map_single(ep_id, |ep| ep.url.clone())
+			}),
+		};
+		match url
+		{
+			Some(url) if !url.is_empty() => {
+				self.copy_to_clipboard(url.as_bytes(), format!("Copied URL: {url}"))
+			}
+			Some(_) => self.notif_win.timed_notif(
+				"This episode has no URL to copy.".to_string(),
+				crate::config::MESSAGE_TIME,
+				false,
+			),
+			None => (),
+		}
+	}
+
+	/// Copies the path of the downloaded file of the selected episode to
+	/// the clipboard.
+	fn copy_path(&mut self, curr_ep_id: Option<i64>)
+	{
+		let path = match curr_ep_id
+		{
+			Some(ep_id) => self.episode_menu.items.map_single(ep_id, |ep| ep.path.clone()),
+			None => return,
+		};
+		match path.flatten()
+		{
+			Some(path) => self.copy_to_clipboard(
+				&clipboard::path_bytes(&path),
+				format!("Copied path: {}", path.to_string_lossy()),
+			),
+			None => self.notif_win.timed_notif(
+				"This episode has not been downloaded.".to_string(),
+				crate::config::MESSAGE_TIME,
+				false,
+			),
+		}
+	}
+
+	/// Puts `data` on the clipboard and shows `message` if that worked.
+	fn copy_to_clipboard(&mut self, data: &[u8], message: String)
+	{
+		match clipboard::copy(data, self.clipboard_command)
+		{
+			Ok(_) => self.notif_win.timed_notif(message, crate::config::MESSAGE_TIME, false),
+			Err(_) => self.notif_win.timed_notif(
+				"Error: Could not copy to clipboard. Check configuration.".to_string(),
+				crate::config::MESSAGE_TIME,
+				true,
+			),
+		}
 	}
 
-	/// Mark all episodes for a given podcast as played or unplayed. If
-	/// there are any unplayed episodes, this will convert all episodes
-	/// to played; if all are played already, only then will it convert
-	/// all to unplayed.
-	pub fn mark_all_played(&mut self, curr_pod_id: Option<i64>) -> Option<UiMsg>
+	/// Archive all episodes of the current podcast that were published
+	/// before the selected episode. The user is asked to confirm first.
+	fn archive_older(
+		&mut self,
+		curr_pod_id: Option<i64>,
+		curr_ep_id: Option<i64>,
+	) -> Option<UiMsg>
 	{
-		if let Some(pod_id) = curr_pod_id
+		let (pod_id, ep_id) = (curr_pod_id?, curr_ep_id?);
+		let pubdate = self
+			.episode_menu
+			.items
+			.map_single(ep_id, |ep| ep.pubdate)
+			.flatten()?;
+		let count = self
+			.episode_menu
+			.items
+			.map(|ep| {
+				!ep.archived && ep.pubdate.is_some_and(|pd| pd < pubdate)
+			}, false)
+			.into_iter()
+			.filter(|older| *older)
+			.count();
+		if count == 0
 		{
-			if let Some(played) = self
-				.podcast_menu
-				.items
-				.map_single(pod_id, |pod| pod.is_played())
-			{
-				return Some(UiMsg::MarkAllPlayed(pod_id, !played));
-			}
+			self.notif_win.timed_notif(
+				"No older episodes to archive".to_string(),
+				crate::config::MESSAGE_TIME,
+				false
+			);
+			return None;
+		}
+		let plural = if count > 1 { "s" } else { "" };
+		if self.ask_for_confirmation(&format!(
+			"Archive {count} episode{plural} older than this one?"
+		)) {
+			return Some(UiMsg::ArchiveOlder(pod_id, ep_id));
 		}
 		return None;
 	}
@@ -852,28 +2578,98 @@ impl<'a> Ui<'a>
 		return (current_pod_id, current_ep_id);
 	}
 
-	/// Calculates the number of columns to allocate for each of the
-	/// main panels: podcast menu, episodes menu, and details panel; if
-	/// the screen is too small to display the details panel, this size
-	/// will be 0
-	pub fn calculate_sizes(n_col: u16) -> (u16, u16, u16)
+	/// Returns the layout actually used in a terminal `n_col` columns
+	/// wide: the automatic layout becomes one of the others, and layouts
+	/// with more panels than fit at their minimum width fall back on
+	/// fewer panels. While the `queue` panel is shown, it needs a third
+	/// panel, so the layout has three panels if they fit, and is zoomed
+	/// otherwise.
+	fn fitting_layout(n_col: u16, layout: Layout, queue: bool) -> Layout
 	{
-		let pod_col;
-		let ep_col;
-		let det_col;
-		if n_col > crate::config::DETAILS_PANEL_LENGTH
+		let min_col = crate::config::MIN_PANEL_WIDTH;
+		let layout = match layout
+		{
+			Layout::Auto | Layout::TwoPane if queue => Layout::ThreePane,
+			Layout::Auto if n_col > crate::config::DETAILS_PANEL_LENGTH => Layout::ThreePane,
+			Layout::Auto => Layout::TwoPane,
+			layout => layout,
+		};
+		// neighbouring panels share a border column
+		if layout == Layout::ThreePane && n_col + 2 < 3 * min_col
 		{
-			pod_col = (n_col + 2) / 3;
-			ep_col = (n_col + 2) / 3;
-			det_col = n_col + 2 - pod_col - ep_col;
+			if queue
+			{
+				return Layout::Zoomed;
+			}
+			return Self::fitting_layout(n_col, Layout::TwoPane, false);
 		}
-		else
+		if layout == Layout::TwoPane && n_col + 1 < 2 * min_col
+		{
+			return Layout::Zoomed;
+		}
+		return layout;
+	}
+
+	/// Calculates the position and width of each of the main panels:
+	/// podcast menu, episodes menu, and details panel (or the queue
+	/// panel, if `queue` is set), which is None if the layout does not
+	/// include it. The podcast menu is `podcast_width` wide if set, but
+	/// no panel is made narrower than `MIN_PANEL_WIDTH`. In the zoomed
+	/// layout, the `active` panel takes the whole width and the others
+	/// are hidden.
+	fn calculate_sizes(
+		n_col: u16,
+		layout: Layout,
+		podcast_width: Option<PanelWidth>,
+		active: ActivePanel,
+		queue: bool,
+	) -> (PanelPos, PanelPos, Option<PanelPos>)
+	{
+		let min_col = crate::config::MIN_PANEL_WIDTH;
+		let shown = |start_x: u16, n_col: u16| PanelPos {
+			start_x: start_x,
+			n_col: n_col,
+			visible: true,
+		};
+		match Self::fitting_layout(n_col, layout, queue)
 		{
-			pod_col = (n_col + 1) / 2;
-			ep_col = n_col + 1 - pod_col;
-			det_col = 0;
+			Layout::ThreePane => {
+				let pod_col = podcast_width
+					.map_or(n_col.div_ceil(3), |width| width.columns(n_col))
+					.clamp(min_col, n_col + 2 - 2 * min_col);
+				let ep_col = (n_col + 2 - pod_col).div_ceil(2);
+				let det_col = n_col + 2 - pod_col - ep_col;
+				return (
+					shown(0, pod_col),
+					shown(pod_col - 1, ep_col),
+					Some(shown(pod_col + ep_col - 2, det_col)),
+				);
+			}
+			Layout::Zoomed => {
+				let active = match active
+				{
+					ActivePanel::QueuePanel => ActivePanel::DetailsPanel,
+					active => active,
+				};
+				let zoomed = |panel: ActivePanel| PanelPos {
+					start_x: 0,
+					n_col: n_col,
+					visible: panel == active,
+				};
+				return (
+					zoomed(ActivePanel::PodcastMenu),
+					zoomed(ActivePanel::EpisodeMenu),
+					Some(zoomed(ActivePanel::DetailsPanel)),
+				);
+			}
+			_ => {
+				let pod_col = podcast_width
+					.map_or(n_col.div_ceil(2), |width| width.columns(n_col))
+					.clamp(min_col, n_col + 1 - min_col);
+				let ep_col = n_col + 1 - pod_col;
+				return (shown(0, pod_col), shown(pod_col - 1, ep_col), None);
+			}
 		}
-		return (pod_col, ep_col, det_col);
 	}
 
 	/// Checks whether the user has downloaded any episodes for the
@@ -908,6 +2704,40 @@ impl<'a> Ui<'a>
 		self.spawn_yes_no_notif(message).unwrap_or(false)
 	}
 
+	/// Asks whether to remove each of the podcasts that were unsubscribed
+	/// from on gpodder.net, but have downloaded episodes here, given
+	/// their IDs and titles. Returns the IDs of those to remove.
+	pub fn confirm_gpodder_removals(&self, podcasts: Vec<(i64, String)>) -> Vec<i64>
+	{
+		return podcasts
+			.into_iter()
+			.filter(|(_, title)| {
+				return self.ask_for_confirmation(&format!(
+					"\"{title}\" was unsubscribed from on another device, but has downloaded episodes. Remove it and its files?"
+				));
+			})
+			.map(|(pod_id, _)| pod_id)
+			.collect();
+	}
+
+	/// Asks what to do with the downloads in progress before quitting:
+	/// wait for them to finish, cancel them, or keep running. Returns
+	/// None to keep running, which is also the answer to anything other
+	/// than "w" or "c".
+	pub fn confirm_quit(&self, downloads: usize) -> Option<Shutdown>
+	{
+		let plural = if downloads > 1 { "s" } else { "" };
+		let input = self.notif_win.input_notif(&format!(
+			"{downloads} download{plural} in progress: (w)ait, (c)ancel, or (k)eep running? "
+		));
+		return match input.trim().chars().next()
+		{
+			Some('w') | Some('W') => Some(Shutdown::Finish),
+			Some('c') | Some('C') => Some(Shutdown::Cancel),
+			_ => None,
+		};
+	}
+
 	/// Adds a notification to the bottom of the screen that solicits
 	/// user text input. A prefix can be specified as a prompt for the
 	/// user at the beginning of the input line. This returns the user's
@@ -917,6 +2747,39 @@ impl<'a> Ui<'a>
 		return self.notif_win.input_notif(prefix);
 	}
 
+	/// Checks whether the user is already subscribed to a feed before
+	/// adding it. If the same URL is already in the list, the user is
+	/// told so; if a URL that differs only slightly is in the list (e.g.,
+	/// http instead of https), the user is asked whether to add the feed
+	/// anyway. Returns whether the feed should be added.
+	fn confirm_new_feed(&mut self, url: &str) -> bool
+	{
+		let normalized = crate::feeds::normalize_url(url);
+		let existing = self.podcast_menu.items.map(
+			|pod| (pod.title.clone(), pod.url.clone()),
+			false
+		);
+		for (title, pod_url) in existing.into_iter()
+		{
+			if pod_url == url.trim()
+			{
+				self.notif_win.timed_notif(
+					format!("Already subscribed to {title}"),
+					crate::config::MESSAGE_TIME,
+					true
+				);
+				return false;
+			}
+			if crate::feeds::normalize_url(&pod_url) == normalized
+			{
+				return self.spawn_yes_no_notif(&format!(
+					"Already subscribed to {title} as {pod_url}. Add anyway?"
+				)) == Some(true);
+			}
+		}
+		return true;
+	}
+
 	/// Adds a notification to the bottom of the screen that solicits
 	/// user for a yes/no input. A prefix can be specified as a prompt
 	/// for the user at the beginning of the input line. "(y/n)" will
@@ -965,19 +2828,200 @@ impl<'a> Ui<'a>
 		self.notif_win.clear_persistent_notif();
 	}
 
+	/// Shows the tag that the podcasts are filtered by, if any, in the
+	/// title of the podcast panel.
+	pub fn set_podcast_filter(&mut self, label: Option<String>)
+	{
+		self.podcast_filter = label;
+		self.update_podcast_title();
+		self.podcast_menu.redraw();
+	}
+
+	/// Keeps track of the podcast whose episodes the user is looking at,
+	/// i.e., while the episode menu or details panel is active. Once the
+	/// user moves away from them, or quits, this returns a message to
+	/// record the time at which they started looking.
+	fn check_viewing(&mut self, quit: bool) -> Option<UiMsg>
+	{
+		let pod_id = match self.active_panel
+		{
+			_ if quit => None,
+			ActivePanel::PodcastMenu => None,
+			_ => self.get_current_ids().0,
+		};
+		if pod_id == self.viewing.map(|(id, _)| id)
+		{
+			return None;
+		}
+		let finished = self.viewing.take();
+		self.viewing = pod_id.map(|id| (id, Utc::now()));
+		return finished.map(|(id, time)| UiMsg::PodcastViewed(id, time));
+	}
+
+	/// Shows which episodes are left by the played and downloaded
+	/// filters in the title of the episode panel.
+	pub fn set_episode_filter(&mut self, label: Option<String>)
+	{
+		self.episode_filter = label;
+		self.update_episode_title();
+		self.episode_menu.redraw();
+	}
+
+	/// Shows the order of the podcasts, and the tag that they are
+	/// filtered by, if any, in the title of the podcast panel. The panel
+	/// must be redrawn for the change to show.
+	fn update_podcast_title(&mut self)
+	{
+		let mut title = format!("Podcasts ({})", self.podcast_sort.label());
+		if let Some(label) = &self.podcast_filter
+		{
+			title += &format!(" [{label}]");
+		}
+		self.podcast_menu.panel.set_title(title);
+	}
+
+	/// Shows the order of the episodes of the selected podcast, and the
+	/// played and downloaded filters, if any, in the title of the episode
+	/// panel. The panel must be redrawn for the change to show.
+	fn update_episode_title(&mut self)
+	{
+		let sort = self
+			.get_current_ids()
+			.0
+			.and_then(|pod_id| {
+				self.podcast_menu.items.map_single(pod_id, |pod| pod.episode_sort)
+			})
+			.unwrap_or_default();
+		let mut title = format!("Episodes ({})", sort.label());
+		if let Some(label) = &self.episode_filter
+		{
+			title += &format!(" [{label}]");
+		}
+		self.episode_menu.panel.set_title(title);
+	}
+
+	/// Sets the message shown in the podcast panel when no podcasts are
+	/// shown, explaining how to add some, or that the filters leave none
+	/// of them. The panel must be redrawn for the change to show.
+	fn update_podcast_message(&mut self)
+	{
+		let message = if self.podcast_menu.items.is_empty()
+		{
+			match self.keymap.key_for_action(UserAction::AddFeed)
+			{
+				Some(key) => format!(
+					"No podcasts yet — press '{key}' to add a feed URL, or run 'shellcaster import' with an OPML file."
+				),
+				None => "No podcasts yet — run 'shellcaster import' with an OPML file.".to_string(),
+			}
+		}
+		else
+		{
+			"No podcasts match the current filters.".to_string()
+		};
+		self.podcast_menu.empty_message = Some(message);
+	}
+
+	/// Sets the message shown in the episode panel when the selected
+	/// podcast has no episodes to show, e.g., because its feed could not
+	/// be synced. The panel must be redrawn for the change to show.
+	fn update_episode_message(&mut self)
+	{
+		let pod_id = match self.get_current_ids().0
+		{
+			Some(pod_id) => pod_id,
+			None => {
+				self.episode_menu.empty_message = None;
+				return;
+			}
+		};
+		let (no_episodes, failed) = self
+			.podcast_menu
+			.items
+			.map_single(pod_id, |pod| (pod.episodes.is_empty(), pod.last_error.is_some()))
+			.unwrap_or((true, false));
+		let sync_key = self.keymap.key_for_action(UserAction::Sync);
+		let message = match (no_episodes, failed, sync_key)
+		{
+			(true, true, Some(key)) => format!("Feed not synced yet — press '{key}' to try again."),
+			(true, true, None) => "Feed not synced yet.".to_string(),
+			(true, false, Some(key)) => format!("No episodes — press '{key}' to sync the feed."),
+			(true, false, None) => "No episodes.".to_string(),
+			(false, _, _) => "No episodes match the current filters.".to_string(),
+		};
+		self.episode_menu.empty_message = Some(message);
+	}
+
 	/// Forces the menus to check the list of podcasts/episodes again and
 	/// update.
 	pub fn update_menus(&mut self)
 	{
+		// e.g., after a search is cancelled, go back to the items that
+		// were selected before
+		let restored = self.restore_positions.take();
+		let mut selection_moved = false;
+		if let Some([pod_pos, _]) = restored
+		{
+			self.podcast_menu.top_row = pod_pos.top_row;
+			self.podcast_menu.selected = pod_pos.selected;
+			if let Some(pod_id) = pod_pos.id
+			{
+				self.podcast_menu.select_id(pod_id);
+			}
+		}
+		else
+		{
+			// the podcasts may have been sorted again, e.g., after a
+			// sync brought in new episodes, or the selected one removed
+			selection_moved = self.podcast_menu.restore_selection();
+		}
+
+		self.update_podcast_message();
 		self.podcast_menu.redraw();
 
-		self.episode_menu.items = if !self.podcast_menu.items.is_empty() {
+		// marks only apply to the episodes of one podcast, and the
+		// episodes of another podcast are shown from the top
+		let same_podcast = self.get_current_ids().0 == self.selected_pod_id;
+		if !same_podcast
+		{
+			self.episode_menu.marked.clear();
+			self.episode_menu.top_row = 0;
+			self.episode_menu.selected = 0;
+			selection_moved = true;
+		}
+		self.episode_menu.items = if self.podcast_menu.items.len(true) > 0 {
 			self.podcast_menu.get_episodes()
 		} else {
 			LockVec::new(Vec::new())
 		};
+		self.update_episode_title();
+		self.update_episode_message();
+		if let Some([_, ep_pos]) = restored
+		{
+			self.episode_menu.top_row = ep_pos.top_row;
+			self.episode_menu.selected = ep_pos.selected;
+			if let Some(ep_id) = ep_pos.id
+			{
+				self.episode_menu.select_id(ep_id);
+			}
+		}
+		else if same_podcast && self.episode_menu.restore_selection()
+		{
+			selection_moved = true;
+		}
 		self.episode_menu.redraw();
 		self.highlight_items();
+
+		// searching or sorting may change which item is selected, and
+		// a sync may change the details of the selected podcast
+		if self.search_prompt.is_some() ||
+			restored.is_some() ||
+			selection_moved ||
+			self.active_panel == ActivePanel::PodcastMenu
+		{
+			self.update_details_panel();
+		}
+		self.selected_pod_id = self.get_current_ids().0;
 	}
 
 	/// Forces the menus to redraw the highlighted item.
@@ -992,50 +3036,108 @@ impl<'a> Ui<'a>
 				self.podcast_menu.highlight_selected();
 				self.episode_menu.highlight_selected();
 			}
-			_ => (),
+			ActivePanel::QueuePanel => self.queue_menu.highlight_selected(),
+			ActivePanel::DetailsPanel => (),
 		}
 	}
 
 	/// When the program is ending, this performs tear-down functions so
 	/// that the terminal is properly restored to its prior settings.
+	/// Runs a player that uses the terminal, e.g., mpv, in the
+	/// foreground, as with `play_file::run_in_terminal()`. The terminal
+	/// is restored for the player, and taken back and drawn again once it
+	/// exits. No input is read in the meantime, so key presses go to the
+	/// player. Returns how the player exited, for the main controller.
+	pub fn run_player(&mut self, playback: play_file::Playback) -> play_file::PlayerExit
+	{
+		restore_terminal();
+		let exit = play_file::run_in_terminal(playback);
+		take_terminal(self.enable_mouse);
+
+		// the terminal may have been resized while the player ran
+		let (n_col, n_row) = terminal::size().unwrap_or((self.n_col, self.n_row));
+		self.resize(n_col, n_row);
+		return exit;
+	}
+
+	/// Runs an editor on the config file in the foreground, giving up the
+	/// terminal for it as for `run_player()`. Returns the error the
+	/// editor failed with, if any.
+	pub fn run_editor(&mut self, args: &[String]) -> Option<String>
+	{
+		restore_terminal();
+		let result = play_file::run_editor(args);
+		take_terminal(self.enable_mouse);
+
+		let (n_col, n_row) = terminal::size().unwrap_or((self.n_col, self.n_row));
+		self.resize(n_col, n_row);
+		return result.err().map(|err| err.to_string());
+	}
+
 	pub fn tear_down(&self)
 	{
-		terminal::disable_raw_mode().unwrap();
-		execute!(
-			io::stdout(),
-			terminal::Clear(terminal::ClearType::All),
-			terminal::LeaveAlternateScreen,
-			cursor::Show
-		)
-		.unwrap();
+		execute!(io::stdout(), terminal::Clear(terminal::ClearType::All)).unwrap();
+		restore_terminal();
 	}
 
 	/// Updates the details panel with information about the current
-	/// podcast and episode, and redraws to the screen.
+	/// podcast and episode, and redraws to the screen. While the
+	/// podcast menu is active, the details describe the podcast itself;
+	/// otherwise, they describe the current episode.
 	pub fn update_details_panel(&mut self)
 	{
-		if self.details_panel.is_some()
+		let (curr_pod_id, curr_ep_id) = self.get_current_ids();
+		if let Some(det) = self.details_panel.as_mut()
 		{
-			let (curr_pod_id, curr_ep_id) = self.get_current_ids();
-			let det = self.details_panel.as_mut().unwrap();
 			if let Some(pod_id) = curr_pod_id
 			{
-				if let Some(ep_id) = curr_ep_id
+				let pod_map = self.podcast_menu.items.borrow_map();
+				let pod = match pod_map.get(&pod_id)
 				{
-					// get a couple details from the current podcast
-					let mut pod_title = None;
-					let mut pod_explicit = None;
-					if let Some(pod) =
-						self.podcast_menu.items.borrow_map().get(&pod_id)
-					{
-						pod_title = if pod.title.is_empty() {
-							None
-						} else {
-							Some(pod.title.clone())
-						};
-						pod_explicit = pod.explicit;
+					Some(pod) => pod,
+					None => return,
+				};
+				let pod_title = if pod.title.is_empty() {
+					None
+				} else {
+					Some(pod.title.clone())
+				};
+
+				if let ActivePanel::PodcastMenu = self.active_panel
+				{
+					let details = Details {
+						is_podcast: true,
+						pod_title: pod_title,
+						ep_title: None,
+						author: pod.author.clone().filter(|a| !a.is_empty()),
+						feed_url: Some(pod.url.clone()),
+						pubdate: None,
+						last_checked: Some(pod.last_checked),
+						last_error: pod.last_error.clone(),
+						last_error_at: pod.last_error_at,
+						inactive: pod.inactive,
+						synced: pod.synced,
+						counts: Some(EpisodeCounts {
+							total: pod.episodes.len(false),
+							downloaded: pod.num_downloaded(),
+							unplayed: pod.num_unplayed(),
+						}),
+						cover: pod.cover_path
+							.as_ref()
+							.filter(|path| path.exists())
+							.map(|path| path.to_string_lossy().to_string()),
+						duration: None,
+						downloaded: None,
+						explicit: pod.explicit,
+						description: pod.description
+							.as_deref()
+							.filter(|d| !d.is_empty())
+							.map(clean_html),
 					};
-
+					det.change_details(details);
+				}
+				else if let Some(ep_id) = curr_ep_id
+				{
 					// the rest of the details come from the current episode
 					if let Some(ep) =
 						self.episode_menu.items.borrow_map().get(&ep_id)
@@ -1052,31 +3154,26 @@ impl<'a> Ui<'a>
 						}
 						else
 						{
-							// convert <br/> tags to a single line break
-							let br_to_lb = RE_BR_TAGS.replace_all(&ep.description, "\n");
-
-							// strip all HTML tags
-							let stripped_tags = RE_HTML_TAGS.replace_all(&br_to_lb, "");
-
-							// convert HTML entities (e.g., &amp;)
-							let decoded = match escaper::decode_html(&stripped_tags)
-							{
-								Err(_) => stripped_tags.to_string(),
-								Ok(s) => s,
-							};
-
-							// remove anything more than two line breaks (i.e., one blank line)
-							let no_line_breaks = RE_MULT_LINE_BREAKS.replace_all(&decoded, "\n\n");
-
-							Some(no_line_breaks.to_string())
+							Some(clean_html(&ep.description))
 						};
 
 						let details = Details {
+							is_podcast: false,
 							pod_title: pod_title,
 							ep_title: ep_title,
-							pubdate: ep.pubdate,
-							duration: Some(ep.format_duration()),
-							explicit: pod_explicit,
+							author: None,
+							feed_url: None,
+							pubdate: ep.pubdate.map(|date| self.detail_date_format.format(&date)),
+							last_checked: None,
+							last_error: None,
+							last_error_at: None,
+							inactive: false,
+							synced: true,
+							counts: None,
+							cover: None,
+							duration: ep.duration.map(|_| ep.format_duration()),
+							downloaded: ep.path.as_ref().map(|_| ep.format_download(&self.date_format)),
+							explicit: pod.explicit,
 							description: desc,
 						};
 						det.change_details(details);
@@ -1086,3 +3183,360 @@ impl<'a> Ui<'a>
 		}
 	}
 }
+
+/// Sets up the terminal for the UI: raw mode, the alternate screen, and
+/// a hidden cursor, along with mouse capture if `enable_mouse` is set.
+fn take_terminal(enable_mouse: bool)
+{
+	TERMINAL_TAKEN.store(true, Ordering::SeqCst);
+	terminal::enable_raw_mode().expect("Terminal can't run in raw mode.");
+	execute!(
+		io::stdout(),
+		terminal::EnterAlternateScreen,
+		terminal::Clear(terminal::ClearType::All),
+		cursor::Hide
+	)
+	.expect("Can't draw to screen.");
+	// not every terminal supports bracketed paste; without it, pasted
+	// text just arrives as separate key presses
+	let _ = execute!(io::stdout(), event::EnableBracketedPaste);
+	if enable_mouse
+	{
+		execute!(io::stdout(), event::EnableMouseCapture)
+			.expect("Can't capture mouse events.");
+	}
+}
+
+/// Puts the terminal back the way it was before the UI started: leaves
+/// raw mode and the alternate screen, and shows the cursor again. This
+/// does nothing if the UI has not set up the terminal, or has already
+/// restored it, so it is safe to call from the panic hook. Errors are
+/// ignored, since there is nothing better to do with them at this point.
+pub fn restore_terminal()
+{
+	if !TERMINAL_TAKEN.swap(false, Ordering::SeqCst)
+	{
+		return;
+	}
+	let _ = terminal::disable_raw_mode();
+	let _ = execute!(
+		io::stdout(),
+		event::DisableMouseCapture,
+		event::DisableBracketedPaste,
+		terminal::LeaveAlternateScreen,
+		cursor::Show
+	);
+}
+
+/// Describes a number of episodes to download along with their total
+/// size, e.g., "27 episodes (~4.1 GB, 12 with unknown size)". Episodes
+/// whose size is not known are counted separately.
+fn download_summary(sizes: &[Option<u64>]) -> String
+{
+	let count = sizes.len();
+	let plural = if count == 1 { "" } else { "s" };
+	let known: Vec<u64> = sizes.iter().flatten().copied().collect();
+	let unknown = count - known.len();
+	let total = crate::stats::format_size(known.iter().sum());
+	let size = if known.is_empty()
+	{
+		"unknown size".to_string()
+	}
+	else if unknown == 0
+	{
+		format!("~{total}")
+	}
+	else
+	{
+		format!("~{total}, {unknown} with unknown size")
+	};
+	return format!("{count} episode{plural} ({size})");
+}
+
+/// Checks whether a terminal of the given size is too small to draw the
+/// panels in.
+fn is_too_small(n_col: u16, n_row: u16) -> bool
+{
+	return n_col < crate::config::MIN_TERMINAL_COLS || n_row < crate::config::MIN_TERMINAL_ROWS;
+}
+
+/// Returns the digit typed with `input`, if it goes towards a count: any
+/// digit once a count has been started with `count`, and otherwise a
+/// digit other than 0 that is not `bound` to an action.
+fn count_digit(input: KeyEvent, bound: bool, count: Option<usize>) -> Option<usize>
+{
+	if input.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+	{
+		return None;
+	}
+	let digit = match input.code
+	{
+		KeyCode::Char(c) => c.to_digit(10)? as usize,
+		_ => return None,
+	};
+	if count.is_some() || (!bound && digit > 0)
+	{
+		return Some(digit);
+	}
+	return None;
+}
+
+/// Adds a typed digit to the end of a count, e.g., "1" then "5" gives 15.
+/// The count stops growing at `MAX_COUNT`.
+fn push_digit(count: Option<usize>, digit: usize) -> Option<usize>
+{
+	let count = count.unwrap_or(0).saturating_mul(10).saturating_add(digit);
+	return Some(count.min(MAX_COUNT));
+}
+
+/// Converts a description containing HTML into plain text, removing all
+/// tags and extra blank lines. Line breaks and paragraphs are kept, list
+/// items are shown with a dash, and links are shown as "text (url)".
+/// Descriptions without any HTML tags keep their line breaks as they are.
+fn clean_html(text: &str) -> String
+{
+	let text = RE_HTML_HIDDEN.replace_all(text, "");
+
+	// like a browser would, ignore line breaks in the HTML source, and
+	// only keep the ones produced by tags
+	let text = if RE_HTML_TAGS.is_match(&text)
+	{
+		RE_WHITESPACE.replace_all(&text, " ")
+	}
+	else
+	{
+		text
+	};
+
+	let text = RE_LINK_TAGS.replace_all(&text, |cap: &regex::Captures| {
+		let url = cap.get(1)
+			.or_else(|| cap.get(2))
+			.or_else(|| cap.get(3))
+			.map(|m| decode_entities(m.as_str().trim()))
+			.unwrap_or_default();
+		let link_text = cap[4].to_string();
+		let plain_text = decode_entities(RE_HTML_TAGS.replace_all(&link_text, "").trim());
+		if url.is_empty() || url.starts_with('#') || plain_text == url
+		{
+			link_text
+		}
+		else if plain_text.is_empty()
+		{
+			url
+		}
+		else
+		{
+			format!("{link_text} ({url})")
+		}
+	});
+
+	// convert <br/> tags to a single line break, and separate list items
+	// and paragraphs
+	let text = RE_BR_TAGS.replace_all(&text, "\n");
+	let text = RE_LIST_ITEMS.replace_all(&text, "\n- ");
+	let text = RE_BLOCK_TAGS.replace_all(&text, "\n\n");
+
+	// strip all remaining HTML tags, and convert HTML entities
+	let text = RE_HTML_TAGS.replace_all(&text, "");
+	let text = decode_entities(&text).replace('\u{a0}', " ");
+
+	// remove anything more than two line breaks (i.e., one blank line)
+	let text = RE_LINE_SPACES.replace_all(&text, "\n");
+	let text = RE_MULT_LINE_BREAKS.replace_all(&text, "\n\n");
+	return text.trim().to_string();
+}
+
+/// Converts HTML entities (e.g., &amp;) into the characters they stand
+/// for. Unknown or malformed entities are left as they are.
+fn decode_entities(text: &str) -> String
+{
+	return RE_ENTITIES
+		.replace_all(text, |cap: &regex::Captures| {
+			let entity = &cap[0];
+			escaper::decode_html(entity).unwrap_or_else(|_| entity.to_string())
+		})
+		.into_owned();
+}
+
+
+// TESTS -----------------------------------------------------------------
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	#[test]
+	fn layout_sizes()
+	{
+		let sizes = |n_col, layout, width| {
+			Ui::calculate_sizes(n_col, layout, width, ActivePanel::EpisodeMenu, false)
+		};
+
+		// the automatic layout shows the details in wide terminals only
+		let (pod, ep, det) = sizes(150, Layout::Auto, None);
+		let det = det.unwrap();
+		assert_eq!((pod.start_x, pod.n_col), (0, 50));
+		assert_eq!(ep.start_x, pod.n_col - 1);
+		assert_eq!(det.start_x, ep.start_x + ep.n_col - 1);
+		assert_eq!(det.start_x + det.n_col, 150);
+		let (pod, ep, det) = sizes(100, Layout::Auto, None);
+		assert_eq!((pod.n_col, ep.start_x, ep.n_col), (50, 49, 51));
+		assert!(det.is_none());
+
+		// the podcast width is kept above the minimum
+		let (pod, ep, _) = sizes(100, Layout::TwoPane, Some(PanelWidth::Percent(20)));
+		assert_eq!((pod.n_col, ep.n_col), (20, 81));
+		let (pod, _, _) = sizes(100, Layout::TwoPane, Some(PanelWidth::Columns(5)));
+		assert_eq!(pod.n_col, crate::config::MIN_PANEL_WIDTH);
+		let (_, ep, _) = sizes(100, Layout::TwoPane, Some(PanelWidth::Columns(95)));
+		assert_eq!(ep.n_col, crate::config::MIN_PANEL_WIDTH);
+
+		// narrow terminals fall back on fewer panels
+		assert!(sizes(30, Layout::ThreePane, None).2.is_none());
+		let (pod, ep, det) = sizes(20, Layout::TwoPane, None);
+		assert!(!pod.visible && ep.visible && !det.unwrap().visible);
+		assert_eq!((ep.start_x, ep.n_col), (0, 20));
+
+		// the queue panel always gets the third column, and is zoomed in
+		// on when it has the focus if three panels do not fit
+		let queue = |n_col, layout, active| Ui::calculate_sizes(n_col, layout, None, active, true);
+		let (_, ep, det) = queue(100, Layout::TwoPane, ActivePanel::EpisodeMenu);
+		assert_eq!(det.unwrap().start_x, ep.start_x + ep.n_col - 1);
+		let (pod, ep, det) = queue(30, Layout::Auto, ActivePanel::QueuePanel);
+		let det = det.unwrap();
+		assert!(!pod.visible && !ep.visible && det.visible);
+		assert_eq!((det.start_x, det.n_col), (0, 30));
+
+		assert_eq!(PanelWidth::parse("25%"), Some(PanelWidth::Percent(25)));
+		assert_eq!(PanelWidth::parse(" 30 "), Some(PanelWidth::Columns(30)));
+		assert_eq!(PanelWidth::parse("0%"), None);
+		assert_eq!(PanelWidth::parse("wide"), None);
+	}
+
+	#[test]
+	fn smallest_terminal()
+	{
+		use crate::config::{MIN_PANEL_WIDTH, MIN_TERMINAL_COLS, MIN_TERMINAL_ROWS};
+		assert!(is_too_small(MIN_TERMINAL_COLS - 1, MIN_TERMINAL_ROWS));
+		assert!(is_too_small(MIN_TERMINAL_COLS, 0));
+		assert!(!is_too_small(MIN_TERMINAL_COLS, MIN_TERMINAL_ROWS));
+
+		// every layout still fits its panels in the smallest terminal
+		for layout in [Layout::Auto, Layout::TwoPane, Layout::ThreePane, Layout::Zoomed]
+		{
+			let (pod, ep, det) = Ui::calculate_sizes(
+				MIN_TERMINAL_COLS, layout, None, ActivePanel::PodcastMenu, false
+			);
+			for pos in [Some(pod), Some(ep), det].into_iter().flatten()
+			{
+				assert!(pos.n_col >= MIN_PANEL_WIDTH);
+				assert!(pos.start_x + pos.n_col <= MIN_TERMINAL_COLS);
+			}
+		}
+	}
+
+	#[test]
+	fn download_sizes()
+	{
+		let gb = 1_000_000_000;
+		assert_eq!(download_summary(&[Some(gb), Some(gb / 2)]), "2 episodes (~1.5 GB)");
+		assert_eq!(
+			download_summary(&[Some(gb), None, None]),
+			"3 episodes (~1.0 GB, 2 with unknown size)"
+		);
+		assert_eq!(download_summary(&[None]), "1 episode (unknown size)");
+	}
+
+	#[test]
+	fn count_digits()
+	{
+		let count = push_digit(None, 1);
+		assert_eq!(count, Some(1));
+		assert_eq!(push_digit(count, 5), Some(15));
+		assert_eq!(push_digit(Some(2000), 7), Some(MAX_COUNT));
+
+		assert!(matches!(Scroll::Down(1).repeat(15), Scroll::Down(15)));
+		assert!(matches!(Scroll::Up(100).repeat(1000), Scroll::Up(u16::MAX)));
+	}
+
+	#[test]
+	fn counts_with_default_keys()
+	{
+		// types the keys as getch() does, and returns the count along
+		// with what the first key that is not part of the count does
+		let keymap = Keybindings::default();
+		let type_keys = |keys: &str| -> (Option<usize>, KeyMatch) {
+			let mut count = None;
+			for c in keys.chars()
+			{
+				let input = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+				let key = keymap::input_to_str(input).unwrap();
+				let key_match = keymap.get_from_keys(&[key]);
+				match count_digit(input, key_match != KeyMatch::Unbound, count)
+				{
+					Some(digit) => count = push_digit(count, digit),
+					None => return (count, key_match),
+				}
+			}
+			return (count, KeyMatch::Unbound);
+		};
+		assert_eq!(type_keys("15j"), (Some(15), KeyMatch::Action(UserAction::Down)));
+		assert_eq!(type_keys("3d"), (Some(3), KeyMatch::Action(UserAction::Download)));
+		assert_eq!(type_keys("105G"), (Some(105), KeyMatch::Action(UserAction::GoBot)));
+		assert_eq!(type_keys("j"), (None, KeyMatch::Action(UserAction::Down)));
+	}
+
+	#[test]
+	fn scroll_for_actions()
+	{
+		assert!(matches!(Scroll::from_action(UserAction::PageDown, 20), Some(Scroll::Down(20))));
+		assert!(matches!(Scroll::from_action(UserAction::BigUp, 20), Some(Scroll::Up(5))));
+		assert!(matches!(Scroll::from_action(UserAction::BigDown, 2), Some(Scroll::Down(1))));
+		assert!(matches!(Scroll::from_action(UserAction::PageUp, 0), Some(Scroll::Up(1))));
+		assert!(matches!(Scroll::from_action(UserAction::GoBot, 20), Some(Scroll::Down(u16::MAX))));
+		assert!(Scroll::from_action(UserAction::Left, 20).is_none());
+	}
+
+	#[test]
+	fn html_paragraphs()
+	{
+		let html = "<p>First paragraph &amp; more.</p>\n\n\n<p>Second<br/>\nline</p>";
+		assert_eq!(clean_html(html), "First paragraph & more.\n\nSecond\nline");
+	}
+
+	#[test]
+	fn html_links()
+	{
+		let html = "Visit <a href=\"https://example.org/?a=1&amp;b=2\" target=\"_blank\">\
+			<b>our site</b></a> or <a href='https://example.org/'>https://example.org/</a>.";
+		assert_eq!(
+			clean_html(html),
+			"Visit our site (https://example.org/?a=1&b=2) or https://example.org/."
+		);
+	}
+
+	#[test]
+	fn html_lists()
+	{
+		let html = "<p>Links:</p><ul><li>One</li><li>Two&nbsp;&#8211; three</li></ul>";
+		assert_eq!(clean_html(html), "Links:\n\n- One\n- Two \u{2013} three");
+	}
+
+	#[test]
+	fn html_malformed()
+	{
+		let html = "<div><p>Unclosed <b>bold <i>text</div><!-- hidden --> \
+			<a href=\"x\">dangling <<>> &bogus; &#xZZ; 5 < 6 & 7 > 3 <a href=";
+		assert_eq!(
+			clean_html(html),
+			"Unclosed bold text\n\ndangling <<>> &bogus; &#xZZ; 5 < 6 & 7 > 3 <a href="
+		);
+	}
+
+	#[test]
+	fn plain_text_line_breaks()
+	{
+		let text = "Line one\nLine two\n\n\n\nLine three";
+		assert_eq!(clean_html(text), "Line one\nLine two\n\nLine three");
+	}
+}
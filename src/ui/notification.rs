@@ -4,11 +4,13 @@ use std::time::{Duration, Instant};
 
 use crossterm::{
 	cursor,
-	event::{self, KeyCode},
+	event::{self, KeyCode, KeyEventKind},
 	execute, queue, style,
 	style::Stylize,
 };
+use unicode_width::UnicodeWidthStr;
 
+use super::line_editor::LineEditor;
 use super::AppColors;
 
 /// Holds details of a notification message. The `expiry` is optional,
@@ -47,6 +49,10 @@ impl Notification
 /// stack based on their expiry times. As such, it will generally be a
 /// FIFO approach (older notifications will generally expire first), but
 /// not necessarily.
+///
+/// `pending` holds what was typed of a command so far, e.g., the "15"
+/// in "15j", or the first key of a sequence such as "g g", which is
+/// shown in the bottom right corner until the command is entered.
 #[derive(Debug)]
 pub struct NotifWin
 {
@@ -57,6 +63,7 @@ pub struct NotifWin
 	msg_stack: Vec<Notification>,
 	persistent_msg: Option<Notification>,
 	current_msg: Option<Notification>,
+	pending: Option<String>,
 }
 
 impl NotifWin
@@ -74,6 +81,7 @@ impl NotifWin
 			msg_stack: Vec::new(),
 			persistent_msg: None,
 			current_msg: None,
+			pending: None,
 		};
 	}
 
@@ -88,12 +96,53 @@ impl NotifWin
 			io::stdout(),
 			cursor::MoveTo(0, self.start_y),
 			style::PrintStyledContent(
-				style::style(&empty_string)
-					.with(self.colors.normal.0)
-					.on(self.colors.normal.1)
+				self.colors.style(self.colors.normal).apply(&empty_string)
 			),
 		)
 		.unwrap();
+		self.draw_pending();
+	}
+
+	/// Prints the pending command, if any, in the bottom right corner.
+	fn draw_pending(&self)
+	{
+		if let Some(pending) = &self.pending
+		{
+			let text = format!(" {pending} ");
+			let start_x = self.total_cols.saturating_sub(text.chars().count() as u16);
+			queue!(
+				io::stdout(),
+				cursor::MoveTo(start_x, self.start_y),
+				style::PrintStyledContent(
+					self.colors.style(self.colors.normal).apply(text)
+						.attribute(style::Attribute::Bold)
+				),
+			)
+			.unwrap();
+		}
+	}
+
+	/// Replaces the colors, e.g., after the config file has been
+	/// reloaded. The window must be redrawn for the change to show.
+	pub fn set_colors(&mut self, colors: Rc<AppColors>)
+	{
+		self.colors = colors;
+	}
+
+	/// Sets the pending command shown in the corner of the window, or
+	/// clears it if `pending` is None.
+	pub fn set_pending(&mut self, pending: Option<String>)
+	{
+		if pending == self.pending
+		{
+			return;
+		}
+		self.pending = pending;
+		match &self.current_msg
+		{
+			Some(curr) => self.display_notif(curr),
+			None => self.redraw(),
+		}
 	}
 
 	/// Checks if the current notification needs to be changed, and
@@ -156,108 +205,46 @@ impl NotifWin
 	/// be specified as a prompt for the user at the beginning of the
 	/// input line. This returns the user's input; if the user cancels
 	/// their input, the String will be empty.
+	///
+	/// The input can be edited with the usual keys (arrows, Home/End,
+	/// Delete, Ctrl-U to clear and Ctrl-W to delete a word), and scrolls
+	/// sideways if it does not fit on the line. Pasted text is inserted
+	/// all at once.
 	pub fn input_notif(&self, prefix: &str) -> String
 	{
-		execute!(
-			io::stdout(),
-			cursor::MoveTo(0, self.start_y),
-			style::Print(&prefix),
-			cursor::Show
-		)
-		.unwrap();
+		let mut editor = LineEditor::new();
+		self.draw_input(prefix, &mut editor);
+		execute!(io::stdout(), cursor::Show).unwrap();
 
-		let mut inputs = String::new();
 		let mut cancelled = false;
-
-		let min_x = prefix.len() as u16;
-		let mut current_max_x = prefix.len() as u16;
-		let mut cursor_x = prefix.len() as u16;
 		loop
 		{
-			if let event::Event::Key(input) = event::read().expect("")
+			match event::read().expect("Can't read inputs")
 			{
-				let cursor_idx = (cursor_x - min_x) as usize;
-				match input.code
-				{
-					// Cancel input
-					KeyCode::Esc | KeyCode::Char('\u{1b}') => {
-						cancelled = true;
-						break;
-					}
-					// Complete input
-					KeyCode::Enter | KeyCode::Char('\n') => {
-						break;
-					}
-					KeyCode::Backspace | KeyCode::Char('\u{7f}') => {
-						if current_max_x > min_x
-						{
-							current_max_x -= 1;
-							cursor_x -= 1;
-							let _ = inputs.remove(cursor_idx - 1);
-							execute!(io::stdout(), cursor::MoveLeft(1)).unwrap();
-							for i in inputs.chars().skip(cursor_idx - 1)
-							{
-								execute!(io::stdout(), style::Print(i)).unwrap();
-							}
-							execute!(
-								io::stdout(),
-								style::Print(" "),
-								cursor::MoveTo(cursor_x, self.start_y)
-							)
-							.unwrap();
+				event::Event::Key(input) if input.kind != KeyEventKind::Release => {
+					match input.code
+					{
+						// Cancel input
+						KeyCode::Esc | KeyCode::Char('\u{1b}') => {
+							cancelled = true;
+							break;
 						}
-					}
-					KeyCode::Delete => {
-						if cursor_x < current_max_x
-						{
-							current_max_x -= 1;
-							let _ = inputs.remove(cursor_idx);
-							for i in inputs.chars().skip(cursor_idx)
-							{
-								execute!(io::stdout(), style::Print(i)).unwrap();
-							}
-							execute!(
-								io::stdout(),
-								style::Print(" "),
-								cursor::MoveTo(cursor_x, self.start_y)
-							)
-							.unwrap();
+						// Complete input
+						KeyCode::Enter | KeyCode::Char('\n') => {
+							break;
 						}
-					}
-					KeyCode::Left => {
-						if cursor_x > min_x
-						{
-							cursor_x -= 1;
-							execute!(io::stdout(), cursor::MoveLeft(1)).unwrap();
-						}
-					}
-					KeyCode::Right => {
-						if cursor_x < current_max_x
-						{
-							cursor_x += 1;
-							execute!(io::stdout(), cursor::MoveRight(1)).unwrap();
-						}
-					}
-					KeyCode::Char(c) => {
-						current_max_x += 1;
-						cursor_x += 1;
-						if cursor_x < current_max_x
-						{
-							inputs.insert(cursor_idx, c);
-							for i in inputs.chars().skip(cursor_idx) {
-								execute!(io::stdout(), style::Print(i)).unwrap();
+						_ => {
+							if !editor.handle_key(input)
+							{
+								continue;
 							}
-							execute!(io::stdout(), cursor::MoveTo(cursor_x, self.start_y)).unwrap();
-						}
-						else
-						{
-							inputs.push(c);
-							execute!(io::stdout(), style::Print(c)).unwrap();
 						}
 					}
-					_ => (),
 				}
+				event::Event::Paste(text) => editor.insert_str(&text),
+				_ => continue,
 			}
+			self.draw_input(prefix, &mut editor);
 		}
 
 		execute!(io::stdout(), cursor::Hide).unwrap();
@@ -267,7 +254,28 @@ impl NotifWin
 		{
 			return String::from("");
 		}
-		return inputs;
+		return editor.text();
+	}
+
+	/// Draws the prompt and the part of the input that fits after it,
+	/// and places the cursor where the user is typing. The prompt is
+	/// measured in columns rather than bytes, so that the cursor ends up
+	/// in the right place even if it contains, e.g., accented letters.
+	fn draw_input(&self, prefix: &str, editor: &mut LineEditor)
+	{
+		let prefix_width = prefix.width();
+		let width = (self.total_cols as usize).saturating_sub(prefix_width);
+		let (visible, column) = editor.view(width);
+		let padding = width.saturating_sub(visible.width());
+		execute!(
+			io::stdout(),
+			cursor::MoveTo(0, self.start_y),
+			style::Print(prefix),
+			style::Print(visible),
+			style::Print(" ".repeat(padding)),
+			cursor::MoveTo((prefix_width + column) as u16, self.start_y)
+		)
+		.unwrap();
 	}
 
 	/// Prints a notification to the window.
@@ -276,16 +284,12 @@ impl NotifWin
 		self.redraw();
 		let styled = if notif.error
 		{
-			style::style(&notif.message)
-				.with(self.colors.error.0)
-				.on(self.colors.error.1)
+			self.colors.style(self.colors.error).apply(&notif.message)
 				.attribute(style::Attribute::Bold)
 		}
 		else
 		{
-			style::style(&notif.message)
-				.with(self.colors.normal.0)
-				.on(self.colors.normal.1)
+			self.colors.style(self.colors.normal).apply(&notif.message)
 		};
 		queue!(
 			io::stdout(),
@@ -293,6 +297,7 @@ impl NotifWin
 			style::PrintStyledContent(styled)
 		)
 		.unwrap();
+		self.draw_pending();
 	}
 
 	/// Adds a notification to the user. `duration` indicates how long
@@ -1,10 +1,11 @@
 use std::io;
 use std::rc::Rc;
 
-use crossterm::style::{self, Stylize};
+use crossterm::style;
 use crossterm::{cursor, queue};
 
 use super::AppColors;
+use crate::types::StringUtils;
 
 pub const VERTICAL: &str = "│";
 pub const HORIZONTAL: &str = "─";
@@ -19,22 +20,28 @@ pub const BOTTOM_TEE: &str = "┴";
 /// Panels abstract away a terminal "window" (section of the screen),
 /// and handle all methods associated with writing data to that window.
 /// A panel includes a border and margin around the edge of the window,
-/// and a title that appears at the top. Margins are set individually,
-/// in the order (top, right, bottom, left). The Panel will translate
-/// the x and y coordinates to account for the border and margins, so
-/// users of the methods can calculate rows and columns relative to the
-/// Panel (i.e., x = 0 and y = 0 represent the top-left printable
-/// cell in the window).
+/// a title that appears at the top, and an optional footer that appears
+/// at the bottom, with an optional indicator of the position in the
+/// list in the bottom right corner. Margins are set individually, in
+/// the order (top, right, bottom, left). The Panel will translate the x
+/// and y coordinates to account for the border and margins, so users of
+/// the methods can calculate rows and columns relative to the Panel
+/// (i.e., x = 0 and y = 0 represent the top-left printable cell in the
+/// window). A panel that is not visible, e.g., one hidden by the zoomed
+/// layout, keeps its size and position but does not draw anything.
 #[derive(Debug)]
 pub struct Panel
 {
 	screen_pos: usize,
 	pub colors: Rc<AppColors>,
 	title: String,
+	footer: Option<String>,
+	position: Option<String>,
 	start_x: u16,
 	n_row: u16,
 	n_col: u16,
 	margins: (u16, u16, u16, u16),
+	visible: bool,
 }
 
 impl Panel
@@ -54,16 +61,52 @@ impl Panel
 			screen_pos: screen_pos,
 			colors: colors,
 			title: title,
+			footer: None,
+			position: None,
 			start_x: start_x,
 			n_row: n_row,
 			n_col: n_col,
 			margins: margins,
+			visible: true,
 		};
 	}
 
+	/// Changes the title shown at the top of the panel. The panel must be
+	/// redrawn for the change to show.
+	pub fn set_title(&mut self, title: String)
+	{
+		self.title = title;
+	}
+
+	/// Changes the text shown at the bottom of the panel, if any. The
+	/// panel must be redrawn for the change to show.
+	pub fn set_footer(&mut self, footer: Option<String>)
+	{
+		self.footer = footer;
+	}
+
+	/// Changes the position indicator shown in the bottom right corner of
+	/// the panel, if any. It is drawn along with the bottom border, see
+	/// `draw_bottom()`.
+	pub fn set_position(&mut self, position: Option<String>)
+	{
+		self.position = position;
+	}
+
+	/// Shows or hides the panel. The panel must be redrawn for the
+	/// change to show.
+	pub fn set_visible(&mut self, visible: bool)
+	{
+		self.visible = visible;
+	}
+
 	/// Redraws borders and refreshes the window to display on terminal.
 	pub fn redraw(&self)
 	{
+		if !self.visible
+		{
+			return;
+		}
 		self.clear();
 		self.draw_border();
 	}
@@ -71,17 +114,19 @@ impl Panel
 	/// Clears the whole Panel.
 	pub fn clear(&self)
 	{
+		if !self.visible
+		{
+			return;
+		}
 		let empty = vec![" "; self.n_col as usize];
 		let empty_string = empty.join("");
-		for r in 0..(self.n_row - 1)
+		for r in 0..self.n_row.saturating_sub(1)
 		{
 			queue!(
 				io::stdout(),
 				cursor::MoveTo(self.start_x, r),
 				style::PrintStyledContent(
-					style::style(&empty_string)
-						.with(self.colors.normal.0)
-						.on(self.colors.normal.1)
+					self.colors.style(self.colors.normal).apply(&empty_string)
 				),
 			)
 			.unwrap();
@@ -92,17 +137,19 @@ impl Panel
 	/// intact.
 	pub fn clear_inner(&self)
 	{
-		let empty = vec![" "; self.n_col as usize - 2];
+		if !self.visible
+		{
+			return;
+		}
+		let empty = vec![" "; (self.n_col as usize).saturating_sub(2)];
 		let empty_string = empty.join("");
-		for r in 1..(self.n_row - 1)
+		for r in 1..self.n_row.saturating_sub(1)
 		{
 			queue!(
 				io::stdout(),
 				cursor::MoveTo(self.start_x + 1, r),
 				style::PrintStyledContent(
-					style::style(&empty_string)
-						.with(self.colors.normal.0)
-						.on(self.colors.normal.1)
+					self.colors.style(self.colors.normal).apply(&empty_string)
 				),
 			)
 			.unwrap();
@@ -112,49 +159,32 @@ impl Panel
 	/// Draws a border around the window.
 	fn draw_border(&self)
 	{
-		let top_left;
-		let bot_left;
-		match self.screen_pos
-		{
-			0 => {
-				top_left = TOP_LEFT;
-				bot_left = BOTTOM_LEFT;
-			}
-			_ => {
-				top_left = TOP_TEE;
-				bot_left = BOTTOM_TEE;
-			}
-		}
+		// panels after the first one share their left border with the
+		// panel before them, unless they fill the screen on their own
+		let top_left = if self.screen_pos == 0 || self.start_x == 0 {
+			TOP_LEFT
+		} else {
+			TOP_TEE
+		};
 		let mut border_top = vec![top_left];
-		let mut border_bottom = vec![bot_left];
-		for _ in 0..(self.n_col - 2)
-		{
-			border_top.push(HORIZONTAL);
-			border_bottom.push(HORIZONTAL);
-		}
+		border_top.extend(vec![HORIZONTAL; (self.n_col as usize).saturating_sub(2)]);
 		border_top.push(TOP_RIGHT);
-		border_bottom.push(BOTTOM_RIGHT);
 
 		queue!(
 			io::stdout(),
-			style::SetColors(style::Colors::new(
-				self.colors.normal.0,
-				self.colors.normal.1
-			)),
+			style::SetStyle(self.colors.style(self.colors.normal)),
 			cursor::MoveTo(self.start_x, 0),
 			style::Print(border_top.join("")),
-			cursor::MoveTo(self.start_x, self.n_row - 1),
-			style::Print(border_bottom.join("")),
 		)
 		.unwrap();
 
-		for r in 1..(self.n_row - 1)
+		for r in 1..self.n_row.saturating_sub(1)
 		{
 			queue!(
 				io::stdout(),
 				cursor::MoveTo(self.start_x, r),
 				style::Print(VERTICAL.to_string()),
-				cursor::MoveTo(self.start_x + self.n_col - 1, r),
+				cursor::MoveTo(self.start_x + self.n_col.saturating_sub(1), r),
 				style::Print(VERTICAL.to_string()),
 			)
 			.unwrap();
@@ -163,10 +193,68 @@ impl Panel
 		queue!(
 			io::stdout(),
 			cursor::MoveTo(self.start_x + 2, 0),
-			style::Print(&self.title),
-			style::ResetColor,
+			style::Print(self.title.truncate_width((self.n_col as usize).saturating_sub(4))),
+		)
+		.unwrap();
+		self.draw_bottom();
+	}
+
+	/// Draws the bottom border of the window, along with the footer and
+	/// the position indicator. This can be called on its own when only
+	/// these have changed, without redrawing the whole panel.
+	pub fn draw_bottom(&self)
+	{
+		if !self.visible
+		{
+			return;
+		}
+		let bot_left = if self.screen_pos == 0 || self.start_x == 0 {
+			BOTTOM_LEFT
+		} else {
+			BOTTOM_TEE
+		};
+		let mut border_bottom = vec![bot_left];
+		border_bottom.extend(vec![HORIZONTAL; (self.n_col as usize).saturating_sub(2)]);
+		border_bottom.push(BOTTOM_RIGHT);
+
+		queue!(
+			io::stdout(),
+			style::SetStyle(self.colors.style(self.colors.normal)),
+			cursor::MoveTo(self.start_x, self.n_row.saturating_sub(1)),
+			style::Print(border_bottom.join("")),
 		)
 		.unwrap();
+
+		// the position goes in the right corner, leaving room for the
+		// footer on the left, which is cut short if needed
+		let mut footer_width = (self.n_col as usize).saturating_sub(4);
+		if let Some(position) = &self.position
+		{
+			let len = position.grapheme_len();
+			if len + 4 <= self.n_col as usize
+			{
+				queue!(
+					io::stdout(),
+					cursor::MoveTo(
+						self.start_x + self.n_col - 2 - len as u16,
+						self.n_row.saturating_sub(1)
+					),
+					style::Print(position),
+				)
+				.unwrap();
+				footer_width = footer_width.saturating_sub(len + 1);
+			}
+		}
+		if let Some(footer) = &self.footer
+		{
+			queue!(
+				io::stdout(),
+				cursor::MoveTo(self.start_x + 2, self.n_row.saturating_sub(1)),
+				style::Print(footer.truncate_width(footer_width)),
+			)
+			.unwrap();
+		}
+		queue!(io::stdout(), style::ResetColor).unwrap();
 	}
 
 	/// Writes a line of text to the window. Note that this does not do
@@ -179,12 +267,14 @@ impl Panel
 		string: String,
 		style: Option<style::ContentStyle>
 	) {
+		if !self.visible
+		{
+			return;
+		}
 		let styled = match style
 		{
 			Some(style) => style.apply(string),
-			None => style::style(string)
-				.with(self.colors.normal.0)
-				.on(self.colors.normal.1),
+			None => self.colors.style(self.colors.normal).apply(string),
 		};
 		queue!(
 			io::stdout(),
@@ -208,8 +298,16 @@ impl Panel
 		key_style: Option<style::ContentStyle>,
 		value_style: Option<style::ContentStyle>,
 	) {
+		if !self.visible
+		{
+			return;
+		}
 		key.push(':');
 		value.insert(0, ' ');
+		// cut the line short rather than letting it run past the border
+		let cols = self.get_cols() as usize;
+		let key = key.truncate_width(cols);
+		let value = value.truncate_width(cols - key.display_width());
 
 		queue!(
 			io::stdout(),
@@ -219,17 +317,13 @@ impl Panel
 		let key_styled = match key_style
 		{
 			Some(kstyle) => kstyle.apply(key),
-			None => style::style(key)
-				.with(self.colors.normal.0)
-				.on(self.colors.normal.1),
+			None => self.colors.style(self.colors.normal).apply(key),
 		};
 		queue!(io::stdout(), style::PrintStyledContent(key_styled)).unwrap();
 		let value_styled = match value_style
 		{
 			Some(vstyle) => vstyle.apply(value),
-			None => style::style(value)
-				.with(self.colors.normal.0)
-				.on(self.colors.normal.1),
+			None => self.colors.style(self.colors.normal).apply(value),
 		};
 		queue!(io::stdout(), style::PrintStyledContent(value_styled)).unwrap();
 	}
@@ -247,16 +341,14 @@ impl Panel
 	{
 		let mut row = start_y;
 		let max_row = self.get_rows();
-		if row >= max_row
+		if row >= max_row || !self.visible
 		{
 			return row;
 		}
 		let content_style = match style
 		{
 			Some(style) => style,
-			None => style::ContentStyle::new()
-				.with(self.colors.normal.0)
-				.on(self.colors.normal.1),
+			None => self.colors.style(self.colors.normal),
 		};
 		let wrapper = textwrap::wrap(string, self.get_cols() as usize);
 		for line in wrapper
@@ -290,7 +382,7 @@ impl Panel
 	pub fn get_rows(&self) -> u16
 	{
 		// 2 for borders on top and bottom
-		return self.n_row - self.margins.0 - self.margins.2 - 2;
+		return self.n_row.saturating_sub(self.margins.0 + self.margins.2 + 2);
 	}
 
 	/// Returns the effective number of columns (accounting for
@@ -298,7 +390,26 @@ impl Panel
 	pub fn get_cols(&self) -> u16
 	{
 		// 2 for borders on left and right
-		return self.n_col - self.margins.1 - self.margins.3 - 2;
+		return self.n_col.saturating_sub(self.margins.1 + self.margins.3 + 2);
+	}
+
+	/// Calculates the position relative to the panel from a position
+	/// relative to the terminal, i.e., the reverse of `abs_x()` and
+	/// `abs_y()`. Returns None if the position is outside of the
+	/// printable area of the panel.
+	pub fn rel_coords(&self, x: u16, y: u16) -> Option<(u16, u16)>
+	{
+		if !self.visible
+		{
+			return None;
+		}
+		let rel_x = x.checked_sub(self.abs_x(0))?;
+		let rel_y = y.checked_sub(self.abs_y(0))?;
+		if rel_x < self.get_cols() && rel_y < self.get_rows()
+		{
+			return Some((rel_x, rel_y));
+		}
+		return None;
 	}
 
 	/// Calculates the y-value relative to the terminal rather than to
@@ -1,15 +1,12 @@
 use std::cmp::min;
 use std::rc::Rc;
 
-use crossterm::{
-	event::{KeyCode, KeyEvent},
-	style,
-	style::Stylize,
-};
+use crossterm::{style, style::Stylize};
 
 use super::{AppColors, Menu, Panel, Scroll, UiMsg};
 use crate::config::BIG_SCROLL_AMOUNT;
 use crate::keymap::{Keybindings, UserAction};
+use crate::stats::{LibraryStats, PodcastStats};
 use crate::types::*;
 
 /// Enum indicating the type of the currently active popup window.
@@ -19,6 +16,9 @@ pub enum ActivePopup
 	WelcomeWin(Panel),
 	HelpWin(Panel),
 	DownloadWin(Menu<NewEpisode>),
+	FeedWin(Menu<FeedCandidate>),
+	StatsWin(Menu<PodcastStats>),
+	LogWin(Menu<LogEntry>),
 	None,
 }
 
@@ -39,6 +39,21 @@ impl ActivePopup
 		return matches!(self, ActivePopup::DownloadWin(_));
 	}
 
+	pub fn is_feed_win(&self) -> bool
+	{
+		return matches!(self, ActivePopup::FeedWin(_));
+	}
+
+	pub fn is_stats_win(&self) -> bool
+	{
+		return matches!(self, ActivePopup::StatsWin(_));
+	}
+
+	pub fn is_log_win(&self) -> bool
+	{
+		return matches!(self, ActivePopup::LogWin(_));
+	}
+
 	pub fn is_none(&self) -> bool
 	{
 		return matches!(self, ActivePopup::None);
@@ -52,24 +67,32 @@ impl ActivePopup
 /// for multiple popup windows to exist (though only one is "active" at
 /// any given time).
 #[derive(Debug)]
-pub struct PopupWin<'a>
+pub struct PopupWin
 {
 	popup: ActivePopup,
 	new_episodes: Vec<NewEpisode>,
-	keymap: &'a Keybindings,
+	feed_candidates: Vec<FeedCandidate>,
+	stats: Option<LibraryStats>,
+	log: Vec<LogEntry>,
+	keymap: Rc<Keybindings>,
 	colors: Rc<AppColors>,
 	total_rows: u16,
 	total_cols: u16,
+	help_page: usize,
+	help_pages: usize,
 	pub welcome_win: bool,
 	pub help_win: bool,
 	pub download_win: bool,
+	pub feed_win: bool,
+	pub stats_win: bool,
+	pub log_win: bool,
 }
 
-impl<'a> PopupWin<'a>
+impl PopupWin
 {
 	/// Set up struct for handling popup windows.
 	pub fn new(
-		keymap: &'a Keybindings,
+		keymap: Rc<Keybindings>,
 		colors: Rc<AppColors>,
 		total_rows: u16,
 		total_cols: u16,
@@ -79,13 +102,21 @@ impl<'a> PopupWin<'a>
 		{
 			popup: ActivePopup::None,
 			new_episodes: Vec::new(),
+			feed_candidates: Vec::new(),
+			stats: None,
+			log: Vec::new(),
 			keymap: keymap,
 			colors: colors,
 			total_rows: total_rows,
 			total_cols: total_cols,
+			help_page: 0,
+			help_pages: 1,
 			welcome_win: false,
 			help_win: false,
 			download_win: false,
+			feed_win: false,
+			stats_win: false,
+			log_win: false,
 		};
 	}
 
@@ -93,14 +124,16 @@ impl<'a> PopupWin<'a>
 	/// screen.
 	pub fn is_popup_active(&self) -> bool
 	{
-		return self.welcome_win || self.help_win || self.download_win;
+		return self.welcome_win || self.help_win || self.download_win
+			|| self.feed_win || self.stats_win || self.log_win;
 	}
 
 	/// Indicates whether a popup window *other than the welcome window*
 	/// is currently on the screen.
 	pub fn is_non_welcome_popup_active(&self) -> bool
 	{
-		return self.help_win || self.download_win;
+		return self.help_win || self.download_win || self.feed_win
+			|| self.stats_win || self.log_win;
 	}
 
 	/// Resize the currently active popup window if one exists.
@@ -123,10 +156,34 @@ impl<'a> PopupWin<'a>
 				download_win.activate();
 				self.popup = ActivePopup::DownloadWin(download_win);
 			}
+			ActivePopup::FeedWin(_win) => {
+				let mut feed_win = self.make_feed_win();
+				feed_win.activate();
+				self.popup = ActivePopup::FeedWin(feed_win);
+			}
+			ActivePopup::StatsWin(_win) => {
+				let mut stats_win = self.make_stats_win();
+				stats_win.activate();
+				self.popup = ActivePopup::StatsWin(stats_win);
+			}
+			ActivePopup::LogWin(_win) => {
+				let mut log_win = self.make_log_win();
+				log_win.activate();
+				self.popup = ActivePopup::LogWin(log_win);
+			}
 			ActivePopup::None => (),
 		}
 	}
 
+	/// Replaces the keybindings and colors after the config file has
+	/// been reloaded. They are used for windows opened from now on, and
+	/// for the open one once it is redrawn with `resize()`.
+	pub fn set_config(&mut self, keymap: Rc<Keybindings>, colors: Rc<AppColors>)
+	{
+		self.keymap = keymap;
+		self.colors = colors;
+	}
+
 	/// Create a welcome window and draw it to the screen.
 	pub fn spawn_welcome_win(&mut self)
 	{
@@ -194,68 +251,157 @@ impl<'a> PopupWin<'a>
 	pub fn spawn_help_win(&mut self)
 	{
 		self.help_win = true;
+		self.help_page = 0;
 		self.change_win();
 	}
 
-	/// Create a new Panel holding a help window.
-	pub fn make_help_win(&self) -> Panel
+	/// Returns the lines of the help window: the keys currently bound
+	/// to every action, grouped by category, with a heading line at the
+	/// start of each group and a blank line between groups.
+	fn help_lines(&self) -> Vec<String>
 	{
 		let big_scroll_up = format!("Up 1/{BIG_SCROLL_AMOUNT} page:");
 		let big_scroll_dn = format!("Down 1/{BIG_SCROLL_AMOUNT} page:");
-		let actions = vec![
-			(Some(UserAction::Left), "Left:"),
-			(Some(UserAction::Right), "Right:"),
-			(Some(UserAction::Up), "Up:"),
-			(Some(UserAction::Down), "Down:"),
-			(Some(UserAction::BigUp), &big_scroll_up),
-			(Some(UserAction::BigDown), &big_scroll_dn),
-			(Some(UserAction::PageUp), "Page up:"),
-			(Some(UserAction::PageDown), "Page down:"),
-			(Some(UserAction::GoTop), "Go to top:"),
-			(Some(UserAction::GoBot), "Go to bottom:"),
-			// (None, ""),
-			(Some(UserAction::AddFeed), "Add feed:"),
-			(Some(UserAction::Sync), "Sync:"),
-			(Some(UserAction::SyncAll), "Sync all:"),
-			// (None, ""),
-			(Some(UserAction::Play), "Play:"),
-			(Some(UserAction::MarkPlayed), "Mark as played:"),
-			(Some(UserAction::MarkAllPlayed), "Mark all as played:"),
-			// (None, ""),
-			(Some(UserAction::Download), "Download:"),
-			(Some(UserAction::DownloadAll), "Download all:"),
-			(Some(UserAction::UnmarkDownloaded), "Unmark as downloaded:"),
-			(Some(UserAction::Delete), "Delete file:"),
-			(Some(UserAction::DeleteAll), "Delete all files:"),
-			(Some(UserAction::Remove), "Remove from list:"),
-			(Some(UserAction::RemoveAll), "Remove all from list:"),
-			// (None, ""),
-			(Some(UserAction::Help), "Help:"),
-			(Some(UserAction::Quit), "Quit:"),
+		let groups: Vec<(&str, Vec<(UserAction, &str)>)> = vec![
+			("Navigation", vec![
+				(UserAction::Left, "Left:"),
+				(UserAction::Right, "Right:"),
+				(UserAction::Up, "Up:"),
+				(UserAction::Down, "Down:"),
+				(UserAction::BigUp, &big_scroll_up),
+				(UserAction::BigDown, &big_scroll_dn),
+				(UserAction::PageUp, "Page up:"),
+				(UserAction::PageDown, "Page down:"),
+				(UserAction::GoTop, "Go to top:"),
+				(UserAction::GoBot, "Go to bottom:"),
+				(UserAction::Search, "Search:"),
+				(UserAction::JumpTo, "Jump to title:"),
+				(UserAction::SortEpisodes, "Sort episodes:"),
+				(UserAction::SortPodcasts, "Sort podcasts:"),
+				(UserAction::CycleLayout, "Change layout:"),
+			]),
+			("Podcasts", vec![
+				(UserAction::AddFeed, "Add feed:"),
+				(UserAction::Sync, "Sync:"),
+				(UserAction::SyncAll, "Sync all:"),
+				(UserAction::SyncGpodder, "Sync with gpodder.net:"),
+				(UserAction::Rename, "Rename podcast:"),
+				(UserAction::MaxEpisodes, "Episodes to keep:"),
+				(UserAction::EditTags, "Edit tags:"),
+				(UserAction::ToggleFavorite, "Favorite:"),
+				(UserAction::Remove, "Remove from list:"),
+				(UserAction::RemoveAll, "Remove all from list:"),
+				(UserAction::UndoRemoval, "Undo feed removal:"),
+			]),
+			("Playback", vec![
+				(UserAction::Play, "Play:"),
+				(UserAction::MarkPlayed, "Mark as played:"),
+				(UserAction::MarkAllPlayed, "Mark all as played:"),
+				(UserAction::SetPosition, "Set position:"),
+				(UserAction::Archive, "Archive:"),
+				(UserAction::ArchiveOlder, "Archive older:"),
+				(UserAction::Enqueue, "Add to queue:"),
+				(UserAction::ShowQueue, "Show/hide the queue:"),
+				(UserAction::PlayNext, "Play next in queue:"),
+				(UserAction::ToggleMark, "Mark/unmark episode:"),
+				(UserAction::MarkAll, "Mark all shown:"),
+				(UserAction::ClearMarks, "Clear marks:"),
+			]),
+			("Downloads", vec![
+				(UserAction::Download, "Download:"),
+				(UserAction::DownloadAll, "Download all:"),
+				(UserAction::UnmarkDownloaded, "Unmark as downloaded:"),
+				(UserAction::Delete, "Delete file:"),
+				(UserAction::DeleteAll, "Delete all files:"),
+				(UserAction::SetWorkers, "Simultaneous downloads:"),
+			]),
+			("General", vec![
+				(UserAction::FilterPlayed, "Filter by played:"),
+				(UserAction::FilterDownloaded, "Filter by downloaded:"),
+				(UserAction::FilterView, "Cycle episode view:"),
+				(UserAction::FilterTag, "Filter by tag:"),
+				(UserAction::FilterArchived, "Show archived:"),
+				(UserAction::FilterFavorites, "Show favorites only:"),
+				(UserAction::ShowStats, "Show statistics:"),
+				(UserAction::ShowLog, "Show message log:"),
+				(UserAction::OpenLink, "Open web page:"),
+				(UserAction::CopyUrl, "Copy URL:"),
+				(UserAction::CopyPath, "Copy file path:"),
+				(UserAction::ExportFeeds, "Export shown podcasts:"),
+				(UserAction::ReloadConfig, "Reload config file:"),
+				(UserAction::EditConfig, "Edit config file:"),
+				(UserAction::Help, "Help:"),
+				(UserAction::Quit, "Quit:"),
+			]),
 		];
-		let mut key_strs = Vec::new();
-		for (action, action_str) in actions
+
+		let mut entries = Vec::new();
+		for (name, actions) in groups
 		{
-			match action
+			if !entries.is_empty()
 			{
-				Some(action) => {
-					let keys = self.keymap.keys_for_action(action);
-					// longest prefix is 21 chars long
-					let key_str = match keys.len()
-					{
-						0 => format!("{:>21} <missing>", action_str),
-						1 => format!("{:>21} \"{}\"", action_str, &keys[0]),
-						_ => format!(
-							"{:>21} \"{}\" or \"{}\"",
-							action_str, &keys[0], &keys[1]
-						),
-					};
-					key_strs.push(key_str);
-				}
-				None => key_strs.push(" ".to_string()),
+				entries.push((None, String::new()));
+			}
+			entries.push((Some(name), String::new()));
+			for (action, action_str) in actions
+			{
+				let mut keys = self.keymap.keys_for_action(action);
+				keys.sort();
+				// longest prefix is 21 chars long
+				let key_str = if keys.is_empty() {
+					format!("{:>21} <unbound>", action_str)
+				} else {
+					let keys: Vec<String> = keys.iter().map(|key| format!("\"{key}\"")).collect();
+					format!("{:>21} {}", action_str, keys.join(" or "))
+				};
+				entries.push((None, key_str));
 			}
 		}
 
+		// headings are centered over the width of the longest line
+		let longest_line = entries
+			.iter()
+			.map(|(_, line)| line.chars().count())
+			.max()
+			.expect("Could not parse keybindings.");
+		return entries
+			.into_iter()
+			.map(|(heading, line)| match heading
+			{
+				Some(name) => format!("{:─^width$}", format!(" {name} "), width = longest_line),
+				None => line,
+			})
+			.collect();
+	}
+
+	/// Create a new Panel holding a help window. The keybindings are
+	/// shown in two columns if the terminal is wide enough, and split
+	/// into pages if the terminal is too short to show all of them.
+	pub fn make_help_win(&mut self) -> Panel
+	{
+		let lines = self.help_lines();
+		let longest_line = lines
+			.iter()
+			.map(|x| x.chars().count())
+			.max()
+			.expect("Could not parse keybindings.");
+
+		// check how long our strings are, and map to two columns
+		// if possible; `col_spacing` is the space to leave in between
+		// the two columns. The window is centered on the screen, and
+		// only as wide as it needs to be, accounting for the borders
+		// and margins
+		let col_spacing = 5;
+		let n_cols = if self.total_cols as usize > longest_line * 2 + col_spacing + 4 {
+			2
+		} else {
+			1
+		};
+		let width = min(
+			self.total_cols as usize,
+			(longest_line * n_cols + col_spacing * (n_cols - 1) + 4).max(50),
+		) as u16;
+
 		// the warning on the unused mut is a function of Rust getting
 		// confused between panel.rs and mock_panel.rs
 		#[allow(unused_mut)]
@@ -264,8 +410,8 @@ impl<'a> PopupWin<'a>
 			0,
 			self.colors.clone(),
 			self.total_rows - 1,
-			self.total_cols,
-			0,
+			width,
+			(self.total_cols - width) / 2,
 			(1, 1, 1, 1),
 		);
 		help_win.redraw();
@@ -275,64 +421,84 @@ impl<'a> PopupWin<'a>
 			row,
 			"Available keybindings:",
 			Some(
-				style::ContentStyle::new()
-					.with(self.colors.normal.0)
-					.on(self.colors.normal.1)
+				self.colors.style(self.colors.normal)
 					.attribute(style::Attribute::Underlined),
 			),
 		);
 		row += 1;
 
-		// check how long our strings are, and map to two columns
-		// if possible; `col_spacing` is the space to leave in between
-		// the two columns
-		let longest_line = key_strs
-			.iter()
-			.map(|x| x.chars().count())
-			.max()
-			.expect("Could not parse keybindings.");
-		let col_spacing = 5;
-		let n_cols = if help_win.get_cols() > (longest_line * 2 + col_spacing) as u16 {
-			2
-		} else {
-			1
-		};
-		let keys_per_row = key_strs.len() as u16 / n_cols;
+		// leave room for the instructions at the bottom; if everything
+		// fits on one page, the columns are made about the same length
+		let max_rows = (help_win.get_rows() as usize)
+			.saturating_sub(row as usize + 4)
+			.max(1);
+		let mut rows_per_col = min(max_rows, lines.len().div_ceil(n_cols));
+		let mut columns = split_columns(&lines, rows_per_col);
+		while columns.len() > n_cols && rows_per_col < max_rows
+		{
+			rows_per_col += 1;
+			columns = split_columns(&lines, rows_per_col);
+		}
 
-		// write each line of keys -- the list will be presented "down"
-		// rather than "across", but we print to the screen a line at a
-		// time, so the offset jumps down in the list if we have more
-		// than one column
-		for i in 0..keys_per_row
+		let pages: Vec<&[Vec<&String>]> = columns.chunks(n_cols).collect();
+		self.help_pages = pages.len();
+		self.help_page = min(self.help_page, self.help_pages - 1);
+
+		// the list is presented "down" rather than "across", but we
+		// print to the screen a line at a time
+		let page = pages[self.help_page];
+		for i in 0..rows_per_col
 		{
 			let mut line = String::new();
-			for j in 0..n_cols
+			for (j, col) in page.iter().enumerate()
 			{
-				let offset = j * keys_per_row;
-				if let Some(val) = key_strs.get((i + offset) as usize)
-				{
-					// apply `col_spacing` to the right side of the
-					// first column
-					let width = if n_cols > 1 && offset == 0 {
-						longest_line + col_spacing
-					} else {
-						longest_line
-					};
-					line += &format!("{val:<width$}", width = width);
-				}
+				let val = col.get(i).map_or("", |val| val.as_str());
+				// apply `col_spacing` to the right side of the first
+				// column
+				let width = if j + 1 < page.len() {
+					longest_line + col_spacing
+				} else {
+					longest_line
+				};
+				line += &format!("{val:<width$}", width = width);
 			}
 			help_win.write_line(row + 1, line, None);
 			row += 1;
 		}
 
-		let _ = help_win.write_wrap_line(
-			row + 2,
-			"Press \"q\" to close this window.",
-			None
-		);
+		let close_str = if self.help_pages > 1 {
+			format!(
+				"Page {}/{}. Press {} or {} to change pages, or any other key to close this window.",
+				self.help_page + 1,
+				self.help_pages,
+				self.first_key(UserAction::PageDown, UserAction::Down),
+				self.first_key(UserAction::PageUp, UserAction::Up),
+			)
+		} else {
+			"Press any key to close this window.".to_string()
+		};
+		let _ = help_win.write_wrap_line(row + 2, &close_str, None);
 		return help_win;
 	}
 
+	/// Returns the first key bound to an action, or to another action
+	/// that does the same thing in the help window if the first one has
+	/// no keys.
+	fn first_key(&self, action: UserAction, fallback: UserAction) -> String
+	{
+		let mut keys = self.keymap.keys_for_action(action);
+		if keys.is_empty()
+		{
+			keys = self.keymap.keys_for_action(fallback);
+		}
+		keys.sort();
+		return match keys.first()
+		{
+			Some(key) => format!("\"{key}\""),
+			None => "<missing>".to_string(),
+		};
+	}
+
 	/// Create a new download window and draw it to the screen.
 	pub fn spawn_download_win(
 		&mut self,
@@ -380,6 +546,137 @@ impl<'a> PopupWin<'a>
 		return download_win;
 	}
 
+	/// Create a new feed selection window and draw it to the screen.
+	pub fn spawn_feed_win(&mut self, urls: Vec<String>)
+	{
+		self.feed_candidates = urls
+			.into_iter()
+			.enumerate()
+			.map(|(i, url)| FeedCandidate {
+				id: i as i64,
+				url: url,
+			})
+			.collect();
+		self.feed_win = true;
+		self.change_win();
+	}
+
+	/// Create a new Menu holding a feed selection window.
+	pub fn make_feed_win(&self) -> Menu<FeedCandidate>
+	{
+		// the warning on the unused mut is a function of Rust getting
+		// confused between panel.rs and mock_panel.rs
+		#[allow(unused_mut)]
+		let mut feed_panel = Panel::new(
+			"Choose a feed".to_string(),
+			0,
+			self.colors.clone(),
+			self.total_rows - 1,
+			self.total_cols,
+			0,
+			(1, 0, 0, 0),
+		);
+
+		let header = format!(
+			"This web page links to more than one feed. Press {} to subscribe to the highlighted feed, or {} to cancel.",
+			self.list_keys(UserAction::Play, Some(2)),
+			self.list_keys(UserAction::Quit, Some(2))
+		);
+		let mut feed_win = Menu::new(
+			feed_panel,
+			Some(header),
+			LockVec::new(self.feed_candidates.clone()),
+		);
+		feed_win.redraw();
+
+		return feed_win;
+	}
+
+	/// Create a new statistics window and draw it to the screen.
+	pub fn spawn_stats_win(&mut self, stats: LibraryStats)
+	{
+		self.stats = Some(stats);
+		self.stats_win = true;
+		self.change_win();
+	}
+
+	/// Create a new Menu holding a statistics window, with the totals
+	/// above the statistics of each podcast.
+	pub fn make_stats_win(&self) -> Menu<PodcastStats>
+	{
+		// the warning on the unused mut is a function of Rust getting
+		// confused between panel.rs and mock_panel.rs
+		#[allow(unused_mut)]
+		let mut stats_panel = Panel::new(
+			"Statistics".to_string(),
+			0,
+			self.colors.clone(),
+			self.total_rows - 1,
+			self.total_cols,
+			0,
+			(1, 0, 0, 0),
+		);
+
+		let (summary, per_podcast) = match &self.stats
+		{
+			Some(stats) => (stats.summary(), stats.per_podcast.clone()),
+			None => (String::new(), Vec::new()),
+		};
+		let header = format!(
+			"Total: {}. Press {} to close this window.",
+			summary,
+			self.list_keys(UserAction::Quit, Some(2))
+		);
+		let mut stats_win = Menu::new(
+			stats_panel,
+			Some(header),
+			LockVec::new(per_podcast),
+		);
+		stats_win.redraw();
+
+		return stats_win;
+	}
+
+	/// Create a new message log window and draw it to the screen.
+	pub fn spawn_log_win(&mut self, entries: Vec<LogEntry>)
+	{
+		self.log = entries;
+		self.log_win = true;
+		self.change_win();
+	}
+
+	/// Create a new Menu holding the message log, scrolled down so that
+	/// the most recent messages are shown.
+	pub fn make_log_win(&self) -> Menu<LogEntry>
+	{
+		// the warning on the unused mut is a function of Rust getting
+		// confused between panel.rs and mock_panel.rs
+		#[allow(unused_mut)]
+		let mut log_panel = Panel::new(
+			"Message log".to_string(),
+			0,
+			self.colors.clone(),
+			self.total_rows - 1,
+			self.total_cols,
+			0,
+			(1, 0, 0, 0),
+		);
+
+		let header = format!(
+			"Recent messages, oldest first. Press {} to close this window.",
+			self.list_keys(UserAction::Quit, Some(2))
+		);
+		let mut log_win = Menu::new(
+			log_panel,
+			Some(header),
+			LockVec::new(self.log.clone()),
+		);
+		log_win.redraw();
+		log_win.scroll(Scroll::Down(self.log.len() as u16));
+
+		return log_win;
+	}
+
 	/// Appends a new episode to the list of new episodes.
 	pub fn _add_episodes(&mut self, mut episodes: Vec<NewEpisode>)
 	{
@@ -407,6 +704,30 @@ impl<'a> PopupWin<'a>
 		self.change_win();
 	}
 
+	/// Gets rid of the statistics window.
+	pub fn turn_off_stats_win(&mut self)
+	{
+		self.stats_win = false;
+		self.stats = None;
+		self.change_win();
+	}
+
+	/// Gets rid of the message log window.
+	pub fn turn_off_log_win(&mut self)
+	{
+		self.log_win = false;
+		self.log = Vec::new();
+		self.change_win();
+	}
+
+	/// Gets rid of the feed selection window.
+	pub fn turn_off_feed_win(&mut self)
+	{
+		self.feed_win = false;
+		self.feed_candidates = Vec::new();
+		self.change_win();
+	}
+
 	/// When there is a change to the active popup window, this should
 	/// be called to check for other popup windows that are "in the
 	/// queue" -- this lets one popup window appear over top of another
@@ -429,41 +750,66 @@ impl<'a> PopupWin<'a>
 			win.activate();
 			self.popup = ActivePopup::DownloadWin(win);
 		}
+		else if self.feed_win && !self.popup.is_feed_win()
+		{
+			let mut win = self.make_feed_win();
+			win.activate();
+			self.popup = ActivePopup::FeedWin(win);
+		}
+		else if self.stats_win && !self.popup.is_stats_win()
+		{
+			let mut win = self.make_stats_win();
+			win.activate();
+			self.popup = ActivePopup::StatsWin(win);
+		}
+		else if self.log_win && !self.popup.is_log_win()
+		{
+			let mut win = self.make_log_win();
+			win.activate();
+			self.popup = ActivePopup::LogWin(win);
+		}
 		else if self.welcome_win && !self.popup.is_welcome_win()
 		{
 			let win = self.make_welcome_win();
 			self.popup = ActivePopup::WelcomeWin(win);
 		}
-		else if !self.help_win && !self.download_win && !self.welcome_win && !self.popup.is_none()
+		else if !self.help_win && !self.download_win && !self.feed_win
+			&& !self.stats_win && !self.log_win
+			&& !self.welcome_win
+			&& !self.popup.is_none()
 		{
 			self.popup = ActivePopup::None;
 		}
 	}
 
-	/// When a popup window is active, this handles the user's keyboard
-	/// input that is relevant for that window.
-	pub fn handle_input(&mut self, input: KeyEvent) -> UiMsg
+	/// When a popup window is active, this handles the action of the
+	/// key or sequence of keys the user typed, if it is relevant for that
+	/// window. `action` is None for keys that are not bound to anything.
+	pub fn handle_input(&mut self, action: Option<UserAction>) -> UiMsg
 	{
 		let mut msg = UiMsg::Noop;
 		match self.popup
 		{
-			ActivePopup::HelpWin(ref mut _win) => {
-				match input.code
+			ActivePopup::HelpWin(ref mut _win) => match action
+			{
+				Some(UserAction::PageDown | UserAction::Down | UserAction::Right)
+					if self.help_page + 1 < self.help_pages =>
 				{
-					KeyCode::Esc
-					| KeyCode::Char('\u{1b}') // Esc
-					| KeyCode::Char('q')
-					| KeyCode::Char('Q') => {
-						self.turn_off_help_win();
-					}
-					_ => (),
+					self.help_page += 1;
+					let win = self.make_help_win();
+					self.popup = ActivePopup::HelpWin(win);
 				}
-			}
-			ActivePopup::DownloadWin(ref mut menu) => match self.keymap.get_from_input(input)
+				Some(UserAction::PageUp | UserAction::Up | UserAction::Left)
+					if self.help_page > 0 =>
+				{
+					self.help_page -= 1;
+					let win = self.make_help_win();
+					self.popup = ActivePopup::HelpWin(win);
+				}
+				_ => self.turn_off_help_win(),
+			},
+			ActivePopup::DownloadWin(ref mut menu) => match action
 			{
-				Some(UserAction::Down) => menu.scroll(Scroll::Down(1)),
-				Some(UserAction::Up) => menu.scroll(Scroll::Up(1)),
-
 				Some(UserAction::MarkPlayed) => {
 					menu.select_item();
 				}
@@ -491,7 +837,42 @@ impl<'a> PopupWin<'a>
 					self.turn_off_download_win();
 				}
 
-				Some(_) | None => (),
+				Some(action) => menu.scroll_for_action(action),
+				None => (),
+			},
+			ActivePopup::FeedWin(ref mut menu) => match action
+			{
+				Some(UserAction::Play) => {
+					let index = menu.get_menu_idx(menu.selected);
+					if let Some(candidate) = self.feed_candidates.get(index)
+					{
+						msg = UiMsg::AddFeed(candidate.url.clone());
+					}
+					self.turn_off_feed_win();
+				}
+
+				Some(UserAction::Quit) => self.turn_off_feed_win(),
+
+				Some(action) => menu.scroll_for_action(action),
+				None => (),
+			},
+			ActivePopup::StatsWin(ref mut menu) => match action
+			{
+				Some(UserAction::Quit) | Some(UserAction::ShowStats) => {
+					self.turn_off_stats_win()
+				}
+
+				Some(action) => menu.scroll_for_action(action),
+				None => (),
+			},
+			ActivePopup::LogWin(ref mut menu) => match action
+			{
+				Some(UserAction::Quit) | Some(UserAction::ShowLog) => {
+					self.turn_off_log_win()
+				}
+
+				Some(action) => menu.scroll_for_action(action),
+				None => (),
 			},
 			_ => (),
 		}
@@ -513,7 +894,7 @@ impl<'a> PopupWin<'a>
 		}
 		return match max_keys
 		{
-			0 => "<missing>".to_string(),
+			0 => "<unbound>".to_string(),
 			1 => format!("\"{}\"", &keys[0]),
 			2 => format!("\"{}\" or \"{}\"", &keys[0], &keys[1]),
 			_ => {
@@ -534,3 +915,30 @@ impl<'a> PopupWin<'a>
 		};
 	}
 }
+
+/// Splits the lines of the help window into columns of at most
+/// `rows_per_col` lines, filled from top to bottom. Columns never start
+/// with a blank line or end with a group heading.
+fn split_columns(lines: &[String], rows_per_col: usize) -> Vec<Vec<&String>>
+{
+	let mut columns: Vec<Vec<&String>> = vec![Vec::new()];
+	for (i, line) in lines.iter().enumerate()
+	{
+		let col = columns.last_mut().unwrap();
+		if col.is_empty() && line.is_empty()
+		{
+			continue;
+		}
+		let heading = i == 0 || lines[i - 1].is_empty();
+		if col.len() >= rows_per_col
+			|| (heading && !col.is_empty() && col.len() + 1 >= rows_per_col)
+		{
+			columns.push(vec![line]);
+		}
+		else
+		{
+			col.push(line);
+		}
+	}
+	return columns;
+}